@@ -0,0 +1,149 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use ff::{Field, PrimeField};
+use halo2_proofs::plonk::{keygen_pk, keygen_vk, Circuit};
+use pasta_curves::pallas;
+use rand::{rngs::OsRng, Rng};
+use taiga_halo2::{
+    circuit::{
+        cost::estimate,
+        resource_logic_circuit::ResourceLogicCircuit,
+        resource_logic_examples::{
+            receiver_resource_logic::{ReceiverResourceLogicCircuit, COMPRESSED_RECEIVER_VK},
+            signature_verification::COMPRESSED_TOKEN_AUTH_VK,
+            token::{Token, TokenAuthorization, TokenResourceLogicCircuit},
+            TrivialResourceLogicCircuit,
+        },
+    },
+    compliance::ComplianceInfo,
+    constant::{
+        COMPLIANCE_CIRCUIT_PARAMS_SIZE, RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        TAIGA_COMMITMENT_TREE_DEPTH, TAIGA_RESOURCE_TREE_DEPTH,
+    },
+    merkle_tree::{MerklePath, LR},
+    params::get_params,
+    proof::Proof,
+    resource::{RandomSeed, Resource},
+    resource_tree::ResourceExistenceWitness,
+};
+
+fn bench_circuit<C: Circuit<pallas::Base> + Clone>(
+    c: &mut Criterion,
+    name: &str,
+    k: u32,
+    circuit: C,
+    instance: &[pallas::Base],
+) {
+    let mut rng = OsRng;
+    let cost = estimate(&circuit).expect("synthesis should not fail");
+    println!("{}: {} rows, k >= {}", name, cost.rows, cost.min_k());
+    for (region, rows) in &cost.regions {
+        println!("    {:>6}  {}", rows, region);
+    }
+    let params = get_params(k).unwrap();
+
+    // Keygen bench
+    c.bench_function(&format!("{}-keygen", name), |b| {
+        b.iter(|| {
+            let vk = keygen_vk(params, &circuit).expect("keygen_vk should not fail");
+            keygen_pk(params, vk, &circuit).expect("keygen_pk should not fail")
+        })
+    });
+
+    // Prover bench
+    let vk = keygen_vk(params, &circuit).expect("keygen_vk should not fail");
+    let pk = keygen_pk(params, vk, &circuit).expect("keygen_pk should not fail");
+    c.bench_function(&format!("{}-prover", name), |b| {
+        b.iter(|| Proof::create(&pk, params, circuit.clone(), &[instance], &mut rng).unwrap())
+    });
+
+    // Verifier bench
+    let proof = Proof::create(&pk, params, circuit.clone(), &[instance], &mut rng).unwrap();
+    c.bench_function(&format!("{}-verifier", name), |b| {
+        b.iter(|| assert!(proof.verify(pk.get_vk(), params, &[instance]).is_ok()))
+    });
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut rng = OsRng;
+
+    // Compliance of padding resources
+    let (compliance, compliance_circuit) = {
+        let input_resource = Resource::padding_resource(&mut rng);
+        let mut output_resource = Resource::padding_resource(&mut rng);
+        let merkle_path = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
+        ComplianceInfo::new(
+            input_resource,
+            merkle_path,
+            None,
+            &mut output_resource,
+            &mut rng,
+        )
+        .build()
+    };
+    bench_circuit(
+        c,
+        "compliance",
+        COMPLIANCE_CIRCUIT_PARAMS_SIZE,
+        compliance_circuit,
+        &compliance.to_instance(),
+    );
+
+    let trivial = TrivialResourceLogicCircuit::new(
+        Resource::padding_resource(&mut rng),
+        [(pallas::Base::zero(), LR::L); TAIGA_RESOURCE_TREE_DEPTH],
+    );
+    let public_inputs = trivial.get_public_inputs(&mut rng);
+    bench_circuit(
+        c,
+        "trivial-resource-logic",
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        trivial,
+        public_inputs.inner(),
+    );
+
+    // The token and receiver logics of a created token resource
+    let auth = TokenAuthorization::random(&mut rng);
+    let npk = pallas::Base::random(&mut rng);
+    let token =
+        Token::new("btc".to_string(), 1).create_random_output_token_resource(&mut rng, npk, &auth);
+    let self_resource = ResourceExistenceWitness::new(
+        token.resource,
+        [(pallas::Base::zero(), LR::L); TAIGA_RESOURCE_TREE_DEPTH],
+    );
+    let token_logic = TokenResourceLogicCircuit {
+        self_resource: self_resource.clone(),
+        token_name: token.token_name,
+        auth,
+        receiver_resource_logic_vk: *COMPRESSED_RECEIVER_VK,
+        rseed: RandomSeed::random(&mut rng),
+    };
+    let public_inputs = token_logic.get_public_inputs(&mut rng);
+    bench_circuit(
+        c,
+        "token-resource-logic",
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        token_logic,
+        public_inputs.inner(),
+    );
+
+    // The receiver logic is dominated by the resource encryption gadget
+    let receiver_logic = ReceiverResourceLogicCircuit {
+        self_resource,
+        resource_logic_vk: *COMPRESSED_RECEIVER_VK,
+        encrypt_nonce: pallas::Base::from_u128(rng.gen()),
+        sk: pallas::Base::random(&mut rng),
+        rcv_pk: auth.pk,
+        auth_resource_logic_vk: *COMPRESSED_TOKEN_AUTH_VK,
+    };
+    let public_inputs = receiver_logic.get_public_inputs(&mut rng);
+    bench_circuit(
+        c,
+        "receiver-resource-logic",
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        receiver_logic,
+        public_inputs.inner(),
+    );
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -9,12 +9,11 @@ use rand::rngs::OsRng;
 use rand::Rng;
 use taiga_halo2::{
     compliance::ComplianceInfo,
-    constant::{
-        COMPLIANCE_CIRCUIT_PARAMS_SIZE, COMPLIANCE_PROVING_KEY, COMPLIANCE_VERIFYING_KEY,
-        SETUP_PARAMS_MAP, TAIGA_COMMITMENT_TREE_DEPTH,
-    },
+    constant::{COMPLIANCE_CIRCUIT_PARAMS_SIZE, TAIGA_COMMITMENT_TREE_DEPTH},
     merkle_tree::MerklePath,
     nullifier::{Nullifier, NullifierKeyContainer},
+    params::{ProvingContext, VerifyingContext},
+    quantity::Quantity,
     resource::{Resource, ResourceKind},
 };
 
@@ -30,16 +29,16 @@ fn bench_compliance_proof(name: &str, c: &mut Criterion) {
                 ResourceKind::new(logic, label)
             };
             let value = pallas::Base::random(&mut rng);
-            let quantity: u64 = rng.gen();
+            let quantity = Quantity::new(rng.gen());
             let rseed = pallas::Base::random(&mut rng);
             Resource {
                 kind,
                 value,
                 quantity,
-                nk_container: nk,
+                nk_container: nk.into(),
                 is_ephemeral: false,
                 nonce,
-                rseed,
+                rseed: rseed.into(),
             }
         };
         let mut output_resource = {
@@ -51,16 +50,16 @@ fn bench_compliance_proof(name: &str, c: &mut Criterion) {
                 ResourceKind::new(logic, label)
             };
             let value = pallas::Base::random(&mut rng);
-            let quantity: u64 = rng.gen();
+            let quantity = Quantity::new(rng.gen());
             let rseed = pallas::Base::random(&mut rng);
             Resource {
                 kind,
                 value,
                 quantity,
-                nk_container: npk,
+                nk_container: npk.into(),
                 is_ephemeral: false,
                 nonce,
-                rseed,
+                rseed: rseed.into(),
             }
         };
         let input_merkle_path = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
@@ -73,9 +72,9 @@ fn bench_compliance_proof(name: &str, c: &mut Criterion) {
         )
     };
     let (compliance, compliance_circuit) = compliance_info.build();
-    let params = SETUP_PARAMS_MAP
-        .get(&COMPLIANCE_CIRCUIT_PARAMS_SIZE)
-        .unwrap();
+    let proving_context = ProvingContext::default();
+    let verifying_context = VerifyingContext::default();
+    let params = proving_context.params(COMPLIANCE_CIRCUIT_PARAMS_SIZE);
 
     // Prover bench
     let prover_name = name.to_string() + "-prover";
@@ -84,7 +83,7 @@ fn bench_compliance_proof(name: &str, c: &mut Criterion) {
             let mut transcript = Blake2bWrite::<_, vesta::Affine, _>::init(vec![]);
             create_proof(
                 params,
-                &COMPLIANCE_PROVING_KEY,
+                proving_context.compliance_proving_key(),
                 &[compliance_circuit.clone()],
                 &[&[&compliance.to_instance()]],
                 &mut rng,
@@ -101,7 +100,7 @@ fn bench_compliance_proof(name: &str, c: &mut Criterion) {
         let mut transcript = Blake2bWrite::<_, vesta::Affine, _>::init(vec![]);
         create_proof(
             params,
-            &COMPLIANCE_PROVING_KEY,
+            proving_context.compliance_proving_key(),
             &[compliance_circuit],
             &[&[&compliance.to_instance()]],
             &mut rng,
@@ -118,7 +117,7 @@ fn bench_compliance_proof(name: &str, c: &mut Criterion) {
             let mut transcript = Blake2bRead::init(&proof[..]);
             assert!(verify_proof(
                 params,
-                &COMPLIANCE_VERIFYING_KEY,
+                verifying_context.compliance_verifying_key(),
                 strategy,
                 &[&[&compliance.to_instance()]],
                 &mut transcript
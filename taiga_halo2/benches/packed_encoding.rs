@@ -0,0 +1,72 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use ff::{Field, PrimeField};
+use pasta_curves::pallas;
+use rand::rngs::OsRng;
+use taiga_halo2::{
+    circuit::resource_logic_circuit::ResourceLogicPublicInputs,
+    constant::RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM,
+    packed::{pack_public_inputs, PackedPublicInputs},
+};
+
+// The term encoding sends a public input as a list of 32-byte binaries, one
+// allocation per element on both sides of the boundary
+fn term_encode(public_inputs: &[ResourceLogicPublicInputs]) -> Vec<Vec<Vec<u8>>> {
+    public_inputs
+        .iter()
+        .map(|inputs| {
+            inputs
+                .inner()
+                .iter()
+                .map(|element| element.to_repr().to_vec())
+                .collect()
+        })
+        .collect()
+}
+
+fn term_decode(terms: &[Vec<Vec<u8>>]) -> Vec<ResourceLogicPublicInputs> {
+    terms
+        .iter()
+        .map(|elements| {
+            elements
+                .iter()
+                .map(|bytes| pallas::Base::from_repr(bytes.as_slice().try_into().unwrap()).unwrap())
+                .collect::<Vec<_>>()
+                .into()
+        })
+        .collect()
+}
+
+fn bench_packed_encoding(name: &str, c: &mut Criterion) {
+    let mut rng = OsRng;
+    let batch_size = 1000;
+    let public_inputs: Vec<ResourceLogicPublicInputs> = (0..batch_size)
+        .map(|_| {
+            (0..RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM)
+                .map(|_| pallas::Base::random(&mut rng))
+                .collect::<Vec<_>>()
+                .into()
+        })
+        .collect();
+
+    let term_name = name.to_string() + "-term";
+    c.bench_function(&term_name, |b| {
+        b.iter(|| term_decode(&term_encode(&public_inputs)))
+    });
+
+    let packed_name = name.to_string() + "-packed";
+    c.bench_function(&packed_name, |b| {
+        b.iter(|| {
+            let bytes = pack_public_inputs(&public_inputs);
+            PackedPublicInputs::new(&bytes, batch_size)
+                .unwrap()
+                .decode()
+                .unwrap()
+        })
+    });
+}
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_packed_encoding("public-inputs-encoding", c);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -6,7 +6,8 @@ use taiga_halo2::{
         resource_logic_circuit::ResourceLogicCircuit,
         resource_logic_examples::TrivialResourceLogicCircuit,
     },
-    constant::{RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE, SETUP_PARAMS_MAP},
+    constant::RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+    params::get_params,
     proof::Proof,
 };
 
@@ -14,9 +15,7 @@ fn bench_resource_logic_proof(name: &str, c: &mut Criterion) {
     let mut rng = OsRng;
 
     let resource_logic_circuit = TrivialResourceLogicCircuit::default();
-    let params = SETUP_PARAMS_MAP
-        .get(&RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE)
-        .unwrap();
+    let params = get_params(RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE).unwrap();
     let empty_circuit: TrivialResourceLogicCircuit = Default::default();
     let vk = keygen_vk(params, &empty_circuit).expect("keygen_vk should not fail");
     let pk = keygen_pk(params, vk, &empty_circuit).expect("keygen_pk should not fail");
@@ -28,7 +27,7 @@ fn bench_resource_logic_proof(name: &str, c: &mut Criterion) {
         b.iter(|| {
             Proof::create(
                 &pk,
-                &params,
+                params,
                 resource_logic_circuit.clone(),
                 &[public_inputs.inner()],
                 &mut rng,
@@ -41,7 +40,7 @@ fn bench_resource_logic_proof(name: &str, c: &mut Criterion) {
     // Create a proof for verifier
     let proof = Proof::create(
         &pk,
-        &params,
+        params,
         resource_logic_circuit.clone(),
         &[public_inputs.inner()],
         &mut rng,
@@ -52,7 +51,7 @@ fn bench_resource_logic_proof(name: &str, c: &mut Criterion) {
     c.bench_function(&verifier_name, |b| {
         b.iter(|| {
             assert!(proof
-                .verify(pk.get_vk(), &params, &[public_inputs.inner()])
+                .verify(pk.get_vk(), params, &[public_inputs.inner()])
                 .is_ok());
         })
     });
@@ -0,0 +1,6 @@
+//! Exports the transaction fixtures to `fixtures/`, see `taiga_halo2::fixtures`.
+//!
+//! cargo run --release --features apps --example export_fixtures
+fn main() {
+    taiga_halo2::fixtures::export_fixtures().expect("the fixtures should be valid");
+}
@@ -0,0 +1,7 @@
+//! Exports the JSON test vectors to `fixtures/test_vectors.json`, see
+//! `taiga_halo2::test_vectors`.
+//!
+//! cargo run --release --features test-vectors --example export_test_vectors
+fn main() {
+    taiga_halo2::test_vectors::export_test_vectors().expect("the test vectors should be valid");
+}
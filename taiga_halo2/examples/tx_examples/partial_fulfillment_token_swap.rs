@@ -9,10 +9,9 @@ use halo2_proofs::arithmetic::Field;
 use pasta_curves::{group::Curve, pallas};
 use rand::{CryptoRng, RngCore};
 use taiga_halo2::{
-    circuit::resource_logic_examples::{
-        partial_fulfillment_intent::{PartialFulfillmentIntentResourceLogicCircuit, Swap},
-        signature_verification::COMPRESSED_TOKEN_AUTH_VK,
-        token::{Token, TokenAuthorization, TokenResource},
+    apps::{
+        swap::{PartialFulfillmentIntentResourceLogicCircuit, Swap},
+        token::{Token, TokenAuthorization, TokenResource, COMPRESSED_TOKEN_AUTH_VK},
     },
     compliance::ComplianceInfo,
     constant::TAIGA_COMMITMENT_TREE_DEPTH,
@@ -39,7 +38,7 @@ pub fn create_token_intent_ptx<R: RngCore>(
     // Create compliance pairs
     let compliances = {
         let compliance_1 = ComplianceInfo::new(
-            *swap.sell.resource(),
+            swap.sell.resource().clone(),
             merkle_path.clone(),
             None,
             &mut intent_resource,
@@ -69,13 +68,13 @@ pub fn create_token_intent_ptx<R: RngCore>(
     // Create intent resource logics
     let intent_resource_logics = {
         let sell_resource_witness =
-            ResourceExistenceWitness::new(*swap.sell.resource(), input_merkle_path);
+            ResourceExistenceWitness::new(swap.sell.resource().clone(), input_merkle_path);
 
         let intent_resource_witness = {
             let merkle_path = resource_merkle_tree
                 .generate_path(output_resource_cm)
                 .unwrap();
-            ResourceExistenceWitness::new(intent_resource, merkle_path)
+            ResourceExistenceWitness::new(intent_resource.clone(), merkle_path)
         };
 
         let intent_circuit = PartialFulfillmentIntentResourceLogicCircuit {
@@ -111,7 +110,7 @@ pub fn consume_token_intent_ptx<R: RngCore>(
     output_auth_pk: pallas::Point,
 ) -> ShieldedPartialTransaction {
     let (mut offer_resource, mut returned_resource) = swap.fill(&mut rng, offer);
-    let padding_input_resource = Resource::random_padding_resource(&mut rng);
+    let padding_input_resource = Resource::padding_resource(&mut rng);
 
     // output resources
     let output_auth = TokenAuthorization::new(output_auth_pk, *COMPRESSED_TOKEN_AUTH_VK);
@@ -123,7 +122,7 @@ pub fn consume_token_intent_ptx<R: RngCore>(
     // Create compliance pairs
     let compliances = {
         let compliance_1 = ComplianceInfo::new(
-            intent_resource,
+            intent_resource.clone(),
             merkle_path.clone(),
             Some(anchor),
             &mut offer_resource,
@@ -131,7 +130,7 @@ pub fn consume_token_intent_ptx<R: RngCore>(
         );
 
         let compliance_2 = ComplianceInfo::new(
-            padding_input_resource,
+            padding_input_resource.clone(),
             merkle_path,
             Some(anchor),
             &mut returned_resource,
@@ -156,26 +155,26 @@ pub fn consume_token_intent_ptx<R: RngCore>(
 
         let offer_resource_witness = {
             let merkle_path = resource_merkle_tree.generate_path(offer_cm).unwrap();
-            ResourceExistenceWitness::new(offer_resource, merkle_path)
+            ResourceExistenceWitness::new(offer_resource.clone(), merkle_path)
         };
 
         let padding_resource_witness = {
             let merkle_path = resource_merkle_tree.generate_path(padding_nf).unwrap();
-            ResourceExistenceWitness::new(padding_input_resource, merkle_path)
+            ResourceExistenceWitness::new(padding_input_resource.clone(), merkle_path)
         };
 
         let returned_resource_witness = {
             let merkle_path = resource_merkle_tree.generate_path(returned_cm).unwrap();
-            ResourceExistenceWitness::new(returned_resource, merkle_path)
+            ResourceExistenceWitness::new(returned_resource.clone(), merkle_path)
         };
 
         // Create resource_logics for the intent
         let intent_resource_logics = {
             let intent_resource_logic = PartialFulfillmentIntentResourceLogicCircuit {
                 self_resource: intent_resource_witness,
-                sell_resource: padding_resource_witness, // a dummy one
-                offer_resource: offer_resource_witness,
-                returned_resource: returned_resource_witness,
+                sell_resource: padding_resource_witness.clone(), // a dummy one
+                offer_resource: offer_resource_witness.clone(),
+                returned_resource: returned_resource_witness.clone(),
                 swap: swap.clone(),
             };
 
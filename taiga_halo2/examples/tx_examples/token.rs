@@ -2,10 +2,7 @@ use pasta_curves::pallas;
 use rand::RngCore;
 
 use taiga_halo2::{
-    circuit::resource_logic_examples::{
-        signature_verification::COMPRESSED_TOKEN_AUTH_VK,
-        token::{Token, TokenAuthorization},
-    },
+    apps::token::{Token, TokenAuthorization, COMPRESSED_TOKEN_AUTH_VK},
     compliance::ComplianceInfo,
     constant::TAIGA_COMMITMENT_TREE_DEPTH,
     merkle_tree::MerklePath,
@@ -40,7 +37,7 @@ pub fn create_token_swap_ptx<R: RngCore>(
     // Create compliance pairs
     let compliances = {
         let compliance_1 = ComplianceInfo::new(
-            *input_resource.resource(),
+            input_resource.resource().clone(),
             merkle_path.clone(),
             None,
             &mut output_resource.resource,
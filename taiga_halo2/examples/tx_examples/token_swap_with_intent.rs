@@ -9,10 +9,9 @@ use halo2_proofs::arithmetic::Field;
 use pasta_curves::{group::Curve, pallas};
 use rand::{CryptoRng, RngCore};
 use taiga_halo2::{
-    circuit::resource_logic_examples::{
-        or_relation_intent::{create_intent_resource, OrRelationIntentResourceLogicCircuit},
-        signature_verification::COMPRESSED_TOKEN_AUTH_VK,
-        token::{Token, TokenAuthorization},
+    apps::token::{Token, TokenAuthorization, COMPRESSED_TOKEN_AUTH_VK},
+    circuit::resource_logic_examples::or_relation_intent::{
+        create_intent_resource, OrRelationIntentResourceLogicCircuit,
     },
     compliance::ComplianceInfo,
     constant::TAIGA_COMMITMENT_TREE_DEPTH,
@@ -59,7 +58,7 @@ pub fn create_token_intent_ptx<R: RngCore>(
     // Create compliance pairs
     let compliances = {
         let compliance_1 = ComplianceInfo::new(
-            *input_resource.resource(),
+            input_resource.resource().clone(),
             merkle_path.clone(),
             None,
             &mut intent_resource,
@@ -95,7 +94,7 @@ pub fn create_token_intent_ptx<R: RngCore>(
             .unwrap();
         let intent_resource_witness = ResourceExistenceWitness::new(intent_resource, merkle_path);
         let circuit = OrRelationIntentResourceLogicCircuit {
-            self_resource: intent_resource_witness,
+            self_resource: intent_resource_witness.clone(),
             // the desired resource won't be checked.
             desired_resource: intent_resource_witness,
             token_1,
@@ -153,7 +152,7 @@ pub fn consume_token_intent_ptx<R: RngCore>(
 
     // Create compliance proof
     let compliance = ComplianceInfo::new(
-        intent_resource,
+        intent_resource.clone(),
         merkle_path.clone(),
         Some(anchor),
         &mut output_resource.resource,
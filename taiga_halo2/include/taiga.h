@@ -0,0 +1,41 @@
+/* C declarations of the Taiga FFI, see src/ffi.rs. */
+#ifndef TAIGA_H
+#define TAIGA_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+typedef enum {
+    TAIGA_OK = 0,
+    TAIGA_NULL_POINTER = 1,
+    TAIGA_INVALID_INPUT = 2,
+    TAIGA_PROVING_FAILED = 3,
+    TAIGA_INVALID_TRANSACTION = 4,
+    TAIGA_PANIC = 5,
+} TaigaStatus;
+
+/* A buffer owned by the library, released with taiga_buffer_free. */
+typedef struct {
+    uint8_t *data;
+    size_t len;
+} TaigaBuffer;
+
+void taiga_buffer_free(TaigaBuffer buffer);
+
+TaigaStatus taiga_create_shielded_transaction(
+    const uint8_t *compliances, size_t compliances_len,
+    const uint8_t *input_apps, size_t input_apps_len,
+    const uint8_t *output_apps, size_t output_apps_len,
+    const uint8_t *hints, size_t hints_len,
+    TaigaBuffer *out);
+
+TaigaStatus taiga_verify_transaction(
+    const uint8_t *tx, size_t tx_len,
+    TaigaBuffer *out);
+
+TaigaStatus taiga_decrypt_resource(
+    const uint8_t *tx, size_t tx_len,
+    const uint8_t *viewing_key,
+    TaigaBuffer *out);
+
+#endif
@@ -0,0 +1,244 @@
+//! Constant-product AMM pools of two tokens, see
+//! `AmmPoolResourceLogicCircuit`.
+//!
+//! A pool is opened with [`open`] from the first deposit of its owner, and
+//! grows with [`add_liquidity`]. A trader sells one token of the pool for the
+//! other with [`swap`], in a ptx consuming the pool, the paid tokens and a
+//! reserve resource of the bought token, and creating the new pool, the
+//! deposit of the paid tokens to the owner, the bought tokens for the trader
+//! and the change of the reserve. The trader and the owner both sign the ptx,
+//! the pool logic guarantees the price.
+pub use crate::circuit::resource_logic_examples::amm::{
+    AmmPoolResourceLogicCircuit, Pool, AMM_POOL_VK, COMPRESSED_AMM_POOL_VK,
+};
+use crate::{
+    apps::token::{self, Token, TokenAuthorization, TokenResource, COMPRESSED_TOKEN_AUTH_VK},
+    builder::TransactionBuilder,
+    circuit::resource_logic_bytecode::ApplicationByteCode,
+    constant::TAIGA_COMMITMENT_TREE_DEPTH,
+    merkle_tree::MerklePath,
+    resource::Resource,
+};
+use pasta_curves::pallas;
+use rand::RngCore;
+
+// Consumes the pool and creates the pool of the new reserves, with the deposit
+// of `amount_in` to the owner and the payout of `amount_out` to `receiver`.
+#[allow(clippy::too_many_arguments)]
+fn trade<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    pool: &Pool,
+    (pool_resource, merkle_path): (Resource, MerklePath),
+    npk: pallas::Base,
+    sell_x: bool,
+    amount_in: u64,
+    (amount_out, receiver, receiver_npk): (u64, TokenAuthorization, pallas::Base),
+) -> Pool {
+    let new_pool = pool
+        .swap(sell_x, amount_in, amount_out)
+        .expect("the swap must not decrease the product of the reserves");
+    let (token_in, token_out) = pool.tokens(sell_x);
+
+    let pool_index = builder.spent_num();
+    builder.spend(pool_resource, merkle_path, None);
+    let new_pool_index = builder.created_num();
+    builder.create(new_pool.create_resource(&mut rng, npk));
+    let deposit_index = builder.created_num();
+    let deposit = Token::new(token_in.inner(), amount_in);
+    token::create(builder, &mut rng, &deposit, pool.owner, npk);
+    let payout_index = builder.created_num();
+    let payout = Token::new(token_out.inner(), amount_out);
+    token::create(builder, &mut rng, &payout, receiver, receiver_npk);
+
+    // The consumed and the created pool have the same logic
+    for (is_input, index) in [(true, pool_index), (false, new_pool_index)] {
+        let pool = pool.clone();
+        builder.add_resource_logic_with_ptx(is_input, index, move |witness, ptx| {
+            let logic = AmmPoolResourceLogicCircuit {
+                self_resource: witness,
+                pool_resource: ptx.spent(pool_index).expect("the pool is consumed"),
+                new_pool_resource: ptx.created(new_pool_index).expect("the pool is created"),
+                deposit_resource: ptx.created(deposit_index).expect("the deposit is created"),
+                payout_resource: ptx.created(payout_index).expect("the payout is created"),
+                pool: pool.clone(),
+                sell_x,
+            };
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        });
+    }
+    new_pool
+}
+
+// Deposits the paid tokens to the pool, for an empty payout to the owner
+fn deposit<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    pool: &Pool,
+    pool_input: (Resource, MerklePath),
+    npk: pallas::Base,
+    (paid, merkle_path): (&TokenResource, MerklePath),
+    auth_sk: pallas::Scalar,
+) -> Pool {
+    let sell_x = paid.token_name() == &pool.token_x;
+    assert!(sell_x || paid.token_name() == &pool.token_y);
+    token::spend(builder, &mut rng, paid, merkle_path, auth_sk);
+    trade(
+        builder,
+        rng,
+        pool,
+        pool_input,
+        npk,
+        sell_x,
+        paid.quantity.inner(),
+        (0, pool.owner, npk),
+    )
+}
+
+/// Opens a pool of empty reserves, owned by `npk`, with the deposit of the
+/// paid tokens. Returns the opened pool.
+///
+/// Panics if the paid tokens are not a token of the pool.
+pub fn open<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    pool: &Pool,
+    npk: pallas::Base,
+    paid: (&TokenResource, MerklePath),
+    auth_sk: pallas::Scalar,
+) -> Pool {
+    let empty = Pool::new(pool.token_x.clone(), pool.token_y.clone(), pool.owner);
+    let ephemeral_pool = (
+        empty.create_ephemeral_resource(&mut rng),
+        MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+    );
+    deposit(builder, rng, &empty, ephemeral_pool, npk, paid, auth_sk)
+}
+
+/// Adds the paid tokens to the reserves of the pool. Returns the new pool.
+///
+/// Panics if the paid tokens are not a token of the pool.
+pub fn add_liquidity<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    rng: R,
+    pool: &Pool,
+    pool_input: (Resource, MerklePath),
+    paid: (&TokenResource, MerklePath),
+    auth_sk: pallas::Scalar,
+) -> Pool {
+    let npk = pool_input.0.get_npk();
+    deposit(builder, rng, pool, pool_input, npk, paid, auth_sk)
+}
+
+/// Sells the paid tokens of the trader of `trader_sk` to the pool for the
+/// quote of the pool, paid from the reserve resource of the owner of
+/// `owner_sk`. Returns the new pool.
+///
+/// Panics if the paid tokens are not a token of the pool, or if the reserve
+/// resource is not of the other token or doesn't cover the quote.
+#[allow(clippy::too_many_arguments)]
+pub fn swap<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    pool: &Pool,
+    pool_input: (Resource, MerklePath),
+    (paid, paid_path): (&TokenResource, MerklePath),
+    trader_sk: pallas::Scalar,
+    (reserve, reserve_path): (&TokenResource, MerklePath),
+    owner_sk: pallas::Scalar,
+) -> Pool {
+    let sell_x = paid.token_name() == &pool.token_x;
+    assert!(sell_x || paid.token_name() == &pool.token_y);
+    let (_, token_out) = pool.tokens(sell_x);
+    assert_eq!(reserve.token_name(), token_out);
+    let amount_in = paid.quantity.inner();
+    let amount_out = pool
+        .quote(sell_x, amount_in)
+        .expect("the quote must fit in a u64");
+    let change = reserve
+        .quantity
+        .inner()
+        .checked_sub(amount_out)
+        .expect("the reserve resource must cover the quote");
+
+    token::spend(builder, &mut rng, paid, paid_path, trader_sk);
+    token::spend(builder, &mut rng, reserve, reserve_path, owner_sk);
+    let npk = pool_input.0.get_npk();
+    let trader = TokenAuthorization::from_sk_vk(&trader_sk, &COMPRESSED_TOKEN_AUTH_VK);
+    let new_pool = trade(
+        builder,
+        &mut rng,
+        pool,
+        pool_input,
+        npk,
+        sell_x,
+        amount_in,
+        (amount_out, trader, paid.get_npk()),
+    );
+    if change > 0 {
+        let change = Token::new(token_out.inner(), change);
+        token::create(builder, &mut rng, &change, pool.owner, reserve.get_npk());
+    }
+    new_pool
+}
+
+#[test]
+fn test_amm_swap() {
+    use crate::nullifier::NullifierKeyContainer;
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let owner_sk = pallas::Scalar::random(&mut rng);
+    let owner = TokenAuthorization::from_sk_vk(&owner_sk, &COMPRESSED_TOKEN_AUTH_VK);
+    let owner_nk = pallas::Base::random(&mut rng);
+    let owner_npk = NullifierKeyContainer::from_key(owner_nk).get_npk();
+    let btc = Token::new("btc".to_string(), 1000);
+    let eth = Token::new("eth".to_string(), 2000);
+    let path = || MerklePath::random(&mut OsRng, TAIGA_COMMITMENT_TREE_DEPTH);
+
+    // The owner opens the pool with 1000 btc
+    let pool = Pool::new(btc.name().clone(), eth.name().clone(), owner);
+    let paid = btc.create_random_input_token_resource(&mut rng, owner_nk, &owner);
+    let mut builder = TransactionBuilder::new();
+    let opened = open(
+        &mut builder,
+        &mut rng,
+        &pool,
+        owner_npk,
+        (&paid, path()),
+        owner_sk,
+    );
+    assert_eq!((opened.reserve_x, opened.reserve_y), (1000, 0));
+    assert!(builder.is_balanced());
+    builder.finalize(&mut rng).unwrap().execute().unwrap();
+
+    // A trader sells 100 btc to the pool of 1000 btc and 2000 eth for 181 eth
+    let pool = Pool {
+        reserve_x: 1000,
+        reserve_y: 2000,
+        ..pool
+    };
+    let mut pool_resource = pool.create_resource(&mut rng, owner_npk);
+    pool_resource.nk_container = NullifierKeyContainer::from_key(owner_nk).into();
+    let trader_sk = pallas::Scalar::random(&mut rng);
+    let trader = TokenAuthorization::from_sk_vk(&trader_sk, &COMPRESSED_TOKEN_AUTH_VK);
+    let trader_nk = pallas::Base::random(&mut rng);
+    let paid = Token::new("btc".to_string(), 100)
+        .create_random_input_token_resource(&mut rng, trader_nk, &trader);
+    let reserve = eth.create_random_input_token_resource(&mut rng, owner_nk, &owner);
+    let mut builder = TransactionBuilder::new();
+    let new_pool = swap(
+        &mut builder,
+        &mut rng,
+        &pool,
+        (pool_resource, path()),
+        (&paid, path()),
+        trader_sk,
+        (&reserve, path()),
+        owner_sk,
+    );
+    assert_eq!((new_pool.reserve_x, new_pool.reserve_y), (1100, 1819));
+    assert!(builder.is_balanced());
+    builder.finalize(&mut rng).unwrap().execute().unwrap();
+}
@@ -0,0 +1,163 @@
+//! Resources consumed with the proof of one of several logics, e.g. "spent by
+//! the owner OR by the recovery logic", see
+//! `DisjunctionResourceLogicCircuit`.
+//!
+//! The branches are fixed by the label of the resource when it is created
+//! with [`create`]. The spender picks the satisfied branch with [`spend`]. The
+//! choice is not private: the proof of the branch logic is verified with its
+//! vk, which the ptx carries in the clear, so the verifier learns which branch
+//! was proven.
+pub use crate::circuit::resource_logic_examples::disjunction::{
+    DisjunctionBranch, DisjunctionResourceLogicCircuit, COMPRESSED_DISJUNCTION_VK,
+    DISJUNCTION_BRANCH_NUM, DISJUNCTION_VK,
+};
+use crate::{
+    builder::TransactionBuilder,
+    circuit::resource_logic_bytecode::{ApplicationByteCode, ResourceLogicByteCode},
+    merkle_tree::MerklePath,
+    resource::{RandomSeed, Resource, ResourceKind},
+    resource_tree::ResourceExistenceWitness,
+};
+use pasta_curves::pallas;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disjunction {
+    pub branches: [DisjunctionBranch; DISJUNCTION_BRANCH_NUM],
+}
+
+impl Disjunction {
+    pub fn new(branches: [DisjunctionBranch; DISJUNCTION_BRANCH_NUM]) -> Self {
+        Self { branches }
+    }
+
+    pub fn label(&self) -> pallas::Base {
+        DisjunctionResourceLogicCircuit::encode_label(&self.branches)
+    }
+
+    /// The kind of the resources guarded by the disjunction.
+    pub fn kind(&self) -> ResourceKind {
+        ResourceKind::new(*COMPRESSED_DISJUNCTION_VK, self.label())
+    }
+
+    fn logic(
+        &self,
+        witness: ResourceExistenceWitness,
+        satisfied_branch: usize,
+        rseed: RandomSeed,
+    ) -> ResourceLogicByteCode {
+        DisjunctionResourceLogicCircuit {
+            self_resource: witness,
+            branches: self.branches,
+            satisfied_branch,
+            rseed,
+        }
+        .to_bytecode()
+    }
+}
+
+/// Creates a resource of the kind of `disjunction`.
+///
+/// Panics if the resource is of another kind.
+pub fn create<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    disjunction: Disjunction,
+    resource: Resource,
+) -> &mut TransactionBuilder {
+    assert_eq!(resource.kind, disjunction.kind());
+    // The factory outlives the rng, it builds the logics from a seed
+    let seed = rng.next_u64();
+    builder.create_with_logic(resource, move |witness| {
+        let rseed = RandomSeed::random(StdRng::seed_from_u64(seed));
+        ApplicationByteCode::new(disjunction.logic(witness, 0, rseed), vec![])
+    })
+}
+
+/// Spends a resource of the kind of `disjunction` with the proof of the branch
+/// `satisfied_branch`. `logic` builds the logic of that branch from the
+/// existence witness of the spent resource.
+///
+/// Panics if the resource is of another kind or the branch doesn't exist.
+pub fn spend<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    disjunction: Disjunction,
+    resource: Resource,
+    merkle_path: MerklePath,
+    satisfied_branch: usize,
+    logic: impl Fn(ResourceExistenceWitness) -> ResourceLogicByteCode + Send + Sync + 'static,
+) -> &mut TransactionBuilder {
+    assert_eq!(resource.kind, disjunction.kind());
+    assert!(satisfied_branch < DISJUNCTION_BRANCH_NUM);
+    let seed = rng.next_u64();
+    builder.spend_with_logic(resource, merkle_path, None, move |witness| {
+        let rseed = RandomSeed::random(StdRng::seed_from_u64(seed));
+        ApplicationByteCode::new(
+            disjunction.logic(witness.clone(), satisfied_branch, rseed),
+            vec![logic(witness)],
+        )
+    })
+}
+
+#[test]
+fn test_disjunction_spend() {
+    use crate::circuit::resource_logic_examples::{
+        TrivialResourceLogicCircuit, COMPRESSED_TRIVIAL_RESOURCE_LOGIC_VK,
+    };
+    use crate::constant::TAIGA_COMMITMENT_TREE_DEPTH;
+    use crate::nullifier::Nullifier;
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    // The owner branch can't be proven here, the trivial logic stands for the
+    // recovery branch
+    let disjunction = Disjunction::new([
+        DisjunctionBranch {
+            logic_vk: pallas::Base::random(&mut rng),
+            data: pallas::Base::random(&mut rng),
+        },
+        DisjunctionBranch {
+            logic_vk: *COMPRESSED_TRIVIAL_RESOURCE_LOGIC_VK,
+            data: pallas::Base::random(&mut rng),
+        },
+    ]);
+    let kind = disjunction.kind();
+    let input = Resource::new_input_resource(
+        kind.logic,
+        kind.label,
+        pallas::Base::zero(),
+        1u64.into(),
+        pallas::Base::random(&mut rng),
+        Nullifier::random(&mut rng),
+        false,
+        pallas::Base::random(&mut rng),
+    );
+    let output = Resource::new_output_resource(
+        kind.logic,
+        kind.label,
+        pallas::Base::zero(),
+        1u64.into(),
+        pallas::Base::random(&mut rng),
+        false,
+        pallas::Base::random(&mut rng),
+    );
+
+    let mut builder = TransactionBuilder::new();
+    spend(
+        &mut builder,
+        &mut rng,
+        disjunction,
+        input,
+        MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+        1,
+        |witness| {
+            TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path())
+                .to_bytecode()
+        },
+    );
+    create(&mut builder, &mut rng, disjunction, output);
+    let tx = builder.finalize(&mut rng).unwrap();
+    tx.execute().unwrap();
+}
@@ -0,0 +1,167 @@
+//! The intent application: ephemeral resources that are consumed only along
+//! with a proof of their satisfaction condition.
+//!
+//! The label of an intent resource commits to the compressed vk of the
+//! condition logic and to the data of the condition, see
+//! `IntentResourceLogicCircuit::encode_label`. The intent logic commits the
+//! condition logic as its first dynamic logic when the intent is consumed, so
+//! the solver must prove the condition logic in the ptx consuming the intent.
+//! A condition logic witnesses its own vk and the data, checks that the label
+//! of the intent resource is their hash and then checks the condition on the
+//! ptx, e.g. that the desired resource is created.
+//!
+//! The user creates the intent with [`create`], usually along with spending
+//! the offered resource, and the solver consumes it in a later ptx with
+//! [`consume`]. The created and consumed intent resources balance each other.
+pub use crate::circuit::resource_logic_examples::intent::{
+    IntentResourceLogicCircuit, COMPRESSED_INTENT_VK, INTENT_VK,
+};
+use crate::{
+    builder::TransactionBuilder,
+    circuit::resource_logic_bytecode::{ApplicationByteCode, ResourceLogicByteCode},
+    constant::TAIGA_COMMITMENT_TREE_DEPTH,
+    merkle_tree::MerklePath,
+    nullifier::Nullifier,
+    resource::{RandomSeed, Resource, ResourceKind},
+    resource_tree::ResourceExistenceWitness,
+};
+use ff::Field;
+use pasta_curves::pallas;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntentResource {
+    pub condition_vk: pallas::Base,
+    pub condition_data: pallas::Base,
+}
+
+impl IntentResource {
+    pub fn new(condition_vk: pallas::Base, condition_data: pallas::Base) -> Self {
+        Self {
+            condition_vk,
+            condition_data,
+        }
+    }
+
+    pub fn label(&self) -> pallas::Base {
+        IntentResourceLogicCircuit::encode_label(self.condition_vk, self.condition_data)
+    }
+
+    pub fn kind(&self) -> ResourceKind {
+        ResourceKind::new(*COMPRESSED_INTENT_VK, self.label())
+    }
+
+    /// The intent resource created by the user. Nobody needs to spend it, so
+    /// it has a random npk.
+    pub fn create_output_resource<R: RngCore>(&self, mut rng: R) -> Resource {
+        Resource::new_output_resource(
+            *COMPRESSED_INTENT_VK,
+            self.label(),
+            pallas::Base::zero(),
+            1u64.into(),
+            pallas::Base::random(&mut rng),
+            true,
+            pallas::Base::random(&mut rng),
+        )
+    }
+
+    /// The intent resource consumed by the solver. It is ephemeral, so it is
+    /// not the created one and a fresh nk works.
+    pub fn create_input_resource<R: RngCore>(&self, mut rng: R) -> Resource {
+        Resource::new_input_resource(
+            *COMPRESSED_INTENT_VK,
+            self.label(),
+            pallas::Base::zero(),
+            1u64.into(),
+            pallas::Base::random(&mut rng),
+            Nullifier::random(&mut rng),
+            true,
+            pallas::Base::random(&mut rng),
+        )
+    }
+
+    fn logic(&self, witness: ResourceExistenceWitness, rseed: RandomSeed) -> ResourceLogicByteCode {
+        IntentResourceLogicCircuit {
+            self_resource: witness,
+            condition_vk: self.condition_vk,
+            condition_data: self.condition_data,
+            rseed,
+        }
+        .to_bytecode()
+    }
+}
+
+/// Creates the intent resource with the intent logic.
+pub fn create<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    intent: IntentResource,
+) -> &mut TransactionBuilder {
+    let resource = intent.create_output_resource(&mut rng);
+    // The factory outlives the rng, it builds the logics from a seed
+    let seed = rng.next_u64();
+    builder.create_with_logic(resource, move |witness| {
+        let rseed = RandomSeed::random(StdRng::seed_from_u64(seed));
+        ApplicationByteCode::new(intent.logic(witness, rseed), vec![])
+    })
+}
+
+/// Consumes an intent with the proof of its condition. `condition` builds the
+/// condition logic from the existence witness of the consumed intent resource,
+/// the other resources it checks are up to the condition logic.
+pub fn consume<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    intent: IntentResource,
+    condition: impl Fn(ResourceExistenceWitness) -> ResourceLogicByteCode + Send + Sync + 'static,
+) -> &mut TransactionBuilder {
+    let resource = intent.create_input_resource(&mut rng);
+    let merkle_path = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
+    let seed = rng.next_u64();
+    builder.spend_with_logic(resource, merkle_path, None, move |witness| {
+        let rseed = RandomSeed::random(StdRng::seed_from_u64(seed));
+        ApplicationByteCode::new(
+            intent.logic(witness.clone(), rseed),
+            vec![condition(witness)],
+        )
+    })
+}
+
+#[test]
+fn test_intent_in_later_ptx() {
+    use crate::circuit::resource_logic_examples::{
+        TrivialResourceLogicCircuit, COMPRESSED_TRIVIAL_RESOURCE_LOGIC_VK,
+    };
+    use crate::error::TransactionError;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    // The trivial logic stands for a condition that always holds
+    let intent = IntentResource::new(
+        *COMPRESSED_TRIVIAL_RESOURCE_LOGIC_VK,
+        pallas::Base::random(&mut rng),
+    );
+
+    let mut user = TransactionBuilder::new();
+    create(&mut user, &mut rng, intent);
+    assert!(!user.is_balanced());
+    let user_ptx = user.finalize_partial_transaction(&mut rng).unwrap();
+
+    let mut solver = TransactionBuilder::new();
+    consume(&mut solver, &mut rng, intent, |witness| {
+        TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path()).to_bytecode()
+    });
+    let tx = solver
+        .add_partial_transaction(user_ptx)
+        .finalize(&mut rng)
+        .unwrap();
+    tx.execute().unwrap();
+
+    // The intent must be consumed by somebody
+    let mut user = TransactionBuilder::new();
+    create(&mut user, &mut rng, intent);
+    assert!(matches!(
+        user.finalize(&mut rng),
+        Err(TransactionError::UnbalancedResources)
+    ));
+}
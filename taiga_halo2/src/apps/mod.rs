@@ -0,0 +1,12 @@
+//! Applications supported by the library, as opposed to the logics of
+//! `resource_logic_examples` that only illustrate the resource logic API.
+pub mod amm;
+pub mod disjunction;
+pub mod intent;
+pub mod nft;
+pub mod solver_fee;
+pub mod swap;
+pub mod sweep;
+pub mod timelock;
+pub mod token;
+pub mod voting;
@@ -0,0 +1,201 @@
+//! Non-fungible tokens paying royalties to their creator, see
+//! `NftResourceLogicCircuit`.
+//!
+//! An NFT is minted with [`mint`] and sold with [`transfer`], in a ptx
+//! consuming the NFT of the seller and the payment of the buyer, and creating
+//! the NFT for the buyer, the sale for the seller and the royalty for the
+//! creator. The executor registers the mints of the transactions in a
+//! [`MintRegistry`], which rejects the second mint of an NFT.
+pub use crate::circuit::resource_logic_examples::nft::{
+    Nft, NftResourceLogicCircuit, COMPRESSED_NFT_VK, NFT_VK,
+};
+use crate::{
+    apps::token::{self, Token, TokenAuthorization, TokenResource},
+    builder::TransactionBuilder,
+    circuit::resource_logic_bytecode::ApplicationByteCode,
+    constant::TAIGA_COMMITMENT_TREE_DEPTH,
+    error::TransactionError,
+    linkability::{LinkabilityTag, LinkabilityTagSet},
+    merkle_tree::MerklePath,
+    resource::Resource,
+    transaction::Transaction,
+};
+use ff::Field;
+use pasta_curves::pallas;
+use rand::RngCore;
+
+// Consumes the NFT and creates it for `npk`, with the logics of both sides.
+// The payments are the created resources at `payment_indexes`, the created NFT
+// itself for a mint.
+fn move_nft<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    nft: Nft,
+    (nft_resource, merkle_path): (Resource, MerklePath),
+    npk: pallas::Base,
+    payment_indexes: Option<(usize, usize)>,
+) {
+    let nft_index = builder.spent_num();
+    builder.spend(nft_resource, merkle_path, None);
+    let new_nft_index = builder.created_num();
+    builder.create(nft.create_resource(&mut rng, npk));
+    let (sale_index, royalty_index) = payment_indexes.unwrap_or((new_nft_index, new_nft_index));
+
+    for (is_input, index) in [(true, nft_index), (false, new_nft_index)] {
+        builder.add_resource_logic_with_ptx(is_input, index, move |witness, ptx| {
+            let logic = NftResourceLogicCircuit {
+                self_resource: witness,
+                nft_resource: ptx.spent(nft_index).expect("the nft is consumed"),
+                new_nft_resource: ptx.created(new_nft_index).expect("the nft is created"),
+                sale_resource: ptx.created(sale_index).expect("the sale is created"),
+                royalty_resource: ptx.created(royalty_index).expect("the royalty is created"),
+                nft,
+            };
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        });
+    }
+}
+
+/// Mints the NFT, owned by `npk`.
+pub fn mint<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    nft: Nft,
+    npk: pallas::Base,
+) -> &mut TransactionBuilder {
+    let ephemeral_nft = (
+        nft.create_ephemeral_resource(&mut rng),
+        MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+    );
+    move_nft(builder, rng, nft, ephemeral_nft, npk, None);
+    builder
+}
+
+/// Sells the NFT to the buyer of `buyer_sk` for their payment, owned by
+/// `buyer_npk`. The royalty of the price goes to the creator, owned by
+/// `creator_npk`, and the rest to the seller. The payment must be at least
+/// the min price of the NFT.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    nft: Nft,
+    nft_input: (Resource, MerklePath),
+    (payment, payment_path): (&TokenResource, MerklePath),
+    (buyer_sk, buyer_npk): (pallas::Scalar, pallas::Base),
+    (seller, seller_npk): (TokenAuthorization, pallas::Base),
+    creator_npk: pallas::Base,
+) -> &mut TransactionBuilder {
+    let price = payment.quantity.inner();
+    let royalty = nft.royalty_of(price);
+    let token_name = payment.token_name().inner();
+
+    token::spend(builder, &mut rng, payment, payment_path, buyer_sk);
+    let sale_index = builder.created_num();
+    let sale = Token::new(token_name.clone(), price - royalty);
+    token::create(builder, &mut rng, &sale, seller, seller_npk);
+    let royalty_index = builder.created_num();
+    let royalty = Token::new(token_name, royalty);
+    token::create(builder, &mut rng, &royalty, nft.creator, creator_npk);
+    move_nft(
+        builder,
+        rng,
+        nft,
+        nft_input,
+        buyer_npk,
+        Some((sale_index, royalty_index)),
+    );
+    builder
+}
+
+/// The NFTs minted so far.
+#[derive(Debug, Clone, Default)]
+pub struct MintRegistry(LinkabilityTagSet);
+
+impl MintRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Registers the mints of an executed transaction. Fails without
+    /// registering any of them if an NFT is already minted.
+    pub fn register(&mut self, tx: &Transaction) -> Result<(), TransactionError> {
+        // The logics of the transfers publish zero
+        let mints: Vec<LinkabilityTag> = tx
+            .get_linkability_tags(&COMPRESSED_NFT_VK)
+            .into_iter()
+            .filter(|tag| tag.inner() != pallas::Base::zero())
+            .collect();
+        self.0.check_and_insert(&mints)
+    }
+}
+
+#[test]
+fn test_nft_royalty() {
+    use crate::{apps::token::COMPRESSED_TOKEN_AUTH_VK, nullifier::NullifierKeyContainer};
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let creator_sk = pallas::Scalar::random(&mut rng);
+    let creator = TokenAuthorization::from_sk_vk(&creator_sk, &COMPRESSED_TOKEN_AUTH_VK);
+    let creator_npk = NullifierKeyContainer::from_key(pallas::Base::random(&mut rng)).get_npk();
+    let seller_nk = pallas::Base::random(&mut rng);
+    let seller_npk = NullifierKeyContainer::from_key(seller_nk).get_npk();
+    let nft = Nft::new(creator, 10, 1000, 1).unwrap();
+    let mut registry = MintRegistry::new();
+
+    // The NFT is minted once
+    let mut builder = TransactionBuilder::new();
+    mint(&mut builder, &mut rng, nft, seller_npk);
+    let tx = builder.finalize(&mut rng).unwrap();
+    tx.execute().unwrap();
+    registry.register(&tx).unwrap();
+    assert_eq!(registry.len(), 1);
+    assert!(matches!(
+        registry.register(&tx),
+        Err(TransactionError::DuplicateLinkabilityTag(_))
+    ));
+
+    // The seller sells the NFT for 1000 usdc, 100 of them to the creator
+    let mut nft_resource = nft.create_resource(&mut rng, seller_npk);
+    nft_resource.nk_container = NullifierKeyContainer::from_key(seller_nk).into();
+    let seller = TokenAuthorization::random(&mut rng);
+    let buyer_sk = pallas::Scalar::random(&mut rng);
+    let buyer = TokenAuthorization::from_sk_vk(&buyer_sk, &COMPRESSED_TOKEN_AUTH_VK);
+    let buyer_nk = pallas::Base::random(&mut rng);
+    let buyer_npk = NullifierKeyContainer::from_key(buyer_nk).get_npk();
+    let payment = Token::new("usdc".to_string(), 1000)
+        .create_random_input_token_resource(&mut rng, buyer_nk, &buyer);
+    let path = || MerklePath::random(&mut OsRng, TAIGA_COMMITMENT_TREE_DEPTH);
+    let mut builder = TransactionBuilder::new();
+    transfer(
+        &mut builder,
+        &mut rng,
+        nft,
+        (nft_resource, path()),
+        (&payment, path()),
+        (buyer_sk, buyer_npk),
+        (seller, seller_npk),
+        creator_npk,
+    );
+    let royalty = builder
+        .output_resources()
+        .into_iter()
+        .find(|resource| resource.value == creator.to_value())
+        .unwrap();
+    assert_eq!(royalty.quantity.inner(), 100);
+    assert!(builder.is_balanced());
+    let tx = builder.finalize(&mut rng).unwrap();
+    tx.execute().unwrap();
+    // Transfers don't mint
+    registry.register(&tx).unwrap();
+    assert_eq!(registry.len(), 1);
+}
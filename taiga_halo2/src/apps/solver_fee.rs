@@ -0,0 +1,157 @@
+//! Fees for the solvers of intents, bounded by the intent author, see
+//! `SolverFeeResourceLogicCircuit`.
+//!
+//! The author locks the max fee with [`lock_fee`], usually in the ptx creating
+//! the intent. The solver of the intent collects its cut with [`collect_fee`]
+//! and refunds the rest to the author. Solvers compete on their cut, the author
+//! never pays more than the max fee.
+pub use crate::circuit::resource_logic_examples::solver_fee::{
+    SolverFee, SolverFeeResourceLogicCircuit, COMPRESSED_SOLVER_FEE_VK, SOLVER_FEE_VK,
+};
+use crate::{
+    apps::token::{self, Token, TokenAuthorization, TokenResource},
+    builder::TransactionBuilder,
+    circuit::resource_logic_bytecode::ApplicationByteCode,
+    constant::TAIGA_COMMITMENT_TREE_DEPTH,
+    merkle_tree::MerklePath,
+    quantity::Quantity,
+};
+use pasta_curves::pallas;
+use rand::RngCore;
+
+/// Spends the fee tokens of the author, authorized by the secret key of the
+/// author, and creates the fee resource.
+///
+/// Panics if `resource` is not the max fee of `fee`.
+pub fn lock_fee<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    fee: &SolverFee,
+    resource: &TokenResource,
+    merkle_path: MerklePath,
+    auth_sk: pallas::Scalar,
+) -> &mut TransactionBuilder {
+    assert_eq!(resource.token_name(), fee.max_fee.name());
+    assert_eq!(resource.resource().quantity, fee.max_fee.quantity());
+    token::spend(builder, &mut rng, resource, merkle_path, auth_sk);
+
+    let fee_resource = fee.create_fee_resource(&mut rng, false);
+    let fee = fee.clone();
+    builder.create_with_logic(fee_resource, move |witness| {
+        let logic = SolverFeeResourceLogicCircuit {
+            self_resource: witness,
+            refund_resource: Default::default(),
+            fee: fee.clone(),
+            solver_cut: Quantity::ZERO,
+        };
+        ApplicationByteCode::new(logic.to_bytecode(), vec![])
+    })
+}
+
+/// Consumes the fee resource of `fee`: creates the cut of the solver, owned by
+/// `solver`, and refunds the rest of the max fee to the author.
+///
+/// Panics if `cut` exceeds the max fee.
+pub fn collect_fee<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    fee: &SolverFee,
+    cut: Quantity,
+    solver: TokenAuthorization,
+    solver_npk: pallas::Base,
+) -> &mut TransactionBuilder {
+    let refund = fee
+        .max_fee
+        .quantity()
+        .checked_sub(cut)
+        .expect("the cut of the solver exceeds the max fee");
+    let token_name = fee.max_fee.name().inner();
+    if !cut.is_zero() {
+        token::create(
+            builder,
+            &mut rng,
+            &Token::new(token_name.clone(), cut.inner()),
+            solver,
+            solver_npk,
+        );
+    }
+    let refund_index = builder.created_num();
+    token::create(
+        builder,
+        &mut rng,
+        &Token::new(token_name, refund.inner()),
+        fee.author_auth,
+        fee.author_npk,
+    );
+
+    // The fee resource is ephemeral, the consumed one is not the created one
+    let fee_index = builder.spent_num();
+    let fee_resource = fee.create_fee_resource(&mut rng, true);
+    let fee = fee.clone();
+    builder
+        .spend(
+            fee_resource,
+            MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+            None,
+        )
+        .add_resource_logic_with_ptx(true, fee_index, move |witness, ptx| {
+            let logic = SolverFeeResourceLogicCircuit {
+                self_resource: witness,
+                refund_resource: ptx.created(refund_index).expect("the refund is created"),
+                fee: fee.clone(),
+                solver_cut: cut,
+            };
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        })
+}
+
+#[test]
+fn test_solver_fee() {
+    use crate::{
+        apps::token::COMPRESSED_TOKEN_AUTH_VK, nullifier::tests::random_nullifier_key_commitment,
+    };
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    // Alice offers at most 5 eth to the solver
+    let alice_sk = pallas::Scalar::random(&mut rng);
+    let alice_auth = TokenAuthorization::from_sk_vk(&alice_sk, &COMPRESSED_TOKEN_AUTH_VK);
+    let fee = SolverFee {
+        max_fee: Token::new("eth".to_string(), 5u64),
+        author_auth: alice_auth,
+        author_npk: random_nullifier_key_commitment(&mut rng).get_npk(),
+    };
+    let alice_nk = pallas::Base::random(&mut rng);
+    let alice_eth = fee
+        .max_fee
+        .create_random_input_token_resource(&mut rng, alice_nk, &alice_auth);
+    let mut alice = TransactionBuilder::new();
+    lock_fee(
+        &mut alice,
+        &mut rng,
+        &fee,
+        &alice_eth,
+        MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+        alice_sk,
+    );
+    let alice_ptx = alice.finalize_partial_transaction(&mut rng).unwrap();
+
+    // The solver takes 2 eth and refunds 3 eth
+    let solver_auth = TokenAuthorization::random(&mut rng);
+    let solver_npk = random_nullifier_key_commitment(&mut rng).get_npk();
+    let mut solver = TransactionBuilder::new();
+    collect_fee(
+        &mut solver,
+        &mut rng,
+        &fee,
+        2u64.into(),
+        solver_auth,
+        solver_npk,
+    );
+    let tx = solver
+        .add_partial_transaction(alice_ptx)
+        .finalize(&mut rng)
+        .unwrap();
+    tx.execute().unwrap();
+}
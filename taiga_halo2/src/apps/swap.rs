@@ -0,0 +1,202 @@
+//! The swap application: intents to sell a token resource for a quantity of
+//! another token, which solvers can fill partially, see
+//! `PartialFulfillmentIntentResourceLogicCircuit`.
+//!
+//! The owner spends the sold resource into the intent with [`create_intent`].
+//! A solver fills a part of it with [`fill`], in a ptx consuming the intent and
+//! creating the bought tokens for the owner at the price of the swap and, for
+//! a partial fill, the rest of the sold tokens returned to the owner. The
+//! owner can offer the returned resource again with `Swap::remainder`. The
+//! sold tokens and the paid tokens are balanced by the ptxs of the takers.
+pub use crate::circuit::resource_logic_examples::partial_fulfillment_intent::{
+    PartialFulfillmentIntentResourceLogicCircuit, Swap, COMPRESSED_PARTIAL_FULFILLMENT_INTENT_VK,
+    PARTIAL_FULFILLMENT_INTENT_VK,
+};
+use crate::{
+    apps::token::{self, Token},
+    builder::TransactionBuilder,
+    circuit::resource_logic_bytecode::ApplicationByteCode,
+    constant::TAIGA_COMMITMENT_TREE_DEPTH,
+    error::TransactionError,
+    merkle_tree::MerklePath,
+    quantity::Quantity,
+};
+use pasta_curves::pallas;
+use rand::RngCore;
+
+/// Spends the sold resource of `swap`, authorized by the secret key of its
+/// owner, and creates the intent.
+pub fn create_intent<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    swap: &Swap,
+    merkle_path: MerklePath,
+    auth_sk: pallas::Scalar,
+) -> &mut TransactionBuilder {
+    let sell_index = builder.spent_num();
+    token::spend(builder, &mut rng, &swap.sell, merkle_path, auth_sk);
+
+    let intent_index = builder.created_num();
+    let intent_resource = swap.create_intent_resource(&mut rng);
+    let swap = swap.clone();
+    builder.create(intent_resource).add_resource_logic_with_ptx(
+        false,
+        intent_index,
+        move |witness, ptx| {
+            let logic = PartialFulfillmentIntentResourceLogicCircuit {
+                self_resource: witness,
+                sell_resource: ptx.spent(sell_index).expect("the sold resource is spent"),
+                offer_resource: Default::default(),
+                returned_resource: Default::default(),
+                swap: swap.clone(),
+            };
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        },
+    )
+}
+
+/// Consumes the intent of `swap` for `filled` of its sold tokens: creates the
+/// bought tokens for the owner and, unless the swap is filled completely,
+/// returns the rest of the sold tokens to the owner. Fails if `filled` is zero,
+/// exceeds the sold quantity or is not worth a whole quantity of the bought
+/// token, see `Swap::offer`.
+pub fn fill<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    swap: &Swap,
+    filled: Quantity,
+) -> Result<&mut TransactionBuilder, TransactionError> {
+    let offer = swap
+        .offer(filled)
+        .ok_or(TransactionError::InvalidSwapFill)?;
+    let owner_npk = swap.sell.get_npk();
+
+    let offer_index = builder.created_num();
+    token::create(builder, &mut rng, &offer, swap.auth, owner_npk);
+    let returned_index = if filled < swap.sell.quantity {
+        let returned = Token::new(
+            swap.sell.token_name().inner(),
+            swap.sell.quantity.checked_sub(filled).unwrap().inner(),
+        );
+        let index = builder.created_num();
+        token::create(builder, &mut rng, &returned, swap.auth, owner_npk);
+        Some(index)
+    } else {
+        None
+    };
+
+    // The intent is ephemeral, the consumed one is not the created one
+    let intent_index = builder.spent_num();
+    let intent_resource = swap.create_intent_resource(&mut rng);
+    let swap = swap.clone();
+    Ok(builder
+        .spend(
+            intent_resource,
+            MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+            None,
+        )
+        .add_resource_logic_with_ptx(true, intent_index, move |witness, ptx| {
+            let logic = PartialFulfillmentIntentResourceLogicCircuit {
+                self_resource: witness,
+                sell_resource: Default::default(),
+                offer_resource: ptx.created(offer_index).expect("the offer is created"),
+                returned_resource: returned_index
+                    .and_then(|index| ptx.created(index))
+                    .unwrap_or_default(),
+                swap: swap.clone(),
+            };
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        }))
+}
+
+#[test]
+fn test_partial_fill() {
+    use crate::{
+        apps::token::{TokenAuthorization, COMPRESSED_TOKEN_AUTH_VK},
+        nullifier::tests::random_nullifier_key_commitment,
+    };
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    // Alice sells 2 btc for 4 eth
+    let alice_sk = pallas::Scalar::random(&mut rng);
+    let alice_auth = TokenAuthorization::from_sk_vk(&alice_sk, &COMPRESSED_TOKEN_AUTH_VK);
+    let swap = Swap::random(
+        &mut rng,
+        Token::new("btc".to_string(), 2u64),
+        Token::new("eth".to_string(), 4u64),
+        alice_auth,
+    );
+    let mut alice = TransactionBuilder::new();
+    create_intent(
+        &mut alice,
+        &mut rng,
+        &swap,
+        MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+        alice_sk,
+    );
+    let alice_ptx = alice.finalize_partial_transaction(&mut rng).unwrap();
+
+    // Bob pays 2 eth for 1 btc
+    let bob_sk = pallas::Scalar::random(&mut rng);
+    let bob_auth = TokenAuthorization::from_sk_vk(&bob_sk, &COMPRESSED_TOKEN_AUTH_VK);
+    let bob_nk = pallas::Base::random(&mut rng);
+    let bob_eth = Token::new("eth".to_string(), 2u64)
+        .create_random_input_token_resource(&mut rng, bob_nk, &bob_auth);
+    let bob_npk = random_nullifier_key_commitment(&mut rng).get_npk();
+    let mut bob = TransactionBuilder::new();
+    token::spend(
+        &mut bob,
+        &mut rng,
+        &bob_eth,
+        MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+        bob_sk,
+    );
+    token::create(
+        &mut bob,
+        &mut rng,
+        &Token::new("btc".to_string(), 1u64),
+        bob_auth,
+        bob_npk,
+    );
+    let bob_ptx = bob.finalize_partial_transaction(&mut rng).unwrap();
+
+    let mut solver = TransactionBuilder::new();
+    // A fill of nothing or of more than the sold tokens is rejected
+    assert!(matches!(
+        fill(&mut solver, &mut rng, &swap, Quantity::ZERO),
+        Err(TransactionError::InvalidSwapFill)
+    ));
+    assert!(matches!(
+        fill(&mut solver, &mut rng, &swap, 3u64.into()),
+        Err(TransactionError::InvalidSwapFill)
+    ));
+    fill(&mut solver, &mut rng, &swap, 1u64.into()).unwrap();
+    let tx = solver
+        .add_partial_transaction(alice_ptx)
+        .add_partial_transaction(bob_ptx)
+        .finalize(&mut rng)
+        .unwrap();
+    tx.execute().unwrap();
+
+    // Alice can offer the returned btc at the same price
+    let returned = solver.output_resources()[1].clone();
+    let remainder = swap.remainder(&returned).unwrap();
+    assert_eq!(remainder.sell.quantity, Quantity::from(1u64));
+    assert_eq!(remainder.buy.quantity(), Quantity::from(2u64));
+    assert_eq!(remainder.sell.get_npk(), swap.sell.get_npk());
+
+    // The price is not truncated: at 5 eth for 2 btc, 1 btc is not worth a
+    // whole quantity of eth
+    let odd_swap = Swap {
+        buy: Token::new("eth".to_string(), 5u64),
+        ..swap.clone()
+    };
+    assert!(odd_swap.offer(1u64.into()).is_none());
+    assert_eq!(
+        odd_swap.offer(2u64.into()).unwrap().quantity(),
+        Quantity::from(5u64)
+    );
+    assert!(odd_swap.remainder(&returned).is_none());
+}
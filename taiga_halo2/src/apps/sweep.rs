@@ -0,0 +1,260 @@
+//! Garbage collection of expired and dust resources, see
+//! `SweepResourceLogicCircuit`.
+//!
+//! Sweepable resources carry an expiry epoch. Once the epoch is reached, or if
+//! the quantity is at most the dust threshold of the executor, anyone may
+//! consume them with [`sweep_expired`] and keep their whole quantity as the
+//! bounty. Sweepers are paid for shrinking the state, so deployments bound it
+//! without a hard fork. `Transaction::execute` checks the published expiries
+//! and quantities with a [`SweepPolicy`] at the epoch of the compliances and
+//! the dust threshold of the verifying context, see [`check_sweeps`].
+pub use crate::circuit::resource_logic_examples::sweep::{
+    SweepResourceLogicCircuit, Sweepable, COMPRESSED_SWEEP_VK, SWEEP_NPK, SWEEP_VK,
+};
+use crate::{
+    apps::token::Token,
+    builder::TransactionBuilder,
+    circuit::{
+        resource_logic_bytecode::ApplicationByteCode,
+        resource_logic_circuit::ResourceLogicPublicInputs,
+    },
+    constant::{
+        RESOURCE_LOGIC_CIRCUIT_SWEEP_EXPIRY_IDX, RESOURCE_LOGIC_CIRCUIT_SWEEP_QUANTITY_IDX,
+    },
+    error::TransactionError,
+    merkle_tree::MerklePath,
+    params::VerifyingContext,
+    quantity::Quantity,
+    resource::Resource,
+    transaction::Transaction,
+};
+use ff::PrimeField;
+use pasta_curves::pallas;
+use rand::RngCore;
+
+/// The expiry and the quantity published by the logic of a swept resource,
+/// zeros if the owner consumes it.
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub struct SweepClaim {
+    pub expiry: pallas::Base,
+    pub quantity: pallas::Base,
+}
+
+impl SweepClaim {
+    pub fn from_public_inputs(public_inputs: &ResourceLogicPublicInputs) -> Self {
+        Self {
+            expiry: public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_SWEEP_EXPIRY_IDX),
+            quantity: public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_SWEEP_QUANTITY_IDX),
+        }
+    }
+}
+
+/// The sweeps an executor accepts at its current epoch.
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub struct SweepPolicy {
+    pub epoch: u64,
+    pub dust_threshold: Quantity,
+}
+
+// The field element as a u64, if it fits
+fn to_u64(x: &pallas::Base) -> Option<u64> {
+    let repr = x.to_repr();
+    if repr[8..].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    Some(u64::from_le_bytes(repr[..8].try_into().unwrap()))
+}
+
+impl SweepPolicy {
+    /// Whether the resource of the claim is expired or dust.
+    pub fn allows(&self, claim: &SweepClaim) -> bool {
+        let expired = to_u64(&claim.expiry).is_some_and(|expiry| expiry <= self.epoch);
+        let dust =
+            to_u64(&claim.quantity).is_some_and(|quantity| quantity <= self.dust_threshold.inner());
+        expired || dust
+    }
+
+    /// Checks the claims of all the sweepable resources of the transaction.
+    pub fn check(&self, tx: &Transaction) -> Result<(), TransactionError> {
+        let allowed = tx
+            .get_logic_public_inputs(&COMPRESSED_SWEEP_VK)
+            .into_iter()
+            .all(|public_inputs| self.allows(&SweepClaim::from_public_inputs(public_inputs)));
+        if allowed {
+            Ok(())
+        } else {
+            Err(TransactionError::SweepNotAllowed)
+        }
+    }
+}
+
+/// Checks the sweeps of the transaction at the `epochs` of its compliances,
+/// which the executor checks against its current epoch, and with the dust
+/// threshold of `context`. Without a compliance no resource is expired. Called
+/// by `Transaction::execute`.
+pub fn check_sweeps(
+    tx: &Transaction,
+    epochs: &[u64],
+    context: &VerifyingContext,
+) -> Result<(), TransactionError> {
+    let policy = SweepPolicy {
+        epoch: epochs.iter().copied().min().unwrap_or_default(),
+        dust_threshold: context.dust_threshold(),
+    };
+    policy.check(tx)
+}
+
+/// Creates a sweepable resource.
+pub fn create<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    sweepable: &Sweepable,
+) -> &mut TransactionBuilder {
+    let resource = sweepable.create_resource(&mut rng, false);
+    let sweepable = sweepable.clone();
+    builder.create_with_logic(resource, move |witness| {
+        let logic = SweepResourceLogicCircuit {
+            self_resource: witness,
+            sweepable: sweepable.clone(),
+            owner_secret: pallas::Base::zero(),
+            is_sweep: false,
+        };
+        ApplicationByteCode::new(logic.to_bytecode(), vec![])
+    })
+}
+
+/// Spends a sweepable resource of the owner of `owner_secret`.
+pub fn spend(
+    builder: &mut TransactionBuilder,
+    sweepable: &Sweepable,
+    resource: Resource,
+    merkle_path: MerklePath,
+    owner_secret: pallas::Base,
+) -> &mut TransactionBuilder {
+    let sweepable = sweepable.clone();
+    builder.spend_with_logic(resource, merkle_path, None, move |witness| {
+        let logic = SweepResourceLogicCircuit {
+            self_resource: witness,
+            sweepable: sweepable.clone(),
+            owner_secret,
+            is_sweep: false,
+        };
+        ApplicationByteCode::new(logic.to_bytecode(), vec![])
+    })
+}
+
+/// Sweeps expired or dust resources of a token and pays their whole quantity
+/// to `sweeper` as a new sweepable resource expiring at `expiry`.
+///
+/// Fails if `resources` is empty, mixes tokens or overflows the bounty.
+pub fn sweep_expired<'a, R: RngCore>(
+    builder: &'a mut TransactionBuilder,
+    mut rng: R,
+    resources: &[(Sweepable, Resource, MerklePath)],
+    sweeper: pallas::Base,
+    expiry: u64,
+) -> Result<&'a mut TransactionBuilder, TransactionError> {
+    let token_name = resources
+        .first()
+        .ok_or(TransactionError::InvalidSweep)?
+        .0
+        .token
+        .name()
+        .clone();
+    if resources
+        .iter()
+        .any(|(sweepable, _, _)| sweepable.token.name() != &token_name)
+    {
+        return Err(TransactionError::InvalidSweep);
+    }
+    let mut bounty = 0u64;
+    for (sweepable, resource, merkle_path) in resources {
+        bounty = bounty
+            .checked_add(resource.quantity.inner())
+            .ok_or(TransactionError::InvalidSweep)?;
+        let sweepable = sweepable.clone();
+        builder.spend_with_logic(
+            resource.clone(),
+            merkle_path.clone(),
+            None,
+            move |witness| {
+                let logic = SweepResourceLogicCircuit {
+                    self_resource: witness,
+                    sweepable: sweepable.clone(),
+                    owner_secret: pallas::Base::zero(),
+                    is_sweep: true,
+                };
+                ApplicationByteCode::new(logic.to_bytecode(), vec![])
+            },
+        );
+    }
+
+    let bounty = Sweepable {
+        token: Token::new(token_name.inner(), bounty),
+        owner: sweeper,
+        expiry,
+    };
+    Ok(create(builder, &mut rng, &bounty))
+}
+
+#[test]
+fn test_sweep_expired() {
+    use crate::constant::TAIGA_COMMITMENT_TREE_DEPTH;
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    // Alice lets 3 eth expire at epoch 10 and 4 eth at epoch 20
+    let alice = Sweepable::derive_owner(pallas::Base::random(&mut rng));
+    let expired: Vec<_> = [(3u64, 10u64), (4, 20)]
+        .into_iter()
+        .map(|(quantity, expiry)| {
+            let sweepable = Sweepable {
+                token: Token::new("eth".to_string(), quantity),
+                owner: alice,
+                expiry,
+            };
+            let resource = sweepable.create_resource(&mut rng, true);
+            (
+                sweepable,
+                resource,
+                MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+            )
+        })
+        .collect();
+
+    // The sweeper collects the 7 eth
+    let sweeper = Sweepable::derive_owner(pallas::Base::random(&mut rng));
+    let mut builder = TransactionBuilder::new();
+    assert!(matches!(
+        sweep_expired(&mut builder, &mut rng, &[], sweeper, 1000),
+        Err(TransactionError::InvalidSweep)
+    ));
+    sweep_expired(&mut builder, &mut rng, &expired, sweeper, 1000).unwrap();
+    assert!(builder.is_balanced());
+    // The 4 eth are not expired at epoch 15
+    builder.at_epoch(15);
+    let tx = builder.clone().finalize(&mut rng).unwrap();
+    assert!(matches!(
+        tx.execute(),
+        Err(TransactionError::SweepNotAllowed)
+    ));
+    // Unless they are dust for the executor
+    let context = VerifyingContext::default().with_dust_threshold(4.into());
+    tx.execute_with_context(&context).unwrap();
+    builder.at_epoch(20);
+    let tx = builder.finalize(&mut rng).unwrap();
+    tx.execute().unwrap();
+
+    let policy = |epoch: u64, dust_threshold: u64| SweepPolicy {
+        epoch,
+        dust_threshold: dust_threshold.into(),
+    };
+    assert!(policy(20, 0).check(&tx).is_ok());
+    // The 4 eth are neither expired nor dust at epoch 15
+    assert!(matches!(
+        policy(15, 3).check(&tx),
+        Err(TransactionError::SweepNotAllowed)
+    ));
+    assert!(policy(15, 4).check(&tx).is_ok());
+}
@@ -0,0 +1,152 @@
+//! Time-locked resources, see `TimelockResourceLogicCircuit`.
+//!
+//! A timelocked resource can only be consumed from its `not_before` epoch to
+//! its `expiry` epoch, e.g. vested tokens or offers expiring at a deadline,
+//! see [`Timelock`]. The logic proves that the epoch it publishes is in the
+//! window, the transaction checks with [`check_epoch`] that it is the epoch of
+//! the compliances, and the executor that the compliances are proven at its
+//! current epoch, so the prover can't pick another epoch.
+pub use crate::circuit::resource_logic_examples::timelock::{
+    Timelock, TimelockResourceLogicCircuit, COMPRESSED_TIMELOCK_VK, TIMELOCK_VK,
+};
+use crate::{
+    builder::TransactionBuilder, circuit::resource_logic_bytecode::ApplicationByteCode,
+    constant::RESOURCE_LOGIC_CIRCUIT_TIMELOCK_EPOCH_IDX, error::TransactionError,
+    merkle_tree::MerklePath, resource::Resource, transaction::Transaction,
+};
+use pasta_curves::pallas;
+
+/// Checks that the timelock logics of the transaction are proven at one of
+/// the `epochs` of its compliances, which the executor checks against its
+/// current epoch. Called by `Transaction::execute`.
+pub fn check_epoch(tx: &Transaction, epochs: &[u64]) -> Result<(), TransactionError> {
+    let current = tx
+        .get_logic_public_inputs(&COMPRESSED_TIMELOCK_VK)
+        .into_iter()
+        .all(|public_inputs| {
+            let logic_epoch =
+                public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_TIMELOCK_EPOCH_IDX);
+            epochs
+                .iter()
+                .any(|epoch| pallas::Base::from(*epoch) == logic_epoch)
+        });
+    if current {
+        Ok(())
+    } else {
+        Err(TransactionError::InvalidEpoch)
+    }
+}
+
+/// Creates a resource carrying `data` under the timelock. The value of the
+/// resource must be `timelock.encode_value(data)`.
+pub fn create(
+    builder: &mut TransactionBuilder,
+    resource: Resource,
+    timelock: Timelock,
+    data: pallas::Base,
+    epoch: u64,
+) -> &mut TransactionBuilder {
+    builder.create_with_logic(resource, move |witness| {
+        let logic = TimelockResourceLogicCircuit {
+            self_resource: witness,
+            timelock,
+            data,
+            epoch,
+        };
+        ApplicationByteCode::new(logic.to_bytecode(), vec![])
+    })
+}
+
+/// Spends a timelocked resource at `epoch`, which must be in its window. The
+/// compliances must be proven at the same epoch, see
+/// `TransactionBuilder::at_epoch`.
+pub fn spend(
+    builder: &mut TransactionBuilder,
+    resource: Resource,
+    merkle_path: MerklePath,
+    timelock: Timelock,
+    data: pallas::Base,
+    epoch: u64,
+) -> &mut TransactionBuilder {
+    builder.spend_with_logic(resource, merkle_path, None, move |witness| {
+        let logic = TimelockResourceLogicCircuit {
+            self_resource: witness,
+            timelock,
+            data,
+            epoch,
+        };
+        ApplicationByteCode::new(logic.to_bytecode(), vec![])
+    })
+}
+
+#[test]
+fn test_timelock() {
+    use crate::{ledger::LedgerState, nullifier::Nullifier, transaction::TransactionResult};
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let label = pallas::Base::random(&mut rng);
+    let data = pallas::Base::random(&mut rng);
+    let nk = pallas::Base::random(&mut rng);
+    // The resource is vested from epoch 10
+    let vested = Timelock::new(Some(10), None);
+    let input = Resource::new_input_resource(
+        *COMPRESSED_TIMELOCK_VK,
+        label,
+        vested.encode_value(data),
+        5u64.into(),
+        nk,
+        Nullifier::random(&mut rng),
+        false,
+        pallas::Base::random(&mut rng),
+    );
+    let unlocked = Timelock::default();
+    let output = Resource::new_output_resource(
+        *COMPRESSED_TIMELOCK_VK,
+        label,
+        unlocked.encode_value(data),
+        5u64.into(),
+        input.get_npk(),
+        false,
+        pallas::Base::random(&mut rng),
+    );
+
+    // The resource was minted before
+    let ledger = LedgerState::new();
+    ledger
+        .apply(&TransactionResult {
+            anchors: vec![],
+            nullifiers: vec![],
+            output_cms: vec![input.commitment()],
+            conversion_rules: vec![],
+            epochs: vec![],
+            blacklist_roots: vec![],
+        })
+        .unwrap();
+    let path = ledger.read_view().merkle_path(&input.commitment()).unwrap();
+
+    let mut builder = TransactionBuilder::new();
+    builder.at_epoch(12);
+    spend(&mut builder, input, path, vested, data, 12);
+    create(&mut builder, output, unlocked, data, 12);
+    let tx = builder.finalize(&mut rng).unwrap();
+    let result = tx.execute().unwrap();
+    assert_eq!(result.epochs, vec![12]);
+
+    // The logic must be proven at the epoch of the compliances
+    assert!(check_epoch(&tx, &[12]).is_ok());
+    assert!(matches!(
+        check_epoch(&tx, &[0]),
+        Err(TransactionError::InvalidEpoch)
+    ));
+
+    // The executor only accepts the compliances proven at its current epoch
+    ledger.set_epoch(13);
+    assert!(matches!(
+        ledger.apply(&result),
+        Err(TransactionError::InvalidEpoch)
+    ));
+    ledger.set_epoch(12);
+    ledger.apply(&result).unwrap();
+}
@@ -0,0 +1,304 @@
+//! The token application: fungible resources labeled with the name of the
+//! token and owned by the Schnorr public key of their authorization.
+//!
+//! A token resource is spent with a signature of its owner, see
+//! `SignatureVerificationResourceLogicCircuit`, and created with its data
+//! encrypted to the receiver, see `ReceiverResourceLogicCircuit`. The functions
+//! below add both sides to a [`TransactionBuilder`], the logics are built once
+//! the resource tree of the ptx is known.
+//!
+//! The token logic rejects ephemeral resources, so the supply of a token is
+//! conserved: the created token resources must be balanced by spent ones of the
+//! same token, in the same ptx as in `transfer` or in the ptx of another party.
+//! The supply only changes with the conversion rules the issuer of the token
+//! authorizes, see `crate::conversion`: `mint` converts a resource of another
+//! kind, e.g. the asset backing the token, into the token and `burn` converts
+//! the token back.
+pub use crate::circuit::resource_logic_examples::{
+    receiver_resource_logic::{ReceiverResourceLogicCircuit, COMPRESSED_RECEIVER_VK},
+    signature_verification::{SignatureVerificationResourceLogicCircuit, COMPRESSED_TOKEN_AUTH_VK},
+    token::{
+        Token, TokenAuthorization, TokenName, TokenResource, TokenResourceLogicCircuit,
+        COMPRESSED_TOKEN_VK, TOKEN_VK,
+    },
+};
+use crate::{builder::TransactionBuilder, conversion::ConversionRule, merkle_tree::MerklePath};
+use pasta_curves::pallas;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+/// Creates a resource of `token` owned by `receiver`, e.g.
+/// `TokenAuthorization::new(pk, *COMPRESSED_TOKEN_AUTH_VK)`. It must be
+/// balanced by spent resources of the token.
+pub fn create<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    token: &Token,
+    receiver: TokenAuthorization,
+    receiver_npk: pallas::Base,
+) -> &mut TransactionBuilder {
+    let resource = token.create_random_output_token_resource(&mut rng, receiver_npk, &receiver);
+    let token_name = token.name().clone();
+    // The factory outlives the rng, it builds the logics from a seed
+    let seed = rng.next_u64();
+    builder.create_with_logic(resource.resource().clone(), move |witness| {
+        let resource = TokenResource {
+            token_name: token_name.clone(),
+            resource: witness.get_resource(),
+        };
+        resource.generate_output_token_app(
+            StdRng::seed_from_u64(seed),
+            receiver,
+            witness.get_path(),
+        )
+    })
+}
+
+/// Spends a token resource, authorized by the secret key of its owner. It must
+/// be balanced by created resources of the token.
+pub fn spend<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    resource: &TokenResource,
+    merkle_path: MerklePath,
+    auth_sk: pallas::Scalar,
+) -> &mut TransactionBuilder {
+    let auth = TokenAuthorization::from_sk_vk(&auth_sk, &COMPRESSED_TOKEN_AUTH_VK);
+    let token_name = resource.token_name().clone();
+    let seed = rng.next_u64();
+    builder.spend_with_logic(
+        resource.resource().clone(),
+        merkle_path,
+        None,
+        move |witness| {
+            let resource = TokenResource {
+                token_name: token_name.clone(),
+                resource: witness.get_resource(),
+            };
+            resource.generate_input_token_app(
+                StdRng::seed_from_u64(seed),
+                auth,
+                auth_sk,
+                witness.get_path(),
+            )
+        },
+    )
+}
+
+/// Spends a token resource and creates the same quantity of the token to
+/// `receiver`.
+pub fn transfer<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    resource: &TokenResource,
+    merkle_path: MerklePath,
+    auth_sk: pallas::Scalar,
+    receiver: TokenAuthorization,
+    receiver_npk: pallas::Base,
+) -> &mut TransactionBuilder {
+    let token = Token::new(
+        resource.token_name().inner(),
+        resource.resource().quantity.inner(),
+    );
+    spend(builder, &mut rng, resource, merkle_path, auth_sk);
+    create(builder, rng, &token, receiver, receiver_npk)
+}
+
+/// Creates a resource of `token` owned by `receiver`, converted with `rule`
+/// from the resource spent at the same index, e.g. the asset backing the
+/// token. The ledger only records the mint once the issuer of the token
+/// authorized the rule, see `LedgerState::authorize_conversion`.
+pub fn mint<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    rng: R,
+    token: &Token,
+    receiver: TokenAuthorization,
+    receiver_npk: pallas::Base,
+    rule: ConversionRule,
+) -> &mut TransactionBuilder {
+    let index = builder.created_num();
+    create(builder, rng, token, receiver, receiver_npk).apply_conversion(index, rule)
+}
+
+/// Spends a token resource, converted with `rule` into the resource created at
+/// the same index, e.g. the asset backing the token. The ledger only records
+/// the burn once the issuer of the created kind authorized the rule.
+pub fn burn<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    rng: R,
+    resource: &TokenResource,
+    merkle_path: MerklePath,
+    auth_sk: pallas::Scalar,
+    rule: ConversionRule,
+) -> &mut TransactionBuilder {
+    let index = builder.spent_num();
+    spend(builder, rng, resource, merkle_path, auth_sk).apply_conversion(index, rule)
+}
+
+#[test]
+fn test_token_transfer() {
+    use crate::{
+        constant::TAIGA_COMMITMENT_TREE_DEPTH, error::TransactionError,
+        nullifier::tests::random_nullifier_key_commitment,
+    };
+    use ff::Field;
+    use group::Group;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let auth_sk = pallas::Scalar::random(&mut rng);
+    let auth = TokenAuthorization::from_sk_vk(&auth_sk, &COMPRESSED_TOKEN_AUTH_VK);
+    let token = Token::new("btc".to_string(), 5u64);
+    let nk = pallas::Base::random(&mut rng);
+    let resource = token.create_random_input_token_resource(&mut rng, nk, &auth);
+    let receiver =
+        TokenAuthorization::new(pallas::Point::random(&mut rng), *COMPRESSED_TOKEN_AUTH_VK);
+    let receiver_npk = random_nullifier_key_commitment(&mut rng).get_npk();
+
+    let mut builder = TransactionBuilder::new();
+    transfer(
+        &mut builder,
+        &mut rng,
+        &resource,
+        MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+        auth_sk,
+        receiver,
+        receiver_npk,
+    );
+    assert!(builder.is_balanced());
+    let tx = builder.finalize(&mut rng).unwrap();
+    tx.execute().unwrap();
+
+    // The supply of the token is conserved
+    let mut builder = TransactionBuilder::new();
+    create(&mut builder, &mut rng, &token, receiver, receiver_npk);
+    assert!(matches!(
+        builder.finalize(&mut rng),
+        Err(TransactionError::UnbalancedResources)
+    ));
+}
+
+#[test]
+fn test_token_mint_burn() {
+    use crate::{
+        circuit::{
+            resource_logic_bytecode::ApplicationByteCode,
+            resource_logic_examples::TrivialResourceLogicCircuit,
+        },
+        conversion::ConversionSigningKey,
+        error::TransactionError,
+        ledger::LedgerState,
+        resource::{tests::random_resource, ResourceKind},
+        resource_tree::ResourceExistenceWitness,
+        transaction::TransactionResult,
+    };
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let trivial_logic = |witness: ResourceExistenceWitness| {
+        let logic = TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path());
+        ApplicationByteCode::new(logic.to_bytecode(), vec![])
+    };
+    // One unit of the backing asset mints two tokens
+    let token = Token::new("wbtc".to_string(), 10u64);
+    let token_kind = ResourceKind::new(*COMPRESSED_TOKEN_VK, token.encode_name());
+    let mut backing = random_resource(&mut rng);
+    backing.quantity = 5u64.into();
+    let mint_rule = ConversionRule::new(backing.kind, token_kind, 1, 2).unwrap();
+    let burn_rule = ConversionRule::new(token_kind, backing.kind, 2, 1).unwrap();
+    let auth_sk = pallas::Scalar::random(&mut rng);
+    let auth = TokenAuthorization::from_sk_vk(&auth_sk, &COMPRESSED_TOKEN_AUTH_VK);
+    let nk = pallas::Base::random(&mut rng);
+    let burnt = token.create_random_input_token_resource(&mut rng, nk, &auth);
+
+    let ledger = LedgerState::new();
+    ledger
+        .apply(&TransactionResult {
+            anchors: vec![],
+            nullifiers: vec![],
+            output_cms: vec![backing.commitment(), burnt.resource().commitment()],
+            conversion_rules: vec![],
+            epochs: vec![],
+            blacklist_roots: vec![],
+        })
+        .unwrap();
+    let view = ledger.read_view();
+
+    // The backing asset is converted into the token
+    let mut builder = TransactionBuilder::new();
+    builder
+        .spend(
+            backing.clone(),
+            view.merkle_path(&backing.commitment()).unwrap(),
+            None,
+        )
+        .add_resource_logic(true, 0, trivial_logic);
+    mint(
+        &mut builder,
+        &mut rng,
+        &token,
+        auth,
+        burnt.get_npk(),
+        mint_rule,
+    );
+    assert!(builder.is_balanced());
+    let result = builder.finalize(&mut rng).unwrap().execute().unwrap();
+    assert_eq!(result.conversion_rules, vec![mint_rule.id()]);
+
+    // Only once the issuer of the token authorized the rule
+    assert!(matches!(
+        ledger.apply(&result),
+        Err(TransactionError::UnauthorizedConversion(_))
+    ));
+    let token_issuer = ConversionSigningKey::random(&mut rng);
+    ledger.register_issuer(token_kind, token_issuer.get_vk());
+    ledger
+        .authorize_conversion(&token_issuer.authorize(&mut rng, mint_rule))
+        .unwrap();
+    ledger.apply(&result).unwrap();
+
+    // The token is converted back into the backing asset
+    let mut redeemed = random_resource(&mut rng);
+    redeemed.kind = backing.kind;
+    redeemed.quantity = 5u64.into();
+    let mut builder = TransactionBuilder::new();
+    burn(
+        &mut builder,
+        &mut rng,
+        &burnt,
+        view.merkle_path(&burnt.resource().commitment()).unwrap(),
+        auth_sk,
+        burn_rule,
+    );
+    builder
+        .create(redeemed)
+        .add_resource_logic(false, 0, trivial_logic);
+    assert!(builder.is_balanced());
+    let result = builder.finalize(&mut rng).unwrap().execute().unwrap();
+    let backing_issuer = ConversionSigningKey::random(&mut rng);
+    ledger.register_issuer(backing.kind, backing_issuer.get_vk());
+    ledger
+        .authorize_conversion(&backing_issuer.authorize(&mut rng, burn_rule))
+        .unwrap();
+    ledger.apply(&result).unwrap();
+
+    // A pair not following the rule fails before proving
+    let mut builder = TransactionBuilder::new();
+    builder.spend(
+        backing.clone(),
+        view.merkle_path(&backing.commitment()).unwrap(),
+        None,
+    );
+    mint(
+        &mut builder,
+        &mut rng,
+        &token,
+        auth,
+        burnt.get_npk(),
+        burn_rule,
+    );
+    assert!(matches!(
+        builder.finalize(&mut rng),
+        Err(TransactionError::InvalidConversion)
+    ));
+}
@@ -0,0 +1,154 @@
+//! Private voting among the members of a census, see
+//! `BallotResourceLogicCircuit`.
+//!
+//! A voter of the census casts a vote with [`cast`], by consuming their
+//! ephemeral ballot with their nk. The ballot, and so its nullifier, only
+//! depends on the voter and the election: the ledger rejects the transactions
+//! of the voters who already voted as double spends. The ballot logic
+//! publishes the linkability tag of the voter for the election and the vote,
+//! and the [`Tally`] counts the votes of the transactions, rejecting the
+//! repeated tags as well.
+pub use crate::circuit::resource_logic_examples::voting::{
+    BallotResourceLogicCircuit, Election, BALLOT_VK, COMPRESSED_BALLOT_VK,
+};
+use crate::{
+    builder::TransactionBuilder,
+    circuit::resource_logic_bytecode::ApplicationByteCode,
+    constant::{RESOURCE_LOGIC_CIRCUIT_BALLOT_VOTE_IDX, TAIGA_COMMITMENT_TREE_DEPTH},
+    error::TransactionError,
+    linkability::LinkabilityTagSet,
+    merkle_tree::MerklePath,
+    transaction::Transaction,
+};
+use pasta_curves::pallas;
+use rand::RngCore;
+
+/// Casts the vote of the voter of `nk` for `choice`. `census_path` is the path
+/// of the npk of the voter in the census, see `Election::census_path`.
+pub fn cast<R: RngCore>(
+    builder: &mut TransactionBuilder,
+    mut rng: R,
+    election: Election,
+    nk: pallas::Base,
+    census_path: MerklePath,
+    choice: u64,
+) -> &mut TransactionBuilder {
+    let ballot = election.create_ballot(nk);
+    let merkle_path = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
+    builder.spend_with_logic(ballot, merkle_path, None, move |witness| {
+        let logic = BallotResourceLogicCircuit {
+            self_resource: witness,
+            election,
+            nk,
+            census_path: census_path.clone(),
+            choice,
+        };
+        ApplicationByteCode::new(logic.to_bytecode(), vec![])
+    })
+}
+
+/// The running tally of an election.
+#[derive(Debug, Clone)]
+pub struct Tally {
+    election: Election,
+    tags: LinkabilityTagSet,
+    counts: Vec<u64>,
+}
+
+impl Tally {
+    pub fn new(election: Election) -> Self {
+        Self {
+            election,
+            tags: LinkabilityTagSet::new(),
+            counts: vec![0; election.options as usize],
+        }
+    }
+
+    /// Counts the votes of an executed transaction. Fails without counting any
+    /// of them if a ballot votes for another election, or if a voter already
+    /// voted.
+    pub fn count(&mut self, tx: &Transaction) -> Result<(), TransactionError> {
+        let votes: Vec<pallas::Base> = tx
+            .get_logic_public_inputs(&COMPRESSED_BALLOT_VK)
+            .into_iter()
+            .map(|public_inputs| {
+                public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_BALLOT_VOTE_IDX)
+            })
+            .collect();
+        let choices = votes
+            .iter()
+            .map(|vote| {
+                (0..self.election.options)
+                    .position(|choice| self.election.vote(choice) == *vote)
+                    .ok_or(TransactionError::InvalidVote)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.tags
+            .check_and_insert(&tx.get_linkability_tags(&COMPRESSED_BALLOT_VK))?;
+        for choice in choices {
+            self.counts[choice] += 1;
+        }
+        Ok(())
+    }
+
+    /// The number of votes of every option.
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+}
+
+#[test]
+fn test_voting() {
+    use crate::ledger::LedgerState;
+    use crate::nullifier::NullifierKeyContainer;
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let nks: Vec<pallas::Base> = (0..3).map(|_| pallas::Base::random(&mut rng)).collect();
+    let census: Vec<pallas::Base> = nks
+        .iter()
+        .map(|nk| NullifierKeyContainer::from_key(*nk).get_npk())
+        .collect();
+    let election = Election::new(&census, pallas::Base::random(&mut rng), 2);
+    let mut tally = Tally::new(election);
+    let ledger = LedgerState::new();
+
+    // The ballots are ephemeral, they are proven against the anchor of the
+    // ledger
+    let vote = |nk: pallas::Base, npk: pallas::Base, choice: u64| {
+        let census_path = Election::census_path(&census, npk).unwrap();
+        let mut builder = TransactionBuilder::new();
+        builder.at_anchor(ledger.anchor());
+        cast(&mut builder, OsRng, election, nk, census_path, choice);
+        builder.finalize(OsRng).unwrap()
+    };
+
+    let first = vote(nks[0], census[0], 1);
+    ledger.apply(&first.execute().unwrap()).unwrap();
+    tally.count(&first).unwrap();
+    let second = vote(nks[1], census[1], 0);
+    ledger.apply(&second.execute().unwrap()).unwrap();
+    tally.count(&second).unwrap();
+    assert_eq!(tally.counts(), &[1, 1]);
+
+    // The first voter can't vote again, even for another option: the ballot
+    // nullifier is published again
+    let again = vote(nks[0], census[0], 0);
+    assert!(matches!(
+        ledger.apply(&again.execute().unwrap()),
+        Err(TransactionError::DoubleSpend(_))
+    ));
+    assert!(matches!(
+        tally.count(&again),
+        Err(TransactionError::DuplicateLinkabilityTag(_))
+    ));
+    assert_eq!(tally.counts(), &[1, 1]);
+
+    // The ballots of another election are not counted
+    let other = Election::new(&census, pallas::Base::random(&mut rng), 2);
+    assert!(matches!(
+        Tally::new(other).count(&first),
+        Err(TransactionError::InvalidVote)
+    ));
+}
@@ -0,0 +1,209 @@
+//! Auxiliary deltas let apps enforce conservation laws over quantities other
+//! than the resource quantity, e.g. "the total voting weight is preserved".
+//!
+//! A resource logic contributes `amount * G_domain + blind * R` for a consumed
+//! resource and `-amount * G_domain + blind * R` for a created one, where
+//! `G_domain` is derived from a domain fixed in the logic. The logic publishes
+//! its contribution in its first extra instance column as `[tag, x, y]`, and
+//! the executor requires the contributions of each logic of a transaction,
+//! told apart by their vks, to sum to zero. The conservation laws are thus
+//! scoped to the vk of the logic enforcing them, and a logic can't cancel the
+//! delta of another app. The blinds of the contributions of a logic must
+//! therefore sum to zero as well, see [`AuxiliaryDelta::balancing_blind`].
+use crate::constant::{AUXILIARY_DELTA_TAG, RESOURCE_COMMITMENT_R_GENERATOR};
+use crate::error::TransactionError;
+use crate::resource::ResourceKind;
+use halo2_proofs::arithmetic::{CurveAffine, Field};
+use pasta_curves::group::cofactor::CofactorCurveAffine;
+use pasta_curves::group::{Curve, Group};
+use pasta_curves::pallas;
+use std::iter::Sum;
+use std::ops::Add;
+
+#[cfg(feature = "serde")]
+use serde;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuxiliaryDelta(pallas::Point);
+
+impl AuxiliaryDelta {
+    /// The generator of a domain fixed in a logic.
+    pub fn generator(domain: pallas::Base) -> pallas::Point {
+        ResourceKind::new(*AUXILIARY_DELTA_TAG, domain).derive_kind()
+    }
+
+    /// Checks that the deltas contributed by each logic, keyed by its
+    /// compressed vk, sum to zero.
+    pub fn check_balances(
+        deltas: &[(pallas::Base, AuxiliaryDelta)],
+    ) -> Result<(), TransactionError> {
+        let mut sums: Vec<(pallas::Base, AuxiliaryDelta)> = vec![];
+        for (logic, delta) in deltas.iter() {
+            match sums.iter_mut().find(|(l, _)| l == logic) {
+                Some((_, sum)) => *sum = *sum + *delta,
+                None => sums.push((*logic, *delta)),
+            }
+        }
+        if sums.iter().any(|(_, sum)| !sum.is_zero()) {
+            return Err(TransactionError::UnbalancedAuxiliaryDelta);
+        }
+        Ok(())
+    }
+
+    pub fn commit(
+        domain: pallas::Base,
+        consumed_amount: u64,
+        created_amount: u64,
+        blind: &pallas::Scalar,
+    ) -> Self {
+        let generator = Self::generator(domain);
+        AuxiliaryDelta(
+            generator * pallas::Scalar::from(consumed_amount)
+                - generator * pallas::Scalar::from(created_amount)
+                + RESOURCE_COMMITMENT_R_GENERATOR.to_curve() * blind,
+        )
+    }
+
+    /// The blind making the blinds of a transaction sum to zero.
+    pub fn balancing_blind(blinds: &[pallas::Scalar]) -> pallas::Scalar {
+        -blinds.iter().sum::<pallas::Scalar>()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        bool::from(self.0.is_identity())
+    }
+
+    pub fn get_x(&self) -> pallas::Base {
+        if self.is_zero() {
+            pallas::Base::zero()
+        } else {
+            *self.0.to_affine().coordinates().unwrap().x()
+        }
+    }
+
+    pub fn get_y(&self) -> pallas::Base {
+        if self.is_zero() {
+            pallas::Base::zero()
+        } else {
+            *self.0.to_affine().coordinates().unwrap().y()
+        }
+    }
+
+    pub fn inner(&self) -> pallas::Point {
+        self.0
+    }
+
+    /// The extra instance column publishing the delta.
+    pub fn to_instance(&self) -> Vec<pallas::Base> {
+        vec![*AUXILIARY_DELTA_TAG, self.get_x(), self.get_y()]
+    }
+
+    /// Reads the delta from an extra instance column. Returns None if the
+    /// column doesn't publish an auxiliary delta.
+    pub fn from_instance(column: &[pallas::Base]) -> Result<Option<Self>, TransactionError> {
+        match column {
+            [tag, x, y, ..] if *tag == *AUXILIARY_DELTA_TAG => {
+                if bool::from(x.is_zero()) && bool::from(y.is_zero()) {
+                    return Ok(Some(AuxiliaryDelta(pallas::Point::identity())));
+                }
+                Option::<pallas::Affine>::from(pallas::Affine::from_xy(*x, *y))
+                    .map(|p| Some(AuxiliaryDelta(p.to_curve())))
+                    .ok_or(TransactionError::InvalidAuxiliaryDelta)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Add for AuxiliaryDelta {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        AuxiliaryDelta(self.0 + rhs.0)
+    }
+}
+
+impl Sum for AuxiliaryDelta {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(AuxiliaryDelta(pallas::Point::identity()), Add::add)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for AuxiliaryDelta {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        use pasta_curves::group::GroupEncoding;
+        writer.write_all(&self.0.to_bytes())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for AuxiliaryDelta {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let point = crate::utils::read_point(reader)?;
+        Ok(Self(point))
+    }
+}
+
+#[test]
+fn test_auxiliary_delta() {
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let domain = pallas::Base::random(&mut rng);
+    let blinds = [
+        pallas::Scalar::random(&mut rng),
+        pallas::Scalar::random(&mut rng),
+    ];
+    let last_blind = AuxiliaryDelta::balancing_blind(&blinds);
+
+    // 7 + 3 consumed, 10 created
+    let deltas = [
+        AuxiliaryDelta::commit(domain, 7, 0, &blinds[0]),
+        AuxiliaryDelta::commit(domain, 3, 0, &blinds[1]),
+        AuxiliaryDelta::commit(domain, 0, 10, &last_blind),
+    ];
+    assert!(deltas.iter().copied().sum::<AuxiliaryDelta>().is_zero());
+
+    // Creating more than consumed doesn't balance
+    let unbalanced = AuxiliaryDelta::commit(domain, 0, 11, &last_blind);
+    assert!(!(deltas[0] + deltas[1] + unbalanced).is_zero());
+
+    // Another domain doesn't balance either
+    let other_domain = AuxiliaryDelta::commit(pallas::Base::one(), 0, 10, &last_blind);
+    assert!(!(deltas[0] + deltas[1] + other_domain).is_zero());
+
+    // Each logic balances its own deltas, another logic can't cancel them
+    let logic = domain;
+    let other_logic = pallas::Base::one();
+    assert!(AuxiliaryDelta::check_balances(&deltas.map(|delta| (logic, delta))).is_ok());
+    let mut mixed = deltas.map(|delta| (logic, delta));
+    mixed[2].0 = other_logic;
+    assert!(matches!(
+        AuxiliaryDelta::check_balances(&mixed),
+        Err(TransactionError::UnbalancedAuxiliaryDelta)
+    ));
+
+    let zero = AuxiliaryDelta::commit(domain, 0, 0, &pallas::Scalar::zero());
+    assert!(zero.is_zero());
+    for delta in deltas.iter().chain([zero].iter()) {
+        assert_eq!(
+            AuxiliaryDelta::from_instance(&delta.to_instance()).unwrap(),
+            Some(*delta)
+        );
+    }
+    assert_eq!(
+        AuxiliaryDelta::from_instance(&[pallas::Base::one()]).unwrap(),
+        None
+    );
+    assert!(AuxiliaryDelta::from_instance(&[
+        *AUXILIARY_DELTA_TAG,
+        pallas::Base::one(),
+        pallas::Base::one()
+    ])
+    .is_err());
+}
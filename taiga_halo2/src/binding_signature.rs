@@ -1,6 +1,11 @@
 use crate::constant::RESOURCE_COMMITMENT_R_GENERATOR;
+use crate::domains::BINDING_SIG_H_STAR_PERSONALIZATION;
+use blake2b_simd::Params as Blake2bParams;
 use pasta_curves::group::cofactor::CofactorCurveAffine;
-use pasta_curves::group::{ff::PrimeField, GroupEncoding};
+use pasta_curves::group::{
+    ff::{Field, FromUniformBytes, PrimeField},
+    Group, GroupEncoding,
+};
 use pasta_curves::pallas;
 use rand::{CryptoRng, RngCore};
 use reddsa::{private, Error, SigType, Signature, SigningKey, VerificationKey};
@@ -21,7 +26,7 @@ impl Default for TaigaBinding {
 }
 
 impl private::Sealed<TaigaBinding> for TaigaBinding {
-    const H_STAR_PERSONALIZATION: &'static [u8; 16] = b"Taiga_RedPallasH";
+    const H_STAR_PERSONALIZATION: &'static [u8; 16] = BINDING_SIG_H_STAR_PERSONALIZATION;
     type Point = pallas::Point;
     type Scalar = pallas::Scalar;
 
@@ -121,8 +126,151 @@ impl BindingVerificationKey {
     }
 }
 
+#[cfg(feature = "borsh")]
+impl BorshSerialize for BindingVerificationKey {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let bytes: [u8; 32] = self.0.into();
+        writer.write_all(&bytes)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for BindingVerificationKey {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        use std::io;
+        let mut key_bytes = [0u8; 32];
+        reader.read_exact(&mut key_bytes)?;
+        VerificationKey::<TaigaBinding>::try_from(key_bytes)
+            .map(BindingVerificationKey)
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "BindingVerificationKey not a point",
+                )
+            })
+    }
+}
+
 impl From<pallas::Point> for BindingVerificationKey {
     fn from(p: pallas::Point) -> Self {
         BindingVerificationKey(p.to_bytes().try_into().unwrap())
     }
 }
+
+/// The secret nonce of a party signing its share of the binding signature of
+/// a multi-party transaction, where each party only knows the binding
+/// randomness of its own ptxs.
+///
+/// The signature is a two-round Schnorr multi-signature: every party publishes
+/// the commitment of a fresh nonce, then signs its share for the
+/// [`BindingSigningSession`] of the transaction, and the shares are summed into
+/// a regular [`BindingSignature`]. A nonce must be used for one session only,
+/// so `sign_share` consumes it, and a party must not sign several sessions
+/// concurrently.
+#[derive(Debug)]
+pub struct BindingNonce(pallas::Scalar);
+
+/// The commitment of a [`BindingNonce`], published to the other parties.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BindingNonceCommitment(pallas::Point);
+
+/// The share of the binding signature signed by a party.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BindingSignatureShare(pallas::Scalar);
+
+/// What the parties sign their shares for: the message, the binding
+/// verification key of the whole transaction and the sum of the nonce
+/// commitments of all the parties.
+#[derive(Clone, Debug)]
+pub struct BindingSigningSession {
+    msg: Vec<u8>,
+    vk: pallas::Point,
+    nonce_commitment: pallas::Point,
+}
+
+impl BindingNonce {
+    pub fn random<R: RngCore + CryptoRng>(rng: R) -> Self {
+        Self(pallas::Scalar::random(rng))
+    }
+
+    pub fn commitment(&self) -> BindingNonceCommitment {
+        BindingNonceCommitment(RESOURCE_COMMITMENT_R_GENERATOR.to_curve() * self.0)
+    }
+
+    /// Signs the share of a party whose ptxs have the binding randomness `sk`,
+    /// the sum of their `get_binding_sig_r`.
+    pub fn sign_share(
+        self,
+        sk: &pallas::Scalar,
+        session: &BindingSigningSession,
+    ) -> BindingSignatureShare {
+        BindingSignatureShare(self.0 + session.challenge() * sk)
+    }
+}
+
+impl BindingSigningSession {
+    pub fn new(
+        msg: &[u8],
+        vk: pallas::Point,
+        nonce_commitments: &[BindingNonceCommitment],
+    ) -> Self {
+        let nonce_commitment = nonce_commitments
+            .iter()
+            .fold(pallas::Point::identity(), |acc, commitment| {
+                acc + commitment.0
+            });
+        Self {
+            msg: msg.to_vec(),
+            vk,
+            nonce_commitment,
+        }
+    }
+
+    // The challenge of the RedDSA signature: H*(R || vk || msg)
+    fn challenge(&self) -> pallas::Scalar {
+        let hash = Blake2bParams::new()
+            .hash_length(64)
+            .personal(BINDING_SIG_H_STAR_PERSONALIZATION)
+            .to_state()
+            .update(&self.nonce_commitment.to_bytes())
+            .update(&self.vk.to_bytes())
+            .update(&self.msg)
+            .finalize();
+        pallas::Scalar::from_uniform_bytes(hash.as_array())
+    }
+
+    /// Sums the shares of all the parties into the binding signature. The
+    /// signature only verifies if no share is missing or invalid.
+    pub fn aggregate(&self, shares: &[BindingSignatureShare]) -> BindingSignature {
+        let s: pallas::Scalar = shares.iter().map(|share| share.0).sum();
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.nonce_commitment.to_bytes());
+        bytes[32..].copy_from_slice(&s.to_repr());
+        BindingSignature::from_bytes(bytes)
+    }
+}
+
+#[test]
+fn test_binding_signature_shares() {
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let msg = b"transaction digest";
+    let sks: Vec<pallas::Scalar> = (0..3).map(|_| pallas::Scalar::random(&mut rng)).collect();
+    let vk = RESOURCE_COMMITMENT_R_GENERATOR.to_curve() * sks.iter().sum::<pallas::Scalar>();
+
+    let nonces: Vec<BindingNonce> = (0..3).map(|_| BindingNonce::random(&mut rng)).collect();
+    let commitments: Vec<BindingNonceCommitment> =
+        nonces.iter().map(|nonce| nonce.commitment()).collect();
+    let session = BindingSigningSession::new(msg, vk, &commitments);
+    let shares: Vec<BindingSignatureShare> = nonces
+        .into_iter()
+        .zip(sks.iter())
+        .map(|(nonce, sk)| nonce.sign_share(sk, &session))
+        .collect();
+
+    // The aggregated signature is a regular binding signature
+    let vk = BindingVerificationKey::from(vk);
+    assert!(vk.verify(msg, &session.aggregate(&shares)).is_ok());
+    assert!(vk.verify(msg, &session.aggregate(&shares[1..])).is_err());
+}
@@ -0,0 +1,364 @@
+//! A block bundle is an ordered set of transactions submitted to an executor
+//! together. Transactions in the same bundle may consume the resources created
+//! by others, so they can't all be applied in parallel. The dependency graph
+//! tells the executor which transactions must be applied first.
+use crate::{
+    error::TransactionError,
+    nullifier::Nullifier,
+    resource::ResourceCommitment,
+    transaction::{Transaction, TransactionResult},
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+#[cfg(feature = "serde")]
+use serde;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockBundle(Vec<Transaction>);
+
+/// The resources a transaction touches, as far as they are visible to the
+/// executor.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionFootprint {
+    /// The commitments of the consumed resources. Only the transparent inputs
+    /// are known, the shielded ones are hidden behind their nullifiers.
+    pub input_cms: Vec<ResourceCommitment>,
+    pub nullifiers: Vec<Nullifier>,
+    pub output_cms: Vec<ResourceCommitment>,
+}
+
+/// `dependencies[i]` are the indices of the transactions that must be applied
+/// before transaction `i`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    dependencies: Vec<Vec<usize>>,
+}
+
+/// What `BlockBundle::verify_parallel` does when a transaction is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationPolicy {
+    /// The bundle is rejected as a whole: the remaining transactions are not
+    /// verified after the first failure.
+    AllOrNothing,
+    /// Every transaction is verified, the invalid ones are dropped by the
+    /// proposer.
+    BestEffort,
+}
+
+/// Stops a running verification from another thread, e.g. when the latency
+/// budget of a block proposal is exhausted. The transactions being verified
+/// are finished, the ones not started yet are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl BlockBundle {
+    pub fn new(transactions: Vec<Transaction>) -> Self {
+        Self(transactions)
+    }
+
+    pub fn add_transaction(&mut self, tx: Transaction) {
+        self.0.push(tx);
+    }
+
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn footprints(&self) -> Vec<TransactionFootprint> {
+        self.0
+            .iter()
+            .map(|tx| TransactionFootprint {
+                input_cms: tx.get_input_cms(),
+                nullifiers: tx.get_nullifiers(),
+                output_cms: tx.get_output_cms(),
+            })
+            .collect()
+    }
+
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        DependencyGraph::from_footprints(&self.footprints())
+    }
+
+    /// Verifies the transactions on all the available threads, see
+    /// [`BlockBundle::verify_parallel_with_threads`].
+    pub fn verify_parallel(
+        &self,
+        policy: VerificationPolicy,
+        cancel_token: &CancelToken,
+    ) -> Vec<Option<Result<TransactionResult, TransactionError>>> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        self.verify_parallel_with_threads(policy, cancel_token, threads)
+    }
+
+    /// Verifies every transaction on its own, on up to `threads` threads. The
+    /// result of a transaction is None if it was skipped, because the
+    /// verification was cancelled or, under
+    /// [`VerificationPolicy::AllOrNothing`], another transaction failed first.
+    ///
+    /// The transactions are verified independently of each other, the
+    /// executor still applies them along the dependency graph. Without the
+    /// `std` feature, e.g. in the browser, they are verified on the calling
+    /// thread.
+    pub fn verify_parallel_with_threads(
+        &self,
+        policy: VerificationPolicy,
+        cancel_token: &CancelToken,
+        threads: usize,
+    ) -> Vec<Option<Result<TransactionResult, TransactionError>>> {
+        let next = AtomicUsize::new(0);
+        let aborted = AtomicBool::new(false);
+        let results: Mutex<Vec<Option<Result<TransactionResult, TransactionError>>>> =
+            Mutex::new((0..self.0.len()).map(|_| None).collect());
+        let verify = || loop {
+            if cancel_token.is_cancelled() || aborted.load(Ordering::SeqCst) {
+                return;
+            }
+            let index = next.fetch_add(1, Ordering::SeqCst);
+            let Some(tx) = self.0.get(index) else {
+                return;
+            };
+            let result = tx.execute();
+            if result.is_err() && policy == VerificationPolicy::AllOrNothing {
+                aborted.store(true, Ordering::SeqCst);
+            }
+            results.lock().unwrap()[index] = Some(result);
+        };
+        let threads = if cfg!(feature = "std") {
+            threads.clamp(1, self.0.len().max(1))
+        } else {
+            1
+        };
+        if threads == 1 {
+            verify();
+        } else {
+            std::thread::scope(|s| {
+                for _ in 0..threads {
+                    s.spawn(verify);
+                }
+            });
+        }
+        results.into_inner().unwrap()
+    }
+}
+
+impl DependencyGraph {
+    /// Builds the graph of the transactions described by `footprints`:
+    /// * a transaction depends on the transactions creating the commitments it
+    ///   consumes;
+    /// * two transactions publishing the same nullifier conflict, the later one
+    ///   in the bundle depends on the earlier one, so that the executor applies
+    ///   the first and rejects the second.
+    pub fn from_footprints(footprints: &[TransactionFootprint]) -> Self {
+        let mut creators: HashMap<ResourceCommitment, usize> = HashMap::new();
+        for (i, footprint) in footprints.iter().enumerate() {
+            for cm in footprint.output_cms.iter() {
+                creators.entry(*cm).or_insert(i);
+            }
+        }
+
+        let mut spenders: HashMap<Nullifier, usize> = HashMap::new();
+        let dependencies = footprints
+            .iter()
+            .enumerate()
+            .map(|(i, footprint)| {
+                let mut deps: Vec<usize> = footprint
+                    .input_cms
+                    .iter()
+                    .filter_map(|cm| creators.get(cm).copied())
+                    .chain(
+                        footprint
+                            .nullifiers
+                            .iter()
+                            .filter_map(|nf| spenders.insert(*nf, i)),
+                    )
+                    .filter(|&j| j != i)
+                    .collect();
+                deps.sort_unstable();
+                deps.dedup();
+                deps
+            })
+            .collect();
+
+        Self { dependencies }
+    }
+
+    pub fn len(&self) -> usize {
+        self.dependencies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty()
+    }
+
+    pub fn dependencies(&self, tx_index: usize) -> &[usize] {
+        &self.dependencies[tx_index]
+    }
+
+    /// Splits the transactions into batches to be applied one after another.
+    /// The transactions within a batch don't depend on each other and can be
+    /// applied in parallel.
+    pub fn execution_batches(&self) -> Result<Vec<Vec<usize>>, TransactionError> {
+        let mut dependents = vec![vec![]; self.len()];
+        let mut pending: Vec<usize> = self.dependencies.iter().map(|deps| deps.len()).collect();
+        for (i, deps) in self.dependencies.iter().enumerate() {
+            for &j in deps {
+                dependents[j].push(i);
+            }
+        }
+
+        let mut ready: VecDeque<usize> = (0..self.len()).filter(|&i| pending[i] == 0).collect();
+        let mut batches = vec![];
+        let mut scheduled = 0;
+        while !ready.is_empty() {
+            let batch: Vec<usize> = ready.drain(..).collect();
+            for &i in batch.iter() {
+                for &k in dependents[i].iter() {
+                    pending[k] -= 1;
+                    if pending[k] == 0 {
+                        ready.push_back(k);
+                    }
+                }
+            }
+            scheduled += batch.len();
+            batches.push(batch);
+        }
+
+        if scheduled != self.len() {
+            return Err(TransactionError::CyclicTransactionDependency);
+        }
+        Ok(batches)
+    }
+}
+
+#[test]
+fn test_dependency_graph() {
+    use halo2_proofs::arithmetic::Field;
+    use pasta_curves::pallas;
+    use rand::rngs::OsRng;
+
+    let random_cm = || ResourceCommitment::from(pallas::Base::random(&mut OsRng));
+    let (cm_0, cm_1, cm_2) = (random_cm(), random_cm(), random_cm());
+    let nf = Nullifier::random(&mut OsRng);
+
+    let footprints = vec![
+        // tx 0 consumes the resource created by tx 2
+        TransactionFootprint {
+            input_cms: vec![cm_2],
+            nullifiers: vec![Nullifier::random(&mut OsRng)],
+            output_cms: vec![cm_0],
+        },
+        // tx 1 is independent
+        TransactionFootprint {
+            input_cms: vec![],
+            nullifiers: vec![nf],
+            output_cms: vec![cm_1],
+        },
+        TransactionFootprint {
+            input_cms: vec![],
+            nullifiers: vec![Nullifier::random(&mut OsRng)],
+            output_cms: vec![cm_2],
+        },
+        // tx 3 consumes the output of tx 0 and conflicts with tx 1
+        TransactionFootprint {
+            input_cms: vec![cm_0],
+            nullifiers: vec![nf],
+            output_cms: vec![],
+        },
+    ];
+
+    let graph = DependencyGraph::from_footprints(&footprints);
+    assert_eq!(graph.dependencies(0), &[2]);
+    assert!(graph.dependencies(1).is_empty());
+    assert_eq!(graph.dependencies(3), &[0, 1]);
+    assert_eq!(
+        graph.execution_batches().unwrap(),
+        vec![vec![1, 2], vec![0], vec![3]]
+    );
+
+    // Two transactions consuming the outputs of each other can't be ordered
+    let cyclic = DependencyGraph::from_footprints(&[
+        TransactionFootprint {
+            input_cms: vec![cm_1],
+            nullifiers: vec![],
+            output_cms: vec![cm_0],
+        },
+        TransactionFootprint {
+            input_cms: vec![cm_0],
+            nullifiers: vec![],
+            output_cms: vec![cm_1],
+        },
+    ]);
+    assert!(matches!(
+        cyclic.execution_batches(),
+        Err(TransactionError::CyclicTransactionDependency)
+    ));
+}
+
+#[test]
+fn test_verify_parallel() {
+    use crate::shielded_ptx::testing::create_shielded_ptx;
+    use crate::transaction::{ShieldedPartialTxBundle, TransparentPartialTxBundle};
+    use rand::rngs::OsRng;
+
+    let tx = Transaction::build(
+        OsRng,
+        ShieldedPartialTxBundle::new(vec![create_shielded_ptx()]),
+        TransparentPartialTxBundle::default(),
+    )
+    .unwrap();
+    // The same ptx twice is an invalid transaction
+    let invalid = Transaction::build(
+        OsRng,
+        ShieldedPartialTxBundle::new(vec![create_shielded_ptx(); 2]),
+        TransparentPartialTxBundle::default(),
+    )
+    .unwrap();
+    let bundle = BlockBundle::new(vec![invalid, tx.clone(), tx]);
+
+    let results =
+        bundle.verify_parallel_with_threads(VerificationPolicy::BestEffort, &CancelToken::new(), 2);
+    assert!(matches!(results[0], Some(Err(_))));
+    assert!(matches!(results[1], Some(Ok(_))));
+    assert!(matches!(results[2], Some(Ok(_))));
+
+    // On a single thread, the invalid first transaction stops the others
+    let results = bundle.verify_parallel_with_threads(
+        VerificationPolicy::AllOrNothing,
+        &CancelToken::new(),
+        1,
+    );
+    assert!(matches!(results[0], Some(Err(_))));
+    assert!(results[1..].iter().all(Option::is_none));
+
+    let cancel_token = CancelToken::new();
+    cancel_token.cancel();
+    let results = bundle.verify_parallel(VerificationPolicy::BestEffort, &cancel_token);
+    assert!(results.iter().all(Option::is_none));
+}
@@ -0,0 +1,1367 @@
+//! A transaction builder that records the high-level actions it is fed in an
+//! [`ActionLog`]. The log is serializable, so a user reporting a proof failure
+//! can send it along and the transaction can be rebuilt from it, e.g. with a
+//! fixed RNG seed to reproduce the failure in a regression test.
+//!
+//! Every created resource is paired with the spent resource of the same index
+//! in a compliance, and the application logics are attached per resource.
+//! Unpaired resources are paired with padding resources by `pad`. The
+//! resources read with `read` are not consumed: each gets a read proof of its
+//! existence, and its read tag follows the paired resources in the resource
+//! tree. A pair can be converted
+//! with a conversion rule instead of balanced, see `apply_conversion`.
+//! Logics that depend on the resource tree of the ptx are added as factories
+//! and built by `finalize` once all the resources are known. The memos of the
+//! created resources are encrypted when the transaction is built.
+use crate::{
+    circuit::resource_logic_bytecode::ApplicationByteCode,
+    compliance::{ComplianceInfo, ReadInfo},
+    constant::RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+    conversion::ConversionRule,
+    delta_commitment::BalanceSummary,
+    error::TransactionError,
+    fee::Fee,
+    merkle_tree::{Anchor, MerklePath},
+    params::ProvingContext,
+    proving_cost::{HostCalibration, ProvingEstimate},
+    resource::Resource,
+    resource_encryption::{Address, Memo},
+    resource_tree::{ResourceExistenceWitness, ResourceMerkleTreeLeaves},
+    shielded_ptx::{DraftedPartialTransaction, ShieldedPartialTransaction},
+    transaction::{
+        ShieldedPartialTxBundle, Transaction, TransparentPartialTxBundle, UnsignedTransaction,
+    },
+    witness_set::WitnessSet,
+};
+#[cfg(feature = "borsh")]
+use crate::{
+    circuit::resource_logic_examples::TrivialResourceLogicCircuit,
+    constant::{MAX_PTX_COMPLIANCE_NUM, TAIGA_COMMITMENT_TREE_DEPTH},
+};
+use ff::Field;
+use pasta_curves::pallas;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::{fmt, sync::Arc};
+
+#[cfg(feature = "serde")]
+use serde;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BuilderAction {
+    /// Spend a resource. The anchor is calculated from the path unless a
+    /// custom anchor is given, see `ComplianceInfo::new`.
+    Spend {
+        resource: Resource,
+        merkle_path: MerklePath,
+        custom_anchor: Option<Anchor>,
+    },
+    /// Create a resource. Its nonce is set from the paired spent resource.
+    Create {
+        resource: Resource,
+    },
+    /// Attach the application logic of the spent resource at `index`.
+    AttachInputLogic {
+        index: usize,
+        app: ApplicationByteCode,
+    },
+    /// Attach the application logic of the created resource at `index`.
+    AttachOutputLogic {
+        index: usize,
+        app: ApplicationByteCode,
+    },
+    AddHints(Vec<u8>),
+    /// Read a resource without consuming it, see `TransactionBuilder::read`.
+    /// The anchor is calculated from the path unless a custom anchor is given.
+    Read {
+        resource: Resource,
+        merkle_path: MerklePath,
+        custom_anchor: Option<Anchor>,
+    },
+    /// Attach a memo to the created resource at `index`, encrypted to the
+    /// address of its receiver.
+    AttachMemo {
+        index: usize,
+        address: Address,
+        memo: Vec<u8>,
+    },
+    /// Pay a fee with the surplus of fee resources spent, replacing the fee
+    /// paid before.
+    PayFee(Fee),
+    /// Prove the compliances at an epoch, replacing the epoch set before.
+    SetEpoch(u64),
+    /// Convert the spent resource at `index` into the created resource at
+    /// `index` with a rule, replacing the rule applied before.
+    ApplyConversion {
+        index: usize,
+        rule: ConversionRule,
+    },
+    /// Prove the padding and the ephemeral spent resources against an anchor,
+    /// replacing the anchor set before.
+    SetAnchor(Anchor),
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActionLog(Vec<BuilderAction>);
+
+/// Builds the application logic of a resource from its existence witness in
+/// the resource tree of the ptx, and those of the other resources.
+#[derive(Clone)]
+pub struct ResourceLogicFactory(
+    Arc<
+        dyn Fn(ResourceExistenceWitness, &PtxResourceWitnesses) -> ApplicationByteCode
+            + Send
+            + Sync,
+    >,
+);
+
+/// The existence witnesses of all the resources of the ptx, for the logics
+/// checking other resources than their own, e.g. an intent checking the
+/// resources it is fulfilled with.
+#[derive(Debug, Clone, Default)]
+pub struct PtxResourceWitnesses {
+    spent: Vec<ResourceExistenceWitness>,
+    created: Vec<ResourceExistenceWitness>,
+    read: Vec<ResourceExistenceWitness>,
+}
+
+impl PtxResourceWitnesses {
+    /// The witness of the spent resource at `index`.
+    pub fn spent(&self, index: usize) -> Option<ResourceExistenceWitness> {
+        self.spent.get(index).cloned()
+    }
+
+    /// The witness of the created resource at `index`, with the nonce it gets
+    /// in the transaction.
+    pub fn created(&self, index: usize) -> Option<ResourceExistenceWitness> {
+        self.created.get(index).cloned()
+    }
+
+    /// The witness of the read resource at `index`, whose path opens its read
+    /// tag, see `integrity::load_read_resource`.
+    pub fn read(&self, index: usize) -> Option<ResourceExistenceWitness> {
+        self.read.get(index).cloned()
+    }
+}
+
+impl fmt::Debug for ResourceLogicFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ResourceLogicFactory")
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TransactionBuilder {
+    log: ActionLog,
+    // The logics waiting for the resource tree: (is_input, index, factory)
+    pending_logics: Vec<(bool, usize, ResourceLogicFactory)>,
+    // The ptxs of the other parties, e.g. the solver matching an intent. They
+    // are not recorded in the log.
+    partial_transactions: Vec<ShieldedPartialTransaction>,
+    // The number of threads generating the proofs, see
+    // `ShieldedPartialTransaction::from_bytecode_with_threads`
+    proving_threads: usize,
+    // The params and the keys of the proofs, the global ones by default
+    proving_context: ProvingContext,
+}
+
+impl ActionLog {
+    pub fn actions(&self) -> &[BuilderAction] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Rebuilds the transaction with an RNG seeded from `seed` and the params
+    /// of `context`. Replaying the same log with the same seed and params
+    /// reproduces the same transaction.
+    pub fn replay(
+        &self,
+        seed: u64,
+        context: &ProvingContext,
+    ) -> Result<Transaction, TransactionError> {
+        TransactionBuilder::from(self.clone())
+            .proving_context(context.clone())
+            .build(ChaCha20Rng::seed_from_u64(seed))
+    }
+}
+
+impl From<ActionLog> for TransactionBuilder {
+    fn from(log: ActionLog) -> Self {
+        Self {
+            log,
+            ..Default::default()
+        }
+    }
+}
+
+impl TransactionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spend(
+        &mut self,
+        resource: Resource,
+        merkle_path: MerklePath,
+        custom_anchor: Option<Anchor>,
+    ) -> &mut Self {
+        self.log.0.push(BuilderAction::Spend {
+            resource,
+            merkle_path,
+            custom_anchor,
+        });
+        self
+    }
+
+    pub fn create(&mut self, resource: Resource) -> &mut Self {
+        self.log.0.push(BuilderAction::Create { resource });
+        self
+    }
+
+    /// Reads a resource, e.g. an oracle or a shared config resource. Its
+    /// existence is proven against the anchor by a read proof, see `ReadInfo`:
+    /// no nullifier is published and no resource is created, so the next
+    /// readers read the same resource. The logics constrain its fields from
+    /// its witness, see `PtxResourceWitnesses::read`.
+    ///
+    /// Reading takes the nullifier key of the resource. Only the membership is
+    /// proven: a resource spent after the anchor can still be read, the logics
+    /// reading a resource that changes bound the anchor, e.g. with the epoch.
+    pub fn read(
+        &mut self,
+        resource: Resource,
+        merkle_path: MerklePath,
+        custom_anchor: Option<Anchor>,
+    ) -> &mut Self {
+        self.log.0.push(BuilderAction::Read {
+            resource,
+            merkle_path,
+            custom_anchor,
+        });
+        self
+    }
+
+    pub fn attach_input_logic(&mut self, index: usize, app: ApplicationByteCode) -> &mut Self {
+        self.log
+            .0
+            .push(BuilderAction::AttachInputLogic { index, app });
+        self
+    }
+
+    pub fn attach_output_logic(&mut self, index: usize, app: ApplicationByteCode) -> &mut Self {
+        self.log
+            .0
+            .push(BuilderAction::AttachOutputLogic { index, app });
+        self
+    }
+
+    pub fn add_hints(&mut self, hints: Vec<u8>) -> &mut Self {
+        self.log.0.push(BuilderAction::AddHints(hints));
+        self
+    }
+
+    /// Attaches a memo of up to `MEMO_SIZE` bytes to the created resource at
+    /// `index`, for the receiver of the address to find with
+    /// `scan_transaction_with_memos`.
+    pub fn attach_memo(&mut self, index: usize, address: Address, memo: Vec<u8>) -> &mut Self {
+        self.log.0.push(BuilderAction::AttachMemo {
+            index,
+            address,
+            memo,
+        });
+        self
+    }
+
+    /// Pays `fee` to the executor. The spent fee resources must exceed the
+    /// created ones by the fee, see `crate::fee`.
+    pub fn pay_fee(&mut self, fee: Fee) -> &mut Self {
+        self.log.0.push(BuilderAction::PayFee(fee));
+        self
+    }
+
+    /// The fee of the transaction, the last one paid.
+    pub fn fee(&self) -> Option<Fee> {
+        self.log.0.iter().rev().find_map(|action| match action {
+            BuilderAction::PayFee(fee) => Some(*fee),
+            _ => None,
+        })
+    }
+
+    /// Proves the compliances at `epoch`, the current epoch of the executor,
+    /// see `ComplianceInfo::with_epoch`.
+    pub fn at_epoch(&mut self, epoch: u64) -> &mut Self {
+        self.log.0.push(BuilderAction::SetEpoch(epoch));
+        self
+    }
+
+    /// Proves the padding and the ephemeral spent resources without a custom
+    /// anchor against `anchor`, a recent root of the ledger. By default, they
+    /// are proven against the anchor of the first spent resource that is not
+    /// ephemeral, if any, and the roots of their random paths otherwise, which
+    /// the ledger rejects.
+    pub fn at_anchor(&mut self, anchor: Anchor) -> &mut Self {
+        self.log.0.push(BuilderAction::SetAnchor(anchor));
+        self
+    }
+
+    /// The epoch of the compliances, the last one set or zero.
+    pub fn epoch(&self) -> u64 {
+        self.log
+            .0
+            .iter()
+            .rev()
+            .find_map(|action| match action {
+                BuilderAction::SetEpoch(epoch) => Some(*epoch),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Converts the spent resource at `index` into the created resource at
+    /// `index` with `rule`, instead of balancing them, see
+    /// `ComplianceInfo::with_conversion`. The transaction is only recorded
+    /// once the issuer of the target kind authorized the rule.
+    pub fn apply_conversion(&mut self, index: usize, rule: ConversionRule) -> &mut Self {
+        self.log
+            .0
+            .push(BuilderAction::ApplyConversion { index, rule });
+        self
+    }
+
+    /// The rule converting the pair at `index`, the last one applied.
+    pub fn conversion(&self, index: usize) -> Option<ConversionRule> {
+        self.log.0.iter().rev().find_map(|action| match action {
+            BuilderAction::ApplyConversion { index: i, rule } if *i == index => Some(*rule),
+            _ => None,
+        })
+    }
+
+    /// Adds the application logic of the spent (`is_input`) or created resource
+    /// at `index`, built from the existence witness of the resource.
+    pub fn add_resource_logic(
+        &mut self,
+        is_input: bool,
+        index: usize,
+        factory: impl Fn(ResourceExistenceWitness) -> ApplicationByteCode + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.add_resource_logic_with_ptx(is_input, index, move |witness, _| factory(witness))
+    }
+
+    /// Like `add_resource_logic`, for the logics that also need the witnesses
+    /// of the other resources of the ptx.
+    pub fn add_resource_logic_with_ptx(
+        &mut self,
+        is_input: bool,
+        index: usize,
+        factory: impl Fn(ResourceExistenceWitness, &PtxResourceWitnesses) -> ApplicationByteCode
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self {
+        self.pending_logics
+            .push((is_input, index, ResourceLogicFactory(Arc::new(factory))));
+        self
+    }
+
+    /// Spends a resource with the factory of its application logic.
+    pub fn spend_with_logic(
+        &mut self,
+        resource: Resource,
+        merkle_path: MerklePath,
+        custom_anchor: Option<Anchor>,
+        logic: impl Fn(ResourceExistenceWitness) -> ApplicationByteCode + Send + Sync + 'static,
+    ) -> &mut Self {
+        let index = self.spent_num();
+        self.spend(resource, merkle_path, custom_anchor)
+            .add_resource_logic(true, index, logic)
+    }
+
+    /// Creates a resource with the factory of its application logic.
+    pub fn create_with_logic(
+        &mut self,
+        resource: Resource,
+        logic: impl Fn(ResourceExistenceWitness) -> ApplicationByteCode + Send + Sync + 'static,
+    ) -> &mut Self {
+        let index = self.created_num();
+        self.create(resource)
+            .add_resource_logic(false, index, logic)
+    }
+
+    /// Creates an intent resource with its intent logic. It is paired with the
+    /// spent resource of the same index, e.g. the resource offered for the
+    /// intent.
+    pub fn attach_intent(
+        &mut self,
+        intent_resource: Resource,
+        logic: impl Fn(ResourceExistenceWitness) -> ApplicationByteCode + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.create_with_logic(intent_resource, logic)
+    }
+
+    /// Pairs the unpaired spent or created resources with padding resources,
+    /// see `Resource::padding_resource`. The padding inputs are proven against
+    /// the anchor of the ptx, see `at_anchor`. Fails if the ptx has more than `MAX_PTX_COMPLIANCE_NUM` resources
+    /// of either side, counting the read resources.
+    #[cfg(feature = "borsh")]
+    pub fn pad<R: RngCore>(&mut self, mut rng: R) -> Result<&mut Self, TransactionError> {
+        let spent_num = self.spent_resources().count();
+        let created_num = self.created_resources().count();
+        let num = spent_num.max(created_num);
+        if num + self.read_num() > MAX_PTX_COMPLIANCE_NUM {
+            return Err(TransactionError::TooManyResources);
+        }
+        let padding_logic = |witness: ResourceExistenceWitness| {
+            let logic =
+                TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path());
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        };
+        let anchor = self.anchor();
+        for index in spent_num..num {
+            self.spend(
+                Resource::padding_resource(&mut rng),
+                MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+                anchor,
+            )
+            .add_resource_logic(true, index, padding_logic);
+        }
+        for index in created_num..num {
+            self.create(Resource::padding_resource(&mut rng))
+                .add_resource_logic(false, index, padding_logic);
+        }
+        Ok(self)
+    }
+
+    /// Adds the ptx of another party to the transaction.
+    pub fn add_partial_transaction(
+        &mut self,
+        ptx: impl Into<ShieldedPartialTransaction>,
+    ) -> &mut Self {
+        self.partial_transactions.push(ptx.into());
+        self
+    }
+
+    /// Generates the proofs of the ptx on up to `threads` threads. The proofs
+    /// are generated serially by default.
+    pub fn proving_threads(&mut self, threads: usize) -> &mut Self {
+        self.proving_threads = threads;
+        self
+    }
+
+    /// Generates the proofs with the params and the keys of `context`, e.g.
+    /// params of a ceremony of the application rather than the global ones.
+    pub fn proving_context(&mut self, context: ProvingContext) -> &mut Self {
+        self.proving_context = context;
+        self
+    }
+
+    pub fn action_log(&self) -> &ActionLog {
+        &self.log
+    }
+
+    /// The number of spent resources, i.e. the index of the next one.
+    pub fn spent_num(&self) -> usize {
+        self.spent_resources().count()
+    }
+
+    /// The number of created resources, i.e. the index of the next one.
+    pub fn created_num(&self) -> usize {
+        self.created_resources().count()
+    }
+
+    /// The number of read resources, i.e. the index of the next one.
+    pub fn read_num(&self) -> usize {
+        self.read_resources().count()
+    }
+
+    // The anchor last set, or else the one of the first spent resource that
+    // is not ephemeral
+    fn anchor(&self) -> Option<Anchor> {
+        self.log
+            .0
+            .iter()
+            .rev()
+            .find_map(|action| match action {
+                BuilderAction::SetAnchor(anchor) => Some(*anchor),
+                _ => None,
+            })
+            .or_else(|| {
+                self.spent_resources()
+                    .find(|(resource, _, _)| !resource.is_ephemeral)
+                    .map(|(resource, merkle_path, custom_anchor)| {
+                        custom_anchor.unwrap_or_else(|| resource.calculate_root(merkle_path))
+                    })
+            })
+    }
+
+    fn spent_resources(&self) -> impl Iterator<Item = (&Resource, &MerklePath, &Option<Anchor>)> {
+        self.log.0.iter().filter_map(|action| match action {
+            BuilderAction::Spend {
+                resource,
+                merkle_path,
+                custom_anchor,
+            } => Some((resource, merkle_path, custom_anchor)),
+            _ => None,
+        })
+    }
+
+    fn created_resources(&self) -> impl Iterator<Item = &Resource> {
+        self.log.0.iter().filter_map(|action| match action {
+            BuilderAction::Create { resource } => Some(resource),
+            _ => None,
+        })
+    }
+
+    fn read_resources(&self) -> impl Iterator<Item = (&Resource, &MerklePath, &Option<Anchor>)> {
+        self.log.0.iter().filter_map(|action| match action {
+            BuilderAction::Read {
+                resource,
+                merkle_path,
+                custom_anchor,
+            } => Some((resource, merkle_path, custom_anchor)),
+            _ => None,
+        })
+    }
+
+    /// Returns the created resources with the nonces they get in the
+    /// transaction. The output logics must be built from these resources.
+    pub fn output_resources(&self) -> Vec<Resource> {
+        self.spent_resources()
+            .zip(self.created_resources())
+            .map(|((input, _, _), output)| {
+                let mut output = output.clone();
+                output.set_nonce(input);
+                output
+            })
+            .collect()
+    }
+
+    /// The resource tree of the ptx: the input nfs and the output cms of the
+    /// compliances, in order, followed by the read tags of the read resources
+    /// paired with zero, see `Executable::get_resource_merkle_root`.
+    pub fn resource_tree(&self, witnesses: &mut WitnessSet) -> ResourceMerkleTreeLeaves {
+        let leaves = self
+            .spent_resources()
+            .map(|(input, _, _)| input)
+            .zip(self.output_resources())
+            .flat_map(|(input, output)| {
+                [
+                    witnesses.get_or_derive(input).get_identity(true),
+                    witnesses.get_or_derive(&output).get_identity(false),
+                ]
+            })
+            .chain(self.read_resources().flat_map(|(resource, _, _)| {
+                [
+                    resource.get_read_tag().unwrap_or_default(),
+                    pallas::Base::zero(),
+                ]
+            }))
+            .collect();
+        ResourceMerkleTreeLeaves::new(leaves)
+    }
+
+    /// Whether the quantities of the spent and created resources balance for
+    /// every kind, once the fee is paid.
+    pub fn is_balanced(&self) -> bool {
+        self.balance_summary().is_balanced()
+    }
+
+    /// The net quantity of each kind of the spent and created resources, the
+    /// converted pairs and the fee deducted.
+    pub fn balance_summary(&self) -> BalanceSummary {
+        let mut summary = BalanceSummary::default();
+        self.spent_resources()
+            .enumerate()
+            .filter(|(index, _)| self.conversion(*index).is_none())
+            .for_each(|(_, (resource, _, _))| summary.consume(resource));
+        self.created_resources()
+            .enumerate()
+            .filter(|(index, _)| self.conversion(*index).is_none())
+            .for_each(|(_, resource)| summary.create(resource));
+        if let Some(fee) = self.fee() {
+            summary.add(fee.kind(), -i128::from(fee.quantity().inner()));
+        }
+        summary
+    }
+
+    /// Estimates the cost of proving the ptx on the host, with the padding of
+    /// `finalize`, see `crate::proving_cost`. The host is calibrated on the
+    /// first call. The ptxs of the other parties are already proven, and the
+    /// logics not built yet are assumed to use the default params size.
+    #[cfg(feature = "std")]
+    pub fn estimate(&self) -> ProvingEstimate {
+        self.estimate_with(crate::proving_cost::host_calibration())
+    }
+
+    pub fn estimate_with(&self, calibration: &HostCalibration) -> ProvingEstimate {
+        let compliance_num = self.spent_num().max(self.created_num());
+        let mut input_apps = vec![vec![RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE]; compliance_num];
+        let mut output_apps = input_apps.clone();
+        for action in self.log.0.iter() {
+            let (apps, index, app) = match action {
+                BuilderAction::AttachInputLogic { index, app } => (&mut input_apps, index, app),
+                BuilderAction::AttachOutputLogic { index, app } => (&mut output_apps, index, app),
+                _ => continue,
+            };
+            if let Some(sizes) = apps.get_mut(*index) {
+                *sizes = app.params_size_hints();
+            }
+        }
+        input_apps.append(&mut output_apps);
+        // The read proofs are counted as compliance proofs, which bounds them
+        calibration.estimate(
+            compliance_num + self.read_num(),
+            &input_apps,
+            self.proving_threads,
+        )
+    }
+
+    /// Builds the pending logics from the resource tree and records them in
+    /// the log, so that the log replays without the factories.
+    fn resolve_logics(&mut self) -> Result<(), TransactionError> {
+        if self.pending_logics.is_empty() {
+            return Ok(());
+        }
+        if self.spent_resources().count() != self.created_resources().count() {
+            return Err(TransactionError::UnpairedResources);
+        }
+        let spent_resources: Vec<Resource> = self
+            .spent_resources()
+            .map(|(resource, _, _)| resource.clone())
+            .collect();
+        let output_resources = self.output_resources();
+        let mut witnesses = WitnessSet::new();
+        let resource_tree = self.resource_tree(&mut witnesses);
+        let mut existence_witnesses = |resources: &[Resource], is_input: bool| {
+            resources
+                .iter()
+                .map(|resource| witnesses.existence_witness(&resource_tree, resource, is_input))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(TransactionError::MissingApplicationLogic)
+        };
+        let read = self
+            .reads()?
+            .iter()
+            .map(|read| {
+                let resource = read.get_resource();
+                resource
+                    .get_read_tag()
+                    .and_then(|tag| resource_tree.generate_path(tag))
+                    .map(|merkle_path| ResourceExistenceWitness::new(resource.clone(), merkle_path))
+                    .ok_or(TransactionError::MissingApplicationLogic)
+            })
+            .collect::<Result<_, _>>()?;
+        let ptx_witnesses = PtxResourceWitnesses {
+            spent: existence_witnesses(&spent_resources, true)?,
+            created: existence_witnesses(&output_resources, false)?,
+            read,
+        };
+        for (is_input, index, factory) in std::mem::take(&mut self.pending_logics) {
+            let witness = if is_input {
+                ptx_witnesses.spent(index)
+            } else {
+                ptx_witnesses.created(index)
+            }
+            .ok_or(TransactionError::MissingApplicationLogic)?;
+            let app = (factory.0)(witness, &ptx_witnesses);
+            if is_input {
+                self.attach_input_logic(index, app);
+            } else {
+                self.attach_output_logic(index, app);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pairs the spent and created resources into compliances. The ephemeral
+    /// spent resources without a custom anchor are proven against the anchor
+    /// of the ptx, see `at_anchor`. Fails if a pair doesn't follow the rule
+    /// converting it.
+    pub fn compliances<R: RngCore>(
+        &self,
+        mut rng: R,
+    ) -> Result<Vec<ComplianceInfo>, TransactionError> {
+        let epoch = self.epoch();
+        let anchor = self.anchor();
+        self.spent_resources()
+            .zip(self.created_resources())
+            .enumerate()
+            .map(|(index, ((input, merkle_path, custom_anchor), output))| {
+                let mut output = output.clone();
+                let custom_anchor = if input.is_ephemeral {
+                    custom_anchor.or(anchor)
+                } else {
+                    *custom_anchor
+                };
+                let compliance = ComplianceInfo::new(
+                    input.clone(),
+                    merkle_path.clone(),
+                    custom_anchor,
+                    &mut output,
+                    &mut rng,
+                )
+                .with_epoch(epoch);
+                match self.conversion(index) {
+                    Some(rule) => compliance.with_conversion(rule),
+                    None => Ok(compliance),
+                }
+            })
+            .collect()
+    }
+
+    /// The reads of the read resources, in order. Fails if a read resource
+    /// doesn't contain its nullifier key.
+    pub fn reads(&self) -> Result<Vec<ReadInfo>, TransactionError> {
+        self.read_resources()
+            .map(|(resource, merkle_path, custom_anchor)| {
+                resource
+                    .get_nk()
+                    .ok_or(TransactionError::MissingNullifierKey)?;
+                Ok(ReadInfo::new(
+                    resource.clone(),
+                    merkle_path.clone(),
+                    *custom_anchor,
+                ))
+            })
+            .collect()
+    }
+
+    fn apps(
+        &self,
+        is_input: bool,
+        num: usize,
+    ) -> Result<Vec<ApplicationByteCode>, TransactionError> {
+        let mut apps = vec![None; num];
+        for action in self.log.0.iter() {
+            let (index, app) = match (action, is_input) {
+                (BuilderAction::AttachInputLogic { index, app }, true)
+                | (BuilderAction::AttachOutputLogic { index, app }, false) => (*index, app),
+                _ => continue,
+            };
+            *apps
+                .get_mut(index)
+                .ok_or(TransactionError::MissingApplicationLogic)? = Some(app.clone());
+        }
+        apps.into_iter()
+            .map(|app| app.ok_or(TransactionError::MissingApplicationLogic))
+            .collect()
+    }
+
+    /// Encrypts the memos of the created resources. Fails if a memo is too
+    /// long, or attached to a resource that isn't created.
+    pub fn memos<R: RngCore>(&self, mut rng: R) -> Result<Vec<Memo>, TransactionError> {
+        let output_resources = self.output_resources();
+        self.log
+            .0
+            .iter()
+            .filter_map(|action| match action {
+                BuilderAction::AttachMemo {
+                    index,
+                    address,
+                    memo,
+                } => Some((index, address, memo)),
+                _ => None,
+            })
+            .map(|(index, address, memo)| {
+                let output = output_resources
+                    .get(*index)
+                    .ok_or(TransactionError::InvalidMemo)?;
+                Memo::seal(
+                    memo,
+                    output.commitment(),
+                    address,
+                    &pallas::Base::random(&mut rng),
+                    &pallas::Base::random(&mut rng),
+                )
+            })
+            .collect()
+    }
+
+    pub fn build_partial_transaction<R: RngCore>(
+        &self,
+        mut rng: R,
+    ) -> Result<ShieldedPartialTransaction, TransactionError> {
+        let spent_num = self.spent_resources().count();
+        if spent_num != self.created_resources().count() {
+            return Err(TransactionError::UnpairedResources);
+        }
+        let compliances = self.compliances(&mut rng)?;
+        let input_resource_app = self.apps(true, compliances.len())?;
+        let output_resource_app = self.apps(false, compliances.len())?;
+        let mut ptx = DraftedPartialTransaction::new();
+        for ((compliance, input_app), output_app) in compliances
+            .into_iter()
+            .zip(input_resource_app)
+            .zip(output_resource_app)
+        {
+            ptx.add_compliance(compliance, input_app, output_app);
+        }
+        for read in self.reads()? {
+            ptx.add_read(read);
+        }
+        for action in self.log.0.iter() {
+            if let BuilderAction::AddHints(hints) = action {
+                ptx.add_hints(hints.clone());
+            }
+        }
+        ptx.prove_with_context(&self.proving_context, self.proving_threads, rng)
+            .map(ShieldedPartialTransaction::from)
+    }
+
+    /// Pads the resources, builds the pending logics and proves the ptx without
+    /// checking the balance, e.g. the ptx creating an intent that a solver
+    /// consumes in a later ptx.
+    pub fn finalize_partial_transaction<R: RngCore>(
+        &mut self,
+        mut rng: R,
+    ) -> Result<ShieldedPartialTransaction, TransactionError> {
+        #[cfg(feature = "borsh")]
+        self.pad(&mut rng)?;
+        self.resolve_logics()?;
+        self.build_partial_transaction(rng)
+    }
+
+    /// Pads the resources, builds the pending logics, checks that the resources
+    /// balance and builds the transaction with its binding signature. The
+    /// balance is left to the binding signature when the transaction includes
+    /// the ptxs of other parties.
+    pub fn finalize<R: RngCore + CryptoRng>(
+        &mut self,
+        mut rng: R,
+    ) -> Result<Transaction, TransactionError> {
+        #[cfg(feature = "borsh")]
+        self.pad(&mut rng)?;
+        self.resolve_logics()?;
+        if self.partial_transactions.is_empty() && !self.is_balanced() {
+            return Err(TransactionError::UnbalancedResources);
+        }
+        let memos = self.memos(&mut rng)?;
+        let mut ptxs = vec![self.build_partial_transaction(&mut rng)?];
+        ptxs.extend(self.partial_transactions.iter().cloned());
+        let unsigned = UnsignedTransaction::new(
+            ShieldedPartialTxBundle::new(ptxs),
+            TransparentPartialTxBundle::default(),
+        )
+        .with_memos(memos)
+        .with_fee(self.fee());
+        let signature = unsigned.sign(rng)?;
+        unsigned.finalize(signature)
+    }
+
+    pub fn build<R: RngCore + CryptoRng>(
+        &self,
+        mut rng: R,
+    ) -> Result<Transaction, TransactionError> {
+        let memos = self.memos(&mut rng)?;
+        let ptx = self.build_partial_transaction(&mut rng)?;
+        let unsigned = UnsignedTransaction::new(
+            ShieldedPartialTxBundle::new(vec![ptx]),
+            TransparentPartialTxBundle::default(),
+        )
+        .with_memos(memos)
+        .with_fee(self.fee());
+        let signature = unsigned.sign(rng)?;
+        unsigned.finalize(signature)
+    }
+
+    /// Same as `build`, with a ChaCha RNG seeded from `seed`. The same builder
+    /// and seed give the same transaction on any number of proving threads,
+    /// e.g. for reproducible tests and test vectors.
+    pub fn build_with_seed(&self, seed: [u8; 32]) -> Result<Transaction, TransactionError> {
+        self.build(ChaCha20Rng::from_seed(seed))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "borsh")]
+pub mod tests {
+    use super::{ActionLog, TransactionBuilder};
+    use crate::{
+        circuit::{
+            resource_logic_bytecode::ApplicationByteCode,
+            resource_logic_examples::TrivialResourceLogicCircuit,
+        },
+        constant::{
+            MAX_PTX_COMPLIANCE_NUM, RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE, TAIGA_COMMITMENT_TREE_DEPTH,
+        },
+        error::TransactionError,
+        ledger::LedgerState,
+        merkle_tree::{Anchor, MerklePath},
+        params::{get_params, ProvingContext},
+        proving_cost::{HostCalibration, ProofBenchmark},
+        resource::tests::random_resource,
+        resource_tree::ResourceExistenceWitness,
+        resource_tree::ResourceMerkleTreeLeaves,
+        transaction::TransactionResult,
+        witness_set::WitnessSet,
+    };
+    use borsh::BorshDeserialize;
+    use ff::Field;
+    use pasta_curves::pallas;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_action_log_replay() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let input_resource = random_resource(&mut rng);
+        let mut output_resource = random_resource(&mut rng);
+        output_resource.kind = input_resource.kind;
+        output_resource.quantity = input_resource.quantity;
+        let merkle_path = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
+
+        let mut builder = TransactionBuilder::new();
+        builder
+            .spend(input_resource.clone(), merkle_path, None)
+            .create(output_resource);
+        let output_resource = builder.output_resources()[0].clone();
+
+        let input_nf = input_resource.get_nf().unwrap().inner();
+        let output_cm = output_resource.commitment().inner();
+        let resource_merkle_tree = ResourceMerkleTreeLeaves::new(vec![input_nf, output_cm]);
+        let input_logic = TrivialResourceLogicCircuit::new(
+            input_resource,
+            resource_merkle_tree.generate_path(input_nf).unwrap(),
+        );
+        let output_logic = TrivialResourceLogicCircuit::new(
+            output_resource,
+            resource_merkle_tree.generate_path(output_cm).unwrap(),
+        );
+        builder
+            .attach_input_logic(
+                0,
+                ApplicationByteCode::new(input_logic.to_bytecode(), vec![]),
+            )
+            .attach_output_logic(
+                0,
+                ApplicationByteCode::new(output_logic.to_bytecode(), vec![]),
+            );
+
+        // The log survives a round trip and replays to the same compliances
+        let bytes = borsh::to_vec(builder.action_log()).unwrap();
+        let log = ActionLog::deserialize(&mut bytes.as_ref()).unwrap();
+        let replayed = TransactionBuilder::from(log.clone());
+        let compliances = builder.compliances(StdRng::seed_from_u64(1)).unwrap();
+        let replayed_compliances = replayed.compliances(StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(
+            compliances[0].build().0.to_instance(),
+            replayed_compliances[0].build().0.to_instance()
+        );
+
+        let tx = log.replay(1, &ProvingContext::default()).unwrap();
+        tx.execute().unwrap();
+
+        // Replayed with injected params, verified with the same ones
+        let k = RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+        let context = ProvingContext::default().with_params(k, get_params(k).unwrap().clone());
+        let tx = log.replay(1, &context).unwrap();
+        tx.execute_with_context(&context.verifying_context())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_builder_finalize() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let input_resource = random_resource(&mut rng);
+        let mut output_resource = random_resource(&mut rng);
+        output_resource.kind = input_resource.kind;
+        output_resource.quantity = input_resource.quantity;
+        let merkle_path = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
+        let trivial_logic = |witness: ResourceExistenceWitness| {
+            let logic =
+                TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path());
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        };
+
+        let mut builder = TransactionBuilder::new();
+        builder
+            .spend(input_resource.clone(), merkle_path.clone(), None)
+            .create(output_resource.clone())
+            .add_resource_logic(true, 0, trivial_logic)
+            .add_resource_logic(false, 0, trivial_logic);
+        assert!(builder.is_balanced());
+        let tx = builder.finalize(&mut rng).unwrap();
+        tx.execute().unwrap();
+
+        // The proofs generated on several threads verify as well
+        let tx = builder.proving_threads(4).build(&mut rng).unwrap();
+        tx.execute().unwrap();
+
+        // The built logics are recorded, the log replays without the factories
+        builder
+            .action_log()
+            .replay(1, &ProvingContext::default())
+            .unwrap()
+            .execute()
+            .unwrap();
+
+        // A seeded build is reproducible, whatever the number of threads
+        let tx = builder.build_with_seed([7; 32]).unwrap();
+        let same_tx = builder.proving_threads(1).build_with_seed([7; 32]).unwrap();
+        assert_eq!(
+            borsh::to_vec(&tx).unwrap(),
+            borsh::to_vec(&same_tx).unwrap()
+        );
+
+        // Unbalanced resources fail before proving
+        let mut unbalanced_output_resource = output_resource;
+        unbalanced_output_resource.quantity =
+            input_resource.quantity.inner().wrapping_add(1).into();
+        let mut builder = TransactionBuilder::new();
+        builder
+            .spend(input_resource, merkle_path, None)
+            .create(unbalanced_output_resource)
+            .add_resource_logic(true, 0, trivial_logic)
+            .add_resource_logic(false, 0, trivial_logic);
+        assert!(!builder.is_balanced());
+        assert!(matches!(
+            builder.finalize(&mut rng),
+            Err(TransactionError::UnbalancedResources)
+        ));
+    }
+
+    #[test]
+    fn test_builder_memo() {
+        use crate::{keys::ExtendedSpendingKey, resource_encryption::Diversifier, utils::mod_r_p};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let input_resource = random_resource(&mut rng);
+        let mut output_resource = random_resource(&mut rng);
+        output_resource.kind = input_resource.kind;
+        output_resource.quantity = input_resource.quantity;
+        let viewing_key = ExtendedSpendingKey::account(&[1u8; 32], 0)
+            .unwrap()
+            .viewing_key();
+        let address = viewing_key.address(Diversifier::random(&mut rng));
+        let trivial_logic = |witness: ResourceExistenceWitness| {
+            let logic =
+                TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path());
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        };
+
+        let mut builder = TransactionBuilder::new();
+        builder
+            .spend(
+                input_resource,
+                MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+                None,
+            )
+            .create(output_resource)
+            .add_resource_logic(true, 0, trivial_logic)
+            .add_resource_logic(false, 0, trivial_logic)
+            .attach_memo(0, address, b"thanks for the coffee".to_vec());
+        let tx = builder.finalize(&mut rng).unwrap();
+        let result = tx.execute().unwrap();
+
+        // The memo is attached to the created resource and survives the encoding
+        let tx = crate::transaction::Transaction::from_bytes(&tx.to_bytes()).unwrap();
+        let memo = &tx.get_memos()[0];
+        assert_eq!(memo.output_cm(), result.output_cms[0]);
+        assert_eq!(
+            &memo.open(&mod_r_p(viewing_key.encryption_sk())).unwrap()[..21],
+            b"thanks for the coffee"
+        );
+
+        // Memos of resources the ptx doesn't create are rejected
+        builder.attach_memo(1, address, vec![]);
+        assert!(matches!(
+            builder.build(&mut rng),
+            Err(TransactionError::InvalidMemo)
+        ));
+    }
+
+    #[test]
+    fn test_builder_fee() {
+        use crate::fee::Fee;
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut input_resource = random_resource(&mut rng);
+        let fee = Fee::new(input_resource.kind.logic, 2u64);
+        input_resource.kind = fee.kind();
+        input_resource.quantity = 5u64.into();
+        let mut change = random_resource(&mut rng);
+        change.kind = fee.kind();
+        change.quantity = 3u64.into();
+        let trivial_logic = |witness: ResourceExistenceWitness| {
+            let logic =
+                TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path());
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        };
+
+        let mut builder = TransactionBuilder::new();
+        builder
+            .spend(
+                input_resource.clone(),
+                MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+                None,
+            )
+            .create(change)
+            .add_resource_logic(true, 0, trivial_logic)
+            .add_resource_logic(false, 0, trivial_logic);
+        assert!(!builder.is_balanced());
+
+        // The surplus pays the fee, which survives the encoding
+        builder.pay_fee(fee);
+        assert!(builder.is_balanced());
+        let tx = builder.finalize(&mut rng).unwrap();
+        tx.execute().unwrap();
+        let tx = crate::transaction::Transaction::from_bytes(&tx.to_bytes()).unwrap();
+        assert_eq!(tx.get_fee(), Some(fee));
+        tx.execute().unwrap();
+
+        // A fee that the surplus doesn't match is rejected
+        builder.pay_fee(Fee::new(input_resource.kind.logic, 1u64));
+        assert!(matches!(
+            builder.finalize(&mut rng),
+            Err(TransactionError::UnbalancedResources)
+        ));
+        let unsigned = crate::transaction::UnsignedTransaction::new(
+            crate::transaction::ShieldedPartialTxBundle::new(vec![builder
+                .build_partial_transaction(&mut rng)
+                .unwrap()]),
+            Default::default(),
+        )
+        .with_fee(Some(Fee::new(input_resource.kind.logic, 1u64)));
+        let signature = unsigned.sign(&mut rng).unwrap();
+        assert!(unsigned.finalize(signature).is_err());
+    }
+
+    #[test]
+    fn test_builder_padding() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut input_resources = [random_resource(&mut rng), random_resource(&mut rng)];
+        input_resources[1].kind = input_resources[0].kind;
+        input_resources[0].quantity = 3u64.into();
+        input_resources[1].quantity = 5u64.into();
+        let mut output_resource = random_resource(&mut rng);
+        output_resource.kind = input_resources[0].kind;
+        output_resource.quantity = 8u64.into();
+        let trivial_logic = |witness: ResourceExistenceWitness| {
+            let logic =
+                TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path());
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        };
+
+        // Two inputs and one output: the output is paired with a padding input
+        let mut builder = TransactionBuilder::new();
+        for (index, input_resource) in input_resources.iter().enumerate() {
+            builder
+                .spend(
+                    input_resource.clone(),
+                    MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+                    None,
+                )
+                .add_resource_logic(true, index, trivial_logic);
+        }
+        builder
+            .create(output_resource.clone())
+            .add_resource_logic(false, 0, trivial_logic);
+        let tx = builder.finalize(&mut rng).unwrap();
+        let result = tx.execute().unwrap();
+        assert_eq!(result.nullifiers.len(), 2);
+        assert_eq!(result.output_cms.len(), 2);
+
+        // One input and two outputs: the padding input has the anchor of the input
+        let mut builder = TransactionBuilder::new();
+        let mut output_resources = [output_resource, random_resource(&mut rng)];
+        output_resources[0].quantity = 3u64.into();
+        output_resources[1].kind = input_resources[0].kind;
+        output_resources[1].quantity = 0u64.into();
+        builder
+            .spend(
+                input_resources[0].clone(),
+                MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+                None,
+            )
+            .add_resource_logic(true, 0, trivial_logic);
+        for (index, output_resource) in output_resources.iter().enumerate() {
+            builder
+                .create(output_resource.clone())
+                .add_resource_logic(false, index, trivial_logic);
+        }
+        let tx = builder.finalize(&mut rng).unwrap();
+        let result = tx.execute().unwrap();
+        assert_eq!(result.anchors.len(), 2);
+        assert_eq!(result.anchors[0], result.anchors[1]);
+
+        // Without a spent resource that is not ephemeral, the ephemeral and the
+        // padding inputs are proven against the anchor set
+        let anchor = Anchor::from(pallas::Base::random(&mut rng));
+        let mut builder = TransactionBuilder::new();
+        let mut ephemeral_resource = random_resource(&mut rng);
+        ephemeral_resource.is_ephemeral = true;
+        ephemeral_resource.quantity = 0u64.into();
+        builder
+            .at_anchor(anchor)
+            .spend(
+                ephemeral_resource,
+                MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+                None,
+            )
+            .add_resource_logic(true, 0, trivial_logic);
+        for (index, output_resource) in output_resources.iter().enumerate() {
+            let mut output_resource = output_resource.clone();
+            output_resource.quantity = 0u64.into();
+            builder
+                .create(output_resource)
+                .add_resource_logic(false, index, trivial_logic);
+        }
+        let tx = builder.finalize(&mut rng).unwrap();
+        let result = tx.execute().unwrap();
+        assert_eq!(result.anchors, vec![anchor, anchor]);
+
+        // The resource tree can't hold more than MAX_PTX_COMPLIANCE_NUM compliances
+        let mut builder = TransactionBuilder::new();
+        for _ in 0..=MAX_PTX_COMPLIANCE_NUM {
+            builder.create(random_resource(&mut rng));
+        }
+        assert!(matches!(
+            builder.pad(&mut rng),
+            Err(TransactionError::TooManyResources)
+        ));
+    }
+
+    #[test]
+    fn test_builder_read() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let input_resource = random_resource(&mut rng);
+        let mut output_resource = random_resource(&mut rng);
+        output_resource.kind = input_resource.kind;
+        output_resource.quantity = input_resource.quantity;
+        let config_resource = random_resource(&mut rng);
+        let trivial_logic = |witness: ResourceExistenceWitness| {
+            let logic =
+                TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path());
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        };
+
+        // Both resources were minted before
+        let ledger = LedgerState::new();
+        ledger
+            .apply(&TransactionResult {
+                anchors: vec![],
+                nullifiers: vec![],
+                output_cms: vec![input_resource.commitment(), config_resource.commitment()],
+                conversion_rules: vec![],
+                epochs: vec![],
+                blacklist_roots: vec![],
+            })
+            .unwrap();
+        let view = ledger.read_view();
+
+        let mut builder = TransactionBuilder::new();
+        builder
+            .spend(
+                input_resource.clone(),
+                view.merkle_path(&input_resource.commitment()).unwrap(),
+                None,
+            )
+            .create(output_resource)
+            .read(
+                config_resource.clone(),
+                view.merkle_path(&config_resource.commitment()).unwrap(),
+                None,
+            )
+            .add_resource_logic(true, 0, trivial_logic)
+            .add_resource_logic(false, 0, trivial_logic);
+        assert_eq!(builder.read_num(), 1);
+        assert_eq!(builder.compliances(&mut rng).unwrap().len(), 1);
+
+        // The read resource is in the resource tree under its read tag, neither
+        // as an input nor as an output
+        let mut witnesses = WitnessSet::new();
+        let resource_tree = builder.resource_tree(&mut witnesses);
+        assert!(resource_tree
+            .generate_path(config_resource.get_read_tag().unwrap())
+            .is_some());
+        assert!(witnesses
+            .existence_witness(&resource_tree, &config_resource, true)
+            .is_none());
+        assert!(witnesses
+            .existence_witness(&resource_tree, &config_resource, false)
+            .is_none());
+
+        // Its existence is proven against the anchor, it's neither consumed
+        // nor created again
+        let config_anchor = config_resource
+            .calculate_root(&view.merkle_path(&config_resource.commitment()).unwrap());
+        let tx = builder.finalize(&mut rng).unwrap();
+        let result = tx.execute().unwrap();
+        assert_eq!(result.nullifiers.len(), 1);
+        assert!(!result
+            .nullifiers
+            .contains(&config_resource.get_nf().unwrap()));
+        assert_eq!(result.output_cms.len(), 1);
+        assert!(!result.output_cms.contains(&config_resource.commitment()));
+        assert!(result.anchors.contains(&config_anchor));
+
+        // The read resource stays unspent for the next readers
+        ledger.apply(&result).unwrap();
+        assert!(!ledger.contains_nullifier(&config_resource.get_nf().unwrap()));
+        assert_eq!(ledger.commitments().len(), 3);
+
+        // A read against an unknown anchor is rejected
+        let mut unknown = result.clone();
+        unknown.nullifiers = vec![];
+        unknown.output_cms = vec![];
+        unknown.anchors = vec![pallas::Base::one().into()];
+        assert!(matches!(
+            ledger.apply(&unknown),
+            Err(TransactionError::UnknownAnchor)
+        ));
+    }
+
+    #[test]
+    fn test_builder_estimate() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let calibration = HostCalibration {
+            compliance: ProofBenchmark {
+                k: 15,
+                seconds: 3.0,
+                memory: 1 << 30,
+            },
+            resource_logic: ProofBenchmark {
+                k: 15,
+                seconds: 2.0,
+                memory: 1 << 29,
+            },
+        };
+        let trivial_logic = |witness: ResourceExistenceWitness| {
+            let logic =
+                TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path());
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        };
+
+        // Two spent resources and one created: the output is padded
+        let mut builder = TransactionBuilder::new();
+        for index in 0..2 {
+            builder
+                .spend(
+                    random_resource(&mut rng),
+                    MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+                    None,
+                )
+                .add_resource_logic(true, index, trivial_logic);
+        }
+        builder.create(random_resource(&mut rng));
+        let estimate = builder.estimate_with(&calibration);
+        assert_eq!(estimate.proofs, 6);
+        assert_eq!(estimate.wall_time.as_secs(), 2 * 3 + 4 * 2);
+
+        let estimate = builder.proving_threads(2).estimate_with(&calibration);
+        assert_eq!(estimate.wall_time.as_secs(), 7);
+        assert_eq!(estimate.peak_memory, 2 << 30);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::auxiliary_delta::AuxiliaryDelta;
+use crate::circuit::gadgets::assign_free_constant;
+use crate::constant::{TaigaFixedBases, TaigaFixedBasesFull, AUXILIARY_DELTA_TAG};
+use halo2_gadgets::ecc::{
+    chip::EccChip, FixedPoint, NonIdentityPoint, Point, ScalarFixed, ScalarVar,
+};
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, Error, Instance},
+};
+use pasta_curves::group::Curve;
+use pasta_curves::pallas;
+use std::ops::Neg;
+
+/// Computes the auxiliary delta `consumed_amount * G_domain - created_amount *
+/// G_domain + blind * R`, see [`AuxiliaryDelta::commit`]. The amounts are not
+/// range checked here, the logic must constrain them to 64 bits.
+pub fn compute_auxiliary_delta(
+    mut layouter: impl Layouter<pallas::Base>,
+    ecc_chip: EccChip<TaigaFixedBases>,
+    domain: pallas::Base,
+    consumed_amount: AssignedCell<pallas::Base, pallas::Base>,
+    created_amount: AssignedCell<pallas::Base, pallas::Base>,
+    blind: pallas::Scalar,
+) -> Result<Point<pallas::Affine, EccChip<TaigaFixedBases>>, Error> {
+    // The domain is fixed by the logic, witness its generator and constrain it
+    // to the constant
+    let generator_value = AuxiliaryDelta::generator(domain).to_affine();
+    let generator = NonIdentityPoint::new(
+        ecc_chip.clone(),
+        layouter.namespace(|| "auxiliary delta generator"),
+        Value::known(generator_value),
+    )?;
+    let coordinates = generator_value.coordinates().unwrap();
+    layouter.assign_region(
+        || "constrain auxiliary delta generator",
+        |mut region| {
+            region.constrain_constant(generator.inner().x().cell(), *coordinates.x())?;
+            region.constrain_constant(generator.inner().y().cell(), *coordinates.y())
+        },
+    )?;
+
+    let consumed_scalar = ScalarVar::from_base(
+        ecc_chip.clone(),
+        layouter.namespace(|| "ScalarVar from_base"),
+        &consumed_amount,
+    )?;
+    let (consumed_point, _) = generator.mul(
+        layouter.namespace(|| "consumed amount point"),
+        consumed_scalar,
+    )?;
+
+    let created_scalar = ScalarVar::from_base(
+        ecc_chip.clone(),
+        layouter.namespace(|| "ScalarVar from_base"),
+        &created_amount,
+    )?;
+    let (created_point, _) = generator.mul(
+        layouter.namespace(|| "created amount point"),
+        created_scalar,
+    )?;
+
+    // Get and constrain the negative created amount point
+    let neg_created_point = Point::new(
+        ecc_chip.clone(),
+        layouter.namespace(|| "negative created amount point"),
+        created_point.inner().point().neg(),
+    )?;
+    let zero_point = created_point.add(
+        layouter.namespace(|| "created_point + neg_created_point"),
+        &neg_created_point,
+    )?;
+    layouter.assign_region(
+        || "constrain zero point",
+        |mut region| {
+            region.constrain_constant(zero_point.inner().x().cell(), pallas::Base::zero())?;
+            region.constrain_constant(zero_point.inner().y().cell(), pallas::Base::zero())
+        },
+    )?;
+
+    let amount_point = consumed_point.add(
+        layouter.namespace(|| "consumed_point - created_point"),
+        &neg_created_point,
+    )?;
+
+    // blind point
+    let blind_scalar = ScalarFixed::new(
+        ecc_chip.clone(),
+        layouter.namespace(|| "blind scalar"),
+        Value::known(blind),
+    )?;
+    let blind_base = FixedPoint::from_inner(ecc_chip, TaigaFixedBasesFull::ResourceCommitmentR);
+    let (blind_point, _) = blind_base.mul(
+        layouter.namespace(|| "blind_scalar * blind_base"),
+        &blind_scalar,
+    )?;
+
+    amount_point.add(layouter.namespace(|| "auxiliary delta"), &blind_point)
+}
+
+/// Publishes the auxiliary delta as `[tag, x, y]` in the instance column, so
+/// that the executor adds it to the auxiliary delta of the transaction.
+pub fn publicize_auxiliary_delta(
+    mut layouter: impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    instance: Column<Instance>,
+    delta: &Point<pallas::Affine, EccChip<TaigaFixedBases>>,
+) -> Result<(), Error> {
+    let tag = assign_free_constant(
+        layouter.namespace(|| "auxiliary delta tag"),
+        advice,
+        *AUXILIARY_DELTA_TAG,
+    )?;
+    layouter.constrain_instance(tag.cell(), instance, 0)?;
+    layouter.constrain_instance(delta.inner().x().cell(), instance, 1)?;
+    layouter.constrain_instance(delta.inner().y().cell(), instance, 2)
+}
@@ -8,6 +8,7 @@ use crate::constant::{
     RESOURCE_LOGIC_COMMITMENT_PERSONALIZATION,
 };
 use crate::resource_logic_commitment::ResourceLogicCommitment;
+use crate::utils::to_le_bytes;
 use byteorder::{ByteOrder, LittleEndian};
 use group::ff::PrimeField;
 use halo2_gadgets::utilities::bool_check;
@@ -783,8 +784,9 @@ impl<F: PrimeField> Blake2sChip<F> {
         // the decomposition from bytes to bits
         let mut bits = vec![];
         let mut bytes = vec![];
+        let field_bytes = field.value().map(to_le_bytes);
         for i in 0..32 {
-            let byte_value = field.value().map(|f| f.to_repr().as_ref()[i]);
+            let byte_value = field_bytes.as_ref().map(|bytes| bytes[i]);
             let byte =
                 Blake2sByte::from_u8(byte_value, layouter.namespace(|| "from_u8"), &self.config)?;
             bits.append(&mut byte.get_bits().to_vec());
@@ -948,7 +950,7 @@ impl<F: PrimeField> Blake2sChip<F> {
                 y.copy_advice(|| "word_add y", &mut region, self.config.advices[1], 0)?;
                 let sum = x.value().zip(y.value()).map(|(&x, &y)| {
                     let sum = x + y;
-                    let carry = F::from(sum.to_repr().as_ref()[4] as u64);
+                    let carry = F::from(to_le_bytes(&sum)[4] as u64);
                     let ret = sum - carry * F::from(1 << 32);
                     (ret, carry)
                 });
@@ -1085,8 +1087,9 @@ impl<F: PrimeField> Blake2sWord<F> {
     ) -> Result<Self, Error> {
         let mut bytes = Vec::with_capacity(4);
         let mut bits = Vec::with_capacity(32);
+        let word_bytes = word.value().map(to_le_bytes);
         for i in 0..4 {
-            let byte_value = word.value().map(|v| v.to_repr().as_ref()[i]);
+            let byte_value = word_bytes.as_ref().map(|bytes| bytes[i]);
             let byte =
                 Blake2sByte::from_u8(byte_value, layouter.namespace(|| "from_u8"), &chip.config)?;
             bits.append(&mut byte.get_bits().to_vec());
@@ -1,5 +1,5 @@
 use crate::circuit::blake2s::{resource_logic_commitment_gadget, Blake2sChip, Blake2sConfig};
-use crate::circuit::gadgets::assign_free_advice;
+use crate::circuit::gadgets::{assign_free_advice, poseidon_hash::poseidon_hash_gadget};
 use crate::circuit::hash_to_curve::HashToCurveConfig;
 use crate::circuit::integrity::{
     check_input_resource, check_output_resource, compute_delta_commitment,
@@ -8,13 +8,14 @@ use crate::circuit::merkle_circuit::{
     merkle_poseidon_gadget, MerklePoseidonChip, MerklePoseidonConfig,
 };
 use crate::constant::{
-    TaigaFixedBases, COMPLIANCE_ANCHOR_PUBLIC_INPUT_ROW_IDX,
+    TaigaFixedBases, COMPLIANCE_ANCHOR_PUBLIC_INPUT_ROW_IDX, COMPLIANCE_CONVERSION_RULE_ROW_IDX,
     COMPLIANCE_DELTA_CM_X_PUBLIC_INPUT_ROW_IDX, COMPLIANCE_DELTA_CM_Y_PUBLIC_INPUT_ROW_IDX,
-    COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_1_ROW_IDX, COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_2_ROW_IDX,
-    COMPLIANCE_NF_PUBLIC_INPUT_ROW_IDX, COMPLIANCE_OUTPUT_CM_PUBLIC_INPUT_ROW_IDX,
-    COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_1_ROW_IDX, COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_2_ROW_IDX,
-    TAIGA_COMMITMENT_TREE_DEPTH,
+    COMPLIANCE_EPOCH_ROW_IDX, COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_1_ROW_IDX,
+    COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_2_ROW_IDX, COMPLIANCE_NF_PUBLIC_INPUT_ROW_IDX,
+    COMPLIANCE_OUTPUT_CM_PUBLIC_INPUT_ROW_IDX, COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_1_ROW_IDX,
+    COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_2_ROW_IDX, TAIGA_COMMITMENT_TREE_DEPTH,
 };
+use crate::conversion::ConversionRule;
 use crate::merkle_tree::LR;
 use crate::resource::Resource;
 
@@ -44,6 +45,7 @@ pub struct ComplianceConfig {
     poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
     merkle_config: MerklePoseidonConfig,
     merkle_path_selector: Selector,
+    conversion_selector: Selector,
     hash_to_curve_config: HashToCurveConfig,
     blake2s_config: Blake2sConfig<pallas::Base>,
     resource_commit_config: ResourceCommitConfig,
@@ -64,6 +66,10 @@ pub struct ComplianceCircuit {
     pub input_resource_logic_cm_r: pallas::Base,
     /// The randomness of output resource logic commitment
     pub output_resource_logic_cm_r: pallas::Base,
+    /// The conversion rule from the input resource to the output resource
+    pub conversion: Option<ConversionRule>,
+    /// The epoch the compliance is proven at
+    pub epoch: u64,
 }
 
 impl Circuit<pallas::Base> for ComplianceCircuit {
@@ -139,6 +145,54 @@ impl Circuit<pallas::Base> for ComplianceCircuit {
             )
         });
 
+        // Without a conversion rule the quantities go to the delta commitment
+        // and the rule id is zero. With a rule, the quantities follow its
+        // ratio, they are masked out of the delta commitment and the rule id
+        // is the hash of the rule.
+        let conversion_selector = meta.selector();
+        meta.create_gate("conversion check", |meta| {
+            let conversion_selector = meta.query_selector(conversion_selector);
+            let is_conversion = meta.query_advice(advices[0], Rotation::cur());
+            let input_quantity = meta.query_advice(advices[1], Rotation::cur());
+            let output_quantity = meta.query_advice(advices[2], Rotation::cur());
+            let from_quantity = meta.query_advice(advices[3], Rotation::cur());
+            let to_quantity = meta.query_advice(advices[4], Rotation::cur());
+            let rule_hash = meta.query_advice(advices[5], Rotation::cur());
+            let masked_input_quantity = meta.query_advice(advices[6], Rotation::cur());
+            let masked_output_quantity = meta.query_advice(advices[7], Rotation::cur());
+            let rule_id = meta.query_advice(advices[8], Rotation::cur());
+            let constant_one = Expression::Constant(pallas::Base::one());
+            let not_conversion = constant_one - is_conversion.clone();
+
+            Constraints::with_selector(
+                conversion_selector,
+                [
+                    (
+                        "is_conversion is bool",
+                        is_conversion.clone() * not_conversion.clone(),
+                    ),
+                    (
+                        "is_conversion is false, or input * to = output * from",
+                        is_conversion.clone()
+                            * (input_quantity.clone() * to_quantity
+                                - output_quantity.clone() * from_quantity),
+                    ),
+                    (
+                        "masked_input_quantity = (1 - is_conversion) * input_quantity",
+                        masked_input_quantity - not_conversion.clone() * input_quantity,
+                    ),
+                    (
+                        "masked_output_quantity = (1 - is_conversion) * output_quantity",
+                        masked_output_quantity - not_conversion * output_quantity,
+                    ),
+                    (
+                        "rule_id = is_conversion * rule_hash",
+                        rule_id - is_conversion * rule_hash,
+                    ),
+                ],
+            )
+        });
+
         let merkle_config = MerklePoseidonChip::configure(
             meta,
             advices[..5].try_into().unwrap(),
@@ -165,12 +219,17 @@ impl Circuit<pallas::Base> for ComplianceCircuit {
             poseidon_config,
             merkle_config,
             merkle_path_selector,
+            conversion_selector,
             hash_to_curve_config,
             blake2s_config,
             resource_commit_config,
         }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "compliance.synthesize", skip_all)
+    )]
     fn synthesize(
         &self,
         config: Self::Config,
@@ -210,7 +269,7 @@ impl Circuit<pallas::Base> for ComplianceCircuit {
             config.advices,
             config.instances,
             resource_commit_chip.clone(),
-            self.input_resource,
+            &self.input_resource,
             COMPLIANCE_NF_PUBLIC_INPUT_ROW_IDX,
         )?;
 
@@ -228,11 +287,101 @@ impl Circuit<pallas::Base> for ComplianceCircuit {
             config.advices,
             config.instances,
             resource_commit_chip,
-            self.output_resource,
+            &self.output_resource,
             input_resource_variables.nf,
             COMPLIANCE_OUTPUT_CM_PUBLIC_INPUT_ROW_IDX,
         )?;
 
+        // Conversion rule check
+        let is_conversion = assign_free_advice(
+            layouter.namespace(|| "witness is_conversion"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.conversion.is_some())),
+        )?;
+        let from_quantity = assign_free_advice(
+            layouter.namespace(|| "witness from_quantity"),
+            config.advices[0],
+            Value::known(pallas::Base::from(
+                self.conversion.map_or(0, |rule| rule.from_quantity),
+            )),
+        )?;
+        let to_quantity = assign_free_advice(
+            layouter.namespace(|| "witness to_quantity"),
+            config.advices[0],
+            Value::known(pallas::Base::from(
+                self.conversion.map_or(0, |rule| rule.to_quantity),
+            )),
+        )?;
+        let rule_hash = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "conversion rule hash"),
+            [
+                input_resource_variables.resource_variables.logic.clone(),
+                input_resource_variables.resource_variables.label.clone(),
+                output_resource_vars.logic.clone(),
+                output_resource_vars.label.clone(),
+                from_quantity.clone(),
+                to_quantity.clone(),
+            ],
+        )?;
+        let (masked_input_quantity, masked_output_quantity, rule_id) = layouter.assign_region(
+            || "conversion check",
+            |mut region| {
+                is_conversion.copy_advice(|| "is_conversion", &mut region, config.advices[0], 0)?;
+                input_resource_variables
+                    .resource_variables
+                    .quantity
+                    .copy_advice(|| "input_quantity", &mut region, config.advices[1], 0)?;
+                output_resource_vars.quantity.copy_advice(
+                    || "output_quantity",
+                    &mut region,
+                    config.advices[2],
+                    0,
+                )?;
+                from_quantity.copy_advice(|| "from_quantity", &mut region, config.advices[3], 0)?;
+                to_quantity.copy_advice(|| "to_quantity", &mut region, config.advices[4], 0)?;
+                rule_hash.copy_advice(|| "rule_hash", &mut region, config.advices[5], 0)?;
+                let (masked_input_quantity, masked_output_quantity, rule_id) = match self.conversion
+                {
+                    Some(_) => (
+                        Value::known(pallas::Base::zero()),
+                        Value::known(pallas::Base::zero()),
+                        rule_hash.value().copied(),
+                    ),
+                    None => (
+                        input_resource_variables
+                            .resource_variables
+                            .quantity
+                            .value()
+                            .copied(),
+                        output_resource_vars.quantity.value().copied(),
+                        Value::known(pallas::Base::zero()),
+                    ),
+                };
+                let masked_input_quantity = region.assign_advice(
+                    || "masked_input_quantity",
+                    config.advices[6],
+                    0,
+                    || masked_input_quantity,
+                )?;
+                let masked_output_quantity = region.assign_advice(
+                    || "masked_output_quantity",
+                    config.advices[7],
+                    0,
+                    || masked_output_quantity,
+                )?;
+                let rule_id =
+                    region.assign_advice(|| "rule_id", config.advices[8], 0, || rule_id)?;
+                config.conversion_selector.enable(&mut region, 0)?;
+                Ok((masked_input_quantity, masked_output_quantity, rule_id))
+            },
+        )?;
+        layouter.constrain_instance(
+            rule_id.cell(),
+            config.instances,
+            COMPLIANCE_CONVERSION_RULE_ROW_IDX,
+        )?;
+
         // compute and public delta commitment(input_value_commitment - output_value_commitment)
         let delta = compute_delta_commitment(
             layouter.namespace(|| "delta commitment"),
@@ -240,10 +389,10 @@ impl Circuit<pallas::Base> for ComplianceCircuit {
             config.hash_to_curve_config.clone(),
             input_resource_variables.resource_variables.logic.clone(),
             input_resource_variables.resource_variables.label.clone(),
-            input_resource_variables.resource_variables.quantity.clone(),
+            masked_input_quantity,
             output_resource_vars.logic.clone(),
             output_resource_vars.label.clone(),
-            output_resource_vars.quantity,
+            masked_output_quantity,
             self.rcv,
         )?;
         layouter.constrain_instance(
@@ -323,6 +472,14 @@ impl Circuit<pallas::Base> for ComplianceCircuit {
             COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_2_ROW_IDX,
         )?;
 
+        // Publicize the epoch, binding the proof to it
+        let epoch = assign_free_advice(
+            layouter.namespace(|| "witness epoch"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.epoch)),
+        )?;
+        layouter.constrain_instance(epoch.cell(), config.instances, COMPLIANCE_EPOCH_ROW_IDX)?;
+
         Ok(())
     }
 }
@@ -372,3 +529,54 @@ fn test_halo2_compliance_circuit() {
         )
         .is_ok());
 }
+
+#[test]
+fn test_halo2_compliance_circuit_with_conversion() {
+    use crate::compliance::ComplianceInfo;
+    use crate::constant::COMPLIANCE_CIRCUIT_PARAMS_SIZE;
+    use crate::merkle_tree::MerklePath;
+    use crate::resource::tests::random_resource;
+    use halo2_proofs::dev::MockProver;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let mut input_resource = random_resource(&mut rng);
+    input_resource.quantity = 30.into();
+    let mut output_resource = random_resource(&mut rng);
+    output_resource.quantity = 20.into();
+    let rule = ConversionRule::new(input_resource.kind, output_resource.kind, 3, 2).unwrap();
+    let compliance_info = ComplianceInfo::new(
+        input_resource,
+        MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+        None,
+        &mut output_resource,
+        &mut rng,
+    )
+    .with_conversion(rule)
+    .unwrap();
+    let (compliance, compliance_circuit) = compliance_info.build();
+    assert_eq!(compliance.conversion_rule, rule.id());
+    let prover = MockProver::<pallas::Base>::run(
+        COMPLIANCE_CIRCUIT_PARAMS_SIZE,
+        &compliance_circuit,
+        vec![compliance.to_instance()],
+    )
+    .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // The quantities must follow the ratio of the rule
+    let mut invalid_circuit = compliance_circuit;
+    invalid_circuit.conversion = Some(ConversionRule {
+        to_quantity: 3,
+        ..rule
+    });
+    let mut instance = compliance.to_instance();
+    instance[COMPLIANCE_CONVERSION_RULE_ROW_IDX] = invalid_circuit.conversion.unwrap().id().inner();
+    let prover = MockProver::<pallas::Base>::run(
+        COMPLIANCE_CIRCUIT_PARAMS_SIZE,
+        &invalid_circuit,
+        vec![instance],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+}
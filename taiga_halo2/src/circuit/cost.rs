@@ -0,0 +1,176 @@
+//! The cost of a circuit instance, see [`estimate`]. Logic authors can check
+//! that their custom constraints fit in `RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE`
+//! before hitting a proving failure.
+use halo2_proofs::{
+    circuit::Value,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+        FloorPlanner, Instance, Selector,
+    },
+};
+use pasta_curves::pallas;
+use std::ops::Range;
+
+/// The rows and columns used by a circuit instance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitCost {
+    /// The rows assigned by the circuit, including the lookup tables.
+    pub rows: usize,
+    /// The rows reserved by the prover for the blinding factors.
+    pub reserved_rows: usize,
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub selectors: usize,
+    /// The name and rows of every region, in the order of assignment.
+    pub regions: Vec<(String, usize)>,
+}
+
+impl CircuitCost {
+    /// The minimal params size of the circuit.
+    pub fn min_k(&self) -> u32 {
+        (self.rows + self.reserved_rows)
+            .next_power_of_two()
+            .trailing_zeros()
+    }
+
+    /// Whether the circuit fits in params of size `k`.
+    pub fn fits(&self, k: u32) -> bool {
+        self.min_k() <= k
+    }
+}
+
+/// Synthesizes the circuit without a prover, recording the rows it assigns.
+pub fn estimate<C: Circuit<pallas::Base>>(circuit: &C) -> Result<CircuitCost, Error> {
+    let mut cs = ConstraintSystem::default();
+    let config = C::configure(&mut cs);
+    let mut recorder = RowRecorder::default();
+    C::FloorPlanner::synthesize(&mut recorder, circuit, config, cs.constants().clone())?;
+    Ok(CircuitCost {
+        rows: recorder.rows,
+        reserved_rows: cs.minimum_rows(),
+        advice_columns: cs.num_advice_columns(),
+        fixed_columns: cs.num_fixed_columns(),
+        selectors: cs.num_selectors(),
+        regions: recorder.regions,
+    })
+}
+
+#[derive(Default)]
+struct RowRecorder {
+    regions: Vec<(String, usize)>,
+    current: Option<(String, Option<Range<usize>>)>,
+    rows: usize,
+}
+
+impl RowRecorder {
+    fn use_row(&mut self, row: usize) {
+        self.rows = self.rows.max(row + 1);
+        if let Some((_, range)) = &mut self.current {
+            let range = range.get_or_insert(row..row + 1);
+            range.start = range.start.min(row);
+            range.end = range.end.max(row + 1);
+        }
+    }
+}
+
+impl Assignment<pallas::Base> for RowRecorder {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.current = Some((name_fn().into(), None));
+    }
+
+    fn exit_region(&mut self) {
+        if let Some((name, range)) = self.current.take() {
+            self.regions
+                .push((name, range.map_or(0, |range| range.len())));
+        }
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<pallas::Base>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<pallas::Base>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<pallas::Base>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<pallas::Base>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[test]
+fn test_circuit_cost() {
+    use crate::circuit::{
+        compliance_circuit::ComplianceCircuit, resource_logic_examples::TrivialResourceLogicCircuit,
+    };
+    use crate::constant::{COMPLIANCE_CIRCUIT_PARAMS_SIZE, RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE};
+
+    let cost = estimate(&TrivialResourceLogicCircuit::default()).unwrap();
+    assert!(cost.fits(RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE));
+    assert!(!cost.fits(cost.min_k() - 1));
+    assert!(cost.regions.iter().all(|(_, rows)| *rows <= cost.rows));
+
+    let cost = estimate(&ComplianceCircuit::default()).unwrap();
+    assert!(cost.fits(COMPLIANCE_CIRCUIT_PARAMS_SIZE));
+}
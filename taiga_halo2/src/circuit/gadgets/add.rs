@@ -109,3 +109,34 @@ impl<F: Field> AddInstructions<F> for AddChip<F> {
         )
     }
 }
+
+#[test]
+fn test_add_chip() {
+    use crate::circuit::gadgets::{assign_free_advice, gadget_test_harness};
+    use halo2_proofs::{circuit::Value, dev::MockProver};
+    use pasta_curves::pallas;
+
+    gadget_test_harness!(
+        AddCircuit { a: pallas::Base, b: pallas::Base },
+        advices: 2,
+        config: AddConfig,
+        configure: |meta, advices| AddChip::<pallas::Base>::configure(meta, advices),
+        synthesize: |circuit, config, layouter, advices, instance| {
+            let a = assign_free_advice(layouter.namespace(|| "a"), advices[0], Value::known(circuit.a))?;
+            let b = assign_free_advice(layouter.namespace(|| "b"), advices[1], Value::known(circuit.b))?;
+            let add_chip = AddChip::<pallas::Base>::construct(config, ());
+            let c = add_chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+            layouter.constrain_instance(c.cell(), instance, 0)
+        }
+    );
+
+    let circuit = AddCircuit {
+        a: pallas::Base::from(2u64),
+        b: pallas::Base::from(3u64),
+    };
+    let prover = MockProver::run(5, &circuit, vec![vec![pallas::Base::from(5u64)]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let prover = MockProver::run(5, &circuit, vec![vec![pallas::Base::from(6u64)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
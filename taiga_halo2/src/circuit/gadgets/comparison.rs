@@ -0,0 +1,253 @@
+/// Comparisons of u64 values, e.g. quantities.
+///
+/// The inputs are range checked to 64 bits and the results are boolean cells,
+/// which a logic constrains to one to assert the predicate.
+use group::ff::PrimeField;
+use halo2_gadgets::utilities::{bool_check, lookup_range_check::LookupRangeCheckConfig};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use pasta_curves::pallas;
+
+#[derive(Clone, Debug)]
+pub struct ComparisonConfig {
+    q_less_than: Selector,
+    q_in_range: Selector,
+    advice: [Column<Advice>; 3],
+    lookup_config: LookupRangeCheckConfig<pallas::Base, 10>,
+}
+
+impl ComparisonConfig {
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        advice: [Column<Advice>; 3],
+        lookup_config: LookupRangeCheckConfig<pallas::Base, 10>,
+    ) -> Self {
+        let config = Self {
+            q_less_than: meta.selector(),
+            q_in_range: meta.selector(),
+            advice,
+            lookup_config,
+        };
+
+        config.create_gate(meta);
+
+        config
+    }
+
+    fn create_gate(&self, meta: &mut ConstraintSystem<pallas::Base>) {
+        let two_pow_64 = pallas::Base::from_u128(1 << 64);
+
+        // For a and b less than 2^64, a - b + 2^64 is in [1, 2^65), below
+        // 2^64 iff a < b.
+        meta.create_gate("less than", |meta| {
+            let q_less_than = meta.query_selector(self.q_less_than);
+
+            let a = meta.query_advice(self.advice[0], Rotation::cur());
+            let b = meta.query_advice(self.advice[1], Rotation::cur());
+            let lt = meta.query_advice(self.advice[2], Rotation::cur());
+            let diff = meta.query_advice(self.advice[0], Rotation::next());
+
+            Constraints::with_selector(
+                q_less_than,
+                [
+                    ("bool_check lt", bool_check(lt.clone())),
+                    ("a + 2^64 * lt = b + diff", a + lt * two_pow_64 - b - diff),
+                ],
+            )
+        });
+
+        meta.create_gate("in range", |meta| {
+            let q_in_range = meta.query_selector(self.q_in_range);
+
+            let below = meta.query_advice(self.advice[0], Rotation::cur());
+            let above = meta.query_advice(self.advice[1], Rotation::cur());
+            let in_range = meta.query_advice(self.advice[2], Rotation::cur());
+            let one = Expression::Constant(pallas::Base::one());
+
+            Constraints::with_selector(
+                q_in_range,
+                [(
+                    "in_range = (1 - below) * (1 - above)",
+                    in_range - (one.clone() - below) * (one - above),
+                )],
+            )
+        });
+    }
+
+    /// Constrains the value to be less than 2^64.
+    pub fn range_check_u64(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        value: &AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<(), Error> {
+        let zs = self.lookup_config.copy_check(
+            layouter.namespace(|| "6 * K(10) bits range check"),
+            value.clone(),
+            6,
+            false,
+        )?;
+        self.lookup_config.copy_short_check(
+            layouter.namespace(|| "4 bits range check"),
+            zs[6].clone(),
+            4,
+        )
+    }
+
+    /// Returns 1 if a < b, else 0.
+    pub fn less_than_u64(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        a: &AssignedCell<pallas::Base, pallas::Base>,
+        b: &AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+        self.range_check_u64(layouter.namespace(|| "range check a"), a)?;
+        self.range_check_u64(layouter.namespace(|| "range check b"), b)?;
+
+        let witness = a.value().zip(b.value()).map(|(a, b)| {
+            let lt = pallas::Base::from(to_u64(a) < to_u64(b));
+            (lt, lt * pallas::Base::from_u128(1 << 64) + a - b)
+        });
+        let (lt, diff) = layouter.assign_region(
+            || "less than",
+            |mut region| {
+                self.q_less_than.enable(&mut region, 0)?;
+
+                a.copy_advice(|| "a", &mut region, self.advice[0], 0)?;
+                b.copy_advice(|| "b", &mut region, self.advice[1], 0)?;
+                let lt =
+                    region.assign_advice(|| "lt", self.advice[2], 0, || witness.map(|w| w.0))?;
+                let diff =
+                    region.assign_advice(|| "diff", self.advice[0], 1, || witness.map(|w| w.1))?;
+                Ok((lt, diff))
+            },
+        )?;
+        self.range_check_u64(layouter.namespace(|| "range check diff"), &diff)?;
+
+        Ok(lt)
+    }
+
+    /// Returns 1 if a > b, else 0.
+    pub fn greater_than(
+        &self,
+        layouter: impl Layouter<pallas::Base>,
+        a: &AssignedCell<pallas::Base, pallas::Base>,
+        b: &AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+        self.less_than_u64(layouter, b, a)
+    }
+
+    /// Returns 1 if min <= value <= max, else 0.
+    pub fn in_range(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        value: &AssignedCell<pallas::Base, pallas::Base>,
+        min: &AssignedCell<pallas::Base, pallas::Base>,
+        max: &AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+        let below = self.less_than_u64(layouter.namespace(|| "value < min"), value, min)?;
+        let above = self.less_than_u64(layouter.namespace(|| "max < value"), max, value)?;
+
+        layouter.assign_region(
+            || "in range",
+            |mut region| {
+                self.q_in_range.enable(&mut region, 0)?;
+
+                below.copy_advice(|| "below", &mut region, self.advice[0], 0)?;
+                above.copy_advice(|| "above", &mut region, self.advice[1], 0)?;
+                let in_range = below.value().zip(above.value()).map(|(below, above)| {
+                    (pallas::Base::one() - below) * (pallas::Base::one() - above)
+                });
+                region.assign_advice(|| "in_range", self.advice[2], 0, || in_range)
+            },
+        )
+    }
+}
+
+// The low 64 bits of a value, all of it for the range checked values.
+fn to_u64(value: &pallas::Base) -> u64 {
+    u64::from_le_bytes(value.to_repr()[..8].try_into().unwrap())
+}
+
+#[test]
+fn test_comparison_gadget() {
+    use crate::circuit::gadgets::{assign_free_advice, gadget_test_harness};
+    use halo2_proofs::{circuit::Value, dev::MockProver, plonk::TableColumn};
+
+    gadget_test_harness!(
+        ComparisonCircuit { value: pallas::Base, min: pallas::Base, max: pallas::Base },
+        advices: 4,
+        config: (ComparisonConfig, TableColumn),
+        configure: |meta, advices| {
+            let table_idx = meta.lookup_table_column();
+            let lookup_config = LookupRangeCheckConfig::configure(meta, advices[3], table_idx);
+            (
+                ComparisonConfig::configure(meta, advices[..3].try_into().unwrap(), lookup_config),
+                table_idx,
+            )
+        },
+        synthesize: |circuit, config, layouter, advices, instance| {
+            let (config, table_idx) = config;
+            layouter.assign_table(
+                || "table_idx",
+                |mut table| {
+                    for index in 0..(1 << 10) {
+                        table.assign_cell(
+                            || "table_idx",
+                            table_idx,
+                            index,
+                            || Value::known(pallas::Base::from(index as u64)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+            let value = assign_free_advice(layouter.namespace(|| "value"), advices[0], Value::known(circuit.value))?;
+            let min = assign_free_advice(layouter.namespace(|| "min"), advices[1], Value::known(circuit.min))?;
+            let max = assign_free_advice(layouter.namespace(|| "max"), advices[2], Value::known(circuit.max))?;
+            let lt = config.less_than_u64(layouter.namespace(|| "value < max"), &value, &max)?;
+            let gt = config.greater_than(layouter.namespace(|| "value > min"), &value, &min)?;
+            let in_range = config.in_range(layouter.namespace(|| "in range"), &value, &min, &max)?;
+            layouter.constrain_instance(lt.cell(), instance, 0)?;
+            layouter.constrain_instance(gt.cell(), instance, 1)?;
+            layouter.constrain_instance(in_range.cell(), instance, 2)
+        }
+    );
+
+    let run = |value: u64, min: u64, max: u64, expected: [u64; 3]| {
+        let circuit = ComparisonCircuit {
+            value: pallas::Base::from(value),
+            min: pallas::Base::from(min),
+            max: pallas::Base::from(max),
+        };
+        let instance = expected.iter().map(|b| pallas::Base::from(*b)).collect();
+        MockProver::run(11, &circuit, vec![instance])
+            .unwrap()
+            .verify()
+    };
+
+    assert_eq!(run(5, 3, 9, [1, 1, 1]), Ok(()));
+    assert_eq!(run(3, 3, 9, [1, 0, 1]), Ok(()));
+    assert_eq!(run(9, 3, 9, [0, 1, 1]), Ok(()));
+    assert_eq!(run(2, 3, 9, [1, 0, 0]), Ok(()));
+    assert_eq!(run(u64::MAX, 0, u64::MAX, [0, 1, 1]), Ok(()));
+    assert!(run(5, 3, 9, [0, 1, 1]).is_err());
+
+    // Values of 64 bits or more are rejected
+    let circuit = ComparisonCircuit {
+        value: pallas::Base::from_u128(1 << 64),
+        min: pallas::Base::zero(),
+        max: pallas::Base::from(u64::MAX),
+    };
+    let instance = vec![
+        pallas::Base::zero(),
+        pallas::Base::one(),
+        pallas::Base::zero(),
+    ];
+    assert!(MockProver::run(11, &circuit, vec![instance])
+        .unwrap()
+        .verify()
+        .is_err());
+}
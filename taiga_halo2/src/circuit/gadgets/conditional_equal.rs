@@ -60,3 +60,38 @@ impl ConditionalEqualConfig {
         Ok(())
     }
 }
+
+#[test]
+fn test_conditional_equal_gadget() {
+    use crate::circuit::gadgets::{assign_free_advice, gadget_test_harness};
+    use halo2_proofs::{circuit::Value, dev::MockProver};
+
+    gadget_test_harness!(
+        ConditionalEqualCircuit { flag: pallas::Base, lhs: pallas::Base, rhs: pallas::Base },
+        advices: 3,
+        config: ConditionalEqualConfig,
+        configure: |meta, advices| ConditionalEqualConfig::configure(meta, advices),
+        synthesize: |circuit, config, layouter, advices, instance| {
+            let flag = assign_free_advice(layouter.namespace(|| "flag"), advices[0], Value::known(circuit.flag))?;
+            let lhs = assign_free_advice(layouter.namespace(|| "lhs"), advices[1], Value::known(circuit.lhs))?;
+            let rhs = assign_free_advice(layouter.namespace(|| "rhs"), advices[2], Value::known(circuit.rhs))?;
+            layouter.assign_region(
+                || "conditional equal",
+                |mut region| config.assign_region(&flag, &lhs, &rhs, 0, &mut region),
+            )
+        }
+    );
+
+    let check = |flag: u64, lhs: u64, rhs: u64| {
+        let circuit = ConditionalEqualCircuit {
+            flag: pallas::Base::from(flag),
+            lhs: pallas::Base::from(lhs),
+            rhs: pallas::Base::from(rhs),
+        };
+        MockProver::run(5, &circuit, vec![vec![]]).unwrap().verify()
+    };
+
+    assert_eq!(check(1, 7, 7), Ok(()));
+    assert_eq!(check(0, 7, 8), Ok(()));
+    assert!(check(1, 7, 8).is_err());
+}
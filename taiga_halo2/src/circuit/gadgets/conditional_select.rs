@@ -70,3 +70,41 @@ impl ConditionalSelectConfig {
         region.assign_advice(|| "ret", self.advice[0], offset + 1, || ret_value)
     }
 }
+
+#[test]
+fn test_conditional_select_gadget() {
+    use crate::circuit::gadgets::{assign_free_advice, gadget_test_harness};
+    use halo2_proofs::{circuit::Value, dev::MockProver};
+
+    gadget_test_harness!(
+        ConditionalSelectCircuit { flag: pallas::Base, lhs: pallas::Base, rhs: pallas::Base },
+        advices: 2,
+        config: ConditionalSelectConfig,
+        configure: |meta, advices| ConditionalSelectConfig::configure(meta, advices),
+        synthesize: |circuit, config, layouter, advices, instance| {
+            let flag = assign_free_advice(layouter.namespace(|| "flag"), advices[0], Value::known(circuit.flag))?;
+            let lhs = assign_free_advice(layouter.namespace(|| "lhs"), advices[1], Value::known(circuit.lhs))?;
+            let rhs = assign_free_advice(layouter.namespace(|| "rhs"), advices[1], Value::known(circuit.rhs))?;
+            let ret = layouter.assign_region(
+                || "conditional select",
+                |mut region| config.assign_region(&flag, &lhs, &rhs, 0, &mut region),
+            )?;
+            layouter.constrain_instance(ret.cell(), instance, 0)
+        }
+    );
+
+    let select = |flag: u64, expected: u64| {
+        let circuit = ConditionalSelectCircuit {
+            flag: pallas::Base::from(flag),
+            lhs: pallas::Base::from(7u64),
+            rhs: pallas::Base::from(8u64),
+        };
+        MockProver::run(5, &circuit, vec![vec![pallas::Base::from(expected)]])
+            .unwrap()
+            .verify()
+    };
+
+    assert_eq!(select(1, 7), Ok(()));
+    assert_eq!(select(0, 8), Ok(()));
+    assert!(select(1, 8).is_err());
+}
@@ -0,0 +1,60 @@
+//! Gadget for logics publishing a linkability tag, so that the executor can
+//! detect that the same nk was used twice for the app without learning it, e.g.
+//! one vote per credential. The native counterpart is `LinkabilityTag::derive`.
+//!
+//! A logic publishing the tag of the nk behind the npk of its self resource:
+//! ```ignore
+//! let nk = nk_possession_gadget(
+//!     layouter.namespace(|| "nk possession"),
+//!     config.advices[0],
+//!     config.poseidon_config.clone(),
+//!     Value::known(nk),
+//!     &self_resource.resource.npk,
+//! )?;
+//! linkability_tag_gadget(
+//!     layouter.namespace(|| "linkability tag"),
+//!     config.advices[0],
+//!     config.instances,
+//!     config.poseidon_config.clone(),
+//!     nk,
+//!     app_domain,
+//! )?;
+//! ```
+use super::{assign_free_constant, poseidon_hash::poseidon_hash_gadget};
+use crate::constant::{LINKABILITY_TAG_DOMAIN, RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX};
+use halo2_gadgets::poseidon::Pow5Config as PoseidonConfig;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, Error, Instance},
+};
+use pasta_curves::pallas;
+
+/// Derives tag = PRF(nk, app_domain) and publishes it at
+/// `RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX`.
+pub fn linkability_tag_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    instances: Column<Instance>,
+    poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+    nk: AssignedCell<pallas::Base, pallas::Base>,
+    app_domain: AssignedCell<pallas::Base, pallas::Base>,
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+    let domain = assign_free_constant(
+        layouter.namespace(|| "linkability tag domain"),
+        advice,
+        *LINKABILITY_TAG_DOMAIN,
+    )?;
+
+    let tag = poseidon_hash_gadget(
+        poseidon_config,
+        layouter.namespace(|| "derive linkability tag"),
+        [domain, nk, app_domain],
+    )?;
+
+    layouter.constrain_instance(
+        tag.cell(),
+        instances,
+        RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX,
+    )?;
+    Ok(tag)
+}
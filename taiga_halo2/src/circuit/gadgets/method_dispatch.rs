@@ -0,0 +1,260 @@
+//! Gadgets for logics dispatching several methods, see `crate::method_call`.
+//!
+//! A logic allowing the methods `transfer` and `burn` for the self resource:
+//! ```ignore
+//! let (selector, args_digest) = method_call_gadget(
+//!     layouter.namespace(|| "method call"),
+//!     config.advices[0],
+//!     config.poseidon_config.clone(),
+//!     Value::known(call),
+//!     &self_resource.resource.value,
+//! )?;
+//! let transfer = assign_free_constant(..., method_selector("transfer"))?;
+//! let burn = assign_free_constant(..., method_selector("burn"))?;
+//! let flags = layouter.assign_region(
+//!     || "method dispatch",
+//!     |mut region| {
+//!         config
+//!             .method_dispatch_config
+//!             .assign_region(&selector, &[transfer.clone(), burn.clone()], 0, &mut region)
+//!     },
+//! )?;
+//! // Constrain the transfer with flags[0] and the burn with flags[1]
+//! ```
+use super::{assign_free_advice, assign_free_constant, poseidon_hash::poseidon_hash_gadget};
+use crate::{constant::METHOD_CALL_DOMAIN, method_call::MethodCall};
+use halo2_gadgets::poseidon::Pow5Config as PoseidonConfig;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use pasta_curves::pallas;
+
+/// Opens `value` as the method call
+/// poseidon_hash(METHOD_CALL_DOMAIN || selector || args_digest) and returns
+/// the selector and the args digest.
+#[allow(clippy::type_complexity)]
+pub fn method_call_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+    call: Value<MethodCall>,
+    value: &AssignedCell<pallas::Base, pallas::Base>,
+) -> Result<
+    (
+        AssignedCell<pallas::Base, pallas::Base>,
+        AssignedCell<pallas::Base, pallas::Base>,
+    ),
+    Error,
+> {
+    let domain = assign_free_constant(
+        layouter.namespace(|| "method call domain"),
+        advice,
+        *METHOD_CALL_DOMAIN,
+    )?;
+    let selector = assign_free_advice(
+        layouter.namespace(|| "selector"),
+        advice,
+        call.map(|call| call.selector),
+    )?;
+    let args_digest = assign_free_advice(
+        layouter.namespace(|| "args digest"),
+        advice,
+        call.map(|call| call.args_digest),
+    )?;
+
+    let encoded = poseidon_hash_gadget(
+        poseidon_config,
+        layouter.namespace(|| "encode method call"),
+        [domain, selector.clone(), args_digest.clone()],
+    )?;
+    layouter.assign_region(
+        || "value = method call",
+        |mut region| region.constrain_equal(encoded.cell(), value.cell()),
+    )?;
+    Ok((selector, args_digest))
+}
+
+/// Selects one of the allowed methods: flag_i is boolean, the flags sum to one
+/// and sum(flag_i * allowed_i) = selector. The allowed selectors must be
+/// distinct.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MethodDispatchConfig {
+    q_method_dispatch: Selector,
+    advice: [Column<Advice>; 4],
+}
+
+impl MethodDispatchConfig {
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        advice: [Column<Advice>; 4],
+    ) -> Self {
+        let config = Self {
+            q_method_dispatch: meta.selector(),
+            advice,
+        };
+
+        config.create_gate(meta);
+
+        config
+    }
+
+    fn create_gate(&self, meta: &mut ConstraintSystem<pallas::Base>) {
+        meta.create_gate("method dispatch", |meta| {
+            let q_method_dispatch = meta.query_selector(self.q_method_dispatch);
+
+            let flag = meta.query_advice(self.advice[0], Rotation::cur());
+            let allowed = meta.query_advice(self.advice[1], Rotation::cur());
+            let flag_sum = meta.query_advice(self.advice[2], Rotation::cur());
+            let selector_sum = meta.query_advice(self.advice[3], Rotation::cur());
+            let flag_sum_next = meta.query_advice(self.advice[2], Rotation::next());
+            let selector_sum_next = meta.query_advice(self.advice[3], Rotation::next());
+            let one = Expression::Constant(pallas::Base::one());
+
+            Constraints::with_selector(
+                q_method_dispatch,
+                [
+                    ("flag is boolean", flag.clone() * (one - flag.clone())),
+                    ("flag sum", flag_sum_next - flag_sum - flag.clone()),
+                    (
+                        "selector sum",
+                        selector_sum_next - selector_sum - flag * allowed,
+                    ),
+                ],
+            )
+        });
+    }
+
+    /// Returns the flags of the allowed methods, one row per method.
+    pub fn assign_region(
+        &self,
+        selector: &AssignedCell<pallas::Base, pallas::Base>,
+        allowed: &[AssignedCell<pallas::Base, pallas::Base>],
+        offset: usize,
+        region: &mut Region<'_, pallas::Base>,
+    ) -> Result<Vec<AssignedCell<pallas::Base, pallas::Base>>, Error> {
+        let mut flag_sum = region.assign_advice_from_constant(
+            || "initial flag sum",
+            self.advice[2],
+            offset,
+            pallas::Base::zero(),
+        )?;
+        let mut selector_sum = region.assign_advice_from_constant(
+            || "initial selector sum",
+            self.advice[3],
+            offset,
+            pallas::Base::zero(),
+        )?;
+
+        let mut flags = vec![];
+        for (i, allowed) in allowed.iter().enumerate() {
+            let row = offset + i;
+            // Enable `q_method_dispatch` selector
+            self.q_method_dispatch.enable(region, row)?;
+
+            let flag_value = selector.value().zip(allowed.value()).map(|(s, a)| {
+                if s == a {
+                    pallas::Base::one()
+                } else {
+                    pallas::Base::zero()
+                }
+            });
+            let flag = region.assign_advice(|| "flag", self.advice[0], row, || flag_value)?;
+            allowed.copy_advice(|| "allowed", region, self.advice[1], row)?;
+            flag_sum = region.assign_advice(
+                || "flag sum",
+                self.advice[2],
+                row + 1,
+                || flag_sum.value().copied() + flag_value,
+            )?;
+            selector_sum = region.assign_advice(
+                || "selector sum",
+                self.advice[3],
+                row + 1,
+                || selector_sum.value().copied() + flag_value * allowed.value().copied(),
+            )?;
+            flags.push(flag);
+        }
+
+        // Exactly one allowed method is selected and it's the called one
+        region.constrain_constant(flag_sum.cell(), pallas::Base::one())?;
+        region.constrain_equal(selector_sum.cell(), selector.cell())?;
+
+        Ok(flags)
+    }
+}
+
+#[test]
+fn test_method_dispatch_gadget() {
+    use crate::circuit::gadgets::gadget_test_harness;
+    use crate::method_call::method_selector;
+    use halo2_gadgets::poseidon::{primitives as poseidon, Pow5Chip as PoseidonChip};
+    use halo2_proofs::dev::MockProver;
+
+    gadget_test_harness!(
+        MethodDispatchCircuit { call: MethodCall, value: pallas::Base, allowed: [pallas::Base; 3] },
+        advices: 4,
+        config: (MethodDispatchConfig, PoseidonConfig<pallas::Base, 3, 2>),
+        configure: |meta, advices| {
+            let rc_a = [(); 3].map(|_| meta.fixed_column());
+            let rc_b = [(); 3].map(|_| meta.fixed_column());
+            let poseidon_config = PoseidonChip::configure::<poseidon::P128Pow5T3>(
+                meta,
+                advices[0..3].try_into().unwrap(),
+                advices[3],
+                rc_a,
+                rc_b,
+            );
+            (MethodDispatchConfig::configure(meta, advices), poseidon_config)
+        },
+        synthesize: |circuit, config, layouter, advices, instance| {
+            let (dispatch_config, poseidon_config) = config;
+            let value = assign_free_advice(layouter.namespace(|| "value"), advices[0], Value::known(circuit.value))?;
+            let (selector, _) = method_call_gadget(
+                layouter.namespace(|| "method call"),
+                advices[0],
+                poseidon_config,
+                Value::known(circuit.call),
+                &value,
+            )?;
+            let mut allowed = vec![];
+            for method in circuit.allowed {
+                allowed.push(assign_free_constant(layouter.namespace(|| "allowed"), advices[1], method)?);
+            }
+            let flags = layouter.assign_region(
+                || "method dispatch",
+                |mut region| dispatch_config.assign_region(&selector, &allowed, 0, &mut region),
+            )?;
+            for (i, flag) in flags.iter().enumerate() {
+                layouter.constrain_instance(flag.cell(), instance, i)?;
+            }
+            Ok(())
+        }
+    );
+
+    let allowed = ["transfer", "burn", "mint"].map(method_selector);
+    let check = |call: MethodCall, value: pallas::Base, flags: [u64; 3]| {
+        let circuit = MethodDispatchCircuit {
+            call,
+            value,
+            allowed,
+        };
+        let instance = flags.map(pallas::Base::from).to_vec();
+        MockProver::run(8, &circuit, vec![instance])
+            .unwrap()
+            .verify()
+    };
+
+    let burn = MethodCall::new("burn", pallas::Base::from(42u64));
+    assert_eq!(burn.dispatch(&allowed), Some(1));
+    assert_eq!(check(burn, burn.encode_value(), [0, 1, 0]), Ok(()));
+    // The flags must select the called method
+    assert!(check(burn, burn.encode_value(), [1, 0, 0]).is_err());
+    // The value must encode the call
+    assert!(check(burn, pallas::Base::from(42u64), [0, 1, 0]).is_err());
+    // Methods outside the allowed ones are rejected
+    let split = MethodCall::new("split", pallas::Base::from(42u64));
+    assert_eq!(split.dispatch(&allowed), None);
+    assert!(check(split, split.encode_value(), [0, 0, 0]).is_err());
+}
@@ -0,0 +1,65 @@
+//! Gadgets for logics that need to prove the knowledge of the nk behind the npk
+//! of a resource, e.g. of a resource referenced but not consumed by the
+//! transaction. The native counterparts are `NullifierKeyContainer::derive_npk`
+//! and `NullifierKeyContainer::possesses`.
+//!
+//! A logic proving the possession of the nk of `npk`:
+//! ```ignore
+//! let nk = nk_possession_gadget(
+//!     layouter.namespace(|| "nk possession"),
+//!     config.advices[0],
+//!     config.poseidon_config.clone(),
+//!     Value::known(nk),
+//!     &npk,
+//! )?;
+//! ```
+use super::{assign_free_advice, assign_free_constant, poseidon_hash::poseidon_hash_gadget};
+use halo2_gadgets::poseidon::Pow5Config as PoseidonConfig;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, Error},
+};
+use pasta_curves::pallas;
+
+/// Derives npk = Com_r(nk, zero).
+pub fn derive_npk_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+    nk: AssignedCell<pallas::Base, pallas::Base>,
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+    let zero_constant = assign_free_constant(
+        layouter.namespace(|| "constant zero"),
+        advice,
+        pallas::Base::zero(),
+    )?;
+
+    poseidon_hash_gadget(
+        poseidon_config,
+        layouter.namespace(|| "npk encoding"),
+        [nk, zero_constant],
+    )
+}
+
+/// Witnesses nk and constrains it to derive `npk`. Returns the nk variable, so
+/// that the logic can use it further, e.g. to derive nullifiers.
+pub fn nk_possession_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+    nk: Value<pallas::Base>,
+    npk: &AssignedCell<pallas::Base, pallas::Base>,
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+    let nk_var = assign_free_advice(layouter.namespace(|| "witness nk"), advice, nk)?;
+    let derived_npk = derive_npk_gadget(
+        layouter.namespace(|| "derive npk"),
+        advice,
+        poseidon_config,
+        nk_var.clone(),
+    )?;
+    layouter.assign_region(
+        || "constrain npk",
+        |mut region| region.constrain_equal(derived_npk.cell(), npk.cell()),
+    )?;
+    Ok(nk_var)
+}
@@ -0,0 +1,58 @@
+/// Schnorr signatures over Pallas, verified in circuit.
+///
+/// A signature (R, s) of the public key P on the message m is valid if
+/// s*G = R + Hash(R||P||m)*P, where G is the Pallas generator and Hash is
+/// Poseidon, the hash being reduced to a scalar.
+use crate::circuit::gadgets::poseidon_hash::poseidon_hash_gadget;
+use crate::constant::{TaigaFixedBases, TaigaFixedBasesFull};
+use halo2_gadgets::{
+    ecc::{chip::EccChip, FixedPoint, NonIdentityPoint, ScalarFixed, ScalarVar},
+    poseidon::Pow5Config as PoseidonConfig,
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::Error,
+};
+use pasta_curves::pallas;
+
+/// Constrains (r, s) to be a valid signature of pk on the message.
+pub fn schnorr_verify_gadget(
+    ecc_chip: EccChip<TaigaFixedBases>,
+    poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+    mut layouter: impl Layouter<pallas::Base>,
+    pk: &NonIdentityPoint<pallas::Affine, EccChip<TaigaFixedBases>>,
+    r: &NonIdentityPoint<pallas::Affine, EccChip<TaigaFixedBases>>,
+    s: Value<pallas::Scalar>,
+    message: AssignedCell<pallas::Base, pallas::Base>,
+) -> Result<(), Error> {
+    let s_scalar = ScalarFixed::new(ecc_chip.clone(), layouter.namespace(|| "witness s"), s)?;
+
+    // s*G
+    let generator = FixedPoint::from_inner(ecc_chip.clone(), TaigaFixedBasesFull::BaseGenerator);
+    let (s_g, _) = generator.mul(layouter.namespace(|| "s_scalar * generator"), &s_scalar)?;
+
+    // Hash(r||P||m)
+    let h_scalar = {
+        let h = poseidon_hash_gadget(
+            poseidon_config,
+            layouter.namespace(|| "Poseidon_hash(r, P, m)"),
+            [
+                r.inner().x(),
+                r.inner().y(),
+                pk.inner().x(),
+                pk.inner().y(),
+                message,
+            ],
+        )?;
+
+        ScalarVar::from_base(ecc_chip, layouter.namespace(|| "ScalarVar from_base"), &h)?
+    };
+
+    // Hash(r||P||m)*P
+    let (h_p, _) = pk.mul(layouter.namespace(|| "hP"), h_scalar)?;
+
+    // R + Hash(r||P||m)*P
+    let rhs = r.add(layouter.namespace(|| "R + Hash(r||P||m)*P"), &h_p)?;
+
+    s_g.constrain_equal(layouter.namespace(|| "s*G = R + Hash(r||P||m)*P"), &rhs)
+}
@@ -108,3 +108,35 @@ impl<F: Field> SubInstructions<F> for SubChip<F> {
         )
     }
 }
+
+#[test]
+fn test_sub_chip() {
+    use crate::circuit::gadgets::{assign_free_advice, gadget_test_harness};
+    use halo2_proofs::{circuit::Value, dev::MockProver};
+    use pasta_curves::pallas;
+
+    gadget_test_harness!(
+        SubCircuit { a: pallas::Base, b: pallas::Base },
+        advices: 2,
+        config: SubConfig,
+        configure: |meta, advices| SubChip::<pallas::Base>::configure(meta, advices),
+        synthesize: |circuit, config, layouter, advices, instance| {
+            let a = assign_free_advice(layouter.namespace(|| "a"), advices[0], Value::known(circuit.a))?;
+            let b = assign_free_advice(layouter.namespace(|| "b"), advices[1], Value::known(circuit.b))?;
+            let sub_chip = SubChip::<pallas::Base>::construct(config, ());
+            let c = sub_chip.sub(layouter.namespace(|| "a - b"), &a, &b)?;
+            layouter.constrain_instance(c.cell(), instance, 0)
+        }
+    );
+
+    // 3 - 5 wraps around the field modulus
+    let circuit = SubCircuit {
+        a: pallas::Base::from(3u64),
+        b: pallas::Base::from(5u64),
+    };
+    let prover = MockProver::run(5, &circuit, vec![vec![-pallas::Base::from(2u64)]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let prover = MockProver::run(5, &circuit, vec![vec![pallas::Base::from(2u64)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -1,17 +1,20 @@
 use crate::circuit::{
     gadgets::{
         assign_free_advice, assign_free_constant, conditional_select::ConditionalSelectConfig,
-        poseidon_hash::poseidon_hash_gadget,
+        nullifier_key::derive_npk_gadget, poseidon_hash::poseidon_hash_gadget,
     },
     hash_to_curve::{hash_to_curve_circuit, HashToCurveConfig},
     merkle_circuit::{merkle_poseidon_gadget, MerklePoseidonChip},
     resource_commitment::{resource_commit, ResourceCommitChip},
-    resource_logic_circuit::{InputResourceVariables, ResourceStatus, ResourceVariables},
+    resource_logic_circuit::{
+        InputResourceVariables, ReadResourceStatus, ResourceStatus, ResourceVariables,
+    },
 };
 use crate::constant::{
     TaigaFixedBases, TaigaFixedBasesFull, POSEIDON_TO_CURVE_INPUT_LEN,
     PRF_EXPAND_PERSONALIZATION_TO_FIELD, PRF_EXPAND_PSI, PRF_EXPAND_RCM,
 };
+use crate::quantity::Quantity;
 use crate::resource::Resource;
 use crate::resource_tree::ResourceExistenceWitness;
 use crate::utils::poseidon_to_curve;
@@ -53,8 +56,29 @@ pub fn check_input_resource(
     advices: [Column<Advice>; 10],
     instances: Column<Instance>,
     resource_commit_chip: ResourceCommitChip,
-    input_resource: Resource,
+    input_resource: &Resource,
     nf_row_idx: usize,
+) -> Result<InputResourceVariables, Error> {
+    let input_resource_variables = witness_input_resource(
+        layouter.namespace(|| "witness input resource"),
+        advices,
+        resource_commit_chip,
+        input_resource,
+    )?;
+
+    // Public nullifier
+    layouter.constrain_instance(input_resource_variables.nf.cell(), instances, nf_row_idx)?;
+
+    Ok(input_resource_variables)
+}
+
+// Witness the input resource and derive its commitment and nullifier, without
+// publishing them
+pub fn witness_input_resource(
+    mut layouter: impl Layouter<pallas::Base>,
+    advices: [Column<Advice>; 10],
+    resource_commit_chip: ResourceCommitChip,
+    input_resource: &Resource,
 ) -> Result<InputResourceVariables, Error> {
     // Witness nk
     let nk = input_resource.get_nk().unwrap();
@@ -64,17 +88,12 @@ pub fn check_input_resource(
         Value::known(nk),
     )?;
 
-    let zero_constant = assign_free_constant(
-        layouter.namespace(|| "constant zero"),
-        advices[0],
-        pallas::Base::zero(),
-    )?;
-
     // npk = Com_r(nk, zero)
-    let npk = poseidon_hash_gadget(
+    let npk = derive_npk_gadget(
+        layouter.namespace(|| "derive npk"),
+        advices[0],
         resource_commit_chip.get_poseidon_config(),
-        layouter.namespace(|| "npk encoding"),
-        [nk_var.clone(), zero_constant],
+        nk_var.clone(),
     )?;
 
     // Witness value
@@ -116,7 +135,7 @@ pub fn check_input_resource(
     let rseed = assign_free_advice(
         layouter.namespace(|| "witness rseed"),
         advices[0],
-        Value::known(input_resource.rseed),
+        Value::known(*input_resource.rseed.expose_secret()),
     )?;
 
     // We don't need the constraints on psi and rcm derivation for input resource.
@@ -168,9 +187,6 @@ pub fn check_input_resource(
         cm.clone(),
     )?;
 
-    // Public nullifier
-    layouter.constrain_instance(nf.cell(), instances, nf_row_idx)?;
-
     let resource_variables = ResourceVariables {
         logic,
         quantity,
@@ -195,7 +211,7 @@ pub fn check_output_resource(
     advices: [Column<Advice>; 10],
     instances: Column<Instance>,
     resource_commit_chip: ResourceCommitChip,
-    output_resource: Resource,
+    output_resource: &Resource,
     old_nf: AssignedCell<pallas::Base, pallas::Base>,
     cm_row_idx: usize,
 ) -> Result<ResourceVariables, Error> {
@@ -238,7 +254,7 @@ pub fn check_output_resource(
     let rseed = assign_free_advice(
         layouter.namespace(|| "witness rseed"),
         advices[0],
-        Value::known(output_resource.rseed),
+        Value::known(*output_resource.rseed.expose_secret()),
     )?;
 
     // Witness rcm
@@ -357,17 +373,12 @@ pub fn load_resource(
         Value::known(nk_or_npk),
     )?;
 
-    let zero_constant = assign_free_constant(
-        layouter.namespace(|| "constant zero"),
-        advices[0],
-        pallas::Base::zero(),
-    )?;
-
     // npk = Com_r(nk, zero)
-    let input_npk = poseidon_hash_gadget(
+    let input_npk = derive_npk_gadget(
+        layouter.namespace(|| "derive npk"),
+        advices[0],
         resource_commit_chip.get_poseidon_config(),
-        layouter.namespace(|| "npk encoding"),
-        [nk_or_npk_var.clone(), zero_constant],
+        nk_or_npk_var.clone(),
     )?;
 
     let npk = layouter.assign_region(
@@ -422,7 +433,7 @@ pub fn load_resource(
     let rseed = assign_free_advice(
         layouter.namespace(|| "witness rseed"),
         advices[0],
-        Value::known(resource.rseed),
+        Value::known(*resource.rseed.expose_secret()),
     )?;
 
     // We don't need the constraints on psi and rcm derivation for input resource.
@@ -511,6 +522,59 @@ pub fn load_resource(
     })
 }
 
+// The read tag of a resource, see `Resource::get_read_tag`
+pub fn read_tag_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+    nf: AssignedCell<pallas::Base, pallas::Base>,
+    cm: AssignedCell<pallas::Base, pallas::Base>,
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+    poseidon_hash_gadget(
+        poseidon_config,
+        layouter.namespace(|| "derive read tag"),
+        [nf, cm],
+    )
+}
+
+// Load a resource read by the ptx: its read tag is checked in the resource
+// tree instead of its nullifier or its commitment. Reading takes the nullifier
+// key of the resource.
+pub fn load_read_resource(
+    mut layouter: impl Layouter<pallas::Base>,
+    advices: [Column<Advice>; 10],
+    resource_commit_chip: ResourceCommitChip,
+    merkle_chip: MerklePoseidonChip,
+    resource_witness: &ResourceExistenceWitness,
+) -> Result<ReadResourceStatus, Error> {
+    let read_resource_variables = witness_input_resource(
+        layouter.namespace(|| "witness read resource"),
+        advices,
+        resource_commit_chip.clone(),
+        &resource_witness.get_resource(),
+    )?;
+
+    let read_tag = read_tag_gadget(
+        layouter.namespace(|| "read tag"),
+        resource_commit_chip.get_poseidon_config(),
+        read_resource_variables.nf,
+        read_resource_variables.cm,
+    )?;
+
+    // Check the read tag existence(merkle path)
+    let root = merkle_poseidon_gadget(
+        layouter.namespace(|| "poseidon merkle"),
+        merkle_chip,
+        read_tag.clone(),
+        &resource_witness.get_path(),
+    )?;
+
+    Ok(ReadResourceStatus {
+        resource_merkle_root: root,
+        identity: read_tag,
+        resource: read_resource_variables.resource_variables,
+    })
+}
+
 pub fn derive_kind(
     mut layouter: impl Layouter<pallas::Base>,
     hash_to_curve_config: HashToCurveConfig,
@@ -632,7 +696,7 @@ pub fn compute_delta_commitment(
 fn quantity_range_check<const K: usize>(
     mut layouter: impl Layouter<pallas::Base>,
     lookup_config: &LookupRangeCheckConfig<pallas::Base, K>,
-    quantity: u64,
+    quantity: Quantity,
 ) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
     let zs = lookup_config.witness_check(
         layouter.namespace(|| "6 * K(10) bits range check"),
@@ -1,5 +1,6 @@
-use crate::circuit::gadgets::poseidon_hash::poseidon_hash_gadget;
+use crate::circuit::gadgets::{assign_free_advice, poseidon_hash::poseidon_hash_gadget};
 use crate::merkle_tree::LR;
+use crate::resource_tree::{fold_multiproof, ResourceMultiproof};
 use halo2_gadgets::{
     poseidon::Pow5Config as PoseidonConfig,
     utilities::cond_swap::{CondSwapChip, CondSwapConfig, CondSwapInstructions},
@@ -105,6 +106,45 @@ pub fn merkle_poseidon_gadget(
     Ok(cur)
 }
 
+/// Computes the root of the resource tree from several opened leaves, given
+/// in the order of `multiproof.positions()`. The nodes shared by the openings
+/// are hashed once. The positions of the leaves are part of the circuit
+/// layout, so the logics using the gadget must fix them. The synthesis fails
+/// if the positions are not strictly increasing or out of the tree, see
+/// [`ResourceMultiproof`].
+pub fn merkle_poseidon_multiproof_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    chip: MerklePoseidonChip,
+    leaves: &[AssignedCell<pallas::Base, pallas::Base>],
+    multiproof: &ResourceMultiproof,
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+    let mut siblings = multiproof
+        .siblings()
+        .iter()
+        .map(|sibling| {
+            assign_free_advice(
+                layouter.namespace(|| "multiproof sibling"),
+                chip.config().advices[0],
+                Value::known(*sibling),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter();
+    fold_multiproof(
+        multiproof.positions(),
+        leaves.to_vec(),
+        |_, _| siblings.next().ok_or(Error::Synthesis),
+        |left, right| {
+            poseidon_hash_gadget(
+                chip.config().poseidon_config.clone(),
+                layouter.namespace(|| "multiproof poseidon hash"),
+                [left, right],
+            )
+        },
+        || Error::Synthesis,
+    )
+}
+
 #[test]
 fn test_halo2_merkle_circuit() {
     use crate::circuit::gadgets::assign_free_advice;
@@ -212,3 +252,74 @@ fn test_halo2_merkle_circuit() {
     let prover = MockProver::run(11, &circuit, vec![]).unwrap();
     assert_eq!(prover.verify(), Ok(()))
 }
+
+#[test]
+fn test_halo2_merkle_multiproof_circuit() {
+    use crate::circuit::gadgets::gadget_test_harness;
+    use crate::resource_tree::ResourceMerkleTreeLeaves;
+    use halo2_gadgets::poseidon::{primitives as poseidon, Pow5Chip as PoseidonChip};
+    use halo2_proofs::{arithmetic::Field, dev::MockProver};
+    use rand::rngs::OsRng;
+
+    gadget_test_harness!(
+        MultiproofCircuit { leaves: Vec<pallas::Base>, multiproof: ResourceMultiproof },
+        advices: 5,
+        config: MerklePoseidonConfig,
+        configure: |meta, advices| {
+            let state = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+            let partial_sbox = meta.advice_column();
+            let rc_a = [meta.fixed_column(), meta.fixed_column(), meta.fixed_column()];
+            let rc_b = [meta.fixed_column(), meta.fixed_column(), meta.fixed_column()];
+            meta.enable_constant(rc_b[0]);
+            let poseidon_config = PoseidonChip::configure::<poseidon::P128Pow5T3>(
+                meta,
+                state,
+                partial_sbox,
+                rc_a,
+                rc_b,
+            );
+            MerklePoseidonChip::configure(meta, advices, poseidon_config)
+        },
+        synthesize: |circuit, config, layouter, advices, instance| {
+            let leaves = circuit
+                .leaves
+                .iter()
+                .map(|leaf| {
+                    assign_free_advice(
+                        layouter.namespace(|| "leaf"),
+                        advices[0],
+                        Value::known(*leaf),
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let root = merkle_poseidon_multiproof_gadget(
+                layouter.namespace(|| "poseidon merkle multiproof"),
+                MerklePoseidonChip::construct(config),
+                &leaves,
+                &circuit.multiproof,
+            )?;
+            layouter.constrain_instance(root.cell(), instance, 0)
+        }
+    );
+
+    let mut rng = OsRng;
+    let tree_leaves: Vec<pallas::Base> = (0..8).map(|_| pallas::Base::random(&mut rng)).collect();
+    let tree = ResourceMerkleTreeLeaves::new(tree_leaves.clone());
+    let leaves = vec![tree_leaves[2], tree_leaves[3], tree_leaves[6]];
+    let multiproof = tree.generate_multiproof(&leaves).unwrap();
+    let circuit = MultiproofCircuit { leaves, multiproof };
+
+    let prover = MockProver::run(11, &circuit, vec![vec![tree.root()]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let prover = MockProver::run(11, &circuit, vec![vec![pallas::Base::one()]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // A duplicate position doesn't synthesize
+    let multiproof = ResourceMultiproof::new(vec![2, 2, 6], circuit.multiproof.siblings().to_vec());
+    let circuit = MultiproofCircuit {
+        leaves: circuit.leaves,
+        multiproof,
+    };
+    assert!(MockProver::run(11, &circuit, vec![vec![tree.root()]]).is_err());
+}
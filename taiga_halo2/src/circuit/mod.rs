@@ -1,7 +1,14 @@
+pub mod auxiliary_delta;
 pub mod compliance_circuit;
+pub mod cost;
 pub mod gadgets;
 pub mod integrity;
 pub mod merkle_circuit;
+#[cfg(feature = "noir")]
+pub mod noir_resource_logic;
+pub mod read_circuit;
+pub mod reserve_circuit;
+pub mod spent_circuit;
 #[macro_use]
 pub mod resource_logic_circuit;
 pub mod blake2s;
@@ -11,4 +18,11 @@ pub mod resource_commitment;
 pub mod resource_encryption_circuit;
 pub mod resource_logic_bytecode;
 pub mod resource_logic_examples;
+pub mod resource_non_membership;
+#[cfg(feature = "vamp-ir")]
+pub mod vamp_ir_resource_logic;
+#[cfg(feature = "vamp-ir")]
 mod vamp_ir_utils;
+#[cfg(feature = "wasm")]
+pub mod wasm_resource_logic;
+pub mod zkvm_receipt;
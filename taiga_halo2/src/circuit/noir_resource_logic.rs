@@ -0,0 +1,330 @@
+//! Resource logics written in Noir, loaded from the ACIR artifact of `nargo`.
+//!
+//! The public parameters of the main function, in the order of their
+//! declaration, followed by its return values, are the public inputs of the
+//! logic in the layout of `ResourceLogicPublicInputs`: the merkle root first,
+//! the self resource id second, and so on. The remaining public inputs are
+//! padded.
+//!
+//! The `AssertZero` opcodes are adapted to the standard plonk gates of
+//! `WasmConstraintSystem`, each opcode with at most one product and three
+//! distinct witnesses. Brillig calls only compute witnesses and are skipped,
+//! the other opcodes are not supported yet.
+//!
+//! ACIR works over the BN254 scalar field, which is smaller than the pallas
+//! base field. Field elements are mapped to pallas by their signed
+//! representative, e.g. BN254 -1 is pallas -1, and the adapter rejects the
+//! gates whose terms, evaluated on the witness, may exceed half of the BN254
+//! modulus. The gates then hold over the integers, so they hold over both
+//! fields. The bound is only checked on the witness of the prover: a logic
+//! that relies on the wrap-around of BN254 is rejected, and one whose values a
+//! malicious prover could make wrap must range check them.
+use crate::{
+    circuit::{
+        resource_logic_circuit::{
+            ResourceLogicPublicInputs, ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait,
+        },
+        wasm_resource_logic::{WasmConstraintSystem, WasmGate, WasmResourceLogicCircuit},
+    },
+    error::TransactionError,
+    params::ProvingContext,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+};
+use acir::{
+    circuit::{Circuit, Opcode, Program},
+    native_types::{Expression, Witness, WitnessMap, WitnessStack},
+    FieldElement,
+};
+use ff::PrimeField;
+use pasta_curves::pallas;
+use rand::RngCore;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+#[derive(Debug)]
+pub enum NoirCircuitError {
+    /// The artifact or the witness can't be deserialized.
+    Deserialization(String),
+    /// The circuit uses an opcode the adapter doesn't support.
+    UnsupportedOpcode(String),
+    /// The witness misses the value of a witness index.
+    MissingWitness(u32),
+    /// The adapted circuit is not a valid resource logic.
+    InvalidCircuit(String),
+    /// A gate may exceed the BN254 field, it doesn't mean the same over pallas.
+    FieldOverflow(String),
+}
+
+impl Display for NoirCircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoirCircuitError::Deserialization(e) => write!(f, "ACIR deserialization error: {e}"),
+            NoirCircuitError::UnsupportedOpcode(e) => write!(f, "Unsupported ACIR opcode: {e}"),
+            NoirCircuitError::MissingWitness(index) => write!(f, "Missing witness {index}"),
+            NoirCircuitError::InvalidCircuit(e) => write!(f, "Invalid ACIR circuit: {e}"),
+            NoirCircuitError::FieldOverflow(e) => write!(f, "ACIR gate exceeding BN254: {e}"),
+        }
+    }
+}
+
+impl From<NoirCircuitError> for TransactionError {
+    fn from(e: NoirCircuitError) -> Self {
+        TransactionError::InvalidNoirResourceLogic(e.to_string())
+    }
+}
+
+/// Maps a BN254 element to pallas by its signed representative.
+fn to_pallas(value: &FieldElement) -> pallas::Base {
+    let neg = -*value;
+    let (abs, is_neg) = if neg.num_bits() < value.num_bits() {
+        (neg, true)
+    } else {
+        (*value, false)
+    };
+    let mut repr = [0u8; 32];
+    let bytes = abs.to_be_bytes();
+    repr[..bytes.len()].copy_from_slice(&bytes);
+    repr[..bytes.len()].reverse();
+    // BN254 elements are smaller than the pallas modulus
+    let abs = pallas::Base::from_repr(repr).unwrap();
+    if is_neg {
+        -abs
+    } else {
+        abs
+    }
+}
+
+// The terms of a gate sum to less than 2^252, below half of the BN254 modulus
+const MAX_GATE_BITS: u32 = 252;
+
+// The bits of the magnitude of the signed representative
+fn signed_bits(value: &FieldElement) -> u32 {
+    value.num_bits().min((-*value).num_bits())
+}
+
+// Checks that the terms of the gate, evaluated on the witness, sum to less than
+// 2^MAX_GATE_BITS over the integers
+fn check_gate_bound(expr: &Expression, witness: &WitnessMap) -> Result<(), NoirCircuitError> {
+    let bits = |w: &Witness| {
+        witness
+            .get(w)
+            .map(signed_bits)
+            .ok_or(NoirCircuitError::MissingWitness(w.witness_index()))
+    };
+    let mut terms = vec![signed_bits(&expr.q_c)];
+    for (q_m, a, b) in expr.mul_terms.iter() {
+        terms.push(signed_bits(q_m) + bits(a)? + bits(b)?);
+    }
+    for (coeff, w) in expr.linear_combinations.iter() {
+        terms.push(signed_bits(coeff) + bits(w)?);
+    }
+    let max_bits = terms.iter().copied().max().unwrap_or_default();
+    let count_bits = usize::BITS - (terms.len() - 1).leading_zeros();
+    if max_bits + count_bits > MAX_GATE_BITS {
+        return Err(NoirCircuitError::FieldOverflow(format!("{expr:?}")));
+    }
+    Ok(())
+}
+
+/// The witnesses of an ACIR circuit, indexed as the variables of the
+/// constraint system.
+struct Variables<'a> {
+    witness: &'a WitnessMap,
+    indices: BTreeMap<Witness, u32>,
+    values: Vec<pallas::Base>,
+}
+
+impl Variables<'_> {
+    fn get(&mut self, witness: Witness) -> Result<u32, NoirCircuitError> {
+        if let Some(index) = self.indices.get(&witness) {
+            return Ok(*index);
+        }
+        let value = self
+            .witness
+            .get(&witness)
+            .ok_or(NoirCircuitError::MissingWitness(witness.witness_index()))?;
+        let index = self.values.len() as u32;
+        self.values.push(to_pallas(value));
+        self.indices.insert(witness, index);
+        Ok(index)
+    }
+}
+
+/// q_l * a + q_r * b + q_o * c + q_m * a * b + q_c = 0
+fn adapt_expression(
+    expr: &Expression,
+    variables: &mut Variables,
+) -> Result<WasmGate, NoirCircuitError> {
+    check_gate_bound(expr, variables.witness)?;
+    let too_wide = || NoirCircuitError::UnsupportedOpcode(format!("{expr:?}"));
+    let mut coeffs = [pallas::Base::zero(); 5];
+    let mut wires: [Option<Witness>; 3] = [None; 3];
+    match expr.mul_terms.as_slice() {
+        [] => {}
+        [(q_m, a, b)] => {
+            coeffs[3] = to_pallas(q_m);
+            wires[0] = Some(*a);
+            wires[1] = Some(*b);
+        }
+        _ => return Err(too_wide()),
+    }
+    for (coeff, witness) in expr.linear_combinations.iter() {
+        let slot = wires
+            .iter()
+            .position(|wire| *wire == Some(*witness))
+            .or_else(|| wires.iter().position(|wire| wire.is_none()))
+            .ok_or_else(too_wide)?;
+        wires[slot] = Some(*witness);
+        coeffs[slot] += to_pallas(coeff);
+    }
+    coeffs[4] = to_pallas(&expr.q_c);
+
+    let mut gate_wires = [0u32; 3];
+    for (gate_wire, wire) in gate_wires.iter_mut().zip(wires) {
+        // The unused wires have zero coefficients, any variable fits
+        *gate_wire = match wire {
+            Some(witness) => variables.get(witness)?,
+            None => 0,
+        };
+    }
+    Ok(WasmGate {
+        coeffs,
+        wires: gate_wires,
+    })
+}
+
+/// A resource logic adapted from an ACIR circuit. It's proven as the
+/// `WasmResourceLogicCircuit` of the adapted constraint system.
+#[derive(Clone, Debug, Default)]
+pub struct NoirResourceLogicCircuit {
+    pub circuit: WasmResourceLogicCircuit,
+}
+
+impl NoirResourceLogicCircuit {
+    /// Loads the ACIR program of a `nargo` artifact and the witness stack
+    /// produced by `nargo execute`, both serialized.
+    pub fn from_artifact(program: &[u8], witness: &[u8]) -> Result<Self, NoirCircuitError> {
+        let program = Program::deserialize_program(program)
+            .map_err(|e| NoirCircuitError::Deserialization(e.to_string()))?;
+        let mut witness_stack = WitnessStack::try_from(witness)
+            .map_err(|e| NoirCircuitError::Deserialization(e.to_string()))?;
+        let witness = witness_stack
+            .pop()
+            .ok_or_else(|| NoirCircuitError::Deserialization("empty witness stack".to_string()))?
+            .witness;
+        match program.functions.as_slice() {
+            [main] => Self::from_acir(main, &witness),
+            _ => Err(NoirCircuitError::UnsupportedOpcode(
+                "calls to other functions".to_string(),
+            )),
+        }
+    }
+
+    pub fn from_acir(circuit: &Circuit, witness: &WitnessMap) -> Result<Self, NoirCircuitError> {
+        let mut variables = Variables {
+            witness,
+            indices: BTreeMap::new(),
+            values: vec![],
+        };
+        let mut gates = vec![];
+        for opcode in circuit.opcodes.iter() {
+            match opcode {
+                Opcode::AssertZero(expr) => gates.push(adapt_expression(expr, &mut variables)?),
+                Opcode::BrilligCall { .. } => {}
+                _ => return Err(NoirCircuitError::UnsupportedOpcode(format!("{opcode:?}"))),
+            }
+        }
+
+        let public_inputs = circuit
+            .public_parameters
+            .0
+            .iter()
+            .chain(circuit.return_values.0.iter())
+            .map(|witness| variables.get(*witness))
+            .collect::<Result<Vec<u32>, NoirCircuitError>>()?;
+        // The unused wires of the gates index the first variable
+        if variables.values.is_empty() {
+            variables.values.push(pallas::Base::zero());
+        }
+
+        let constraint_system = WasmConstraintSystem {
+            values: variables.values,
+            public_inputs,
+            gates,
+        };
+        constraint_system
+            .check()
+            .map_err(|e| NoirCircuitError::InvalidCircuit(e.to_string()))?;
+        Ok(Self {
+            circuit: WasmResourceLogicCircuit { constraint_system },
+        })
+    }
+}
+
+impl ResourceLogicVerifyingInfoTrait for NoirResourceLogicCircuit {
+    fn get_verifying_info_with_context(
+        &self,
+        context: &ProvingContext,
+        rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfo, TransactionError> {
+        self.circuit.get_verifying_info_with_context(context, rng)
+    }
+
+    fn verify_transparently(&self) -> Result<ResourceLogicPublicInputs, TransactionError> {
+        self.circuit.verify_transparently()
+    }
+
+    fn get_resource_logic_vk(&self) -> Result<ResourceLogicVerifyingKey, TransactionError> {
+        self.circuit.get_resource_logic_vk()
+    }
+}
+
+#[test]
+fn test_noir_resource_logic() {
+    use crate::constant::RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX;
+    use acir::circuit::PublicInputs;
+
+    // The basic public inputs, then the custom public input y with x * x - y = 0
+    let public_num = RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX as u32;
+    let x = Witness(public_num);
+    let y = Witness(public_num + 1);
+    let mut circuit = Circuit {
+        current_witness_index: public_num + 1,
+        opcodes: vec![Opcode::AssertZero(Expression {
+            mul_terms: vec![(FieldElement::one(), x, x)],
+            linear_combinations: vec![(-FieldElement::one(), y)],
+            q_c: FieldElement::zero(),
+        })],
+        ..Circuit::default()
+    };
+    circuit.public_parameters = PublicInputs((0..public_num).map(Witness).collect());
+    circuit.return_values = PublicInputs([y].into());
+
+    let mut witness = WitnessMap::new();
+    for i in 0..public_num {
+        witness.insert(Witness(i), FieldElement::from(i as u128 + 1));
+    }
+    witness.insert(x, FieldElement::from(3u128));
+    // A missing witness is rejected
+    assert!(matches!(
+        NoirResourceLogicCircuit::from_acir(&circuit, &witness),
+        Err(NoirCircuitError::MissingWitness(_))
+    ));
+    witness.insert(y, FieldElement::from(9u128));
+    let logic = NoirResourceLogicCircuit::from_acir(&circuit, &witness).unwrap();
+    let public_inputs = logic.verify_transparently().unwrap();
+    assert_eq!(
+        public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX),
+        pallas::Base::from(9)
+    );
+    assert_eq!(to_pallas(&-FieldElement::one()), -pallas::Base::one());
+
+    // A product wrapping around BN254 is rejected
+    let big = FieldElement::from(1u128 << 127);
+    witness.insert(x, big);
+    witness.insert(y, big * big);
+    assert!(matches!(
+        NoirResourceLogicCircuit::from_acir(&circuit, &witness),
+        Err(NoirCircuitError::FieldOverflow(_))
+    ));
+}
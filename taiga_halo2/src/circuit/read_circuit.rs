@@ -0,0 +1,232 @@
+use crate::circuit::integrity::{read_tag_gadget, witness_input_resource};
+use crate::circuit::merkle_circuit::{
+    merkle_poseidon_gadget, MerklePoseidonChip, MerklePoseidonConfig,
+};
+use crate::circuit::resource_commitment::{ResourceCommitChip, ResourceCommitConfig};
+use crate::constant::{
+    READ_ANCHOR_PUBLIC_INPUT_ROW_IDX, READ_TAG_PUBLIC_INPUT_ROW_IDX, TAIGA_COMMITMENT_TREE_DEPTH,
+};
+use crate::merkle_tree::LR;
+use crate::resource::Resource;
+
+use halo2_gadgets::{
+    poseidon::{primitives as poseidon, Pow5Chip as PoseidonChip},
+    utilities::lookup_range_check::LookupRangeCheckConfig,
+};
+use halo2_proofs::{
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, TableColumn},
+};
+use pasta_curves::pallas;
+
+#[derive(Clone, Debug)]
+pub struct ReadConfig {
+    instances: Column<Instance>,
+    advices: [Column<Advice>; 10],
+    table_idx: TableColumn,
+    merkle_config: MerklePoseidonConfig,
+    resource_commit_config: ResourceCommitConfig,
+}
+
+/// The Read circuit proves that a resource exists in the commitment tree of
+/// the public anchor, without consuming it: neither its nullifier nor its
+/// commitment is published. The public read tag identifies the resource in
+/// the resource tree of the ptx, see `Resource::get_read_tag`.
+///
+/// The membership is all that is proven, a resource spent after the anchor
+/// can still be read against it.
+#[derive(Clone, Debug, Default)]
+pub struct ReadCircuit {
+    /// The read resource
+    pub resource: Resource,
+    /// The authorization path of the read resource
+    pub merkle_path: [(pallas::Base, LR); TAIGA_COMMITMENT_TREE_DEPTH],
+}
+
+impl Circuit<pallas::Base> for ReadCircuit {
+    type Config = ReadConfig;
+    type FloorPlanner = floor_planner::V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let instances = meta.instance_column();
+        meta.enable_equality(instances);
+
+        let advices = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+
+        for advice in advices.iter() {
+            meta.enable_equality(*advice);
+        }
+
+        let table_idx = meta.lookup_table_column();
+
+        let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+
+        let lagrange_coeffs = [
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+        ];
+        meta.enable_constant(lagrange_coeffs[0]);
+
+        let poseidon_config = PoseidonChip::configure::<poseidon::P128Pow5T3>(
+            meta,
+            advices[6..9].try_into().unwrap(),
+            advices[5],
+            lagrange_coeffs[2..5].try_into().unwrap(),
+            lagrange_coeffs[5..8].try_into().unwrap(),
+        );
+
+        let merkle_config = MerklePoseidonChip::configure(
+            meta,
+            advices[..5].try_into().unwrap(),
+            poseidon_config.clone(),
+        );
+
+        let resource_commit_config = ResourceCommitChip::configure(
+            meta,
+            advices[0..3].try_into().unwrap(),
+            poseidon_config,
+            range_check,
+        );
+
+        Self::Config {
+            instances,
+            advices,
+            table_idx,
+            merkle_config,
+            resource_commit_config,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "table_idx",
+            |mut table| {
+                for index in 0..(1 << 10) {
+                    table.assign_cell(
+                        || "table_idx",
+                        config.table_idx,
+                        index,
+                        || Value::known(pallas::Base::from(index as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let resource_commit_chip = ResourceCommitChip::construct(config.resource_commit_config);
+
+        // Check the resource commitment and derive the nullifier, both stay
+        // private
+        let resource_variables = witness_input_resource(
+            layouter.namespace(|| "check read resource"),
+            config.advices,
+            resource_commit_chip.clone(),
+            &self.resource,
+        )?;
+
+        // Check the merkle tree path validity and publicize the root. A read
+        // resource must exist, the ephemeral resources can't be read.
+        let merkle_chip = MerklePoseidonChip::construct(config.merkle_config);
+        let root = merkle_poseidon_gadget(
+            layouter.namespace(|| "poseidon merkle"),
+            merkle_chip,
+            resource_variables.cm.clone(),
+            &self.merkle_path,
+        )?;
+        layouter.constrain_instance(
+            root.cell(),
+            config.instances,
+            READ_ANCHOR_PUBLIC_INPUT_ROW_IDX,
+        )?;
+
+        // Publicize the read tag
+        let read_tag = read_tag_gadget(
+            layouter.namespace(|| "read tag"),
+            resource_commit_chip.get_poseidon_config(),
+            resource_variables.nf,
+            resource_variables.cm,
+        )?;
+        layouter.constrain_instance(
+            read_tag.cell(),
+            config.instances,
+            READ_TAG_PUBLIC_INPUT_ROW_IDX,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_halo2_read_circuit() {
+    use crate::constant::READ_CIRCUIT_PARAMS_SIZE;
+    use crate::merkle_tree::MerklePath;
+    use crate::resource::tests::random_resource;
+    use halo2_proofs::dev::MockProver;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let resource = random_resource(&mut rng);
+    let merkle_path = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
+    let anchor = resource.calculate_root(&merkle_path).inner();
+    let read_tag = resource.get_read_tag().unwrap();
+    let circuit = ReadCircuit {
+        resource: resource.clone(),
+        merkle_path: merkle_path.inner().try_into().unwrap(),
+    };
+
+    let prover = MockProver::<pallas::Base>::run(
+        READ_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![vec![read_tag, anchor]],
+    )
+    .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // The read doesn't reveal the nullifier nor the commitment
+    for identity in [
+        resource.get_nf().unwrap().inner(),
+        resource.commitment().inner(),
+    ] {
+        let prover = MockProver::<pallas::Base>::run(
+            READ_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![vec![identity, anchor]],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    // The resource must be in the tree of the anchor
+    let prover = MockProver::<pallas::Base>::run(
+        READ_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![vec![read_tag, pallas::Base::one()]],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,393 @@
+use crate::circuit::gadgets::assign_free_constant;
+use crate::circuit::integrity::check_input_resource;
+use crate::circuit::merkle_circuit::{
+    merkle_poseidon_gadget, MerklePoseidonChip, MerklePoseidonConfig,
+};
+use crate::circuit::resource_commitment::{ResourceCommitChip, ResourceCommitConfig};
+use crate::constant::{
+    RESERVE_ANCHOR_PUBLIC_INPUT_ROW_IDX, RESERVE_CIRCUIT_RESOURCE_NUM,
+    RESERVE_LABEL_PUBLIC_INPUT_ROW_IDX, RESERVE_LOGIC_PUBLIC_INPUT_ROW_IDX,
+    RESERVE_MIN_AMOUNT_PUBLIC_INPUT_ROW_IDX, RESERVE_NF_PUBLIC_INPUT_BEGIN_ROW_IDX,
+    TAIGA_COMMITMENT_TREE_DEPTH,
+};
+use crate::merkle_tree::LR;
+use crate::resource::Resource;
+
+use halo2_gadgets::{
+    poseidon::{primitives as poseidon, Pow5Chip as PoseidonChip},
+    utilities::lookup_range_check::LookupRangeCheckConfig,
+};
+use halo2_proofs::{
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Constraints, Error, Expression, Instance,
+        Selector, TableColumn,
+    },
+    poly::Rotation,
+};
+use pasta_curves::pallas;
+
+// The accumulated quantity is range checked in 7 * K(10) bits, which is enough
+// for the sum of RESERVE_CIRCUIT_RESOURCE_NUM u64 quantities.
+const RESERVE_SURPLUS_WORDS_NUM: usize = 7;
+
+#[derive(Clone, Debug)]
+pub struct ReserveConfig {
+    instances: Column<Instance>,
+    advices: [Column<Advice>; 10],
+    table_idx: TableColumn,
+    merkle_config: MerklePoseidonConfig,
+    resource_commit_config: ResourceCommitConfig,
+    lookup_config: LookupRangeCheckConfig<pallas::Base, 10>,
+    q_reserve_resource: Selector,
+    q_reserve_surplus: Selector,
+}
+
+/// The Reserve circuit proves that the prover controls at least `min_amount`
+/// of a kind across a set of unspent resources, without revealing which.
+///
+/// Every slot is an input resource: the prover knows its nullifier key, its
+/// commitment is in the tree of the public anchor and its nullifier is
+/// published, so that the verifier can check that none of them was spent.
+/// Ephemeral resources are padding and don't contribute to the reserve.
+#[derive(Clone, Debug, Default)]
+pub struct ReserveCircuit {
+    pub resources: [Resource; RESERVE_CIRCUIT_RESOURCE_NUM],
+    pub merkle_paths:
+        [[(pallas::Base, LR); TAIGA_COMMITMENT_TREE_DEPTH]; RESERVE_CIRCUIT_RESOURCE_NUM],
+}
+
+impl Circuit<pallas::Base> for ReserveCircuit {
+    type Config = ReserveConfig;
+    type FloorPlanner = floor_planner::V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let instances = meta.instance_column();
+        meta.enable_equality(instances);
+
+        let advices = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+
+        for advice in advices.iter() {
+            meta.enable_equality(*advice);
+        }
+
+        let table_idx = meta.lookup_table_column();
+
+        let lookup_config = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+
+        let lagrange_coeffs = [
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+        ];
+        meta.enable_constant(lagrange_coeffs[0]);
+
+        let poseidon_config = PoseidonChip::configure::<poseidon::P128Pow5T3>(
+            meta,
+            advices[6..9].try_into().unwrap(),
+            advices[5],
+            lagrange_coeffs[2..5].try_into().unwrap(),
+            lagrange_coeffs[5..8].try_into().unwrap(),
+        );
+
+        let q_reserve_resource = meta.selector();
+        meta.create_gate("reserve resource check", |meta| {
+            let q_reserve_resource = meta.query_selector(q_reserve_resource);
+            let is_ephemeral = meta.query_advice(advices[0], Rotation::cur());
+            let anchor = meta.query_advice(advices[1], Rotation::cur());
+            let root = meta.query_advice(advices[2], Rotation::cur());
+            let logic = meta.query_advice(advices[3], Rotation::cur());
+            let label = meta.query_advice(advices[4], Rotation::cur());
+            let expected_logic = meta.query_advice(advices[5], Rotation::cur());
+            let expected_label = meta.query_advice(advices[6], Rotation::cur());
+            let quantity = meta.query_advice(advices[7], Rotation::cur());
+            let acc_prev = meta.query_advice(advices[8], Rotation::cur());
+            let acc_next = meta.query_advice(advices[9], Rotation::cur());
+            let not_ephemeral = Expression::Constant(pallas::Base::one()) - is_ephemeral;
+
+            Constraints::with_selector(
+                q_reserve_resource,
+                [
+                    (
+                        "is_ephemeral is true, or root = anchor",
+                        not_ephemeral.clone() * (root - anchor),
+                    ),
+                    (
+                        "is_ephemeral is true, or logic = expected_logic",
+                        not_ephemeral.clone() * (logic - expected_logic),
+                    ),
+                    (
+                        "is_ephemeral is true, or label = expected_label",
+                        not_ephemeral.clone() * (label - expected_label),
+                    ),
+                    (
+                        "acc_next = acc_prev + (1 - is_ephemeral) * quantity",
+                        acc_next - acc_prev - not_ephemeral * quantity,
+                    ),
+                ],
+            )
+        });
+
+        let q_reserve_surplus = meta.selector();
+        meta.create_gate("reserve surplus", |meta| {
+            let q_reserve_surplus = meta.query_selector(q_reserve_surplus);
+            let total = meta.query_advice(advices[0], Rotation::cur());
+            let min_amount = meta.query_advice(advices[1], Rotation::cur());
+            let surplus = meta.query_advice(advices[2], Rotation::cur());
+
+            Constraints::with_selector(
+                q_reserve_surplus,
+                [("total = min_amount + surplus", total - min_amount - surplus)],
+            )
+        });
+
+        let merkle_config = MerklePoseidonChip::configure(
+            meta,
+            advices[..5].try_into().unwrap(),
+            poseidon_config.clone(),
+        );
+
+        let resource_commit_config = ResourceCommitChip::configure(
+            meta,
+            advices[0..3].try_into().unwrap(),
+            poseidon_config,
+            lookup_config,
+        );
+
+        Self::Config {
+            instances,
+            advices,
+            table_idx,
+            merkle_config,
+            resource_commit_config,
+            lookup_config,
+            q_reserve_resource,
+            q_reserve_surplus,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "table_idx",
+            |mut table| {
+                for index in 0..(1 << 10) {
+                    table.assign_cell(
+                        || "table_idx",
+                        config.table_idx,
+                        index,
+                        || Value::known(pallas::Base::from(index as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let resource_commit_chip = ResourceCommitChip::construct(config.resource_commit_config);
+
+        let mut acc = assign_free_constant(
+            layouter.namespace(|| "initial reserve"),
+            config.advices[8],
+            pallas::Base::zero(),
+        )?;
+
+        for (i, (resource, merkle_path)) in self
+            .resources
+            .iter()
+            .zip(self.merkle_paths.iter())
+            .enumerate()
+        {
+            // Check the resource commitment and publicize the nullifier
+            let resource_variables = check_input_resource(
+                layouter.namespace(|| "check reserve resource"),
+                config.advices,
+                config.instances,
+                resource_commit_chip.clone(),
+                resource,
+                RESERVE_NF_PUBLIC_INPUT_BEGIN_ROW_IDX + i,
+            )?;
+
+            // Check the merkle tree path validity
+            let merkle_chip = MerklePoseidonChip::construct(config.merkle_config.clone());
+            let root = merkle_poseidon_gadget(
+                layouter.namespace(|| "poseidon merkle"),
+                merkle_chip,
+                resource_variables.cm,
+                merkle_path,
+            )?;
+
+            let quantity = resource.quantity.inner();
+            let is_ephemeral = resource.is_ephemeral;
+            acc = layouter.assign_region(
+                || "reserve resource check",
+                |mut region| {
+                    resource_variables
+                        .resource_variables
+                        .is_ephemeral
+                        .copy_advice(|| "is_ephemeral", &mut region, config.advices[0], 0)?;
+                    region.assign_advice_from_instance(
+                        || "anchor",
+                        config.instances,
+                        RESERVE_ANCHOR_PUBLIC_INPUT_ROW_IDX,
+                        config.advices[1],
+                        0,
+                    )?;
+                    root.copy_advice(|| "root", &mut region, config.advices[2], 0)?;
+                    resource_variables.resource_variables.logic.copy_advice(
+                        || "logic",
+                        &mut region,
+                        config.advices[3],
+                        0,
+                    )?;
+                    resource_variables.resource_variables.label.copy_advice(
+                        || "label",
+                        &mut region,
+                        config.advices[4],
+                        0,
+                    )?;
+                    region.assign_advice_from_instance(
+                        || "expected logic",
+                        config.instances,
+                        RESERVE_LOGIC_PUBLIC_INPUT_ROW_IDX,
+                        config.advices[5],
+                        0,
+                    )?;
+                    region.assign_advice_from_instance(
+                        || "expected label",
+                        config.instances,
+                        RESERVE_LABEL_PUBLIC_INPUT_ROW_IDX,
+                        config.advices[6],
+                        0,
+                    )?;
+                    resource_variables.resource_variables.quantity.copy_advice(
+                        || "quantity",
+                        &mut region,
+                        config.advices[7],
+                        0,
+                    )?;
+                    let acc_prev =
+                        acc.copy_advice(|| "acc_prev", &mut region, config.advices[8], 0)?;
+                    let acc_next = acc_prev.value().map(|acc_prev| {
+                        if is_ephemeral {
+                            *acc_prev
+                        } else {
+                            acc_prev + pallas::Base::from(quantity)
+                        }
+                    });
+                    config.q_reserve_resource.enable(&mut region, 0)?;
+                    region.assign_advice(|| "acc_next", config.advices[9], 0, || acc_next)
+                },
+            )?;
+        }
+
+        // Check total >= min_amount, i.e. the surplus is a small non-negative number
+        let surplus = layouter.assign_region(
+            || "reserve surplus",
+            |mut region| {
+                let total = acc.copy_advice(|| "total", &mut region, config.advices[0], 0)?;
+                let min_amount = region.assign_advice_from_instance(
+                    || "min_amount",
+                    config.instances,
+                    RESERVE_MIN_AMOUNT_PUBLIC_INPUT_ROW_IDX,
+                    config.advices[1],
+                    0,
+                )?;
+                let surplus = total
+                    .value()
+                    .zip(min_amount.value())
+                    .map(|(total, min_amount)| total - min_amount);
+                config.q_reserve_surplus.enable(&mut region, 0)?;
+                region.assign_advice(|| "surplus", config.advices[2], 0, || surplus)
+            },
+        )?;
+        config.lookup_config.copy_check(
+            layouter.namespace(|| "surplus range check"),
+            surplus,
+            RESERVE_SURPLUS_WORDS_NUM,
+            true,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_halo2_reserve_circuit() {
+    use crate::constant::RESERVE_CIRCUIT_PARAMS_SIZE;
+    use crate::merkle_tree::{tests::merkle_paths_from_leaves, Node};
+    use crate::quantity::Quantity;
+    use crate::resource::tests::random_resource;
+    use halo2_proofs::dev::MockProver;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let kind = random_resource(&mut rng).kind;
+    let mut resources = [(); RESERVE_CIRCUIT_RESOURCE_NUM].map(|_| {
+        let mut resource = random_resource(&mut rng);
+        resource.kind = kind;
+        resource.quantity = Quantity::new(10);
+        resource
+    });
+    // The last slot is padding
+    resources[RESERVE_CIRCUIT_RESOURCE_NUM - 1] = Resource::padding_resource(&mut rng);
+
+    let leaves: Vec<Node> = resources.iter().map(Node::from).collect();
+    let paths = merkle_paths_from_leaves(&leaves);
+    let anchor = resources[0].calculate_root(&paths[0]);
+    let merkle_paths = std::array::from_fn(|i| paths[i].inner().try_into().unwrap());
+    let circuit = ReserveCircuit {
+        resources,
+        merkle_paths,
+    };
+
+    let instance = |min_amount: u64| {
+        let mut instance = vec![
+            anchor.inner(),
+            kind.logic,
+            kind.label,
+            pallas::Base::from(min_amount),
+        ];
+        instance.extend(
+            circuit
+                .resources
+                .iter()
+                .map(|resource| resource.get_nf().unwrap().inner()),
+        );
+        instance
+    };
+
+    // Three resources of quantity 10 are counted
+    let prover =
+        MockProver::<pallas::Base>::run(RESERVE_CIRCUIT_PARAMS_SIZE, &circuit, vec![instance(30)])
+            .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // The padding resource doesn't count
+    let prover =
+        MockProver::<pallas::Base>::run(RESERVE_CIRCUIT_PARAMS_SIZE, &circuit, vec![instance(31)])
+            .unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -1,15 +1,17 @@
 use crate::circuit::gadgets::{
     add::{AddChip, AddInstructions},
     assign_free_advice, assign_free_constant,
+    poseidon_hash::poseidon_hash_gadget,
 };
 use crate::constant::{
-    BaseFieldGenerators, TaigaFixedBases, POSEIDON_RATE, POSEIDON_WIDTH,
-    RESOURCE_ENCRYPTION_PLAINTEXT_NUM,
+    BaseFieldGenerators, TaigaFixedBases, MAILBOX_PLAINTEXT_NUM, POSEIDON_RATE, POSEIDON_WIDTH,
+    RESOURCE_ENCRYPTION_TAG, RESOURCE_LOGIC_CIRCUIT_MAILBOX_IDX,
     RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PUBLIC_INPUT_BEGIN_IDX,
 };
+use crate::resource_encryption::EncryptionClass;
 use ff::PrimeField;
 use halo2_gadgets::{
-    ecc::{chip::EccChip, FixedPointBaseField, NonIdentityPoint, ScalarVar},
+    ecc::{chip::EccChip, FixedPointBaseField, NonIdentityPoint, Point, ScalarVar},
     poseidon::{
         primitives::{self as poseidon, Absorbing, ConstantLength},
         PaddedWord, PoseidonInstructions, PoseidonSpongeInstructions, Pow5Chip as PoseidonChip,
@@ -23,6 +25,29 @@ use halo2_proofs::{
 };
 use pasta_curves::pallas;
 
+type EccPoint = Point<pallas::Affine, EccChip<TaigaFixedBases>>;
+type EccNonIdentityPoint = NonIdentityPoint<pallas::Affine, EccChip<TaigaFixedBases>>;
+
+/// The receiver of an encryption in circuit.
+#[derive(Clone, Debug)]
+pub enum ReceiverVar {
+    /// A pk on the fixed generator, the sender pk is sender_sk * generator.
+    Pk(EccNonIdentityPoint),
+    /// A diversified address, see `Address`. The sender pk is
+    /// sender_sk * g_d, and the logic checks that g_d and pk_d are those of
+    /// the receiver, e.g. against `Address::owner`.
+    Address {
+        g_d: EccNonIdentityPoint,
+        pk_d: EccNonIdentityPoint,
+    },
+}
+
+impl From<EccNonIdentityPoint> for ReceiverVar {
+    fn from(rcv_pk: EccNonIdentityPoint) -> Self {
+        ReceiverVar::Pk(rcv_pk)
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn resource_encryption_gadget(
     mut layouter: impl Layouter<pallas::Base>,
@@ -33,29 +58,180 @@ pub fn resource_encryption_gadget(
     ecc_chip: EccChip<TaigaFixedBases>,
     encrypt_nonce: AssignedCell<pallas::Base, pallas::Base>,
     sender_sk: AssignedCell<pallas::Base, pallas::Base>,
-    rcv_pk: NonIdentityPoint<pallas::Affine, EccChip<TaigaFixedBases>>,
+    rcv: impl Into<ReceiverVar>,
     message: &mut Vec<AssignedCell<pallas::Base, pallas::Base>>,
 ) -> Result<(), Error> {
+    let cipher = encrypt_resource_gadget(
+        layouter.namespace(|| "encrypt resource"),
+        advice,
+        poseidon_config,
+        add_chip,
+        ecc_chip,
+        encrypt_nonce,
+        sender_sk,
+        rcv.into(),
+        EncryptionClass::Small,
+        message,
+    )?;
+
+    // Publicize the cipher
+    for (i, ele) in cipher.iter().enumerate() {
+        layouter.constrain_instance(
+            ele.cell(),
+            instances,
+            RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PUBLIC_INPUT_BEGIN_IDX + i,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Encrypts the message padded to `class` and publishes the ciphertext in the
+/// extra instance column `extra_instance` as
+/// RESOURCE_ENCRYPTION_TAG || class || ciphertext || sender_pk, see
+/// `ResourceCiphertext::to_instance`.
+#[allow(clippy::too_many_arguments)]
+pub fn resource_encryption_class_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    extra_instance: Column<Instance>,
+    poseidon_config: PoseidonConfig<pallas::Base, POSEIDON_WIDTH, POSEIDON_RATE>,
+    add_chip: AddChip<pallas::Base>,
+    ecc_chip: EccChip<TaigaFixedBases>,
+    encrypt_nonce: AssignedCell<pallas::Base, pallas::Base>,
+    sender_sk: AssignedCell<pallas::Base, pallas::Base>,
+    rcv: impl Into<ReceiverVar>,
+    class: EncryptionClass,
+    message: &mut Vec<AssignedCell<pallas::Base, pallas::Base>>,
+) -> Result<(), Error> {
+    let tag = assign_free_constant(
+        layouter.namespace(|| "resource encryption tag"),
+        advice,
+        *RESOURCE_ENCRYPTION_TAG,
+    )?;
+    let class_var = assign_free_constant(
+        layouter.namespace(|| "encryption class"),
+        advice,
+        class.to_field(),
+    )?;
+    let cipher = encrypt_resource_gadget(
+        layouter.namespace(|| "encrypt resource"),
+        advice,
+        poseidon_config,
+        add_chip,
+        ecc_chip,
+        encrypt_nonce,
+        sender_sk,
+        rcv.into(),
+        class,
+        message,
+    )?;
+
+    for (i, ele) in [tag, class_var].iter().chain(cipher.iter()).enumerate() {
+        layouter.constrain_instance(ele.cell(), extra_instance, i)?;
+    }
+
+    Ok(())
+}
+
+// Pads the message to the class and encrypts it, returns the cipher followed by
+// the sender's pk
+#[allow(clippy::too_many_arguments)]
+fn encrypt_resource_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    poseidon_config: PoseidonConfig<pallas::Base, POSEIDON_WIDTH, POSEIDON_RATE>,
+    add_chip: AddChip<pallas::Base>,
+    ecc_chip: EccChip<TaigaFixedBases>,
+    encrypt_nonce: AssignedCell<pallas::Base, pallas::Base>,
+    sender_sk: AssignedCell<pallas::Base, pallas::Base>,
+    rcv: ReceiverVar,
+    class: EncryptionClass,
+    message: &mut Vec<AssignedCell<pallas::Base, pallas::Base>>,
+) -> Result<Vec<AssignedCell<pallas::Base, pallas::Base>>, Error> {
+    assert!(
+        message.len() <= class.plaintext_num(),
+        "The message exceeds the plaintext of the encryption class"
+    );
     // message padding
     let padding_zero = assign_free_advice(
         layouter.namespace(|| "padding zero"),
         advice,
         Value::known(pallas::Base::zero()),
     )?;
-    let paddings =
-        std::iter::repeat(padding_zero).take(RESOURCE_ENCRYPTION_PLAINTEXT_NUM - message.len());
+    let paddings = std::iter::repeat(padding_zero).take(class.plaintext_num() - message.len());
     message.extend(paddings);
 
-    // Compute symmetric secret key
+    let (secret_key, sender_pk) = dh_exchange_gadget(
+        layouter.namespace(|| "compute symmetric secret key"),
+        ecc_chip,
+        sender_sk,
+        rcv,
+    )?;
+
+    // Encrypt
+    let mut cipher = sponge_encrypt_gadget(
+        layouter.namespace(|| "encrypt"),
+        advice,
+        poseidon_config,
+        add_chip,
+        &secret_key,
+        encrypt_nonce,
+        message,
+    )?;
+
+    // Add sender's pk
+    cipher.push(sender_pk.inner().x());
+    cipher.push(sender_pk.inner().y());
+
+    Ok(cipher)
+}
+
+// Compute the symmetric secret key and the sender's pk
+fn dh_exchange_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    ecc_chip: EccChip<TaigaFixedBases>,
+    sender_sk: AssignedCell<pallas::Base, pallas::Base>,
+    rcv: ReceiverVar,
+) -> Result<(EccPoint, EccPoint), Error> {
     let sk = ScalarVar::from_base(
         ecc_chip.clone(),
         layouter.namespace(|| "ScalarVar from_base"),
         &sender_sk,
     )?;
-    let generator = FixedPointBaseField::from_inner(ecc_chip, BaseFieldGenerators::BaseGenerator);
-    let sender_pk = generator.mul(layouter.namespace(|| "sender_sk * generator"), sender_sk)?;
-    let (secret_key, _) = rcv_pk.mul(layouter.namespace(|| "sender_sk * rcv_pk"), sk)?;
+    match rcv {
+        ReceiverVar::Pk(rcv_pk) => {
+            let generator =
+                FixedPointBaseField::from_inner(ecc_chip, BaseFieldGenerators::BaseGenerator);
+            let sender_pk =
+                generator.mul(layouter.namespace(|| "sender_sk * generator"), sender_sk)?;
+            let (secret_key, _) = rcv_pk.mul(layouter.namespace(|| "sender_sk * rcv_pk"), sk)?;
+            Ok((secret_key, sender_pk))
+        }
+        ReceiverVar::Address { g_d, pk_d } => {
+            let (sender_pk, _) = g_d.mul(layouter.namespace(|| "sender_sk * g_d"), sk)?;
+            let sk = ScalarVar::from_base(
+                ecc_chip,
+                layouter.namespace(|| "ScalarVar from_base"),
+                &sender_sk,
+            )?;
+            let (secret_key, _) = pk_d.mul(layouter.namespace(|| "sender_sk * pk_d"), sk)?;
+            Ok((secret_key, sender_pk))
+        }
+    }
+}
 
+// Encrypt the message with the poseidon sponge, returns the encrypted message ||
+// encrypt_nonce || MAC
+fn sponge_encrypt_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    poseidon_config: PoseidonConfig<pallas::Base, POSEIDON_WIDTH, POSEIDON_RATE>,
+    add_chip: AddChip<pallas::Base>,
+    secret_key: &EccPoint,
+    encrypt_nonce: AssignedCell<pallas::Base, pallas::Base>,
+    message: &[AssignedCell<pallas::Base, pallas::Base>],
+) -> Result<Vec<AssignedCell<pallas::Base, pallas::Base>>, Error> {
     // length_nonce = length * 2^128 + encrypt_nonce
     let length_var = assign_free_constant(
         layouter.namespace(|| "constant zero"),
@@ -122,18 +298,147 @@ pub fn resource_encryption_gadget(
     >>::permute(&poseidon_chip, &mut layouter, &state)?;
     cipher.push(state[0].clone().into());
 
-    // Add sender's pk
+    Ok(cipher)
+}
+
+/// Encrypts an app payload of any length with the key shared by the sender and
+/// the receiver, and returns the commitment of the ciphertext, see
+/// `PayloadCiphertext::commitment`. The logic publishes the commitment as a
+/// single public input, e.g. a custom public input.
+///
+/// The resource and the payload of a logic must be encrypted with different
+/// nonces.
+#[allow(clippy::too_many_arguments)]
+pub fn payload_encryption_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    poseidon_config: PoseidonConfig<pallas::Base, POSEIDON_WIDTH, POSEIDON_RATE>,
+    add_chip: AddChip<pallas::Base>,
+    ecc_chip: EccChip<TaigaFixedBases>,
+    encrypt_nonce: AssignedCell<pallas::Base, pallas::Base>,
+    sender_sk: AssignedCell<pallas::Base, pallas::Base>,
+    rcv: impl Into<ReceiverVar>,
+    payload: &[AssignedCell<pallas::Base, pallas::Base>],
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+    let (secret_key, _) = dh_exchange_gadget(
+        layouter.namespace(|| "compute symmetric secret key"),
+        ecc_chip,
+        sender_sk,
+        rcv.into(),
+    )?;
+
+    let cipher = sponge_encrypt_gadget(
+        layouter.namespace(|| "encrypt payload"),
+        advice,
+        poseidon_config.clone(),
+        add_chip,
+        &secret_key,
+        encrypt_nonce,
+        payload,
+    )?;
+
+    hash_chain_gadget(
+        layouter.namespace(|| "payload ciphertext commitment"),
+        advice,
+        poseidon_config,
+        cipher,
+    )
+}
+
+/// Encrypts the message the logic leaves in the mailbox of its resource to
+/// the owner of the resource, and publishes the commitment of the mailbox at
+/// `RESOURCE_LOGIC_CIRCUIT_MAILBOX_IDX`, see `Mailbox::commitment`. The message
+/// is padded to `MAILBOX_PLAINTEXT_NUM`.
+///
+/// `owner` is the field of the resource identifying its owner, e.g. the value,
+/// and the gadget constrains it to `Mailbox::owner(rcv_pk)` so that the mailbox
+/// can only be encrypted to the owner.
+#[allow(clippy::too_many_arguments)]
+pub fn mailbox_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    instances: Column<Instance>,
+    poseidon_config: PoseidonConfig<pallas::Base, POSEIDON_WIDTH, POSEIDON_RATE>,
+    add_chip: AddChip<pallas::Base>,
+    ecc_chip: EccChip<TaigaFixedBases>,
+    encrypt_nonce: AssignedCell<pallas::Base, pallas::Base>,
+    sender_sk: AssignedCell<pallas::Base, pallas::Base>,
+    rcv_pk: EccNonIdentityPoint,
+    owner: AssignedCell<pallas::Base, pallas::Base>,
+    message: &[AssignedCell<pallas::Base, pallas::Base>],
+) -> Result<(), Error> {
+    assert!(
+        message.len() <= MAILBOX_PLAINTEXT_NUM,
+        "The mailbox message exceeds MAILBOX_PLAINTEXT_NUM"
+    );
+    let rcv_pk_hash = poseidon_hash_gadget(
+        poseidon_config.clone(),
+        layouter.namespace(|| "hash rcv_pk"),
+        [rcv_pk.inner().x(), rcv_pk.inner().y()],
+    )?;
+    layouter.assign_region(
+        || "rcv_pk is the owner",
+        |mut region| region.constrain_equal(rcv_pk_hash.cell(), owner.cell()),
+    )?;
+    let mut message = message.to_vec();
+    for _ in message.len()..MAILBOX_PLAINTEXT_NUM {
+        message.push(assign_free_constant(
+            layouter.namespace(|| "mailbox padding"),
+            advice,
+            pallas::Base::zero(),
+        )?);
+    }
+
+    let (secret_key, sender_pk) = dh_exchange_gadget(
+        layouter.namespace(|| "compute symmetric secret key"),
+        ecc_chip,
+        sender_sk,
+        rcv_pk.into(),
+    )?;
+
+    let mut cipher = sponge_encrypt_gadget(
+        layouter.namespace(|| "encrypt mailbox"),
+        advice,
+        poseidon_config.clone(),
+        add_chip,
+        &secret_key,
+        encrypt_nonce,
+        &message,
+    )?;
     cipher.push(sender_pk.inner().x());
     cipher.push(sender_pk.inner().y());
 
-    // Publicize the cipher
-    for (i, ele) in cipher.iter().enumerate() {
-        layouter.constrain_instance(
-            ele.cell(),
-            instances,
-            RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PUBLIC_INPUT_BEGIN_IDX + i,
+    let commitment = hash_chain_gadget(
+        layouter.namespace(|| "mailbox commitment"),
+        advice,
+        poseidon_config,
+        cipher,
+    )?;
+    layouter.constrain_instance(
+        commitment.cell(),
+        instances,
+        RESOURCE_LOGIC_CIRCUIT_MAILBOX_IDX,
+    )
+}
+
+// Commit to the elements with a Poseidon hash chain starting from zero
+fn hash_chain_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    poseidon_config: PoseidonConfig<pallas::Base, POSEIDON_WIDTH, POSEIDON_RATE>,
+    elements: Vec<AssignedCell<pallas::Base, pallas::Base>>,
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+    let mut acc = assign_free_constant(
+        layouter.namespace(|| "constant zero"),
+        advice,
+        pallas::Base::zero(),
+    )?;
+    for ele in elements {
+        acc = poseidon_hash_gadget(
+            poseidon_config.clone(),
+            layouter.namespace(|| "hash chain"),
+            [acc, ele],
         )?;
     }
-
-    Ok(())
+    Ok(acc)
 }
@@ -1,37 +1,62 @@
+#[cfg(feature = "noir")]
+use crate::circuit::noir_resource_logic::NoirResourceLogicCircuit;
 #[cfg(feature = "borsh")]
 use crate::circuit::resource_logic_examples::TrivialResourceLogicCircuit;
-#[cfg(feature = "examples")]
+#[cfg(feature = "apps")]
 use crate::circuit::resource_logic_examples::{
-    or_relation_intent::OrRelationIntentResourceLogicCircuit,
+    amm::AmmPoolResourceLogicCircuit, disjunction::DisjunctionResourceLogicCircuit,
+    intent::IntentResourceLogicCircuit, nft::NftResourceLogicCircuit,
     partial_fulfillment_intent::PartialFulfillmentIntentResourceLogicCircuit,
     receiver_resource_logic::ReceiverResourceLogicCircuit,
     signature_verification::SignatureVerificationResourceLogicCircuit,
-    token::TokenResourceLogicCircuit,
+    solver_fee::SolverFeeResourceLogicCircuit, sweep::SweepResourceLogicCircuit,
+    timelock::TimelockResourceLogicCircuit, token::TokenResourceLogicCircuit,
+    voting::BallotResourceLogicCircuit,
 };
+#[cfg(feature = "examples")]
+use crate::circuit::resource_logic_examples::{
+    blacklist::BlacklistResourceLogicCircuit, channel::ChannelSettlementResourceLogicCircuit,
+    identity_provider::IdentityProviderResourceLogicCircuit,
+    or_relation_intent::OrRelationIntentResourceLogicCircuit,
+    signature_ownership::SignatureOwnershipResourceLogicCircuit,
+};
+#[cfg(feature = "vamp-ir")]
+use crate::circuit::vamp_ir_resource_logic::VampIRResourceLogicCircuit;
+#[cfg(feature = "wasm")]
+use crate::circuit::wasm_resource_logic::WasmResourceLogicCircuit;
 use crate::error::TransactionError;
+use crate::params::ProvingContext;
 use crate::shielded_ptx::ResourceLogicVerifyingInfoSet;
 use crate::{
     circuit::resource_logic_circuit::{
-        ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, VampIRResourceLogicCircuit,
+        ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait,
     },
     constant::{
-        RESOURCE_LOGIC_CIRCUIT_RESOURCE_MERKLE_ROOT_IDX,
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE, RESOURCE_LOGIC_CIRCUIT_RESOURCE_MERKLE_ROOT_IDX,
         RESOURCE_LOGIC_CIRCUIT_SELF_RESOURCE_ID_IDX,
     },
 };
 
+#[cfg(feature = "borsh")]
+use crate::transaction::{EncodingLayout, VersionedBorsh};
 #[cfg(feature = "borsh")]
 use borsh::{BorshDeserialize, BorshSerialize};
+use lazy_static::lazy_static;
 use pasta_curves::pallas;
+use rand::{rngs::OsRng, RngCore};
 #[cfg(feature = "serde")]
 use serde;
+use std::collections::HashMap;
+#[cfg(feature = "vamp-ir")]
 use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ResourceLogicRepresentation {
-    // vampir has a unified circuit representation.
+    // vampir has a unified circuit representation. Proven with the `vamp-ir`
+    // feature.
     VampIR(Vec<u8>),
     // Native halo2 circuits don't have a unified representatioin, enumerate the resource_logic circuit examples for the moment.
     // TODO: figure out if we can have a unified circuit presentation. In theory, it's possible to separate the circuit system and proving system.
@@ -42,9 +67,75 @@ pub enum ResourceLogicRepresentation {
     PartialFulfillmentIntent,
     OrRelationIntent,
     CascadeIntent,
+    ChannelSettlement,
+    IdentityProvider,
+    Intent,
+    Disjunction,
+    // A circuit registered by an application, see `register_resource_logic`.
+    Registered(String),
+    SolverFee,
+    Sweep,
+    SignatureOwnership,
+    Blacklist,
+    Timelock,
+    AmmPool,
+    Ballot,
+    Nft,
+    // A WASM module implementing the host interface of `WasmResourceLogicCircuit`,
+    // the inputs are its witness.
+    Wasm(Vec<u8>),
+    // The ACIR program of a nargo artifact, the inputs are the serialized witness stack.
+    Noir(Vec<u8>),
     // Add other native resource_logic types here if needed
 }
 
+/// Builds a registered resource logic circuit from the inputs of its bytecode.
+pub type ResourceLogicFactory = Arc<
+    dyn Fn(&[u8]) -> Result<Box<dyn ResourceLogicVerifyingInfoTrait>, TransactionError>
+        + Send
+        + Sync,
+>;
+
+lazy_static! {
+    static ref RESOURCE_LOGIC_REGISTRY: RwLock<HashMap<String, ResourceLogicFactory>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Registers the circuit of an application under `tag`, so that the bytecodes
+/// of `ResourceLogicRepresentation::Registered(tag)` are proven and verified
+/// with the circuits built by `factory`. Returns the factory previously
+/// registered under `tag`, if any.
+///
+/// The registry is global: the provers and the verifiers must register the
+/// same circuits under the same tags.
+pub fn register_resource_logic(
+    tag: impl Into<String>,
+    factory: ResourceLogicFactory,
+) -> Option<ResourceLogicFactory> {
+    RESOURCE_LOGIC_REGISTRY
+        .write()
+        .unwrap()
+        .insert(tag.into(), factory)
+}
+
+pub fn unregister_resource_logic(tag: &str) -> Option<ResourceLogicFactory> {
+    RESOURCE_LOGIC_REGISTRY.write().unwrap().remove(tag)
+}
+
+fn load_registered_resource_logic(
+    tag: &str,
+    inputs: &[u8],
+) -> Result<Box<dyn ResourceLogicVerifyingInfoTrait>, TransactionError> {
+    // Don't hold the lock while building the circuit
+    let factory = RESOURCE_LOGIC_REGISTRY
+        .read()
+        .unwrap()
+        .get(tag)
+        .cloned()
+        .ok_or_else(|| TransactionError::UnregisteredResourceLogic(tag.to_string()))?;
+    factory(inputs)
+}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -66,8 +157,37 @@ impl ResourceLogicByteCode {
         Self { circuit, inputs }
     }
 
+    /// The root of the blacklist of a blacklist logic, None for the other
+    /// logics.
+    pub fn get_blacklist_root(&self) -> Option<pallas::Base> {
+        #[cfg(feature = "examples")]
+        if let ResourceLogicRepresentation::Blacklist = self.circuit {
+            let resource_logic = BlacklistResourceLogicCircuit::from_bytes(&self.inputs);
+            return Some(resource_logic.get_blacklist_root());
+        }
+        None
+    }
+
     pub fn generate_proof(self) -> Result<ResourceLogicVerifyingInfo, TransactionError> {
+        self.generate_proof_with_rng(&mut OsRng)
+    }
+
+    /// Same as `generate_proof`, with the randomness taken from `rng`.
+    pub fn generate_proof_with_rng(
+        self,
+        rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfo, TransactionError> {
+        self.generate_proof_with_context(&ProvingContext::default(), rng)
+    }
+
+    /// Same as `generate_proof_with_rng`, with the params of `context`.
+    pub fn generate_proof_with_context(
+        self,
+        context: &ProvingContext,
+        rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfo, TransactionError> {
         match self.circuit {
+            #[cfg(feature = "vamp-ir")]
             ResourceLogicRepresentation::VampIR(circuit) => {
                 // TDDO: use the file_name api atm,
                 // request vamp_ir to provide a api to generate circuit from bytes.
@@ -77,46 +197,144 @@ impl ResourceLogicByteCode {
                 let resource_logic_circuit = VampIRResourceLogicCircuit::from_vamp_ir_file(
                     &vamp_ir_circuit_file,
                     &inputs_file,
-                );
-                Ok(resource_logic_circuit.get_verifying_info())
+                )?;
+                resource_logic_circuit.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "wasm")]
+            ResourceLogicRepresentation::Wasm(module) => {
+                let resource_logic = WasmResourceLogicCircuit::from_module(&module, &self.inputs)?;
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "noir")]
+            ResourceLogicRepresentation::Noir(program) => {
+                let resource_logic =
+                    NoirResourceLogicCircuit::from_artifact(&program, &self.inputs)?;
+                resource_logic.get_verifying_info_with_context(context, rng)
             }
             #[cfg(feature = "borsh")]
             ResourceLogicRepresentation::Trivial => {
                 let resource_logic = TrivialResourceLogicCircuit::from_bytes(&self.inputs);
-                Ok(resource_logic.get_verifying_info())
+                resource_logic.get_verifying_info_with_context(context, rng)
             }
-            #[cfg(feature = "examples")]
+            #[cfg(feature = "apps")]
             ResourceLogicRepresentation::Token => {
                 let resource_logic = TokenResourceLogicCircuit::from_bytes(&self.inputs);
-                Ok(resource_logic.get_verifying_info())
+                resource_logic.get_verifying_info_with_context(context, rng)
             }
-            #[cfg(feature = "examples")]
+            #[cfg(feature = "apps")]
             ResourceLogicRepresentation::SignatureVerification => {
                 let resource_logic =
                     SignatureVerificationResourceLogicCircuit::from_bytes(&self.inputs);
-                Ok(resource_logic.get_verifying_info())
+                resource_logic.get_verifying_info_with_context(context, rng)
             }
-            #[cfg(feature = "examples")]
+            #[cfg(feature = "apps")]
             ResourceLogicRepresentation::Receiver => {
                 let resource_logic = ReceiverResourceLogicCircuit::from_bytes(&self.inputs);
-                Ok(resource_logic.get_verifying_info())
+                resource_logic.get_verifying_info_with_context(context, rng)
             }
-            #[cfg(feature = "examples")]
+            #[cfg(feature = "apps")]
             ResourceLogicRepresentation::PartialFulfillmentIntent => {
                 let resource_logic =
                     PartialFulfillmentIntentResourceLogicCircuit::from_bytes(&self.inputs);
-                Ok(resource_logic.get_verifying_info())
+                resource_logic.get_verifying_info_with_context(context, rng)
             }
             #[cfg(feature = "examples")]
             ResourceLogicRepresentation::OrRelationIntent => {
                 let resource_logic = OrRelationIntentResourceLogicCircuit::from_bytes(&self.inputs);
-                Ok(resource_logic.get_verifying_info())
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "examples")]
+            ResourceLogicRepresentation::ChannelSettlement => {
+                let resource_logic =
+                    ChannelSettlementResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "examples")]
+            ResourceLogicRepresentation::IdentityProvider => {
+                let resource_logic = IdentityProviderResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Intent => {
+                let resource_logic = IntentResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Disjunction => {
+                let resource_logic = DisjunctionResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::SolverFee => {
+                let resource_logic = SolverFeeResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Sweep => {
+                let resource_logic = SweepResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "examples")]
+            ResourceLogicRepresentation::SignatureOwnership => {
+                let resource_logic =
+                    SignatureOwnershipResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "examples")]
+            ResourceLogicRepresentation::Blacklist => {
+                let resource_logic = BlacklistResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Timelock => {
+                let resource_logic = TimelockResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::AmmPool => {
+                let resource_logic = AmmPoolResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Ballot => {
+                let resource_logic = BallotResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Nft => {
+                let resource_logic = NftResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.get_verifying_info_with_context(context, rng)
+            }
+            ResourceLogicRepresentation::Registered(tag) => {
+                let resource_logic = load_registered_resource_logic(&tag, &self.inputs)?;
+                resource_logic.get_verifying_info_with_context(context, rng)
             }
             #[allow(unreachable_patterns)]
             _ => Err(TransactionError::InvalidResourceLogicRepresentation),
         }
     }
 
+    /// The params size the logic is likely proven with, without proving it.
+    /// Only the WASM and Noir logics are built to find it, the others are
+    /// assumed to use the default size.
+    pub fn params_size_hint(&self) -> u32 {
+        match &self.circuit {
+            #[cfg(feature = "wasm")]
+            ResourceLogicRepresentation::Wasm(module) => {
+                WasmResourceLogicCircuit::from_module(module, &self.inputs)
+                    .map(|logic| logic.constraint_system.params_size())
+                    .unwrap_or(RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE)
+            }
+            #[cfg(feature = "noir")]
+            ResourceLogicRepresentation::Noir(program) => {
+                NoirResourceLogicCircuit::from_artifact(program, &self.inputs)
+                    .map(|logic| logic.circuit.constraint_system.params_size())
+                    .unwrap_or(RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE)
+            }
+            _ => RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        }
+    }
+
     // Verify resource_logic circuit transparently and return self resource id for further checking
     pub fn verify_transparently(
         &self,
@@ -124,6 +342,7 @@ impl ResourceLogicByteCode {
     ) -> Result<pallas::Base, TransactionError> {
         // check resource logic transparently
         let public_inputs = match &self.circuit {
+            #[cfg(feature = "vamp-ir")]
             ResourceLogicRepresentation::VampIR(circuit) => {
                 // TDDO: use the file_name api atm,
                 // request vamp_ir to provide a api to generate circuit from bytes.
@@ -133,31 +352,42 @@ impl ResourceLogicByteCode {
                 let resource_logic_circuit = VampIRResourceLogicCircuit::from_vamp_ir_file(
                     &vamp_ir_circuit_file,
                     &inputs_file,
-                );
+                )?;
                 resource_logic_circuit.verify_transparently()?
             }
+            #[cfg(feature = "wasm")]
+            ResourceLogicRepresentation::Wasm(module) => {
+                let resource_logic = WasmResourceLogicCircuit::from_module(module, &self.inputs)?;
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "noir")]
+            ResourceLogicRepresentation::Noir(program) => {
+                let resource_logic =
+                    NoirResourceLogicCircuit::from_artifact(program, &self.inputs)?;
+                resource_logic.verify_transparently()?
+            }
             #[cfg(feature = "borsh")]
             ResourceLogicRepresentation::Trivial => {
                 let resource_logic = TrivialResourceLogicCircuit::from_bytes(&self.inputs);
                 resource_logic.verify_transparently()?
             }
-            #[cfg(feature = "examples")]
+            #[cfg(feature = "apps")]
             ResourceLogicRepresentation::Token => {
                 let resource_logic = TokenResourceLogicCircuit::from_bytes(&self.inputs);
                 resource_logic.verify_transparently()?
             }
-            #[cfg(feature = "examples")]
+            #[cfg(feature = "apps")]
             ResourceLogicRepresentation::SignatureVerification => {
                 let resource_logic =
                     SignatureVerificationResourceLogicCircuit::from_bytes(&self.inputs);
                 resource_logic.verify_transparently()?
             }
-            #[cfg(feature = "examples")]
+            #[cfg(feature = "apps")]
             ResourceLogicRepresentation::Receiver => {
                 let resource_logic = ReceiverResourceLogicCircuit::from_bytes(&self.inputs);
                 resource_logic.verify_transparently()?
             }
-            #[cfg(feature = "examples")]
+            #[cfg(feature = "apps")]
             ResourceLogicRepresentation::PartialFulfillmentIntent => {
                 let resource_logic =
                     PartialFulfillmentIntentResourceLogicCircuit::from_bytes(&self.inputs);
@@ -168,6 +398,72 @@ impl ResourceLogicByteCode {
                 let resource_logic = OrRelationIntentResourceLogicCircuit::from_bytes(&self.inputs);
                 resource_logic.verify_transparently()?
             }
+            #[cfg(feature = "examples")]
+            ResourceLogicRepresentation::ChannelSettlement => {
+                let resource_logic =
+                    ChannelSettlementResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "examples")]
+            ResourceLogicRepresentation::IdentityProvider => {
+                let resource_logic = IdentityProviderResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Intent => {
+                let resource_logic = IntentResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Disjunction => {
+                let resource_logic = DisjunctionResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::SolverFee => {
+                let resource_logic = SolverFeeResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Sweep => {
+                let resource_logic = SweepResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "examples")]
+            ResourceLogicRepresentation::SignatureOwnership => {
+                let resource_logic =
+                    SignatureOwnershipResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "examples")]
+            ResourceLogicRepresentation::Blacklist => {
+                let resource_logic = BlacklistResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Timelock => {
+                let resource_logic = TimelockResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::AmmPool => {
+                let resource_logic = AmmPoolResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Ballot => {
+                let resource_logic = BallotResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            #[cfg(feature = "apps")]
+            ResourceLogicRepresentation::Nft => {
+                let resource_logic = NftResourceLogicCircuit::from_bytes(&self.inputs);
+                resource_logic.verify_transparently()?
+            }
+            ResourceLogicRepresentation::Registered(tag) => {
+                let resource_logic = load_registered_resource_logic(tag, &self.inputs)?;
+                resource_logic.verify_transparently()?
+            }
             #[allow(unreachable_patterns)]
             _ => return Err(TransactionError::InvalidResourceLogicRepresentation),
         };
@@ -196,13 +492,31 @@ impl ApplicationByteCode {
     }
 
     pub fn generate_proofs(self) -> Result<ResourceLogicVerifyingInfoSet, TransactionError> {
-        let app_resource_logic_verifying_info =
-            self.app_resource_logic_bytecode.generate_proof()?;
+        self.generate_proofs_with_rng(&mut OsRng)
+    }
+
+    /// Same as `generate_proofs`, with the randomness taken from `rng`.
+    pub fn generate_proofs_with_rng(
+        self,
+        rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfoSet, TransactionError> {
+        self.generate_proofs_with_context(&ProvingContext::default(), rng)
+    }
+
+    /// Same as `generate_proofs_with_rng`, with the params of `context`.
+    pub fn generate_proofs_with_context(
+        self,
+        context: &ProvingContext,
+        rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfoSet, TransactionError> {
+        let app_resource_logic_verifying_info = self
+            .app_resource_logic_bytecode
+            .generate_proof_with_context(context, &mut *rng)?;
 
         let app_dynamic_resource_logic_verifying_info: Result<Vec<_>, _> = self
             .dynamic_resource_logic_bytecode
             .into_iter()
-            .map(|bytecode| bytecode.generate_proof())
+            .map(|bytecode| bytecode.generate_proof_with_context(context, &mut *rng))
             .collect();
         Ok(ResourceLogicVerifyingInfoSet::new(
             app_resource_logic_verifying_info,
@@ -210,6 +524,23 @@ impl ApplicationByteCode {
         ))
     }
 
+    /// The params size hints of the application logic and the dynamic logics,
+    /// in proving order.
+    pub fn params_size_hints(&self) -> Vec<u32> {
+        std::iter::once(&self.app_resource_logic_bytecode)
+            .chain(self.dynamic_resource_logic_bytecode.iter())
+            .map(ResourceLogicByteCode::params_size_hint)
+            .collect()
+    }
+
+    /// The roots of the blacklists of the blacklist logics.
+    pub fn get_blacklist_roots(&self) -> Vec<pallas::Base> {
+        std::iter::once(&self.app_resource_logic_bytecode)
+            .chain(self.dynamic_resource_logic_bytecode.iter())
+            .filter_map(ResourceLogicByteCode::get_blacklist_root)
+            .collect()
+    }
+
     // Verify resource_logic circuits transparently and return owned resource PubID for further checking
     pub fn verify_transparently(
         &self,
@@ -228,3 +559,165 @@ impl ApplicationByteCode {
         Ok(self_resource_id)
     }
 }
+
+// The borsh tags of the Wasm and the Noir representations, 1 and 2 in the
+// interleaved layout of the versions before 6, see `EncodingLayout`
+#[cfg(feature = "borsh")]
+const WASM_TAG: u8 = 21;
+#[cfg(feature = "borsh")]
+const NOIR_TAG: u8 = 22;
+
+#[cfg(feature = "borsh")]
+impl VersionedBorsh for ResourceLogicRepresentation {
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<()> {
+        let mut bytes = borsh::to_vec(self)?;
+        if layout.interleaved_representations {
+            bytes[0] = match bytes[0] {
+                0 => 0,
+                WASM_TAG => 1,
+                NOIR_TAG => 2,
+                tag if tag < WASM_TAG => tag + 2,
+                _ => return Err(EncodingLayout::unsupported("representations")),
+            };
+        }
+        writer.write_all(&bytes)
+    }
+
+    fn deserialize_versioned<R: std::io::Read>(
+        reader: &mut R,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<Self> {
+        use std::io::Read;
+        let mut tag = u8::deserialize_reader(reader)?;
+        if layout.interleaved_representations {
+            tag = match tag {
+                0 => 0,
+                1 => WASM_TAG,
+                2 => NOIR_TAG,
+                tag if tag <= NOIR_TAG => tag - 2,
+                _ => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "invalid representation tag",
+                    ))
+                }
+            };
+        }
+        Self::deserialize_reader(&mut [tag].as_slice().chain(reader))
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl VersionedBorsh for ResourceLogicByteCode {
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<()> {
+        self.circuit.serialize_versioned(writer, layout)?;
+        self.inputs.serialize(writer)
+    }
+
+    fn deserialize_versioned<R: std::io::Read>(
+        reader: &mut R,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<Self> {
+        let circuit = ResourceLogicRepresentation::deserialize_versioned(reader, layout)?;
+        let inputs = Vec::<u8>::deserialize_reader(reader)?;
+        Ok(Self { circuit, inputs })
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl VersionedBorsh for ApplicationByteCode {
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<()> {
+        self.app_resource_logic_bytecode
+            .serialize_versioned(writer, layout)?;
+        self.dynamic_resource_logic_bytecode
+            .serialize_versioned(writer, layout)
+    }
+
+    fn deserialize_versioned<R: std::io::Read>(
+        reader: &mut R,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<Self> {
+        let app_resource_logic_bytecode =
+            ResourceLogicByteCode::deserialize_versioned(reader, layout)?;
+        let dynamic_resource_logic_bytecode =
+            Vec::<ResourceLogicByteCode>::deserialize_versioned(reader, layout)?;
+        Ok(Self {
+            app_resource_logic_bytecode,
+            dynamic_resource_logic_bytecode,
+        })
+    }
+}
+
+#[cfg(feature = "borsh")]
+#[test]
+fn test_representation_versioned_borsh() {
+    let interleaved = EncodingLayout::of(5).unwrap();
+    for (representation, tag, interleaved_tag) in [
+        (ResourceLogicRepresentation::VampIR(vec![]), 0, 0),
+        (ResourceLogicRepresentation::Trivial, 1, 3),
+        (ResourceLogicRepresentation::Nft, 20, 22),
+        (ResourceLogicRepresentation::Wasm(vec![]), WASM_TAG, 1),
+        (ResourceLogicRepresentation::Noir(vec![]), NOIR_TAG, 2),
+    ] {
+        let bytes = borsh::to_vec(&representation).unwrap();
+        assert_eq!(bytes[0], tag);
+        let mut legacy = vec![];
+        representation
+            .serialize_versioned(&mut legacy, &interleaved)
+            .unwrap();
+        assert_eq!(legacy[0], interleaved_tag);
+        assert_eq!(legacy[1..], bytes[1..]);
+        let decoded = ResourceLogicRepresentation::deserialize_versioned(
+            &mut legacy.as_slice(),
+            &interleaved,
+        )
+        .unwrap();
+        assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+    }
+}
+
+#[cfg(feature = "borsh")]
+#[test]
+fn test_registered_resource_logic() {
+    use crate::circuit::resource_logic_circuit::ResourceLogicCircuit;
+    use crate::constant::TAIGA_RESOURCE_TREE_DEPTH;
+    use crate::merkle_tree::LR;
+    use crate::resource::tests::random_resource;
+
+    let merkle_path = [(pallas::Base::zero(), LR::R); TAIGA_RESOURCE_TREE_DEPTH];
+    let circuit = TrivialResourceLogicCircuit::new(random_resource(&mut OsRng), merkle_path);
+    let root = circuit.get_self_resource().get_root();
+    let bytecode = ResourceLogicByteCode::new(
+        ResourceLogicRepresentation::Registered("test_trivial".to_string()),
+        circuit.to_bytes(),
+    );
+    assert!(matches!(
+        bytecode.verify_transparently(&root),
+        Err(TransactionError::UnregisteredResourceLogic(_))
+    ));
+
+    // The application plugs its circuit in without a new representation
+    register_resource_logic(
+        "test_trivial",
+        Arc::new(|inputs| Ok(Box::new(TrivialResourceLogicCircuit::from_bytes(inputs)))),
+    );
+    assert_eq!(
+        bytecode.verify_transparently(&root).unwrap(),
+        circuit.get_self_resource().get_identity()
+    );
+    let verifying_info = bytecode.clone().generate_proof().unwrap();
+    assert!(verifying_info.verify().is_ok());
+    assert!(unregister_resource_logic("test_trivial").is_some());
+}
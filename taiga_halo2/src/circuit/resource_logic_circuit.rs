@@ -1,35 +1,44 @@
 use crate::{
+    auxiliary_delta::AuxiliaryDelta,
     circuit::{
         blake2s::publicize_default_dynamic_resource_logic_commitments,
         blake2s::Blake2sConfig,
         gadgets::{
             add::{AddChip, AddConfig},
+            assign_free_constant,
+            comparison::ComparisonConfig,
             conditional_equal::ConditionalEqualConfig,
             conditional_select::ConditionalSelectConfig,
             extended_or_relation::ExtendedOrRelationConfig,
-            mul::{MulChip, MulConfig},
+            method_dispatch::MethodDispatchConfig,
+            mul::{MulChip, MulConfig, MulInstructions},
+            poseidon_hash::poseidon_hash_gadget,
             sub::{SubChip, SubConfig},
         },
         integrity::load_resource,
         merkle_circuit::{MerklePoseidonChip, MerklePoseidonConfig},
         resource_commitment::{ResourceCommitChip, ResourceCommitConfig},
-        vamp_ir_utils::{get_circuit_assignments, parse, VariableAssignmentError},
+        resource_non_membership::ResourceNonMembershipConfig,
     },
     constant::{
-        TaigaFixedBases, RESOURCE_ENCRYPTION_CIPHERTEXT_NUM, RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        TaigaFixedBases, RESOURCE_ENCRYPTION_CIPHERTEXT_NUM,
+        RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX,
+        RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_NUM, RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
         RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM,
         RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PK_X_IDX,
         RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PK_Y_IDX,
         RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PUBLIC_INPUT_BEGIN_IDX,
         RESOURCE_LOGIC_CIRCUIT_RESOURCE_MERKLE_ROOT_IDX,
-        RESOURCE_LOGIC_CIRCUIT_SELF_RESOURCE_ID_IDX, SETUP_PARAMS_MAP,
+        RESOURCE_LOGIC_CIRCUIT_SELF_RESOURCE_ID_IDX,
     },
     error::TransactionError,
-    proof::Proof,
+    params::{get_params, ProvingContext, VerifyingContext},
+    proof::{BatchVerifier, Proof},
     resource::RandomSeed,
     resource_encryption::{ResourceCiphertext, SecretKey},
+    resource_logic_commitment::ResourceLogicCommitment,
     resource_logic_vk::ResourceLogicVerifyingKey,
-    resource_tree::ResourceExistenceWitness,
+    resource_tree::{owned_resources_hash, ResourceExistenceWitness},
     utils::mod_r_p,
 };
 use dyn_clone::{clone_trait_object, DynClone};
@@ -42,24 +51,15 @@ use halo2_gadgets::{
 };
 use halo2_proofs::{
     arithmetic::CurveAffine,
-    circuit::{AssignedCell, Layouter, Value},
+    circuit::{floor_planner, AssignedCell, Layouter, Value},
+    dev::MockProver,
     plonk::{
         keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error, Instance,
         TableColumn, VerifyingKey,
     },
-    poly::commitment::Params,
 };
-use pasta_curves::{pallas, vesta, EqAffine, Fp};
+use pasta_curves::{pallas, vesta};
 use rand::{rngs::OsRng, RngCore};
-use std::collections::HashMap;
-use std::fs;
-//use std::io;
-use std::path::PathBuf;
-use std::rc::Rc;
-use vamp_ir::ast::Module;
-use vamp_ir::halo2::synth::{make_constant, Halo2Module, PrimeFieldOps};
-use vamp_ir::transform::compile;
-use vamp_ir::util::{read_inputs_from_file, Config};
 
 #[cfg(feature = "serde")]
 use serde;
@@ -87,6 +87,9 @@ pub struct ResourceLogicVerifyingInfo {
     pub vk: VerifyingKey<vesta::Affine>,
     pub proof: Proof,
     pub public_inputs: ResourceLogicPublicInputs,
+    /// Values of the instance columns declared besides the public input column
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extra_instances: Vec<Vec<pallas::Base>>,
 }
 
 #[cfg(feature = "nif")]
@@ -100,6 +103,7 @@ impl Encoder for ResourceLogicVerifyingInfo {
             self.vk.to_bytes().encode(env),
             self.proof.encode(env),
             self.public_inputs.encode(env),
+            self.extra_instances.encode(env),
         )
             .encode(env)
     }
@@ -108,23 +112,21 @@ impl Encoder for ResourceLogicVerifyingInfo {
 #[cfg(feature = "nif")]
 impl<'a> Decoder<'a> for ResourceLogicVerifyingInfo {
     fn decode(term: Term<'a>) -> NifResult<Self> {
-        let (term, vk, proof, public_inputs): (
+        let (term, vk, proof, public_inputs, extra_instances): (
             atom::Atom,
             Vec<u8>,
             Proof,
             ResourceLogicPublicInputs,
+            Vec<Vec<pallas::Base>>,
         ) = term.decode()?;
         if term == verifying_info() {
-            use crate::circuit::resource_logic_examples::TrivialResourceLogicCircuit;
-            let params = SETUP_PARAMS_MAP
-                .get(&RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE)
-                .unwrap();
-            let vk = VerifyingKey::from_bytes::<TrivialResourceLogicCircuit>(&vk, params)
+            let vk = read_resource_logic_vk(&mut vk.as_slice())
                 .map_err(|_e| rustler::Error::Atom("failure to decode"))?;
             Ok(ResourceLogicVerifyingInfo {
                 vk,
                 proof,
                 public_inputs,
+                extra_instances,
             })
         } else {
             Err(rustler::Error::BadArg)
@@ -147,17 +149,53 @@ impl Encoder for ResourceLogicPublicInputs {
 impl<'a> Decoder<'a> for ResourceLogicPublicInputs {
     fn decode(term: Term<'a>) -> NifResult<Self> {
         let val: Vec<pallas::Base> = Decoder::decode(term)?;
-        Ok(val.into())
+        val.try_into()
+            .map(ResourceLogicPublicInputs)
+            .map_err(|_| rustler::Error::BadArg)
     }
 }
 
 impl ResourceLogicVerifyingInfo {
-    pub fn verify(&self) -> Result<(), Error> {
-        let params = SETUP_PARAMS_MAP
-            .get(&RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE)
-            .unwrap();
-        self.proof
-            .verify(&self.vk, params, &[self.public_inputs.inner()])
+    /// The size of the params the logic is proven with.
+    pub fn params_size(&self) -> u32 {
+        self.vk.get_domain().k()
+    }
+
+    pub fn verify(&self) -> Result<(), TransactionError> {
+        self.verify_with_context(&VerifyingContext::default())
+    }
+
+    /// Same as `verify`, with the params of `context`.
+    pub fn verify_with_context(&self, context: &VerifyingContext) -> Result<(), TransactionError> {
+        self.proof.verify(
+            &self.vk,
+            context.params(self.params_size())?,
+            &self
+                .public_inputs
+                .with_extra_instances(&self.extra_instances),
+        )?;
+        Ok(())
+    }
+
+    /// Same as `verify`, with the IPA check deferred to the batch verifier.
+    pub fn batch_verify(&self, batch: &mut BatchVerifier) -> Result<(), TransactionError> {
+        self.batch_verify_with_context(batch, &VerifyingContext::default())
+    }
+
+    pub fn batch_verify_with_context(
+        &self,
+        batch: &mut BatchVerifier,
+        context: &VerifyingContext,
+    ) -> Result<(), TransactionError> {
+        batch.add_proof(
+            &self.proof,
+            &self.vk,
+            context.params(self.params_size())?,
+            &self
+                .public_inputs
+                .with_extra_instances(&self.extra_instances),
+        )?;
+        Ok(())
     }
 
     pub fn get_resource_merkle_root(&self) -> pallas::Base {
@@ -169,6 +207,32 @@ impl ResourceLogicVerifyingInfo {
         self.public_inputs
             .get_from_index(RESOURCE_LOGIC_CIRCUIT_SELF_RESOURCE_ID_IDX)
     }
+
+    /// Returns the auxiliary delta published in the first extra instance
+    /// column, if the logic contributes one.
+    pub fn get_auxiliary_delta(&self) -> Result<Option<AuxiliaryDelta>, TransactionError> {
+        match self.extra_instances.first() {
+            Some(column) => AuxiliaryDelta::from_instance(column),
+            None => Ok(None),
+        }
+    }
+
+    /// Decrypts the resource encrypted to the receiver of `sk`, from an extra
+    /// instance column for the larger encryption classes, or from the public
+    /// inputs for the small class.
+    pub fn decrypt(&self, sk: pallas::Base) -> Option<Vec<pallas::Base>> {
+        match self
+            .extra_instances
+            .iter()
+            .find_map(|column| ResourceCiphertext::from_instance(column))
+        {
+            Some((cipher, sender_pk)) => {
+                let key = SecretKey::from_dh_exchange(&sender_pk, &mod_r_p(sk));
+                cipher.decrypt(&key)
+            }
+            None => self.public_inputs.decrypt(sk),
+        }
+    }
 }
 
 #[cfg(feature = "borsh")]
@@ -176,13 +240,22 @@ impl BorshSerialize for ResourceLogicVerifyingInfo {
     fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
         use ff::PrimeField;
         // Write vk
-        self.vk.write(writer)?;
+        write_resource_logic_vk(&self.vk, writer)?;
         // Write proof
         self.proof.serialize(writer)?;
         // Write public inputs
         for ele in self.public_inputs.inner().iter() {
             writer.write_all(&ele.to_repr())?;
         }
+        // Write extra instances
+        use byteorder::{LittleEndian, WriteBytesExt};
+        writer.write_u32::<LittleEndian>(self.extra_instances.len() as u32)?;
+        for column in self.extra_instances.iter() {
+            writer.write_u32::<LittleEndian>(column.len() as u32)?;
+            for ele in column.iter() {
+                writer.write_all(&ele.to_repr())?;
+            }
+        }
         Ok(())
     }
 }
@@ -191,26 +264,55 @@ impl BorshSerialize for ResourceLogicVerifyingInfo {
 impl BorshDeserialize for ResourceLogicVerifyingInfo {
     fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
         // Read vk
-        use crate::circuit::resource_logic_examples::TrivialResourceLogicCircuit;
         use crate::utils::read_base_field;
-        let params = SETUP_PARAMS_MAP
-            .get(&RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE)
-            .unwrap();
-        let vk = VerifyingKey::read::<_, TrivialResourceLogicCircuit>(reader, params)?;
+        let vk = read_resource_logic_vk(reader)?;
         // Read proof
         let proof = Proof::deserialize_reader(reader)?;
         // Read public inputs
         let public_inputs: Vec<_> = (0..RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM)
             .map(|_| read_base_field(reader))
             .collect::<Result<_, _>>()?;
+        // Read extra instances
+        use byteorder::{LittleEndian, ReadBytesExt};
+        let column_num = reader.read_u32::<LittleEndian>()?;
+        let extra_instances = (0..column_num)
+            .map(|_| {
+                let len = reader.read_u32::<LittleEndian>()?;
+                (0..len)
+                    .map(|_| read_base_field(reader))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<_, _>>()?;
         Ok(ResourceLogicVerifyingInfo {
             vk,
             proof,
             public_inputs: public_inputs.into(),
+            extra_instances,
         })
     }
 }
 
+// The vk is prefixed with its params size, the params are needed to read it.
+fn write_resource_logic_vk<W: std::io::Write>(
+    vk: &VerifyingKey<vesta::Affine>,
+    writer: &mut W,
+) -> std::io::Result<()> {
+    writer.write_all(&vk.get_domain().k().to_le_bytes())?;
+    vk.write(writer)
+}
+
+fn read_resource_logic_vk<R: std::io::Read>(
+    reader: &mut R,
+) -> std::io::Result<VerifyingKey<vesta::Affine>> {
+    use crate::circuit::resource_logic_examples::TrivialResourceLogicCircuit;
+    let mut k = [0u8; 4];
+    reader.read_exact(&mut k)?;
+    let k = u32::from_le_bytes(k);
+    let params = get_params(k)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+    VerifyingKey::read::<_, TrivialResourceLogicCircuit>(reader, params)
+}
+
 #[cfg(feature = "serde")]
 fn serde_serialize_verifying_key<S>(
     x: &VerifyingKey<vesta::Affine>,
@@ -220,7 +322,7 @@ where
     S: serde::Serializer,
 {
     let mut buf = Vec::new();
-    x.write(&mut buf).unwrap();
+    write_resource_logic_vk(x, &mut buf).unwrap();
     s.serialize_bytes(&buf)
 }
 
@@ -232,11 +334,7 @@ where
     use serde::de::Error;
     let buf: Vec<u8> = serde::Deserialize::deserialize(d)?;
 
-    use crate::circuit::resource_logic_examples::TrivialResourceLogicCircuit;
-    let params = SETUP_PARAMS_MAP
-        .get(&RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE)
-        .unwrap();
-    let vk = VerifyingKey::read::<_, TrivialResourceLogicCircuit>(&mut buf.as_slice(), params)
+    let vk = read_resource_logic_vk(&mut buf.as_slice())
         .map_err(|e| Error::custom(format!("Error reading VerifyingKey: {}", e)))?;
     Ok(vk)
 }
@@ -271,6 +369,17 @@ impl ResourceLogicPublicInputs {
         self.0.to_vec()
     }
 
+    /// The instances of all the columns: the public inputs first, followed by
+    /// the extra instance columns.
+    pub fn with_extra_instances<'a>(
+        &'a self,
+        extra_instances: &'a [Vec<pallas::Base>],
+    ) -> Vec<&'a [pallas::Base]> {
+        std::iter::once(self.0.as_slice())
+            .chain(extra_instances.iter().map(|column| column.as_slice()))
+            .collect()
+    }
+
     pub fn decrypt(&self, sk: pallas::Base) -> Option<Vec<pallas::Base>> {
         let cipher: ResourceCiphertext = self.0
             [RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PUBLIC_INPUT_BEGIN_IDX
@@ -278,17 +387,25 @@ impl ResourceLogicPublicInputs {
                     + RESOURCE_ENCRYPTION_CIPHERTEXT_NUM]
             .to_vec()
             .into();
-        let sender_pk = pallas::Affine::from_xy(
+        // The public inputs of logics that don't encrypt their resource are
+        // random padding, not a point
+        let sender_pk: Option<pallas::Affine> = pallas::Affine::from_xy(
             self.get_from_index(RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PK_X_IDX),
             self.get_from_index(RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PK_Y_IDX),
         )
-        .unwrap()
-        .to_curve();
+        .into();
+        let sender_pk = sender_pk?.to_curve();
         let key = SecretKey::from_dh_exchange(&sender_pk, &mod_r_p(sk));
         cipher.decrypt(&key)
     }
 }
 
+impl From<[pallas::Base; RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM]> for ResourceLogicPublicInputs {
+    fn from(public_inputs: [pallas::Base; RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM]) -> Self {
+        ResourceLogicPublicInputs(public_inputs)
+    }
+}
+
 impl From<Vec<pallas::Base>> for ResourceLogicPublicInputs {
     fn from(public_input_vec: Vec<pallas::Base>) -> Self {
         ResourceLogicPublicInputs(
@@ -303,6 +420,8 @@ impl From<Vec<pallas::Base>> for ResourceLogicPublicInputs {
 pub struct ResourceLogicConfig {
     pub advices: [Column<Advice>; 10],
     pub instances: Column<Instance>,
+    // Instance columns declared by the logic besides the public input column
+    pub extra_instances: Vec<Column<Instance>>,
     pub table_idx: TableColumn,
     pub ecc_config: EccConfig<TaigaFixedBases>,
     pub poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
@@ -310,18 +429,36 @@ pub struct ResourceLogicConfig {
     pub conditional_equal_config: ConditionalEqualConfig,
     pub conditional_select_config: ConditionalSelectConfig,
     pub extended_or_relation_config: ExtendedOrRelationConfig,
+    pub method_dispatch_config: MethodDispatchConfig,
     pub add_config: AddConfig,
     pub sub_config: SubConfig,
     pub mul_config: MulConfig,
+    pub comparison_config: ComparisonConfig,
     pub blake2s_config: Blake2sConfig<pallas::Base>,
     pub resource_commit_config: ResourceCommitConfig,
+    pub resource_non_membership_config: ResourceNonMembershipConfig,
 }
 
 impl ResourceLogicConfig {
     pub fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self {
+        Self::configure_with_extra_instances(meta, 0)
+    }
+
+    pub fn configure_with_extra_instances(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        extra_instance_num: usize,
+    ) -> Self {
         let instances = meta.instance_column();
         meta.enable_equality(instances);
 
+        let extra_instances: Vec<Column<Instance>> = (0..extra_instance_num)
+            .map(|_| {
+                let column = meta.instance_column();
+                meta.enable_equality(column);
+                column
+            })
+            .collect();
+
         let advices = [
             meta.advice_column(),
             meta.advice_column(),
@@ -374,9 +511,13 @@ impl ResourceLogicConfig {
         let add_config = AddChip::configure(meta, [advices[0], advices[1]]);
         let sub_config = SubChip::configure(meta, [advices[0], advices[1]]);
         let mul_config = MulChip::configure(meta, [advices[0], advices[1]]);
+        let comparison_config =
+            ComparisonConfig::configure(meta, [advices[0], advices[1], advices[2]], range_check);
 
         let extended_or_relation_config =
             ExtendedOrRelationConfig::configure(meta, [advices[0], advices[1], advices[2]]);
+        let method_dispatch_config =
+            MethodDispatchConfig::configure(meta, advices[0..4].try_into().unwrap());
         let blake2s_config = Blake2sConfig::configure(meta, advices);
         let resource_commit_config = ResourceCommitChip::configure(
             meta,
@@ -391,9 +532,17 @@ impl ResourceLogicConfig {
             poseidon_config.clone(),
         );
 
+        let resource_non_membership_config = ResourceNonMembershipConfig::configure(
+            meta,
+            advices[..7].try_into().unwrap(),
+            poseidon_config.clone(),
+            range_check,
+        );
+
         Self {
             advices,
             instances,
+            extra_instances,
             table_idx,
             ecc_config,
             poseidon_config,
@@ -401,31 +550,121 @@ impl ResourceLogicConfig {
             conditional_equal_config,
             conditional_select_config,
             extended_or_relation_config,
+            method_dispatch_config,
             add_config,
             sub_config,
             mul_config,
+            comparison_config,
             blake2s_config,
             resource_commit_config,
+            resource_non_membership_config,
         }
     }
+
+    /// Loads a resource referenced by the logic that must be consumed in the
+    /// same ptx as the self resource, e.g. "this specific resource is spent
+    /// alongside me". The nullifier is derived from the witnessed resource and
+    /// its nk, and checked to be a leaf of the resource tree of the self
+    /// resource. The returned identity is the nullifier.
+    pub fn load_consumed_resource(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        resource_witness: &ResourceExistenceWitness,
+        self_resource: &ResourceStatus,
+    ) -> Result<ResourceStatus, Error> {
+        // The nullifier needs the nk. A resource that is not an input fails the
+        // constraints below rather than the synthesis, so the keys can be
+        // generated from the default witness.
+        if resource_witness.is_input() && resource_witness.get_resource().get_nk().is_none() {
+            return Err(Error::Synthesis);
+        }
+
+        let consumed_resource = load_resource(
+            layouter.namespace(|| "load the consumed resource"),
+            self.advices,
+            ResourceCommitChip::construct(self.resource_commit_config.clone()),
+            self.conditional_select_config,
+            MerklePoseidonChip::construct(self.merkle_config.clone()),
+            resource_witness,
+        )?;
+
+        let constant_one = assign_free_constant(
+            layouter.namespace(|| "one"),
+            self.advices[0],
+            pallas::Base::one(),
+        )?;
+        layouter.assign_region(
+            || "check the consumed resource",
+            |mut region| {
+                region.constrain_equal(consumed_resource.is_input.cell(), constant_one.cell())?;
+                region.constrain_equal(
+                    consumed_resource.resource_merkle_root.cell(),
+                    self_resource.resource_merkle_root.cell(),
+                )
+            },
+        )?;
+
+        Ok(consumed_resource)
+    }
 }
 
 pub trait ResourceLogicVerifyingInfoTrait: DynClone {
-    fn get_verifying_info(&self) -> ResourceLogicVerifyingInfo;
+    fn get_verifying_info(&self) -> Result<ResourceLogicVerifyingInfo, TransactionError> {
+        self.get_verifying_info_with_rng(&mut OsRng)
+    }
+    // Same as `get_verifying_info`, with the randomness of the proof and of the
+    // public input padding taken from `rng`, e.g. a seeded RNG to reproduce it.
+    fn get_verifying_info_with_rng(
+        &self,
+        rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfo, TransactionError> {
+        self.get_verifying_info_with_context(&ProvingContext::default(), rng)
+    }
+    // Same as `get_verifying_info_with_rng`, with the params of `context`.
+    fn get_verifying_info_with_context(
+        &self,
+        context: &ProvingContext,
+        rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfo, TransactionError>;
     fn verify_transparently(&self) -> Result<ResourceLogicPublicInputs, TransactionError>;
-    fn get_resource_logic_vk(&self) -> ResourceLogicVerifyingKey;
+    fn get_resource_logic_vk(&self) -> Result<ResourceLogicVerifyingKey, TransactionError>;
+    // The ids of the owned resources. Empty means the logic only owns the
+    // resource identified by the self resource id in its public inputs.
+    fn get_owned_resource_ids(&self) -> Vec<pallas::Base> {
+        vec![]
+    }
 }
 
 clone_trait_object!(ResourceLogicVerifyingInfoTrait);
 
 pub trait ResourceLogicCircuit: Circuit<pallas::Base> + ResourceLogicVerifyingInfoTrait {
+    // The number of instance columns the logic uses besides the public input
+    // column, e.g. for large public app state. They are configured in
+    // `ResourceLogicConfig::extra_instances`.
+    const EXTRA_INSTANCE_COLUMNS: usize = 0;
+
+    // The size of the params the logic is proven with. Larger logics may use up to
+    // RESOURCE_LOGIC_CIRCUIT_MAX_PARAMS_SIZE, the verifiers read it from the vk.
+    const PARAMS_SIZE: u32 = RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+
     // Load self resource and return self_resource and resource_merkle_root
     // TODO: how to enforce the constraints in resource_logic circuit?
     fn basic_constraints(
         &self,
         config: ResourceLogicConfig,
-        mut layouter: impl Layouter<pallas::Base>,
+        layouter: impl Layouter<pallas::Base>,
     ) -> Result<ResourceStatus, Error> {
+        let mut owned_resources = self.owned_resources_constraints(config, layouter)?;
+        Ok(owned_resources.swap_remove(0))
+    }
+
+    // Load all the owned resources, publicize the resource_merkle_root and the
+    // hash of the owned resource ids, and return the owned resources
+    fn owned_resources_constraints(
+        &self,
+        config: ResourceLogicConfig,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<Vec<ResourceStatus>, Error> {
         layouter.assign_table(
             || "table_idx",
             |mut table| {
@@ -441,37 +680,70 @@ pub trait ResourceLogicCircuit: Circuit<pallas::Base> + ResourceLogicVerifyingIn
             },
         )?;
 
-        // Construct a merkle chip
-        let merkle_chip = MerklePoseidonChip::construct(config.merkle_config);
-        // Construct a resource_commit chip
-        let resource_commit_chip =
-            ResourceCommitChip::construct(config.resource_commit_config.clone());
-
-        // Load self_resource
-        let self_resource_status = load_resource(
-            layouter.namespace(|| "load self resource"),
-            config.advices,
-            resource_commit_chip,
-            config.conditional_select_config,
-            merkle_chip,
-            &self.get_self_resource(),
-        )?;
+        let owned_resources = self.get_owned_resources();
+        if owned_resources.is_empty() {
+            return Err(Error::Synthesis);
+        }
+
+        // Load the owned resources
+        let owned_resource_status = owned_resources
+            .iter()
+            .map(|resource_witness| {
+                // Construct a merkle chip
+                let merkle_chip = MerklePoseidonChip::construct(config.merkle_config.clone());
+                // Construct a resource_commit chip
+                let resource_commit_chip =
+                    ResourceCommitChip::construct(config.resource_commit_config.clone());
+                load_resource(
+                    layouter.namespace(|| "load self resource"),
+                    config.advices,
+                    resource_commit_chip,
+                    config.conditional_select_config,
+                    merkle_chip,
+                    resource_witness,
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // All the owned resources are in the same resource tree
+        let resource_merkle_root = owned_resource_status[0].resource_merkle_root.clone();
+        for status in owned_resource_status.iter().skip(1) {
+            layouter.assign_region(
+                || "constrain resource_merkle_root",
+                |mut region| {
+                    region.constrain_equal(
+                        resource_merkle_root.cell(),
+                        status.resource_merkle_root.cell(),
+                    )
+                },
+            )?;
+        }
+
+        // Hash the owned resource ids
+        let mut owned_resources_id = owned_resource_status[0].identity.clone();
+        for status in owned_resource_status.iter().skip(1) {
+            owned_resources_id = poseidon_hash_gadget(
+                config.poseidon_config.clone(),
+                layouter.namespace(|| "owned resources hash"),
+                [owned_resources_id, status.identity.clone()],
+            )?;
+        }
 
         // Publicize the resource_merkle_root
         layouter.constrain_instance(
-            self_resource_status.resource_merkle_root.cell(),
+            resource_merkle_root.cell(),
             config.instances,
             RESOURCE_LOGIC_CIRCUIT_RESOURCE_MERKLE_ROOT_IDX,
         )?;
 
-        // Publicize the self resource id
+        // Publicize the self resource id, or the hash of the owned resource ids
         layouter.constrain_instance(
-            self_resource_status.identity.cell(),
+            owned_resources_id.cell(),
             config.instances,
             RESOURCE_LOGIC_CIRCUIT_SELF_RESOURCE_ID_IDX,
         )?;
 
-        Ok(self_resource_status)
+        Ok(owned_resource_status)
     }
 
     // Add custom constraints on basic resource variables and user-defined variables.
@@ -493,15 +765,327 @@ pub trait ResourceLogicCircuit: Circuit<pallas::Base> + ResourceLogicVerifyingIn
     }
 
     fn get_mandatory_public_inputs(&self) -> Vec<pallas::Base> {
-        let resource_witness = self.get_self_resource();
-        let root = resource_witness.get_root();
-        let id = resource_witness.get_identity();
-        vec![root, id]
+        let owned_resources = self.get_owned_resources();
+        let root = owned_resources[0].get_root();
+        let ids: Vec<pallas::Base> = owned_resources
+            .iter()
+            .map(|resource_witness| resource_witness.get_identity())
+            .collect();
+        vec![root, owned_resources_hash(&ids)]
     }
 
     fn get_public_inputs(&self, rng: impl RngCore) -> ResourceLogicPublicInputs;
 
+    // The values of the extra instance columns, one vector per column
+    fn get_extra_instances(&self) -> Vec<Vec<pallas::Base>> {
+        vec![]
+    }
+
+    // The instances of all the columns, checked against the declared layout
+    fn get_instances(&self, public_inputs: &ResourceLogicPublicInputs) -> Vec<Vec<pallas::Base>> {
+        let extra_instances = self.get_extra_instances();
+        assert_eq!(
+            extra_instances.len(),
+            Self::EXTRA_INSTANCE_COLUMNS,
+            "extra instances don't match the declared instance columns"
+        );
+        std::iter::once(public_inputs.to_vec())
+            .chain(extra_instances)
+            .collect()
+    }
+
     fn get_self_resource(&self) -> ResourceExistenceWitness;
+
+    // The resources claimed by the logic. A logic can own several resources,
+    // e.g. a pool logic owning all the pool-token resources.
+    fn get_owned_resources(&self) -> Vec<ResourceExistenceWitness> {
+        vec![self.get_self_resource()]
+    }
+
+    // The custom constraints evaluated on the field values, without
+    // synthesizing the circuit. None if the logic can only be checked with the
+    // MockProver.
+    fn native_custom_constraints(&self) -> Option<Result<(), TransactionError>> {
+        None
+    }
+
+    // Checks the basic and custom constraints natively. The public inputs
+    // are computed natively anyway, so they don't need to be checked.
+    fn verify_natively(&self) -> Option<Result<(), TransactionError>> {
+        let custom_constraints = self.native_custom_constraints()?;
+        let owned_resources = self.get_owned_resources();
+        // The nullifiers of input resources need the nullifier key
+        if owned_resources.iter().any(|resource_witness| {
+            resource_witness.is_input() && resource_witness.get_resource().get_nf().is_none()
+        }) {
+            return Some(Err(
+                TransactionError::MissingTransparentResourceNullifierKey,
+            ));
+        }
+        // All the owned resources are in the same resource tree
+        let root = owned_resources[0].get_root();
+        if owned_resources
+            .iter()
+            .any(|resource_witness| resource_witness.get_root() != root)
+        {
+            return Some(Err(TransactionError::InconsistentResourceMerkleRoot));
+        }
+        Some(custom_constraints)
+    }
+}
+
+/// Runs the MockProver on a resource logic over all its instance columns.
+pub fn mock_prove_resource_logic<C: ResourceLogicCircuit>(
+    circuit: &C,
+    public_inputs: &ResourceLogicPublicInputs,
+) -> MockProver<pallas::Base> {
+    MockProver::<pallas::Base>::run(
+        C::PARAMS_SIZE,
+        circuit,
+        circuit.get_instances(public_inputs),
+    )
+    .unwrap()
+}
+
+/// A condition over the self resource that `ComposedResourceLogicCircuit`
+/// combines with another. Unlike `ResourceLogicCircuit::custom_constraints`,
+/// the condition doesn't enforce itself but returns a cell that is zero iff it
+/// holds, so that it can also be used in a disjunction.
+pub trait ResourceLogicCondition: Clone + std::fmt::Debug + Default {
+    fn condition(
+        &self,
+        config: &ResourceLogicConfig,
+        layouter: impl Layouter<pallas::Base>,
+        self_resource: &ResourceStatus,
+    ) -> Result<ConditionStatus, Error>;
+
+    // The values of the cells publicized by the condition
+    fn get_public_inputs(&self) -> Vec<pallas::Base> {
+        vec![]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConditionStatus {
+    /// Zero iff the condition holds
+    pub error: AssignedCell<pallas::Base, pallas::Base>,
+    /// The cells to publicize as custom public inputs
+    pub public_inputs: Vec<AssignedCell<pallas::Base, pallas::Base>>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub enum ResourceLogicComposition {
+    #[default]
+    And,
+    Or,
+}
+
+/// The logic `A AND B` or `A OR B` of two conditions. The public inputs of the
+/// conditions are merged as the custom public inputs, the ones of A first.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComposedResourceLogicCircuit<A, B> {
+    pub self_resource: ResourceExistenceWitness,
+    pub composition: ResourceLogicComposition,
+    pub a: A,
+    pub b: B,
+}
+
+impl<A: ResourceLogicCondition, B: ResourceLogicCondition> ComposedResourceLogicCircuit<A, B> {
+    pub fn and(self_resource: ResourceExistenceWitness, a: A, b: B) -> Self {
+        Self {
+            self_resource,
+            composition: ResourceLogicComposition::And,
+            a,
+            b,
+        }
+    }
+
+    pub fn or(self_resource: ResourceExistenceWitness, a: A, b: B) -> Self {
+        Self {
+            self_resource,
+            composition: ResourceLogicComposition::Or,
+            a,
+            b,
+        }
+    }
+}
+
+impl<A, B> ResourceLogicCircuit for ComposedResourceLogicCircuit<A, B>
+where
+    A: ResourceLogicCondition,
+    B: ResourceLogicCondition,
+{
+    fn custom_constraints(
+        &self,
+        config: ResourceLogicConfig,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        let a = self.a.condition(
+            &config,
+            layouter.namespace(|| "condition a"),
+            &self_resource,
+        )?;
+        let b = self.b.condition(
+            &config,
+            layouter.namespace(|| "condition b"),
+            &self_resource,
+        )?;
+
+        // AND: both errors are zero, OR: the product of the errors is zero
+        let errors = match self.composition {
+            ResourceLogicComposition::And => vec![a.error, b.error],
+            ResourceLogicComposition::Or => {
+                let mul_chip = MulChip::construct(config.mul_config.clone());
+                vec![mul_chip.mul(layouter.namespace(|| "a * b"), &a.error, &b.error)?]
+            }
+        };
+        for error in errors.iter() {
+            layouter.assign_region(
+                || "check composition",
+                |mut region| region.constrain_constant(error.cell(), pallas::Base::zero()),
+            )?;
+        }
+
+        // Publicize the public inputs of the conditions
+        for (i, cell) in a
+            .public_inputs
+            .iter()
+            .chain(b.public_inputs.iter())
+            .enumerate()
+        {
+            layouter.constrain_instance(
+                cell.cell(),
+                config.instances,
+                RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX + i,
+            )?;
+        }
+
+        // Publicize the dynamic resource_logic commitments with default value
+        publicize_default_dynamic_resource_logic_commitments(
+            &mut layouter,
+            config.advices[0],
+            config.instances,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.extend(default_resource_logic_cm);
+        let custom_public_inputs: Vec<pallas::Base> = self
+            .a
+            .get_public_inputs()
+            .into_iter()
+            .chain(self.b.get_public_inputs())
+            .collect();
+        assert!(
+            custom_public_inputs.len() <= RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_NUM,
+            "The conditions publicize too many public inputs"
+        );
+        public_inputs.extend(custom_public_inputs);
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+}
+
+impl<A, B> Circuit<pallas::Base> for ComposedResourceLogicCircuit<A, B>
+where
+    A: ResourceLogicCondition,
+    B: ResourceLogicCondition,
+{
+    type Config = ResourceLogicConfig;
+    type FloorPlanner = floor_planner::V1;
+
+    // The conditions may set constants of the circuit, keep them
+    fn without_witnesses(&self) -> Self {
+        Self {
+            self_resource: ResourceExistenceWitness::default(),
+            composition: self.composition,
+            a: self.a.clone(),
+            b: self.b.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        Self::Config::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        let self_resource =
+            self.basic_constraints(config.clone(), layouter.namespace(|| "basic constraints"))?;
+        self.custom_constraints(
+            config,
+            layouter.namespace(|| "custom constraints"),
+            self_resource,
+        )
+    }
+}
+
+impl<A, B> ResourceLogicVerifyingInfoTrait for ComposedResourceLogicCircuit<A, B>
+where
+    A: ResourceLogicCondition,
+    B: ResourceLogicCondition,
+{
+    fn get_verifying_info_with_context(
+        &self,
+        context: &ProvingContext,
+        mut rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfo, TransactionError> {
+        let params = context.params(<Self as ResourceLogicCircuit>::PARAMS_SIZE)?;
+        let vk = keygen_vk(params, self).map_err(TransactionError::Keygen)?;
+        let pk = keygen_pk(params, vk.clone(), self).map_err(TransactionError::Keygen)?;
+        let public_inputs = self.get_public_inputs(&mut rng);
+        let extra_instances = self.get_instances(&public_inputs).split_off(1);
+        let proof = Proof::create(
+            &pk,
+            params,
+            self.clone(),
+            &public_inputs.with_extra_instances(&extra_instances),
+            &mut rng,
+        )?;
+        Ok(ResourceLogicVerifyingInfo {
+            vk,
+            proof,
+            public_inputs,
+            extra_instances,
+        })
+    }
+
+    fn verify_transparently(&self) -> Result<ResourceLogicPublicInputs, TransactionError> {
+        let mut rng = OsRng;
+        let public_inputs = self.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(self, &public_inputs);
+        prover
+            .verify()
+            .map_err(|_| TransactionError::UnsatisfiedResourceLogic)?;
+        Ok(public_inputs)
+    }
+
+    fn get_resource_logic_vk(&self) -> Result<ResourceLogicVerifyingKey, TransactionError> {
+        let params = get_params(<Self as ResourceLogicCircuit>::PARAMS_SIZE)?;
+        let vk = keygen_vk(params, self).map_err(TransactionError::Keygen)?;
+        Ok(ResourceLogicVerifyingKey::from_vk(vk))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -512,6 +1096,14 @@ pub struct ResourceStatus {
     pub resource: ResourceVariables,
 }
 
+// A resource read by the ptx, see `load_read_resource`
+#[derive(Debug, Clone)]
+pub struct ReadResourceStatus {
+    pub resource_merkle_root: AssignedCell<pallas::Base, pallas::Base>,
+    pub identity: AssignedCell<pallas::Base, pallas::Base>, // read tag
+    pub resource: ResourceVariables,
+}
+
 #[derive(Debug, Clone)]
 pub struct ResourceVariables {
     pub logic: AssignedCell<pallas::Base, pallas::Base>,
@@ -545,7 +1137,10 @@ macro_rules! resource_logic_circuit_impl {
             }
 
             fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
-                Self::Config::configure(meta)
+                Self::Config::configure_with_extra_instances(
+                    meta,
+                    <Self as ResourceLogicCircuit>::EXTRA_INSTANCE_COLUMNS,
+                )
             }
 
             fn synthesize(
@@ -553,6 +1148,7 @@ macro_rules! resource_logic_circuit_impl {
                 config: Self::Config,
                 mut layouter: impl Layouter<pallas::Base>,
             ) -> Result<(), Error> {
+                let _span = $crate::trace::enter("synthesize", stringify!($name));
                 let self_resource = self.basic_constraints(
                     config.clone(),
                     layouter.namespace(|| "basic constraints"),
@@ -573,301 +1169,86 @@ macro_rules! resource_logic_circuit_impl {
 macro_rules! resource_logic_verifying_info_impl {
     ($name:ident) => {
         impl ResourceLogicVerifyingInfoTrait for $name {
-            fn get_verifying_info(&self) -> ResourceLogicVerifyingInfo {
-                let mut rng = OsRng;
-                let params = SETUP_PARAMS_MAP.get(&15).unwrap();
-                let vk = keygen_vk(params, self).expect("keygen_vk should not fail");
-                let pk = keygen_pk(params, vk.clone(), self).expect("keygen_pk should not fail");
+            fn get_verifying_info_with_context(
+                &self,
+                context: &$crate::params::ProvingContext,
+                mut rng: &mut dyn RngCore,
+            ) -> Result<ResourceLogicVerifyingInfo, TransactionError> {
+                let params = context.params(
+                    <$name as $crate::circuit::resource_logic_circuit::ResourceLogicCircuit>::PARAMS_SIZE,
+                )?;
+                let (vk, pk) = {
+                    let _span = $crate::trace::enter("keygen", stringify!($name));
+                    let vk = keygen_vk(params, self).map_err(TransactionError::Keygen)?;
+                    let pk =
+                        keygen_pk(params, vk.clone(), self).map_err(TransactionError::Keygen)?;
+                    (vk, pk)
+                };
                 let public_inputs = self.get_public_inputs(&mut rng);
+                let extra_instances = self.get_instances(&public_inputs).split_off(1);
+                let _span = $crate::trace::enter("prove", stringify!($name));
                 let proof = Proof::create(
                     &pk,
                     params,
                     self.clone(),
-                    &[public_inputs.inner()],
+                    &public_inputs.with_extra_instances(&extra_instances),
                     &mut rng,
-                )
-                .unwrap();
-                ResourceLogicVerifyingInfo {
+                )?;
+                Ok(ResourceLogicVerifyingInfo {
                     vk,
                     proof,
                     public_inputs,
-                }
+                    extra_instances,
+                })
             }
 
             fn verify_transparently(&self) -> Result<ResourceLogicPublicInputs, TransactionError> {
-                use halo2_proofs::dev::MockProver;
+                let _span = $crate::trace::enter("verify_transparently", stringify!($name));
                 let mut rng = OsRng;
+                // Skip the circuit synthesis if the logic can be evaluated natively
+                if let Some(result) = self.verify_natively() {
+                    result?;
+                    return Ok(self.get_public_inputs(&mut rng));
+                }
                 let public_inputs = self.get_public_inputs(&mut rng);
-                let prover =
-                    MockProver::<pallas::Base>::run(15, self, vec![public_inputs.to_vec()])
-                        .unwrap();
-                prover.verify().unwrap();
+                let prover = $crate::circuit::resource_logic_circuit::mock_prove_resource_logic(
+                    self,
+                    &public_inputs,
+                );
+                prover
+                    .verify()
+                    .map_err(|_| TransactionError::UnsatisfiedResourceLogic)?;
                 Ok(public_inputs)
             }
 
-            fn get_resource_logic_vk(&self) -> ResourceLogicVerifyingKey {
-                let params = SETUP_PARAMS_MAP.get(&15).unwrap();
-                let vk = keygen_vk(params, self).expect("keygen_vk should not fail");
-                ResourceLogicVerifyingKey::from_vk(vk)
+            fn get_resource_logic_vk(&self) -> Result<ResourceLogicVerifyingKey, TransactionError> {
+                let params = $crate::params::get_params(
+                    <$name as $crate::circuit::resource_logic_circuit::ResourceLogicCircuit>::PARAMS_SIZE,
+                )?;
+                let _span = $crate::trace::enter("keygen_vk", stringify!($name));
+                let vk = keygen_vk(params, self).map_err(TransactionError::Keygen)?;
+                Ok(ResourceLogicVerifyingKey::from_vk(vk))
             }
-        }
-    };
-}
 
-#[derive(Clone)]
-pub struct VampIRResourceLogicCircuit {
-    // TODO: vamp_ir doesn't support to set the params size manually, add the params here temporarily.
-    // remove the params once we can set it as RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE in vamp_ir.
-    pub params: Params<vesta::Affine>,
-    pub circuit: Halo2Module<pallas::Base>,
-    pub public_inputs: Vec<pallas::Base>,
-}
-
-#[derive(Debug)]
-pub enum VampIRCircuitError {
-    MissingAssignment(String),
-    SourceParsingError(String),
-}
-
-impl VampIRCircuitError {
-    fn from_variable_assignment_error(error: VariableAssignmentError) -> Self {
-        match error {
-            VariableAssignmentError::MissingAssignment(s) => {
-                VampIRCircuitError::MissingAssignment(s)
+            fn get_owned_resource_ids(&self) -> Vec<pallas::Base> {
+                self.get_owned_resources()
+                    .iter()
+                    .map(|resource_witness| resource_witness.get_identity())
+                    .collect()
             }
         }
-    }
-}
-
-impl VampIRResourceLogicCircuit {
-    pub fn from_vamp_ir_source(
-        vamp_ir_source: &str,
-        named_field_assignments: HashMap<String, Fp>,
-    ) -> Result<Self, VampIRCircuitError> {
-        let config = Config { quiet: true };
-        let parsed_vamp_ir_module =
-            parse(vamp_ir_source).map_err(VampIRCircuitError::SourceParsingError)?;
-        let vamp_ir_module = compile(
-            parsed_vamp_ir_module,
-            &PrimeFieldOps::<Fp>::default(),
-            &config,
-        );
-        let mut circuit = Halo2Module::<Fp>::new(Rc::new(vamp_ir_module));
-        let params = Params::new(circuit.k);
-        let field_assignments = get_circuit_assignments(&circuit.module, &named_field_assignments)
-            .map_err(VampIRCircuitError::from_variable_assignment_error)?;
-
-        // Populate variable definitions
-        circuit.populate_variables(field_assignments.clone());
-
-        // Get public inputs Fp
-        let public_inputs = circuit
-            .module
-            .pubs
-            .iter()
-            .map(|inst| field_assignments[&inst.id])
-            .collect::<Vec<pallas::Base>>();
-
-        Ok(Self {
-            params,
-            circuit,
-            public_inputs,
-        })
-    }
-
-    pub fn from_vamp_ir_file(vamp_ir_file: &PathBuf, inputs_file: &PathBuf) -> Self {
-        let config = Config { quiet: true };
-        let vamp_ir_source = fs::read_to_string(vamp_ir_file).expect("cannot read vamp-ir file");
-        let parsed_vamp_ir_module = Module::parse(&vamp_ir_source).unwrap();
-        let vamp_ir_module = compile(
-            parsed_vamp_ir_module,
-            &PrimeFieldOps::<Fp>::default(),
-            &config,
-        );
-        let mut circuit = Halo2Module::<Fp>::new(Rc::new(vamp_ir_module));
-        let params: Params<EqAffine> = Params::new(circuit.k);
-
-        let var_assignments_ints = read_inputs_from_file(&circuit.module, inputs_file);
-        let mut var_assignments = HashMap::new();
-        for (k, v) in var_assignments_ints {
-            var_assignments.insert(k, make_constant(v));
-        }
-
-        // Populate variable definitions
-        circuit.populate_variables(var_assignments.clone());
-
-        // Get public inputs Fp
-        let public_inputs = circuit
-            .module
-            .pubs
-            .iter()
-            .map(|inst| var_assignments[&inst.id])
-            .collect::<Vec<pallas::Base>>();
-
-        Self {
-            params,
-            circuit,
-            public_inputs,
-        }
-    }
-}
-
-impl ResourceLogicVerifyingInfoTrait for VampIRResourceLogicCircuit {
-    fn get_verifying_info(&self) -> ResourceLogicVerifyingInfo {
-        let mut rng = OsRng;
-        let vk = keygen_vk(&self.params, &self.circuit).expect("keygen_vk should not fail");
-        let pk =
-            keygen_pk(&self.params, vk.clone(), &self.circuit).expect("keygen_pk should not fail");
-
-        let mut public_inputs = self.public_inputs.clone();
-        let rseed = RandomSeed::random(&mut rng);
-        public_inputs.extend(ResourceLogicPublicInputs::get_public_input_padding(
-            self.public_inputs.len(),
-            &rseed,
-        ));
-
-        let proof = Proof::create(
-            &pk,
-            &self.params,
-            self.circuit.clone(),
-            &[&public_inputs.to_vec()],
-            &mut rng,
-        )
-        .unwrap();
-        ResourceLogicVerifyingInfo {
-            vk,
-            proof,
-            public_inputs: public_inputs.into(),
-        }
-    }
-
-    fn verify_transparently(&self) -> Result<ResourceLogicPublicInputs, TransactionError> {
-        use halo2_proofs::dev::MockProver;
-        let mut rng = OsRng;
-        let mut public_inputs = self.public_inputs.clone();
-        let rseed = RandomSeed::random(&mut rng);
-        public_inputs.extend(ResourceLogicPublicInputs::get_public_input_padding(
-            self.public_inputs.len(),
-            &rseed,
-        ));
-        let prover =
-            MockProver::<pallas::Base>::run(15, &self.circuit, vec![public_inputs.to_vec()])
-                .unwrap();
-        prover.verify().unwrap();
-        Ok(ResourceLogicPublicInputs::from(public_inputs))
-    }
-
-    fn get_resource_logic_vk(&self) -> ResourceLogicVerifyingKey {
-        let vk = keygen_vk(&self.params, &self.circuit).expect("keygen_vk should not fail");
-        ResourceLogicVerifyingKey::from_vk(vk)
-    }
+    };
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::circuit::resource_logic_circuit::{
-        ResourceLogicVerifyingInfoTrait, VampIRResourceLogicCircuit,
-    };
-    use num_bigint::BigInt;
-    use std::collections::HashMap;
-    use std::path::PathBuf;
-    use vamp_ir::halo2::synth::make_constant;
-
-    #[ignore]
-    #[test]
-    fn test_create_resource_logic_from_vamp_ir_file() {
-        let vamp_ir_circuit_file = PathBuf::from("./src/circuit/vamp_ir_circuits/pyth.pir");
-        let inputs_file = PathBuf::from("./src/circuit/vamp_ir_circuits/pyth.inputs");
-        let resource_logic_circuit =
-            VampIRResourceLogicCircuit::from_vamp_ir_file(&vamp_ir_circuit_file, &inputs_file);
-
-        // generate proof and instance
-        let resource_logic_info = resource_logic_circuit.get_verifying_info();
-
-        // verify the proof
-        // TODO: use the resource_logic_info.verify() instead. resource_logic_info.verify() doesn't work now because it uses the fixed RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE params.
-        resource_logic_info
-            .proof
-            .verify(
-                &resource_logic_info.vk,
-                &resource_logic_circuit.params,
-                &[resource_logic_info.public_inputs.inner()],
-            )
-            .unwrap();
-    }
-
-    #[test]
-    fn test_create_resource_logic_from_invalid_vamp_ir_file() {
-        let invalid_vamp_ir_source =
-            VampIRResourceLogicCircuit::from_vamp_ir_source("{aaxxx", HashMap::new());
-        assert!(invalid_vamp_ir_source.is_err());
-    }
-
-    #[test]
-    fn test_create_resource_logic_with_missing_assignment() {
-        let missing_x_assignment =
-            VampIRResourceLogicCircuit::from_vamp_ir_source("x = 1;", HashMap::new());
-        assert!(missing_x_assignment.is_err());
-    }
-
-    #[test]
-    fn test_create_resource_logic_with_no_assignment() {
-        let zero_constraint = VampIRResourceLogicCircuit::from_vamp_ir_source("0;", HashMap::new());
-        assert!(zero_constraint.is_ok());
-    }
-
-    #[ignore]
-    #[test]
-    fn test_create_resource_logic_with_valid_assignment() {
-        let x_assignment_circuit = VampIRResourceLogicCircuit::from_vamp_ir_source(
-            "x = 1;",
-            HashMap::from([(String::from("x"), make_constant(BigInt::from(1)))]),
-        );
-
-        assert!(x_assignment_circuit.is_ok());
-
-        let resource_logic_circuit = x_assignment_circuit.unwrap();
-        let resource_logic_info = resource_logic_circuit.get_verifying_info();
-
-        assert!(resource_logic_info
-            .proof
-            .verify(
-                &resource_logic_info.vk,
-                &resource_logic_circuit.params,
-                &[resource_logic_info.public_inputs.inner()]
-            )
-            .is_ok());
-    }
-
-    #[ignore]
-    #[test]
-    fn test_create_resource_logic_with_invalid_assignment() {
-        let x_assignment_circuit = VampIRResourceLogicCircuit::from_vamp_ir_source(
-            "x = 1;",
-            HashMap::from([(String::from("x"), make_constant(BigInt::from(0)))]),
-        );
-
-        assert!(x_assignment_circuit.is_ok());
-
-        let resource_logic_circuit = x_assignment_circuit.unwrap();
-        let resource_logic_info = resource_logic_circuit.get_verifying_info();
-
-        assert!(resource_logic_info
-            .proof
-            .verify(
-                &resource_logic_info.vk,
-                &resource_logic_circuit.params,
-                &[resource_logic_info.public_inputs.inner()]
-            )
-            .is_err());
-    }
-
     #[cfg(feature = "serde")]
     #[test]
     fn test_vk_serialize() {
         use crate::circuit::{
             resource_logic_circuit::{
                 serde_deserialize_verifying_key, serde_serialize_verifying_key,
+                ResourceLogicVerifyingInfoTrait,
             },
             resource_logic_examples::TrivialResourceLogicCircuit,
         };
@@ -884,7 +1265,9 @@ mod tests {
             vk: VerifyingKey<vesta::Affine>,
         }
 
-        let t = TrivialResourceLogicCircuit::default().get_resource_logic_vk();
+        let t = TrivialResourceLogicCircuit::default()
+            .get_resource_logic_vk()
+            .unwrap();
 
         let a = TestStruct {
             vk: t.get_vk().unwrap(),
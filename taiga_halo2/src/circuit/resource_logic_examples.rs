@@ -8,6 +8,7 @@ use crate::{
     constant::{RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE, SETUP_PARAMS_MAP, TAIGA_RESOURCE_TREE_DEPTH},
     error::TransactionError,
     merkle_tree::LR,
+    params::ProvingContext,
     proof::Proof,
     resource::{RandomSeed, Resource},
     resource_logic_commitment::ResourceLogicCommitment,
@@ -24,33 +25,54 @@ use halo2_proofs::{
 use lazy_static::lazy_static;
 use pasta_curves::{pallas, vesta};
 use rand::{rngs::OsRng, RngCore};
+use std::borrow::Cow;
 
+#[cfg(feature = "apps")]
+pub mod amm;
+#[cfg(feature = "examples")]
+pub mod blacklist;
+#[cfg(feature = "examples")]
+pub mod channel;
+#[cfg(feature = "apps")]
+pub mod disjunction;
 #[cfg(feature = "examples")]
 mod field_addition;
 #[cfg(feature = "examples")]
-pub mod or_relation_intent;
+pub mod identity_provider;
+#[cfg(feature = "apps")]
+pub mod intent;
+#[cfg(feature = "apps")]
+pub mod nft;
 #[cfg(feature = "examples")]
+pub mod or_relation_intent;
+#[cfg(feature = "apps")]
 pub mod partial_fulfillment_intent;
-#[cfg(feature = "examples")]
+#[cfg(feature = "apps")]
 pub mod receiver_resource_logic;
 #[cfg(feature = "examples")]
+pub mod signature_ownership;
+#[cfg(feature = "apps")]
 pub mod signature_verification;
-#[cfg(feature = "examples")]
+#[cfg(feature = "apps")]
+pub mod solver_fee;
+#[cfg(feature = "apps")]
+pub mod sweep;
+#[cfg(feature = "apps")]
+pub mod timelock;
+#[cfg(feature = "apps")]
 pub mod token;
+#[cfg(feature = "apps")]
+pub mod voting;
 
 lazy_static! {
     pub static ref TRIVIAL_RESOURCE_LOGIC_VK: ResourceLogicVerifyingKey = {
-        let params = SETUP_PARAMS_MAP
-            .get(&RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE)
-            .unwrap();
+        let params = &SETUP_PARAMS_MAP[&RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE];
         let empty_circuit = TrivialResourceLogicCircuit::default();
         let vk = keygen_vk(params, &empty_circuit).expect("keygen_vk should not fail");
         ResourceLogicVerifyingKey::from_vk(vk)
     };
     pub static ref TRIVIAL_RESOURCE_LOGIC_PK: ProvingKey<vesta::Affine> = {
-        let params = SETUP_PARAMS_MAP
-            .get(&RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE)
-            .unwrap();
+        let params = &SETUP_PARAMS_MAP[&RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE];
         let empty_circuit = TrivialResourceLogicCircuit::default();
         keygen_pk(
             params,
@@ -107,44 +129,63 @@ impl ResourceLogicCircuit for TrivialResourceLogicCircuit {
     }
 
     fn get_self_resource(&self) -> ResourceExistenceWitness {
-        self.self_resource
+        self.self_resource.clone()
+    }
+
+    fn native_custom_constraints(&self) -> Option<Result<(), TransactionError>> {
+        Some(Ok(()))
     }
 }
 
 resource_logic_circuit_impl!(TrivialResourceLogicCircuit);
 
 impl ResourceLogicVerifyingInfoTrait for TrivialResourceLogicCircuit {
-    fn get_verifying_info(&self) -> ResourceLogicVerifyingInfo {
-        let mut rng = OsRng;
-        let params = SETUP_PARAMS_MAP.get(&15).unwrap();
+    fn get_verifying_info_with_context(
+        &self,
+        context: &ProvingContext,
+        mut rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfo, TransactionError> {
+        let params = context.params(RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE)?;
+        // The precomputed keys are derived from the global params
+        let pk = if context.has_params(RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE) {
+            let vk = keygen_vk(params, self).map_err(TransactionError::Keygen)?;
+            Cow::Owned(keygen_pk(params, vk, self).map_err(TransactionError::Keygen)?)
+        } else {
+            Cow::Borrowed(&*TRIVIAL_RESOURCE_LOGIC_PK)
+        };
         let public_inputs = self.get_public_inputs(&mut rng);
         let proof = Proof::create(
-            &TRIVIAL_RESOURCE_LOGIC_PK,
+            &pk,
             params,
             self.clone(),
             &[public_inputs.inner()],
             &mut rng,
-        )
-        .unwrap();
-        ResourceLogicVerifyingInfo {
-            vk: TRIVIAL_RESOURCE_LOGIC_PK.get_vk().clone(),
+        )?;
+        Ok(ResourceLogicVerifyingInfo {
+            vk: pk.get_vk().clone(),
             proof,
             public_inputs,
-        }
+            extra_instances: vec![],
+        })
     }
 
     fn verify_transparently(&self) -> Result<ResourceLogicPublicInputs, TransactionError> {
         use halo2_proofs::dev::MockProver;
         let mut rng = OsRng;
+        if let Some(result) = self.verify_natively() {
+            result?;
+            return Ok(self.get_public_inputs(&mut rng));
+        }
         let public_inputs = self.get_public_inputs(&mut rng);
-        let prover =
-            MockProver::<pallas::Base>::run(15, self, vec![public_inputs.to_vec()]).unwrap();
-        prover.verify().unwrap();
+        let prover = MockProver::<pallas::Base>::run(15, self, vec![public_inputs.to_vec()])?;
+        prover
+            .verify()
+            .map_err(|_| TransactionError::UnsatisfiedResourceLogic)?;
         Ok(public_inputs)
     }
 
-    fn get_resource_logic_vk(&self) -> ResourceLogicVerifyingKey {
-        TRIVIAL_RESOURCE_LOGIC_VK.clone()
+    fn get_resource_logic_vk(&self) -> Result<ResourceLogicVerifyingKey, TransactionError> {
+        Ok(TRIVIAL_RESOURCE_LOGIC_VK.clone())
     }
 }
 
@@ -172,4 +213,1346 @@ pub mod tests {
         .unwrap();
         assert_eq!(prover.verify(), Ok(()));
     }
+
+    #[test]
+    fn test_halo2_owned_resources_resource_logic_circuit() {
+        use super::*;
+        use crate::constant::RESOURCE_LOGIC_CIRCUIT_SELF_RESOURCE_ID_IDX;
+        use crate::resource::tests::random_resource;
+        use crate::resource_tree::{owned_resources_hash, ResourceMerkleTreeLeaves};
+        use halo2_proofs::dev::MockProver;
+
+        // A logic owning several resources with empty custom constraints
+        #[derive(Clone, Debug, Default)]
+        struct OwnedResourcesCircuit {
+            owned_resources: Vec<ResourceExistenceWitness>,
+        }
+
+        impl ResourceLogicCircuit for OwnedResourcesCircuit {
+            fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+                let mut public_inputs = self.get_mandatory_public_inputs();
+                let default_resource_logic_cm: [pallas::Base; 2] =
+                    ResourceLogicCommitment::default().to_public_inputs();
+                public_inputs.extend(default_resource_logic_cm);
+                public_inputs.extend(default_resource_logic_cm);
+                let padding = ResourceLogicPublicInputs::get_public_input_padding(
+                    public_inputs.len(),
+                    &RandomSeed::random(&mut rng),
+                );
+                public_inputs.extend(padding);
+                public_inputs.into()
+            }
+
+            fn get_self_resource(&self) -> ResourceExistenceWitness {
+                self.owned_resources[0].clone()
+            }
+
+            fn get_owned_resources(&self) -> Vec<ResourceExistenceWitness> {
+                self.owned_resources.clone()
+            }
+        }
+
+        resource_logic_circuit_impl!(OwnedResourcesCircuit);
+        resource_logic_verifying_info_impl!(OwnedResourcesCircuit);
+
+        let mut rng = OsRng;
+        let input_resources = [random_resource(&mut rng), random_resource(&mut rng)];
+        let output_resources = [random_resource(&mut rng), random_resource(&mut rng)];
+        let nfs: Vec<pallas::Base> = input_resources
+            .iter()
+            .map(|resource| resource.get_nf().unwrap().inner())
+            .collect();
+        let resource_merkle_tree = ResourceMerkleTreeLeaves::new(vec![
+            nfs[0],
+            output_resources[0].commitment().inner(),
+            nfs[1],
+            output_resources[1].commitment().inner(),
+        ]);
+        let owned_resources = input_resources
+            .iter()
+            .zip(nfs.iter())
+            .map(|(resource, nf)| {
+                let merkle_path = resource_merkle_tree.generate_path(*nf).unwrap();
+                ResourceExistenceWitness::new(resource.clone(), merkle_path)
+            })
+            .collect();
+        let circuit = OwnedResourcesCircuit { owned_resources };
+        assert_eq!(circuit.get_owned_resource_ids(), nfs);
+
+        let mut public_inputs = circuit.get_public_inputs(&mut rng).to_vec();
+        assert_eq!(
+            public_inputs[RESOURCE_LOGIC_CIRCUIT_SELF_RESOURCE_ID_IDX],
+            owned_resources_hash(&nfs)
+        );
+        let prover = MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![public_inputs.clone()],
+        )
+        .unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // Publishing only the first owned resource must fail
+        public_inputs[RESOURCE_LOGIC_CIRCUIT_SELF_RESOURCE_ID_IDX] = nfs[0];
+        let prover = MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![public_inputs],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_halo2_extra_instance_resource_logic_circuit() {
+        use super::*;
+        use crate::circuit::blake2s::publicize_default_dynamic_resource_logic_commitments;
+        use crate::circuit::resource_logic_circuit::{mock_prove_resource_logic, ResourceStatus};
+        use crate::resource::tests::random_resource;
+        use halo2_proofs::dev::MockProver;
+
+        // A logic publicizing the value of its self resource in an extra instance column
+        #[derive(Clone, Debug, Default)]
+        struct ExtraInstanceCircuit {
+            self_resource: ResourceExistenceWitness,
+        }
+
+        impl ResourceLogicCircuit for ExtraInstanceCircuit {
+            const EXTRA_INSTANCE_COLUMNS: usize = 1;
+
+            fn custom_constraints(
+                &self,
+                config: ResourceLogicConfig,
+                mut layouter: impl Layouter<pallas::Base>,
+                self_resource: ResourceStatus,
+            ) -> Result<(), Error> {
+                layouter.constrain_instance(
+                    self_resource.resource.value.cell(),
+                    config.extra_instances[0],
+                    0,
+                )?;
+
+                publicize_default_dynamic_resource_logic_commitments(
+                    &mut layouter,
+                    config.advices[0],
+                    config.instances,
+                )
+            }
+
+            fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+                let mut public_inputs = self.get_mandatory_public_inputs();
+                let default_resource_logic_cm: [pallas::Base; 2] =
+                    ResourceLogicCommitment::default().to_public_inputs();
+                public_inputs.extend(default_resource_logic_cm);
+                public_inputs.extend(default_resource_logic_cm);
+                let padding = ResourceLogicPublicInputs::get_public_input_padding(
+                    public_inputs.len(),
+                    &RandomSeed::random(&mut rng),
+                );
+                public_inputs.extend(padding);
+                public_inputs.into()
+            }
+
+            fn get_extra_instances(&self) -> Vec<Vec<pallas::Base>> {
+                vec![vec![self.self_resource.get_resource().value]]
+            }
+
+            fn get_self_resource(&self) -> ResourceExistenceWitness {
+                self.self_resource.clone()
+            }
+        }
+
+        resource_logic_circuit_impl!(ExtraInstanceCircuit);
+        resource_logic_verifying_info_impl!(ExtraInstanceCircuit);
+
+        let mut rng = OsRng;
+        let resource = random_resource(&mut rng);
+        let circuit = ExtraInstanceCircuit {
+            self_resource: ResourceExistenceWitness::new(resource, Default::default()),
+        };
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+
+        // A wrong value in the extra instance column must fail
+        let prover = MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![public_inputs.to_vec(), vec![pallas::Base::one()]],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_halo2_auxiliary_delta_resource_logic_circuit() {
+        use super::*;
+        use crate::auxiliary_delta::AuxiliaryDelta;
+        use crate::circuit::auxiliary_delta::{compute_auxiliary_delta, publicize_auxiliary_delta};
+        use crate::circuit::blake2s::publicize_default_dynamic_resource_logic_commitments;
+        use crate::circuit::gadgets::{
+            assign_free_constant,
+            mul::{MulChip, MulInstructions},
+            sub::{SubChip, SubInstructions},
+        };
+        use crate::circuit::resource_logic_circuit::{mock_prove_resource_logic, ResourceStatus};
+        use crate::resource::tests::random_resource;
+        use halo2_gadgets::ecc::chip::EccChip;
+        use halo2_proofs::arithmetic::Field;
+        use halo2_proofs::dev::MockProver;
+
+        // A logic conserving the quantities of its resources in a custom domain
+        #[derive(Clone, Debug, Default)]
+        struct AuxiliaryDeltaCircuit {
+            self_resource: ResourceExistenceWitness,
+            blind: pallas::Scalar,
+        }
+
+        const DOMAIN: u64 = 7;
+
+        impl AuxiliaryDeltaCircuit {
+            fn auxiliary_delta(&self) -> AuxiliaryDelta {
+                let quantity = self.self_resource.get_resource().quantity.inner();
+                let (consumed, created) = if self.self_resource.is_input() {
+                    (quantity, 0)
+                } else {
+                    (0, quantity)
+                };
+                AuxiliaryDelta::commit(pallas::Base::from(DOMAIN), consumed, created, &self.blind)
+            }
+        }
+
+        impl ResourceLogicCircuit for AuxiliaryDeltaCircuit {
+            const EXTRA_INSTANCE_COLUMNS: usize = 1;
+
+            fn custom_constraints(
+                &self,
+                config: ResourceLogicConfig,
+                mut layouter: impl Layouter<pallas::Base>,
+                self_resource: ResourceStatus,
+            ) -> Result<(), Error> {
+                let sub_chip = SubChip::construct(config.sub_config.clone(), ());
+                let mul_chip = MulChip::construct(config.mul_config.clone());
+                let constant_one = assign_free_constant(
+                    layouter.namespace(|| "one"),
+                    config.advices[0],
+                    pallas::Base::one(),
+                )?;
+                let is_output = SubInstructions::sub(
+                    &sub_chip,
+                    layouter.namespace(|| "is_output"),
+                    &constant_one,
+                    &self_resource.is_input,
+                )?;
+                let consumed = MulInstructions::mul(
+                    &mul_chip,
+                    layouter.namespace(|| "consumed quantity"),
+                    &self_resource.is_input,
+                    &self_resource.resource.quantity,
+                )?;
+                let created = MulInstructions::mul(
+                    &mul_chip,
+                    layouter.namespace(|| "created quantity"),
+                    &is_output,
+                    &self_resource.resource.quantity,
+                )?;
+
+                let delta = compute_auxiliary_delta(
+                    layouter.namespace(|| "auxiliary delta"),
+                    EccChip::construct(config.ecc_config.clone()),
+                    pallas::Base::from(DOMAIN),
+                    consumed,
+                    created,
+                    self.blind,
+                )?;
+                publicize_auxiliary_delta(
+                    layouter.namespace(|| "publicize auxiliary delta"),
+                    config.advices[0],
+                    config.extra_instances[0],
+                    &delta,
+                )?;
+
+                publicize_default_dynamic_resource_logic_commitments(
+                    &mut layouter,
+                    config.advices[0],
+                    config.instances,
+                )
+            }
+
+            fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+                let mut public_inputs = self.get_mandatory_public_inputs();
+                let default_resource_logic_cm: [pallas::Base; 2] =
+                    ResourceLogicCommitment::default().to_public_inputs();
+                public_inputs.extend(default_resource_logic_cm);
+                public_inputs.extend(default_resource_logic_cm);
+                let padding = ResourceLogicPublicInputs::get_public_input_padding(
+                    public_inputs.len(),
+                    &RandomSeed::random(&mut rng),
+                );
+                public_inputs.extend(padding);
+                public_inputs.into()
+            }
+
+            fn get_extra_instances(&self) -> Vec<Vec<pallas::Base>> {
+                vec![self.auxiliary_delta().to_instance()]
+            }
+
+            fn get_self_resource(&self) -> ResourceExistenceWitness {
+                self.self_resource.clone()
+            }
+        }
+
+        resource_logic_circuit_impl!(AuxiliaryDeltaCircuit);
+        resource_logic_verifying_info_impl!(AuxiliaryDeltaCircuit);
+
+        let mut rng = OsRng;
+        let resource = random_resource(&mut rng);
+        let circuit = AuxiliaryDeltaCircuit {
+            self_resource: ResourceExistenceWitness::new(resource.clone(), Default::default()),
+            blind: pallas::Scalar::random(&mut rng),
+        };
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The published delta is the one the executor reads
+        let extra_instances = circuit.get_extra_instances();
+        assert_eq!(
+            AuxiliaryDelta::from_instance(&extra_instances[0]).unwrap(),
+            Some(circuit.auxiliary_delta())
+        );
+
+        // Publishing the delta of the resource on the other side must fail
+        let (consumed, created) = if circuit.self_resource.is_input() {
+            (0, resource.quantity.inner())
+        } else {
+            (resource.quantity.inner(), 0)
+        };
+        let wrong_delta = AuxiliaryDelta::commit(
+            pallas::Base::from(DOMAIN),
+            consumed,
+            created,
+            &circuit.blind,
+        );
+        let prover = MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![public_inputs.to_vec(), wrong_delta.to_instance()],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_halo2_zkvm_receipt_resource_logic_circuit() {
+        use super::*;
+        use crate::circuit::blake2s::{
+            publicize_default_dynamic_resource_logic_commitments, Blake2sChip,
+        };
+        use crate::circuit::resource_logic_circuit::{mock_prove_resource_logic, ResourceStatus};
+        use crate::circuit::zkvm_receipt::{publicize_receipt_claim, receipt_claim_gadget};
+        use crate::resource::tests::random_resource;
+        use crate::zkvm_receipt::{ReceiptVerifier, ZkvmReceipt};
+        use halo2_proofs::dev::MockProver;
+
+        const IMAGE_ID: [u8; 32] = [7u8; 32];
+
+        // A logic delegating the check of its self resource to a zkVM program
+        #[derive(Clone, Debug, Default)]
+        struct ZkvmReceiptCircuit {
+            self_resource: ResourceExistenceWitness,
+        }
+
+        impl ZkvmReceiptCircuit {
+            // The program checks the app data in the value of the resource
+            fn journal(&self) -> Vec<pallas::Base> {
+                vec![self.self_resource.get_resource().value]
+            }
+        }
+
+        impl ResourceLogicCircuit for ZkvmReceiptCircuit {
+            const EXTRA_INSTANCE_COLUMNS: usize = 1;
+
+            fn custom_constraints(
+                &self,
+                config: ResourceLogicConfig,
+                mut layouter: impl Layouter<pallas::Base>,
+                self_resource: ResourceStatus,
+            ) -> Result<(), Error> {
+                let blake2s_chip = Blake2sChip::construct(config.blake2s_config.clone());
+                let journal = [self_resource.resource.value];
+                let claim = receipt_claim_gadget(
+                    &mut layouter,
+                    &blake2s_chip,
+                    config.advices[0],
+                    &IMAGE_ID,
+                    &journal,
+                )?;
+                publicize_receipt_claim(
+                    &mut layouter,
+                    config.advices[0],
+                    config.extra_instances[0],
+                    &claim,
+                )?;
+
+                publicize_default_dynamic_resource_logic_commitments(
+                    &mut layouter,
+                    config.advices[0],
+                    config.instances,
+                )
+            }
+
+            fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+                let mut public_inputs = self.get_mandatory_public_inputs();
+                let default_resource_logic_cm: [pallas::Base; 2] =
+                    ResourceLogicCommitment::default().to_public_inputs();
+                public_inputs.extend(default_resource_logic_cm);
+                public_inputs.extend(default_resource_logic_cm);
+                let padding = ResourceLogicPublicInputs::get_public_input_padding(
+                    public_inputs.len(),
+                    &RandomSeed::random(&mut rng),
+                );
+                public_inputs.extend(padding);
+                public_inputs.into()
+            }
+
+            fn get_extra_instances(&self) -> Vec<Vec<pallas::Base>> {
+                vec![ZkvmReceipt::new(IMAGE_ID, self.journal(), vec![]).to_instance()]
+            }
+
+            fn get_self_resource(&self) -> ResourceExistenceWitness {
+                self.self_resource.clone()
+            }
+        }
+
+        resource_logic_circuit_impl!(ZkvmReceiptCircuit);
+        resource_logic_verifying_info_impl!(ZkvmReceiptCircuit);
+
+        // Stands in for a zkVM verifier: the seal is the journal
+        #[derive(Debug)]
+        struct MockVerifier;
+
+        impl ReceiptVerifier for MockVerifier {
+            fn verify(&self, image_id: &[u8; 32], journal: &[u8], seal: &[u8]) -> bool {
+                *image_id == IMAGE_ID && journal == seal
+            }
+        }
+
+        let mut rng = OsRng;
+        let resource = random_resource(&mut rng);
+        let circuit = ZkvmReceiptCircuit {
+            self_resource: ResourceExistenceWitness::new(resource, Default::default()),
+        };
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The executor matches the receipt with the published claim
+        let extra_instances = circuit.get_extra_instances();
+        let journal_bytes = ZkvmReceipt::new(IMAGE_ID, circuit.journal(), vec![]).journal_bytes();
+        let receipt = ZkvmReceipt::new(IMAGE_ID, circuit.journal(), journal_bytes);
+        receipt.check_claim(&extra_instances).unwrap();
+        assert!(MockVerifier.verify(receipt.image_id(), &receipt.journal_bytes(), receipt.seal()));
+
+        // A receipt of another program or journal doesn't match
+        let other_program = ZkvmReceipt::new([8u8; 32], circuit.journal(), vec![]);
+        assert!(other_program.check_claim(&extra_instances).is_err());
+        let mut other_journal = circuit.journal();
+        other_journal[0] += pallas::Base::one();
+        let other_journal = ZkvmReceipt::new(IMAGE_ID, other_journal, vec![]);
+        assert!(other_journal.check_claim(&extra_instances).is_err());
+
+        // Publishing the claim of another journal must fail
+        let prover = MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![public_inputs.to_vec(), other_journal.to_instance()],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_halo2_nk_possession_resource_logic_circuit() {
+        use super::*;
+        use crate::circuit::blake2s::publicize_default_dynamic_resource_logic_commitments;
+        use crate::circuit::gadgets::nullifier_key::nk_possession_gadget;
+        use crate::circuit::resource_logic_circuit::{mock_prove_resource_logic, ResourceStatus};
+        use crate::nullifier::NullifierKeyContainer;
+        use crate::resource::tests::random_resource;
+        use halo2_proofs::circuit::Value;
+
+        // A logic letting only the admin, whose npk is the label of the
+        // resource, consume and create the resource. The admin doesn't need to
+        // own any resource in the transaction.
+        #[derive(Clone, Debug, Default)]
+        struct AdminResourceLogicCircuit {
+            self_resource: ResourceExistenceWitness,
+            admin_nk: pallas::Base,
+        }
+
+        impl ResourceLogicCircuit for AdminResourceLogicCircuit {
+            fn custom_constraints(
+                &self,
+                config: ResourceLogicConfig,
+                mut layouter: impl Layouter<pallas::Base>,
+                self_resource: ResourceStatus,
+            ) -> Result<(), Error> {
+                nk_possession_gadget(
+                    layouter.namespace(|| "admin nk possession"),
+                    config.advices[0],
+                    config.poseidon_config.clone(),
+                    Value::known(self.admin_nk),
+                    &self_resource.resource.label,
+                )?;
+
+                publicize_default_dynamic_resource_logic_commitments(
+                    &mut layouter,
+                    config.advices[0],
+                    config.instances,
+                )
+            }
+
+            fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+                let mut public_inputs = self.get_mandatory_public_inputs();
+                let default_resource_logic_cm: [pallas::Base; 2] =
+                    ResourceLogicCommitment::default().to_public_inputs();
+                public_inputs.extend(default_resource_logic_cm);
+                public_inputs.extend(default_resource_logic_cm);
+                let padding = ResourceLogicPublicInputs::get_public_input_padding(
+                    public_inputs.len(),
+                    &RandomSeed::random(&mut rng),
+                );
+                public_inputs.extend(padding);
+                public_inputs.into()
+            }
+
+            fn get_self_resource(&self) -> ResourceExistenceWitness {
+                self.self_resource.clone()
+            }
+        }
+
+        resource_logic_circuit_impl!(AdminResourceLogicCircuit);
+        resource_logic_verifying_info_impl!(AdminResourceLogicCircuit);
+
+        let mut rng = OsRng;
+        let admin = NullifierKeyContainer::random_key(&mut rng);
+        let admin_npk = admin.get_npk();
+        assert!(admin.possesses(admin_npk));
+        assert!(!admin.to_commitment().possesses(admin_npk));
+
+        let mut resource = random_resource(&mut rng);
+        resource.kind.label = admin_npk;
+        let mut circuit = AdminResourceLogicCircuit {
+            self_resource: ResourceExistenceWitness::new(resource, Default::default()),
+            admin_nk: admin.get_nk().unwrap(),
+        };
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+
+        // Another nk must fail
+        let other = NullifierKeyContainer::random_key(&mut rng);
+        assert!(!other.possesses(admin_npk));
+        circuit.admin_nk = other.get_nk().unwrap();
+        let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_halo2_payload_encryption_resource_logic_circuit() {
+        use super::*;
+        use crate::circuit::blake2s::publicize_default_dynamic_resource_logic_commitments;
+        use crate::circuit::gadgets::{add::AddChip, assign_free_advice};
+        use crate::circuit::resource_encryption_circuit::payload_encryption_gadget;
+        use crate::circuit::resource_logic_circuit::{mock_prove_resource_logic, ResourceStatus};
+        use crate::constant::{GENERATOR, RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX};
+        use crate::resource::tests::random_resource;
+        use crate::resource_encryption::{PayloadCiphertext, SecretKey};
+        use crate::utils::mod_r_p;
+        use halo2_gadgets::ecc::{chip::EccChip, NonIdentityPoint};
+        use halo2_proofs::arithmetic::Field;
+        use halo2_proofs::circuit::Value;
+        use pasta_curves::group::{cofactor::CofactorCurveAffine, Curve, Group};
+
+        // A logic attaching an encrypted payload to its self resource
+        #[derive(Clone, Debug)]
+        struct PayloadEncryptionCircuit {
+            self_resource: ResourceExistenceWitness,
+            payload: Vec<pallas::Base>,
+            encrypt_nonce: pallas::Base,
+            sk: pallas::Base,
+            rcv_pk: pallas::Point,
+        }
+
+        impl Default for PayloadEncryptionCircuit {
+            fn default() -> Self {
+                Self {
+                    self_resource: ResourceExistenceWitness::default(),
+                    payload: vec![pallas::Base::zero(); 5],
+                    encrypt_nonce: pallas::Base::zero(),
+                    sk: pallas::Base::zero(),
+                    rcv_pk: pallas::Point::generator(),
+                }
+            }
+        }
+
+        impl PayloadEncryptionCircuit {
+            fn ciphertext(&self) -> PayloadCiphertext {
+                let key = SecretKey::from_dh_exchange(&self.rcv_pk, &mod_r_p(self.sk));
+                PayloadCiphertext::encrypt(&self.payload, &key, &self.encrypt_nonce)
+            }
+        }
+
+        impl ResourceLogicCircuit for PayloadEncryptionCircuit {
+            fn custom_constraints(
+                &self,
+                config: ResourceLogicConfig,
+                mut layouter: impl Layouter<pallas::Base>,
+                _self_resource: ResourceStatus,
+            ) -> Result<(), Error> {
+                let encrypt_nonce = assign_free_advice(
+                    layouter.namespace(|| "witness encrypt_nonce"),
+                    config.advices[0],
+                    Value::known(self.encrypt_nonce),
+                )?;
+                let sk = assign_free_advice(
+                    layouter.namespace(|| "witness sk"),
+                    config.advices[0],
+                    Value::known(self.sk),
+                )?;
+                let ecc_chip = EccChip::construct(config.ecc_config.clone());
+                let rcv_pk = NonIdentityPoint::new(
+                    ecc_chip.clone(),
+                    layouter.namespace(|| "witness rcv_pk"),
+                    Value::known(self.rcv_pk.to_affine()),
+                )?;
+                let payload = self
+                    .payload
+                    .iter()
+                    .map(|ele| {
+                        assign_free_advice(
+                            layouter.namespace(|| "witness payload"),
+                            config.advices[0],
+                            Value::known(*ele),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let commitment = payload_encryption_gadget(
+                    layouter.namespace(|| "payload encryption"),
+                    config.advices[0],
+                    config.poseidon_config.clone(),
+                    AddChip::construct(config.add_config.clone(), ()),
+                    ecc_chip,
+                    encrypt_nonce,
+                    sk,
+                    rcv_pk,
+                    &payload,
+                )?;
+                layouter.constrain_instance(
+                    commitment.cell(),
+                    config.instances,
+                    RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX,
+                )?;
+
+                publicize_default_dynamic_resource_logic_commitments(
+                    &mut layouter,
+                    config.advices[0],
+                    config.instances,
+                )
+            }
+
+            fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+                let mut public_inputs = self.get_mandatory_public_inputs();
+                let default_resource_logic_cm: [pallas::Base; 2] =
+                    ResourceLogicCommitment::default().to_public_inputs();
+                public_inputs.extend(default_resource_logic_cm);
+                public_inputs.extend(default_resource_logic_cm);
+                public_inputs.push(self.ciphertext().commitment());
+                let padding = ResourceLogicPublicInputs::get_public_input_padding(
+                    public_inputs.len(),
+                    &RandomSeed::random(&mut rng),
+                );
+                public_inputs.extend(padding);
+                public_inputs.into()
+            }
+
+            fn get_self_resource(&self) -> ResourceExistenceWitness {
+                self.self_resource.clone()
+            }
+        }
+
+        resource_logic_circuit_impl!(PayloadEncryptionCircuit);
+        resource_logic_verifying_info_impl!(PayloadEncryptionCircuit);
+
+        let mut rng = OsRng;
+        let rcv_sk = pallas::Base::random(&mut rng);
+        let sk = pallas::Base::random(&mut rng);
+        let mut circuit = PayloadEncryptionCircuit {
+            self_resource: ResourceExistenceWitness::new(
+                random_resource(&mut rng),
+                Default::default(),
+            ),
+            payload: (0..5).map(|_| pallas::Base::random(&mut rng)).collect(),
+            encrypt_nonce: pallas::Base::random(&mut rng),
+            sk,
+            rcv_pk: GENERATOR.to_curve() * mod_r_p(rcv_sk),
+        };
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The receiver decrypts the payload matching the public commitment
+        let ciphertext = circuit.ciphertext();
+        assert_eq!(
+            ciphertext.commitment(),
+            public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX)
+        );
+        let sender_pk = GENERATOR.to_curve() * mod_r_p(sk);
+        let key = SecretKey::from_dh_exchange(&sender_pk, &mod_r_p(rcv_sk));
+        assert_eq!(ciphertext.decrypt(&key).unwrap(), circuit.payload);
+
+        // Another payload doesn't match the commitment
+        circuit.payload[0] += pallas::Base::one();
+        let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_halo2_encryption_class_resource_logic_circuit() {
+        use super::*;
+        use crate::circuit::blake2s::publicize_default_dynamic_resource_logic_commitments;
+        use crate::circuit::gadgets::{add::AddChip, assign_free_advice};
+        use crate::circuit::resource_encryption_circuit::{
+            resource_encryption_class_gadget, ReceiverVar,
+        };
+        use crate::circuit::resource_logic_circuit::{mock_prove_resource_logic, ResourceStatus};
+        use crate::constant::GENERATOR;
+        use crate::resource::tests::random_resource;
+        use crate::resource_encryption::{
+            Address, Diversifier, EncryptionClass, ResourceCiphertext, ResourcePlaintext, SecretKey,
+        };
+        use crate::utils::mod_r_p;
+        use halo2_gadgets::ecc::{chip::EccChip, NonIdentityPoint};
+        use halo2_proofs::arithmetic::Field;
+        use halo2_proofs::circuit::Value;
+        use halo2_proofs::dev::MockProver;
+        use pasta_curves::group::{cofactor::CofactorCurveAffine, Curve, Group};
+
+        // A logic encrypting its self resource followed by a memo in the
+        // medium class
+        #[derive(Clone, Debug)]
+        struct EncryptionClassCircuit {
+            self_resource: ResourceExistenceWitness,
+            memo: Vec<pallas::Base>,
+            encrypt_nonce: pallas::Base,
+            sk: pallas::Base,
+            rcv_pk: pallas::Point,
+            // The diversified base if rcv_pk is the pk_d of an address
+            g_d: Option<pallas::Point>,
+        }
+
+        impl Default for EncryptionClassCircuit {
+            fn default() -> Self {
+                Self {
+                    self_resource: ResourceExistenceWitness::default(),
+                    memo: vec![pallas::Base::zero(); 6],
+                    encrypt_nonce: pallas::Base::zero(),
+                    sk: pallas::Base::zero(),
+                    rcv_pk: pallas::Point::generator(),
+                    g_d: None,
+                }
+            }
+        }
+
+        impl EncryptionClassCircuit {
+            fn plaintext(&self) -> ResourcePlaintext {
+                let mut message =
+                    ResourcePlaintext::from_resource(&self.self_resource.get_resource()).to_vec();
+                message.truncate(8);
+                message.extend(self.memo.iter());
+                ResourcePlaintext::padding_to_class(&message, EncryptionClass::Medium)
+            }
+
+            fn ciphertext(&self) -> ResourceCiphertext {
+                let key = SecretKey::from_dh_exchange(&self.rcv_pk, &mod_r_p(self.sk));
+                ResourceCiphertext::encrypt(&self.plaintext(), &key, &self.encrypt_nonce)
+            }
+        }
+
+        impl ResourceLogicCircuit for EncryptionClassCircuit {
+            const EXTRA_INSTANCE_COLUMNS: usize = 1;
+
+            fn custom_constraints(
+                &self,
+                config: ResourceLogicConfig,
+                mut layouter: impl Layouter<pallas::Base>,
+                self_resource: ResourceStatus,
+            ) -> Result<(), Error> {
+                let encrypt_nonce = assign_free_advice(
+                    layouter.namespace(|| "witness encrypt_nonce"),
+                    config.advices[0],
+                    Value::known(self.encrypt_nonce),
+                )?;
+                let sk = assign_free_advice(
+                    layouter.namespace(|| "witness sk"),
+                    config.advices[0],
+                    Value::known(self.sk),
+                )?;
+                let ecc_chip = EccChip::construct(config.ecc_config.clone());
+                let rcv_pk = NonIdentityPoint::new(
+                    ecc_chip.clone(),
+                    layouter.namespace(|| "witness rcv_pk"),
+                    Value::known(self.rcv_pk.to_affine()),
+                )?;
+                let rcv = match self.g_d {
+                    Some(g_d) => ReceiverVar::Address {
+                        g_d: NonIdentityPoint::new(
+                            ecc_chip.clone(),
+                            layouter.namespace(|| "witness g_d"),
+                            Value::known(g_d.to_affine()),
+                        )?,
+                        pk_d: rcv_pk,
+                    },
+                    None => rcv_pk.into(),
+                };
+                let mut message = vec![
+                    self_resource.resource.logic,
+                    self_resource.resource.label,
+                    self_resource.resource.value,
+                    self_resource.resource.quantity,
+                    self_resource.resource.nonce,
+                    self_resource.resource.npk,
+                    self_resource.resource.is_ephemeral,
+                    self_resource.resource.rseed,
+                ];
+                for ele in self.memo.iter() {
+                    message.push(assign_free_advice(
+                        layouter.namespace(|| "witness memo"),
+                        config.advices[0],
+                        Value::known(*ele),
+                    )?);
+                }
+
+                resource_encryption_class_gadget(
+                    layouter.namespace(|| "resource encryption"),
+                    config.advices[0],
+                    config.extra_instances[0],
+                    config.poseidon_config.clone(),
+                    AddChip::construct(config.add_config.clone(), ()),
+                    ecc_chip,
+                    encrypt_nonce,
+                    sk,
+                    rcv,
+                    EncryptionClass::Medium,
+                    &mut message,
+                )?;
+
+                publicize_default_dynamic_resource_logic_commitments(
+                    &mut layouter,
+                    config.advices[0],
+                    config.instances,
+                )
+            }
+
+            fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+                let mut public_inputs = self.get_mandatory_public_inputs();
+                let default_resource_logic_cm: [pallas::Base; 2] =
+                    ResourceLogicCommitment::default().to_public_inputs();
+                public_inputs.extend(default_resource_logic_cm);
+                public_inputs.extend(default_resource_logic_cm);
+                let padding = ResourceLogicPublicInputs::get_public_input_padding(
+                    public_inputs.len(),
+                    &RandomSeed::random(&mut rng),
+                );
+                public_inputs.extend(padding);
+                public_inputs.into()
+            }
+
+            fn get_extra_instances(&self) -> Vec<Vec<pallas::Base>> {
+                let base = self.g_d.unwrap_or(GENERATOR.to_curve());
+                let sender_pk = base * mod_r_p(self.sk);
+                vec![self.ciphertext().to_instance(&sender_pk)]
+            }
+
+            fn get_self_resource(&self) -> ResourceExistenceWitness {
+                self.self_resource.clone()
+            }
+        }
+
+        resource_logic_circuit_impl!(EncryptionClassCircuit);
+        resource_logic_verifying_info_impl!(EncryptionClassCircuit);
+
+        let mut rng = OsRng;
+        let rcv_sk = pallas::Base::random(&mut rng);
+        let resource = random_resource(&mut rng);
+        let circuit = EncryptionClassCircuit {
+            self_resource: ResourceExistenceWitness::new(resource.clone(), Default::default()),
+            memo: (0..6).map(|_| pallas::Base::random(&mut rng)).collect(),
+            encrypt_nonce: pallas::Base::random(&mut rng),
+            sk: pallas::Base::random(&mut rng),
+            rcv_pk: GENERATOR.to_curve() * mod_r_p(rcv_sk),
+            g_d: None,
+        };
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The receiver reads the class and decrypts the resource and the memo
+        let extra_instances = circuit.get_extra_instances();
+        let (cipher, sender_pk) = ResourceCiphertext::from_instance(&extra_instances[0]).unwrap();
+        assert_eq!(cipher.class(), EncryptionClass::Medium);
+        let key = SecretKey::from_dh_exchange(&sender_pk, &mod_r_p(rcv_sk));
+        let plaintext = ResourcePlaintext::from(cipher.decrypt(&key).unwrap());
+        assert_eq!(plaintext.to_vec(), circuit.plaintext().to_vec());
+        assert_eq!(
+            plaintext.to_resource().unwrap().commitment(),
+            resource.commitment()
+        );
+
+        // Recording another class must fail
+        let mut wrong_class = extra_instances[0].clone();
+        wrong_class[1] = EncryptionClass::Large.to_field();
+        let prover = MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![public_inputs.to_vec(), wrong_class],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+
+        // The resource encrypted to an address of the receiver decrypts with
+        // the same key
+        let address = Address::derive(&rcv_sk, Diversifier::random(&mut rng));
+        let circuit = EncryptionClassCircuit {
+            rcv_pk: address.pk_d(),
+            g_d: Some(address.g_d()),
+            ..circuit
+        };
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+        let (cipher, sender_pk) =
+            ResourceCiphertext::from_instance(&circuit.get_extra_instances()[0]).unwrap();
+        let key = SecretKey::from_dh_exchange(&sender_pk, &mod_r_p(rcv_sk));
+        assert_eq!(cipher.decrypt(&key).unwrap(), circuit.plaintext().to_vec());
+    }
+
+    #[test]
+    fn test_halo2_mailbox_resource_logic_circuit() {
+        use super::*;
+        use crate::circuit::blake2s::publicize_default_dynamic_resource_logic_commitments;
+        use crate::circuit::gadgets::{add::AddChip, assign_free_advice};
+        use crate::circuit::resource_encryption_circuit::mailbox_gadget;
+        use crate::circuit::resource_logic_circuit::{mock_prove_resource_logic, ResourceStatus};
+        use crate::constant::{GENERATOR, RESOURCE_LOGIC_CIRCUIT_MAILBOX_IDX};
+        use crate::error::TransactionError;
+        use crate::resource::tests::random_resource;
+        use crate::resource_encryption::Mailbox;
+        use crate::utils::mod_r_p;
+        use halo2_gadgets::ecc::{chip::EccChip, NonIdentityPoint};
+        use halo2_proofs::arithmetic::Field;
+        use halo2_proofs::circuit::Value;
+        use pasta_curves::group::{cofactor::CofactorCurveAffine, Curve, Group};
+
+        // A logic leaving the quantity of its self resource to its owner, whose
+        // pk is committed in the value
+        #[derive(Clone, Debug)]
+        struct MailboxCircuit {
+            self_resource: ResourceExistenceWitness,
+            encrypt_nonce: pallas::Base,
+            sk: pallas::Base,
+            rcv_pk: pallas::Point,
+        }
+
+        impl Default for MailboxCircuit {
+            fn default() -> Self {
+                Self {
+                    self_resource: ResourceExistenceWitness::default(),
+                    encrypt_nonce: pallas::Base::zero(),
+                    sk: pallas::Base::zero(),
+                    rcv_pk: pallas::Point::generator(),
+                }
+            }
+        }
+
+        impl MailboxCircuit {
+            fn mailbox(&self) -> Mailbox {
+                let message = [pallas::Base::from(
+                    self.self_resource.get_resource().quantity,
+                )];
+                Mailbox::seal(&message, &self.rcv_pk, &self.sk, &self.encrypt_nonce)
+            }
+        }
+
+        impl ResourceLogicCircuit for MailboxCircuit {
+            fn custom_constraints(
+                &self,
+                config: ResourceLogicConfig,
+                mut layouter: impl Layouter<pallas::Base>,
+                self_resource: ResourceStatus,
+            ) -> Result<(), Error> {
+                let encrypt_nonce = assign_free_advice(
+                    layouter.namespace(|| "witness encrypt_nonce"),
+                    config.advices[0],
+                    Value::known(self.encrypt_nonce),
+                )?;
+                let sk = assign_free_advice(
+                    layouter.namespace(|| "witness sk"),
+                    config.advices[0],
+                    Value::known(self.sk),
+                )?;
+                let ecc_chip = EccChip::construct(config.ecc_config.clone());
+                let rcv_pk = NonIdentityPoint::new(
+                    ecc_chip.clone(),
+                    layouter.namespace(|| "witness rcv_pk"),
+                    Value::known(self.rcv_pk.to_affine()),
+                )?;
+
+                mailbox_gadget(
+                    layouter.namespace(|| "mailbox"),
+                    config.advices[0],
+                    config.instances,
+                    config.poseidon_config.clone(),
+                    AddChip::construct(config.add_config.clone(), ()),
+                    ecc_chip,
+                    encrypt_nonce,
+                    sk,
+                    rcv_pk,
+                    self_resource.resource.value,
+                    &[self_resource.resource.quantity],
+                )?;
+
+                publicize_default_dynamic_resource_logic_commitments(
+                    &mut layouter,
+                    config.advices[0],
+                    config.instances,
+                )
+            }
+
+            fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+                let mut public_inputs = self.get_mandatory_public_inputs();
+                let default_resource_logic_cm: [pallas::Base; 2] =
+                    ResourceLogicCommitment::default().to_public_inputs();
+                public_inputs.extend(default_resource_logic_cm);
+                public_inputs.extend(default_resource_logic_cm);
+                // The first custom public input is unused
+                public_inputs.push(pallas::Base::zero());
+                public_inputs.push(self.mailbox().commitment());
+                let padding = ResourceLogicPublicInputs::get_public_input_padding(
+                    public_inputs.len(),
+                    &RandomSeed::random(&mut rng),
+                );
+                public_inputs.extend(padding);
+                public_inputs.into()
+            }
+
+            fn get_self_resource(&self) -> ResourceExistenceWitness {
+                self.self_resource.clone()
+            }
+        }
+
+        resource_logic_circuit_impl!(MailboxCircuit);
+        resource_logic_verifying_info_impl!(MailboxCircuit);
+
+        let mut rng = OsRng;
+        let rcv_sk = pallas::Base::random(&mut rng);
+        let rcv_pk = GENERATOR.to_curve() * mod_r_p(rcv_sk);
+        let mut resource = random_resource(&mut rng);
+        resource.value = Mailbox::owner(&rcv_pk);
+        let circuit = MailboxCircuit {
+            self_resource: ResourceExistenceWitness::new(resource.clone(), Default::default()),
+            encrypt_nonce: pallas::Base::random(&mut rng),
+            sk: pallas::Base::random(&mut rng),
+            rcv_pk,
+        };
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The owner opens the mailbox published by the logic
+        let mailbox = circuit.mailbox();
+        let message = mailbox
+            .open_checked(&public_inputs, &mod_r_p(rcv_sk))
+            .unwrap();
+        assert_eq!(message[0], pallas::Base::from(resource.quantity));
+
+        // Another mailbox is not the one published by the logic
+        let other_circuit = MailboxCircuit {
+            encrypt_nonce: pallas::Base::random(&mut rng),
+            ..circuit.clone()
+        };
+        assert!(matches!(
+            other_circuit
+                .mailbox()
+                .open_checked(&public_inputs, &mod_r_p(rcv_sk)),
+            Err(TransactionError::MailboxCommitmentMismatch)
+        ));
+        let prover = mock_prove_resource_logic(&other_circuit, &public_inputs);
+        assert!(prover.verify().is_err());
+
+        // The mailbox can't be encrypted to anyone but the owner
+        let stranger_circuit = MailboxCircuit {
+            rcv_pk: pallas::Point::random(&mut rng),
+            ..circuit.clone()
+        };
+        let public_inputs = stranger_circuit.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(&stranger_circuit, &public_inputs);
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_halo2_linkability_tag_resource_logic_circuit() {
+        use super::*;
+        use crate::circuit::blake2s::publicize_default_dynamic_resource_logic_commitments;
+        use crate::circuit::gadgets::{
+            assign_free_advice, linkability_tag::linkability_tag_gadget,
+            nullifier_key::nk_possession_gadget,
+        };
+        use crate::circuit::resource_logic_circuit::{mock_prove_resource_logic, ResourceStatus};
+        use crate::linkability::{LinkabilityTag, LinkabilityTagSet};
+        use crate::nullifier::NullifierKeyContainer;
+        use crate::resource::tests::random_resource;
+        use halo2_proofs::arithmetic::Field;
+        use halo2_proofs::circuit::Value;
+
+        // A logic publishing the tag of the nk behind its self resource
+        #[derive(Clone, Debug, Default)]
+        struct LinkableCircuit {
+            self_resource: ResourceExistenceWitness,
+            nk: pallas::Base,
+            app_domain: pallas::Base,
+        }
+
+        impl ResourceLogicCircuit for LinkableCircuit {
+            fn custom_constraints(
+                &self,
+                config: ResourceLogicConfig,
+                mut layouter: impl Layouter<pallas::Base>,
+                self_resource: ResourceStatus,
+            ) -> Result<(), Error> {
+                let nk = nk_possession_gadget(
+                    layouter.namespace(|| "nk possession"),
+                    config.advices[0],
+                    config.poseidon_config.clone(),
+                    Value::known(self.nk),
+                    &self_resource.resource.npk,
+                )?;
+                let app_domain = assign_free_advice(
+                    layouter.namespace(|| "witness app_domain"),
+                    config.advices[0],
+                    Value::known(self.app_domain),
+                )?;
+                linkability_tag_gadget(
+                    layouter.namespace(|| "linkability tag"),
+                    config.advices[0],
+                    config.instances,
+                    config.poseidon_config.clone(),
+                    nk,
+                    app_domain,
+                )?;
+
+                publicize_default_dynamic_resource_logic_commitments(
+                    &mut layouter,
+                    config.advices[0],
+                    config.instances,
+                )
+            }
+
+            fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+                let mut public_inputs = self.get_mandatory_public_inputs();
+                let default_resource_logic_cm: [pallas::Base; 2] =
+                    ResourceLogicCommitment::default().to_public_inputs();
+                public_inputs.extend(default_resource_logic_cm);
+                public_inputs.extend(default_resource_logic_cm);
+                public_inputs.push(LinkabilityTag::derive(&self.nk, &self.app_domain).inner());
+                // The second custom public input is unused
+                public_inputs.push(pallas::Base::zero());
+                let padding = ResourceLogicPublicInputs::get_public_input_padding(
+                    public_inputs.len(),
+                    &RandomSeed::random(&mut rng),
+                );
+                public_inputs.extend(padding);
+                public_inputs.into()
+            }
+
+            fn get_self_resource(&self) -> ResourceExistenceWitness {
+                self.self_resource.clone()
+            }
+        }
+
+        resource_logic_circuit_impl!(LinkableCircuit);
+        resource_logic_verifying_info_impl!(LinkableCircuit);
+
+        let mut rng = OsRng;
+        let nk = pallas::Base::random(&mut rng);
+        let app_domain = pallas::Base::random(&mut rng);
+        let linkable_circuit = |mut resource: Resource| {
+            resource.nk_container = NullifierKeyContainer::from_key(nk).into();
+            let path = [(pallas::Base::zero(), LR::R); TAIGA_RESOURCE_TREE_DEPTH];
+            LinkableCircuit {
+                self_resource: ResourceExistenceWitness::new(resource, path),
+                nk,
+                app_domain,
+            }
+        };
+        let circuit = linkable_circuit(random_resource(&mut rng));
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+        assert_eq!(prover.verify(), Ok(()));
+
+        // Another resource of the same nk publishes the same tag
+        let other_circuit = linkable_circuit(random_resource(&mut rng));
+        let other_public_inputs = other_circuit.get_public_inputs(&mut rng);
+        let tag = LinkabilityTag::from_public_inputs(&public_inputs);
+        assert_eq!(
+            tag,
+            LinkabilityTag::from_public_inputs(&other_public_inputs)
+        );
+        let mut tags = LinkabilityTagSet::new();
+        assert!(tags.check_and_insert(&[tag]).is_ok());
+        assert!(tags
+            .check_and_insert(&[LinkabilityTag::from_public_inputs(&other_public_inputs)])
+            .is_err());
+
+        // The tag must be derived from the nk of the resource
+        let invalid_circuit = LinkableCircuit {
+            nk: pallas::Base::random(&mut rng),
+            ..circuit.clone()
+        };
+        let invalid_public_inputs = invalid_circuit.get_public_inputs(&mut rng);
+        let prover = mock_prove_resource_logic(&invalid_circuit, &invalid_public_inputs);
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_halo2_composed_resource_logic_circuit() {
+        use super::*;
+        use crate::circuit::gadgets::{
+            assign_free_constant,
+            sub::{SubChip, SubInstructions},
+        };
+        use crate::circuit::resource_logic_circuit::{
+            mock_prove_resource_logic, ComposedResourceLogicCircuit, ConditionStatus,
+            ResourceLogicCondition, ResourceStatus,
+        };
+        use crate::quantity::Quantity;
+        use crate::resource::tests::random_resource;
+
+        // quantity == expected
+        #[derive(Clone, Debug, Default)]
+        struct QuantityCondition {
+            quantity: u64,
+        }
+
+        impl ResourceLogicCondition for QuantityCondition {
+            fn condition(
+                &self,
+                config: &ResourceLogicConfig,
+                mut layouter: impl Layouter<pallas::Base>,
+                self_resource: &ResourceStatus,
+            ) -> Result<ConditionStatus, Error> {
+                let expected = assign_free_constant(
+                    layouter.namespace(|| "expected quantity"),
+                    config.advices[0],
+                    pallas::Base::from(self.quantity),
+                )?;
+                let sub_chip = SubChip::construct(config.sub_config.clone(), ());
+                let error = sub_chip.sub(
+                    layouter.namespace(|| "quantity - expected"),
+                    &self_resource.resource.quantity,
+                    &expected,
+                )?;
+                Ok(ConditionStatus {
+                    error,
+                    public_inputs: vec![],
+                })
+            }
+        }
+
+        // label == expected, publicizing the expected label
+        #[derive(Clone, Debug, Default)]
+        struct LabelCondition {
+            label: pallas::Base,
+        }
+
+        impl ResourceLogicCondition for LabelCondition {
+            fn condition(
+                &self,
+                config: &ResourceLogicConfig,
+                mut layouter: impl Layouter<pallas::Base>,
+                self_resource: &ResourceStatus,
+            ) -> Result<ConditionStatus, Error> {
+                let expected = assign_free_constant(
+                    layouter.namespace(|| "expected label"),
+                    config.advices[0],
+                    self.label,
+                )?;
+                let sub_chip = SubChip::construct(config.sub_config.clone(), ());
+                let error = sub_chip.sub(
+                    layouter.namespace(|| "label - expected"),
+                    &self_resource.resource.label,
+                    &expected,
+                )?;
+                Ok(ConditionStatus {
+                    error,
+                    public_inputs: vec![expected],
+                })
+            }
+
+            fn get_public_inputs(&self) -> Vec<pallas::Base> {
+                vec![self.label]
+            }
+        }
+
+        let mut rng = OsRng;
+        let mut resource = random_resource(&mut rng);
+        resource.quantity = Quantity::new(10);
+        let label = resource.get_label();
+        let merkle_path = [(pallas::Base::zero(), LR::R); TAIGA_RESOURCE_TREE_DEPTH];
+        let self_resource = ResourceExistenceWitness::new(resource, merkle_path);
+        let quantity_holds = QuantityCondition { quantity: 10 };
+        let quantity_fails = QuantityCondition { quantity: 11 };
+        let label_holds = LabelCondition { label };
+        let label_fails = LabelCondition {
+            label: label + pallas::Base::one(),
+        };
+
+        let check = |circuit: ComposedResourceLogicCircuit<QuantityCondition, LabelCondition>| {
+            let public_inputs = circuit.get_public_inputs(OsRng);
+            mock_prove_resource_logic(&circuit, &public_inputs)
+                .verify()
+                .is_ok()
+        };
+
+        // AND
+        assert!(check(ComposedResourceLogicCircuit::and(
+            self_resource.clone(),
+            quantity_holds.clone(),
+            label_holds.clone()
+        )));
+        assert!(!check(ComposedResourceLogicCircuit::and(
+            self_resource.clone(),
+            quantity_fails.clone(),
+            label_holds.clone()
+        )));
+
+        // OR
+        assert!(check(ComposedResourceLogicCircuit::or(
+            self_resource.clone(),
+            quantity_fails.clone(),
+            label_holds
+        )));
+        assert!(check(ComposedResourceLogicCircuit::or(
+            self_resource.clone(),
+            quantity_holds,
+            label_fails.clone()
+        )));
+        assert!(!check(ComposedResourceLogicCircuit::or(
+            self_resource,
+            quantity_fails,
+            label_fails
+        )));
+    }
 }
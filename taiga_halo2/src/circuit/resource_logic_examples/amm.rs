@@ -0,0 +1,712 @@
+/// A constant-product AMM pool of two tokens. Traders swap against the pool by
+/// consuming the pool resource and creating the pool resource of the new
+/// reserves, in a ptx that also creates the deposit of the paid tokens to the
+/// pool owner and the payout of the bought tokens.
+///
+/// The pool resource encodes:
+/// * label: Poseidon(token_vk, token_x, token_y, owner), the owner being the
+///   value of the token authorization holding the reserves
+/// * value: Poseidon(reserve_x, reserve_y)
+///
+/// The pool logic checks that the reserves change by the quantities of the
+/// deposit and the payout, and that their product doesn't decrease. Adding
+/// liquidity is a swap with an empty payout. A pool is opened from an
+/// ephemeral pool of empty reserves, so the reserves of a pool are always
+/// backed by deposits to its owner.
+///
+/// The reserves are token resources of the owner, who signs their spending
+/// for the payouts: traders are guaranteed the price of the pool, and the
+/// owner is trusted with the custody of the reserves.
+use crate::{
+    circuit::{
+        blake2s::publicize_default_dynamic_resource_logic_commitments,
+        gadgets::{
+            add::{AddChip, AddInstructions},
+            assign_free_advice, assign_free_constant,
+            mul::{MulChip, MulInstructions},
+            poseidon_hash::poseidon_hash_gadget,
+            sub::{SubChip, SubInstructions},
+        },
+        integrity::load_resource,
+        merkle_circuit::MerklePoseidonChip,
+        resource_commitment::ResourceCommitChip,
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+        resource_logic_examples::token::{TokenAuthorization, TokenName, COMPRESSED_TOKEN_VK},
+    },
+    error::TransactionError,
+    nullifier::Nullifier,
+    proof::Proof,
+    quantity::Quantity,
+    resource::{RandomSeed, Resource},
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::ResourceExistenceWitness,
+    utils::{poseidon_hash, poseidon_hash_n},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::pallas;
+use rand::{rngs::OsRng, RngCore};
+
+lazy_static! {
+    pub static ref AMM_POOL_VK: ResourceLogicVerifyingKey = AmmPoolResourceLogicCircuit::default()
+        .get_resource_logic_vk()
+        .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_AMM_POOL_VK: pallas::Base = AMM_POOL_VK.get_compressed();
+}
+
+/// The state of a pool.
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize)]
+pub struct Pool {
+    pub token_x: TokenName,
+    pub token_y: TokenName,
+    // The authorization of the token resources of the reserves
+    pub owner: TokenAuthorization,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+}
+
+impl Pool {
+    /// A pool of empty reserves.
+    pub fn new(token_x: TokenName, token_y: TokenName, owner: TokenAuthorization) -> Self {
+        Self {
+            token_x,
+            token_y,
+            owner,
+            reserve_x: 0,
+            reserve_y: 0,
+        }
+    }
+
+    pub fn encode_label(&self) -> pallas::Base {
+        poseidon_hash_n([
+            *COMPRESSED_TOKEN_VK,
+            self.token_x.encode(),
+            self.token_y.encode(),
+            self.owner.to_value(),
+        ])
+    }
+
+    pub fn encode_value(&self) -> pallas::Base {
+        poseidon_hash(
+            pallas::Base::from(self.reserve_x),
+            pallas::Base::from(self.reserve_y),
+        )
+    }
+
+    /// The sold and the bought token of a swap, selling x if `sell_x`.
+    pub fn tokens(&self, sell_x: bool) -> (&TokenName, &TokenName) {
+        if sell_x {
+            (&self.token_x, &self.token_y)
+        } else {
+            (&self.token_y, &self.token_x)
+        }
+    }
+
+    fn reserves(&self, sell_x: bool) -> (u64, u64) {
+        if sell_x {
+            (self.reserve_x, self.reserve_y)
+        } else {
+            (self.reserve_y, self.reserve_x)
+        }
+    }
+
+    /// The most the pool pays for `amount_in` of the sold token.
+    pub fn quote(&self, sell_x: bool, amount_in: u64) -> Option<u64> {
+        let (reserve_in, reserve_out) = self.reserves(sell_x);
+        let new_in = reserve_in.checked_add(amount_in)?;
+        if new_in == 0 {
+            return Some(0);
+        }
+        u64::try_from(u128::from(reserve_out) * u128::from(amount_in) / u128::from(new_in)).ok()
+    }
+
+    /// The pool after a swap of `amount_in` of the sold token for `amount_out`
+    /// of the bought token. None if the swap decreases the product of the
+    /// reserves.
+    pub fn swap(&self, sell_x: bool, amount_in: u64, amount_out: u64) -> Option<Self> {
+        let (reserve_in, reserve_out) = self.reserves(sell_x);
+        let new_in = reserve_in.checked_add(amount_in)?;
+        let new_out = reserve_out.checked_sub(amount_out)?;
+        if u128::from(new_in) * u128::from(new_out)
+            < u128::from(reserve_in) * u128::from(reserve_out)
+        {
+            return None;
+        }
+        let (reserve_x, reserve_y) = if sell_x {
+            (new_in, new_out)
+        } else {
+            (new_out, new_in)
+        };
+        Some(Self {
+            reserve_x,
+            reserve_y,
+            ..self.clone()
+        })
+    }
+
+    /// Creates the pool resource, owned by `npk`.
+    pub fn create_resource<R: RngCore>(&self, mut rng: R, npk: pallas::Base) -> Resource {
+        Resource::new_output_resource(
+            *COMPRESSED_AMM_POOL_VK,
+            self.encode_label(),
+            self.encode_value(),
+            Quantity::new(1),
+            npk,
+            false,
+            pallas::Base::random(&mut rng),
+        )
+    }
+
+    /// The ephemeral pool of empty reserves a pool is opened from.
+    pub fn create_ephemeral_resource<R: RngCore>(&self, mut rng: R) -> Resource {
+        let empty = Self::new(self.token_x.clone(), self.token_y.clone(), self.owner);
+        Resource::new_input_resource(
+            *COMPRESSED_AMM_POOL_VK,
+            empty.encode_label(),
+            empty.encode_value(),
+            Quantity::new(1),
+            pallas::Base::random(&mut rng),
+            Nullifier::random(&mut rng),
+            true,
+            pallas::Base::random(&mut rng),
+        )
+    }
+}
+
+// AmmPoolResourceLogicCircuit
+#[derive(Clone, Debug, Default)]
+pub struct AmmPoolResourceLogicCircuit {
+    pub self_resource: ResourceExistenceWitness,
+    // the consumed pool resource
+    pub pool_resource: ResourceExistenceWitness,
+    // the created pool resource
+    pub new_pool_resource: ResourceExistenceWitness,
+    // the paid tokens, created for the owner
+    pub deposit_resource: ResourceExistenceWitness,
+    // the bought tokens
+    pub payout_resource: ResourceExistenceWitness,
+    // the consumed pool
+    pub pool: Pool,
+    pub sell_x: bool,
+}
+
+impl AmmPoolResourceLogicCircuit {
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(ResourceLogicRepresentation::AmmPool, self.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+
+    /// The pool after the swap.
+    pub fn new_pool(&self) -> Option<Pool> {
+        self.pool.swap(
+            self.sell_x,
+            self.deposit_resource.get_resource().quantity.inner(),
+            self.payout_resource.get_resource().quantity.inner(),
+        )
+    }
+}
+
+impl ResourceLogicCircuit for AmmPoolResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        let merkle_chip = MerklePoseidonChip::construct(config.merkle_config.clone());
+        let resource_commit_chip =
+            ResourceCommitChip::construct(config.resource_commit_config.clone());
+        let add_chip = AddChip::construct(config.add_config.clone(), ());
+        let sub_chip = SubChip::construct(config.sub_config.clone(), ());
+        let mul_chip = MulChip::construct(config.mul_config.clone());
+
+        // The consumed pool is consumed in the same ptx
+        let pool_resource = config.load_consumed_resource(
+            layouter.namespace(|| "load the pool resource"),
+            &self.pool_resource,
+            &self_resource,
+        )?;
+        let [new_pool_resource, deposit_resource, payout_resource] = [
+            &self.new_pool_resource,
+            &self.deposit_resource,
+            &self.payout_resource,
+        ]
+        .map(|resource| {
+            load_resource(
+                layouter.namespace(|| "load a created resource"),
+                config.advices,
+                resource_commit_chip.clone(),
+                config.conditional_select_config,
+                merkle_chip.clone(),
+                resource,
+            )
+        });
+        let new_pool_resource = new_pool_resource?;
+        let deposit_resource = deposit_resource?;
+        let payout_resource = payout_resource?;
+
+        let constant_zero = assign_free_constant(
+            layouter.namespace(|| "zero"),
+            config.advices[0],
+            pallas::Base::zero(),
+        )?;
+        let constant_one = assign_free_constant(
+            layouter.namespace(|| "one"),
+            config.advices[0],
+            pallas::Base::one(),
+        )?;
+
+        // Witness the label of the pool
+        let token_vk = assign_free_advice(
+            layouter.namespace(|| "witness token vk"),
+            config.advices[0],
+            Value::known(*COMPRESSED_TOKEN_VK),
+        )?;
+        let token_x = assign_free_advice(
+            layouter.namespace(|| "witness token_x"),
+            config.advices[0],
+            Value::known(self.pool.token_x.encode()),
+        )?;
+        let token_y = assign_free_advice(
+            layouter.namespace(|| "witness token_y"),
+            config.advices[0],
+            Value::known(self.pool.token_y.encode()),
+        )?;
+        let owner = assign_free_advice(
+            layouter.namespace(|| "witness owner"),
+            config.advices[0],
+            Value::known(self.pool.owner.to_value()),
+        )?;
+        let label = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "label encoding"),
+            [
+                token_vk.clone(),
+                token_x.clone(),
+                token_y.clone(),
+                owner.clone(),
+            ],
+        )?;
+
+        // The pool resources are the consumed and the created pool of the same
+        // ptx, the deposit and the payout are created token resources
+        layouter.assign_region(
+            || "check the swap resources",
+            |mut region| {
+                region.constrain_equal(label.cell(), self_resource.resource.label.cell())?;
+                region.constrain_equal(pool_resource.is_input.cell(), constant_one.cell())?;
+                for resource in [&new_pool_resource, &deposit_resource, &payout_resource] {
+                    region.constrain_equal(resource.is_input.cell(), constant_zero.cell())?;
+                    region.constrain_equal(
+                        resource.resource_merkle_root.cell(),
+                        self_resource.resource_merkle_root.cell(),
+                    )?;
+                }
+                for resource in [&pool_resource, &new_pool_resource] {
+                    region.constrain_equal(
+                        resource.resource.logic.cell(),
+                        self_resource.resource.logic.cell(),
+                    )?;
+                    region.constrain_equal(resource.resource.label.cell(), label.cell())?;
+                }
+                for resource in [&deposit_resource, &payout_resource] {
+                    region.constrain_equal(resource.resource.logic.cell(), token_vk.cell())?;
+                }
+                // The deposit goes to the owner of the pool
+                region.constrain_equal(deposit_resource.resource.value.cell(), owner.cell())?;
+                region.constrain_equal(
+                    deposit_resource.resource.npk.cell(),
+                    new_pool_resource.resource.npk.cell(),
+                )
+            },
+        )?;
+
+        // The self resource is the consumed pool when consumed, the created pool when created
+        let is_output_resource = SubInstructions::sub(
+            &sub_chip,
+            layouter.namespace(|| "is_output"),
+            &constant_one,
+            &self_resource.is_input,
+        )?;
+        layouter.assign_region(
+            || "conditional equal: check consumed self resource",
+            |mut region| {
+                config.conditional_equal_config.assign_region(
+                    &self_resource.is_input,
+                    &self_resource.identity,
+                    &pool_resource.identity,
+                    0,
+                    &mut region,
+                )
+            },
+        )?;
+        layouter.assign_region(
+            || "conditional equal: check created self resource",
+            |mut region| {
+                config.conditional_equal_config.assign_region(
+                    &is_output_resource,
+                    &self_resource.identity,
+                    &new_pool_resource.identity,
+                    0,
+                    &mut region,
+                )
+            },
+        )?;
+
+        // Check the value encoding of the consumed pool
+        let reserve_x = assign_free_advice(
+            layouter.namespace(|| "witness reserve_x"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.pool.reserve_x)),
+        )?;
+        let reserve_y = assign_free_advice(
+            layouter.namespace(|| "witness reserve_y"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.pool.reserve_y)),
+        )?;
+        let value = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "value encoding"),
+            [reserve_x.clone(), reserve_y.clone()],
+        )?;
+        layouter.assign_region(
+            || "check value encoding",
+            |mut region| region.constrain_equal(value.cell(), pool_resource.resource.value.cell()),
+        )?;
+
+        // An ephemeral pool has empty reserves
+        for reserve in [&reserve_x, &reserve_y] {
+            layouter.assign_region(
+                || "conditional equal: check ephemeral pool",
+                |mut region| {
+                    config.conditional_equal_config.assign_region(
+                        &pool_resource.resource.is_ephemeral,
+                        reserve,
+                        &constant_zero,
+                        0,
+                        &mut region,
+                    )
+                },
+            )?;
+        }
+
+        // Select the sold and the bought token
+        let sell_x = assign_free_advice(
+            layouter.namespace(|| "witness sell_x"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.sell_x)),
+        )?;
+        let sell_x_square = MulInstructions::mul(
+            &mul_chip,
+            layouter.namespace(|| "sell_x * sell_x"),
+            &sell_x,
+            &sell_x,
+        )?;
+        layouter.assign_region(
+            || "check sell_x is boolean",
+            |mut region| region.constrain_equal(sell_x_square.cell(), sell_x.cell()),
+        )?;
+        let mut select = |name: &'static str, lhs: &_, rhs: &_| {
+            layouter.assign_region(
+                || name,
+                |mut region| {
+                    config.conditional_select_config.assign_region(
+                        &sell_x,
+                        lhs,
+                        rhs,
+                        0,
+                        &mut region,
+                    )
+                },
+            )
+        };
+        let token_in = select("select token_in", &token_x, &token_y)?;
+        let token_out = select("select token_out", &token_y, &token_x)?;
+        let reserve_in = select("select reserve_in", &reserve_x, &reserve_y)?;
+        let reserve_out = select("select reserve_out", &reserve_y, &reserve_x)?;
+        layouter.assign_region(
+            || "check the swapped tokens",
+            |mut region| {
+                region.constrain_equal(deposit_resource.resource.label.cell(), token_in.cell())?;
+                region.constrain_equal(payout_resource.resource.label.cell(), token_out.cell())
+            },
+        )?;
+
+        // The reserves change by the deposit and the payout
+        let new_in = AddInstructions::add(
+            &add_chip,
+            layouter.namespace(|| "reserve_in + deposit"),
+            &reserve_in,
+            &deposit_resource.resource.quantity,
+        )?;
+        let new_out = SubInstructions::sub(
+            &sub_chip,
+            layouter.namespace(|| "reserve_out - payout"),
+            &reserve_out,
+            &payout_resource.resource.quantity,
+        )?;
+        config
+            .comparison_config
+            .range_check_u64(layouter.namespace(|| "range check new_in"), &new_in)?;
+        config
+            .comparison_config
+            .range_check_u64(layouter.namespace(|| "range check new_out"), &new_out)?;
+        let mut select = |name: &'static str, lhs: &_, rhs: &_| {
+            layouter.assign_region(
+                || name,
+                |mut region| {
+                    config.conditional_select_config.assign_region(
+                        &sell_x,
+                        lhs,
+                        rhs,
+                        0,
+                        &mut region,
+                    )
+                },
+            )
+        };
+        let new_reserve_x = select("select new reserve_x", &new_in, &new_out)?;
+        let new_reserve_y = select("select new reserve_y", &new_out, &new_in)?;
+        let new_value = poseidon_hash_gadget(
+            config.poseidon_config,
+            layouter.namespace(|| "new value encoding"),
+            [new_reserve_x, new_reserve_y],
+        )?;
+        layouter.assign_region(
+            || "check new value encoding",
+            |mut region| {
+                region.constrain_equal(new_value.cell(), new_pool_resource.resource.value.cell())
+            },
+        )?;
+
+        // check: new_in * new_out >= reserve_in * reserve_out, i.e. the
+        // difference is a u128, the products of u64 being less than 2^128
+        let product = MulInstructions::mul(
+            &mul_chip,
+            layouter.namespace(|| "reserve_in * reserve_out"),
+            &reserve_in,
+            &reserve_out,
+        )?;
+        let new_product = MulInstructions::mul(
+            &mul_chip,
+            layouter.namespace(|| "new_in * new_out"),
+            &new_in,
+            &new_out,
+        )?;
+        let product_gap = SubInstructions::sub(
+            &sub_chip,
+            layouter.namespace(|| "new_product - product"),
+            &new_product,
+            &product,
+        )?;
+        let lookup_config = resource_commit_chip.get_lookup_config();
+        let zs = lookup_config.copy_check(
+            layouter.namespace(|| "12 * K(10) bits range check"),
+            product_gap,
+            12,
+            false,
+        )?;
+        lookup_config.copy_short_check(
+            layouter.namespace(|| "8 bits range check"),
+            zs[12].clone(),
+            8,
+        )?;
+
+        // Publicize the dynamic resource_logic commitments with default value
+        publicize_default_dynamic_resource_logic_commitments(
+            &mut layouter,
+            config.advices[0],
+            config.instances,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.extend(default_resource_logic_cm);
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+}
+
+resource_logic_circuit_impl!(AmmPoolResourceLogicCircuit);
+resource_logic_verifying_info_impl!(AmmPoolResourceLogicCircuit);
+
+impl BorshSerialize for AmmPoolResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        self.pool_resource.serialize(writer)?;
+        self.new_pool_resource.serialize(writer)?;
+        self.deposit_resource.serialize(writer)?;
+        self.payout_resource.serialize(writer)?;
+        self.pool.serialize(writer)?;
+        writer.write_u8(self.sell_x as u8)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for AmmPoolResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let pool_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let new_pool_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let deposit_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let payout_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let pool = Pool::deserialize_reader(reader)?;
+        let sell_x = reader.read_u8()? != 0;
+        Ok(Self {
+            self_resource,
+            pool_resource,
+            new_pool_resource,
+            deposit_resource,
+            payout_resource,
+            pool,
+            sell_x,
+        })
+    }
+}
+
+#[test]
+fn test_halo2_amm_pool_resource_logic_circuit() {
+    use crate::circuit::resource_logic_examples::{
+        signature_verification::COMPRESSED_TOKEN_AUTH_VK, token::Token,
+    };
+    use crate::constant::RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+    use crate::nullifier::tests::random_nullifier_key;
+    use crate::resource_tree::ResourceMerkleTreeLeaves;
+    use halo2_proofs::dev::MockProver;
+
+    let mut rng = OsRng;
+    let owner = TokenAuthorization::from_sk_vk(
+        &pallas::Scalar::random(&mut rng),
+        &COMPRESSED_TOKEN_AUTH_VK,
+    );
+    let trader = TokenAuthorization::from_sk_vk(
+        &pallas::Scalar::random(&mut rng),
+        &COMPRESSED_TOKEN_AUTH_VK,
+    );
+    let npk = pallas::Base::random(&mut rng);
+    let pool = Pool {
+        reserve_x: 1000,
+        reserve_y: 2000,
+        ..Pool::new(
+            Token::new("btc".to_string(), 0).name().clone(),
+            Token::new("eth".to_string(), 0).name().clone(),
+            owner,
+        )
+    };
+
+    // Sell 100 btc for the eth quoted by the pool
+    let quote = pool.quote(true, 100).unwrap();
+    assert_eq!(quote, 181);
+
+    // Builds the circuit of a swap for the self resource at `self_index`
+    let mut swap_circuit = |amount_out: u64, self_index: usize| {
+        let mut pool_resource = pool.create_resource(&mut rng, npk);
+        pool_resource.nk_container = random_nullifier_key(&mut rng).into();
+        let new_pool = pool.swap(true, 100, amount_out).unwrap_or_else(|| Pool {
+            reserve_x: 1100,
+            reserve_y: 2000 - amount_out,
+            ..pool.clone()
+        });
+        let mut new_pool_resource = new_pool.create_resource(&mut rng, npk);
+        new_pool_resource.set_nonce(&pool_resource);
+        let mut deposit = Token::new("btc".to_string(), 100)
+            .create_random_output_token_resource(&mut rng, npk, &owner)
+            .resource()
+            .clone();
+        deposit.set_nonce(&pool_resource);
+        let mut payout = Token::new("eth".to_string(), amount_out)
+            .create_random_output_token_resource(&mut rng, npk, &trader)
+            .resource()
+            .clone();
+        payout.set_nonce(&pool_resource);
+
+        let pool_nf = pool_resource.get_nf().unwrap().inner();
+        let leaves = vec![
+            pool_nf,
+            new_pool_resource.commitment().inner(),
+            pallas::Base::zero(),
+            deposit.commitment().inner(),
+            pallas::Base::zero(),
+            payout.commitment().inner(),
+        ];
+        let resource_merkle_tree = ResourceMerkleTreeLeaves::new(leaves.clone());
+        let witness = |resource: Resource, leaf: pallas::Base| {
+            ResourceExistenceWitness::new(
+                resource,
+                resource_merkle_tree.generate_path(leaf).unwrap(),
+            )
+        };
+        let pool_witness = witness(pool_resource, leaves[0]);
+        let new_pool_witness = witness(new_pool_resource, leaves[1]);
+        AmmPoolResourceLogicCircuit {
+            self_resource: [&pool_witness, &new_pool_witness][self_index].clone(),
+            pool_resource: pool_witness,
+            new_pool_resource: new_pool_witness,
+            deposit_resource: witness(deposit, leaves[3]),
+            payout_resource: witness(payout, leaves[5]),
+            pool: pool.clone(),
+            sell_x: true,
+        }
+    };
+
+    for self_index in [0, 1] {
+        let circuit = swap_circuit(quote, self_index);
+        assert_eq!(circuit.new_pool().unwrap().reserve_y, 2000 - quote);
+        // Test serialization
+        let circuit = AmmPoolResourceLogicCircuit::from_bytes(&circuit.to_bytes());
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+        let prover = MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![public_inputs.to_vec()],
+        )
+        .unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    // The pool doesn't pay more than its quote
+    let circuit = swap_circuit(quote + 1, 1);
+    assert!(circuit.new_pool().is_none());
+    let public_inputs = circuit.get_public_inputs(&mut rng);
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,246 @@
+/// The logic of resources whose owners must not be blacklisted, e.g. to
+/// enforce a denylist of sanctioned addresses inside shielded transactions.
+///
+/// The value of a resource is its owner. The blacklist is a sorted tree of the
+/// denied owners, see `SortedMerkleTree`, and the logic proves that the owner
+/// is not a leaf of it, for inputs and outputs alike: a blacklisted owner can
+/// neither spend nor receive the resource.
+///
+/// The logic publishes the root of the blacklist, `Executable::execute` and
+/// the ledger reject it unless it is the root of the enforced blacklist, see
+/// `StateView::blacklist_root`.
+use crate::{
+    circuit::{
+        blake2s::publicize_default_dynamic_resource_logic_commitments,
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+        resource_non_membership::resource_non_membership_gadget,
+    },
+    constant::RESOURCE_LOGIC_CIRCUIT_BLACKLIST_ROOT_IDX,
+    error::TransactionError,
+    proof::Proof,
+    resource::RandomSeed,
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::{ResourceExistenceWitness, ResourceNonExistenceWitness, SortedMerkleTree},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use halo2_proofs::{
+    circuit::{floor_planner, Layouter},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::pallas;
+use rand::{rngs::OsRng, RngCore};
+
+lazy_static! {
+    pub static ref BLACKLIST_VK: ResourceLogicVerifyingKey =
+        BlacklistResourceLogicCircuit::default()
+            .get_resource_logic_vk()
+            .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_BLACKLIST_VK: pallas::Base = BLACKLIST_VK.get_compressed();
+}
+
+// BlacklistResourceLogicCircuit
+#[derive(Clone, Debug, Default)]
+pub struct BlacklistResourceLogicCircuit {
+    pub self_resource: ResourceExistenceWitness,
+    // The owner, the value of the resource, is not in the blacklist
+    pub owner_non_membership: ResourceNonExistenceWitness,
+}
+
+impl BlacklistResourceLogicCircuit {
+    /// Returns None if the owner of the resource is blacklisted.
+    pub fn new(
+        self_resource: ResourceExistenceWitness,
+        blacklist: &SortedMerkleTree,
+    ) -> Option<Self> {
+        let owner_non_membership =
+            blacklist.generate_non_membership_witness(self_resource.get_resource().value)?;
+        Some(Self {
+            self_resource,
+            owner_non_membership,
+        })
+    }
+
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(ResourceLogicRepresentation::Blacklist, self.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+
+    /// The root of the blacklist the owner is proven absent from.
+    pub fn get_blacklist_root(&self) -> pallas::Base {
+        self.owner_non_membership.get_root()
+    }
+}
+
+impl ResourceLogicCircuit for BlacklistResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        // The owner is not in the blacklist
+        let blacklist_root = resource_non_membership_gadget(
+            layouter.namespace(|| "owner not blacklisted"),
+            &config.resource_non_membership_config,
+            &self_resource.resource.value,
+            &self.owner_non_membership,
+        )?;
+        layouter.constrain_instance(
+            blacklist_root.cell(),
+            config.instances,
+            RESOURCE_LOGIC_CIRCUIT_BLACKLIST_ROOT_IDX,
+        )?;
+
+        // Publicize the dynamic resource_logic commitments with default value
+        publicize_default_dynamic_resource_logic_commitments(
+            &mut layouter,
+            config.advices[0],
+            config.instances,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.push(self.get_blacklist_root());
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+
+    fn native_custom_constraints(&self) -> Option<Result<(), TransactionError>> {
+        let witness = &self.owner_non_membership;
+        let satisfied = witness.get_value() == self.self_resource.get_resource().value
+            && witness.verify(witness.get_root());
+        Some(if satisfied {
+            Ok(())
+        } else {
+            Err(TransactionError::UnsatisfiedResourceLogic)
+        })
+    }
+}
+
+resource_logic_circuit_impl!(BlacklistResourceLogicCircuit);
+resource_logic_verifying_info_impl!(BlacklistResourceLogicCircuit);
+
+impl BorshSerialize for BlacklistResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        self.owner_non_membership.serialize(writer)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for BlacklistResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let owner_non_membership = ResourceNonExistenceWitness::deserialize_reader(reader)?;
+        Ok(Self {
+            self_resource,
+            owner_non_membership,
+        })
+    }
+}
+
+#[test]
+fn test_halo2_blacklist_resource_logic_circuit() {
+    use crate::constant::RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+    use crate::resource::tests::random_resource;
+    use crate::resource_tree::ResourceMerkleTreeLeaves;
+    use halo2_proofs::{arithmetic::Field, dev::MockProver};
+
+    let mut rng = OsRng;
+    let sanctioned = pallas::Base::from(42u64);
+    let blacklist = SortedMerkleTree::new(vec![
+        sanctioned,
+        pallas::Base::from(7u64),
+        pallas::Base::from(1000u64),
+    ]);
+
+    let mut resource = random_resource(&mut rng);
+    resource.value = pallas::Base::from(100u64);
+    let nf = resource.get_nf().unwrap().inner();
+    let resource_merkle_tree =
+        ResourceMerkleTreeLeaves::new(vec![nf, pallas::Base::random(&mut rng)]);
+    let self_resource = ResourceExistenceWitness::new(
+        resource.clone(),
+        resource_merkle_tree.generate_path(nf).unwrap(),
+    );
+    let circuit = BlacklistResourceLogicCircuit::new(self_resource, &blacklist).unwrap();
+
+    // Test serialization
+    let circuit = {
+        let circuit_bytes = circuit.to_bytes();
+        BlacklistResourceLogicCircuit::from_bytes(&circuit_bytes)
+    };
+
+    let public_inputs = circuit.get_public_inputs(&mut rng);
+    assert_eq!(
+        public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_BLACKLIST_ROOT_IDX),
+        blacklist.root()
+    );
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    assert!(circuit.verify_transparently().is_ok());
+
+    // A blacklisted owner has no witness
+    let mut sanctioned_resource = resource;
+    sanctioned_resource.value = sanctioned;
+    let sanctioned_self_resource = ResourceExistenceWitness::new(
+        sanctioned_resource,
+        resource_merkle_tree.generate_path(nf).unwrap(),
+    );
+    assert!(
+        BlacklistResourceLogicCircuit::new(sanctioned_self_resource.clone(), &blacklist).is_none()
+    );
+
+    // Nor passes with the witness of another owner
+    let invalid_circuit = BlacklistResourceLogicCircuit {
+        self_resource: sanctioned_self_resource,
+        ..circuit
+    };
+    let public_inputs = invalid_circuit.get_public_inputs(&mut rng);
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &invalid_circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+    assert!(matches!(
+        invalid_circuit.verify_transparently(),
+        Err(TransactionError::UnsatisfiedResourceLogic)
+    ));
+}
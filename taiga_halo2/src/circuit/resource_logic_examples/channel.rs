@@ -0,0 +1,626 @@
+/// A two-party state channel. The parties exchange mutually-signed updates of
+/// the channel state off-chain, and either of them can settle the latest state
+/// on-chain by consuming the current channel resource and creating the channel
+/// resource of the new state.
+///
+/// The channel resource encodes:
+/// * label: the channel id, Poseidon(pk_a, pk_b)
+/// * value: Poseidon(seq, state)
+///
+/// The settlement logic checks that the new state is signed by both parties
+/// and that its sequence number is strictly greater than the settled one.
+use crate::{
+    circuit::{
+        blake2s::publicize_default_dynamic_resource_logic_commitments,
+        gadgets::{
+            assign_free_advice, assign_free_constant,
+            poseidon_hash::poseidon_hash_gadget,
+            sub::{SubChip, SubInstructions},
+        },
+        integrity::load_resource,
+        merkle_circuit::MerklePoseidonChip,
+        resource_commitment::ResourceCommitChip,
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+        resource_logic_examples::signature_verification::SchnorrSignature,
+    },
+    error::TransactionError,
+    proof::Proof,
+    quantity::Quantity,
+    resource::{RandomSeed, Resource},
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::ResourceExistenceWitness,
+    utils::{poseidon_hash, poseidon_hash_n, read_base_field},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use halo2_gadgets::ecc::chip::EccChip;
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::{
+    arithmetic::CurveAffine,
+    group::{ff::PrimeField, Curve},
+    pallas,
+};
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+lazy_static! {
+    pub static ref CHANNEL_SETTLEMENT_VK: ResourceLogicVerifyingKey =
+        ChannelSettlementResourceLogicCircuit::default()
+            .get_resource_logic_vk()
+            .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_CHANNEL_SETTLEMENT_VK: pallas::Base =
+        CHANNEL_SETTLEMENT_VK.get_compressed();
+}
+
+/// The off-chain state of a channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChannelState {
+    pub seq: u64,
+    // The app state, e.g. the hash of the balances of the parties.
+    pub state: pallas::Base,
+}
+
+/// A channel state signed by both parties, in the order of the channel keys.
+#[derive(Clone, Debug)]
+pub struct ChannelUpdate {
+    pub state: ChannelState,
+    pub signatures: [SchnorrSignature; 2],
+}
+
+#[derive(Clone, Debug)]
+pub struct Channel {
+    pks: [pallas::Point; 2],
+    settled: ChannelState,
+    latest: Option<ChannelUpdate>,
+}
+
+impl ChannelState {
+    pub fn new(seq: u64, state: pallas::Base) -> Self {
+        Self { seq, state }
+    }
+
+    pub fn encode_value(&self) -> pallas::Base {
+        poseidon_hash(pallas::Base::from(self.seq), self.state)
+    }
+
+    // The message both parties sign
+    pub fn message(&self, channel_id: pallas::Base) -> pallas::Base {
+        poseidon_hash_n([channel_id, pallas::Base::from(self.seq), self.state])
+    }
+}
+
+impl ChannelUpdate {
+    pub fn verify(&self, channel: &Channel) -> Result<(), TransactionError> {
+        let message = self.state.message(channel.id());
+        let valid = self
+            .signatures
+            .iter()
+            .zip(channel.pks.iter())
+            .all(|(signature, pk)| signature.pk() == *pk && signature.verify(message));
+        if valid {
+            Ok(())
+        } else {
+            Err(TransactionError::InvalidChannelUpdate)
+        }
+    }
+}
+
+impl Channel {
+    /// Opens a channel with the state settled on-chain.
+    pub fn new(pks: [pallas::Point; 2], settled: ChannelState) -> Self {
+        Self {
+            pks,
+            settled,
+            latest: None,
+        }
+    }
+
+    pub fn id(&self) -> pallas::Base {
+        let a = self.pks[0].to_affine().coordinates().unwrap();
+        let b = self.pks[1].to_affine().coordinates().unwrap();
+        poseidon_hash_n([*a.x(), *a.y(), *b.x(), *b.y()])
+    }
+
+    pub fn settled_state(&self) -> ChannelState {
+        self.settled
+    }
+
+    /// The latest state both parties agreed on.
+    pub fn latest_state(&self) -> ChannelState {
+        self.latest
+            .as_ref()
+            .map_or(self.settled, |update| update.state)
+    }
+
+    pub fn latest_update(&self) -> Option<&ChannelUpdate> {
+        self.latest.as_ref()
+    }
+
+    /// Signs a proposed state with the key of one of the parties.
+    pub fn sign<R: RngCore>(
+        &self,
+        rng: R,
+        sk: pallas::Scalar,
+        state: &ChannelState,
+    ) -> SchnorrSignature {
+        SchnorrSignature::sign(rng, sk, vec![state.message(self.id())])
+    }
+
+    /// Accepts an update received from the counterparty. Updates must be signed
+    /// by both parties and have increasing sequence numbers.
+    pub fn apply_update(&mut self, update: ChannelUpdate) -> Result<(), TransactionError> {
+        if update.state.seq <= self.latest_state().seq {
+            return Err(TransactionError::StaleChannelUpdate);
+        }
+        update.verify(self)?;
+        self.latest = Some(update);
+        Ok(())
+    }
+
+    /// Creates the channel resource of a state.
+    pub fn create_resource<R: RngCore>(
+        &self,
+        mut rng: R,
+        state: &ChannelState,
+        npk: pallas::Base,
+    ) -> Resource {
+        Resource::new_output_resource(
+            *COMPRESSED_CHANNEL_SETTLEMENT_VK,
+            self.id(),
+            state.encode_value(),
+            Quantity::new(1),
+            npk,
+            false,
+            pallas::Base::random(&mut rng),
+        )
+    }
+
+    /// Builds the settlement logic of the latest update for `self_resource`,
+    /// either the consumed channel resource or the created one.
+    pub fn settlement_logic(
+        &self,
+        self_resource: ResourceExistenceWitness,
+        settled_resource: ResourceExistenceWitness,
+        new_resource: ResourceExistenceWitness,
+    ) -> Result<ChannelSettlementResourceLogicCircuit, TransactionError> {
+        let update = self
+            .latest
+            .as_ref()
+            .ok_or(TransactionError::StaleChannelUpdate)?;
+        Ok(ChannelSettlementResourceLogicCircuit {
+            self_resource,
+            settled_resource,
+            new_resource,
+            settled_state: self.settled,
+            new_state: update.state,
+            signatures: update.signatures.clone(),
+        })
+    }
+}
+
+// ChannelSettlementResourceLogicCircuit
+#[derive(Clone, Debug, Default)]
+pub struct ChannelSettlementResourceLogicCircuit {
+    pub self_resource: ResourceExistenceWitness,
+    // the consumed channel resource
+    pub settled_resource: ResourceExistenceWitness,
+    // the created channel resource
+    pub new_resource: ResourceExistenceWitness,
+    pub settled_state: ChannelState,
+    pub new_state: ChannelState,
+    pub signatures: [SchnorrSignature; 2],
+}
+
+impl ChannelSettlementResourceLogicCircuit {
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(
+            ResourceLogicRepresentation::ChannelSettlement,
+            self.to_bytes(),
+        )
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+}
+
+impl ResourceLogicCircuit for ChannelSettlementResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        let merkle_chip = MerklePoseidonChip::construct(config.merkle_config.clone());
+        let resource_commit_chip =
+            ResourceCommitChip::construct(config.resource_commit_config.clone());
+        let sub_chip = SubChip::construct(config.sub_config.clone(), ());
+
+        // The settled resource is consumed in the same ptx
+        let settled_resource = config.load_consumed_resource(
+            layouter.namespace(|| "load the settled resource"),
+            &self.settled_resource,
+            &self_resource,
+        )?;
+        let new_resource = load_resource(
+            layouter.namespace(|| "load the new resource"),
+            config.advices,
+            resource_commit_chip.clone(),
+            config.conditional_select_config,
+            merkle_chip,
+            &self.new_resource,
+        )?;
+
+        let constant_zero = assign_free_constant(
+            layouter.namespace(|| "zero"),
+            config.advices[0],
+            pallas::Base::zero(),
+        )?;
+        let constant_one = assign_free_constant(
+            layouter.namespace(|| "one"),
+            config.advices[0],
+            pallas::Base::one(),
+        )?;
+
+        // The settled resource is consumed and the new resource is created in the same ptx
+        layouter.assign_region(
+            || "check the settlement resources",
+            |mut region| {
+                region.constrain_equal(settled_resource.is_input.cell(), constant_one.cell())?;
+                region.constrain_equal(new_resource.is_input.cell(), constant_zero.cell())?;
+                for resource in [&settled_resource, &new_resource] {
+                    region.constrain_equal(
+                        resource.resource_merkle_root.cell(),
+                        self_resource.resource_merkle_root.cell(),
+                    )?;
+                    region.constrain_equal(
+                        resource.resource.logic.cell(),
+                        self_resource.resource.logic.cell(),
+                    )?;
+                    region.constrain_equal(
+                        resource.resource.label.cell(),
+                        self_resource.resource.label.cell(),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        // The self resource is the settled resource when consumed, the new resource when created
+        let is_output_resource = SubInstructions::sub(
+            &sub_chip,
+            layouter.namespace(|| "is_output"),
+            &self_resource.is_input,
+            &constant_one,
+        )?;
+        layouter.assign_region(
+            || "conditional equal: check consumed self resource",
+            |mut region| {
+                config.conditional_equal_config.assign_region(
+                    &self_resource.is_input,
+                    &self_resource.identity,
+                    &settled_resource.identity,
+                    0,
+                    &mut region,
+                )
+            },
+        )?;
+        layouter.assign_region(
+            || "conditional equal: check created self resource",
+            |mut region| {
+                config.conditional_equal_config.assign_region(
+                    &is_output_resource,
+                    &self_resource.identity,
+                    &new_resource.identity,
+                    0,
+                    &mut region,
+                )
+            },
+        )?;
+
+        // Check the value encodings of the channel states
+        let settled_seq = assign_free_advice(
+            layouter.namespace(|| "witness settled seq"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.settled_state.seq)),
+        )?;
+        let settled_state = assign_free_advice(
+            layouter.namespace(|| "witness settled state"),
+            config.advices[0],
+            Value::known(self.settled_state.state),
+        )?;
+        let new_seq = assign_free_advice(
+            layouter.namespace(|| "witness new seq"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.new_state.seq)),
+        )?;
+        let new_state = assign_free_advice(
+            layouter.namespace(|| "witness new state"),
+            config.advices[0],
+            Value::known(self.new_state.state),
+        )?;
+        let settled_value = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "settled value encoding"),
+            [settled_seq.clone(), settled_state],
+        )?;
+        let new_value = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "new value encoding"),
+            [new_seq.clone(), new_state.clone()],
+        )?;
+        layouter.assign_region(
+            || "check value encodings",
+            |mut region| {
+                region.constrain_equal(
+                    settled_value.cell(),
+                    settled_resource.resource.value.cell(),
+                )?;
+                region.constrain_equal(new_value.cell(), new_resource.resource.value.cell())
+            },
+        )?;
+
+        // check: new_seq > settled_seq, i.e. new_seq - settled_seq - 1 is a u64
+        let seq_gap = {
+            let diff = SubInstructions::sub(
+                &sub_chip,
+                layouter.namespace(|| "new_seq - settled_seq"),
+                &new_seq,
+                &settled_seq,
+            )?;
+            SubInstructions::sub(
+                &sub_chip,
+                layouter.namespace(|| "new_seq - settled_seq - 1"),
+                &diff,
+                &constant_one,
+            )?
+        };
+        let lookup_config = resource_commit_chip.get_lookup_config();
+        let zs = lookup_config.copy_check(
+            layouter.namespace(|| "6 * K(10) bits range check"),
+            seq_gap,
+            6,
+            false,
+        )?;
+        lookup_config.copy_short_check(
+            layouter.namespace(|| "4 bits range check"),
+            zs[6].clone(),
+            4,
+        )?;
+
+        // Both parties signed the new state
+        let ecc_chip = EccChip::construct(config.ecc_config.clone());
+        let message = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "channel message"),
+            [self_resource.resource.label.clone(), new_seq, new_state],
+        )?;
+        let pks = self
+            .signatures
+            .iter()
+            .map(|signature| {
+                signature.verify_in_circuit(
+                    ecc_chip.clone(),
+                    config.poseidon_config.clone(),
+                    layouter.namespace(|| "verify party signature"),
+                    message.clone(),
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // The signers are the parties of the channel
+        let channel_id = poseidon_hash_gadget(
+            config.poseidon_config,
+            layouter.namespace(|| "channel id"),
+            [
+                pks[0].inner().x(),
+                pks[0].inner().y(),
+                pks[1].inner().x(),
+                pks[1].inner().y(),
+            ],
+        )?;
+        layouter.assign_region(
+            || "check channel id",
+            |mut region| {
+                region.constrain_equal(channel_id.cell(), self_resource.resource.label.cell())
+            },
+        )?;
+
+        // Publicize the dynamic resource_logic commitments with default value
+        publicize_default_dynamic_resource_logic_commitments(
+            &mut layouter,
+            config.advices[0],
+            config.instances,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.extend(default_resource_logic_cm);
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+}
+
+resource_logic_circuit_impl!(ChannelSettlementResourceLogicCircuit);
+resource_logic_verifying_info_impl!(ChannelSettlementResourceLogicCircuit);
+
+impl BorshSerialize for ChannelState {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_u64::<LittleEndian>(self.seq)?;
+        writer.write_all(&self.state.to_repr())?;
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for ChannelState {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let seq = reader.read_u64::<LittleEndian>()?;
+        let state = read_base_field(reader)?;
+        Ok(Self { seq, state })
+    }
+}
+
+impl BorshSerialize for ChannelSettlementResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        self.settled_resource.serialize(writer)?;
+        self.new_resource.serialize(writer)?;
+        self.settled_state.serialize(writer)?;
+        self.new_state.serialize(writer)?;
+        for signature in self.signatures.iter() {
+            signature.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for ChannelSettlementResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let settled_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let new_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let settled_state = ChannelState::deserialize_reader(reader)?;
+        let new_state = ChannelState::deserialize_reader(reader)?;
+        let signatures = [
+            SchnorrSignature::deserialize_reader(reader)?,
+            SchnorrSignature::deserialize_reader(reader)?,
+        ];
+        Ok(Self {
+            self_resource,
+            settled_resource,
+            new_resource,
+            settled_state,
+            new_state,
+            signatures,
+        })
+    }
+}
+
+#[test]
+fn test_halo2_channel_settlement_resource_logic_circuit() {
+    use crate::constant::RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+    use crate::nullifier::tests::random_nullifier_key;
+    use crate::resource_tree::ResourceMerkleTreeLeaves;
+    use halo2_proofs::dev::MockProver;
+    use pasta_curves::group::Group;
+
+    let mut rng = OsRng;
+    let sks = [
+        pallas::Scalar::random(&mut rng),
+        pallas::Scalar::random(&mut rng),
+    ];
+    let pks = sks.map(|sk| pallas::Point::generator() * sk);
+    let mut channel = Channel::new(pks, ChannelState::new(3, pallas::Base::random(&mut rng)));
+
+    // Exchange an off-chain update
+    let state = ChannelState::new(5, pallas::Base::random(&mut rng));
+    let signatures = sks.map(|sk| channel.sign(&mut rng, sk, &state));
+    let update = ChannelUpdate {
+        state,
+        signatures: signatures.clone(),
+    };
+    channel.apply_update(update.clone()).unwrap();
+    assert_eq!(channel.latest_state(), state);
+
+    // Stale or half-signed updates are rejected
+    assert!(matches!(
+        channel.apply_update(update),
+        Err(TransactionError::StaleChannelUpdate)
+    ));
+    let half_signed = ChannelUpdate {
+        state: ChannelState::new(6, state.state),
+        signatures: [
+            channel.sign(&mut rng, sks[0], &ChannelState::new(6, state.state)),
+            signatures[1].clone(),
+        ],
+    };
+    assert!(channel.apply_update(half_signed).is_err());
+
+    // Builds the witnesses of a settlement from the settled state to the new state
+    let mut settlement_witnesses = |settled: &ChannelState, new: &ChannelState| {
+        let mut settled_resource = channel.create_resource(&mut rng, settled, pallas::Base::zero());
+        settled_resource.nk_container = random_nullifier_key(&mut rng).into();
+        let mut new_resource = channel.create_resource(&mut rng, new, pallas::Base::zero());
+        new_resource.set_nonce(&settled_resource);
+        let settled_nf = settled_resource.get_nf().unwrap().inner();
+        let new_cm = new_resource.commitment().inner();
+        let resource_merkle_tree = ResourceMerkleTreeLeaves::new(vec![settled_nf, new_cm]);
+        (
+            ResourceExistenceWitness::new(
+                settled_resource,
+                resource_merkle_tree.generate_path(settled_nf).unwrap(),
+            ),
+            ResourceExistenceWitness::new(
+                new_resource,
+                resource_merkle_tree.generate_path(new_cm).unwrap(),
+            ),
+        )
+    };
+
+    // Settle the latest state
+    let (settled_witness, new_witness) =
+        settlement_witnesses(&channel.settled_state(), &channel.latest_state());
+    for self_witness in [settled_witness.clone(), new_witness.clone()] {
+        let circuit = channel
+            .settlement_logic(self_witness, settled_witness.clone(), new_witness.clone())
+            .unwrap();
+        // Test serialization
+        let circuit = ChannelSettlementResourceLogicCircuit::from_bytes(&circuit.to_bytes());
+        let public_inputs = circuit.get_public_inputs(OsRng);
+        let prover = MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![public_inputs.to_vec()],
+        )
+        .unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    // The signed state can't be settled again over itself
+    let (settled_witness, new_witness) = settlement_witnesses(&state, &state);
+    let circuit = ChannelSettlementResourceLogicCircuit {
+        self_resource: new_witness.clone(),
+        settled_resource: settled_witness,
+        new_resource: new_witness,
+        settled_state: state,
+        new_state: state,
+        signatures,
+    };
+    let public_inputs = circuit.get_public_inputs(OsRng);
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,399 @@
+/// A disjunction of logics: the resource is consumed only along with a proof
+/// of one of its branch logics, e.g. "authorized by the owner OR by the
+/// recovery logic".
+///
+/// The label of the resource commits to the compressed vk and to the data of
+/// every branch. When the resource is consumed, the wrapper checks with the
+/// extended or relation that the satisfied branch is one of them and commits
+/// its vk as the first dynamic logic. The commitment is hiding, but the proof
+/// of the branch logic is verified with its vk, which is in the clear, so the
+/// verifier learns which branch was proven. A branch logic witnesses all the
+/// branches, checks that the label of the resource is their hash and then
+/// checks its own condition with its data, e.g. the signature of the owner.
+///
+use crate::{
+    circuit::{
+        blake2s::{resource_logic_commitment_gadget, Blake2sChip},
+        gadgets::{assign_free_advice, poseidon_hash::poseidon_hash_gadget},
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+    },
+    constant::{
+        PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_1_CM_R,
+        RESOURCE_LOGIC_CIRCUIT_FIRST_DYNAMIC_RESOURCE_LOGIC_CM_1,
+        RESOURCE_LOGIC_CIRCUIT_FIRST_DYNAMIC_RESOURCE_LOGIC_CM_2,
+        RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_1,
+        RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_2,
+    },
+    error::TransactionError,
+    proof::Proof,
+    resource::RandomSeed,
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::ResourceExistenceWitness,
+    utils::{poseidon_hash_n, read_base_field},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use halo2_proofs::{
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::{group::ff::PrimeField, pallas};
+use rand::{rngs::OsRng, RngCore};
+
+/// The number of branches of a disjunction. It is fixed by the circuit, a
+/// disjunction with fewer branches repeats one of them.
+pub const DISJUNCTION_BRANCH_NUM: usize = 2;
+
+lazy_static! {
+    pub static ref DISJUNCTION_VK: ResourceLogicVerifyingKey =
+        DisjunctionResourceLogicCircuit::default()
+            .get_resource_logic_vk()
+            .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_DISJUNCTION_VK: pallas::Base = DISJUNCTION_VK.get_compressed();
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DisjunctionBranch {
+    // The compressed vk of the branch logic
+    pub logic_vk: pallas::Base,
+    // The data the branch logic checks, e.g. the public key of the owner
+    pub data: pallas::Base,
+}
+
+// DisjunctionResourceLogicCircuit
+#[derive(Clone, Debug, Default)]
+pub struct DisjunctionResourceLogicCircuit {
+    pub self_resource: ResourceExistenceWitness,
+    pub branches: [DisjunctionBranch; DISJUNCTION_BRANCH_NUM],
+    // The index of the branch proven when the resource is consumed
+    pub satisfied_branch: usize,
+    // rseed is to generate the randomness for resource_logic commitment
+    pub rseed: RandomSeed,
+}
+
+impl DisjunctionResourceLogicCircuit {
+    pub fn encode_label(branches: &[DisjunctionBranch; DISJUNCTION_BRANCH_NUM]) -> pallas::Base {
+        let message: Vec<pallas::Base> = branches
+            .iter()
+            .flat_map(|branch| [branch.logic_vk, branch.data])
+            .collect();
+        poseidon_hash_n::<{ 2 * DISJUNCTION_BRANCH_NUM }>(message.try_into().unwrap())
+    }
+
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(ResourceLogicRepresentation::Disjunction, self.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+
+    // The branch selected after the k-th step of the or relation chain: the
+    // satisfied branch once it is reached, the first branch before.
+    fn selected_branch(&self, k: usize) -> DisjunctionBranch {
+        if self.satisfied_branch <= k {
+            self.branches[self.satisfied_branch]
+        } else {
+            self.branches[0]
+        }
+    }
+}
+
+impl ResourceLogicCircuit for DisjunctionResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        let mut branches = vec![];
+        for branch in self.branches.iter() {
+            let logic_vk = assign_free_advice(
+                layouter.namespace(|| "witness branch logic vk"),
+                config.advices[0],
+                Value::known(branch.logic_vk),
+            )?;
+            let data = assign_free_advice(
+                layouter.namespace(|| "witness branch data"),
+                config.advices[0],
+                Value::known(branch.data),
+            )?;
+            branches.push((logic_vk, data));
+        }
+
+        // check the label of the resource
+        let message: Vec<_> = branches
+            .iter()
+            .flat_map(|(logic_vk, data)| [logic_vk.clone(), data.clone()])
+            .collect();
+        let encoded_label = poseidon_hash_gadget(
+            config.poseidon_config,
+            layouter.namespace(|| "encode label"),
+            <[_; 2 * DISJUNCTION_BRANCH_NUM]>::try_from(message).unwrap(),
+        )?;
+        layouter.assign_region(
+            || "check label",
+            |mut region| {
+                region.constrain_equal(encoded_label.cell(), self_resource.resource.label.cell())
+            },
+        )?;
+
+        // The satisfied branch is one of the branches: chain the or relations,
+        // the k-th selected branch is either the previous one or the k-th
+        // branch. The relations only hold for a consumed resource.
+        let mut selected = branches[0].clone();
+        for (k, branch) in branches.iter().enumerate().skip(1) {
+            let selected_branch = self.selected_branch(k);
+            let next_selected = (
+                assign_free_advice(
+                    layouter.namespace(|| "witness selected logic vk"),
+                    config.advices[0],
+                    Value::known(selected_branch.logic_vk),
+                )?,
+                assign_free_advice(
+                    layouter.namespace(|| "witness selected data"),
+                    config.advices[0],
+                    Value::known(selected_branch.data),
+                )?,
+            );
+            layouter.assign_region(
+                || "extended or relation",
+                |mut region| {
+                    config.extended_or_relation_config.assign_region(
+                        &self_resource.is_input,
+                        (&selected.0, &selected.1),
+                        (&branch.0, &branch.1),
+                        (&next_selected.0, &next_selected.1),
+                        0,
+                        &mut region,
+                    )
+                },
+            )?;
+            selected = next_selected;
+        }
+
+        // Resource Logic Commitment
+        // Commit the satisfied branch logic if the resource is consumed;
+        // publicize the default commitment if the resource is created.
+        let blake2s_chip = Blake2sChip::construct(config.blake2s_config);
+        let resource_logic_cm_r = assign_free_advice(
+            layouter.namespace(|| "resource_logic_cm_r"),
+            config.advices[0],
+            Value::known(
+                self.rseed
+                    .get_resource_logic_cm_r(PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_1_CM_R),
+            ),
+        )?;
+        let branch_cm = resource_logic_commitment_gadget(
+            &mut layouter,
+            &blake2s_chip,
+            selected.0,
+            resource_logic_cm_r,
+        )?;
+
+        let resource_logic_cm_fields: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        let default_cm = [
+            assign_free_advice(
+                layouter.namespace(|| "resource_logic_cm 1"),
+                config.advices[0],
+                Value::known(resource_logic_cm_fields[0]),
+            )?,
+            assign_free_advice(
+                layouter.namespace(|| "resource_logic_cm 2"),
+                config.advices[0],
+                Value::known(resource_logic_cm_fields[1]),
+            )?,
+        ];
+
+        for ((branch_cm, default_cm), instance_idx) in branch_cm.iter().zip(&default_cm).zip([
+            RESOURCE_LOGIC_CIRCUIT_FIRST_DYNAMIC_RESOURCE_LOGIC_CM_1,
+            RESOURCE_LOGIC_CIRCUIT_FIRST_DYNAMIC_RESOURCE_LOGIC_CM_2,
+        ]) {
+            let first_dynamic_resource_logic_cm = layouter.assign_region(
+                || "conditional select: first dynamic resource_logic cm",
+                |mut region| {
+                    config.conditional_select_config.assign_region(
+                        &self_resource.is_input,
+                        branch_cm,
+                        default_cm,
+                        0,
+                        &mut region,
+                    )
+                },
+            )?;
+            layouter.constrain_instance(
+                first_dynamic_resource_logic_cm.cell(),
+                config.instances,
+                instance_idx,
+            )?;
+        }
+
+        // Publicize the second dynamic resource_logic commitment with default value
+        layouter.constrain_instance(
+            default_cm[0].cell(),
+            config.instances,
+            RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_1,
+        )?;
+        layouter.constrain_instance(
+            default_cm[1].cell(),
+            config.instances,
+            RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_2,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        if self.get_self_resource().is_input() {
+            let resource_logic_com_r = self
+                .rseed
+                .get_resource_logic_cm_r(PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_1_CM_R);
+            let resource_logic_com: [pallas::Base; 2] = ResourceLogicCommitment::commit(
+                &self.branches[self.satisfied_branch].logic_vk,
+                &resource_logic_com_r,
+            )
+            .to_public_inputs();
+            public_inputs.extend(resource_logic_com);
+        } else {
+            public_inputs.extend(default_resource_logic_cm);
+        }
+        public_inputs.extend(default_resource_logic_cm);
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+
+    fn native_custom_constraints(&self) -> Option<Result<(), TransactionError>> {
+        let satisfied =
+            self.self_resource.get_resource().get_label() == Self::encode_label(&self.branches);
+        Some(if satisfied {
+            Ok(())
+        } else {
+            Err(TransactionError::UnsatisfiedResourceLogic)
+        })
+    }
+}
+
+resource_logic_circuit_impl!(DisjunctionResourceLogicCircuit);
+resource_logic_verifying_info_impl!(DisjunctionResourceLogicCircuit);
+
+impl BorshSerialize for DisjunctionResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        for branch in self.branches.iter() {
+            writer.write_all(&branch.logic_vk.to_repr())?;
+            writer.write_all(&branch.data.to_repr())?;
+        }
+        (self.satisfied_branch as u32).serialize(writer)?;
+        self.rseed.serialize(writer)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for DisjunctionResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let mut branches = [DisjunctionBranch::default(); DISJUNCTION_BRANCH_NUM];
+        for branch in branches.iter_mut() {
+            branch.logic_vk = read_base_field(reader)?;
+            branch.data = read_base_field(reader)?;
+        }
+        let satisfied_branch = u32::deserialize_reader(reader)? as usize;
+        if satisfied_branch >= DISJUNCTION_BRANCH_NUM {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "satisfied branch out of range",
+            ));
+        }
+        let rseed = RandomSeed::deserialize_reader(reader)?;
+        Ok(Self {
+            self_resource,
+            branches,
+            satisfied_branch,
+            rseed,
+        })
+    }
+}
+
+#[test]
+fn test_halo2_disjunction_resource_logic_circuit() {
+    use crate::constant::{RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE, TAIGA_RESOURCE_TREE_DEPTH};
+    use crate::merkle_tree::LR;
+    use crate::resource::tests::random_resource;
+    use halo2_proofs::arithmetic::Field;
+    use halo2_proofs::dev::MockProver;
+
+    let mut rng = OsRng;
+    let branches = [(); DISJUNCTION_BRANCH_NUM].map(|_| DisjunctionBranch {
+        logic_vk: pallas::Base::random(&mut rng),
+        data: pallas::Base::random(&mut rng),
+    });
+    for (is_input, satisfied_branch) in [(true, 0), (true, 1), (false, 0)] {
+        let circuit = {
+            let mut resource = random_resource(&mut rng);
+            resource.kind.label = DisjunctionResourceLogicCircuit::encode_label(&branches);
+            let lr = if is_input { LR::R } else { LR::L };
+            let merkle_path = [(pallas::Base::zero(), lr); TAIGA_RESOURCE_TREE_DEPTH];
+            DisjunctionResourceLogicCircuit {
+                self_resource: ResourceExistenceWitness::new(resource, merkle_path),
+                branches,
+                satisfied_branch,
+                rseed: RandomSeed::random(&mut rng),
+            }
+        };
+
+        // Test serialization
+        let circuit = {
+            let circuit_bytes = circuit.to_bytes();
+            DisjunctionResourceLogicCircuit::from_bytes(&circuit_bytes)
+        };
+
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+
+        let prover = MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![public_inputs.to_vec()],
+        )
+        .unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        assert!(circuit.verify_transparently().is_ok());
+    }
+
+    // The label doesn't commit to the branches
+    let mut resource = random_resource(&mut rng);
+    resource.kind.label = pallas::Base::random(&mut rng);
+    let merkle_path = [(pallas::Base::zero(), LR::R); TAIGA_RESOURCE_TREE_DEPTH];
+    let invalid_circuit = DisjunctionResourceLogicCircuit {
+        self_resource: ResourceExistenceWitness::new(resource, merkle_path),
+        branches,
+        satisfied_branch: 0,
+        rseed: RandomSeed::random(&mut rng),
+    };
+    assert!(matches!(
+        invalid_circuit.verify_transparently(),
+        Err(TransactionError::UnsatisfiedResourceLogic)
+    ));
+}
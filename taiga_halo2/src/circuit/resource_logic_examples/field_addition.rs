@@ -10,7 +10,7 @@ use crate::{
             ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
         },
     },
-    constant::{RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX, SETUP_PARAMS_MAP},
+    constant::RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX,
     error::TransactionError,
     proof::Proof,
     resource::RandomSeed,
@@ -92,7 +92,7 @@ impl ResourceLogicCircuit for FieldAdditionResourceLogicCircuit {
     }
 
     fn get_self_resource(&self) -> ResourceExistenceWitness {
-        self.self_resource
+        self.self_resource.clone()
     }
 }
 
@@ -0,0 +1,434 @@
+/// Gates the consumption of shielded resources behind an external identity
+/// provider, a centralized authority issuing JWT-style claims. A claim lists
+/// the npk of its subject and Poseidon-hashed attributes (e.g. "kyc_passed"),
+/// and is signed by the authority over their Poseidon digest.
+///
+/// The gated resource encodes:
+/// * label: the attribute required by the app, the first attribute of the claim
+/// * value: Poseidon(authority_pk.x, authority_pk.y)
+///
+/// The logic checks that the claim is signed by the authority, requires the
+/// attribute and that the prover knows the nk of the npk of the claim.
+use crate::{
+    circuit::{
+        blake2s::publicize_default_dynamic_resource_logic_commitments,
+        gadgets::{
+            assign_free_advice, nullifier_key::derive_npk_gadget,
+            poseidon_hash::poseidon_hash_gadget,
+        },
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+        resource_logic_examples::signature_verification::SchnorrSignature,
+    },
+    error::TransactionError,
+    nullifier::NullifierKeyContainer,
+    proof::Proof,
+    resource::RandomSeed,
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::ResourceExistenceWitness,
+    utils::{poseidon_hash, poseidon_hash_n, read_base_field, read_scalar_field},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use halo2_gadgets::ecc::chip::EccChip;
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::{
+    arithmetic::CurveAffine,
+    group::{ff::PrimeField, Curve, Group},
+    pallas,
+};
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+pub const IDENTITY_CLAIM_ATTRIBUTES_NUM: usize = 2;
+const CLAIM_HASH_LEN: usize = IDENTITY_CLAIM_ATTRIBUTES_NUM + 1;
+
+lazy_static! {
+    pub static ref IDENTITY_PROVIDER_VK: ResourceLogicVerifyingKey =
+        IdentityProviderResourceLogicCircuit::default()
+            .get_resource_logic_vk()
+            .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_IDENTITY_PROVIDER_VK: pallas::Base =
+        IDENTITY_PROVIDER_VK.get_compressed();
+}
+
+/// The key of an identity provider.
+#[derive(Clone, Copy, Debug)]
+pub struct IdentityAuthority {
+    sk: pallas::Scalar,
+}
+
+impl IdentityAuthority {
+    pub fn random<R: RngCore>(mut rng: R) -> Self {
+        Self::from_sk(pallas::Scalar::random(&mut rng))
+    }
+
+    pub fn from_sk(sk: pallas::Scalar) -> Self {
+        Self { sk }
+    }
+
+    pub fn sk(&self) -> pallas::Scalar {
+        self.sk
+    }
+
+    pub fn pk(&self) -> pallas::Point {
+        pallas::Point::generator() * self.sk
+    }
+
+    /// The value of the resources gated by the authority.
+    pub fn resource_value(&self) -> pallas::Base {
+        Self::encode_pk(&self.pk())
+    }
+
+    pub fn encode_pk(pk: &pallas::Point) -> pallas::Base {
+        let pk_coord = pk.to_affine().coordinates().unwrap();
+        poseidon_hash(*pk_coord.x(), *pk_coord.y())
+    }
+
+    /// Issues a claim on the attributes of the owner of `npk`.
+    pub fn issue<R: RngCore>(
+        &self,
+        rng: R,
+        npk: pallas::Base,
+        attributes: [pallas::Base; IDENTITY_CLAIM_ATTRIBUTES_NUM],
+    ) -> SignedIdentityClaim {
+        let claim = IdentityClaim { npk, attributes };
+        let signature = SchnorrSignature::sign(rng, self.sk, vec![claim.digest()]);
+        SignedIdentityClaim { claim, signature }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IdentityClaim {
+    /// The npk of the subject
+    pub npk: pallas::Base,
+    /// The Poseidon-hashed attributes of the subject
+    pub attributes: [pallas::Base; IDENTITY_CLAIM_ATTRIBUTES_NUM],
+}
+
+impl IdentityClaim {
+    /// Hashes an attribute, e.g. `b"kyc_passed"`, to a field element. The bytes
+    /// are packed in chunks of 31 bytes.
+    pub fn hash_attribute(attribute: &[u8]) -> pallas::Base {
+        attribute
+            .chunks(31)
+            .fold(pallas::Base::from(attribute.len() as u64), |acc, chunk| {
+                let mut bytes = [0u8; 32];
+                bytes[..chunk.len()].copy_from_slice(chunk);
+                poseidon_hash(acc, pallas::Base::from_repr(bytes).unwrap())
+            })
+    }
+
+    // digest = Poseidon(npk, attributes)
+    pub fn digest(&self) -> pallas::Base {
+        let mut inputs = [self.npk; CLAIM_HASH_LEN];
+        inputs[1..].copy_from_slice(&self.attributes);
+        poseidon_hash_n(inputs)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignedIdentityClaim {
+    pub claim: IdentityClaim,
+    pub signature: SchnorrSignature,
+}
+
+impl SignedIdentityClaim {
+    pub fn verify(&self, authority_pk: &pallas::Point) -> bool {
+        self.signature.pk() == *authority_pk && self.signature.verify(self.claim.digest())
+    }
+}
+
+// IdentityProviderResourceLogicCircuit gates the consumption of resources behind an identity claim
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IdentityProviderResourceLogicCircuit {
+    pub self_resource: ResourceExistenceWitness,
+    pub signed_claim: SignedIdentityClaim,
+    // The nk of the subject of the claim
+    pub nk: pallas::Base,
+}
+
+impl IdentityProviderResourceLogicCircuit {
+    pub fn new(
+        self_resource: ResourceExistenceWitness,
+        signed_claim: SignedIdentityClaim,
+        nk: pallas::Base,
+    ) -> Self {
+        Self {
+            self_resource,
+            signed_claim,
+            nk,
+        }
+    }
+
+    /// Whether the prover can consume the resource with the claim.
+    pub fn is_authorized(&self) -> bool {
+        let resource = self.self_resource.get_resource();
+        NullifierKeyContainer::Key(self.nk).possesses(self.signed_claim.claim.npk)
+            && self.signed_claim.claim.attributes[0] == resource.get_label()
+            && IdentityAuthority::encode_pk(&self.signed_claim.signature.pk()) == resource.value
+            && self
+                .signed_claim
+                .signature
+                .verify(self.signed_claim.claim.digest())
+    }
+
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(
+            ResourceLogicRepresentation::IdentityProvider,
+            self.to_bytes(),
+        )
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+}
+
+impl ResourceLogicCircuit for IdentityProviderResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        // The prover knows the nk of the subject
+        let nk = assign_free_advice(
+            layouter.namespace(|| "witness nk"),
+            config.advices[0],
+            Value::known(self.nk),
+        )?;
+        let npk = derive_npk_gadget(
+            layouter.namespace(|| "derive npk"),
+            config.advices[0],
+            config.poseidon_config.clone(),
+            nk,
+        )?;
+
+        let attributes = self
+            .signed_claim
+            .claim
+            .attributes
+            .iter()
+            .enumerate()
+            .map(|(i, attribute)| {
+                assign_free_advice(
+                    layouter.namespace(|| format!("witness attribute {i}")),
+                    config.advices[0],
+                    Value::known(*attribute),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The claim requires the attribute of the resource
+        layouter.assign_region(
+            || "check required attribute",
+            |mut region| {
+                region.constrain_equal(attributes[0].cell(), self_resource.resource.label.cell())
+            },
+        )?;
+
+        // Verify the signature of the authority on the claim
+        let mut claim = vec![npk];
+        claim.extend(attributes);
+        let claim_digest = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "claim digest"),
+            <[_; CLAIM_HASH_LEN]>::try_from(claim).unwrap(),
+        )?;
+        let ecc_chip = EccChip::construct(config.ecc_config);
+        let authority_pk = self.signed_claim.signature.verify_in_circuit(
+            ecc_chip,
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "verify claim signature"),
+            claim_digest,
+        )?;
+
+        // The value encodes the authority pk
+        let encoded_value = poseidon_hash_gadget(
+            config.poseidon_config,
+            layouter.namespace(|| "value encoding"),
+            [authority_pk.inner().x(), authority_pk.inner().y()],
+        )?;
+        layouter.assign_region(
+            || "check value encoding",
+            |mut region| {
+                region.constrain_equal(encoded_value.cell(), self_resource.resource.value.cell())
+            },
+        )?;
+
+        // Publicize the dynamic resource_logic commitments with default value
+        publicize_default_dynamic_resource_logic_commitments(
+            &mut layouter,
+            config.advices[0],
+            config.instances,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.extend(default_resource_logic_cm);
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+}
+
+resource_logic_circuit_impl!(IdentityProviderResourceLogicCircuit);
+resource_logic_verifying_info_impl!(IdentityProviderResourceLogicCircuit);
+
+impl BorshSerialize for IdentityProviderResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        self.signed_claim.serialize(writer)?;
+        writer.write_all(&self.nk.to_repr())?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for IdentityProviderResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let signed_claim = SignedIdentityClaim::deserialize_reader(reader)?;
+        let nk = read_base_field(reader)?;
+        Ok(Self {
+            self_resource,
+            signed_claim,
+            nk,
+        })
+    }
+}
+
+impl BorshSerialize for SignedIdentityClaim {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.claim.npk.to_repr())?;
+        for attribute in self.claim.attributes.iter() {
+            writer.write_all(&attribute.to_repr())?;
+        }
+        self.signature.serialize(writer)
+    }
+}
+
+impl BorshDeserialize for SignedIdentityClaim {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let npk = read_base_field(reader)?;
+        let mut attributes = [pallas::Base::zero(); IDENTITY_CLAIM_ATTRIBUTES_NUM];
+        for attribute in attributes.iter_mut() {
+            *attribute = read_base_field(reader)?;
+        }
+        let signature = SchnorrSignature::deserialize_reader(reader)?;
+        Ok(Self {
+            claim: IdentityClaim { npk, attributes },
+            signature,
+        })
+    }
+}
+
+impl BorshSerialize for IdentityAuthority {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.sk.to_repr())
+    }
+}
+
+impl BorshDeserialize for IdentityAuthority {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let sk = read_scalar_field(reader)?;
+        Ok(Self { sk })
+    }
+}
+
+#[test]
+fn test_halo2_identity_provider_resource_logic_circuit() {
+    use crate::circuit::resource_logic_circuit::mock_prove_resource_logic;
+    use crate::constant::TAIGA_RESOURCE_TREE_DEPTH;
+    use crate::merkle_tree::LR;
+    use crate::resource::tests::random_resource;
+
+    let mut rng = OsRng;
+    let authority = IdentityAuthority::random(&mut rng);
+    let kyc_passed = IdentityClaim::hash_attribute(b"kyc_passed");
+    let nk = pallas::Base::random(&mut rng);
+    let npk = NullifierKeyContainer::derive_npk(nk);
+    let signed_claim = authority.issue(
+        &mut rng,
+        npk,
+        [kyc_passed, IdentityClaim::hash_attribute(b"country:CH")],
+    );
+    assert!(signed_claim.verify(&authority.pk()));
+    assert!(!signed_claim.verify(&IdentityAuthority::random(&mut rng).pk()));
+
+    // Test the key serialization
+    let authority = {
+        let bytes = borsh::to_vec(&authority).unwrap();
+        IdentityAuthority::deserialize(&mut bytes.as_ref()).unwrap()
+    };
+
+    let mut resource = random_resource(&mut rng);
+    resource.kind.label = kyc_passed;
+    resource.value = authority.resource_value();
+    let merkle_path = [(pallas::Base::zero(), LR::R); TAIGA_RESOURCE_TREE_DEPTH];
+    let circuit = IdentityProviderResourceLogicCircuit::new(
+        ResourceExistenceWitness::new(resource.clone(), merkle_path),
+        signed_claim.clone(),
+        nk,
+    );
+
+    // Test serialization
+    let circuit = {
+        let circuit_bytes = circuit.to_bytes();
+        IdentityProviderResourceLogicCircuit::from_bytes(&circuit_bytes)
+    };
+    assert!(circuit.is_authorized());
+
+    let public_inputs = circuit.get_public_inputs(&mut rng);
+    let prover = mock_prove_resource_logic(&circuit, &public_inputs);
+    assert_eq!(prover.verify(), Ok(()));
+
+    // A prover who doesn't know the nk of the subject can't use the claim
+    let mut invalid_circuit = circuit.clone();
+    invalid_circuit.nk = pallas::Base::random(&mut rng);
+    assert!(!invalid_circuit.is_authorized());
+    let prover = mock_prove_resource_logic(&invalid_circuit, &public_inputs);
+    assert!(prover.verify().is_err());
+
+    // The claim must require the attribute of the resource
+    let mut invalid_circuit = circuit;
+    invalid_circuit.self_resource = {
+        let mut resource = resource;
+        resource.kind.label = IdentityClaim::hash_attribute(b"accredited_investor");
+        ResourceExistenceWitness::new(resource, merkle_path)
+    };
+    assert!(!invalid_circuit.is_authorized());
+    let public_inputs = invalid_circuit.get_public_inputs(&mut rng);
+    let prover = mock_prove_resource_logic(&invalid_circuit, &public_inputs);
+    assert!(prover.verify().is_err());
+}
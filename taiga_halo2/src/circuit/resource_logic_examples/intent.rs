@@ -0,0 +1,326 @@
+/// A generic intent: the intent resource is consumed only along with a proof of
+/// its condition logic. The label of the intent commits to the compressed vk
+/// of the condition logic and to the data of the condition, so the condition
+/// logic checks label == poseidon_hash(condition_vk, condition_data) on its
+/// own, e.g. with the data of the desired resource. The intent logic commits
+/// the condition logic as its first dynamic logic when the intent is consumed.
+///
+use crate::{
+    circuit::{
+        blake2s::{resource_logic_commitment_gadget, Blake2sChip},
+        gadgets::{assign_free_advice, assign_free_constant, poseidon_hash::poseidon_hash_gadget},
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+    },
+    constant::{
+        PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_1_CM_R,
+        RESOURCE_LOGIC_CIRCUIT_FIRST_DYNAMIC_RESOURCE_LOGIC_CM_1,
+        RESOURCE_LOGIC_CIRCUIT_FIRST_DYNAMIC_RESOURCE_LOGIC_CM_2,
+        RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_1,
+        RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_2,
+    },
+    error::TransactionError,
+    proof::Proof,
+    resource::RandomSeed,
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::ResourceExistenceWitness,
+    utils::{poseidon_hash_n, read_base_field},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use halo2_proofs::{
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::{group::ff::PrimeField, pallas};
+use rand::{rngs::OsRng, RngCore};
+
+lazy_static! {
+    pub static ref INTENT_VK: ResourceLogicVerifyingKey = IntentResourceLogicCircuit::default()
+        .get_resource_logic_vk()
+        .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_INTENT_VK: pallas::Base = INTENT_VK.get_compressed();
+}
+
+// IntentResourceLogicCircuit
+#[derive(Clone, Debug, Default)]
+pub struct IntentResourceLogicCircuit {
+    // self_resource is the intent resource
+    pub self_resource: ResourceExistenceWitness,
+    // The compressed vk of the condition logic
+    pub condition_vk: pallas::Base,
+    // The data the condition logic checks the ptx against, e.g. the hash of
+    // the desired resource
+    pub condition_data: pallas::Base,
+    // rseed is to generate the randomness for resource_logic commitment
+    pub rseed: RandomSeed,
+}
+
+impl IntentResourceLogicCircuit {
+    pub fn encode_label(condition_vk: pallas::Base, condition_data: pallas::Base) -> pallas::Base {
+        poseidon_hash_n([condition_vk, condition_data])
+    }
+
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(ResourceLogicRepresentation::Intent, self.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+}
+
+impl ResourceLogicCircuit for IntentResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        let condition_vk = assign_free_advice(
+            layouter.namespace(|| "witness condition vk"),
+            config.advices[0],
+            Value::known(self.condition_vk),
+        )?;
+
+        let condition_data = assign_free_advice(
+            layouter.namespace(|| "witness condition data"),
+            config.advices[0],
+            Value::known(self.condition_data),
+        )?;
+
+        // check the label of intent resource
+        let encoded_label = poseidon_hash_gadget(
+            config.poseidon_config,
+            layouter.namespace(|| "encode label"),
+            [condition_vk.clone(), condition_data],
+        )?;
+        layouter.assign_region(
+            || "check label",
+            |mut region| {
+                region.constrain_equal(encoded_label.cell(), self_resource.resource.label.cell())
+            },
+        )?;
+
+        // check the is_ephemeral flag
+        let constant_one = assign_free_constant(
+            layouter.namespace(|| "one"),
+            config.advices[0],
+            pallas::Base::one(),
+        )?;
+        layouter.assign_region(
+            || "check is_ephemeral",
+            |mut region| {
+                region.constrain_equal(
+                    self_resource.resource.is_ephemeral.cell(),
+                    constant_one.cell(),
+                )
+            },
+        )?;
+
+        // Resource Logic Commitment
+        // Commit the condition resource_logic if the intent is consumed;
+        // publicize the default commitment if the intent is created.
+        let blake2s_chip = Blake2sChip::construct(config.blake2s_config);
+        let resource_logic_cm_r = assign_free_advice(
+            layouter.namespace(|| "resource_logic_cm_r"),
+            config.advices[0],
+            Value::known(
+                self.rseed
+                    .get_resource_logic_cm_r(PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_1_CM_R),
+            ),
+        )?;
+        let condition_cm = resource_logic_commitment_gadget(
+            &mut layouter,
+            &blake2s_chip,
+            condition_vk,
+            resource_logic_cm_r,
+        )?;
+
+        let resource_logic_cm_fields: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        let default_cm = [
+            assign_free_advice(
+                layouter.namespace(|| "resource_logic_cm 1"),
+                config.advices[0],
+                Value::known(resource_logic_cm_fields[0]),
+            )?,
+            assign_free_advice(
+                layouter.namespace(|| "resource_logic_cm 2"),
+                config.advices[0],
+                Value::known(resource_logic_cm_fields[1]),
+            )?,
+        ];
+
+        for ((condition_cm, default_cm), instance_idx) in
+            condition_cm.iter().zip(&default_cm).zip([
+                RESOURCE_LOGIC_CIRCUIT_FIRST_DYNAMIC_RESOURCE_LOGIC_CM_1,
+                RESOURCE_LOGIC_CIRCUIT_FIRST_DYNAMIC_RESOURCE_LOGIC_CM_2,
+            ])
+        {
+            let first_dynamic_resource_logic_cm = layouter.assign_region(
+                || "conditional select: first dynamic resource_logic cm",
+                |mut region| {
+                    config.conditional_select_config.assign_region(
+                        &self_resource.is_input,
+                        condition_cm,
+                        default_cm,
+                        0,
+                        &mut region,
+                    )
+                },
+            )?;
+            layouter.constrain_instance(
+                first_dynamic_resource_logic_cm.cell(),
+                config.instances,
+                instance_idx,
+            )?;
+        }
+
+        // Publicize the second dynamic resource_logic commitment with default value
+        layouter.constrain_instance(
+            default_cm[0].cell(),
+            config.instances,
+            RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_1,
+        )?;
+        layouter.constrain_instance(
+            default_cm[1].cell(),
+            config.instances,
+            RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_2,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        if self.get_self_resource().is_input() {
+            let resource_logic_com_r = self
+                .rseed
+                .get_resource_logic_cm_r(PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_1_CM_R);
+            let resource_logic_com: [pallas::Base; 2] =
+                ResourceLogicCommitment::commit(&self.condition_vk, &resource_logic_com_r)
+                    .to_public_inputs();
+            public_inputs.extend(resource_logic_com);
+        } else {
+            public_inputs.extend(default_resource_logic_cm);
+        }
+        public_inputs.extend(default_resource_logic_cm);
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+
+    fn native_custom_constraints(&self) -> Option<Result<(), TransactionError>> {
+        let resource = self.self_resource.get_resource();
+        let satisfied = resource.get_label()
+            == Self::encode_label(self.condition_vk, self.condition_data)
+            && resource.is_ephemeral;
+        Some(if satisfied {
+            Ok(())
+        } else {
+            Err(TransactionError::UnsatisfiedResourceLogic)
+        })
+    }
+}
+
+resource_logic_circuit_impl!(IntentResourceLogicCircuit);
+resource_logic_verifying_info_impl!(IntentResourceLogicCircuit);
+
+impl BorshSerialize for IntentResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        writer.write_all(&self.condition_vk.to_repr())?;
+        writer.write_all(&self.condition_data.to_repr())?;
+        self.rseed.serialize(writer)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for IntentResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let condition_vk = read_base_field(reader)?;
+        let condition_data = read_base_field(reader)?;
+        let rseed = RandomSeed::deserialize_reader(reader)?;
+        Ok(Self {
+            self_resource,
+            condition_vk,
+            condition_data,
+            rseed,
+        })
+    }
+}
+
+#[test]
+fn test_halo2_intent_resource_logic_circuit() {
+    use crate::constant::{RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE, TAIGA_RESOURCE_TREE_DEPTH};
+    use crate::merkle_tree::LR;
+    use crate::resource::tests::random_resource;
+    use halo2_proofs::arithmetic::Field;
+    use halo2_proofs::dev::MockProver;
+
+    let mut rng = OsRng;
+    let condition_vk = pallas::Base::random(&mut rng);
+    let condition_data = pallas::Base::random(&mut rng);
+    for is_input in [true, false] {
+        let circuit = {
+            let mut resource = random_resource(&mut rng);
+            resource.kind.label =
+                IntentResourceLogicCircuit::encode_label(condition_vk, condition_data);
+            resource.is_ephemeral = true;
+            let lr = if is_input { LR::R } else { LR::L };
+            let merkle_path = [(pallas::Base::zero(), lr); TAIGA_RESOURCE_TREE_DEPTH];
+            IntentResourceLogicCircuit {
+                self_resource: ResourceExistenceWitness::new(resource, merkle_path),
+                condition_vk,
+                condition_data,
+                rseed: RandomSeed::random(&mut rng),
+            }
+        };
+
+        // Test serialization
+        let circuit = {
+            let circuit_bytes = circuit.to_bytes();
+            IntentResourceLogicCircuit::from_bytes(&circuit_bytes)
+        };
+
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+
+        let prover = MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![public_inputs.to_vec()],
+        )
+        .unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The native evaluation agrees with the circuit
+        assert!(circuit.verify_transparently().is_ok());
+        let mut invalid_circuit = circuit.clone();
+        invalid_circuit.condition_data = pallas::Base::random(&mut rng);
+        assert!(matches!(
+            invalid_circuit.verify_transparently(),
+            Err(TransactionError::UnsatisfiedResourceLogic)
+        ));
+    }
+}
@@ -0,0 +1,662 @@
+/// The logic of non-fungible tokens paying a royalty to their creator on every
+/// transfer.
+///
+/// An NFT resource encodes:
+/// * label: Poseidon(creator, royalty, min_price, id), the creator being the
+///   value of the token authorization receiving the royalties, the royalty a
+///   percentage of the price and min_price the least price of a transfer
+/// * quantity: one
+///
+/// A transfer consumes the NFT and creates it for the buyer, in a ptx that
+/// also creates the sale, the tokens paid to the seller, and the royalty, the
+/// tokens of the same kind paid to the creator. The logic checks that the
+/// price, the sum of both, is at least `min_price` and that the royalty is at
+/// least `royalty` percent of it.
+///
+/// The royalty is only due on the price paid in the ptx. The logic can't tell
+/// a sale from a transfer paid in another ptx, or off-chain: such a transfer
+/// pays the royalty of `min_price` only, a `min_price` of zero lets it skip the
+/// royalty entirely.
+///
+/// An NFT is minted from an ephemeral NFT of the same label, without royalty.
+/// The consumed ephemeral NFT publishes the label as a linkability tag, so that
+/// the executor rejects a second mint of the same NFT, see `MintRegistry`.
+use crate::{
+    circuit::{
+        blake2s::publicize_default_dynamic_resource_logic_commitments,
+        gadgets::{
+            add::{AddChip, AddInstructions},
+            assign_free_advice, assign_free_constant,
+            mul::{MulChip, MulInstructions},
+            poseidon_hash::poseidon_hash_gadget,
+            sub::{SubChip, SubInstructions},
+        },
+        integrity::load_resource,
+        merkle_circuit::MerklePoseidonChip,
+        resource_commitment::ResourceCommitChip,
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+        resource_logic_examples::token::{TokenAuthorization, COMPRESSED_TOKEN_VK},
+    },
+    constant::RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX,
+    error::TransactionError,
+    nullifier::Nullifier,
+    proof::Proof,
+    quantity::Quantity,
+    resource::{RandomSeed, Resource},
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::ResourceExistenceWitness,
+    utils::poseidon_hash_n,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::pallas;
+use rand::{rngs::OsRng, RngCore};
+
+lazy_static! {
+    pub static ref NFT_VK: ResourceLogicVerifyingKey = NftResourceLogicCircuit::default()
+        .get_resource_logic_vk()
+        .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_NFT_VK: pallas::Base = NFT_VK.get_compressed();
+}
+
+/// A non-fungible token of a creator.
+#[derive(Copy, Clone, Debug, Default, BorshSerialize, BorshDeserialize)]
+pub struct Nft {
+    pub creator: TokenAuthorization,
+    // The percentage of the price paid to the creator
+    pub royalty: u64,
+    // The least price of a transfer
+    pub min_price: u64,
+    pub id: u64,
+}
+
+impl Nft {
+    /// Fails if the royalty is above 100 percent.
+    pub fn new(
+        creator: TokenAuthorization,
+        royalty: u64,
+        min_price: u64,
+        id: u64,
+    ) -> Result<Self, TransactionError> {
+        if royalty > 100 {
+            return Err(TransactionError::InvalidRoyalty);
+        }
+        Ok(Self {
+            creator,
+            royalty,
+            min_price,
+            id,
+        })
+    }
+
+    pub fn encode_label(&self) -> pallas::Base {
+        poseidon_hash_n([
+            self.creator.to_value(),
+            pallas::Base::from(self.royalty),
+            pallas::Base::from(self.min_price),
+            pallas::Base::from(self.id),
+        ])
+    }
+
+    /// The least royalty due on a transfer for `price`, rounded up.
+    pub fn royalty_of(&self, price: u64) -> u64 {
+        let royalty = (u128::from(price) * u128::from(self.royalty)).div_ceil(100);
+        royalty as u64
+    }
+
+    /// Creates the NFT resource, owned by `npk`.
+    pub fn create_resource<R: RngCore>(&self, mut rng: R, npk: pallas::Base) -> Resource {
+        Resource::new_output_resource(
+            *COMPRESSED_NFT_VK,
+            self.encode_label(),
+            pallas::Base::zero(),
+            Quantity::new(1),
+            npk,
+            false,
+            pallas::Base::random(&mut rng),
+        )
+    }
+
+    /// The ephemeral NFT the NFT is minted from.
+    pub fn create_ephemeral_resource<R: RngCore>(&self, mut rng: R) -> Resource {
+        Resource::new_input_resource(
+            *COMPRESSED_NFT_VK,
+            self.encode_label(),
+            pallas::Base::zero(),
+            Quantity::new(1),
+            pallas::Base::random(&mut rng),
+            Nullifier::random(&mut rng),
+            true,
+            pallas::Base::random(&mut rng),
+        )
+    }
+}
+
+// NftResourceLogicCircuit
+#[derive(Clone, Debug, Default)]
+pub struct NftResourceLogicCircuit {
+    pub self_resource: ResourceExistenceWitness,
+    // the consumed NFT, ephemeral for a mint
+    pub nft_resource: ResourceExistenceWitness,
+    // the created NFT
+    pub new_nft_resource: ResourceExistenceWitness,
+    // the tokens paid to the seller, unchecked for a mint
+    pub sale_resource: ResourceExistenceWitness,
+    // the tokens paid to the creator, unchecked for a mint
+    pub royalty_resource: ResourceExistenceWitness,
+    pub nft: Nft,
+}
+
+impl NftResourceLogicCircuit {
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(ResourceLogicRepresentation::Nft, self.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+
+    /// Whether the logic mints the NFT.
+    pub fn is_mint(&self) -> bool {
+        self.nft_resource.get_resource().is_ephemeral
+    }
+}
+
+impl ResourceLogicCircuit for NftResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        let merkle_chip = MerklePoseidonChip::construct(config.merkle_config.clone());
+        let resource_commit_chip =
+            ResourceCommitChip::construct(config.resource_commit_config.clone());
+        let add_chip = AddChip::construct(config.add_config.clone(), ());
+        let sub_chip = SubChip::construct(config.sub_config.clone(), ());
+        let mul_chip = MulChip::construct(config.mul_config.clone());
+
+        // The consumed NFT is consumed in the same ptx
+        let nft_resource = config.load_consumed_resource(
+            layouter.namespace(|| "load the nft resource"),
+            &self.nft_resource,
+            &self_resource,
+        )?;
+        let [new_nft_resource, sale_resource, royalty_resource] = [
+            &self.new_nft_resource,
+            &self.sale_resource,
+            &self.royalty_resource,
+        ]
+        .map(|resource| {
+            load_resource(
+                layouter.namespace(|| "load a created resource"),
+                config.advices,
+                resource_commit_chip.clone(),
+                config.conditional_select_config,
+                merkle_chip.clone(),
+                resource,
+            )
+        });
+        let new_nft_resource = new_nft_resource?;
+        let sale_resource = sale_resource?;
+        let royalty_resource = royalty_resource?;
+
+        let constant_zero = assign_free_constant(
+            layouter.namespace(|| "zero"),
+            config.advices[0],
+            pallas::Base::zero(),
+        )?;
+        let constant_one = assign_free_constant(
+            layouter.namespace(|| "one"),
+            config.advices[0],
+            pallas::Base::one(),
+        )?;
+        let constant_hundred = assign_free_constant(
+            layouter.namespace(|| "hundred"),
+            config.advices[0],
+            pallas::Base::from(100u64),
+        )?;
+
+        // Witness the label of the NFT
+        let creator = assign_free_advice(
+            layouter.namespace(|| "witness creator"),
+            config.advices[0],
+            Value::known(self.nft.creator.to_value()),
+        )?;
+        let royalty = assign_free_advice(
+            layouter.namespace(|| "witness royalty"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.nft.royalty)),
+        )?;
+        let min_price = assign_free_advice(
+            layouter.namespace(|| "witness min_price"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.nft.min_price)),
+        )?;
+        let id = assign_free_advice(
+            layouter.namespace(|| "witness id"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.nft.id)),
+        )?;
+        let label = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "label encoding"),
+            [creator.clone(), royalty.clone(), min_price.clone(), id],
+        )?;
+
+        // The NFT resources are the consumed and the created NFT of the same
+        // ptx, the sale and the royalty are created resources
+        layouter.assign_region(
+            || "check the nft resources",
+            |mut region| {
+                region.constrain_equal(label.cell(), self_resource.resource.label.cell())?;
+                for resource in [&new_nft_resource, &sale_resource, &royalty_resource] {
+                    region.constrain_equal(resource.is_input.cell(), constant_zero.cell())?;
+                    region.constrain_equal(
+                        resource.resource_merkle_root.cell(),
+                        self_resource.resource_merkle_root.cell(),
+                    )?;
+                }
+                for resource in [&nft_resource, &new_nft_resource] {
+                    region.constrain_equal(
+                        resource.resource.logic.cell(),
+                        self_resource.resource.logic.cell(),
+                    )?;
+                    region.constrain_equal(resource.resource.label.cell(), label.cell())?;
+                    region
+                        .constrain_equal(resource.resource.quantity.cell(), constant_one.cell())?;
+                }
+                region.constrain_equal(
+                    new_nft_resource.resource.value.cell(),
+                    nft_resource.resource.value.cell(),
+                )
+            },
+        )?;
+
+        // The self resource is the consumed NFT when consumed, the created NFT when created
+        let is_output_resource = SubInstructions::sub(
+            &sub_chip,
+            layouter.namespace(|| "is_output"),
+            &constant_one,
+            &self_resource.is_input,
+        )?;
+        layouter.assign_region(
+            || "conditional equal: check consumed self resource",
+            |mut region| {
+                config.conditional_equal_config.assign_region(
+                    &self_resource.is_input,
+                    &self_resource.identity,
+                    &nft_resource.identity,
+                    0,
+                    &mut region,
+                )
+            },
+        )?;
+        layouter.assign_region(
+            || "conditional equal: check created self resource",
+            |mut region| {
+                config.conditional_equal_config.assign_region(
+                    &is_output_resource,
+                    &self_resource.identity,
+                    &new_nft_resource.identity,
+                    0,
+                    &mut region,
+                )
+            },
+        )?;
+
+        // A transfer pays the sale and the royalty in tokens of the same kind,
+        // the royalty to the creator
+        let is_mint = nft_resource.resource.is_ephemeral.clone();
+        let is_transfer = SubInstructions::sub(
+            &sub_chip,
+            layouter.namespace(|| "is_transfer"),
+            &constant_one,
+            &is_mint,
+        )?;
+        let token_vk = assign_free_advice(
+            layouter.namespace(|| "witness token vk"),
+            config.advices[0],
+            Value::known(*COMPRESSED_TOKEN_VK),
+        )?;
+        for (lhs, rhs) in [
+            (&sale_resource.resource.logic, &token_vk),
+            (&royalty_resource.resource.logic, &token_vk),
+            (
+                &royalty_resource.resource.label,
+                &sale_resource.resource.label,
+            ),
+            (&royalty_resource.resource.value, &creator),
+        ] {
+            layouter.assign_region(
+                || "conditional equal: check the payments",
+                |mut region| {
+                    config.conditional_equal_config.assign_region(
+                        &is_transfer,
+                        lhs,
+                        rhs,
+                        0,
+                        &mut region,
+                    )
+                },
+            )?;
+        }
+
+        // check: royalty_quantity * 100 >= (sale_quantity + royalty_quantity) * royalty,
+        // i.e. the difference is a u128 for a transfer
+        config
+            .comparison_config
+            .range_check_u64(layouter.namespace(|| "range check royalty"), &royalty)?;
+        let price = AddInstructions::add(
+            &add_chip,
+            layouter.namespace(|| "price"),
+            &sale_resource.resource.quantity,
+            &royalty_resource.resource.quantity,
+        )?;
+        config
+            .comparison_config
+            .range_check_u64(layouter.namespace(|| "range check price"), &price)?;
+
+        // check: price >= min_price for a transfer, i.e. the difference is a u64
+        config
+            .comparison_config
+            .range_check_u64(layouter.namespace(|| "range check min_price"), &min_price)?;
+        let price_gap = SubInstructions::sub(
+            &sub_chip,
+            layouter.namespace(|| "price - min_price"),
+            &price,
+            &min_price,
+        )?;
+        let price_gap = layouter.assign_region(
+            || "select the price gap of a transfer",
+            |mut region| {
+                config.conditional_select_config.assign_region(
+                    &is_transfer,
+                    &price_gap,
+                    &constant_zero,
+                    0,
+                    &mut region,
+                )
+            },
+        )?;
+        config
+            .comparison_config
+            .range_check_u64(layouter.namespace(|| "range check price gap"), &price_gap)?;
+        let due = MulInstructions::mul(
+            &mul_chip,
+            layouter.namespace(|| "price * royalty"),
+            &price,
+            &royalty,
+        )?;
+        let paid = MulInstructions::mul(
+            &mul_chip,
+            layouter.namespace(|| "royalty_quantity * 100"),
+            &royalty_resource.resource.quantity,
+            &constant_hundred,
+        )?;
+        let royalty_gap =
+            SubInstructions::sub(&sub_chip, layouter.namespace(|| "paid - due"), &paid, &due)?;
+        let royalty_gap = layouter.assign_region(
+            || "select the royalty gap of a transfer",
+            |mut region| {
+                config.conditional_select_config.assign_region(
+                    &is_transfer,
+                    &royalty_gap,
+                    &constant_zero,
+                    0,
+                    &mut region,
+                )
+            },
+        )?;
+        let lookup_config = resource_commit_chip.get_lookup_config();
+        let zs = lookup_config.copy_check(
+            layouter.namespace(|| "12 * K(10) bits range check"),
+            royalty_gap,
+            12,
+            false,
+        )?;
+        lookup_config.copy_short_check(
+            layouter.namespace(|| "8 bits range check"),
+            zs[12].clone(),
+            8,
+        )?;
+
+        // The consumed ephemeral NFT of a mint publishes the label
+        let is_minted = MulInstructions::mul(
+            &mul_chip,
+            layouter.namespace(|| "is_mint * is_input"),
+            &is_mint,
+            &self_resource.is_input,
+        )?;
+        let mint_tag = MulInstructions::mul(
+            &mul_chip,
+            layouter.namespace(|| "mint tag"),
+            &is_minted,
+            &label,
+        )?;
+        layouter.constrain_instance(
+            mint_tag.cell(),
+            config.instances,
+            RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX,
+        )?;
+
+        // Publicize the dynamic resource_logic commitments with default value
+        publicize_default_dynamic_resource_logic_commitments(
+            &mut layouter,
+            config.advices[0],
+            config.instances,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.extend(default_resource_logic_cm);
+        let mint_tag = if self.is_mint() && self.self_resource.is_input() {
+            self.nft.encode_label()
+        } else {
+            pallas::Base::zero()
+        };
+        public_inputs.push(mint_tag);
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+}
+
+resource_logic_circuit_impl!(NftResourceLogicCircuit);
+resource_logic_verifying_info_impl!(NftResourceLogicCircuit);
+
+impl BorshSerialize for NftResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        self.nft_resource.serialize(writer)?;
+        self.new_nft_resource.serialize(writer)?;
+        self.sale_resource.serialize(writer)?;
+        self.royalty_resource.serialize(writer)?;
+        self.nft.serialize(writer)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for NftResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let nft_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let new_nft_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let sale_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let royalty_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let nft = Nft::deserialize_reader(reader)?;
+        Ok(Self {
+            self_resource,
+            nft_resource,
+            new_nft_resource,
+            sale_resource,
+            royalty_resource,
+            nft,
+        })
+    }
+}
+
+#[test]
+fn test_halo2_nft_resource_logic_circuit() {
+    use crate::circuit::resource_logic_examples::{
+        signature_verification::COMPRESSED_TOKEN_AUTH_VK, token::Token,
+    };
+    use crate::constant::RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+    use crate::nullifier::tests::random_nullifier_key;
+    use crate::resource_tree::ResourceMerkleTreeLeaves;
+    use halo2_proofs::dev::MockProver;
+
+    let mut rng = OsRng;
+    let auth = || {
+        TokenAuthorization::from_sk_vk(&pallas::Scalar::random(OsRng), &COMPRESSED_TOKEN_AUTH_VK)
+    };
+    let (creator, seller) = (auth(), auth());
+    let npk = pallas::Base::random(&mut rng);
+    assert!(matches!(
+        Nft::new(creator, 101, 1000, 1),
+        Err(TransactionError::InvalidRoyalty)
+    ));
+    let nft = Nft::new(creator, 5, 1000, 1).unwrap();
+    assert_eq!(nft.royalty_of(1000), 50);
+    assert_eq!(nft.royalty_of(1001), 51);
+
+    // Builds the circuit of a transfer for `royalty` out of `price`, for the
+    // self resource at `self_index`
+    let transfer_circuit = |price: u64, royalty: u64, self_index: usize| {
+        let mut rng = OsRng;
+        let mut nft_resource = nft.create_resource(&mut rng, npk);
+        nft_resource.nk_container = random_nullifier_key(&mut rng).into();
+        let mut new_nft_resource = nft.create_resource(&mut rng, npk);
+        new_nft_resource.set_nonce(&nft_resource);
+        let mut sale = Token::new("usdc".to_string(), price - royalty)
+            .create_random_output_token_resource(&mut rng, npk, &seller)
+            .resource()
+            .clone();
+        sale.set_nonce(&nft_resource);
+        let mut payment = Token::new("usdc".to_string(), royalty)
+            .create_random_output_token_resource(&mut rng, npk, &creator)
+            .resource()
+            .clone();
+        payment.set_nonce(&nft_resource);
+
+        let nft_nf = nft_resource.get_nf().unwrap().inner();
+        let leaves = vec![
+            nft_nf,
+            new_nft_resource.commitment().inner(),
+            pallas::Base::zero(),
+            sale.commitment().inner(),
+            pallas::Base::zero(),
+            payment.commitment().inner(),
+        ];
+        let resource_merkle_tree = ResourceMerkleTreeLeaves::new(leaves.clone());
+        let witness = |resource: Resource, leaf: pallas::Base| {
+            ResourceExistenceWitness::new(
+                resource,
+                resource_merkle_tree.generate_path(leaf).unwrap(),
+            )
+        };
+        let nft_witness = witness(nft_resource, leaves[0]);
+        let new_nft_witness = witness(new_nft_resource, leaves[1]);
+        NftResourceLogicCircuit {
+            self_resource: [&nft_witness, &new_nft_witness][self_index].clone(),
+            nft_resource: nft_witness,
+            new_nft_resource: new_nft_witness,
+            sale_resource: witness(sale, leaves[3]),
+            royalty_resource: witness(payment, leaves[5]),
+            nft,
+        }
+    };
+    let mock_prove = |circuit: &NftResourceLogicCircuit| {
+        let public_inputs = circuit.get_public_inputs(OsRng);
+        MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            circuit,
+            vec![public_inputs.to_vec()],
+        )
+        .unwrap()
+        .verify()
+    };
+
+    for self_index in [0, 1] {
+        let circuit = transfer_circuit(1000, 50, self_index);
+        assert!(!circuit.is_mint());
+        // Test serialization
+        let circuit = NftResourceLogicCircuit::from_bytes(&circuit.to_bytes());
+        assert_eq!(mock_prove(&circuit), Ok(()));
+    }
+
+    // The royalty can't be underpaid
+    let circuit = transfer_circuit(1000, 49, 0);
+    assert!(mock_prove(&circuit).is_err());
+
+    // The price can't be below the min price, even with the royalty paid
+    let circuit = transfer_circuit(999, 50, 0);
+    assert!(mock_prove(&circuit).is_err());
+
+    // A mint is not paid and publishes the label
+    let ephemeral_nft = nft.create_ephemeral_resource(&mut rng);
+    let mut nft_resource = nft.create_resource(&mut rng, npk);
+    nft_resource.set_nonce(&ephemeral_nft);
+    let leaves = vec![
+        ephemeral_nft.get_nf().unwrap().inner(),
+        nft_resource.commitment().inner(),
+    ];
+    let resource_merkle_tree = ResourceMerkleTreeLeaves::new(leaves.clone());
+    let ephemeral_witness = ResourceExistenceWitness::new(
+        ephemeral_nft,
+        resource_merkle_tree.generate_path(leaves[0]).unwrap(),
+    );
+    let nft_witness = ResourceExistenceWitness::new(
+        nft_resource,
+        resource_merkle_tree.generate_path(leaves[1]).unwrap(),
+    );
+    for (self_resource, tag) in [
+        (ephemeral_witness.clone(), nft.encode_label()),
+        (nft_witness.clone(), pallas::Base::zero()),
+    ] {
+        let circuit = NftResourceLogicCircuit {
+            self_resource,
+            nft_resource: ephemeral_witness.clone(),
+            new_nft_resource: nft_witness.clone(),
+            sale_resource: nft_witness.clone(),
+            royalty_resource: nft_witness.clone(),
+            nft,
+        };
+        assert!(circuit.is_mint());
+        let public_inputs = circuit.get_public_inputs(&mut rng);
+        assert_eq!(
+            public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX),
+            tag
+        );
+        assert_eq!(mock_prove(&circuit), Ok(()));
+    }
+}
@@ -16,7 +16,6 @@ use crate::{
         },
         resource_logic_examples::token::{Token, TOKEN_VK},
     },
-    constant::SETUP_PARAMS_MAP,
     error::TransactionError,
     nullifier::Nullifier,
     proof::Proof,
@@ -40,7 +39,9 @@ use rand::RngCore;
 
 lazy_static! {
     pub static ref OR_RELATION_INTENT_VK: ResourceLogicVerifyingKey =
-        OrRelationIntentResourceLogicCircuit::default().get_resource_logic_vk();
+        OrRelationIntentResourceLogicCircuit::default()
+            .get_resource_logic_vk()
+            .expect("keygen_vk should not fail");
     pub static ref COMPRESSED_OR_RELATION_INTENT_VK: pallas::Base =
         OR_RELATION_INTENT_VK.get_compressed();
 }
@@ -323,7 +324,7 @@ impl ResourceLogicCircuit for OrRelationIntentResourceLogicCircuit {
     }
 
     fn get_self_resource(&self) -> ResourceExistenceWitness {
-        self.self_resource
+        self.self_resource.clone()
     }
 }
 
@@ -441,7 +442,7 @@ fn test_halo2_or_relation_intent_resource_logic_circuit() {
             let merkle_path = resource_merkle_tree
                 .generate_path(output_resource_cm_1)
                 .unwrap();
-            ResourceExistenceWitness::new(desired_resource, merkle_path)
+            ResourceExistenceWitness::new(desired_resource.clone(), merkle_path)
         };
 
         OrRelationIntentResourceLogicCircuit {
@@ -14,7 +14,6 @@ use crate::{
             ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
         },
     },
-    constant::SETUP_PARAMS_MAP,
     error::TransactionError,
     proof::Proof,
     resource::RandomSeed,
@@ -40,7 +39,9 @@ use label::PartialFulfillmentIntentLabel;
 
 lazy_static! {
     pub static ref PARTIAL_FULFILLMENT_INTENT_VK: ResourceLogicVerifyingKey =
-        PartialFulfillmentIntentResourceLogicCircuit::default().get_resource_logic_vk();
+        PartialFulfillmentIntentResourceLogicCircuit::default()
+            .get_resource_logic_vk()
+            .expect("keygen_vk should not fail");
     pub static ref COMPRESSED_PARTIAL_FULFILLMENT_INTENT_VK: pallas::Base =
         PARTIAL_FULFILLMENT_INTENT_VK.get_compressed();
 }
@@ -209,7 +210,7 @@ impl ResourceLogicCircuit for PartialFulfillmentIntentResourceLogicCircuit {
     }
 
     fn get_self_resource(&self) -> ResourceExistenceWitness {
-        self.self_resource
+        self.self_resource.clone()
     }
 }
 
@@ -282,7 +283,7 @@ mod tests {
 
         let sell_resource_witness = {
             let merkle_path = resource_merkle_tree.generate_path(sell_nf).unwrap();
-            ResourceExistenceWitness::new(*sell_resource, merkle_path)
+            ResourceExistenceWitness::new(sell_resource.clone(), merkle_path)
         };
 
         let intent_resource_witness = {
@@ -4,6 +4,7 @@ use crate::{
         gadgets::assign_free_advice,
         resource_logic_examples::token::{Token, TokenAuthorization, TokenResource, TOKEN_VK},
     },
+    quantity::Quantity,
     resource::Resource,
     utils::poseidon_hash_n,
 };
@@ -30,7 +31,10 @@ impl Swap {
         buy: Token,
         auth: TokenAuthorization,
     ) -> Self {
-        assert_eq!(buy.quantity() % sell.quantity(), 0);
+        assert_eq!(
+            buy.quantity().checked_rem(sell.quantity()),
+            Some(Quantity::ZERO)
+        );
 
         let sell = {
             let nk = pallas::Base::random(&mut rng);
@@ -47,34 +51,87 @@ impl Swap {
     pub fn fill(&self, mut rng: impl RngCore, offer: Token) -> (Resource, Resource) {
         assert_eq!(offer.name(), self.buy.name());
 
-        let ratio = self.buy.quantity() / self.sell.quantity;
-        assert_eq!(offer.quantity() % ratio, 0);
+        let ratio = self
+            .buy
+            .quantity()
+            .checked_div(self.sell.quantity)
+            .expect("sell quantity must be non-zero");
+        assert_eq!(offer.quantity().checked_rem(ratio), Some(Quantity::ZERO));
 
         let offer_resource = offer.create_random_output_token_resource(
             &mut rng,
-            self.sell.resource().nk_container.get_npk(),
+            self.sell.resource().get_npk(),
             &self.auth,
         );
 
         let returned_resource = if offer.quantity() < self.buy.quantity() {
-            let filled_quantity = offer.quantity() / ratio;
-            let returned_quantity = self.sell.quantity - filled_quantity;
+            let filled_quantity = offer.quantity().checked_div(ratio).unwrap();
+            let returned_quantity = self
+                .sell
+                .quantity
+                .checked_sub(filled_quantity)
+                .expect("filled quantity exceeds the sold quantity");
             let returned_token = Token::new(
                 self.sell.token_name().inner().to_string(),
-                returned_quantity,
+                returned_quantity.inner(),
             );
-            *returned_token
+            returned_token
                 .create_random_output_token_resource(
                     &mut rng,
-                    self.sell.resource().nk_container.get_npk(),
+                    self.sell.resource().get_npk(),
                     &self.auth,
                 )
-                .resource()
+                .resource
         } else {
-            Resource::random_padding_resource(&mut rng)
+            Resource::padding_resource(&mut rng)
         };
 
-        (*offer_resource, returned_resource)
+        (offer_resource.resource, returned_resource)
+    }
+
+    /// The bought quantity worth `sold` of the sold tokens at the price of the
+    /// swap, cross-multiplied in u128 as in the circuit. None if it is not a
+    /// whole quantity.
+    fn price(&self, sold: Quantity) -> Option<Quantity> {
+        let bought = u128::from(sold.inner()) * u128::from(self.buy.quantity().inner());
+        let sell = u128::from(self.sell.quantity.inner());
+        if sell == 0 || bought % sell != 0 {
+            return None;
+        }
+        u64::try_from(bought / sell).ok().map(Quantity::from)
+    }
+
+    /// The bought tokens for filling `filled` of the sold tokens, at the price
+    /// of the swap. None if `filled` is zero, exceeds the sold quantity or is
+    /// not worth a whole quantity of the bought token.
+    pub fn offer(&self, filled: Quantity) -> Option<Token> {
+        if filled.is_zero() || filled > self.sell.quantity {
+            return None;
+        }
+        let quantity = self.price(filled)?;
+        Some(Token::new(self.buy.name().inner(), quantity.inner()))
+    }
+
+    /// The swap of the sold tokens returned by a partial fill, at the same
+    /// price, so that the owner can offer them again. `returned` is the
+    /// returned resource as created in the transaction. None if the returned
+    /// tokens are not worth a whole quantity of the bought token.
+    pub fn remainder(&self, returned: &Resource) -> Option<Self> {
+        let mut resource = returned.clone();
+        resource.nk_container = self.sell.nk_container.clone();
+        let sell = TokenResource {
+            token_name: self.sell.token_name().clone(),
+            resource,
+        };
+        let buy = Token::new(
+            self.buy.name().inner(),
+            self.price(returned.quantity)?.inner(),
+        );
+        Some(Swap {
+            sell,
+            buy,
+            auth: self.auth,
+        })
     }
 
     pub fn encode_label(&self) -> pallas::Base {
@@ -97,8 +154,8 @@ impl Swap {
             *COMPRESSED_PARTIAL_FULFILLMENT_INTENT_VK,
             self.encode_label(),
             pallas::Base::zero(),
-            1u64,
-            self.sell.resource().nk_container.get_nk().unwrap(),
+            1u64.into(),
+            self.sell.resource().get_nk().unwrap(),
             self.sell.resource().get_nf().unwrap(),
             true,
             rseed,
@@ -10,7 +10,7 @@ use crate::{
         },
         resource_logic_examples::signature_verification::COMPRESSED_TOKEN_AUTH_VK,
     },
-    constant::{GENERATOR, SETUP_PARAMS_MAP},
+    constant::GENERATOR,
     error::TransactionError,
     proof::Proof,
     resource::RandomSeed,
@@ -36,8 +36,9 @@ use rand::RngCore;
 const CIPHER_LEN: usize = 9;
 
 lazy_static! {
-    pub static ref RECEIVER_VK: ResourceLogicVerifyingKey =
-        ReceiverResourceLogicCircuit::default().get_resource_logic_vk();
+    pub static ref RECEIVER_VK: ResourceLogicVerifyingKey = ReceiverResourceLogicCircuit::default()
+        .get_resource_logic_vk()
+        .expect("keygen_vk should not fail");
     pub static ref COMPRESSED_RECEIVER_VK: pallas::Base = RECEIVER_VK.get_compressed();
 }
 
@@ -188,18 +189,7 @@ impl ResourceLogicCircuit for ReceiverResourceLogicCircuit {
             );
         public_inputs.extend(custom_public_input_padding.iter());
 
-        let self_resource = self.self_resource.get_resource();
-        let message = vec![
-            self_resource.kind.logic,
-            self_resource.kind.label,
-            self_resource.value,
-            pallas::Base::from(self_resource.quantity),
-            self_resource.nonce.inner(),
-            self_resource.get_npk(),
-            pallas::Base::from(self_resource.is_ephemeral as u64),
-            self_resource.rseed,
-        ];
-        let plaintext = ResourcePlaintext::padding(&message);
+        let plaintext = ResourcePlaintext::from_resource(&self.self_resource.get_resource());
         let key = SecretKey::from_dh_exchange(&self.rcv_pk, &mod_r_p(self.sk));
         let cipher = ResourceCiphertext::encrypt(&plaintext, &key, &self.encrypt_nonce);
         cipher.inner().iter().for_each(|&c| public_inputs.push(c));
@@ -213,7 +203,7 @@ impl ResourceLogicCircuit for ReceiverResourceLogicCircuit {
     }
 
     fn get_self_resource(&self) -> ResourceExistenceWitness {
-        self.self_resource
+        self.self_resource.clone()
     }
 }
 
@@ -320,5 +310,5 @@ fn test_halo2_receiver_resource_logic_circuit() {
         de_cipher[6],
         pallas::Base::from(original_resource.is_ephemeral)
     );
-    assert_eq!(de_cipher[7], original_resource.rseed);
+    assert_eq!(de_cipher[7], *original_resource.rseed.expose_secret());
 }
@@ -0,0 +1,252 @@
+/// The logic of resources owned by a Schnorr public key.
+///
+/// The value of a resource is Poseidon(pk.x, pk.y), the owner consumes the
+/// resource by signing the resource merkle root with the secret key of pk.
+/// The signature is verified for outputs too, so that inputs and outputs
+/// share the circuit, but it may come from any key: the owner of a created
+/// resource doesn't have to authorize it.
+use crate::{
+    circuit::{
+        blake2s::publicize_default_dynamic_resource_logic_commitments,
+        gadgets::poseidon_hash::poseidon_hash_gadget,
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+        resource_logic_examples::signature_verification::SchnorrSignature,
+    },
+    error::TransactionError,
+    proof::Proof,
+    resource::RandomSeed,
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::ResourceExistenceWitness,
+    utils::poseidon_hash,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use halo2_gadgets::ecc::chip::EccChip;
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{floor_planner, Layouter},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::{
+    arithmetic::CurveAffine,
+    group::{Curve, Group},
+    pallas,
+};
+use rand::{rngs::OsRng, RngCore};
+
+lazy_static! {
+    pub static ref SIGNATURE_OWNERSHIP_VK: ResourceLogicVerifyingKey =
+        SignatureOwnershipResourceLogicCircuit::default()
+            .get_resource_logic_vk()
+            .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_SIGNATURE_OWNERSHIP_VK: pallas::Base =
+        SIGNATURE_OWNERSHIP_VK.get_compressed();
+}
+
+// SignatureOwnershipResourceLogicCircuit
+#[derive(Clone, Debug, Default)]
+pub struct SignatureOwnershipResourceLogicCircuit {
+    pub self_resource: ResourceExistenceWitness,
+    pub signature: SchnorrSignature,
+}
+
+impl SignatureOwnershipResourceLogicCircuit {
+    /// The value of the resources owned by pk.
+    pub fn derive_owner(pk: pallas::Point) -> pallas::Base {
+        let pk_coord = pk.to_affine().coordinates().unwrap();
+        poseidon_hash(*pk_coord.x(), *pk_coord.y())
+    }
+
+    /// Signs the resource merkle root with sk. Outputs may be signed with any
+    /// key, e.g. a random one.
+    pub fn sign<R: RngCore>(
+        mut rng: R,
+        self_resource: ResourceExistenceWitness,
+        sk: pallas::Scalar,
+    ) -> Self {
+        let signature = SchnorrSignature::sign(&mut rng, sk, vec![self_resource.get_root()]);
+        Self {
+            self_resource,
+            signature,
+        }
+    }
+
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(
+            ResourceLogicRepresentation::SignatureOwnership,
+            self.to_bytes(),
+        )
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+}
+
+impl ResourceLogicCircuit for SignatureOwnershipResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        let ecc_chip = EccChip::construct(config.ecc_config.clone());
+
+        // Verify the signature on the resource merkle root
+        let pk = self.signature.verify_in_circuit(
+            ecc_chip,
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "verify signature"),
+            self_resource.resource_merkle_root.clone(),
+        )?;
+
+        // The signer owns the resource if it's an input
+        let owner = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "derive owner"),
+            [pk.inner().x(), pk.inner().y()],
+        )?;
+        layouter.assign_region(
+            || "conditional equal: check owner",
+            |mut region| {
+                config.conditional_equal_config.assign_region(
+                    &self_resource.is_input,
+                    &self_resource.resource.value,
+                    &owner,
+                    0,
+                    &mut region,
+                )
+            },
+        )?;
+
+        // Publicize the dynamic resource_logic commitments with default value
+        publicize_default_dynamic_resource_logic_commitments(
+            &mut layouter,
+            config.advices[0],
+            config.instances,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.extend(default_resource_logic_cm);
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+
+    fn native_custom_constraints(&self) -> Option<Result<(), TransactionError>> {
+        let mut satisfied = self.signature.verify(self.self_resource.get_root());
+        if self.self_resource.is_input() {
+            satisfied &=
+                self.self_resource.get_resource().value == Self::derive_owner(self.signature.pk());
+        }
+        Some(if satisfied {
+            Ok(())
+        } else {
+            Err(TransactionError::UnsatisfiedResourceLogic)
+        })
+    }
+}
+
+resource_logic_circuit_impl!(SignatureOwnershipResourceLogicCircuit);
+resource_logic_verifying_info_impl!(SignatureOwnershipResourceLogicCircuit);
+
+impl BorshSerialize for SignatureOwnershipResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        self.signature.serialize(writer)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for SignatureOwnershipResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let signature = SchnorrSignature::deserialize_reader(reader)?;
+        Ok(Self {
+            self_resource,
+            signature,
+        })
+    }
+}
+
+#[test]
+fn test_halo2_signature_ownership_resource_logic_circuit() {
+    use crate::constant::RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+    use crate::resource::tests::random_resource;
+    use crate::resource_tree::ResourceMerkleTreeLeaves;
+    use halo2_proofs::dev::MockProver;
+
+    let mut rng = OsRng;
+    let sk = pallas::Scalar::random(&mut rng);
+    let mut resource = random_resource(&mut rng);
+    resource.value =
+        SignatureOwnershipResourceLogicCircuit::derive_owner(pallas::Point::generator() * sk);
+    let nf = resource.get_nf().unwrap().inner();
+    let resource_merkle_tree =
+        ResourceMerkleTreeLeaves::new(vec![nf, pallas::Base::random(&mut rng)]);
+    let self_resource =
+        ResourceExistenceWitness::new(resource, resource_merkle_tree.generate_path(nf).unwrap());
+
+    // The owner consumes the resource
+    let circuit = SignatureOwnershipResourceLogicCircuit::sign(&mut rng, self_resource.clone(), sk);
+
+    // Test serialization
+    let circuit = {
+        let circuit_bytes = circuit.to_bytes();
+        SignatureOwnershipResourceLogicCircuit::from_bytes(&circuit_bytes)
+    };
+
+    let public_inputs = circuit.get_public_inputs(&mut rng);
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    assert!(circuit.verify_transparently().is_ok());
+
+    // Nobody else consumes the resource
+    let invalid_circuit = SignatureOwnershipResourceLogicCircuit::sign(
+        &mut rng,
+        self_resource,
+        pallas::Scalar::random(&mut rng),
+    );
+    let public_inputs = invalid_circuit.get_public_inputs(&mut rng);
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &invalid_circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+    assert!(matches!(
+        invalid_circuit.verify_transparently(),
+        Err(TransactionError::UnsatisfiedResourceLogic)
+    ));
+}
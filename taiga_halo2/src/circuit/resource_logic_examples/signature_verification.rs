@@ -1,14 +1,17 @@
 use crate::{
     circuit::{
         blake2s::publicize_default_dynamic_resource_logic_commitments,
-        gadgets::{assign_free_advice, poseidon_hash::poseidon_hash_gadget},
+        gadgets::{
+            assign_free_advice, poseidon_hash::poseidon_hash_gadget,
+            schnorr_signature::schnorr_verify_gadget,
+        },
         resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
         resource_logic_circuit::{
             ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
             ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
         },
     },
-    constant::{TaigaFixedBasesFull, SETUP_PARAMS_MAP},
+    constant::TaigaFixedBases,
     error::TransactionError,
     proof::Proof,
     resource::RandomSeed,
@@ -18,10 +21,13 @@ use crate::{
     utils::{mod_r_p, poseidon_hash_n, read_base_field, read_point, read_scalar_field},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
-use halo2_gadgets::ecc::{chip::EccChip, FixedPoint, NonIdentityPoint, ScalarFixed, ScalarVar};
+use halo2_gadgets::{
+    ecc::{chip::EccChip, NonIdentityPoint},
+    poseidon::Pow5Config as PoseidonConfig,
+};
 use halo2_proofs::{
     arithmetic::Field,
-    circuit::{floor_planner, Layouter, Value},
+    circuit::{floor_planner, AssignedCell, Layouter, Value},
     plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
 };
 use lazy_static::lazy_static;
@@ -38,7 +44,9 @@ const MESSAGE_LEN: usize = 1;
 const POSEIDON_HASH_LEN: usize = MESSAGE_LEN + 4;
 lazy_static! {
     pub static ref TOKEN_AUTH_VK: ResourceLogicVerifyingKey =
-        SignatureVerificationResourceLogicCircuit::default().get_resource_logic_vk();
+        SignatureVerificationResourceLogicCircuit::default()
+            .get_resource_logic_vk()
+            .expect("keygen_vk should not fail");
     pub static ref COMPRESSED_TOKEN_AUTH_VK: pallas::Base = TOKEN_AUTH_VK.get_compressed();
 }
 
@@ -85,6 +93,61 @@ impl SchnorrSignature {
         let s = z + h * sk;
         Self { pk, r, s }
     }
+
+    pub fn pk(&self) -> pallas::Point {
+        self.pk
+    }
+
+    fn challenge(&self, message: pallas::Base) -> pallas::Scalar {
+        let r_coord = self.r.to_affine().coordinates().unwrap();
+        let pk_coord = self.pk.to_affine().coordinates().unwrap();
+        mod_r_p(poseidon_hash_n::<POSEIDON_HASH_LEN>([
+            *r_coord.x(),
+            *r_coord.y(),
+            *pk_coord.x(),
+            *pk_coord.y(),
+            message,
+        ]))
+    }
+
+    // Verify the signature natively: s*G = R + Hash(r||P||m)*P
+    pub fn verify(&self, message: pallas::Base) -> bool {
+        if bool::from(self.pk.is_identity()) || bool::from(self.r.is_identity()) {
+            return false;
+        }
+        pallas::Point::generator() * self.s == self.r + self.pk * self.challenge(message)
+    }
+
+    // Witness the signature, constrain it to be valid on the message and return the witnessed pk.
+    pub fn verify_in_circuit(
+        &self,
+        ecc_chip: EccChip<TaigaFixedBases>,
+        poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+        mut layouter: impl Layouter<pallas::Base>,
+        message: AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<NonIdentityPoint<pallas::Affine, EccChip<TaigaFixedBases>>, Error> {
+        let pk = NonIdentityPoint::new(
+            ecc_chip.clone(),
+            layouter.namespace(|| "witness pk"),
+            Value::known(self.pk.to_affine()),
+        )?;
+        let r = NonIdentityPoint::new(
+            ecc_chip.clone(),
+            layouter.namespace(|| "witness r"),
+            Value::known(self.r.to_affine()),
+        )?;
+        schnorr_verify_gadget(
+            ecc_chip,
+            poseidon_config,
+            layouter.namespace(|| "verify signature"),
+            &pk,
+            &r,
+            Value::known(self.s),
+            message,
+        )?;
+
+        Ok(pk)
+    }
 }
 
 // SignatureVerificationResourceLogicCircuit uses the schnorr signature.
@@ -156,10 +219,12 @@ impl ResourceLogicCircuit for SignatureVerificationResourceLogicCircuit {
         // Construct an ECC chip
         let ecc_chip = EccChip::construct(config.ecc_config);
 
-        let pk = NonIdentityPoint::new(
-            ecc_chip.clone(),
-            layouter.namespace(|| "witness pk"),
-            Value::known(self.signature.pk.to_affine()),
+        // Verify the signature on the resource merkle root
+        let pk = self.signature.verify_in_circuit(
+            ecc_chip,
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "verify signature"),
+            self_resource.resource_merkle_root,
         )?;
 
         let auth_resource_logic_vk = assign_free_advice(
@@ -175,7 +240,7 @@ impl ResourceLogicCircuit for SignatureVerificationResourceLogicCircuit {
 
         // Decode the value, and check the value encoding
         let encoded_value = poseidon_hash_gadget(
-            config.poseidon_config.clone(),
+            config.poseidon_config,
             layouter.namespace(|| "value encoding"),
             [
                 pk.inner().x(),
@@ -192,48 +257,6 @@ impl ResourceLogicCircuit for SignatureVerificationResourceLogicCircuit {
             },
         )?;
 
-        let r = NonIdentityPoint::new(
-            ecc_chip.clone(),
-            layouter.namespace(|| "witness r"),
-            Value::known(self.signature.r.to_affine()),
-        )?;
-        let s_scalar = ScalarFixed::new(
-            ecc_chip.clone(),
-            layouter.namespace(|| "witness s"),
-            Value::known(self.signature.s),
-        )?;
-
-        // Verify: s*G = R + Hash(r||P||m)*P
-        // s*G
-        let generator =
-            FixedPoint::from_inner(ecc_chip.clone(), TaigaFixedBasesFull::BaseGenerator);
-        let (s_g, _) = generator.mul(layouter.namespace(|| "s_scalar * generator"), &s_scalar)?;
-
-        // Hash(r||P||m)
-        let h_scalar = {
-            let h = poseidon_hash_gadget(
-                config.poseidon_config,
-                layouter.namespace(|| "Poseidon_hash(r, P, m)"),
-                [
-                    r.inner().x(),
-                    r.inner().y(),
-                    pk.inner().x(),
-                    pk.inner().y(),
-                    self_resource.resource_merkle_root,
-                ],
-            )?;
-
-            ScalarVar::from_base(ecc_chip, layouter.namespace(|| "ScalarVar from_base"), &h)?
-        };
-
-        // Hash(r||P||m)*P
-        let (h_p, _) = pk.mul(layouter.namespace(|| "hP"), h_scalar)?;
-
-        // R + Hash(r||P||m)*P
-        let rhs = r.add(layouter.namespace(|| "R + Hash(r||P||m)*P"), &h_p)?;
-
-        s_g.constrain_equal(layouter.namespace(|| "s*G = R + Hash(r||P||m)*P"), &rhs)?;
-
         // Publicize the dynamic resource_logic commitments with default value
         publicize_default_dynamic_resource_logic_commitments(
             &mut layouter,
@@ -259,7 +282,7 @@ impl ResourceLogicCircuit for SignatureVerificationResourceLogicCircuit {
     }
 
     fn get_self_resource(&self) -> ResourceExistenceWitness {
-        self.self_resource
+        self.self_resource.clone()
     }
 }
 
@@ -347,6 +370,9 @@ fn test_halo2_sig_verification_resource_logic_circuit() {
         SignatureVerificationResourceLogicCircuit::from_bytes(&circuit_bytes)
     };
 
+    assert!(circuit.signature.verify(circuit.self_resource.get_root()));
+    assert!(!circuit.signature.verify(pallas::Base::random(&mut rng)));
+
     let public_inputs = circuit.get_public_inputs(&mut rng);
 
     let prover = MockProver::<pallas::Base>::run(
@@ -0,0 +1,440 @@
+/// The fee an intent author offers to the solver of the intent. The author
+/// locks the max fee, a quantity of a token, by spending it along with the
+/// creation of the ephemeral solver fee resource. The solver consuming the fee
+/// resource takes its cut and must refund the rest of the max fee to the
+/// author in the same ptx, so the cut of the solver never exceeds the max fee:
+/// refund.quantity + solver_cut == max_fee. A solver declaring a negative cut
+/// only refunds more than the max fee to the author.
+///
+use crate::{
+    circuit::{
+        blake2s::publicize_default_dynamic_resource_logic_commitments,
+        gadgets::{
+            add::{AddChip, AddInstructions},
+            assign_free_advice, assign_free_constant,
+            poseidon_hash::poseidon_hash_gadget,
+        },
+        integrity::load_resource,
+        merkle_circuit::MerklePoseidonChip,
+        resource_commitment::ResourceCommitChip,
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+        resource_logic_examples::token::{
+            Token, TokenAuthorization, COMPRESSED_TOKEN_VK, TOKEN_VK,
+        },
+    },
+    error::TransactionError,
+    proof::Proof,
+    quantity::Quantity,
+    resource::{RandomSeed, Resource},
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::ResourceExistenceWitness,
+    utils::{poseidon_hash_n, read_base_field},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use halo2_proofs::{
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::{group::ff::PrimeField, pallas};
+use rand::{rngs::OsRng, RngCore};
+
+lazy_static! {
+    pub static ref SOLVER_FEE_VK: ResourceLogicVerifyingKey =
+        SolverFeeResourceLogicCircuit::default()
+            .get_resource_logic_vk()
+            .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_SOLVER_FEE_VK: pallas::Base = SOLVER_FEE_VK.get_compressed();
+}
+
+/// The terms of a solver fee, committed in the label of the fee resource.
+#[derive(Clone, Debug, Default)]
+pub struct SolverFee {
+    // The fee token and the max fee
+    pub max_fee: Token,
+    // The owner of the refund
+    pub author_auth: TokenAuthorization,
+    pub author_npk: pallas::Base,
+}
+
+impl SolverFee {
+    pub fn encode_label(&self) -> pallas::Base {
+        poseidon_hash_n([
+            self.max_fee.encode_name(),
+            self.max_fee.encode_quantity(),
+            TOKEN_VK.get_compressed(),
+            self.author_npk,
+            self.author_auth.to_value(),
+        ])
+    }
+
+    /// The ephemeral fee resource, created by the author or consumed by the
+    /// solver. Nobody spends the created one, so both have random keys.
+    pub fn create_fee_resource<R: RngCore>(&self, mut rng: R, is_input: bool) -> Resource {
+        use crate::nullifier::Nullifier;
+        use halo2_proofs::arithmetic::Field;
+
+        if is_input {
+            Resource::new_input_resource(
+                *COMPRESSED_SOLVER_FEE_VK,
+                self.encode_label(),
+                pallas::Base::zero(),
+                1u64.into(),
+                pallas::Base::random(&mut rng),
+                Nullifier::random(&mut rng),
+                true,
+                pallas::Base::random(&mut rng),
+            )
+        } else {
+            Resource::new_output_resource(
+                *COMPRESSED_SOLVER_FEE_VK,
+                self.encode_label(),
+                pallas::Base::zero(),
+                1u64.into(),
+                pallas::Base::random(&mut rng),
+                true,
+                pallas::Base::random(&mut rng),
+            )
+        }
+    }
+}
+
+impl BorshSerialize for SolverFee {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.max_fee.serialize(writer)?;
+        self.author_auth.serialize(writer)?;
+        writer.write_all(&self.author_npk.to_repr())?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for SolverFee {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let max_fee = Token::deserialize_reader(reader)?;
+        let author_auth = TokenAuthorization::deserialize_reader(reader)?;
+        let author_npk = read_base_field(reader)?;
+        Ok(Self {
+            max_fee,
+            author_auth,
+            author_npk,
+        })
+    }
+}
+
+// SolverFeeResourceLogicCircuit
+#[derive(Clone, Debug, Default)]
+pub struct SolverFeeResourceLogicCircuit {
+    // self_resource is the solver fee resource
+    pub self_resource: ResourceExistenceWitness,
+    // constraints will be enabled only when consuming the fee resource, otherwise it's a dummy one
+    pub refund_resource: ResourceExistenceWitness,
+    pub fee: SolverFee,
+    // The cut the solver takes, only meaningful when consuming the fee resource
+    pub solver_cut: Quantity,
+}
+
+impl SolverFeeResourceLogicCircuit {
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(ResourceLogicRepresentation::SolverFee, self.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+}
+
+impl ResourceLogicCircuit for SolverFeeResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        let fee_token = assign_free_advice(
+            layouter.namespace(|| "witness fee token"),
+            config.advices[0],
+            Value::known(self.fee.max_fee.encode_name()),
+        )?;
+        let max_fee = assign_free_advice(
+            layouter.namespace(|| "witness max fee"),
+            config.advices[0],
+            Value::known(self.fee.max_fee.encode_quantity()),
+        )?;
+        let token_resource_logic_vk = assign_free_advice(
+            layouter.namespace(|| "witness token resource_logic vk"),
+            config.advices[0],
+            Value::known(TOKEN_VK.get_compressed()),
+        )?;
+        let author_npk = assign_free_advice(
+            layouter.namespace(|| "witness author npk"),
+            config.advices[0],
+            Value::known(self.fee.author_npk),
+        )?;
+        let author_value = assign_free_advice(
+            layouter.namespace(|| "witness author value"),
+            config.advices[0],
+            Value::known(self.fee.author_auth.to_value()),
+        )?;
+
+        // check the label of the fee resource
+        let encoded_label = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "encode label"),
+            [
+                fee_token.clone(),
+                max_fee.clone(),
+                token_resource_logic_vk.clone(),
+                author_npk.clone(),
+                author_value.clone(),
+            ],
+        )?;
+        layouter.assign_region(
+            || "check label",
+            |mut region| {
+                region.constrain_equal(encoded_label.cell(), self_resource.resource.label.cell())
+            },
+        )?;
+
+        // check the is_ephemeral flag
+        let constant_one = assign_free_constant(
+            layouter.namespace(|| "one"),
+            config.advices[0],
+            pallas::Base::one(),
+        )?;
+        layouter.assign_region(
+            || "check is_ephemeral",
+            |mut region| {
+                region.constrain_equal(
+                    self_resource.resource.is_ephemeral.cell(),
+                    constant_one.cell(),
+                )
+            },
+        )?;
+
+        // load the refund resource
+        let refund_resource = load_resource(
+            layouter.namespace(|| "load the refund resource"),
+            config.advices,
+            ResourceCommitChip::construct(config.resource_commit_config.clone()),
+            config.conditional_select_config,
+            MerklePoseidonChip::construct(config.merkle_config),
+            &self.refund_resource,
+        )?;
+
+        // The refund is created in the same ptx when the fee resource is consumed
+        let constant_zero = assign_free_constant(
+            layouter.namespace(|| "zero"),
+            config.advices[0],
+            pallas::Base::zero(),
+        )?;
+        let solver_cut = assign_free_advice(
+            layouter.namespace(|| "witness solver cut"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.solver_cut.inner())),
+        )?;
+        let add_chip = AddChip::construct(config.add_config.clone(), ());
+        let refund_and_cut = AddInstructions::add(
+            &add_chip,
+            layouter.namespace(|| "refund + solver cut"),
+            &refund_resource.resource.quantity,
+            &solver_cut,
+        )?;
+        for (name, lhs, rhs) in [
+            (
+                "refund root",
+                &self_resource.resource_merkle_root,
+                &refund_resource.resource_merkle_root,
+            ),
+            (
+                "refund is output",
+                &constant_zero,
+                &refund_resource.is_input,
+            ),
+            (
+                "refund logic",
+                &token_resource_logic_vk,
+                &refund_resource.resource.logic,
+            ),
+            ("refund label", &fee_token, &refund_resource.resource.label),
+            ("refund npk", &author_npk, &refund_resource.resource.npk),
+            (
+                "refund value",
+                &author_value,
+                &refund_resource.resource.value,
+            ),
+            ("refund + solver cut", &max_fee, &refund_and_cut),
+        ] {
+            layouter.assign_region(
+                || format!("conditional equal: check {}", name),
+                |mut region| {
+                    config.conditional_equal_config.assign_region(
+                        &self_resource.is_input,
+                        lhs,
+                        rhs,
+                        0,
+                        &mut region,
+                    )
+                },
+            )?;
+        }
+
+        // Publicize the dynamic resource_logic commitments with default value
+        publicize_default_dynamic_resource_logic_commitments(
+            &mut layouter,
+            config.advices[0],
+            config.instances,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.extend(default_resource_logic_cm);
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+
+    fn native_custom_constraints(&self) -> Option<Result<(), TransactionError>> {
+        let resource = self.self_resource.get_resource();
+        let mut satisfied =
+            resource.get_label() == self.fee.encode_label() && resource.is_ephemeral;
+        if self.self_resource.is_input() {
+            let refund = self.refund_resource.get_resource();
+            satisfied &= !self.refund_resource.is_input()
+                && self.refund_resource.get_root() == self.self_resource.get_root()
+                && refund.get_logic() == *COMPRESSED_TOKEN_VK
+                && refund.get_label() == self.fee.max_fee.encode_name()
+                && refund.get_npk() == self.fee.author_npk
+                && refund.value == self.fee.author_auth.to_value()
+                && pallas::Base::from(refund.quantity) + pallas::Base::from(self.solver_cut)
+                    == self.fee.max_fee.encode_quantity();
+        }
+        Some(if satisfied {
+            Ok(())
+        } else {
+            Err(TransactionError::UnsatisfiedResourceLogic)
+        })
+    }
+}
+
+resource_logic_circuit_impl!(SolverFeeResourceLogicCircuit);
+resource_logic_verifying_info_impl!(SolverFeeResourceLogicCircuit);
+
+impl BorshSerialize for SolverFeeResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        self.refund_resource.serialize(writer)?;
+        self.fee.serialize(writer)?;
+        self.solver_cut.serialize(writer)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for SolverFeeResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let refund_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let fee = SolverFee::deserialize_reader(reader)?;
+        let solver_cut = Quantity::deserialize_reader(reader)?;
+        Ok(Self {
+            self_resource,
+            refund_resource,
+            fee,
+            solver_cut,
+        })
+    }
+}
+
+#[test]
+fn test_halo2_solver_fee_resource_logic_circuit() {
+    use crate::constant::RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+    use crate::resource_tree::ResourceMerkleTreeLeaves;
+    use halo2_proofs::arithmetic::Field;
+    use halo2_proofs::dev::MockProver;
+
+    let mut rng = OsRng;
+    let author_auth = TokenAuthorization::random(&mut rng);
+    let fee = SolverFee {
+        max_fee: Token::new("eth".to_string(), 5u64),
+        author_auth,
+        author_npk: pallas::Base::random(&mut rng),
+    };
+    let fee_resource = fee.create_fee_resource(&mut rng, true);
+    // The solver takes 2 and refunds 3
+    let refund_resource = Token::new("eth".to_string(), 3u64)
+        .create_random_output_token_resource(&mut rng, fee.author_npk, &author_auth)
+        .resource()
+        .clone();
+
+    let fee_nf = fee_resource.get_nf().unwrap().inner();
+    let refund_cm = refund_resource.commitment().inner();
+    let resource_merkle_tree = ResourceMerkleTreeLeaves::new(vec![fee_nf, refund_cm]);
+    let circuit = SolverFeeResourceLogicCircuit {
+        self_resource: ResourceExistenceWitness::new(
+            fee_resource,
+            resource_merkle_tree.generate_path(fee_nf).unwrap(),
+        ),
+        refund_resource: ResourceExistenceWitness::new(
+            refund_resource,
+            resource_merkle_tree.generate_path(refund_cm).unwrap(),
+        ),
+        fee,
+        solver_cut: 2u64.into(),
+    };
+
+    // Test serialization
+    let circuit = {
+        let circuit_bytes = circuit.to_bytes();
+        SolverFeeResourceLogicCircuit::from_bytes(&circuit_bytes)
+    };
+
+    let public_inputs = circuit.get_public_inputs(&mut rng);
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    assert!(circuit.verify_transparently().is_ok());
+
+    // The solver can't take more than the max fee minus the refund
+    let mut invalid_circuit = circuit.clone();
+    invalid_circuit.solver_cut = 3u64.into();
+    let public_inputs = invalid_circuit.get_public_inputs(&mut rng);
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &invalid_circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+    assert!(matches!(
+        invalid_circuit.verify_transparently(),
+        Err(TransactionError::UnsatisfiedResourceLogic)
+    ));
+}
@@ -0,0 +1,454 @@
+/// The logic of sweepable resources, the resources that anyone may consume
+/// once they are expired or dust, so that deployments keep the state bounded.
+///
+/// A sweepable resource encodes:
+/// * label: the token name, the sweepable resources of a token are fungible
+/// * value: Poseidon(owner, expiry), the owner being Poseidon(owner_secret)
+/// * npk: the npk of the public `SWEEP_NK`, anyone can derive the nullifier
+///
+/// The owner consumes the resource by proving the knowledge of the owner
+/// secret. Anyone else consumes it by sweeping: the logic then publishes the
+/// expiry and the quantity of the resource, and the executor checks them
+/// against its current epoch and dust threshold, see `SweepPolicy`. The
+/// logic publishes zeros for the owner.
+///
+/// The nullifier key is public, so the spending of a sweepable resource is
+/// linkable to its commitment. Sweepers learn the openings of the resources
+/// from their owners, e.g. a wallet publishes the resources it lets expire.
+use crate::{
+    circuit::{
+        blake2s::publicize_default_dynamic_resource_logic_commitments,
+        gadgets::{
+            assign_free_advice, assign_free_constant,
+            mul::{MulChip, MulInstructions},
+            poseidon_hash::poseidon_hash_gadget,
+            sub::{SubChip, SubInstructions},
+        },
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+        resource_logic_examples::token::Token,
+    },
+    constant::{
+        RESOURCE_LOGIC_CIRCUIT_SWEEP_EXPIRY_IDX, RESOURCE_LOGIC_CIRCUIT_SWEEP_QUANTITY_IDX,
+        SWEEP_NK,
+    },
+    error::TransactionError,
+    nullifier::{Nullifier, NullifierKeyContainer},
+    proof::Proof,
+    resource::{RandomSeed, Resource},
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::ResourceExistenceWitness,
+    utils::{poseidon_hash, poseidon_hash_n, read_base_field},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::{group::ff::PrimeField, pallas};
+use rand::{rngs::OsRng, RngCore};
+
+lazy_static! {
+    pub static ref SWEEP_VK: ResourceLogicVerifyingKey = SweepResourceLogicCircuit::default()
+        .get_resource_logic_vk()
+        .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_SWEEP_VK: pallas::Base = SWEEP_VK.get_compressed();
+    pub static ref SWEEP_NPK: pallas::Base = NullifierKeyContainer::derive_npk(*SWEEP_NK);
+}
+
+/// The terms of a sweepable resource.
+#[derive(Clone, Debug, Default)]
+pub struct Sweepable {
+    pub token: Token,
+    // Poseidon(owner_secret)
+    pub owner: pallas::Base,
+    // The epoch from which anyone may sweep the resource
+    pub expiry: u64,
+}
+
+impl Sweepable {
+    pub fn derive_owner(owner_secret: pallas::Base) -> pallas::Base {
+        poseidon_hash_n([owner_secret])
+    }
+
+    pub fn encode_label(&self) -> pallas::Base {
+        self.token.encode_name()
+    }
+
+    pub fn encode_value(&self) -> pallas::Base {
+        poseidon_hash(self.owner, pallas::Base::from(self.expiry))
+    }
+
+    /// The sweepable resource, spent with a random nonce if `is_input`.
+    pub fn create_resource<R: RngCore>(&self, mut rng: R, is_input: bool) -> Resource {
+        if is_input {
+            Resource::new_input_resource(
+                *COMPRESSED_SWEEP_VK,
+                self.encode_label(),
+                self.encode_value(),
+                self.token.quantity(),
+                *SWEEP_NK,
+                Nullifier::random(&mut rng),
+                false,
+                pallas::Base::random(&mut rng),
+            )
+        } else {
+            Resource::new_output_resource(
+                *COMPRESSED_SWEEP_VK,
+                self.encode_label(),
+                self.encode_value(),
+                self.token.quantity(),
+                *SWEEP_NPK,
+                false,
+                pallas::Base::random(&mut rng),
+            )
+        }
+    }
+}
+
+impl BorshSerialize for Sweepable {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.token.serialize(writer)?;
+        writer.write_all(&self.owner.to_repr())?;
+        writer.write_u64::<LittleEndian>(self.expiry)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for Sweepable {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let token = Token::deserialize_reader(reader)?;
+        let owner = read_base_field(reader)?;
+        let expiry = reader.read_u64::<LittleEndian>()?;
+        Ok(Self {
+            token,
+            owner,
+            expiry,
+        })
+    }
+}
+
+// SweepResourceLogicCircuit
+#[derive(Clone, Debug, Default)]
+pub struct SweepResourceLogicCircuit {
+    pub self_resource: ResourceExistenceWitness,
+    pub sweepable: Sweepable,
+    // Only needed when the owner consumes the resource
+    pub owner_secret: pallas::Base,
+    // Whether the resource is consumed by a sweeper, only meaningful for inputs
+    pub is_sweep: bool,
+}
+
+impl SweepResourceLogicCircuit {
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(ResourceLogicRepresentation::Sweep, self.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+
+    // The sweep flag takes effect only for inputs
+    fn is_swept(&self) -> bool {
+        self.self_resource.is_input() && self.is_sweep
+    }
+}
+
+impl ResourceLogicCircuit for SweepResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        let owner = assign_free_advice(
+            layouter.namespace(|| "witness owner"),
+            config.advices[0],
+            Value::known(self.sweepable.owner),
+        )?;
+        let expiry = assign_free_advice(
+            layouter.namespace(|| "witness expiry"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.sweepable.expiry)),
+        )?;
+        let sweep_npk = assign_free_constant(
+            layouter.namespace(|| "sweep npk"),
+            config.advices[0],
+            *SWEEP_NPK,
+        )?;
+
+        // check the value and the npk of the sweepable resource
+        let encoded_value = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "encode value"),
+            [owner.clone(), expiry.clone()],
+        )?;
+        layouter.assign_region(
+            || "check value and npk",
+            |mut region| {
+                region
+                    .constrain_equal(encoded_value.cell(), self_resource.resource.value.cell())?;
+                region.constrain_equal(sweep_npk.cell(), self_resource.resource.npk.cell())
+            },
+        )?;
+
+        // is_sweep is boolean
+        let mul_chip = MulChip::construct(config.mul_config.clone());
+        let sub_chip = SubChip::construct(config.sub_config.clone(), ());
+        let is_sweep = assign_free_advice(
+            layouter.namespace(|| "witness is_sweep"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.is_sweep)),
+        )?;
+        let is_sweep_square = MulInstructions::mul(
+            &mul_chip,
+            layouter.namespace(|| "is_sweep * is_sweep"),
+            &is_sweep,
+            &is_sweep,
+        )?;
+        layouter.assign_region(
+            || "check is_sweep is boolean",
+            |mut region| region.constrain_equal(is_sweep_square.cell(), is_sweep.cell()),
+        )?;
+
+        // The resource is swept or consumed by the owner if it's an input
+        let is_swept = MulInstructions::mul(
+            &mul_chip,
+            layouter.namespace(|| "is_input * is_sweep"),
+            &self_resource.is_input,
+            &is_sweep,
+        )?;
+        let is_owned = SubInstructions::sub(
+            &sub_chip,
+            layouter.namespace(|| "is_input - is_swept"),
+            &self_resource.is_input,
+            &is_swept,
+        )?;
+
+        // The owner proves the knowledge of the owner secret
+        let owner_secret = assign_free_advice(
+            layouter.namespace(|| "witness owner secret"),
+            config.advices[0],
+            Value::known(self.owner_secret),
+        )?;
+        let derived_owner = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "derive owner"),
+            [owner_secret],
+        )?;
+        layouter.assign_region(
+            || "conditional equal: check owner",
+            |mut region| {
+                config.conditional_equal_config.assign_region(
+                    &is_owned,
+                    &owner,
+                    &derived_owner,
+                    0,
+                    &mut region,
+                )
+            },
+        )?;
+
+        // Publicize the expiry and the quantity of the swept resource
+        let published_expiry = MulInstructions::mul(
+            &mul_chip,
+            layouter.namespace(|| "is_swept * expiry"),
+            &is_swept,
+            &expiry,
+        )?;
+        let published_quantity = MulInstructions::mul(
+            &mul_chip,
+            layouter.namespace(|| "is_swept * quantity"),
+            &is_swept,
+            &self_resource.resource.quantity,
+        )?;
+        layouter.constrain_instance(
+            published_expiry.cell(),
+            config.instances,
+            RESOURCE_LOGIC_CIRCUIT_SWEEP_EXPIRY_IDX,
+        )?;
+        layouter.constrain_instance(
+            published_quantity.cell(),
+            config.instances,
+            RESOURCE_LOGIC_CIRCUIT_SWEEP_QUANTITY_IDX,
+        )?;
+
+        // Publicize the dynamic resource_logic commitments with default value
+        publicize_default_dynamic_resource_logic_commitments(
+            &mut layouter,
+            config.advices[0],
+            config.instances,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.extend(default_resource_logic_cm);
+        if self.is_swept() {
+            public_inputs.push(pallas::Base::from(self.sweepable.expiry));
+            public_inputs.push(pallas::Base::from(
+                self.self_resource.get_resource().quantity,
+            ));
+        } else {
+            public_inputs.extend([pallas::Base::zero(); 2]);
+        }
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+
+    fn native_custom_constraints(&self) -> Option<Result<(), TransactionError>> {
+        let resource = self.self_resource.get_resource();
+        let mut satisfied =
+            resource.value == self.sweepable.encode_value() && resource.get_npk() == *SWEEP_NPK;
+        if self.self_resource.is_input() && !self.is_sweep {
+            satisfied &= Sweepable::derive_owner(self.owner_secret) == self.sweepable.owner;
+        }
+        Some(if satisfied {
+            Ok(())
+        } else {
+            Err(TransactionError::UnsatisfiedResourceLogic)
+        })
+    }
+}
+
+resource_logic_circuit_impl!(SweepResourceLogicCircuit);
+resource_logic_verifying_info_impl!(SweepResourceLogicCircuit);
+
+impl BorshSerialize for SweepResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        self.sweepable.serialize(writer)?;
+        writer.write_all(&self.owner_secret.to_repr())?;
+        writer.write_u8(self.is_sweep as u8)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for SweepResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let sweepable = Sweepable::deserialize_reader(reader)?;
+        let owner_secret = read_base_field(reader)?;
+        let is_sweep = reader.read_u8()? != 0;
+        Ok(Self {
+            self_resource,
+            sweepable,
+            owner_secret,
+            is_sweep,
+        })
+    }
+}
+
+#[test]
+fn test_halo2_sweep_resource_logic_circuit() {
+    use crate::constant::RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+    use crate::resource_tree::ResourceMerkleTreeLeaves;
+    use halo2_proofs::dev::MockProver;
+
+    let mut rng = OsRng;
+    let owner_secret = pallas::Base::random(&mut rng);
+    let sweepable = Sweepable {
+        token: Token::new("eth".to_string(), 5u64),
+        owner: Sweepable::derive_owner(owner_secret),
+        expiry: 100,
+    };
+    let resource = sweepable.create_resource(&mut rng, true);
+    let nf = resource.get_nf().unwrap().inner();
+    let resource_merkle_tree =
+        ResourceMerkleTreeLeaves::new(vec![nf, pallas::Base::random(&mut rng)]);
+    let self_resource =
+        ResourceExistenceWitness::new(resource, resource_merkle_tree.generate_path(nf).unwrap());
+
+    // A sweeper consumes the resource without the owner secret
+    let circuit = SweepResourceLogicCircuit {
+        self_resource,
+        sweepable: sweepable.clone(),
+        owner_secret: pallas::Base::zero(),
+        is_sweep: true,
+    };
+
+    // Test serialization
+    let circuit = {
+        let circuit_bytes = circuit.to_bytes();
+        SweepResourceLogicCircuit::from_bytes(&circuit_bytes)
+    };
+
+    let public_inputs = circuit.get_public_inputs(&mut rng);
+    assert_eq!(
+        public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_SWEEP_EXPIRY_IDX),
+        pallas::Base::from(100)
+    );
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    assert!(circuit.verify_transparently().is_ok());
+
+    // The owner consumes the resource with the owner secret, nothing is published
+    let owned_circuit = SweepResourceLogicCircuit {
+        owner_secret,
+        is_sweep: false,
+        ..circuit.clone()
+    };
+    let public_inputs = owned_circuit.get_public_inputs(&mut rng);
+    assert_eq!(
+        public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_SWEEP_EXPIRY_IDX),
+        pallas::Base::zero()
+    );
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &owned_circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // Nobody else consumes the resource without sweeping it
+    let invalid_circuit = SweepResourceLogicCircuit {
+        owner_secret: pallas::Base::random(&mut rng),
+        is_sweep: false,
+        ..circuit
+    };
+    let public_inputs = invalid_circuit.get_public_inputs(&mut rng);
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &invalid_circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+    assert!(matches!(
+        invalid_circuit.verify_transparently(),
+        Err(TransactionError::UnsatisfiedResourceLogic)
+    ));
+}
@@ -0,0 +1,280 @@
+/// The logic of time-locked resources, which can only be consumed within a
+/// window of epochs, e.g. vesting or expiring offers.
+///
+/// The value of a resource is its `data` encoded with the window, see
+/// `Timelock::encode_value`. The resource can be consumed from the epoch
+/// `not_before` to the epoch `expiry`, both included. The logic publishes the
+/// epoch it is proven at and proves that the epoch is in the window. The
+/// transaction is rejected if the epoch is not the one of its compliances,
+/// see `check_epoch`, which the executor checks against its current epoch, so
+/// the prover can't pick the epoch. Created resources are not locked.
+pub use crate::resource::Timelock;
+use crate::{
+    circuit::{
+        blake2s::publicize_default_dynamic_resource_logic_commitments,
+        gadgets::{assign_free_advice, assign_free_constant, poseidon_hash::poseidon_hash_gadget},
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+    },
+    constant::RESOURCE_LOGIC_CIRCUIT_TIMELOCK_EPOCH_IDX,
+    error::TransactionError,
+    proof::Proof,
+    resource::RandomSeed,
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::ResourceExistenceWitness,
+    utils::read_base_field,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::{group::ff::PrimeField, pallas};
+use rand::{rngs::OsRng, RngCore};
+
+lazy_static! {
+    pub static ref TIMELOCK_VK: ResourceLogicVerifyingKey = TimelockResourceLogicCircuit::default()
+        .get_resource_logic_vk()
+        .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_TIMELOCK_VK: pallas::Base = TIMELOCK_VK.get_compressed();
+}
+
+// TimelockResourceLogicCircuit
+#[derive(Clone, Debug, Default)]
+pub struct TimelockResourceLogicCircuit {
+    pub self_resource: ResourceExistenceWitness,
+    pub timelock: Timelock,
+    pub data: pallas::Base,
+    // The current epoch of the executor
+    pub epoch: u64,
+}
+
+impl TimelockResourceLogicCircuit {
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(ResourceLogicRepresentation::Timelock, self.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+}
+
+impl ResourceLogicCircuit for TimelockResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        let data = assign_free_advice(
+            layouter.namespace(|| "witness data"),
+            config.advices[0],
+            Value::known(self.data),
+        )?;
+        let not_before = assign_free_advice(
+            layouter.namespace(|| "witness not_before"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.timelock.not_before)),
+        )?;
+        let expiry = assign_free_advice(
+            layouter.namespace(|| "witness expiry"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.timelock.expiry)),
+        )?;
+        let epoch = assign_free_advice(
+            layouter.namespace(|| "witness epoch"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.epoch)),
+        )?;
+
+        // check the value of the resource
+        let encoded_value = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "encode value"),
+            [data, not_before.clone(), expiry.clone()],
+        )?;
+        layouter.assign_region(
+            || "check value",
+            |mut region| {
+                region.constrain_equal(encoded_value.cell(), self_resource.resource.value.cell())
+            },
+        )?;
+
+        // An input is consumed within the window
+        let is_open = config.comparison_config.in_range(
+            layouter.namespace(|| "not_before <= epoch <= expiry"),
+            &epoch,
+            &not_before,
+            &expiry,
+        )?;
+        let one = assign_free_constant(
+            layouter.namespace(|| "one"),
+            config.advices[0],
+            pallas::Base::one(),
+        )?;
+        layouter.assign_region(
+            || "conditional equal: check the window",
+            |mut region| {
+                config.conditional_equal_config.assign_region(
+                    &self_resource.is_input,
+                    &is_open,
+                    &one,
+                    0,
+                    &mut region,
+                )
+            },
+        )?;
+
+        // Publicize the epoch
+        layouter.constrain_instance(
+            epoch.cell(),
+            config.instances,
+            RESOURCE_LOGIC_CIRCUIT_TIMELOCK_EPOCH_IDX,
+        )?;
+
+        // Publicize the dynamic resource_logic commitments with default value
+        publicize_default_dynamic_resource_logic_commitments(
+            &mut layouter,
+            config.advices[0],
+            config.instances,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.push(pallas::Base::from(self.epoch));
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+
+    fn native_custom_constraints(&self) -> Option<Result<(), TransactionError>> {
+        let satisfied = self.self_resource.get_resource().value
+            == self.timelock.encode_value(self.data)
+            && (!self.self_resource.is_input() || self.timelock.is_open(self.epoch));
+        Some(if satisfied {
+            Ok(())
+        } else {
+            Err(TransactionError::UnsatisfiedResourceLogic)
+        })
+    }
+}
+
+resource_logic_circuit_impl!(TimelockResourceLogicCircuit);
+resource_logic_verifying_info_impl!(TimelockResourceLogicCircuit);
+
+impl BorshSerialize for TimelockResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        self.timelock.serialize(writer)?;
+        writer.write_all(&self.data.to_repr())?;
+        writer.write_u64::<LittleEndian>(self.epoch)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for TimelockResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let timelock = Timelock::deserialize_reader(reader)?;
+        let data = read_base_field(reader)?;
+        let epoch = reader.read_u64::<LittleEndian>()?;
+        Ok(Self {
+            self_resource,
+            timelock,
+            data,
+            epoch,
+        })
+    }
+}
+
+#[test]
+fn test_halo2_timelock_resource_logic_circuit() {
+    use crate::constant::RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+    use crate::resource::tests::random_resource;
+    use crate::resource_tree::ResourceMerkleTreeLeaves;
+    use halo2_proofs::dev::MockProver;
+
+    let mut rng = OsRng;
+    let timelock = Timelock::new(Some(10), Some(20));
+    let data = pallas::Base::random(&mut rng);
+    let mut resource = random_resource(&mut rng);
+    resource.value = timelock.encode_value(data);
+    let nf = resource.get_nf().unwrap().inner();
+    let resource_merkle_tree =
+        ResourceMerkleTreeLeaves::new(vec![nf, pallas::Base::random(&mut rng)]);
+    let self_resource =
+        ResourceExistenceWitness::new(resource, resource_merkle_tree.generate_path(nf).unwrap());
+    let circuit = TimelockResourceLogicCircuit {
+        self_resource,
+        timelock,
+        data,
+        epoch: 15,
+    };
+
+    // Test serialization
+    let circuit = {
+        let circuit_bytes = circuit.to_bytes();
+        TimelockResourceLogicCircuit::from_bytes(&circuit_bytes)
+    };
+
+    let public_inputs = circuit.get_public_inputs(&mut rng);
+    assert_eq!(
+        public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_TIMELOCK_EPOCH_IDX),
+        pallas::Base::from(15)
+    );
+    let prover = MockProver::<pallas::Base>::run(
+        RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![public_inputs.to_vec()],
+    )
+    .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    assert!(circuit.verify_transparently().is_ok());
+
+    // The resource can't be consumed out of the window
+    for epoch in [9, 21] {
+        let locked_circuit = TimelockResourceLogicCircuit {
+            epoch,
+            ..circuit.clone()
+        };
+        let public_inputs = locked_circuit.get_public_inputs(&mut rng);
+        let prover = MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            &locked_circuit,
+            vec![public_inputs.to_vec()],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+        assert!(matches!(
+            locked_circuit.verify_transparently(),
+            Err(TransactionError::UnsatisfiedResourceLogic)
+        ));
+    }
+}
@@ -2,7 +2,9 @@ use crate::{
     circuit::{
         blake2s::{resource_logic_commitment_gadget, Blake2sChip},
         gadgets::{assign_free_advice, assign_free_constant, poseidon_hash::poseidon_hash_gadget},
-        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_bytecode::{
+            ApplicationByteCode, ResourceLogicByteCode, ResourceLogicRepresentation,
+        },
         resource_logic_circuit::{
             ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
             ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
@@ -19,13 +21,13 @@ use crate::{
         RESOURCE_LOGIC_CIRCUIT_FIRST_DYNAMIC_RESOURCE_LOGIC_CM_1,
         RESOURCE_LOGIC_CIRCUIT_FIRST_DYNAMIC_RESOURCE_LOGIC_CM_2,
         RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_1,
-        RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_2, SETUP_PARAMS_MAP,
-        TAIGA_RESOURCE_TREE_DEPTH,
+        RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_2, TAIGA_RESOURCE_TREE_DEPTH,
     },
     error::TransactionError,
     merkle_tree::LR,
     nullifier::Nullifier,
     proof::Proof,
+    quantity::Quantity,
     resource::{RandomSeed, Resource, ResourceLogics},
     resource_logic_commitment::ResourceLogicCommitment,
     resource_logic_vk::ResourceLogicVerifyingKey,
@@ -46,8 +48,9 @@ use pasta_curves::{group::ff::PrimeField, pallas};
 use rand::{rngs::OsRng, Rng, RngCore};
 
 lazy_static! {
-    pub static ref TOKEN_VK: ResourceLogicVerifyingKey =
-        TokenResourceLogicCircuit::default().get_resource_logic_vk();
+    pub static ref TOKEN_VK: ResourceLogicVerifyingKey = TokenResourceLogicCircuit::default()
+        .get_resource_logic_vk()
+        .expect("keygen_vk should not fail");
     pub static ref COMPRESSED_TOKEN_VK: pallas::Base = TOKEN_VK.get_compressed();
 }
 
@@ -70,25 +73,42 @@ impl TokenName {
 #[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize)]
 pub struct Token {
     name: TokenName,
-    quantity: u64,
+    quantity: Quantity,
+    /// Number of decimals used when displaying the quantity. It's metadata only
+    /// and not encoded into the resource.
+    decimals: Option<u8>,
 }
 
 impl Token {
     pub fn new(name: String, quantity: u64) -> Self {
         Self {
             name: TokenName(name),
-            quantity,
+            quantity: Quantity::new(quantity),
+            decimals: None,
         }
     }
 
+    pub fn with_decimals(mut self, decimals: u8) -> Self {
+        self.decimals = Some(decimals);
+        self
+    }
+
     pub fn name(&self) -> &TokenName {
         &self.name
     }
 
-    pub fn quantity(&self) -> u64 {
+    pub fn quantity(&self) -> Quantity {
         self.quantity
     }
 
+    pub fn decimals(&self) -> Option<u8> {
+        self.decimals
+    }
+
+    pub fn display_quantity(&self) -> String {
+        self.quantity.display(self.decimals).to_string()
+    }
+
     pub fn encode_name(&self) -> pallas::Base {
         self.name.encode()
     }
@@ -183,15 +203,49 @@ impl TokenResource {
 
     pub fn generate_input_token_resource_logics<R: RngCore>(
         &self,
-        mut rng: R,
+        rng: R,
         auth: TokenAuthorization,
         auth_sk: pallas::Scalar,
         merkle_path: [(pallas::Base, LR); TAIGA_RESOURCE_TREE_DEPTH],
     ) -> ResourceLogics {
+        let (token_resource_logic, token_auth_resource_logic) =
+            self.input_token_resource_logic_circuits(rng, auth, auth_sk, merkle_path);
+        ResourceLogics::new(
+            Box::new(token_resource_logic),
+            vec![Box::new(token_auth_resource_logic)],
+        )
+    }
+
+    /// The same logics as `generate_input_token_resource_logics`, as bytecode.
+    pub fn generate_input_token_app<R: RngCore>(
+        &self,
+        rng: R,
+        auth: TokenAuthorization,
+        auth_sk: pallas::Scalar,
+        merkle_path: [(pallas::Base, LR); TAIGA_RESOURCE_TREE_DEPTH],
+    ) -> ApplicationByteCode {
+        let (token_resource_logic, token_auth_resource_logic) =
+            self.input_token_resource_logic_circuits(rng, auth, auth_sk, merkle_path);
+        ApplicationByteCode::new(
+            token_resource_logic.to_bytecode(),
+            vec![token_auth_resource_logic.to_bytecode()],
+        )
+    }
+
+    fn input_token_resource_logic_circuits<R: RngCore>(
+        &self,
+        mut rng: R,
+        auth: TokenAuthorization,
+        auth_sk: pallas::Scalar,
+        merkle_path: [(pallas::Base, LR); TAIGA_RESOURCE_TREE_DEPTH],
+    ) -> (
+        TokenResourceLogicCircuit,
+        SignatureVerificationResourceLogicCircuit,
+    ) {
         // token resource logic
-        let self_resource = ResourceExistenceWitness::new(self.resource, merkle_path);
+        let self_resource = ResourceExistenceWitness::new(self.resource.clone(), merkle_path);
         let token_resource_logic = TokenResourceLogicCircuit {
-            self_resource,
+            self_resource: self_resource.clone(),
             token_name: self.token_name.clone(),
             auth,
             receiver_resource_logic_vk: *COMPRESSED_RECEIVER_VK,
@@ -207,21 +261,47 @@ impl TokenResource {
             *COMPRESSED_RECEIVER_VK,
         );
 
+        (token_resource_logic, token_auth_resource_logic)
+    }
+
+    pub fn generate_output_token_resource_logics<R: RngCore>(
+        &self,
+        rng: R,
+        auth: TokenAuthorization,
+        merkle_path: [(pallas::Base, LR); TAIGA_RESOURCE_TREE_DEPTH],
+    ) -> ResourceLogics {
+        let (token_resource_logic, receiver_resource_logic) =
+            self.output_token_resource_logic_circuits(rng, auth, merkle_path);
         ResourceLogics::new(
             Box::new(token_resource_logic),
-            vec![Box::new(token_auth_resource_logic)],
+            vec![Box::new(receiver_resource_logic)],
         )
     }
 
-    pub fn generate_output_token_resource_logics<R: RngCore>(
+    /// The same logics as `generate_output_token_resource_logics`, as bytecode.
+    pub fn generate_output_token_app<R: RngCore>(
+        &self,
+        rng: R,
+        auth: TokenAuthorization,
+        merkle_path: [(pallas::Base, LR); TAIGA_RESOURCE_TREE_DEPTH],
+    ) -> ApplicationByteCode {
+        let (token_resource_logic, receiver_resource_logic) =
+            self.output_token_resource_logic_circuits(rng, auth, merkle_path);
+        ApplicationByteCode::new(
+            token_resource_logic.to_bytecode(),
+            vec![receiver_resource_logic.to_bytecode()],
+        )
+    }
+
+    fn output_token_resource_logic_circuits<R: RngCore>(
         &self,
         mut rng: R,
         auth: TokenAuthorization,
         merkle_path: [(pallas::Base, LR); TAIGA_RESOURCE_TREE_DEPTH],
-    ) -> ResourceLogics {
-        let self_resource = ResourceExistenceWitness::new(self.resource, merkle_path);
+    ) -> (TokenResourceLogicCircuit, ReceiverResourceLogicCircuit) {
+        let self_resource = ResourceExistenceWitness::new(self.resource.clone(), merkle_path);
         let token_resource_logic = TokenResourceLogicCircuit {
-            self_resource,
+            self_resource: self_resource.clone(),
             token_name: self.token_name.clone(),
             auth,
             receiver_resource_logic_vk: *COMPRESSED_RECEIVER_VK,
@@ -238,17 +318,14 @@ impl TokenResource {
             auth_resource_logic_vk: *COMPRESSED_TOKEN_AUTH_VK,
         };
 
-        ResourceLogics::new(
-            Box::new(token_resource_logic),
-            vec![Box::new(receiver_resource_logic)],
-        )
+        (token_resource_logic, receiver_resource_logic)
     }
 }
 
 // TokenResourceLogicCircuit
 #[derive(Clone, Debug)]
 pub struct TokenResourceLogicCircuit {
-    self_resource: ResourceExistenceWitness,
+    pub self_resource: ResourceExistenceWitness,
     // The token_name goes to label. It can be extended to a list and embedded to label.
     pub token_name: TokenName,
     // The auth goes to value and defines how to consume and create the resource.
@@ -481,7 +558,28 @@ impl ResourceLogicCircuit for TokenResourceLogicCircuit {
     }
 
     fn get_self_resource(&self) -> ResourceExistenceWitness {
-        self.self_resource
+        self.self_resource.clone()
+    }
+
+    fn native_custom_constraints(&self) -> Option<Result<(), TransactionError>> {
+        let resource = self.self_resource.get_resource();
+        // The pk is witnessed as a non-identity point
+        let encoded_value = self.auth.pk.to_affine().coordinates().map(|pk_coord| {
+            poseidon_hash_n::<4>([
+                *pk_coord.x(),
+                *pk_coord.y(),
+                self.auth.vk,
+                self.receiver_resource_logic_vk,
+            ])
+        });
+        let satisfied = resource.get_label() == self.token_name.encode()
+            && Option::from(encoded_value) == Some(resource.value)
+            && !resource.is_ephemeral;
+        Some(if satisfied {
+            Ok(())
+        } else {
+            Err(TransactionError::UnsatisfiedResourceLogic)
+        })
     }
 }
 
@@ -604,4 +702,13 @@ fn test_halo2_token_resource_logic_circuit() {
     )
     .unwrap();
     assert_eq!(prover.verify(), Ok(()));
+
+    // The native evaluation agrees with the circuit
+    assert!(circuit.verify_transparently().is_ok());
+    let mut invalid_circuit = circuit.clone();
+    invalid_circuit.token_name = TokenName("Other_token".to_string());
+    assert!(matches!(
+        invalid_circuit.verify_transparently(),
+        Err(TransactionError::UnsatisfiedResourceLogic)
+    ));
 }
@@ -0,0 +1,469 @@
+/// The logic of ballots, for anonymous voting among the members of a census.
+///
+/// The census is a commitment tree of the npks of the voters. An election is
+/// identified by Poseidon(census_root, topic, options), the options being the
+/// number of choices. A ballot encodes:
+/// * label: the election id
+/// * value: zero
+/// * quantity: zero, so that casting a ballot doesn't unbalance the transaction
+/// * nonce: the linkability tag of the nk of the voter for the election
+/// * rseed: Poseidon(nk, nonce)
+///
+/// A voter casts a vote by consuming an ephemeral ballot with their nk. The
+/// ballot of a voter is thus unique to the election and so is its nullifier:
+/// the ledger rejects a second vote as a double spend. The choice, less than
+/// the options, is a witness of the logic. The logic proves that the npk of
+/// the ballot is in the census and publishes the linkability tag of the nk for
+/// the election, see `LinkabilityTag`, and the vote Poseidon(election_id,
+/// choice). The tally counts the votes without learning the voters, see
+/// `Tally`. Created ballots are rejected.
+use crate::{
+    circuit::{
+        blake2s::publicize_default_dynamic_resource_logic_commitments,
+        gadgets::{
+            assign_free_advice, assign_free_constant, linkability_tag::linkability_tag_gadget,
+            nullifier_key::nk_possession_gadget, poseidon_hash::poseidon_hash_gadget,
+        },
+        merkle_circuit::{merkle_poseidon_gadget, MerklePoseidonChip},
+        resource_logic_bytecode::{ResourceLogicByteCode, ResourceLogicRepresentation},
+        resource_logic_circuit::{
+            ResourceLogicCircuit, ResourceLogicConfig, ResourceLogicPublicInputs,
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait, ResourceStatus,
+        },
+    },
+    constant::RESOURCE_LOGIC_CIRCUIT_BALLOT_VOTE_IDX,
+    error::TransactionError,
+    linkability::LinkabilityTag,
+    merkle_tree::{commitment_tree_path, commitment_tree_root, MerklePath, Node},
+    nullifier::{Nullifier, NullifierKeyContainer},
+    proof::Proof,
+    quantity::Quantity,
+    resource::{RandomSeed, Resource},
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+    resource_tree::ResourceExistenceWitness,
+    utils::{poseidon_hash, poseidon_hash_n, read_base_field},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, Error},
+};
+use lazy_static::lazy_static;
+use pasta_curves::{group::ff::PrimeField, pallas};
+use rand::{rngs::OsRng, RngCore};
+
+lazy_static! {
+    pub static ref BALLOT_VK: ResourceLogicVerifyingKey = BallotResourceLogicCircuit::default()
+        .get_resource_logic_vk()
+        .expect("keygen_vk should not fail");
+    pub static ref COMPRESSED_BALLOT_VK: pallas::Base = BALLOT_VK.get_compressed();
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Election {
+    pub census_root: pallas::Base,
+    pub topic: pallas::Base,
+    pub options: u64,
+}
+
+impl Election {
+    /// An election among the voters of the census, the npks of the voters.
+    pub fn new(census: &[pallas::Base], topic: pallas::Base, options: u64) -> Self {
+        let leaves: Vec<Node> = census.iter().map(|npk| Node::from(*npk)).collect();
+        Self {
+            census_root: commitment_tree_root(&leaves).inner(),
+            topic,
+            options,
+        }
+    }
+
+    /// The path of the voter of `npk` in the census.
+    pub fn census_path(census: &[pallas::Base], npk: pallas::Base) -> Option<MerklePath> {
+        let position = census.iter().position(|voter| *voter == npk)?;
+        let leaves: Vec<Node> = census.iter().map(|npk| Node::from(*npk)).collect();
+        commitment_tree_path(&leaves, position)
+    }
+
+    pub fn id(&self) -> pallas::Base {
+        poseidon_hash_n([
+            self.census_root,
+            self.topic,
+            pallas::Base::from(self.options),
+        ])
+    }
+
+    /// Whether the field element is a choice of the election.
+    pub fn is_option(&self, choice: &pallas::Base) -> bool {
+        let repr = choice.to_repr();
+        repr[8..].iter().all(|byte| *byte == 0)
+            && u64::from_le_bytes(repr[..8].try_into().unwrap()) < self.options
+    }
+
+    /// The vote published by the ballots of `choice`.
+    pub fn vote(&self, choice: u64) -> pallas::Base {
+        poseidon_hash(self.id(), pallas::Base::from(choice))
+    }
+
+    /// The ephemeral ballot of the voter of `nk`, consumed with their nk. The
+    /// ballot only depends on the voter and the election, so does its
+    /// nullifier.
+    pub fn create_ballot(&self, nk: pallas::Base) -> Resource {
+        let nonce = LinkabilityTag::derive(&nk, &self.id()).inner();
+        Resource::new_input_resource(
+            *COMPRESSED_BALLOT_VK,
+            self.id(),
+            pallas::Base::zero(),
+            Quantity::ZERO,
+            nk,
+            Nullifier::from(nonce),
+            true,
+            poseidon_hash(nk, nonce),
+        )
+    }
+}
+
+impl BorshSerialize for Election {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.census_root.to_repr())?;
+        writer.write_all(&self.topic.to_repr())?;
+        writer.write_u64::<LittleEndian>(self.options)?;
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for Election {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let census_root = read_base_field(reader)?;
+        let topic = read_base_field(reader)?;
+        let options = reader.read_u64::<LittleEndian>()?;
+        Ok(Self {
+            census_root,
+            topic,
+            options,
+        })
+    }
+}
+
+// BallotResourceLogicCircuit
+#[derive(Clone, Debug, Default)]
+pub struct BallotResourceLogicCircuit {
+    pub self_resource: ResourceExistenceWitness,
+    pub election: Election,
+    // The nk of the voter
+    pub nk: pallas::Base,
+    // The path of the npk of the voter in the census
+    pub census_path: MerklePath,
+    pub choice: u64,
+}
+
+impl BallotResourceLogicCircuit {
+    pub fn to_bytecode(&self) -> ResourceLogicByteCode {
+        ResourceLogicByteCode::new(ResourceLogicRepresentation::Ballot, self.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(&self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &Vec<u8>) -> Self {
+        BorshDeserialize::deserialize(&mut bytes.as_ref()).unwrap()
+    }
+}
+
+impl ResourceLogicCircuit for BallotResourceLogicCircuit {
+    // Add custom constraints
+    fn custom_constraints(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+        self_resource: ResourceStatus,
+    ) -> Result<(), Error> {
+        // Ballots are only consumed
+        let constant_one = assign_free_constant(
+            layouter.namespace(|| "one"),
+            config.advices[0],
+            pallas::Base::one(),
+        )?;
+        layouter.assign_region(
+            || "check the ballot is consumed",
+            |mut region| region.constrain_equal(self_resource.is_input.cell(), constant_one.cell()),
+        )?;
+
+        // The voter owns the ballot and is in the census
+        let nk = nk_possession_gadget(
+            layouter.namespace(|| "nk possession"),
+            config.advices[0],
+            config.poseidon_config.clone(),
+            Value::known(self.nk),
+            &self_resource.resource.npk,
+        )?;
+        let census_root = merkle_poseidon_gadget(
+            layouter.namespace(|| "census membership"),
+            MerklePoseidonChip::construct(config.merkle_config.clone()),
+            self_resource.resource.npk.clone(),
+            &self.census_path.inner(),
+        )?;
+
+        // The ballot is of the election of the census
+        let topic = assign_free_advice(
+            layouter.namespace(|| "witness topic"),
+            config.advices[0],
+            Value::known(self.election.topic),
+        )?;
+        let options = assign_free_advice(
+            layouter.namespace(|| "witness options"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.election.options)),
+        )?;
+        let election_id = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "election id"),
+            [census_root, topic, options.clone()],
+        )?;
+        layouter.assign_region(
+            || "check label",
+            |mut region| {
+                region.constrain_equal(election_id.cell(), self_resource.resource.label.cell())
+            },
+        )?;
+
+        // check: value = 0
+        let constant_zero = assign_free_constant(
+            layouter.namespace(|| "zero"),
+            config.advices[0],
+            pallas::Base::zero(),
+        )?;
+        layouter.assign_region(
+            || "check value",
+            |mut region| {
+                region.constrain_equal(self_resource.resource.value.cell(), constant_zero.cell())
+            },
+        )?;
+
+        // check: choice < options
+        let choice = assign_free_advice(
+            layouter.namespace(|| "witness choice"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.choice)),
+        )?;
+        let is_option = config.comparison_config.less_than_u64(
+            layouter.namespace(|| "choice < options"),
+            &choice,
+            &options,
+        )?;
+        layouter.assign_region(
+            || "check choice",
+            |mut region| region.constrain_equal(is_option.cell(), constant_one.cell()),
+        )?;
+
+        // Publicize the linkability tag of the voter and the vote
+        let tag = linkability_tag_gadget(
+            layouter.namespace(|| "linkability tag"),
+            config.advices[0],
+            config.instances,
+            config.poseidon_config.clone(),
+            nk.clone(),
+            election_id.clone(),
+        )?;
+        let vote = poseidon_hash_gadget(
+            config.poseidon_config.clone(),
+            layouter.namespace(|| "vote"),
+            [election_id, choice],
+        )?;
+        layouter.constrain_instance(
+            vote.cell(),
+            config.instances,
+            RESOURCE_LOGIC_CIRCUIT_BALLOT_VOTE_IDX,
+        )?;
+
+        // The ballot is the one of the voter for the election, so that its
+        // nullifier is: nonce = tag and rseed = Poseidon(nk, tag)
+        let rseed = poseidon_hash_gadget(
+            config.poseidon_config,
+            layouter.namespace(|| "ballot rseed"),
+            [nk, tag.clone()],
+        )?;
+        layouter.assign_region(
+            || "check nonce and rseed",
+            |mut region| {
+                region.constrain_equal(tag.cell(), self_resource.resource.nonce.cell())?;
+                region.constrain_equal(rseed.cell(), self_resource.resource.rseed.cell())
+            },
+        )?;
+
+        // Publicize the dynamic resource_logic commitments with default value
+        publicize_default_dynamic_resource_logic_commitments(
+            &mut layouter,
+            config.advices[0],
+            config.instances,
+        )?;
+
+        Ok(())
+    }
+
+    fn get_public_inputs(&self, mut rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let mut public_inputs = self.get_mandatory_public_inputs();
+        let default_resource_logic_cm: [pallas::Base; 2] =
+            ResourceLogicCommitment::default().to_public_inputs();
+        public_inputs.extend(default_resource_logic_cm);
+        public_inputs.extend(default_resource_logic_cm);
+        let election_id = self.election.id();
+        public_inputs.push(LinkabilityTag::derive(&self.nk, &election_id).inner());
+        public_inputs.push(self.election.vote(self.choice));
+        let padding = ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &RandomSeed::random(&mut rng),
+        );
+        public_inputs.extend(padding);
+        public_inputs.into()
+    }
+
+    fn get_self_resource(&self) -> ResourceExistenceWitness {
+        self.self_resource.clone()
+    }
+
+    fn native_custom_constraints(&self) -> Option<Result<(), TransactionError>> {
+        let resource = self.self_resource.get_resource();
+        let npk = resource.get_npk();
+        let is_voter = NullifierKeyContainer::from_key(self.nk).get_npk() == npk
+            && self.census_path.root(Node::from(npk)).inner() == self.election.census_root;
+        let ballot = self.election.create_ballot(self.nk);
+        let satisfied = self.self_resource.is_input()
+            && is_voter
+            && resource.kind.label == self.election.id()
+            && resource.value == ballot.value
+            && resource.nonce == ballot.nonce
+            && resource.rseed.expose_secret() == ballot.rseed.expose_secret()
+            && self.choice < self.election.options;
+        Some(if satisfied {
+            Ok(())
+        } else {
+            Err(TransactionError::UnsatisfiedResourceLogic)
+        })
+    }
+}
+
+resource_logic_circuit_impl!(BallotResourceLogicCircuit);
+resource_logic_verifying_info_impl!(BallotResourceLogicCircuit);
+
+impl BorshSerialize for BallotResourceLogicCircuit {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.self_resource.serialize(writer)?;
+        self.election.serialize(writer)?;
+        writer.write_all(&self.nk.to_repr())?;
+        self.census_path.serialize(writer)?;
+        writer.write_u64::<LittleEndian>(self.choice)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for BallotResourceLogicCircuit {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let self_resource = ResourceExistenceWitness::deserialize_reader(reader)?;
+        let election = Election::deserialize_reader(reader)?;
+        let nk = read_base_field(reader)?;
+        let census_path = MerklePath::deserialize_reader(reader)?;
+        let choice = reader.read_u64::<LittleEndian>()?;
+        Ok(Self {
+            self_resource,
+            election,
+            nk,
+            census_path,
+            choice,
+        })
+    }
+}
+
+#[test]
+fn test_halo2_ballot_resource_logic_circuit() {
+    use crate::constant::{
+        RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX, RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+    };
+    use crate::resource_tree::ResourceMerkleTreeLeaves;
+    use halo2_proofs::dev::MockProver;
+
+    let mut rng = OsRng;
+    let nks: Vec<pallas::Base> = (0..3).map(|_| pallas::Base::random(&mut rng)).collect();
+    let census: Vec<pallas::Base> = nks
+        .iter()
+        .map(|nk| NullifierKeyContainer::from_key(*nk).get_npk())
+        .collect();
+    let election = Election::new(&census, pallas::Base::random(&mut rng), 3);
+
+    // Builds the circuit of the ballot of the voter of `nk`
+    let ballot_circuit = |nk: pallas::Base, choice: u64, census_path: MerklePath| {
+        let mut rng = OsRng;
+        let ballot = election.create_ballot(nk);
+        let nf = ballot.get_nf().unwrap().inner();
+        let resource_merkle_tree =
+            ResourceMerkleTreeLeaves::new(vec![nf, pallas::Base::random(&mut rng)]);
+        BallotResourceLogicCircuit {
+            self_resource: ResourceExistenceWitness::new(
+                ballot,
+                resource_merkle_tree.generate_path(nf).unwrap(),
+            ),
+            election,
+            nk,
+            census_path,
+            choice,
+        }
+    };
+    let mock_prove = |circuit: &BallotResourceLogicCircuit| {
+        let public_inputs = circuit.get_public_inputs(OsRng);
+        MockProver::<pallas::Base>::run(
+            RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+            circuit,
+            vec![public_inputs.to_vec()],
+        )
+        .unwrap()
+        .verify()
+    };
+
+    let census_path = Election::census_path(&census, census[1]).unwrap();
+    let circuit = ballot_circuit(nks[1], 2, census_path.clone());
+    // Test serialization
+    let circuit = BallotResourceLogicCircuit::from_bytes(&circuit.to_bytes());
+    let public_inputs = circuit.get_public_inputs(&mut rng);
+    assert_eq!(
+        public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX),
+        LinkabilityTag::derive(&nks[1], &election.id()).inner()
+    );
+    assert_eq!(
+        public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_BALLOT_VOTE_IDX),
+        election.vote(2)
+    );
+    assert_eq!(mock_prove(&circuit), Ok(()));
+    assert!(circuit.verify_transparently().is_ok());
+
+    // A choice out of the options and a voter out of the census are rejected
+    let circuit = ballot_circuit(nks[1], 3, census_path.clone());
+    assert!(mock_prove(&circuit).is_err());
+    assert!(circuit.verify_transparently().is_err());
+    let circuit = ballot_circuit(pallas::Base::random(&mut rng), 0, census_path.clone());
+    assert!(mock_prove(&circuit).is_err());
+    assert!(circuit.verify_transparently().is_err());
+
+    // The ballots of a voter are the same whatever the choice, so is the
+    // nullifier, and any other ballot is rejected
+    assert_eq!(
+        election.create_ballot(nks[1]).get_nf(),
+        ballot_circuit(nks[1], 0, census_path.clone())
+            .self_resource
+            .get_resource()
+            .get_nf()
+    );
+    let mut circuit = ballot_circuit(nks[1], 0, census_path);
+    let mut ballot = circuit.self_resource.get_resource();
+    ballot.nonce = Nullifier::random(&mut rng);
+    let nf = ballot.get_nf().unwrap().inner();
+    let resource_merkle_tree =
+        ResourceMerkleTreeLeaves::new(vec![nf, pallas::Base::random(&mut rng)]);
+    circuit.self_resource =
+        ResourceExistenceWitness::new(ballot, resource_merkle_tree.generate_path(nf).unwrap());
+    assert!(mock_prove(&circuit).is_err());
+    assert!(circuit.verify_transparently().is_err());
+}
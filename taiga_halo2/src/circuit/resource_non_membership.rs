@@ -0,0 +1,581 @@
+//! Non-membership of a value in a sorted tree, see `SortedMerkleTree`.
+//!
+//! The prover opens the two adjacent leaves `low` and `high` of the sorted
+//! tree and shows `low < value < high`. Values are compared as two 127-bit
+//! limbs, so the sorted tree and the value must be less than 2^254.
+use crate::circuit::gadgets::{assign_free_advice, poseidon_hash::poseidon_hash_gadget};
+use crate::merkle_tree::LR;
+use crate::resource_tree::ResourceNonExistenceWitness;
+use group::ff::PrimeField;
+use halo2_gadgets::{
+    poseidon::Pow5Config as PoseidonConfig,
+    utilities::{bool_check, lookup_range_check::LookupRangeCheckConfig},
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use pasta_curves::pallas;
+
+const LIMB_BITS: usize = 127;
+
+#[derive(Clone, Debug)]
+pub struct ResourceNonMembershipConfig {
+    q_merkle_swap: Selector,
+    q_decompose: Selector,
+    q_less_than: Selector,
+    q_position: Selector,
+    q_adjacent: Selector,
+    advices: [Column<Advice>; 7],
+    poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+    lookup_config: LookupRangeCheckConfig<pallas::Base, 10>,
+}
+
+impl ResourceNonMembershipConfig {
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        advices: [Column<Advice>; 7],
+        poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+        lookup_config: LookupRangeCheckConfig<pallas::Base, 10>,
+    ) -> Self {
+        let config = Self {
+            q_merkle_swap: meta.selector(),
+            q_decompose: meta.selector(),
+            q_less_than: meta.selector(),
+            q_position: meta.selector(),
+            q_adjacent: meta.selector(),
+            advices,
+            poseidon_config,
+            lookup_config,
+        };
+
+        config.create_gate(meta);
+
+        config
+    }
+
+    fn create_gate(&self, meta: &mut ConstraintSystem<pallas::Base>) {
+        let two_pow_limb = pallas::Base::from_u128(1 << LIMB_BITS);
+
+        // The swap bit is 1 if the current node is the right child.
+        meta.create_gate("merkle swap", |meta| {
+            let q_merkle_swap = meta.query_selector(self.q_merkle_swap);
+
+            let cur = meta.query_advice(self.advices[0], Rotation::cur());
+            let sibling = meta.query_advice(self.advices[1], Rotation::cur());
+            let bit = meta.query_advice(self.advices[2], Rotation::cur());
+            let left = meta.query_advice(self.advices[3], Rotation::cur());
+            let right = meta.query_advice(self.advices[4], Rotation::cur());
+
+            let left_check = left - (cur.clone() + bit.clone() * (sibling.clone() - cur.clone()));
+            let right_check = right - (sibling.clone() + bit.clone() * (cur - sibling));
+
+            Constraints::with_selector(
+                q_merkle_swap,
+                [
+                    ("bool_check bit", bool_check(bit)),
+                    ("left = cur + bit * (sibling - cur)", left_check),
+                    ("right = sibling + bit * (cur - sibling)", right_check),
+                ],
+            )
+        });
+
+        meta.create_gate("decompose", |meta| {
+            let q_decompose = meta.query_selector(self.q_decompose);
+
+            let value = meta.query_advice(self.advices[0], Rotation::cur());
+            let hi = meta.query_advice(self.advices[1], Rotation::cur());
+            let lo = meta.query_advice(self.advices[2], Rotation::cur());
+
+            Constraints::with_selector(
+                q_decompose,
+                [("value = hi * 2^127 + lo", value - (hi * two_pow_limb + lo))],
+            )
+        });
+
+        // lhs < rhs iff rhs - lhs - 1 = diff_hi * 2^127 + diff_lo with both
+        // diff limbs in [0, 2^127).
+        meta.create_gate("less than", |meta| {
+            let q_less_than = meta.query_selector(self.q_less_than);
+
+            let lhs_hi = meta.query_advice(self.advices[0], Rotation::cur());
+            let lhs_lo = meta.query_advice(self.advices[1], Rotation::cur());
+            let rhs_hi = meta.query_advice(self.advices[2], Rotation::cur());
+            let rhs_lo = meta.query_advice(self.advices[3], Rotation::cur());
+            let borrow = meta.query_advice(self.advices[4], Rotation::cur());
+            let diff_hi = meta.query_advice(self.advices[5], Rotation::cur());
+            let diff_lo = meta.query_advice(self.advices[6], Rotation::cur());
+
+            let lo_check = diff_lo
+                - (rhs_lo - lhs_lo - Expression::Constant(pallas::Base::one())
+                    + borrow.clone() * two_pow_limb);
+            let hi_check = diff_hi - (rhs_hi - lhs_hi - borrow.clone());
+
+            Constraints::with_selector(
+                q_less_than,
+                [
+                    ("bool_check borrow", bool_check(borrow)),
+                    ("low limb", lo_check),
+                    ("high limb", hi_check),
+                ],
+            )
+        });
+
+        // The positions of the two paths are accumulated from their swap bits,
+        // the bits closest to the root first.
+        meta.create_gate("position", |meta| {
+            let q_position = meta.query_selector(self.q_position);
+
+            let checks = [(0, "low position"), (2, "high position")].map(|(column, name)| {
+                let acc = meta.query_advice(self.advices[column], Rotation::cur());
+                let bit = meta.query_advice(self.advices[column + 1], Rotation::cur());
+                let acc_next = meta.query_advice(self.advices[column], Rotation::next());
+                (name, acc_next - (acc * pallas::Base::from(2) + bit))
+            });
+
+            Constraints::with_selector(q_position, checks)
+        });
+
+        // The positions of the two paths differ by one.
+        meta.create_gate("adjacent", |meta| {
+            let q_adjacent = meta.query_selector(self.q_adjacent);
+
+            let low = meta.query_advice(self.advices[0], Rotation::cur());
+            let high = meta.query_advice(self.advices[2], Rotation::cur());
+
+            Constraints::with_selector(
+                q_adjacent,
+                [(
+                    "high position = low position + 1",
+                    high - low - Expression::Constant(pallas::Base::one()),
+                )],
+            )
+        });
+    }
+
+    // Returns the root and the swap bits of the path
+    #[allow(clippy::type_complexity)]
+    fn merkle_root(
+        &self,
+        layouter: &mut impl Layouter<pallas::Base>,
+        leaf: AssignedCell<pallas::Base, pallas::Base>,
+        merkle_path: &[(pallas::Base, LR)],
+    ) -> Result<
+        (
+            AssignedCell<pallas::Base, pallas::Base>,
+            Vec<AssignedCell<pallas::Base, pallas::Base>>,
+        ),
+        Error,
+    > {
+        let mut cur = leaf;
+        let mut bits = vec![];
+        for (sibling, lr) in merkle_path.iter() {
+            let (bit, left, right) = layouter.assign_region(
+                || "merkle swap",
+                |mut region| {
+                    self.q_merkle_swap.enable(&mut region, 0)?;
+
+                    cur.copy_advice(|| "cur", &mut region, self.advices[0], 0)?;
+                    region.assign_advice(
+                        || "sibling",
+                        self.advices[1],
+                        0,
+                        || Value::known(*sibling),
+                    )?;
+                    let bit = region.assign_advice(
+                        || "swap bit",
+                        self.advices[2],
+                        0,
+                        || Value::known(pallas::Base::from(u64::from(lr.is_left()))),
+                    )?;
+                    let (left_value, right_value) = if lr.is_left() {
+                        (Value::known(*sibling), cur.value().copied())
+                    } else {
+                        (cur.value().copied(), Value::known(*sibling))
+                    };
+                    let left =
+                        region.assign_advice(|| "left", self.advices[3], 0, || left_value)?;
+                    let right =
+                        region.assign_advice(|| "right", self.advices[4], 0, || right_value)?;
+                    Ok((bit, left, right))
+                },
+            )?;
+            bits.push(bit);
+
+            cur = poseidon_hash_gadget(
+                self.poseidon_config.clone(),
+                layouter.namespace(|| "merkle poseidon hash"),
+                [left, right],
+            )?;
+        }
+
+        Ok((cur, bits))
+    }
+
+    fn limb_range_check(
+        &self,
+        layouter: &mut impl Layouter<pallas::Base>,
+        limb: &AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<(), Error> {
+        let zs = self.lookup_config.copy_check(
+            layouter.namespace(|| "12 * K(10) bits range check"),
+            limb.clone(),
+            12,
+            false,
+        )?;
+
+        self.lookup_config.copy_short_check(
+            layouter.namespace(|| "7 bits range check"),
+            zs[12].clone(),
+            7,
+        )
+    }
+
+    // Decompose a value into range checked (hi, lo) limbs
+    fn decompose(
+        &self,
+        layouter: &mut impl Layouter<pallas::Base>,
+        value: &AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<
+        (
+            AssignedCell<pallas::Base, pallas::Base>,
+            AssignedCell<pallas::Base, pallas::Base>,
+        ),
+        Error,
+    > {
+        let limbs = value.value().map(split_limbs);
+        let (hi, lo) = layouter.assign_region(
+            || "decompose",
+            |mut region| {
+                self.q_decompose.enable(&mut region, 0)?;
+
+                value.copy_advice(|| "value", &mut region, self.advices[0], 0)?;
+                let hi = region.assign_advice(
+                    || "hi",
+                    self.advices[1],
+                    0,
+                    || limbs.map(|(hi, _)| pallas::Base::from_u128(hi)),
+                )?;
+                let lo = region.assign_advice(
+                    || "lo",
+                    self.advices[2],
+                    0,
+                    || limbs.map(|(_, lo)| pallas::Base::from_u128(lo)),
+                )?;
+                Ok((hi, lo))
+            },
+        )?;
+
+        self.limb_range_check(layouter, &hi)?;
+        self.limb_range_check(layouter, &lo)?;
+
+        Ok((hi, lo))
+    }
+
+    // Constrain lhs < rhs given their limbs
+    fn less_than(
+        &self,
+        layouter: &mut impl Layouter<pallas::Base>,
+        lhs: &(
+            AssignedCell<pallas::Base, pallas::Base>,
+            AssignedCell<pallas::Base, pallas::Base>,
+        ),
+        rhs: &(
+            AssignedCell<pallas::Base, pallas::Base>,
+            AssignedCell<pallas::Base, pallas::Base>,
+        ),
+    ) -> Result<(), Error> {
+        let borrow = lhs.1.value().zip(rhs.1.value()).map(|(lhs_lo, rhs_lo)| {
+            pallas::Base::from(u64::from(limb_to_u128(rhs_lo) <= limb_to_u128(lhs_lo)))
+        });
+        let diff_lo =
+            lhs.1
+                .value()
+                .zip(rhs.1.value())
+                .zip(borrow)
+                .map(|((lhs_lo, rhs_lo), borrow)| {
+                    rhs_lo - lhs_lo - pallas::Base::one()
+                        + borrow * pallas::Base::from_u128(1 << LIMB_BITS)
+                });
+        let diff_hi = lhs
+            .0
+            .value()
+            .zip(rhs.0.value())
+            .zip(borrow)
+            .map(|((lhs_hi, rhs_hi), borrow)| rhs_hi - lhs_hi - borrow);
+
+        let (diff_hi, diff_lo) = layouter.assign_region(
+            || "less than",
+            |mut region| {
+                self.q_less_than.enable(&mut region, 0)?;
+
+                lhs.0
+                    .copy_advice(|| "lhs hi", &mut region, self.advices[0], 0)?;
+                lhs.1
+                    .copy_advice(|| "lhs lo", &mut region, self.advices[1], 0)?;
+                rhs.0
+                    .copy_advice(|| "rhs hi", &mut region, self.advices[2], 0)?;
+                rhs.1
+                    .copy_advice(|| "rhs lo", &mut region, self.advices[3], 0)?;
+                region.assign_advice(|| "borrow", self.advices[4], 0, || borrow)?;
+                let diff_hi = region.assign_advice(|| "diff hi", self.advices[5], 0, || diff_hi)?;
+                let diff_lo = region.assign_advice(|| "diff lo", self.advices[6], 0, || diff_lo)?;
+                Ok((diff_hi, diff_lo))
+            },
+        )?;
+
+        self.limb_range_check(layouter, &diff_hi)?;
+        self.limb_range_check(layouter, &diff_lo)
+    }
+
+    fn adjacent(
+        &self,
+        layouter: &mut impl Layouter<pallas::Base>,
+        low_bits: &[AssignedCell<pallas::Base, pallas::Base>],
+        high_bits: &[AssignedCell<pallas::Base, pallas::Base>],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "adjacent",
+            |mut region| {
+                let mut low_acc = region.assign_advice_from_constant(
+                    || "low position",
+                    self.advices[0],
+                    0,
+                    pallas::Base::zero(),
+                )?;
+                let mut high_acc = region.assign_advice_from_constant(
+                    || "high position",
+                    self.advices[2],
+                    0,
+                    pallas::Base::zero(),
+                )?;
+                let next =
+                    |acc: &AssignedCell<pallas::Base, pallas::Base>,
+                     bit: &AssignedCell<pallas::Base, pallas::Base>| {
+                        acc.value()
+                            .zip(bit.value())
+                            .map(|(acc, bit)| acc + acc + bit)
+                    };
+                for (offset, (low, high)) in low_bits.iter().zip(high_bits.iter()).rev().enumerate()
+                {
+                    self.q_position.enable(&mut region, offset)?;
+
+                    low.copy_advice(|| "low bit", &mut region, self.advices[1], offset)?;
+                    high.copy_advice(|| "high bit", &mut region, self.advices[3], offset)?;
+
+                    let low_next = next(&low_acc, low);
+                    let high_next = next(&high_acc, high);
+                    low_acc = region.assign_advice(
+                        || "low position",
+                        self.advices[0],
+                        offset + 1,
+                        || low_next,
+                    )?;
+                    high_acc = region.assign_advice(
+                        || "high position",
+                        self.advices[2],
+                        offset + 1,
+                        || high_next,
+                    )?;
+                }
+                self.q_adjacent.enable(&mut region, low_bits.len())
+            },
+        )
+    }
+}
+
+// Split a value below 2^254 into 127-bit (hi, lo) limbs
+fn split_limbs(value: &pallas::Base) -> (u128, u128) {
+    let repr = value.to_repr();
+    let low = u128::from_le_bytes(repr[..16].try_into().unwrap());
+    let high = u128::from_le_bytes(repr[16..].try_into().unwrap());
+    let mask = (1u128 << LIMB_BITS) - 1;
+    ((high << 1) | (low >> LIMB_BITS), low & mask)
+}
+
+fn limb_to_u128(limb: &pallas::Base) -> u128 {
+    u128::from_le_bytes(limb.to_repr()[..16].try_into().unwrap())
+}
+
+/// Constrains that `value` is not a leaf of the sorted resource tree and
+/// returns the root of the tree. The caller must constrain the root.
+pub fn resource_non_membership_gadget(
+    mut layouter: impl Layouter<pallas::Base>,
+    config: &ResourceNonMembershipConfig,
+    value: &AssignedCell<pallas::Base, pallas::Base>,
+    witness: &ResourceNonExistenceWitness,
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+    let low_leaf = assign_free_advice(
+        layouter.namespace(|| "witness low leaf"),
+        config.advices[0],
+        Value::known(witness.get_low_leaf()),
+    )?;
+    let high_leaf = assign_free_advice(
+        layouter.namespace(|| "witness high leaf"),
+        config.advices[0],
+        Value::known(witness.get_high_leaf()),
+    )?;
+
+    let (low_root, low_bits) =
+        config.merkle_root(&mut layouter, low_leaf.clone(), &witness.get_low_path())?;
+    let (high_root, high_bits) =
+        config.merkle_root(&mut layouter, high_leaf.clone(), &witness.get_high_path())?;
+    layouter.assign_region(
+        || "low root = high root",
+        |mut region| region.constrain_equal(low_root.cell(), high_root.cell()),
+    )?;
+    config.adjacent(&mut layouter, &low_bits, &high_bits)?;
+
+    let low = config.decompose(&mut layouter, &low_leaf)?;
+    let value = config.decompose(&mut layouter, value)?;
+    let high = config.decompose(&mut layouter, &high_leaf)?;
+    config.less_than(&mut layouter, &low, &value)?;
+    config.less_than(&mut layouter, &value, &high)?;
+
+    Ok(low_root)
+}
+
+#[test]
+fn test_halo2_resource_non_membership_circuit() {
+    use crate::resource_tree::SortedMerkleTree;
+    use halo2_gadgets::poseidon::{primitives as poseidon, Pow5Chip as PoseidonChip};
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        plonk::{Circuit, Instance, TableColumn},
+    };
+
+    #[derive(Default)]
+    struct MyCircuit {
+        value: pallas::Base,
+        witness: ResourceNonExistenceWitness,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = (ResourceNonMembershipConfig, TableColumn, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let table_idx = meta.lookup_table_column();
+            let lookup_config = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+
+            let lagrange_coeffs = [
+                meta.fixed_column(),
+                meta.fixed_column(),
+                meta.fixed_column(),
+                meta.fixed_column(),
+                meta.fixed_column(),
+                meta.fixed_column(),
+                meta.fixed_column(),
+                meta.fixed_column(),
+            ];
+            meta.enable_constant(lagrange_coeffs[0]);
+
+            let poseidon_config = PoseidonChip::configure::<poseidon::P128Pow5T3>(
+                meta,
+                advices[6..9].try_into().unwrap(),
+                advices[5],
+                lagrange_coeffs[2..5].try_into().unwrap(),
+                lagrange_coeffs[5..8].try_into().unwrap(),
+            );
+
+            let config = ResourceNonMembershipConfig::configure(
+                meta,
+                advices[..7].try_into().unwrap(),
+                poseidon_config,
+                lookup_config,
+            );
+            (config, table_idx, instance)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let (config, table_idx, instance) = config;
+            layouter.assign_table(
+                || "table_idx",
+                |mut table| {
+                    for index in 0..(1 << 10) {
+                        table.assign_cell(
+                            || "table_idx",
+                            table_idx,
+                            index,
+                            || Value::known(pallas::Base::from(index as u64)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+
+            let value = assign_free_advice(
+                layouter.namespace(|| "witness value"),
+                config.advices[0],
+                Value::known(self.value),
+            )?;
+            let root = resource_non_membership_gadget(
+                layouter.namespace(|| "non-membership"),
+                &config,
+                &value,
+                &self.witness,
+            )?;
+            layouter.constrain_instance(root.cell(), instance, 0)
+        }
+    }
+
+    let leaves = vec![
+        pallas::Base::from(3u64),
+        pallas::Base::from(7u64),
+        pallas::Base::from_u128(1 << 127) + pallas::Base::from(5u64),
+    ];
+    let tree = SortedMerkleTree::new(leaves);
+    let root = tree.root();
+
+    // Absent values, including ones crossing the limb boundary
+    for value in [
+        pallas::Base::from(5u64),
+        pallas::Base::from_u128(1 << 127),
+        pallas::Base::from_u128(1 << 127) + pallas::Base::from(9u64),
+    ] {
+        let witness = tree.generate_non_membership_witness(value).unwrap();
+        let circuit = MyCircuit { value, witness };
+        let prover = MockProver::run(13, &circuit, vec![vec![root]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    // A leaf of the tree can't be proven absent
+    let witness = tree
+        .generate_non_membership_witness(pallas::Base::from(5u64))
+        .unwrap();
+    let circuit = MyCircuit {
+        value: pallas::Base::from(7u64),
+        witness,
+    };
+    let prover = MockProver::run(13, &circuit, vec![vec![root]]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,205 @@
+use crate::circuit::gadgets::assign_free_advice;
+use crate::circuit::integrity::check_input_resource;
+use crate::circuit::resource_commitment::{ResourceCommitChip, ResourceCommitConfig};
+use crate::constant::{
+    SPENT_CM_PUBLIC_INPUT_ROW_IDX, SPENT_EPOCH_PUBLIC_INPUT_ROW_IDX, SPENT_NF_PUBLIC_INPUT_ROW_IDX,
+};
+use crate::resource::Resource;
+
+use halo2_gadgets::{
+    poseidon::{primitives as poseidon, Pow5Chip as PoseidonChip},
+    utilities::lookup_range_check::LookupRangeCheckConfig,
+};
+use halo2_proofs::{
+    circuit::{floor_planner, Layouter, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, TableColumn},
+};
+use pasta_curves::pallas;
+
+#[derive(Clone, Debug)]
+pub struct SpentConfig {
+    instances: Column<Instance>,
+    advices: [Column<Advice>; 10],
+    table_idx: TableColumn,
+    resource_commit_config: ResourceCommitConfig,
+}
+
+/// The Spent circuit links a resource commitment to its nullifier: it proves
+/// that the public nullifier is derived from the resource behind the public
+/// commitment, without revealing the nullifier key. The audit epoch is
+/// constrained to the public one, binding the proof to the epoch.
+#[derive(Clone, Debug, Default)]
+pub struct SpentCircuit {
+    /// The spent resource
+    pub resource: Resource,
+    /// The audit epoch
+    pub epoch: u64,
+}
+
+impl Circuit<pallas::Base> for SpentCircuit {
+    type Config = SpentConfig;
+    type FloorPlanner = floor_planner::V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let instances = meta.instance_column();
+        meta.enable_equality(instances);
+
+        let advices = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+
+        for advice in advices.iter() {
+            meta.enable_equality(*advice);
+        }
+
+        let table_idx = meta.lookup_table_column();
+
+        let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+
+        let lagrange_coeffs = [
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+        ];
+        meta.enable_constant(lagrange_coeffs[0]);
+
+        let poseidon_config = PoseidonChip::configure::<poseidon::P128Pow5T3>(
+            meta,
+            advices[6..9].try_into().unwrap(),
+            advices[5],
+            lagrange_coeffs[2..5].try_into().unwrap(),
+            lagrange_coeffs[5..8].try_into().unwrap(),
+        );
+
+        let resource_commit_config = ResourceCommitChip::configure(
+            meta,
+            advices[0..3].try_into().unwrap(),
+            poseidon_config,
+            range_check,
+        );
+
+        Self::Config {
+            instances,
+            advices,
+            table_idx,
+            resource_commit_config,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "table_idx",
+            |mut table| {
+                for index in 0..(1 << 10) {
+                    table.assign_cell(
+                        || "table_idx",
+                        config.table_idx,
+                        index,
+                        || Value::known(pallas::Base::from(index as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let resource_commit_chip = ResourceCommitChip::construct(config.resource_commit_config);
+
+        // Check the resource commitment and publicize the nullifier
+        let resource_variables = check_input_resource(
+            layouter.namespace(|| "check spent resource"),
+            config.advices,
+            config.instances,
+            resource_commit_chip,
+            &self.resource,
+            SPENT_NF_PUBLIC_INPUT_ROW_IDX,
+        )?;
+
+        // Publicize the resource commitment
+        layouter.constrain_instance(
+            resource_variables.cm.cell(),
+            config.instances,
+            SPENT_CM_PUBLIC_INPUT_ROW_IDX,
+        )?;
+
+        // Publicize the epoch
+        let epoch = assign_free_advice(
+            layouter.namespace(|| "witness epoch"),
+            config.advices[0],
+            Value::known(pallas::Base::from(self.epoch)),
+        )?;
+        layouter.constrain_instance(
+            epoch.cell(),
+            config.instances,
+            SPENT_EPOCH_PUBLIC_INPUT_ROW_IDX,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_halo2_spent_circuit() {
+    use crate::constant::SPENT_CIRCUIT_PARAMS_SIZE;
+    use crate::resource::tests::random_resource;
+    use halo2_proofs::dev::MockProver;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let resource = random_resource(&mut rng);
+    let circuit = SpentCircuit {
+        resource: resource.clone(),
+        epoch: 7,
+    };
+    let cm = resource.commitment().inner();
+    let nf = resource.get_nf().unwrap().inner();
+    let epoch = pallas::Base::from(7u64);
+
+    let prover = MockProver::<pallas::Base>::run(
+        SPENT_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![vec![cm, nf, epoch]],
+    )
+    .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // The nullifier of another resource must not be linked
+    let other_nf = random_resource(&mut rng).get_nf().unwrap().inner();
+    let prover = MockProver::<pallas::Base>::run(
+        SPENT_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![vec![cm, other_nf, epoch]],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+
+    // The proof is bound to its epoch
+    let prover = MockProver::<pallas::Base>::run(
+        SPENT_CIRCUIT_PARAMS_SIZE,
+        &circuit,
+        vec![vec![cm, nf, pallas::Base::from(8u64)]],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,298 @@
+//! Resource logics written in vamp-ir, compiled to halo2 by the vamp-ir
+//! compiler, behind the `vamp-ir` feature.
+//!
+//! The logic is only bound to the rest of the crate by
+//! `ResourceLogicVerifyingInfoTrait`. Its public inputs are the public
+//! variables of the vamp-ir module, padded to the layout of
+//! `ResourceLogicPublicInputs`. vamp-ir picks the params size of the circuit,
+//! so the params are generated with the circuit rather than taken from
+//! `params::get_params`.
+use crate::{
+    circuit::{
+        resource_logic_circuit::{
+            ResourceLogicPublicInputs, ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait,
+        },
+        vamp_ir_utils::{get_circuit_assignments, parse, VariableAssignmentError},
+    },
+    error::TransactionError,
+    params::ProvingContext,
+    proof::Proof,
+    resource::RandomSeed,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+};
+use halo2_proofs::{
+    dev::MockProver,
+    plonk::{keygen_pk, keygen_vk},
+    poly::commitment::Params,
+};
+use pasta_curves::{pallas, vesta, EqAffine, Fp};
+use rand::{rngs::OsRng, RngCore};
+use std::{collections::HashMap, fs, path::PathBuf, rc::Rc};
+use vamp_ir::halo2::synth::{make_constant, Halo2Module, PrimeFieldOps};
+use vamp_ir::transform::compile;
+use vamp_ir::util::{read_inputs_from_file, Config};
+
+#[derive(Clone)]
+pub struct VampIRResourceLogicCircuit {
+    // TODO: vamp_ir doesn't support to set the params size manually, add the params here temporarily.
+    // remove the params once we can set it as RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE in vamp_ir.
+    pub params: Params<vesta::Affine>,
+    pub circuit: Halo2Module<pallas::Base>,
+    pub public_inputs: Vec<pallas::Base>,
+}
+
+#[derive(Debug)]
+pub enum VampIRCircuitError {
+    MissingAssignment(String),
+    SourceParsingError(String),
+    FileReadError(String),
+}
+
+impl From<VampIRCircuitError> for TransactionError {
+    fn from(e: VampIRCircuitError) -> Self {
+        TransactionError::InvalidVampIRResourceLogic(format!("{e:?}"))
+    }
+}
+
+impl VampIRCircuitError {
+    fn from_variable_assignment_error(error: VariableAssignmentError) -> Self {
+        match error {
+            VariableAssignmentError::MissingAssignment(s) => {
+                VampIRCircuitError::MissingAssignment(s)
+            }
+        }
+    }
+}
+
+impl VampIRResourceLogicCircuit {
+    pub fn from_vamp_ir_source(
+        vamp_ir_source: &str,
+        named_field_assignments: HashMap<String, Fp>,
+    ) -> Result<Self, VampIRCircuitError> {
+        let config = Config { quiet: true };
+        let parsed_vamp_ir_module =
+            parse(vamp_ir_source).map_err(VampIRCircuitError::SourceParsingError)?;
+        let vamp_ir_module = compile(
+            parsed_vamp_ir_module,
+            &PrimeFieldOps::<Fp>::default(),
+            &config,
+        );
+        let mut circuit = Halo2Module::<Fp>::new(Rc::new(vamp_ir_module));
+        let params = Params::new(circuit.k);
+        let field_assignments = get_circuit_assignments(&circuit.module, &named_field_assignments)
+            .map_err(VampIRCircuitError::from_variable_assignment_error)?;
+
+        // Populate variable definitions
+        circuit.populate_variables(field_assignments.clone());
+
+        // Get public inputs Fp
+        let public_inputs = circuit
+            .module
+            .pubs
+            .iter()
+            .map(|inst| field_assignments[&inst.id])
+            .collect::<Vec<pallas::Base>>();
+
+        Ok(Self {
+            params,
+            circuit,
+            public_inputs,
+        })
+    }
+
+    pub fn from_vamp_ir_file(
+        vamp_ir_file: &PathBuf,
+        inputs_file: &PathBuf,
+    ) -> Result<Self, VampIRCircuitError> {
+        let config = Config { quiet: true };
+        let vamp_ir_source = fs::read_to_string(vamp_ir_file)
+            .map_err(|e| VampIRCircuitError::FileReadError(e.to_string()))?;
+        let parsed_vamp_ir_module =
+            parse(&vamp_ir_source).map_err(VampIRCircuitError::SourceParsingError)?;
+        let vamp_ir_module = compile(
+            parsed_vamp_ir_module,
+            &PrimeFieldOps::<Fp>::default(),
+            &config,
+        );
+        let mut circuit = Halo2Module::<Fp>::new(Rc::new(vamp_ir_module));
+        let params: Params<EqAffine> = Params::new(circuit.k);
+
+        let var_assignments_ints = read_inputs_from_file(&circuit.module, inputs_file);
+        let mut var_assignments = HashMap::new();
+        for (k, v) in var_assignments_ints {
+            var_assignments.insert(k, make_constant(v));
+        }
+
+        // Populate variable definitions
+        circuit.populate_variables(var_assignments.clone());
+
+        // Get public inputs Fp
+        let public_inputs = circuit
+            .module
+            .pubs
+            .iter()
+            .map(|inst| var_assignments[&inst.id])
+            .collect::<Vec<pallas::Base>>();
+
+        Ok(Self {
+            params,
+            circuit,
+            public_inputs,
+        })
+    }
+}
+
+impl ResourceLogicVerifyingInfoTrait for VampIRResourceLogicCircuit {
+    // vamp-ir picks the params size, the logic is proven with its own params
+    fn get_verifying_info_with_context(
+        &self,
+        _: &ProvingContext,
+        mut rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfo, TransactionError> {
+        let vk = keygen_vk(&self.params, &self.circuit).map_err(TransactionError::Keygen)?;
+        let pk =
+            keygen_pk(&self.params, vk.clone(), &self.circuit).map_err(TransactionError::Keygen)?;
+
+        let mut public_inputs = self.public_inputs.clone();
+        let rseed = RandomSeed::random(&mut rng);
+        public_inputs.extend(ResourceLogicPublicInputs::get_public_input_padding(
+            self.public_inputs.len(),
+            &rseed,
+        ));
+
+        let proof = Proof::create(
+            &pk,
+            &self.params,
+            self.circuit.clone(),
+            &[&public_inputs.to_vec()],
+            &mut rng,
+        )?;
+        Ok(ResourceLogicVerifyingInfo {
+            vk,
+            proof,
+            public_inputs: public_inputs.into(),
+            extra_instances: vec![],
+        })
+    }
+
+    fn verify_transparently(&self) -> Result<ResourceLogicPublicInputs, TransactionError> {
+        let mut rng = OsRng;
+        let mut public_inputs = self.public_inputs.clone();
+        let rseed = RandomSeed::random(&mut rng);
+        public_inputs.extend(ResourceLogicPublicInputs::get_public_input_padding(
+            self.public_inputs.len(),
+            &rseed,
+        ));
+        let prover =
+            MockProver::<pallas::Base>::run(15, &self.circuit, vec![public_inputs.to_vec()])?;
+        prover
+            .verify()
+            .map_err(|_| TransactionError::UnsatisfiedResourceLogic)?;
+        Ok(ResourceLogicPublicInputs::from(public_inputs))
+    }
+
+    fn get_resource_logic_vk(&self) -> Result<ResourceLogicVerifyingKey, TransactionError> {
+        let vk = keygen_vk(&self.params, &self.circuit).map_err(TransactionError::Keygen)?;
+        Ok(ResourceLogicVerifyingKey::from_vk(vk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VampIRResourceLogicCircuit;
+    use crate::circuit::resource_logic_circuit::ResourceLogicVerifyingInfoTrait;
+    use num_bigint::BigInt;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use vamp_ir::halo2::synth::make_constant;
+
+    #[ignore]
+    #[test]
+    fn test_create_resource_logic_from_vamp_ir_file() {
+        let vamp_ir_circuit_file = PathBuf::from("./src/circuit/vamp_ir_circuits/pyth.pir");
+        let inputs_file = PathBuf::from("./src/circuit/vamp_ir_circuits/pyth.inputs");
+        let resource_logic_circuit =
+            VampIRResourceLogicCircuit::from_vamp_ir_file(&vamp_ir_circuit_file, &inputs_file)
+                .unwrap();
+
+        // generate proof and instance
+        let resource_logic_info = resource_logic_circuit.get_verifying_info().unwrap();
+
+        // verify the proof
+        // TODO: use the resource_logic_info.verify() instead. resource_logic_info.verify() doesn't work now because it uses the fixed RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE params.
+        resource_logic_info
+            .proof
+            .verify(
+                &resource_logic_info.vk,
+                &resource_logic_circuit.params,
+                &[resource_logic_info.public_inputs.inner()],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_create_resource_logic_from_invalid_vamp_ir_file() {
+        let invalid_vamp_ir_source =
+            VampIRResourceLogicCircuit::from_vamp_ir_source("{aaxxx", HashMap::new());
+        assert!(invalid_vamp_ir_source.is_err());
+    }
+
+    #[test]
+    fn test_create_resource_logic_with_missing_assignment() {
+        let missing_x_assignment =
+            VampIRResourceLogicCircuit::from_vamp_ir_source("x = 1;", HashMap::new());
+        assert!(missing_x_assignment.is_err());
+    }
+
+    #[test]
+    fn test_create_resource_logic_with_no_assignment() {
+        let zero_constraint = VampIRResourceLogicCircuit::from_vamp_ir_source("0;", HashMap::new());
+        assert!(zero_constraint.is_ok());
+    }
+
+    #[ignore]
+    #[test]
+    fn test_create_resource_logic_with_valid_assignment() {
+        let x_assignment_circuit = VampIRResourceLogicCircuit::from_vamp_ir_source(
+            "x = 1;",
+            HashMap::from([(String::from("x"), make_constant(BigInt::from(1)))]),
+        );
+
+        assert!(x_assignment_circuit.is_ok());
+
+        let resource_logic_circuit = x_assignment_circuit.unwrap();
+        let resource_logic_info = resource_logic_circuit.get_verifying_info().unwrap();
+
+        assert!(resource_logic_info
+            .proof
+            .verify(
+                &resource_logic_info.vk,
+                &resource_logic_circuit.params,
+                &[resource_logic_info.public_inputs.inner()]
+            )
+            .is_ok());
+    }
+
+    #[ignore]
+    #[test]
+    fn test_create_resource_logic_with_invalid_assignment() {
+        let x_assignment_circuit = VampIRResourceLogicCircuit::from_vamp_ir_source(
+            "x = 1;",
+            HashMap::from([(String::from("x"), make_constant(BigInt::from(0)))]),
+        );
+
+        assert!(x_assignment_circuit.is_ok());
+
+        let resource_logic_circuit = x_assignment_circuit.unwrap();
+        let resource_logic_info = resource_logic_circuit.get_verifying_info().unwrap();
+
+        assert!(resource_logic_info
+            .proof
+            .verify(
+                &resource_logic_info.vk,
+                &resource_logic_circuit.params,
+                &[resource_logic_info.public_inputs.inner()]
+            )
+            .is_err());
+    }
+}
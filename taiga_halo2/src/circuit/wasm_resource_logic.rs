@@ -0,0 +1,533 @@
+//! Resource logics compiled to WASM, e.g. by Juvix, proven without
+//! recompiling Taiga.
+//!
+//! The module implements the host interface:
+//! - it exports its `memory`;
+//! - it exports `alloc(len: i32) -> i32`, returning the address of a buffer of
+//!   `len` bytes, the host writes the witness there;
+//! - it exports `synthesize(witness_ptr: i32, witness_len: i32) -> i32`,
+//!   returning the address of the output: the u32 length of the encoded
+//!   `WasmConstraintSystem` followed by its bytes.
+//!
+//! The constraint system is a list of standard plonk gates
+//! `q_l * a + q_r * b + q_o * c + q_m * a * b + q_c = 0` over variables, the
+//! wires of the gates index the variables. Gates sharing a variable are
+//! copy-constrained. The public inputs are variables too, in the layout of
+//! `ResourceLogicPublicInputs`: the merkle root first, the self resource id
+//! second, and so on. The remaining public inputs are padded by the host.
+use crate::{
+    circuit::resource_logic_circuit::{
+        ResourceLogicPublicInputs, ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait,
+    },
+    constant::{
+        RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX,
+        RESOURCE_LOGIC_CIRCUIT_MAX_PARAMS_SIZE, RESOURCE_LOGIC_CIRCUIT_MIN_PARAMS_SIZE,
+        RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM,
+    },
+    error::TransactionError,
+    params::{get_params, ProvingContext},
+    proof::Proof,
+    resource::RandomSeed,
+    resource_logic_vk::ResourceLogicVerifyingKey,
+};
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance,
+    },
+    poly::Rotation,
+};
+use pasta_curves::pallas;
+use rand::{rngs::OsRng, RngCore};
+use std::fmt::{self, Display};
+use wasmi::{Config, Engine, Linker, Module, Store};
+
+/// The fuel of a run of `synthesize`, bounding the instructions it executes.
+pub const WASM_RESOURCE_LOGIC_FUEL: u64 = 1 << 32;
+
+// Rows reserved by the proving system for the blinding factors
+const WASM_RESOURCE_LOGIC_BLINDING_ROWS: usize = 8;
+
+#[derive(Debug)]
+pub enum WasmCircuitError {
+    /// The module fails to load or to run the host interface.
+    Runtime(String),
+    /// The output of the module is not a valid constraint system.
+    InvalidOutput(String),
+}
+
+impl Display for WasmCircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WasmCircuitError::Runtime(e) => write!(f, "WASM runtime error: {e}"),
+            WasmCircuitError::InvalidOutput(e) => write!(f, "Invalid WASM output: {e}"),
+        }
+    }
+}
+
+impl From<WasmCircuitError> for TransactionError {
+    fn from(e: WasmCircuitError) -> Self {
+        TransactionError::InvalidWasmResourceLogic(e.to_string())
+    }
+}
+
+fn runtime_error(e: impl Display) -> WasmCircuitError {
+    WasmCircuitError::Runtime(e.to_string())
+}
+
+/// `q_l * a + q_r * b + q_o * c + q_m * a * b + q_c = 0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasmGate {
+    /// `[q_l, q_r, q_o, q_m, q_c]`
+    pub coeffs: [pallas::Base; 5],
+    /// The variables of `[a, b, c]`.
+    pub wires: [u32; 3],
+}
+
+/// The output of a WASM resource logic.
+///
+/// Encoded in little endian as the number of variables and their values, the
+/// number of public inputs and their variables, then the number of gates and
+/// their coefficients and wires.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WasmConstraintSystem {
+    pub values: Vec<pallas::Base>,
+    pub public_inputs: Vec<u32>,
+    pub gates: Vec<WasmGate>,
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], WasmCircuitError> {
+        if self.0.len() < len {
+            return Err(WasmCircuitError::InvalidOutput(
+                "unexpected end".to_string(),
+            ));
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, WasmCircuitError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn read_base(&mut self) -> Result<pallas::Base, WasmCircuitError> {
+        let repr: [u8; 32] = self.take(32)?.try_into().unwrap();
+        Option::from(pallas::Base::from_repr(repr))
+            .ok_or_else(|| WasmCircuitError::InvalidOutput("non-canonical value".to_string()))
+    }
+
+    fn read_vec<T>(
+        &mut self,
+        mut read: impl FnMut(&mut Self) -> Result<T, WasmCircuitError>,
+    ) -> Result<Vec<T>, WasmCircuitError> {
+        let len = self.read_u32()?;
+        (0..len).map(|_| read(self)).collect()
+    }
+}
+
+impl WasmConstraintSystem {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WasmCircuitError> {
+        let mut reader = Reader(bytes);
+        let values = reader.read_vec(Reader::read_base)?;
+        let public_inputs = reader.read_vec(Reader::read_u32)?;
+        let gates = reader.read_vec(|reader| {
+            let mut coeffs = [pallas::Base::zero(); 5];
+            for coeff in coeffs.iter_mut() {
+                *coeff = reader.read_base()?;
+            }
+            let mut wires = [0u32; 3];
+            for wire in wires.iter_mut() {
+                *wire = reader.read_u32()?;
+            }
+            Ok(WasmGate { coeffs, wires })
+        })?;
+        if !reader.0.is_empty() {
+            return Err(WasmCircuitError::InvalidOutput(
+                "trailing bytes".to_string(),
+            ));
+        }
+        let cs = Self {
+            values,
+            public_inputs,
+            gates,
+        };
+        cs.check()?;
+        Ok(cs)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend((self.values.len() as u32).to_le_bytes());
+        for value in self.values.iter() {
+            bytes.extend(value.to_repr());
+        }
+        bytes.extend((self.public_inputs.len() as u32).to_le_bytes());
+        for wire in self.public_inputs.iter() {
+            bytes.extend(wire.to_le_bytes());
+        }
+        bytes.extend((self.gates.len() as u32).to_le_bytes());
+        for gate in self.gates.iter() {
+            for coeff in gate.coeffs.iter() {
+                bytes.extend(coeff.to_repr());
+            }
+            for wire in gate.wires.iter() {
+                bytes.extend(wire.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    pub(crate) fn check(&self) -> Result<(), WasmCircuitError> {
+        let in_range = |wire: &u32| (*wire as usize) < self.values.len();
+        if !self.public_inputs.iter().all(in_range)
+            || !self
+                .gates
+                .iter()
+                .all(|gate| gate.wires.iter().all(in_range))
+        {
+            return Err(WasmCircuitError::InvalidOutput(
+                "wire out of range".to_string(),
+            ));
+        }
+        if self.public_inputs.len() < RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX
+            || self.public_inputs.len() >= RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM
+        {
+            return Err(WasmCircuitError::InvalidOutput(
+                "invalid number of public inputs".to_string(),
+            ));
+        }
+        if self.rows() > 1 << RESOURCE_LOGIC_CIRCUIT_MAX_PARAMS_SIZE {
+            return Err(WasmCircuitError::InvalidOutput(
+                "too many gates".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Every gate takes a row, so does every public input outside the gates
+    fn rows(&self) -> usize {
+        self.gates.len() + self.public_inputs.len() + WASM_RESOURCE_LOGIC_BLINDING_ROWS
+    }
+
+    /// The smallest params size the constraint system fits in.
+    pub fn params_size(&self) -> u32 {
+        let mut k = RESOURCE_LOGIC_CIRCUIT_MIN_PARAMS_SIZE;
+        while 1 << k < self.rows() {
+            k += 1;
+        }
+        k
+    }
+}
+
+/// Runs the host interface of `module` on `witness` and returns the encoded
+/// constraint system.
+pub fn run_wasm_resource_logic(module: &[u8], witness: &[u8]) -> Result<Vec<u8>, WasmCircuitError> {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, module).map_err(runtime_error)?;
+    let mut store = Store::new(&engine, ());
+    store
+        .add_fuel(WASM_RESOURCE_LOGIC_FUEL)
+        .map_err(runtime_error)?;
+    // No host functions are exposed to the module
+    let linker = Linker::<()>::new(&engine);
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .map_err(runtime_error)?
+        .start(&mut store)
+        .map_err(runtime_error)?;
+    let memory = instance
+        .get_memory(&store, "memory")
+        .ok_or_else(|| runtime_error("missing memory export"))?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&store, "alloc")
+        .map_err(runtime_error)?;
+    let synthesize = instance
+        .get_typed_func::<(i32, i32), i32>(&store, "synthesize")
+        .map_err(runtime_error)?;
+
+    let witness_len = i32::try_from(witness.len()).map_err(runtime_error)?;
+    let witness_ptr = alloc.call(&mut store, witness_len).map_err(runtime_error)?;
+    memory
+        .write(&mut store, witness_ptr as u32 as usize, witness)
+        .map_err(runtime_error)?;
+    let output_ptr = synthesize
+        .call(&mut store, (witness_ptr, witness_len))
+        .map_err(runtime_error)? as u32 as usize;
+    let mut len = [0u8; 4];
+    memory
+        .read(&store, output_ptr, &mut len)
+        .map_err(runtime_error)?;
+    let mut output = vec![0u8; u32::from_le_bytes(len) as usize];
+    memory
+        .read(&store, output_ptr + 4, &mut output)
+        .map_err(runtime_error)?;
+    Ok(output)
+}
+
+#[derive(Clone, Debug)]
+pub struct WasmResourceLogicConfig {
+    advices: [Column<Advice>; 3],
+    coeffs: [Column<Fixed>; 5],
+    instance: Column<Instance>,
+}
+
+/// A resource logic given by the constraint system a WASM module outputs. The
+/// gates are fixed columns of the circuit, so the logic vk identifies them.
+#[derive(Clone, Debug, Default)]
+pub struct WasmResourceLogicCircuit {
+    pub constraint_system: WasmConstraintSystem,
+}
+
+impl WasmResourceLogicCircuit {
+    pub fn from_module(module: &[u8], witness: &[u8]) -> Result<Self, WasmCircuitError> {
+        let output = run_wasm_resource_logic(module, witness)?;
+        Ok(Self {
+            constraint_system: WasmConstraintSystem::from_bytes(&output)?,
+        })
+    }
+
+    fn get_public_inputs(&self, rng: impl RngCore) -> ResourceLogicPublicInputs {
+        let cs = &self.constraint_system;
+        let mut public_inputs: Vec<pallas::Base> = cs
+            .public_inputs
+            .iter()
+            .map(|wire| cs.values[*wire as usize])
+            .collect();
+        let rseed = RandomSeed::random(rng);
+        public_inputs.extend(ResourceLogicPublicInputs::get_public_input_padding(
+            public_inputs.len(),
+            &rseed,
+        ));
+        public_inputs.into()
+    }
+}
+
+impl Circuit<pallas::Base> for WasmResourceLogicCircuit {
+    type Config = WasmResourceLogicConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // The gates are part of the circuit, only the values are witnesses
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let advices = [(); 3].map(|_| meta.advice_column());
+        for advice in advices.iter() {
+            meta.enable_equality(*advice);
+        }
+        let coeffs = [(); 5].map(|_| meta.fixed_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        meta.create_gate("wasm gate", |meta| {
+            let [a, b, c] = advices.map(|advice| meta.query_advice(advice, Rotation::cur()));
+            let [q_l, q_r, q_o, q_m, q_c] =
+                coeffs.map(|coeff| meta.query_fixed(coeff, Rotation::cur()));
+            vec![q_l * a.clone() + q_r * b.clone() + q_o * c + q_m * a * b + q_c]
+        });
+
+        WasmResourceLogicConfig {
+            advices,
+            coeffs,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        let cs = &self.constraint_system;
+        let cells = layouter.assign_region(
+            || "wasm gates",
+            |mut region| {
+                let mut cells: Vec<Option<AssignedCell<pallas::Base, pallas::Base>>> =
+                    vec![None; cs.values.len()];
+                for (offset, gate) in cs.gates.iter().enumerate() {
+                    for (column, coeff) in config.coeffs.iter().zip(gate.coeffs) {
+                        region.assign_fixed(|| "coeff", *column, offset, || Value::known(coeff))?;
+                    }
+                    for (column, wire) in config.advices.iter().zip(gate.wires) {
+                        let wire = wire as usize;
+                        let cell = region.assign_advice(
+                            || "wire",
+                            *column,
+                            offset,
+                            || Value::known(cs.values[wire]),
+                        )?;
+                        match &cells[wire] {
+                            Some(first) => region.constrain_equal(first.cell(), cell.cell())?,
+                            None => cells[wire] = Some(cell),
+                        }
+                    }
+                }
+
+                // The public inputs outside the gates take a row with zero coefficients
+                let mut offset = cs.gates.len();
+                for wire in cs.public_inputs.iter() {
+                    let wire = *wire as usize;
+                    if cells[wire].is_none() {
+                        cells[wire] = Some(region.assign_advice(
+                            || "public input",
+                            config.advices[0],
+                            offset,
+                            || Value::known(cs.values[wire]),
+                        )?);
+                        offset += 1;
+                    }
+                }
+                Ok(cells)
+            },
+        )?;
+
+        for (row, wire) in cs.public_inputs.iter().enumerate() {
+            let cell = cells[*wire as usize].as_ref().unwrap();
+            layouter.constrain_instance(cell.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+impl ResourceLogicVerifyingInfoTrait for WasmResourceLogicCircuit {
+    fn get_verifying_info_with_context(
+        &self,
+        context: &ProvingContext,
+        rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfo, TransactionError> {
+        let params = context.params(self.constraint_system.params_size())?;
+        let vk = keygen_vk(params, self).map_err(TransactionError::Keygen)?;
+        let pk = keygen_pk(params, vk.clone(), self).map_err(TransactionError::Keygen)?;
+        let public_inputs = self.get_public_inputs(&mut *rng);
+        let proof = Proof::create(
+            &pk,
+            params,
+            self.clone(),
+            &public_inputs.with_extra_instances(&[]),
+            rng,
+        )?;
+        Ok(ResourceLogicVerifyingInfo {
+            vk,
+            proof,
+            public_inputs,
+            extra_instances: vec![],
+        })
+    }
+
+    fn verify_transparently(&self) -> Result<ResourceLogicPublicInputs, TransactionError> {
+        let public_inputs = self.get_public_inputs(OsRng);
+        let prover = MockProver::<pallas::Base>::run(
+            self.constraint_system.params_size(),
+            self,
+            vec![public_inputs.to_vec()],
+        )?;
+        prover
+            .verify()
+            .map_err(|_| TransactionError::UnsatisfiedResourceLogic)?;
+        Ok(public_inputs)
+    }
+
+    fn get_resource_logic_vk(&self) -> Result<ResourceLogicVerifyingKey, TransactionError> {
+        let params = get_params(self.constraint_system.params_size())?;
+        let vk = keygen_vk(params, self).map_err(TransactionError::Keygen)?;
+        Ok(ResourceLogicVerifyingKey::from_vk(vk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ff::Field;
+
+    // Echoes the witness: the witness is the encoded constraint system
+    const ECHO_MODULE: &str = r#"
+        (module
+            (memory (export "memory") 2)
+            (func (export "alloc") (param $len i32) (result i32)
+                i32.const 8)
+            (func (export "synthesize") (param $ptr i32) (param $len i32) (result i32)
+                (i32.store (i32.sub (local.get $ptr) (i32.const 4)) (local.get $len))
+                (i32.sub (local.get $ptr) (i32.const 4))))
+    "#;
+
+    // x * x = y, with y as the first custom public input
+    fn square_constraint_system(x: u64, y: u64) -> WasmConstraintSystem {
+        let mut rng = OsRng;
+        let mut values: Vec<pallas::Base> = (0
+            ..RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX)
+            .map(|_| pallas::Base::random(&mut rng))
+            .collect();
+        let x_wire = values.len() as u32;
+        values.push(pallas::Base::from(x));
+        values.push(pallas::Base::from(y));
+        let mut public_inputs: Vec<u32> =
+            (0..RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX as u32).collect();
+        public_inputs.push(x_wire + 1);
+        let gate = WasmGate {
+            coeffs: [
+                pallas::Base::zero(),
+                pallas::Base::zero(),
+                -pallas::Base::one(),
+                pallas::Base::one(),
+                pallas::Base::zero(),
+            ],
+            wires: [x_wire, x_wire, x_wire + 1],
+        };
+        WasmConstraintSystem {
+            values,
+            public_inputs,
+            gates: vec![gate],
+        }
+    }
+
+    #[test]
+    fn test_wasm_resource_logic() {
+        let module = wat::parse_str(ECHO_MODULE).unwrap();
+        let cs = square_constraint_system(3, 9);
+        let circuit = WasmResourceLogicCircuit::from_module(&module, &cs.to_bytes()).unwrap();
+        assert_eq!(circuit.constraint_system, cs);
+        let public_inputs = circuit.verify_transparently().unwrap();
+        assert_eq!(
+            public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX),
+            pallas::Base::from(9)
+        );
+        // Small logics are proven with the smallest params
+        assert_eq!(cs.params_size(), RESOURCE_LOGIC_CIRCUIT_MIN_PARAMS_SIZE);
+        let verifying_info = circuit.get_verifying_info().unwrap();
+        assert_eq!(
+            verifying_info.params_size(),
+            RESOURCE_LOGIC_CIRCUIT_MIN_PARAMS_SIZE
+        );
+        assert!(verifying_info.verify().is_ok());
+
+        // A wrong square doesn't satisfy the gate
+        let wrong = WasmResourceLogicCircuit {
+            constraint_system: square_constraint_system(3, 10),
+        };
+        let prover = MockProver::<pallas::Base>::run(
+            wrong.constraint_system.params_size(),
+            &wrong,
+            vec![wrong.get_public_inputs(OsRng).to_vec()],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+        assert!(matches!(
+            wrong.verify_transparently(),
+            Err(crate::error::TransactionError::UnsatisfiedResourceLogic)
+        ));
+
+        // Truncated outputs are rejected
+        let bytes = cs.to_bytes();
+        assert!(WasmConstraintSystem::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(WasmResourceLogicCircuit::from_module(&[0u8; 4], &bytes).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use crate::circuit::{blake2s::Blake2sChip, gadgets::assign_free_constant};
+use crate::constant::{ZKVM_RECEIPT_CLAIM_PERSONALIZATION, ZKVM_RECEIPT_TAG};
+use crate::zkvm_receipt::ZkvmReceipt;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, Error, Instance},
+};
+use pasta_curves::pallas;
+
+/// Computes the claim of a receipt of the `image_id` program committing
+/// `journal`, see [`ZkvmReceipt::claim_digest`]. The image id is a constant of
+/// the logic, so that the computation can't be delegated to another program.
+pub fn receipt_claim_gadget(
+    layouter: &mut impl Layouter<pallas::Base>,
+    blake2s_chip: &Blake2sChip<pallas::Base>,
+    advice: Column<Advice>,
+    image_id: &[u8; 32],
+    journal: &[AssignedCell<pallas::Base, pallas::Base>],
+) -> Result<[AssignedCell<pallas::Base, pallas::Base>; 2], Error> {
+    let mut inputs = vec![];
+    for (i, ele) in ZkvmReceipt::image_id_fields(image_id)
+        .into_iter()
+        .enumerate()
+    {
+        inputs.push(assign_free_constant(
+            layouter.namespace(|| format!("image id {i}")),
+            advice,
+            ele,
+        )?);
+    }
+    inputs.extend(journal.iter().cloned());
+    if journal.len() % 2 == 1 {
+        inputs.push(assign_free_constant(
+            layouter.namespace(|| "journal padding"),
+            advice,
+            pallas::Base::zero(),
+        )?);
+    }
+
+    let hash = blake2s_chip.process(layouter, &inputs, ZKVM_RECEIPT_CLAIM_PERSONALIZATION)?;
+    blake2s_chip.encode_result(layouter, &hash)
+}
+
+/// Publishes the claim as `[tag, claim_lo, claim_hi]` in the instance column.
+pub fn publicize_receipt_claim(
+    layouter: &mut impl Layouter<pallas::Base>,
+    advice: Column<Advice>,
+    instance: Column<Instance>,
+    claim: &[AssignedCell<pallas::Base, pallas::Base>; 2],
+) -> Result<(), Error> {
+    let tag = assign_free_constant(
+        layouter.namespace(|| "receipt tag"),
+        advice,
+        *ZKVM_RECEIPT_TAG,
+    )?;
+    layouter.constrain_instance(tag.cell(), instance, 0)?;
+    layouter.constrain_instance(claim[0].cell(), instance, 1)?;
+    layouter.constrain_instance(claim[1].cell(), instance, 2)
+}
@@ -0,0 +1,247 @@
+//! Incrementally-updatable storage of the resource commitment tree.
+//!
+//! [`MerkleStore`] records the roots of the tree after every append, so that
+//! the anchor a transaction was built against can be looked up by the tree
+//! size it had, and serves the paths of the stored commitments. Executor nodes
+//! use a `FileMerkleStore`, with the `std` feature, to keep the tree across
+//! restarts.
+use crate::{
+    constant::TAIGA_COMMITMENT_TREE_DEPTH,
+    error::TransactionError,
+    merkle_tree::{commitment_tree_path, Anchor, MerklePath, Node},
+};
+use ff::{Field, PrimeField};
+use pasta_curves::pallas;
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+const NODE_BYTES: u64 = 32;
+
+pub trait MerkleStore {
+    /// Appends a leaf and returns its position.
+    fn append(&mut self, leaf: Node) -> Result<u64, TransactionError>;
+
+    /// The number of leaves in the tree.
+    fn size(&self) -> u64;
+
+    /// The root of the tree when it had `size` leaves.
+    fn root_at(&self, size: u64) -> Option<Anchor>;
+
+    /// The position of the first occurrence of the leaf.
+    fn position(&self, leaf: &Node) -> Option<u64>;
+
+    /// The path of the leaf at `position` to the current root.
+    fn witness_at(&self, position: u64) -> Option<MerklePath>;
+
+    /// The current root.
+    fn root(&self) -> Anchor {
+        self.root_at(self.size())
+            .expect("the root of the current size is stored")
+    }
+
+    /// The path of the leaf to the current root.
+    fn witness(&self, leaf: &Node) -> Option<MerklePath> {
+        self.witness_at(self.position(leaf)?)
+    }
+}
+
+/// The commitment tree kept in memory.
+#[derive(Debug, Clone)]
+pub struct InMemoryMerkleStore {
+    leaves: Vec<Node>,
+    positions: HashMap<[u8; 32], u64>,
+    /// The last left node of every level
+    frontier: Vec<Node>,
+    /// The roots indexed by tree size
+    roots: Vec<Anchor>,
+    /// The empty node of every level
+    empty: Vec<Node>,
+}
+
+impl Default for InMemoryMerkleStore {
+    fn default() -> Self {
+        let mut empty = Vec::with_capacity(TAIGA_COMMITMENT_TREE_DEPTH + 1);
+        empty.push(Node::from(pallas::Base::zero()));
+        for level in 0..TAIGA_COMMITMENT_TREE_DEPTH {
+            empty.push(Node::combine(&empty[level], &empty[level]));
+        }
+        Self {
+            leaves: vec![],
+            positions: HashMap::new(),
+            frontier: empty[..TAIGA_COMMITMENT_TREE_DEPTH].to_vec(),
+            roots: vec![empty[TAIGA_COMMITMENT_TREE_DEPTH].into()],
+            empty,
+        }
+    }
+}
+
+impl InMemoryMerkleStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn leaves(&self) -> &[Node] {
+        &self.leaves
+    }
+}
+
+impl MerkleStore for InMemoryMerkleStore {
+    fn append(&mut self, leaf: Node) -> Result<u64, TransactionError> {
+        let position = self.leaves.len() as u64;
+        if position >> TAIGA_COMMITMENT_TREE_DEPTH != 0 {
+            return Err(TransactionError::CommitmentTreeFull);
+        }
+        // Only the path of the new leaf changes
+        let mut node = leaf;
+        for level in 0..TAIGA_COMMITMENT_TREE_DEPTH {
+            node = if (position >> level) & 1 == 1 {
+                Node::combine(&self.frontier[level], &node)
+            } else {
+                self.frontier[level] = node;
+                Node::combine(&node, &self.empty[level])
+            };
+        }
+        self.leaves.push(leaf);
+        self.positions
+            .entry(leaf.inner().to_repr())
+            .or_insert(position);
+        self.roots.push(node.into());
+        Ok(position)
+    }
+
+    fn size(&self) -> u64 {
+        self.leaves.len() as u64
+    }
+
+    fn root_at(&self, size: u64) -> Option<Anchor> {
+        self.roots.get(usize::try_from(size).ok()?).copied()
+    }
+
+    fn position(&self, leaf: &Node) -> Option<u64> {
+        self.positions.get(&leaf.inner().to_repr()).copied()
+    }
+
+    fn witness_at(&self, position: u64) -> Option<MerklePath> {
+        commitment_tree_path(&self.leaves, usize::try_from(position).ok()?)
+    }
+}
+
+/// The commitment tree persisted in a file of the appended leaves. The file
+/// is replayed when the store is opened.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FileMerkleStore {
+    file: File,
+    tree: InMemoryMerkleStore,
+}
+
+#[cfg(feature = "std")]
+impl FileMerkleStore {
+    /// Opens the store at `path`, creating it if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, TransactionError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        // Drop the leaf torn by a crash in the middle of an append
+        let len = bytes.len() as u64 / NODE_BYTES * NODE_BYTES;
+        if len != bytes.len() as u64 {
+            file.set_len(len)?;
+        }
+        let mut tree = InMemoryMerkleStore::new();
+        for chunk in bytes.chunks_exact(NODE_BYTES as usize) {
+            let leaf = crate::utils::read_base_field(&mut &chunk[..])?;
+            tree.append(Node::from(leaf))?;
+        }
+        Ok(Self { file, tree })
+    }
+
+    pub fn leaves(&self) -> &[Node] {
+        self.tree.leaves()
+    }
+}
+
+#[cfg(feature = "std")]
+impl MerkleStore for FileMerkleStore {
+    fn append(&mut self, leaf: Node) -> Result<u64, TransactionError> {
+        if self.tree.size() >> TAIGA_COMMITMENT_TREE_DEPTH != 0 {
+            return Err(TransactionError::CommitmentTreeFull);
+        }
+        self.file.write_all(&leaf.inner().to_repr())?;
+        self.file.sync_data()?;
+        self.tree.append(leaf)
+    }
+
+    fn size(&self) -> u64 {
+        self.tree.size()
+    }
+
+    fn root_at(&self, size: u64) -> Option<Anchor> {
+        self.tree.root_at(size)
+    }
+
+    fn position(&self, leaf: &Node) -> Option<u64> {
+        self.tree.position(leaf)
+    }
+
+    fn witness_at(&self, position: u64) -> Option<MerklePath> {
+        self.tree.witness_at(position)
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_merkle_store() {
+    use crate::merkle_tree::commitment_tree_root;
+    use rand::rngs::OsRng;
+    use rand::RngCore;
+
+    let mut rng = OsRng;
+    let leaves: Vec<Node> = (0..7).map(|_| Node::rand(&mut rng)).collect();
+
+    let path = std::env::temp_dir().join(format!("taiga_merkle_store_{}", rng.next_u64()));
+    let mut store = FileMerkleStore::open(&path).unwrap();
+    assert_eq!(store.root(), commitment_tree_root(&[]));
+    for (position, leaf) in leaves[..5].iter().enumerate() {
+        assert_eq!(store.append(*leaf).unwrap(), position as u64);
+    }
+    let root = store.root();
+    assert_eq!(root, commitment_tree_root(&leaves[..5]));
+
+    // The store is restored from the file
+    drop(store);
+    let mut store = FileMerkleStore::open(&path).unwrap();
+    assert_eq!(store.size(), 5);
+    assert_eq!(store.root(), root);
+    for leaf in leaves[5..].iter() {
+        store.append(*leaf).unwrap();
+    }
+
+    let mut in_memory = InMemoryMerkleStore::new();
+    for leaf in leaves.iter() {
+        in_memory.append(*leaf).unwrap();
+    }
+    assert_eq!(store.root(), in_memory.root());
+    assert_eq!(store.root(), commitment_tree_root(&leaves));
+    for size in 0..=leaves.len() {
+        assert_eq!(
+            store.root_at(size as u64).unwrap(),
+            commitment_tree_root(&leaves[..size])
+        );
+    }
+    assert!(store.root_at(leaves.len() as u64 + 1).is_none());
+    for leaf in leaves.iter() {
+        assert_eq!(store.witness(leaf).unwrap().root(*leaf), store.root());
+    }
+    assert!(store.witness(&Node::rand(&mut rng)).is_none());
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -4,15 +4,31 @@
 /// to the rules for commitment/nullifier derivation. It also requires explicit check of the presence of all
 /// other required proofs
 use crate::{
-    circuit::compliance_circuit::ComplianceCircuit,
-    constant::{PRF_EXPAND_INPUT_RESOURCE_LOGIC_CM_R, PRF_EXPAND_OUTPUT_RESOURCE_LOGIC_CM_R},
+    circuit::{compliance_circuit::ComplianceCircuit, read_circuit::ReadCircuit},
+    commitment_tree::MerkleStore,
+    constant::{
+        COMPLIANCE_ANCHOR_PUBLIC_INPUT_ROW_IDX, COMPLIANCE_CONVERSION_RULE_ROW_IDX,
+        COMPLIANCE_DELTA_CM_X_PUBLIC_INPUT_ROW_IDX, COMPLIANCE_DELTA_CM_Y_PUBLIC_INPUT_ROW_IDX,
+        COMPLIANCE_DIGEST_PERSONALIZATION, COMPLIANCE_EPOCH_ROW_IDX,
+        COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_1_ROW_IDX, COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_2_ROW_IDX,
+        COMPLIANCE_NF_PUBLIC_INPUT_ROW_IDX, COMPLIANCE_OUTPUT_CM_PUBLIC_INPUT_ROW_IDX,
+        COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_1_ROW_IDX,
+        COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_2_ROW_IDX, COMPLIANCE_PUBLIC_INPUT_NUM,
+        PRF_EXPAND_INPUT_RESOURCE_LOGIC_CM_R, PRF_EXPAND_OUTPUT_RESOURCE_LOGIC_CM_R,
+        READ_ANCHOR_PUBLIC_INPUT_ROW_IDX, READ_TAG_PUBLIC_INPUT_ROW_IDX,
+        TAIGA_COMMITMENT_TREE_DEPTH,
+    },
+    conversion::{ConversionRule, ConversionRuleId},
     delta_commitment::DeltaCommitment,
-    merkle_tree::{Anchor, MerklePath},
+    error::TransactionError,
+    merkle_tree::{Anchor, MerklePath, Node},
     nullifier::Nullifier,
     resource::{RandomSeed, Resource, ResourceCommitment},
     resource_logic_commitment::ResourceLogicCommitment,
+    witness_set::WitnessSet,
 };
-use pasta_curves::pallas;
+use blake2b_simd::Params as Blake2bParams;
+use pasta_curves::{group::ff::PrimeField, pallas};
 use rand::RngCore;
 
 #[cfg(feature = "nif")]
@@ -21,6 +37,8 @@ use rustler::NifStruct;
 #[cfg(feature = "serde")]
 use serde;
 
+#[cfg(feature = "borsh")]
+use crate::transaction::{EncodingLayout, VersionedBorsh};
 #[cfg(feature = "borsh")]
 use borsh::{BorshDeserialize, BorshSerialize};
 
@@ -42,6 +60,26 @@ pub struct CompliancePublicInputs {
     pub input_resource_logic_commitment: ResourceLogicCommitment,
     /// The commitment to output resource logic
     pub output_resource_logic_commitment: ResourceLogicCommitment,
+    /// The id of the conversion rule applied by the compliance, zero if the
+    /// input and the output resources are in the delta commitment.
+    pub conversion_rule: ConversionRuleId,
+    /// The epoch the compliance is proven at, which the executor checks
+    /// against its current epoch. The logics of timelocked resources check
+    /// their window against it, see `crate::resource::Timelock`.
+    pub epoch: u64,
+}
+
+/// The public inputs of read proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "nif", derive(NifStruct))]
+#[cfg_attr(feature = "nif", module = "Taiga.Read.PublicInputs")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReadPublicInputs {
+    /// The root of the resource commitment Merkle tree.
+    pub anchor: Anchor,
+    /// The tag of the read resource in the resource tree of the ptx, see
+    /// `Resource::get_read_tag`.
+    pub tag: pallas::Base,
 }
 
 /// The information to build CompliancePublicInputs and ComplianceCircuit.
@@ -56,44 +94,130 @@ pub struct ComplianceInfo {
     // rseed is to generate the randomness of the delta commitment and resource
     // logic commitments
     rseed: RandomSeed,
+    conversion: Option<ConversionRule>,
+    epoch: u64,
 }
 
 impl CompliancePublicInputs {
     pub fn to_instance(&self) -> Vec<pallas::Base> {
-        let input_resource_logic_commitment =
+        let input_resource_logic_commitment: [pallas::Base; 2] =
             self.input_resource_logic_commitment.to_public_inputs();
-        let output_resource_logic_commitment =
+        let output_resource_logic_commitment: [pallas::Base; 2] =
             self.output_resource_logic_commitment.to_public_inputs();
-        vec![
-            self.nf.inner(),
-            self.anchor.inner(),
-            self.cm.inner(),
-            self.delta.get_x(),
-            self.delta.get_y(),
-            input_resource_logic_commitment[0],
-            input_resource_logic_commitment[1],
-            output_resource_logic_commitment[0],
-            output_resource_logic_commitment[1],
-        ]
+        let mut instance = vec![pallas::Base::zero(); COMPLIANCE_PUBLIC_INPUT_NUM];
+        instance[COMPLIANCE_NF_PUBLIC_INPUT_ROW_IDX] = self.nf.inner();
+        instance[COMPLIANCE_ANCHOR_PUBLIC_INPUT_ROW_IDX] = self.anchor.inner();
+        instance[COMPLIANCE_OUTPUT_CM_PUBLIC_INPUT_ROW_IDX] = self.cm.inner();
+        instance[COMPLIANCE_DELTA_CM_X_PUBLIC_INPUT_ROW_IDX] = self.delta.get_x();
+        instance[COMPLIANCE_DELTA_CM_Y_PUBLIC_INPUT_ROW_IDX] = self.delta.get_y();
+        instance[COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_1_ROW_IDX] = input_resource_logic_commitment[0];
+        instance[COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_2_ROW_IDX] = input_resource_logic_commitment[1];
+        instance[COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_1_ROW_IDX] =
+            output_resource_logic_commitment[0];
+        instance[COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_2_ROW_IDX] =
+            output_resource_logic_commitment[1];
+        instance[COMPLIANCE_CONVERSION_RULE_ROW_IDX] = self.conversion_rule.inner();
+        instance[COMPLIANCE_EPOCH_ROW_IDX] = pallas::Base::from(self.epoch);
+        instance
+    }
+
+    /// The inverse of `to_instance`. Fails if the instance has the wrong length,
+    /// the delta commitment is not on the curve or the epoch doesn't fit in 64
+    /// bits.
+    pub fn from_instance(instance: &[pallas::Base]) -> Option<Self> {
+        if instance.len() != COMPLIANCE_PUBLIC_INPUT_NUM {
+            return None;
+        }
+        let epoch = instance[COMPLIANCE_EPOCH_ROW_IDX].to_repr();
+        if epoch[8..].iter().any(|byte| *byte != 0) {
+            return None;
+        }
+        let delta = Option::from(DeltaCommitment::from_coordinates(
+            instance[COMPLIANCE_DELTA_CM_X_PUBLIC_INPUT_ROW_IDX],
+            instance[COMPLIANCE_DELTA_CM_Y_PUBLIC_INPUT_ROW_IDX],
+        ))?;
+        Some(Self {
+            anchor: instance[COMPLIANCE_ANCHOR_PUBLIC_INPUT_ROW_IDX].into(),
+            nf: instance[COMPLIANCE_NF_PUBLIC_INPUT_ROW_IDX].into(),
+            cm: instance[COMPLIANCE_OUTPUT_CM_PUBLIC_INPUT_ROW_IDX].into(),
+            delta,
+            input_resource_logic_commitment: ResourceLogicCommitment::from_public_inputs(&[
+                instance[COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_1_ROW_IDX],
+                instance[COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_2_ROW_IDX],
+            ]),
+            output_resource_logic_commitment: ResourceLogicCommitment::from_public_inputs(&[
+                instance[COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_1_ROW_IDX],
+                instance[COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_2_ROW_IDX],
+            ]),
+            conversion_rule: instance[COMPLIANCE_CONVERSION_RULE_ROW_IDX].into(),
+            epoch: u64::from_le_bytes(epoch[..8].try_into().unwrap()),
+        })
+    }
+
+    /// A stable reference to the compliance: the Blake2b digest of the named
+    /// fields in the order of their declaration, independent of the layout of
+    /// the instance.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut h = Blake2bParams::new()
+            .hash_length(32)
+            .personal(COMPLIANCE_DIGEST_PERSONALIZATION)
+            .to_state();
+        h.update(&self.anchor.to_bytes());
+        h.update(&self.nf.to_bytes());
+        h.update(&self.cm.to_bytes());
+        h.update(&self.delta.to_bytes());
+        h.update(&self.input_resource_logic_commitment.to_bytes());
+        h.update(&self.output_resource_logic_commitment.to_bytes());
+        h.update(&self.conversion_rule.to_bytes());
+        h.update(&self.epoch.to_le_bytes());
+        h.finalize().as_bytes().try_into().unwrap()
     }
 }
 
 #[cfg(feature = "borsh")]
 impl BorshSerialize for CompliancePublicInputs {
     fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.serialize_versioned(writer, &EncodingLayout::current())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for CompliancePublicInputs {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Self::deserialize_versioned(reader, &EncodingLayout::current())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl VersionedBorsh for CompliancePublicInputs {
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<()> {
         writer.write_all(&self.anchor.to_bytes())?;
         writer.write_all(&self.nf.to_bytes())?;
         writer.write_all(&self.cm.to_bytes())?;
         writer.write_all(&self.delta.to_bytes())?;
         writer.write_all(&self.input_resource_logic_commitment.to_bytes())?;
         writer.write_all(&self.output_resource_logic_commitment.to_bytes())?;
+        if layout.conversion {
+            writer.write_all(&self.conversion_rule.to_bytes())?;
+        } else if self.conversion_rule != ConversionRuleId::default() {
+            return Err(EncodingLayout::unsupported("conversion rules"));
+        }
+        if layout.epoch {
+            writer.write_all(&self.epoch.to_le_bytes())?;
+        } else if self.epoch != 0 {
+            return Err(EncodingLayout::unsupported("epochs"));
+        }
         Ok(())
     }
-}
 
-#[cfg(feature = "borsh")]
-impl BorshDeserialize for CompliancePublicInputs {
-    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+    fn deserialize_versioned<R: std::io::Read>(
+        reader: &mut R,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<Self> {
         use std::io;
         let anchor_bytes = <[u8; 32]>::deserialize_reader(reader)?;
         let anchor = Option::from(Anchor::from_bytes(anchor_bytes))
@@ -113,6 +237,14 @@ impl BorshDeserialize for CompliancePublicInputs {
         let output_resource_logic_commitment_bytes = <[u8; 32]>::deserialize_reader(reader)?;
         let output_resource_logic_commitment =
             ResourceLogicCommitment::from_bytes(output_resource_logic_commitment_bytes);
+        let conversion_rule = match layout.conversion {
+            true => ConversionRuleId::deserialize_reader(reader)?,
+            false => ConversionRuleId::default(),
+        };
+        let epoch = match layout.epoch {
+            true => u64::deserialize_reader(reader)?,
+            false => 0,
+        };
 
         Ok(CompliancePublicInputs {
             anchor,
@@ -121,6 +253,62 @@ impl BorshDeserialize for CompliancePublicInputs {
             delta,
             input_resource_logic_commitment,
             output_resource_logic_commitment,
+            conversion_rule,
+            epoch,
+        })
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl VersionedBorsh for ComplianceInfo {
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<()> {
+        self.input_resource.serialize(writer)?;
+        self.input_merkle_path.serialize(writer)?;
+        self.input_anchor.serialize(writer)?;
+        self.output_resource.serialize(writer)?;
+        self.rseed.serialize(writer)?;
+        if layout.conversion {
+            self.conversion.serialize(writer)?;
+        } else if self.conversion.is_some() {
+            return Err(EncodingLayout::unsupported("conversion rules"));
+        }
+        if layout.epoch {
+            self.epoch.serialize(writer)?;
+        } else if self.epoch != 0 {
+            return Err(EncodingLayout::unsupported("epochs"));
+        }
+        Ok(())
+    }
+
+    fn deserialize_versioned<R: std::io::Read>(
+        reader: &mut R,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<Self> {
+        let input_resource = Resource::deserialize_reader(reader)?;
+        let input_merkle_path = MerklePath::deserialize_reader(reader)?;
+        let input_anchor = Anchor::deserialize_reader(reader)?;
+        let output_resource = Resource::deserialize_reader(reader)?;
+        let rseed = RandomSeed::deserialize_reader(reader)?;
+        let conversion = match layout.conversion {
+            true => Option::<ConversionRule>::deserialize_reader(reader)?,
+            false => None,
+        };
+        let epoch = match layout.epoch {
+            true => u64::deserialize_reader(reader)?,
+            false => 0,
+        };
+        Ok(Self {
+            input_resource,
+            input_merkle_path,
+            input_anchor,
+            output_resource,
+            rseed,
+            conversion,
+            epoch,
         })
     }
 }
@@ -147,9 +335,38 @@ impl ComplianceInfo {
             input_resource,
             input_merkle_path,
             input_anchor,
-            output_resource: *output_resource,
+            output_resource: output_resource.clone(),
             rseed: RandomSeed::random(&mut rng),
+            conversion: None,
+            epoch: 0,
+        }
+    }
+
+    /// Converts the input resource into the output resource with a rule,
+    /// instead of balancing them in the delta commitment. The transaction is
+    /// only accepted if the issuer of the target kind authorized the rule.
+    pub fn with_conversion(mut self, rule: ConversionRule) -> Result<Self, TransactionError> {
+        if !rule.converts(&self.input_resource, &self.output_resource) {
+            return Err(TransactionError::InvalidConversion);
         }
+        self.conversion = Some(rule);
+        Ok(self)
+    }
+
+    pub fn get_conversion(&self) -> Option<&ConversionRule> {
+        self.conversion.as_ref()
+    }
+
+    /// Proves the compliance at `epoch`, the current epoch of the executor
+    /// the transaction is sent to. The compliances are proven at epoch zero by
+    /// default.
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    pub fn get_epoch(&self) -> u64 {
+        self.epoch
     }
 
     // Get the randomness of delta commitment
@@ -174,27 +391,73 @@ impl ComplianceInfo {
         self.input_resource.calculate_root(&self.input_merkle_path)
     }
 
-    // Get delta commitment
+    // Get delta commitment, only the blind if the resources are converted
     pub fn get_delta_commitment(&self, blind_r: &pallas::Scalar) -> DeltaCommitment {
-        DeltaCommitment::commit(&self.input_resource, &self.output_resource, blind_r)
+        match self.conversion {
+            Some(_) => DeltaCommitment::from_blind(blind_r),
+            None => DeltaCommitment::commit(&self.input_resource, &self.output_resource, blind_r),
+        }
+    }
+
+    pub fn get_input_resource(&self) -> &Resource {
+        &self.input_resource
+    }
+
+    pub fn get_output_resource(&self) -> &Resource {
+        &self.output_resource
+    }
+
+    // Check the compliance on the field values, without the compliance circuit.
+    // The nullifier, the commitment and the delta are recomputed by the
+    // transparent executor, only the relation between the resources is left.
+    pub fn verify_transparently(&self) -> Result<(), TransactionError> {
+        let nf = self
+            .input_resource
+            .get_nf()
+            .ok_or(TransactionError::MissingTransparentResourceNullifierKey)?;
+        if self.output_resource.nonce != nf {
+            return Err(TransactionError::InvalidOutputResourceNonce);
+        }
+        if let Some(rule) = self.conversion {
+            if !rule.converts(&self.input_resource, &self.output_resource) {
+                return Err(TransactionError::InvalidConversion);
+            }
+        }
+        Ok(())
     }
 
     pub fn get_input_resource_nullifier(&self) -> Nullifier {
         self.input_resource.get_nf().unwrap()
     }
 
+    pub fn get_input_resource_cm(&self) -> ResourceCommitment {
+        self.input_resource.commitment()
+    }
+
     pub fn get_output_resource_cm(&self) -> ResourceCommitment {
         self.output_resource.commitment()
     }
 
     pub fn build(&self) -> (CompliancePublicInputs, ComplianceCircuit) {
-        let nf = self.get_input_resource_nullifier();
+        self.build_with_witnesses(&mut WitnessSet::new())
+    }
+
+    /// Same as `build`, but takes the nf and cm from the witnesses shared
+    /// within the ptx.
+    pub fn build_with_witnesses(
+        &self,
+        witnesses: &mut WitnessSet,
+    ) -> (CompliancePublicInputs, ComplianceCircuit) {
+        let nf = witnesses
+            .get_or_derive(&self.input_resource)
+            .nf
+            .expect("the input resource must contain the nullifier key");
         assert_eq!(
             nf, self.output_resource.nonce,
             "The nf of input resource must be equal to the nonce of output resource"
         );
 
-        let cm = self.get_output_resource_cm();
+        let cm = witnesses.get_or_derive(&self.output_resource).cm;
 
         let rcv = self.get_rcv();
         let delta = self.get_delta_commitment(&rcv);
@@ -218,28 +481,242 @@ impl ComplianceInfo {
             delta,
             input_resource_logic_commitment,
             output_resource_logic_commitment,
+            conversion_rule: self
+                .conversion
+                .map_or(ConversionRuleId::default(), |rule| rule.id()),
+            epoch: self.epoch,
         };
 
         let compliance_circuit = ComplianceCircuit {
-            input_resource: self.input_resource,
+            input_resource: self.input_resource.clone(),
             merkle_path: self.input_merkle_path.inner().try_into().unwrap(),
-            output_resource: self.output_resource,
+            output_resource: self.output_resource.clone(),
             rcv,
             input_resource_logic_cm_r,
             output_resource_logic_cm_r,
+            conversion: self.conversion,
+            epoch: self.epoch,
         };
 
         (compliance, compliance_circuit)
     }
 }
 
+impl ReadPublicInputs {
+    pub fn to_instance(&self) -> Vec<pallas::Base> {
+        let mut instance = vec![pallas::Base::zero(); 2];
+        instance[READ_TAG_PUBLIC_INPUT_ROW_IDX] = self.tag;
+        instance[READ_ANCHOR_PUBLIC_INPUT_ROW_IDX] = self.anchor.inner();
+        instance
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for ReadPublicInputs {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.anchor.to_bytes())?;
+        writer.write_all(&self.tag.to_repr())?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for ReadPublicInputs {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        use std::io;
+        let anchor_bytes = <[u8; 32]>::deserialize_reader(reader)?;
+        let anchor = Option::from(Anchor::from_bytes(anchor_bytes))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "anchor not in field"))?;
+        let tag = crate::utils::read_base_field(reader)?;
+        Ok(ReadPublicInputs { anchor, tag })
+    }
+}
+
+/// The third category of resources of a ptx, next to the input and the output
+/// resources of the compliances: a resource read by the ptx, e.g. an oracle or
+/// a shared config resource. Its existence is proven against the anchor
+/// without consuming it, no nullifier is published and no resource is created,
+/// see `ReadCircuit`. It carries no quantity to the delta commitments.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct ReadInfo {
+    resource: Resource,
+    merkle_path: MerklePath,
+    anchor: Anchor,
+}
+
+impl ReadInfo {
+    // The anchor is calculated from the resource and the path unless a custom
+    // anchor is given. The resource must contain the nullifier key.
+    pub fn new(resource: Resource, merkle_path: MerklePath, custom_anchor: Option<Anchor>) -> Self {
+        let anchor = match custom_anchor {
+            Some(anchor) => anchor,
+            None => resource.calculate_root(&merkle_path),
+        };
+        Self {
+            resource,
+            merkle_path,
+            anchor,
+        }
+    }
+
+    pub fn get_resource(&self) -> &Resource {
+        &self.resource
+    }
+
+    pub fn get_anchor(&self) -> Anchor {
+        self.anchor
+    }
+
+    pub fn build(&self) -> (ReadPublicInputs, ReadCircuit) {
+        let tag = self
+            .resource
+            .get_read_tag()
+            .expect("the read resource must contain the nullifier key");
+        let public_inputs = ReadPublicInputs {
+            anchor: self.anchor,
+            tag,
+        };
+        let circuit = ReadCircuit {
+            resource: self.resource.clone(),
+            merkle_path: self.merkle_path.inner().try_into().unwrap(),
+        };
+        (public_inputs, circuit)
+    }
+}
+
+/// Builds a compliance from the state of a node, for executor integrators: the
+/// path of the input resource comes from the commitment tree, and the witness
+/// is checked before proving, so that an inconsistent state fails with an
+/// error instead of an unsatisfied circuit.
+#[derive(Debug, Clone)]
+pub struct ComplianceWitnessBuilder {
+    input_resource: Resource,
+    input_merkle_path: MerklePath,
+    anchor: Anchor,
+    output_resource: Resource,
+    conversion: Option<ConversionRule>,
+    epoch: u64,
+}
+
+impl ComplianceWitnessBuilder {
+    /// Takes the path of the input resource to the anchor, e.g. as served by
+    /// a node. The anchor of an ephemeral input resource is not checked.
+    pub fn new(
+        input_resource: Resource,
+        input_merkle_path: MerklePath,
+        anchor: Anchor,
+        output_resource: Resource,
+    ) -> Self {
+        Self {
+            input_resource,
+            input_merkle_path,
+            anchor,
+            output_resource,
+            conversion: None,
+            epoch: 0,
+        }
+    }
+
+    /// Takes the path of the input resource to the current root of the
+    /// commitment tree.
+    pub fn from_store(
+        input_resource: Resource,
+        store: &impl MerkleStore,
+        output_resource: Resource,
+    ) -> Result<Self, TransactionError> {
+        let input_merkle_path = store
+            .witness(&Node::from(&input_resource))
+            .ok_or(TransactionError::UnknownResourceCommitment)?;
+        Ok(Self::new(
+            input_resource,
+            input_merkle_path,
+            store.root(),
+            output_resource,
+        ))
+    }
+
+    pub fn with_conversion(mut self, rule: ConversionRule) -> Self {
+        self.conversion = Some(rule);
+        self
+    }
+
+    /// The current epoch of the node, see `ComplianceInfo::with_epoch`.
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// Checks the witness and derives the nonce of the output resource from
+    /// the nullifier of the input resource.
+    pub fn build_info<R: RngCore>(&self, rng: R) -> Result<ComplianceInfo, TransactionError> {
+        if self.input_resource.get_nf().is_none() {
+            return Err(TransactionError::MissingNullifierKey);
+        }
+        if self.input_merkle_path.inner().len() != TAIGA_COMMITMENT_TREE_DEPTH
+            || (!self.input_resource.is_ephemeral
+                && self.input_resource.calculate_root(&self.input_merkle_path) != self.anchor)
+        {
+            return Err(TransactionError::InvalidComplianceAnchor);
+        }
+
+        let mut output_resource = self.output_resource.clone();
+        let mut info = ComplianceInfo::new(
+            self.input_resource.clone(),
+            self.input_merkle_path.clone(),
+            Some(self.anchor),
+            &mut output_resource,
+            rng,
+        )
+        .with_epoch(self.epoch);
+        if let Some(rule) = self.conversion {
+            info = info.with_conversion(rule)?;
+        }
+        info.verify_transparently()?;
+        Ok(info)
+    }
+
+    /// The public inputs and the circuit of the compliance, ready to prove.
+    pub fn build<R: RngCore>(
+        &self,
+        rng: R,
+    ) -> Result<(CompliancePublicInputs, ComplianceCircuit), TransactionError> {
+        Ok(self.build_info(rng)?.build())
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
-    use super::ComplianceInfo;
+    use super::{ComplianceInfo, CompliancePublicInputs, ComplianceWitnessBuilder};
     use crate::constant::TAIGA_COMMITMENT_TREE_DEPTH;
     use crate::merkle_tree::MerklePath;
-    use crate::resource::tests::random_resource;
-    use rand::RngCore;
+    use crate::nullifier::tests::random_nullifier_key;
+    use crate::resource::{tests::random_resource, Resource};
+    use proptest::prelude::*;
+    use rand::{rngs::OsRng, rngs::StdRng, RngCore, SeedableRng};
+
+    /// The compliance of the resources, with the randomness drawn from `seed`.
+    /// The input resource is given a nullifier key if it lacks one.
+    pub fn compliance_info(mut input: Resource, mut output: Resource, seed: u64) -> ComplianceInfo {
+        let mut rng = StdRng::seed_from_u64(seed);
+        if input.get_nk().is_none() {
+            input.nk_container = random_nullifier_key(&mut rng).into();
+        }
+        let input_merkle_path = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
+        ComplianceInfo::new(input, input_merkle_path, None, &mut output, &mut rng)
+    }
+
+    impl Arbitrary for ComplianceInfo {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (any::<Resource>(), any::<Resource>(), any::<u64>())
+                .prop_map(|(input, output, seed)| compliance_info(input, output, seed))
+                .boxed()
+        }
+    }
 
     pub fn random_compliance_info<R: RngCore>(mut rng: R) -> ComplianceInfo {
         let input_resource = random_resource(&mut rng);
@@ -253,4 +730,175 @@ pub mod tests {
             &mut rng,
         )
     }
+
+    #[test]
+    fn test_compliance_public_inputs() {
+        use crate::constant::COMPLIANCE_EPOCH_ROW_IDX;
+        use pasta_curves::pallas;
+
+        let mut rng = OsRng;
+        let (public_inputs, _) = random_compliance_info(&mut rng).build();
+        let instance = public_inputs.to_instance();
+        let decoded = CompliancePublicInputs::from_instance(&instance).unwrap();
+        assert_eq!(decoded.to_instance(), instance);
+        assert_eq!(decoded.digest(), public_inputs.digest());
+
+        let (other_public_inputs, _) = random_compliance_info(&mut rng).build();
+        assert_ne!(other_public_inputs.digest(), public_inputs.digest());
+        assert!(CompliancePublicInputs::from_instance(&instance[1..]).is_none());
+
+        // The epoch is a public input, and must fit in 64 bits
+        let (public_inputs, _) = random_compliance_info(&mut rng).with_epoch(7).build();
+        let mut instance = public_inputs.to_instance();
+        let decoded = CompliancePublicInputs::from_instance(&instance).unwrap();
+        assert_eq!(decoded.epoch, 7);
+        instance[COMPLIANCE_EPOCH_ROW_IDX] = -pallas::Base::one();
+        assert!(CompliancePublicInputs::from_instance(&instance).is_none());
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_compliance_public_inputs_versioned_borsh() {
+        use crate::transaction::{EncodingLayout, VersionedBorsh};
+
+        let mut rng = OsRng;
+        let (public_inputs, _) = random_compliance_info(&mut rng).build();
+        let bytes = borsh::to_vec(&public_inputs).unwrap();
+        // The conversion rule is added in version 2, the epoch in version 4
+        for (version, len) in [(1, 6 * 32), (2, 7 * 32), (4, 7 * 32 + 8), (6, 7 * 32 + 8)] {
+            let layout = EncodingLayout::of(version).unwrap();
+            let mut encoded = vec![];
+            public_inputs
+                .serialize_versioned(&mut encoded, &layout)
+                .unwrap();
+            assert_eq!(encoded.len(), len);
+            let decoded =
+                CompliancePublicInputs::deserialize_versioned(&mut encoded.as_slice(), &layout)
+                    .unwrap();
+            assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+        }
+
+        // A previous version can't encode an epoch
+        let (public_inputs, _) = random_compliance_info(&mut rng).with_epoch(7).build();
+        assert!(public_inputs
+            .serialize_versioned(&mut vec![], &EncodingLayout::of(3).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_compliance_witness_builder() {
+        use crate::circuit::compliance_circuit::ComplianceCircuit;
+        use crate::commitment_tree::{InMemoryMerkleStore, MerkleStore};
+        use crate::constant::COMPLIANCE_CIRCUIT_PARAMS_SIZE;
+        use crate::error::TransactionError;
+        use crate::merkle_tree::{Anchor, Node};
+        use halo2_proofs::dev::MockProver;
+        use pasta_curves::pallas;
+
+        let mut rng = OsRng;
+        let input_resource = random_resource(&mut rng);
+        let output_resource = random_resource(&mut rng);
+        let mut store = InMemoryMerkleStore::new();
+        store
+            .append(Node::from(&random_resource(&mut rng)))
+            .unwrap();
+        store.append(Node::from(&input_resource)).unwrap();
+
+        let builder = ComplianceWitnessBuilder::from_store(
+            input_resource.clone(),
+            &store,
+            output_resource.clone(),
+        )
+        .unwrap();
+        let (public_inputs, circuit): (_, ComplianceCircuit) = builder.build(&mut rng).unwrap();
+        assert_eq!(public_inputs.anchor, store.root());
+        assert_eq!(
+            circuit.output_resource.nonce,
+            input_resource.get_nf().unwrap()
+        );
+        let prover = MockProver::<pallas::Base>::run(
+            COMPLIANCE_CIRCUIT_PARAMS_SIZE,
+            &circuit,
+            vec![public_inputs.to_instance()],
+        )
+        .unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The resource must be in the tree, and its path lead to the anchor
+        assert!(matches!(
+            ComplianceWitnessBuilder::from_store(
+                output_resource.clone(),
+                &store,
+                output_resource.clone()
+            ),
+            Err(TransactionError::UnknownResourceCommitment)
+        ));
+        let path = store.witness_at(1).unwrap();
+        let stale = ComplianceWitnessBuilder::new(
+            input_resource.clone(),
+            path,
+            Anchor::from(pallas::Base::one()),
+            output_resource.clone(),
+        );
+        assert!(matches!(
+            stale.build(&mut rng),
+            Err(TransactionError::InvalidComplianceAnchor)
+        ));
+
+        // The input resource must hold its nullifier key
+        let mut watched_resource = input_resource;
+        watched_resource.nk_container = watched_resource
+            .nk_container
+            .expose_secret()
+            .to_commitment()
+            .into();
+        let builder = ComplianceWitnessBuilder::new(
+            watched_resource,
+            store.witness_at(1).unwrap(),
+            store.root(),
+            output_resource,
+        );
+        assert!(matches!(
+            builder.build(&mut rng),
+            Err(TransactionError::MissingNullifierKey)
+        ));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_compliance_info_borsh_round_trip(info: ComplianceInfo) {
+            let bytes = borsh::to_vec(&info).unwrap();
+            let decoded: ComplianceInfo = borsh::from_slice(&bytes).unwrap();
+            prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+        }
+
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_compliance_info_versioned_borsh(info: ComplianceInfo) {
+            use crate::transaction::{EncodingLayout, VersionedBorsh};
+            // The current layout is the borsh one
+            let mut bytes = vec![];
+            info.serialize_versioned(&mut bytes, &EncodingLayout::current()).unwrap();
+            prop_assert_eq!(&bytes, &borsh::to_vec(&info).unwrap());
+
+            // Version 1 has neither the conversion rule nor the epoch
+            let layout = EncodingLayout::of(1).unwrap();
+            let mut v1 = vec![];
+            info.serialize_versioned(&mut v1, &layout).unwrap();
+            prop_assert_eq!(v1.len(), bytes.len() - 1 - 8);
+            let decoded = ComplianceInfo::deserialize_versioned(&mut v1.as_slice(), &layout).unwrap();
+            prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_compliance_info_serde_round_trip(info: ComplianceInfo) {
+            let json = serde_json::to_string(&info).unwrap();
+            let decoded: ComplianceInfo = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+        }
+    }
 }
@@ -1,4 +1,7 @@
 use crate::circuit::compliance_circuit::ComplianceCircuit;
+use crate::circuit::read_circuit::ReadCircuit;
+use crate::circuit::reserve_circuit::ReserveCircuit;
+use crate::circuit::spent_circuit::SpentCircuit;
 use crate::utils::to_field_elements;
 use group::Group;
 use halo2_gadgets::{
@@ -19,28 +22,42 @@ use lazy_static::lazy_static;
 use pasta_curves::{group::Curve, pallas, vesta};
 use std::collections::HashMap;
 
-/// SWU hash-to-curve personalization for the resource commitment generator
-pub const RESOURCE_COMMITMENT_PERSONALIZATION: &str = "Taiga-NoteCommit";
-
-pub const TRANSACTION_BINDING_HASH_PERSONALIZATION: &[u8; 16] = b"TxBindingSigHash";
-
-pub const RESOURCE_LOGIC_COMMITMENT_PERSONALIZATION: &[u8; 8] = b"VPCommit";
+pub use crate::domains::{
+    AUXILIARY_DELTA_PERSONALIZATION, COMPLIANCE_DIGEST_PERSONALIZATION,
+    CONVERSION_SIG_H_STAR_PERSONALIZATION, DIVERSIFIER_PERSONALIZATION,
+    FEE_RESOURCE_PERSONALIZATION, HISTORY_LOG_PERSONALIZATION, KEY_CHILD_PERSONALIZATION,
+    KEY_MASTER_PERSONALIZATION, LINKABILITY_TAG_PERSONALIZATION, METHOD_CALL_PERSONALIZATION,
+    PARAMS_DIGEST_PERSONALIZATION, POSEIDON_TRANSCRIPT_PERSONALIZATION,
+    PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_1_CM_R, PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_2_CM_R,
+    PRF_EXPAND_ENCRYPTION_SK, PRF_EXPAND_INPUT_RESOURCE_LOGIC_CM_R, PRF_EXPAND_NK,
+    PRF_EXPAND_OUTPUT_RESOURCE_LOGIC_CM_R, PRF_EXPAND_PERSONALIZATION, PRF_EXPAND_PSI,
+    PRF_EXPAND_PUBLIC_INPUT_PADDING, PRF_EXPAND_RCM, PRF_EXPAND_VCM_R,
+    RESOURCE_COMMITMENT_PERSONALIZATION, RESOURCE_ENCRYPTION_PERSONALIZATION,
+    RESOURCE_LOGIC_COMMITMENT_PERSONALIZATION, SWEEP_NK_PERSONALIZATION,
+    TRANSACTION_BINDING_HASH_PERSONALIZATION, TRANSACTION_ID_PERSONALIZATION,
+    TRANSACTION_PROOFS_HASH_PERSONALIZATION, VALUE_BASE_DOMAIN_POSTFIX,
+    ZKVM_RECEIPT_CLAIM_PERSONALIZATION,
+};
 
-pub const PRF_EXPAND_PERSONALIZATION: &[u8; 16] = b"Taiga_ExpandSeed";
 lazy_static! {
     pub static ref PRF_EXPAND_PERSONALIZATION_TO_FIELD: pallas::Base =
         to_field_elements(PRF_EXPAND_PERSONALIZATION)[0];
+    pub static ref AUXILIARY_DELTA_TAG: pallas::Base =
+        to_field_elements(AUXILIARY_DELTA_PERSONALIZATION.as_bytes())[0];
+    pub static ref ZKVM_RECEIPT_TAG: pallas::Base =
+        to_field_elements(ZKVM_RECEIPT_CLAIM_PERSONALIZATION)[0];
+    pub static ref LINKABILITY_TAG_DOMAIN: pallas::Base =
+        to_field_elements(LINKABILITY_TAG_PERSONALIZATION.as_bytes())[0];
+    pub static ref METHOD_CALL_DOMAIN: pallas::Base =
+        to_field_elements(METHOD_CALL_PERSONALIZATION.as_bytes())[0];
+    pub static ref SWEEP_NK: pallas::Base =
+        to_field_elements(SWEEP_NK_PERSONALIZATION.as_bytes())[0];
+    pub static ref RESOURCE_ENCRYPTION_TAG: pallas::Base =
+        to_field_elements(RESOURCE_ENCRYPTION_PERSONALIZATION.as_bytes())[0];
+    pub static ref FEE_RESOURCE_LABEL: pallas::Base =
+        to_field_elements(FEE_RESOURCE_PERSONALIZATION.as_bytes())[0];
 }
 
-pub const PRF_EXPAND_PSI: u8 = 0;
-pub const PRF_EXPAND_RCM: u8 = 1;
-pub const PRF_EXPAND_PUBLIC_INPUT_PADDING: u8 = 2;
-pub const PRF_EXPAND_VCM_R: u8 = 3;
-pub const PRF_EXPAND_INPUT_RESOURCE_LOGIC_CM_R: u8 = 4;
-pub const PRF_EXPAND_OUTPUT_RESOURCE_LOGIC_CM_R: u8 = 5;
-pub const PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_1_CM_R: u8 = 6;
-pub const PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_2_CM_R: u8 = 7;
-
 /// Commitment merkle tree depth
 pub const TAIGA_COMMITMENT_TREE_DEPTH: usize = 32;
 
@@ -48,6 +65,10 @@ pub const TAIGA_COMMITMENT_TREE_DEPTH: usize = 32;
 pub const TAIGA_RESOURCE_TREE_DEPTH: usize = 4;
 /// resource merkle tree leaves num
 pub const TAIGA_RESOURCE_TREE_LEAVES_NUM: usize = 1 << TAIGA_RESOURCE_TREE_DEPTH;
+/// The max number of compliances in a ptx: the resource tree holds the input nf
+/// and the output cm of every compliance. Raising the tree depth raises it
+/// without changing the structure of the compliance circuit.
+pub const MAX_PTX_COMPLIANCE_NUM: usize = TAIGA_RESOURCE_TREE_LEAVES_NUM / 2;
 
 pub const BASE_BITS_NUM: usize = 255;
 
@@ -60,10 +81,31 @@ pub const COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_1_ROW_IDX: usize = 5;
 pub const COMPLIANCE_INPUT_RESOURCE_LOGIC_CM_2_ROW_IDX: usize = 6;
 pub const COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_1_ROW_IDX: usize = 7;
 pub const COMPLIANCE_OUTPUT_RESOURCE_LOGIC_CM_2_ROW_IDX: usize = 8;
+pub const COMPLIANCE_CONVERSION_RULE_ROW_IDX: usize = 9;
+pub const COMPLIANCE_EPOCH_ROW_IDX: usize = 10;
+pub const COMPLIANCE_PUBLIC_INPUT_NUM: usize = 11;
+
+// Reserve circuit
+pub const RESERVE_CIRCUIT_RESOURCE_NUM: usize = 4;
+pub const RESERVE_ANCHOR_PUBLIC_INPUT_ROW_IDX: usize = 0;
+pub const RESERVE_LOGIC_PUBLIC_INPUT_ROW_IDX: usize = 1;
+pub const RESERVE_LABEL_PUBLIC_INPUT_ROW_IDX: usize = 2;
+pub const RESERVE_MIN_AMOUNT_PUBLIC_INPUT_ROW_IDX: usize = 3;
+pub const RESERVE_NF_PUBLIC_INPUT_BEGIN_ROW_IDX: usize = 4;
+pub const RESERVE_PUBLIC_INPUT_NUM: usize =
+    RESERVE_NF_PUBLIC_INPUT_BEGIN_ROW_IDX + RESERVE_CIRCUIT_RESOURCE_NUM;
+
+// Spent circuit
+pub const SPENT_CM_PUBLIC_INPUT_ROW_IDX: usize = 0;
+pub const SPENT_NF_PUBLIC_INPUT_ROW_IDX: usize = 1;
+pub const SPENT_EPOCH_PUBLIC_INPUT_ROW_IDX: usize = 2;
+
+// Read circuit
+pub const READ_TAG_PUBLIC_INPUT_ROW_IDX: usize = 0;
+pub const READ_ANCHOR_PUBLIC_INPUT_ROW_IDX: usize = 1;
 
 pub const POSEIDON_TO_CURVE_INPUT_LEN: usize = 3;
 pub const CURVE_ID: &str = "pallas";
-pub const VALUE_BASE_DOMAIN_POSTFIX: &str = "Taiga-NoteType";
 
 pub const RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM: usize =
     RESOURCE_LOGIC_CIRCUIT_MANDATORY_PUBLIC_INPUT_NUM
@@ -83,6 +125,33 @@ pub const RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_1: usize = 4;
 pub const RESOURCE_LOGIC_CIRCUIT_SECOND_DYNAMIC_RESOURCE_LOGIC_CM_2: usize = 5;
 pub const RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX: usize =
     RESOURCE_LOGIC_CIRCUIT_MANDATORY_PUBLIC_INPUT_NUM;
+// The first custom public input is the linkability tag of the logics
+// publishing one, see `LinkabilityTag`.
+pub const RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX: usize =
+    RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX;
+// The last custom public input is the mailbox commitment of the logics using a
+// mailbox, see `Mailbox`.
+pub const RESOURCE_LOGIC_CIRCUIT_MAILBOX_IDX: usize =
+    RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX
+        + RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_NUM
+        - 1;
+// The sweep logic publishes the expiry and the quantity of the swept resource,
+// see `SweepClaim`.
+pub const RESOURCE_LOGIC_CIRCUIT_SWEEP_EXPIRY_IDX: usize =
+    RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX;
+pub const RESOURCE_LOGIC_CIRCUIT_SWEEP_QUANTITY_IDX: usize =
+    RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX + 1;
+// The blacklist logic publishes the root of the blacklist it checks against.
+pub const RESOURCE_LOGIC_CIRCUIT_BLACKLIST_ROOT_IDX: usize =
+    RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX;
+// The timelock logic publishes the epoch it is proven at, which must be the
+// epoch of the compliances, see `check_epoch`.
+pub const RESOURCE_LOGIC_CIRCUIT_TIMELOCK_EPOCH_IDX: usize =
+    RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX;
+// The ballot logic publishes the linkability tag of the voter and the vote, see
+// `Tally`.
+pub const RESOURCE_LOGIC_CIRCUIT_BALLOT_VOTE_IDX: usize =
+    RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_BEGIN_IDX + 1;
 pub const RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PUBLIC_INPUT_BEGIN_IDX: usize =
     RESOURCE_LOGIC_CIRCUIT_MANDATORY_PUBLIC_INPUT_NUM
         + RESOURCE_LOGIC_CIRCUIT_CUSTOM_PUBLIC_INPUT_NUM;
@@ -95,6 +164,18 @@ pub const RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PK_Y_IDX: usize = 21;
 pub const RESOURCE_ENCRYPTION_PLAINTEXT_NUM: usize = 10;
 pub const RESOURCE_ENCRYPTION_CIPHERTEXT_NUM: usize = RESOURCE_ENCRYPTION_PLAINTEXT_NUM + 2; // msg(10) + MAC(1) + NOUNCE(1)
 
+// The plaintext lengths of the larger encryption classes, see `EncryptionClass`
+pub const RESOURCE_ENCRYPTION_MEDIUM_PLAINTEXT_NUM: usize = 20;
+pub const RESOURCE_ENCRYPTION_LARGE_PLAINTEXT_NUM: usize = 40;
+
+// Mailbox messages are padded to a fixed length so that they don't leak it
+pub const MAILBOX_PLAINTEXT_NUM: usize = 4;
+
+// Memos are padded to a fixed number of bytes, as in Zcash, which fit in
+// MEMO_PLAINTEXT_NUM field elements of 31 bytes
+pub const MEMO_SIZE: usize = 512;
+pub const MEMO_PLAINTEXT_NUM: usize = (MEMO_SIZE + 30) / 31;
+
 // Poseidon parameters
 pub const POSEIDON_RATE: usize = 2;
 pub const POSEIDON_WIDTH: usize = 3;
@@ -122,7 +203,26 @@ lazy_static! {
 
 pub const PARAMS_SIZE: u32 = 15;
 pub const COMPLIANCE_CIRCUIT_PARAMS_SIZE: u32 = PARAMS_SIZE;
+/// The default params size of resource logics, a logic can declare its own
+/// with `ResourceLogicCircuit::PARAMS_SIZE`.
 pub const RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE: u32 = PARAMS_SIZE;
+/// The bounds of the params sizes of resource logics. The range check table of
+/// `ResourceLogicConfig` takes 2^10 rows.
+pub const RESOURCE_LOGIC_CIRCUIT_MIN_PARAMS_SIZE: u32 = 11;
+pub const RESOURCE_LOGIC_CIRCUIT_MAX_PARAMS_SIZE: u32 = 18;
+pub const RESERVE_CIRCUIT_PARAMS_SIZE: u32 = PARAMS_SIZE;
+pub const SPENT_CIRCUIT_PARAMS_SIZE: u32 = PARAMS_SIZE;
+pub const READ_CIRCUIT_PARAMS_SIZE: u32 = PARAMS_SIZE;
+
+/// The expected digests of the setup params: (size, digest), see
+/// [`crate::params::params_digest`].
+pub const SETUP_PARAMS_DIGESTS: &[(u32, [u8; 32])] = &[(
+    PARAMS_SIZE,
+    [
+        250, 100, 223, 249, 154, 126, 191, 52, 137, 250, 27, 168, 62, 202, 61, 125, 111, 101, 10,
+        233, 193, 43, 46, 39, 243, 152, 233, 238, 174, 156, 146, 110,
+    ],
+)];
 
 // Setup params map
 lazy_static! {
@@ -143,15 +243,49 @@ lazy_static! {
     pub static ref COMPLIANCE_VERIFYING_KEY: VerifyingKey<vesta::Affine> =
         COMPLIANCE_PROVING_KEY.get_vk().clone();
     pub static ref COMPLIANCE_PROVING_KEY: ProvingKey<vesta::Affine> = {
-        let params = SETUP_PARAMS_MAP
-            .get(&COMPLIANCE_CIRCUIT_PARAMS_SIZE)
-            .unwrap();
+        let params = &SETUP_PARAMS_MAP[&COMPLIANCE_CIRCUIT_PARAMS_SIZE];
         let empty_circuit: ComplianceCircuit = Default::default();
         let vk = keygen_vk(params, &empty_circuit).expect("keygen_vk should not fail");
         keygen_pk(params, vk, &empty_circuit).expect("keygen_pk should not fail")
     };
 }
 
+// Reserve proving key and verifying key
+lazy_static! {
+    pub static ref RESERVE_VERIFYING_KEY: VerifyingKey<vesta::Affine> =
+        RESERVE_PROVING_KEY.get_vk().clone();
+    pub static ref RESERVE_PROVING_KEY: ProvingKey<vesta::Affine> = {
+        let params = &SETUP_PARAMS_MAP[&RESERVE_CIRCUIT_PARAMS_SIZE];
+        let empty_circuit: ReserveCircuit = Default::default();
+        let vk = keygen_vk(params, &empty_circuit).expect("keygen_vk should not fail");
+        keygen_pk(params, vk, &empty_circuit).expect("keygen_pk should not fail")
+    };
+}
+
+// Spent proving key and verifying key
+lazy_static! {
+    pub static ref SPENT_VERIFYING_KEY: VerifyingKey<vesta::Affine> =
+        SPENT_PROVING_KEY.get_vk().clone();
+    pub static ref SPENT_PROVING_KEY: ProvingKey<vesta::Affine> = {
+        let params = &SETUP_PARAMS_MAP[&SPENT_CIRCUIT_PARAMS_SIZE];
+        let empty_circuit: SpentCircuit = Default::default();
+        let vk = keygen_vk(params, &empty_circuit).expect("keygen_vk should not fail");
+        keygen_pk(params, vk, &empty_circuit).expect("keygen_pk should not fail")
+    };
+}
+
+// Read proving key and verifying key
+lazy_static! {
+    pub static ref READ_VERIFYING_KEY: VerifyingKey<vesta::Affine> =
+        READ_PROVING_KEY.get_vk().clone();
+    pub static ref READ_PROVING_KEY: ProvingKey<vesta::Affine> = {
+        let params = &SETUP_PARAMS_MAP[&READ_CIRCUIT_PARAMS_SIZE];
+        let empty_circuit: ReadCircuit = Default::default();
+        let vk = keygen_vk(params, &empty_circuit).expect("keygen_vk should not fail");
+        keygen_pk(params, vk, &empty_circuit).expect("keygen_pk should not fail")
+    };
+}
+
 // SinsemillaCommit parameters
 lazy_static! {
     pub static ref RESOURCE_COMMIT_DOMAIN: CommitDomain =
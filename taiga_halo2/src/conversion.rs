@@ -0,0 +1,296 @@
+//! Conversion rules let a compliance consume a resource of one kind and create
+//! a resource of another kind, e.g. to wrap an asset or to rebase it.
+//!
+//! A rule converts `from_quantity` of the `from` kind into `to_quantity` of
+//! the `to` kind. A compliance that applies a rule doesn't commit to the
+//! quantities in its delta commitment, it publishes the id of the rule
+//! instead, and the compliance circuit checks that the quantities of the
+//! resources follow the ratio of the rule. The issuer of the `to` kind
+//! authorizes the rule by signing its id, and the ledger only accepts
+//! transactions whose rules are authorized.
+use crate::constant::CONVERSION_SIG_H_STAR_PERSONALIZATION;
+use crate::error::TransactionError;
+use crate::resource::{Resource, ResourceKind};
+use crate::utils::poseidon_hash_n;
+use pasta_curves::group::{ff::PrimeField, Group};
+use pasta_curves::pallas;
+use rand::{CryptoRng, RngCore};
+use reddsa::{private, Error, SigType, Signature, SigningKey, VerificationKey};
+use std::hash::{Hash, Hasher};
+use subtle::CtOption;
+
+#[cfg(feature = "nif")]
+use rustler::NifTuple;
+
+#[cfg(feature = "serde")]
+use serde;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TaigaConversion {}
+
+impl Default for TaigaConversion {
+    fn default() -> Self {
+        unimplemented!()
+    }
+}
+
+impl private::Sealed<TaigaConversion> for TaigaConversion {
+    const H_STAR_PERSONALIZATION: &'static [u8; 16] = CONVERSION_SIG_H_STAR_PERSONALIZATION;
+    type Point = pallas::Point;
+    type Scalar = pallas::Scalar;
+
+    fn basepoint() -> pallas::Point {
+        pallas::Point::generator()
+    }
+}
+
+impl SigType for TaigaConversion {}
+
+/// The id of a conversion rule, zero for the compliances without a rule.
+#[derive(Copy, Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "nif", derive(NifTuple))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConversionRuleId(pallas::Base);
+
+impl ConversionRuleId {
+    pub fn inner(&self) -> pallas::Base {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == pallas::Base::zero()
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<Self> {
+        pallas::Base::from_repr(bytes).map(ConversionRuleId)
+    }
+}
+
+impl From<pallas::Base> for ConversionRuleId {
+    fn from(id: pallas::Base) -> Self {
+        ConversionRuleId(id)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for ConversionRuleId {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for ConversionRuleId {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let value = crate::utils::read_base_field(reader)?;
+        Ok(Self(value))
+    }
+}
+
+impl Hash for ConversionRuleId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().as_ref().hash(state);
+    }
+}
+
+/// Converts `from_quantity` of the `from` kind into `to_quantity` of the `to`
+/// kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConversionRule {
+    pub from: ResourceKind,
+    pub to: ResourceKind,
+    pub from_quantity: u64,
+    pub to_quantity: u64,
+}
+
+impl ConversionRule {
+    pub fn new(
+        from: ResourceKind,
+        to: ResourceKind,
+        from_quantity: u64,
+        to_quantity: u64,
+    ) -> Result<Self, TransactionError> {
+        if from_quantity == 0 || to_quantity == 0 || from == to {
+            return Err(TransactionError::InvalidConversion);
+        }
+        Ok(Self {
+            from,
+            to,
+            from_quantity,
+            to_quantity,
+        })
+    }
+
+    /// The id of the rule, published by the compliances that apply it.
+    pub fn id(&self) -> ConversionRuleId {
+        ConversionRuleId(poseidon_hash_n([
+            self.from.logic,
+            self.from.label,
+            self.to.logic,
+            self.to.label,
+            pallas::Base::from(self.from_quantity),
+            pallas::Base::from(self.to_quantity),
+        ]))
+    }
+
+    /// Whether consuming `input` and creating `output` follows the rule.
+    pub fn converts(&self, input: &Resource, output: &Resource) -> bool {
+        let input_quantity = u128::from(input.quantity.inner());
+        let output_quantity = u128::from(output.quantity.inner());
+        input.kind == self.from
+            && output.kind == self.to
+            && input_quantity * u128::from(self.to_quantity)
+                == output_quantity * u128::from(self.from_quantity)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for ConversionRule {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.from.logic.to_repr())?;
+        writer.write_all(&self.from.label.to_repr())?;
+        writer.write_all(&self.to.logic.to_repr())?;
+        writer.write_all(&self.to.label.to_repr())?;
+        self.from_quantity.serialize(writer)?;
+        self.to_quantity.serialize(writer)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for ConversionRule {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        use crate::utils::read_base_field;
+        let from = ResourceKind {
+            logic: read_base_field(reader)?,
+            label: read_base_field(reader)?,
+        };
+        let to = ResourceKind {
+            logic: read_base_field(reader)?,
+            label: read_base_field(reader)?,
+        };
+        let from_quantity = u64::deserialize_reader(reader)?;
+        let to_quantity = u64::deserialize_reader(reader)?;
+        Ok(Self {
+            from,
+            to,
+            from_quantity,
+            to_quantity,
+        })
+    }
+}
+
+/// The key of the issuer of a resource kind, signing the conversion rules
+/// into the kind.
+#[derive(Clone, Debug)]
+pub struct ConversionSigningKey(SigningKey<TaigaConversion>);
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConversionVerificationKey(VerificationKey<TaigaConversion>);
+
+/// A conversion rule signed by the issuer of its `to` kind.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConversionAuthorization {
+    pub rule: ConversionRule,
+    signature: Signature<TaigaConversion>,
+}
+
+impl ConversionSigningKey {
+    pub fn random<R: RngCore + CryptoRng>(rng: R) -> Self {
+        Self(SigningKey::new(rng))
+    }
+
+    pub fn get_vk(&self) -> ConversionVerificationKey {
+        ConversionVerificationKey(VerificationKey::from(&self.0))
+    }
+
+    pub fn authorize<R: RngCore + CryptoRng>(
+        &self,
+        rng: R,
+        rule: ConversionRule,
+    ) -> ConversionAuthorization {
+        ConversionAuthorization {
+            rule,
+            signature: self.0.sign(rng, &rule.id().to_bytes()),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.into()
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Result<Self, Error> {
+        SigningKey::try_from(bytes).map(Self)
+    }
+}
+
+impl ConversionVerificationKey {
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.into()
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Result<Self, Error> {
+        VerificationKey::try_from(bytes).map(Self)
+    }
+}
+
+impl ConversionAuthorization {
+    pub fn verify(&self, vk: &ConversionVerificationKey) -> Result<(), TransactionError> {
+        vk.0.verify(&self.rule.id().to_bytes(), &self.signature)
+            .map_err(|_| TransactionError::UnauthorizedConversion(self.rule.id().inner()))
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for ConversionAuthorization {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.rule.serialize(writer)?;
+        let signature: [u8; 64] = self.signature.into();
+        writer.write_all(&signature)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for ConversionAuthorization {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let rule = ConversionRule::deserialize_reader(reader)?;
+        let signature = <[u8; 64]>::deserialize_reader(reader)?;
+        Ok(Self {
+            rule,
+            signature: Signature::from(signature),
+        })
+    }
+}
+
+#[test]
+fn test_conversion_rule() {
+    use crate::resource::tests::random_resource;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let mut input = random_resource(&mut rng);
+    let mut output = random_resource(&mut rng);
+    input.quantity = 30.into();
+    output.quantity = 20.into();
+    let rule = ConversionRule::new(input.kind, output.kind, 3, 2).unwrap();
+    assert!(rule.converts(&input, &output));
+    output.quantity = 21.into();
+    assert!(!rule.converts(&input, &output));
+    assert!(!rule.converts(&output, &input));
+    assert!(ConversionRule::new(input.kind, output.kind, 0, 2).is_err());
+
+    let issuer = ConversionSigningKey::random(&mut rng);
+    let authorization = issuer.authorize(&mut rng, rule);
+    assert!(authorization.verify(&issuer.get_vk()).is_ok());
+    let other = ConversionSigningKey::random(&mut rng);
+    assert!(authorization.verify(&other.get_vk()).is_err());
+}
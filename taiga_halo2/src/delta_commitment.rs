@@ -1,8 +1,9 @@
+use crate::compliance::ComplianceInfo;
 use crate::constant::RESOURCE_COMMITMENT_R_GENERATOR;
-use crate::resource::Resource;
+use crate::resource::{Resource, ResourceKind};
 use halo2_proofs::arithmetic::CurveAffine;
 use pasta_curves::group::cofactor::CofactorCurveAffine;
-use pasta_curves::group::{Curve, Group, GroupEncoding};
+use pasta_curves::group::{ff::PrimeField, Curve, Group, GroupEncoding};
 use pasta_curves::pallas;
 #[cfg(feature = "nif")]
 use rustler::NifTuple;
@@ -11,6 +12,9 @@ use subtle::CtOption;
 #[cfg(feature = "serde")]
 use serde;
 
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(feature = "nif", derive(NifTuple))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -22,8 +26,8 @@ impl DeltaCommitment {
         output_resource: &Resource,
         blind_r: &pallas::Scalar,
     ) -> Self {
-        let base_input = input_resource.get_kind();
-        let base_output = output_resource.get_kind();
+        let base_input = input_resource.kind();
+        let base_output = output_resource.kind();
         DeltaCommitment(
             base_input * pallas::Scalar::from(input_resource.quantity)
                 - base_output * pallas::Scalar::from(output_resource.quantity)
@@ -31,6 +35,11 @@ impl DeltaCommitment {
         )
     }
 
+    /// The delta commitment of resources that don't change the balance.
+    pub fn from_blind(blind_r: &pallas::Scalar) -> Self {
+        DeltaCommitment(RESOURCE_COMMITMENT_R_GENERATOR.to_curve() * blind_r)
+    }
+
     pub fn get_x(&self) -> pallas::Base {
         if self.0 == pallas::Point::identity() {
             pallas::Base::zero()
@@ -47,6 +56,14 @@ impl DeltaCommitment {
         }
     }
 
+    /// The inverse of `get_x` and `get_y`, (0, 0) being the identity.
+    pub fn from_coordinates(x: pallas::Base, y: pallas::Base) -> CtOption<DeltaCommitment> {
+        if x == pallas::Base::zero() && y == pallas::Base::zero() {
+            return CtOption::new(DeltaCommitment(pallas::Point::identity()), 1.into());
+        }
+        pallas::Affine::from_xy(x, y).map(|point| DeltaCommitment(point.to_curve()))
+    }
+
     pub fn inner(&self) -> pallas::Point {
         self.0
     }
@@ -59,3 +76,166 @@ impl DeltaCommitment {
         pallas::Point::from_bytes(&bytes).map(DeltaCommitment)
     }
 }
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for DeltaCommitment {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for DeltaCommitment {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let point = crate::utils::read_point(reader)?;
+        Ok(Self(point))
+    }
+}
+
+/// The net quantity of each resource kind, consumed minus created, listed in
+/// the order the kinds first appear.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BalanceSummary(Vec<(ResourceKind, i128)>);
+
+impl BalanceSummary {
+    /// The summary of the resources the delta commitments of the compliances
+    /// commit to. The resources converted by a rule are not in them.
+    pub fn from_compliances(compliances: &[ComplianceInfo]) -> Self {
+        let mut summary = Self::default();
+        for compliance in compliances.iter().filter(|c| c.get_conversion().is_none()) {
+            summary.consume(compliance.get_input_resource());
+            summary.create(compliance.get_output_resource());
+        }
+        summary
+    }
+
+    pub fn add(&mut self, kind: ResourceKind, quantity: i128) {
+        match self.0.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, balance)) => *balance += quantity,
+            None => self.0.push((kind, quantity)),
+        }
+    }
+
+    pub fn consume(&mut self, resource: &Resource) {
+        self.add(resource.kind, i128::from(resource.quantity.inner()));
+    }
+
+    pub fn create(&mut self, resource: &Resource) {
+        self.add(resource.kind, -i128::from(resource.quantity.inner()));
+    }
+
+    pub fn merge(&mut self, other: &BalanceSummary) {
+        for (kind, quantity) in other.0.iter() {
+            self.add(*kind, *quantity);
+        }
+    }
+
+    /// The net quantity of the kind, zero if no resource has the kind.
+    pub fn get(&self, kind: &ResourceKind) -> i128 {
+        self.0
+            .iter()
+            .find(|(k, _)| k == kind)
+            .map_or(0, |(_, quantity)| *quantity)
+    }
+
+    pub fn inner(&self) -> &[(ResourceKind, i128)] {
+        &self.0
+    }
+
+    /// The kinds with a non-zero net quantity.
+    pub fn unbalanced_kinds(&self) -> Vec<ResourceKind> {
+        self.0
+            .iter()
+            .filter(|(_, quantity)| *quantity != 0)
+            .map(|(kind, _)| *kind)
+            .collect()
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.0.iter().all(|(_, quantity)| *quantity == 0)
+    }
+
+    /// The sum of the delta commitments of the summarized resources, without
+    /// their blinds.
+    pub fn delta_commitment(&self) -> DeltaCommitment {
+        let point = self
+            .0
+            .iter()
+            .fold(pallas::Point::identity(), |acc, (kind, quantity)| {
+                let magnitude = pallas::Scalar::from_u128(quantity.unsigned_abs());
+                let scalar = if *quantity < 0 { -magnitude } else { magnitude };
+                acc + kind.derive_kind() * scalar
+            });
+        DeltaCommitment(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BalanceSummary, DeltaCommitment};
+    use crate::compliance::tests::compliance_info;
+    use crate::resource::Resource;
+    use ff::FromUniformBytes;
+    use pasta_curves::pallas;
+    use proptest::prelude::*;
+
+    impl Arbitrary for DeltaCommitment {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            any::<[u8; 32]>()
+                .prop_map(|bytes| {
+                    let mut wide = [0u8; 64];
+                    wide[..32].copy_from_slice(&bytes);
+                    DeltaCommitment::from_blind(&pallas::Scalar::from_uniform_bytes(&wide))
+                })
+                .boxed()
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_delta_commitment_borsh_round_trip(delta: DeltaCommitment) {
+            let bytes = borsh::to_vec(&delta).unwrap();
+            let decoded: DeltaCommitment = borsh::from_slice(&bytes).unwrap();
+            prop_assert_eq!(decoded.inner(), delta.inner());
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_delta_commitment_serde_round_trip(delta: DeltaCommitment) {
+            let json = serde_json::to_string(&delta).unwrap();
+            let decoded: DeltaCommitment = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(decoded.inner(), delta.inner());
+        }
+
+        // delta(a) + delta(b) = delta(a ∪ b), whether the compliances share a
+        // kind or not
+        #[test]
+        fn test_delta_commitment_additivity(
+            a_input: Resource,
+            a_output: Resource,
+            mut b_input: Resource,
+            b_output: Resource,
+            shared_kind: bool,
+            seed: u64,
+        ) {
+            if shared_kind {
+                b_input.kind = a_input.kind;
+            }
+            let a = compliance_info(a_input, a_output, seed);
+            let b = compliance_info(b_input, b_output, seed.wrapping_add(1));
+            let sum = a.get_delta_commitment(&a.get_rcv()).inner()
+                + b.get_delta_commitment(&b.get_rcv()).inner();
+            let union = BalanceSummary::from_compliances(&[a.clone(), b.clone()])
+                .delta_commitment()
+                .inner()
+                + DeltaCommitment::from_blind(&(a.get_rcv() + b.get_rcv())).inner();
+            prop_assert_eq!(sum, union);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! A walk through the lifecycle of a transfer, the smoke test of the whole
+//! stack. The code is [`crate::taiga_api::examples::hello_transfer`]:
+//!
+//! 1. The resources of the sender and of the receiver are created with the
+//!    trivial resource logic, which accepts any transaction.
+//! 2. The resource of the sender is minted in an in-memory
+//!    [`LedgerState`](crate::ledger::LedgerState), which gives the Merkle path
+//!    proving it exists.
+//! 3. The [`TransactionBuilder`](crate::builder::TransactionBuilder) pairs the
+//!    spent and the created resources in a ptx, proves the compliances and
+//!    the logics, and signs the balance of the transaction.
+//! 4. The ledger executes the transaction: it verifies the proofs, publishes
+//!    the nullifier of the sender's resource and appends the commitment of
+//!    the receiver's one.
+//!
+//! ```
+//! use rand::rngs::OsRng;
+//! use taiga_halo2::taiga_api::examples::hello_transfer;
+//!
+//! let (ledger, result) = hello_transfer(OsRng).unwrap();
+//! assert!(ledger.contains_nullifier(&result.nullifiers[0]));
+//! assert_eq!(ledger.commitments().len(), 2);
+//! ```
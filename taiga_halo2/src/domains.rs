@@ -0,0 +1,291 @@
+//! Domain separation tags used across Taiga.
+//!
+//! Every hash personalization and PRF expansion tag is defined here, together
+//! with a registry that lists all of them. The registry is checked for
+//! duplicates at compile time, so a new tag colliding with an existing one
+//! fails the build instead of silently breaking domain separation.
+
+/// SWU hash-to-curve personalization for the resource commitment generator
+pub const RESOURCE_COMMITMENT_PERSONALIZATION: &str = "Taiga-NoteCommit";
+
+/// Blake2b personalization of the transaction binding signature message
+pub const TRANSACTION_BINDING_HASH_PERSONALIZATION: &[u8; 16] = b"TxBindingSigHash";
+
+/// Blake2s personalization of the resource logic commitment
+pub const RESOURCE_LOGIC_COMMITMENT_PERSONALIZATION: &[u8; 8] = b"VPCommit";
+
+/// Blake2b personalization of the random seed expansion
+pub const PRF_EXPAND_PERSONALIZATION: &[u8; 16] = b"Taiga_ExpandSeed";
+
+/// RedPallas H* personalization of the binding signature
+pub const BINDING_SIG_H_STAR_PERSONALIZATION: &[u8; 16] = b"Taiga_RedPallasH";
+
+/// RedPallas H* personalization of the conversion rule signatures of issuers
+pub const CONVERSION_SIG_H_STAR_PERSONALIZATION: &[u8; 16] = b"Taiga_ConvRulesH";
+
+/// Blake2b personalization used to compress a resource logic verifying key
+pub const RESOURCE_LOGIC_VK_COMPRESS_PERSONALIZATION: &[u8; 16] = b"Halo2-Verify-Key";
+
+/// Domain postfix of the poseidon hash-to-curve used to derive resource kinds
+pub const VALUE_BASE_DOMAIN_POSTFIX: &str = "Taiga-NoteType";
+
+/// Tag of the auxiliary delta published by resource logics, also used to
+/// derive the auxiliary delta generators
+pub const AUXILIARY_DELTA_PERSONALIZATION: &str = "Taiga-AuxDelta";
+
+/// Blake2s personalization of the zkVM receipt claim digest
+pub const ZKVM_RECEIPT_CLAIM_PERSONALIZATION: &[u8; 8] = b"ZkvmRcpt";
+
+/// Blake2b personalization of the digest of the proofs stripped by pruning
+pub const TRANSACTION_PROOFS_HASH_PERSONALIZATION: &[u8; 16] = b"Taiga_TxProofsH_";
+
+/// Blake2b personalization of the digest of the setup params
+pub const PARAMS_DIGEST_PERSONALIZATION: &[u8; 16] = b"Taiga_ParamsDgst";
+
+/// Initial state of the Poseidon proof transcript
+pub const POSEIDON_TRANSCRIPT_PERSONALIZATION: &[u8; 16] = b"Taiga_PoseidonTr";
+
+/// Tag of the linkability tags published by resource logics
+pub const LINKABILITY_TAG_PERSONALIZATION: &str = "Taiga-LinkTag";
+
+/// Blake2b personalization of the digest of the compliance public inputs
+pub const COMPLIANCE_DIGEST_PERSONALIZATION: &[u8; 16] = b"Taiga_ComplDgst_";
+
+/// Blake2b personalization of the hash chain of the wallet history log
+pub const HISTORY_LOG_PERSONALIZATION: &[u8; 16] = b"Taiga_HistoryLog";
+
+/// Tag of the method calls encoded in the value of app resources
+pub const METHOD_CALL_PERSONALIZATION: &str = "Taiga-MethodCall";
+
+/// Public nullifier key of the sweepable resources
+pub const SWEEP_NK_PERSONALIZATION: &str = "Taiga-SweepNk";
+
+/// Tag of the resource ciphertexts published in extra instance columns
+pub const RESOURCE_ENCRYPTION_PERSONALIZATION: &str = "Taiga-ResEnc";
+
+/// Blake2s personalization of the transaction id
+pub const TRANSACTION_ID_PERSONALIZATION: &[u8; 8] = b"TaigaTxI";
+
+/// Domain of the hash-to-curve deriving the diversified bases of addresses
+pub const DIVERSIFIER_PERSONALIZATION: &str = "Taiga-Diversify";
+
+/// Label of the fee resources, see `Fee`
+pub const FEE_RESOURCE_PERSONALIZATION: &str = "Taiga-Fee";
+
+/// Blake2b personalization of the master key derivation from a seed
+pub const KEY_MASTER_PERSONALIZATION: &[u8; 16] = b"Taiga_MasterKey_";
+
+/// Blake2b personalization of the hardened child key derivation
+pub const KEY_CHILD_PERSONALIZATION: &[u8; 16] = b"Taiga_ChildKey__";
+
+// PRF expansion tags of the random seed
+pub const PRF_EXPAND_PSI: u8 = 0;
+pub const PRF_EXPAND_RCM: u8 = 1;
+pub const PRF_EXPAND_PUBLIC_INPUT_PADDING: u8 = 2;
+pub const PRF_EXPAND_VCM_R: u8 = 3;
+pub const PRF_EXPAND_INPUT_RESOURCE_LOGIC_CM_R: u8 = 4;
+pub const PRF_EXPAND_OUTPUT_RESOURCE_LOGIC_CM_R: u8 = 5;
+pub const PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_1_CM_R: u8 = 6;
+pub const PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_2_CM_R: u8 = 7;
+// PRF expansion tags of the spending keys
+pub const PRF_EXPAND_NK: u8 = 8;
+pub const PRF_EXPAND_ENCRYPTION_SK: u8 = 9;
+
+/// All personalization strings: (name, value).
+pub const PERSONALIZATION_REGISTRY: &[(&str, &[u8])] = &[
+    (
+        "RESOURCE_COMMITMENT_PERSONALIZATION",
+        RESOURCE_COMMITMENT_PERSONALIZATION.as_bytes(),
+    ),
+    (
+        "TRANSACTION_BINDING_HASH_PERSONALIZATION",
+        TRANSACTION_BINDING_HASH_PERSONALIZATION,
+    ),
+    (
+        "RESOURCE_LOGIC_COMMITMENT_PERSONALIZATION",
+        RESOURCE_LOGIC_COMMITMENT_PERSONALIZATION,
+    ),
+    ("PRF_EXPAND_PERSONALIZATION", PRF_EXPAND_PERSONALIZATION),
+    (
+        "BINDING_SIG_H_STAR_PERSONALIZATION",
+        BINDING_SIG_H_STAR_PERSONALIZATION,
+    ),
+    (
+        "CONVERSION_SIG_H_STAR_PERSONALIZATION",
+        CONVERSION_SIG_H_STAR_PERSONALIZATION,
+    ),
+    (
+        "RESOURCE_LOGIC_VK_COMPRESS_PERSONALIZATION",
+        RESOURCE_LOGIC_VK_COMPRESS_PERSONALIZATION,
+    ),
+    (
+        "VALUE_BASE_DOMAIN_POSTFIX",
+        VALUE_BASE_DOMAIN_POSTFIX.as_bytes(),
+    ),
+    (
+        "AUXILIARY_DELTA_PERSONALIZATION",
+        AUXILIARY_DELTA_PERSONALIZATION.as_bytes(),
+    ),
+    (
+        "ZKVM_RECEIPT_CLAIM_PERSONALIZATION",
+        ZKVM_RECEIPT_CLAIM_PERSONALIZATION,
+    ),
+    (
+        "TRANSACTION_PROOFS_HASH_PERSONALIZATION",
+        TRANSACTION_PROOFS_HASH_PERSONALIZATION,
+    ),
+    (
+        "PARAMS_DIGEST_PERSONALIZATION",
+        PARAMS_DIGEST_PERSONALIZATION,
+    ),
+    (
+        "POSEIDON_TRANSCRIPT_PERSONALIZATION",
+        POSEIDON_TRANSCRIPT_PERSONALIZATION,
+    ),
+    (
+        "LINKABILITY_TAG_PERSONALIZATION",
+        LINKABILITY_TAG_PERSONALIZATION.as_bytes(),
+    ),
+    (
+        "COMPLIANCE_DIGEST_PERSONALIZATION",
+        COMPLIANCE_DIGEST_PERSONALIZATION,
+    ),
+    ("HISTORY_LOG_PERSONALIZATION", HISTORY_LOG_PERSONALIZATION),
+    (
+        "METHOD_CALL_PERSONALIZATION",
+        METHOD_CALL_PERSONALIZATION.as_bytes(),
+    ),
+    (
+        "SWEEP_NK_PERSONALIZATION",
+        SWEEP_NK_PERSONALIZATION.as_bytes(),
+    ),
+    (
+        "RESOURCE_ENCRYPTION_PERSONALIZATION",
+        RESOURCE_ENCRYPTION_PERSONALIZATION.as_bytes(),
+    ),
+    (
+        "TRANSACTION_ID_PERSONALIZATION",
+        TRANSACTION_ID_PERSONALIZATION,
+    ),
+    ("KEY_MASTER_PERSONALIZATION", KEY_MASTER_PERSONALIZATION),
+    ("KEY_CHILD_PERSONALIZATION", KEY_CHILD_PERSONALIZATION),
+    (
+        "DIVERSIFIER_PERSONALIZATION",
+        DIVERSIFIER_PERSONALIZATION.as_bytes(),
+    ),
+    (
+        "FEE_RESOURCE_PERSONALIZATION",
+        FEE_RESOURCE_PERSONALIZATION.as_bytes(),
+    ),
+];
+
+/// All PRF expansion tags: (name, tag).
+pub const PRF_EXPAND_REGISTRY: &[(&str, u8)] = &[
+    ("PRF_EXPAND_PSI", PRF_EXPAND_PSI),
+    ("PRF_EXPAND_RCM", PRF_EXPAND_RCM),
+    (
+        "PRF_EXPAND_PUBLIC_INPUT_PADDING",
+        PRF_EXPAND_PUBLIC_INPUT_PADDING,
+    ),
+    ("PRF_EXPAND_VCM_R", PRF_EXPAND_VCM_R),
+    (
+        "PRF_EXPAND_INPUT_RESOURCE_LOGIC_CM_R",
+        PRF_EXPAND_INPUT_RESOURCE_LOGIC_CM_R,
+    ),
+    (
+        "PRF_EXPAND_OUTPUT_RESOURCE_LOGIC_CM_R",
+        PRF_EXPAND_OUTPUT_RESOURCE_LOGIC_CM_R,
+    ),
+    (
+        "PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_1_CM_R",
+        PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_1_CM_R,
+    ),
+    (
+        "PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_2_CM_R",
+        PRF_EXPAND_DYNAMIC_RESOURCE_LOGIC_2_CM_R,
+    ),
+    ("PRF_EXPAND_NK", PRF_EXPAND_NK),
+    ("PRF_EXPAND_ENCRYPTION_SK", PRF_EXPAND_ENCRYPTION_SK),
+];
+
+const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn personalizations_are_unique(registry: &[(&str, &[u8])]) -> bool {
+    let mut i = 0;
+    while i < registry.len() {
+        let mut j = i + 1;
+        while j < registry.len() {
+            if bytes_eq(registry[i].1, registry[j].1) {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn prf_expand_tags_are_unique(registry: &[(&str, u8)]) -> bool {
+    let mut i = 0;
+    while i < registry.len() {
+        let mut j = i + 1;
+        while j < registry.len() {
+            if registry[i].1 == registry[j].1 {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const _: () = assert!(
+    personalizations_are_unique(PERSONALIZATION_REGISTRY),
+    "duplicate personalization in PERSONALIZATION_REGISTRY"
+);
+const _: () = assert!(
+    prf_expand_tags_are_unique(PRF_EXPAND_REGISTRY),
+    "duplicate tag in PRF_EXPAND_REGISTRY"
+);
+
+/// Returns a human-readable markdown table of all domain tags.
+pub fn registry() -> String {
+    let mut out = String::from("| Name | Kind | Value |\n| - | - | - |\n");
+    for (name, value) in PERSONALIZATION_REGISTRY {
+        let value = String::from_utf8_lossy(value);
+        out.push_str(&format!("| {name} | personalization | {value:?} |\n"));
+    }
+    for (name, tag) in PRF_EXPAND_REGISTRY {
+        out.push_str(&format!("| {name} | prf expand tag | {tag} |\n"));
+    }
+    out
+}
+
+#[test]
+fn test_domain_registry() {
+    let registry = registry();
+    for (name, _) in PERSONALIZATION_REGISTRY {
+        assert!(registry.contains(name));
+    }
+    for (name, _) in PRF_EXPAND_REGISTRY {
+        assert!(registry.contains(name));
+    }
+    assert!(!personalizations_are_unique(&[
+        ("a", b"tag"),
+        ("b", b"tag")
+    ]));
+    assert!(!prf_expand_tags_are_unique(&[("a", 1), ("b", 1)]));
+}
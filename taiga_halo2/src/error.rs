@@ -1,11 +1,19 @@
+use crate::resource::ResourceKind;
 use core::fmt;
 use halo2_proofs::plonk::Error as PlonkError;
+use pasta_curves::pallas;
 use std::fmt::Display;
 
+/// The errors of the public Taiga APIs, from proving to executing a
+/// transaction.
+pub type TaigaError = TransactionError;
+
 #[derive(Debug)]
 pub enum TransactionError {
     /// An error occurred when creating halo2 proof.
     Proof(PlonkError),
+    /// An error occurred when generating the keys of a circuit.
+    Keygen(PlonkError),
     /// Binding signature is not valid.
     InvalidBindingSignature,
     /// Binding signature is missing.
@@ -28,6 +36,137 @@ pub enum TransactionError {
     InvalidResourceLogicRepresentation,
     /// Resource merkle root is inconsistent between the compliance and the resource logic.
     InconsistentResourceMerkleRoot,
+    /// The resource(nf or cm) is not claimed by any resource logic.
+    MissingResourceLogicProof(pallas::Base),
+    /// The resource(nf or cm) is claimed by more than one resource logic set.
+    DuplicateResourceLogicProof(pallas::Base),
+    /// The resource logic claims a resource that is not in the partial transaction.
+    UnknownOwnedResource(pallas::Base),
+    /// The unspent resources of the wallet don't cover the requested reserve.
+    InsufficientReserves,
+    /// The resources used in the reserve proof are not in the same commitment tree.
+    InconsistentReserveAnchor,
+    /// The nullifier key of the resource is missing.
+    MissingNullifierKey,
+    /// The resource commitment is not known.
+    UnknownResourceCommitment,
+    /// The spent proof is not for the expected commitment or epoch.
+    InvalidSpentProof,
+    /// The nullifier is already published.
+    DoubleSpend(pallas::Base),
+    /// The transactions in the bundle depend on each other in a cycle.
+    CyclicTransactionDependency,
+    /// The numbers of spent and created resources differ.
+    UnpairedResources,
+    /// The quantities of the spent and created resources don't balance.
+    UnbalancedResources,
+    /// The ptx has more compliances than `MAX_PTX_COMPLIANCE_NUM`.
+    TooManyResources,
+    /// A resource has no application logic attached.
+    MissingApplicationLogic,
+    /// The channel update is not signed by both parties.
+    InvalidChannelUpdate,
+    /// The channel update is not newer than the latest state.
+    StaleChannelUpdate,
+    /// A resource logic publishes an auxiliary delta that is not a point.
+    InvalidAuxiliaryDelta,
+    /// The auxiliary deltas of the transaction don't sum to zero.
+    UnbalancedAuxiliaryDelta,
+    /// No resource logic publishes the claim of the zkVM receipt.
+    ReceiptClaimMismatch,
+    /// The zkVM receipt doesn't verify.
+    InvalidReceipt,
+    /// The archive doesn't hold the transaction.
+    TransactionNotArchived,
+    /// The full transaction doesn't match the pruned one.
+    PrunedTransactionMismatch,
+    /// The logic doesn't publish the commitment of the mailbox.
+    MailboxCommitmentMismatch,
+    /// The mailbox can't be decrypted with the key.
+    InvalidMailbox,
+    /// No digest is expected for the params of this size.
+    UnknownParamsSize(u32),
+    /// The params of this size don't match the expected digest.
+    ParamsDigestMismatch(u32),
+    /// All the leaves of the commitment tree are used.
+    CommitmentTreeFull,
+    /// The nonce of the output resource is not the nullifier of the input resource.
+    InvalidOutputResourceNonce,
+    /// The resource logic constraints are not satisfied.
+    UnsatisfiedResourceLogic,
+    /// The linkability tag is already published.
+    DuplicateLinkabilityTag(pallas::Base),
+    /// The same compliance is included twice, e.g. in a replayed ptx.
+    DuplicateCompliance,
+    /// No resource logic is registered with the tag of the bytecode.
+    UnregisteredResourceLogic(String),
+    /// The hash chain of the history log is broken or its heights decrease.
+    InvalidHistoryLog,
+    /// The anchor resource doesn't commit to the history log.
+    HistoryAnchorMismatch,
+    /// The WASM resource logic fails to run or outputs an invalid constraint system.
+    InvalidWasmResourceLogic(String),
+    /// The Noir resource logic can't be adapted from its ACIR artifact.
+    InvalidNoirResourceLogic(String),
+    /// A resource is swept before its expiry and above the dust threshold.
+    SweepNotAllowed,
+    /// A compliance or a timelock logic is proven at another epoch than the
+    /// current one.
+    InvalidEpoch,
+    /// A ballot votes for another election or an unknown option.
+    InvalidVote,
+    /// The royalty of an NFT is above 100 percent.
+    InvalidRoyalty,
+    /// The consumed and created quantities of the kind don't balance.
+    UnbalancedResourceKind(ResourceKind),
+    /// The shielded resources don't balance, their kinds are hidden.
+    UnbalancedShieldedResources,
+    /// The compliances don't open the delta commitments of the ptx.
+    InvalidBalanceOpening,
+    /// The bytes are not a canonical encoding of a transaction.
+    InvalidTransactionEncoding,
+    /// The canonical encoding of the transaction has an unknown version.
+    UnknownTransactionVersion(u8),
+    /// The vamp-ir resource logic can't be read or compiled.
+    InvalidVampIRResourceLogic(String),
+    /// The conversion rule is malformed, or the resources don't follow it.
+    InvalidConversion,
+    /// The conversion rule is not authorized by the issuer of its target kind.
+    UnauthorizedConversion(pallas::Base),
+    /// The memo is longer than `MEMO_SIZE` bytes.
+    MemoTooLong,
+    /// The memo is malformed, or attached to a resource the transaction
+    /// doesn't create.
+    InvalidMemo,
+    /// The key seed is shorter than 32 bytes or longer than 252 bytes.
+    InvalidSeed,
+    /// The child index of a key is at or above `HARDENED_KEY_START`.
+    InvalidKeyIndex(u32),
+    /// The merkle path of the input resource doesn't lead to the anchor.
+    InvalidComplianceAnchor,
+    /// There is no checkpoint of the ledger to roll back to.
+    NoCheckpoint,
+    /// The resource built by the `ResourceBuilder` has a missing or invalid
+    /// field.
+    InvalidResource(String),
+    /// The same resource is counted more than once in a reserve proof.
+    DuplicateReserveResource,
+    /// The anchor is not a recent root of the commitment tree of the ledger.
+    UnknownAnchor,
+    /// A resource logic delegates to a zkVM receipt the transaction doesn't carry.
+    MissingReceipt,
+    /// The transaction carries zkVM receipts but the context has no verifier for them.
+    MissingReceiptVerifier,
+    /// The filled quantity of a swap is zero, exceeds the sold quantity or is
+    /// not worth a whole quantity of the bought token.
+    InvalidSwapFill,
+    /// Params of this size are neither shipped nor generated.
+    UnsupportedParamsSize(u32),
+    /// The swept resources are none, mix tokens or overflow the bounty.
+    InvalidSweep,
+    /// A blacklist logic is proven against another blacklist than the one
+    /// enforced by the ledger.
+    UnknownBlacklist,
 }
 
 impl Display for TransactionError {
@@ -35,6 +174,7 @@ impl Display for TransactionError {
         use TransactionError::*;
         match self {
             Proof(e) => f.write_str(&format!("Proof error: {e}")),
+            Keygen(e) => f.write_str(&format!("Key generation error: {e}")),
             InvalidBindingSignature => f.write_str("Binding signature was invalid"),
             MissingBindingSignatures => f.write_str("Binding signature is missing"),
             InconsistentNullifier => {
@@ -62,6 +202,129 @@ impl Display for TransactionError {
             InconsistentResourceMerkleRoot => {
                 f.write_str("Resource merkle root is not consistent between the compliance and the resource logic")
             }
+            MissingResourceLogicProof(id) => {
+                f.write_str(&format!("Resource {id:?} has no resource logic proof"))
+            }
+            DuplicateResourceLogicProof(id) => f.write_str(&format!(
+                "Resource {id:?} is claimed by more than one resource logic set"
+            )),
+            UnknownOwnedResource(id) => f.write_str(&format!(
+                "Resource {id:?} claimed by the resource logic is not in the partial transaction"
+            )),
+            InsufficientReserves => {
+                f.write_str("Unspent resources are not enough to cover the reserve")
+            }
+            InconsistentReserveAnchor => {
+                f.write_str("Reserve resources are not in the same commitment tree")
+            }
+            MissingNullifierKey => f.write_str("Resource nullifier key is missing"),
+            UnknownResourceCommitment => f.write_str("Resource commitment is unknown"),
+            InvalidSpentProof => {
+                f.write_str("Spent proof is not for the expected commitment or epoch")
+            }
+            DoubleSpend(nf) => f.write_str(&format!("Nullifier {nf:?} is already published")),
+            CyclicTransactionDependency => {
+                f.write_str("Transactions in the bundle depend on each other in a cycle")
+            }
+            UnpairedResources => {
+                f.write_str("The numbers of spent and created resources are different")
+            }
+            UnbalancedResources => {
+                f.write_str("The quantities of spent and created resources don't balance")
+            }
+            TooManyResources => f.write_str("Partial transaction has too many resources"),
+            MissingApplicationLogic => f.write_str("Resource application logic is missing"),
+            InvalidChannelUpdate => f.write_str("Channel update is not signed by both parties"),
+            StaleChannelUpdate => f.write_str("Channel update is not newer than the latest state"),
+            InvalidAuxiliaryDelta => f.write_str("Auxiliary delta is not a valid point"),
+            UnbalancedAuxiliaryDelta => f.write_str("Auxiliary deltas don't sum to zero"),
+            ReceiptClaimMismatch => {
+                f.write_str("No resource logic publishes the claim of the zkVM receipt")
+            }
+            InvalidReceipt => f.write_str("zkVM receipt is not valid"),
+            TransactionNotArchived => f.write_str("Transaction is not in the archive"),
+            PrunedTransactionMismatch => {
+                f.write_str("Transaction doesn't match the pruned transaction")
+            }
+            MailboxCommitmentMismatch => {
+                f.write_str("Mailbox commitment is not published by the resource logic")
+            }
+            InvalidMailbox => f.write_str("Mailbox can't be decrypted"),
+            UnknownParamsSize(k) => f.write_str(&format!("No params digest for size {k}")),
+            ParamsDigestMismatch(k) => f.write_str(&format!(
+                "Params of size {k} don't match the expected digest, proofs would not verify"
+            )),
+            CommitmentTreeFull => f.write_str("Commitment tree is full"),
+            InvalidOutputResourceNonce => {
+                f.write_str("Output resource nonce is not the nullifier of the input resource")
+            }
+            UnsatisfiedResourceLogic => f.write_str("Resource logic constraints are not satisfied"),
+            DuplicateLinkabilityTag(tag) => {
+                f.write_str(&format!("Linkability tag {tag:?} is already published"))
+            }
+            DuplicateCompliance => f.write_str("The same compliance is included twice"),
+            UnregisteredResourceLogic(tag) => {
+                f.write_str(&format!("No resource logic is registered with the tag {tag}"))
+            }
+            InvalidHistoryLog => f.write_str("History log is invalid"),
+            HistoryAnchorMismatch => {
+                f.write_str("Anchor resource doesn't commit to the history log")
+            }
+            InvalidWasmResourceLogic(e) => {
+                f.write_str(&format!("WASM resource logic is invalid: {e}"))
+            }
+            InvalidNoirResourceLogic(e) => {
+                f.write_str(&format!("Noir resource logic is invalid: {e}"))
+            }
+            SweepNotAllowed => {
+                f.write_str("Resource is swept before its expiry and above the dust threshold")
+            }
+            InvalidEpoch => {
+                f.write_str("Transaction is proven at another epoch than the current one")
+            }
+            InvalidVote => f.write_str("Ballot votes for another election or an unknown option"),
+            InvalidRoyalty => f.write_str("NFT royalty is above 100 percent"),
+            UnbalancedResourceKind(kind) => f.write_str(&format!(
+                "The quantities of the resources of kind {kind:?} don't balance"
+            )),
+            UnbalancedShieldedResources => {
+                f.write_str("The quantities of the shielded resources don't balance")
+            }
+            InvalidBalanceOpening => {
+                f.write_str("Compliances don't open the delta commitments of the ptx")
+            }
+            InvalidTransactionEncoding => f.write_str("Transaction encoding is invalid"),
+            UnknownTransactionVersion(version) => f.write_str(&format!(
+                "Transaction encoding version {version} is unknown"
+            )),
+            InvalidVampIRResourceLogic(e) => {
+                f.write_str(&format!("Vamp-IR resource logic is invalid: {e}"))
+            }
+            InvalidConversion => f.write_str("Conversion rule is invalid"),
+            UnauthorizedConversion(id) => {
+                f.write_str(&format!("Conversion rule {id:?} is not authorized"))
+            }
+            MemoTooLong => f.write_str("Memo exceeds MEMO_SIZE bytes"),
+            InvalidMemo => f.write_str("Memo is invalid"),
+            InvalidSeed => f.write_str("Key seed must be 32 to 252 bytes long"),
+            InvalidKeyIndex(index) => {
+                f.write_str(&format!("Key index {index} must be less than 2^31"))
+            }
+            InvalidComplianceAnchor => {
+                f.write_str("Merkle path of the input resource doesn't lead to the anchor")
+            }
+            NoCheckpoint => f.write_str("No ledger checkpoint to roll back to"),
+            InvalidResource(e) => f.write_str(&format!("Resource is invalid: {e}")),
+            DuplicateReserveResource => {
+                f.write_str("Reserve proof counts the same resource more than once")
+            }
+            UnknownAnchor => f.write_str("Anchor is not a recent root of the commitment tree"),
+            MissingReceipt => f.write_str("zkVM receipt of a resource logic is missing"),
+            MissingReceiptVerifier => f.write_str("No verifier for the zkVM receipts"),
+            InvalidSwapFill => f.write_str("Invalid filled quantity of the swap"),
+            UnsupportedParamsSize(k) => f.write_str(&format!("Unsupported params size {k}")),
+            InvalidSweep => f.write_str("Invalid swept resources"),
+            UnknownBlacklist => f.write_str("Blacklist is not the one enforced by the ledger"),
         }
     }
 }
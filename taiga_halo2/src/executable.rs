@@ -1,17 +1,131 @@
+//! The interface the executor runs partial transactions through.
+//!
+//! A partial transaction is the atomic executable unit. `validate` checks it
+//! on its own: the proofs, or the native checks of a transparent ptx, and the
+//! consistency of its public inputs. `execute` then checks it against a
+//! [`StateView`] of the ledger and returns the [`StateDelta`] to apply, so
+//! that an executor node runs shielded and transparent ptxs alike without
+//! knowing about either.
 use pasta_curves::pallas;
+use std::collections::HashSet;
 
 use crate::{
-    delta_commitment::DeltaCommitment, error::TransactionError, merkle_tree::Anchor,
-    nullifier::Nullifier, resource::ResourceCommitment, resource_tree::ResourceMerkleTreeLeaves,
+    conversion::ConversionRuleId,
+    delta_commitment::DeltaCommitment,
+    error::TransactionError,
+    merkle_tree::Anchor,
+    nullifier::Nullifier,
+    resource::{Resource, ResourceCommitment},
+    resource_tree::ResourceMerkleTreeLeaves,
 };
 
-// Executable is an unified interface for partial transaction, which is the atomic executable uinit.
+/// The ledger state a partial transaction is executed against.
+pub trait StateView {
+    /// Whether the nullifier is already published.
+    fn contains_nullifier(&self, nf: &Nullifier) -> bool;
+    /// Whether the anchor is a recent root of the commitment tree, the
+    /// compliances and the reads can be proven against.
+    fn is_known_anchor(&self, anchor: &Anchor) -> bool;
+    /// Whether the issuer of the target kind authorized the conversion rule.
+    fn is_conversion_authorized(&self, id: &ConversionRuleId) -> bool;
+    /// The current epoch, the compliances must be proven at.
+    fn epoch(&self) -> u64;
+    /// The root of the enforced blacklist, the blacklist logics must be
+    /// proven against, see `SortedMerkleTree`.
+    fn blacklist_root(&self) -> Anchor;
+}
+
+/// The changes of the ledger state made by an executed partial transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDelta {
+    /// The nullifiers to publish.
+    pub nullifiers: Vec<Nullifier>,
+    /// The cms to append to the commitment tree.
+    pub output_cms: Vec<ResourceCommitment>,
+}
+
+/// A write to the state of an app, decoded from a transparent ptx. The
+/// resources of the shielded ptxs are hidden and give no writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateWrite {
+    /// The resource is created under its cm.
+    Insert(ResourceCommitment, Resource),
+    /// The resource of the cm is consumed.
+    Delete(ResourceCommitment),
+}
+
+/// The unified interface of the partial transactions.
 pub trait Executable {
-    fn execute(&self) -> Result<(), TransactionError>;
+    /// Checks the partial transaction independently of the ledger state.
+    fn validate(&self) -> Result<(), TransactionError>;
+
+    /// Validates the partial transaction and checks it against the state:
+    /// the nullifiers are unpublished and distinct, the anchors are known,
+    /// the conversion rules are authorized, the compliances are proven at the
+    /// current epoch and the blacklist logics against the enforced blacklist.
+    /// The state is not changed, the caller applies the returned delta.
+    fn execute(&self, state: &dyn StateView) -> Result<StateDelta, TransactionError> {
+        self.validate()?;
+        let nullifiers = self.get_nullifiers();
+        let mut seen = HashSet::with_capacity(nullifiers.len());
+        if let Some(nf) = nullifiers
+            .iter()
+            .find(|nf| state.contains_nullifier(nf) || !seen.insert(**nf))
+        {
+            return Err(TransactionError::DoubleSpend(nf.inner()));
+        }
+        if self
+            .get_anchors()
+            .iter()
+            .any(|anchor| !state.is_known_anchor(anchor))
+        {
+            return Err(TransactionError::UnknownAnchor);
+        }
+        if let Some(id) = self
+            .get_conversion_rules()
+            .into_iter()
+            .find(|id| !state.is_conversion_authorized(id))
+        {
+            return Err(TransactionError::UnauthorizedConversion(id.inner()));
+        }
+        if self
+            .get_epochs()
+            .into_iter()
+            .any(|epoch| epoch != state.epoch())
+        {
+            return Err(TransactionError::InvalidEpoch);
+        }
+        if self
+            .get_blacklist_roots()
+            .into_iter()
+            .any(|root| root != state.blacklist_root())
+        {
+            return Err(TransactionError::UnknownBlacklist);
+        }
+        Ok(StateDelta {
+            nullifiers,
+            output_cms: self.get_output_cms(),
+        })
+    }
+
     fn get_nullifiers(&self) -> Vec<Nullifier>;
     fn get_output_cms(&self) -> Vec<ResourceCommitment>;
     fn get_delta_commitments(&self) -> Vec<DeltaCommitment>;
     fn get_anchors(&self) -> Vec<Anchor>;
+    // The ids of the conversion rules applied by the compliances. Their
+    // authorization is checked by the ledger.
+    fn get_conversion_rules(&self) -> Vec<ConversionRuleId>;
+    // The epochs the compliances are proven at. They are checked against the
+    // current epoch by the ledger.
+    fn get_epochs(&self) -> Vec<u64>;
+    // The roots of the blacklists the blacklist logics are proven against.
+    // They are checked against the enforced blacklist by the ledger.
+    fn get_blacklist_roots(&self) -> Vec<Anchor>;
+    // The tags of the resources read by the ptx, see `Resource::get_read_tag`.
+    // The reads neither publish a nullifier nor create a resource.
+    fn get_read_tags(&self) -> Vec<pallas::Base>;
+    // The resource tree: the pairs of the input nf and the output cm of the
+    // compliances, followed by the pairs of a read tag and zero.
     fn get_resource_merkle_root(&self) -> pallas::Base {
         let mut leaves = vec![];
         self.get_nullifiers()
@@ -21,6 +135,10 @@ pub trait Executable {
                 leaves.push(nf.inner());
                 leaves.push(cm.inner());
             });
+        self.get_read_tags().into_iter().for_each(|tag| {
+            leaves.push(tag);
+            leaves.push(pallas::Base::zero());
+        });
         let tree = ResourceMerkleTreeLeaves::new(leaves);
         tree.root()
     }
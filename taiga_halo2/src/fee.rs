@@ -0,0 +1,117 @@
+//! Transaction fees, for executors to charge for the execution.
+//!
+//! A fee is paid in fee resources: the resources of the fee logic chosen by
+//! the chain, labeled with `FEE_RESOURCE_LABEL`. The transaction consumes
+//! more fee resources than it creates and declares the difference as its
+//! [`Fee`]. The binding signature is checked against the delta commitments
+//! minus the unblinded commitment of the fee, so the transaction only
+//! balances if its surplus of fee resources is exactly the declared fee. The
+//! executor then collects the fee, e.g. by minting it to the block producer.
+use crate::{
+    constant::FEE_RESOURCE_LABEL,
+    delta_commitment::{BalanceSummary, DeltaCommitment},
+    quantity::Quantity,
+    resource::ResourceKind,
+};
+use pasta_curves::pallas;
+
+#[cfg(feature = "serde")]
+use serde;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct Fee {
+    kind: ResourceKind,
+    quantity: Quantity,
+}
+
+impl Fee {
+    /// A fee of `quantity` fee resources of the fee logic `logic`.
+    pub fn new(logic: pallas::Base, quantity: impl Into<Quantity>) -> Self {
+        Self {
+            kind: Self::resource_kind(logic),
+            quantity: quantity.into(),
+        }
+    }
+
+    /// The kind of the fee resources of the fee logic `logic`.
+    pub fn resource_kind(logic: pallas::Base) -> ResourceKind {
+        ResourceKind::new(logic, *FEE_RESOURCE_LABEL)
+    }
+
+    pub fn kind(&self) -> ResourceKind {
+        self.kind
+    }
+
+    pub fn quantity(&self) -> Quantity {
+        self.quantity
+    }
+
+    /// The net quantity the fee leaves unbalanced: the fee resources consumed
+    /// but not created.
+    pub fn balance_summary(&self) -> BalanceSummary {
+        let mut summary = BalanceSummary::default();
+        summary.add(self.kind, i128::from(self.quantity.inner()));
+        summary
+    }
+
+    /// The unblinded delta commitment of the fee, taken out of the delta
+    /// commitments of the transaction before the binding signature is checked.
+    pub fn delta_commitment(&self) -> DeltaCommitment {
+        self.balance_summary().delta_commitment()
+    }
+}
+
+#[test]
+fn test_fee() {
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let logic = pallas::Base::random(&mut OsRng);
+    let fee = Fee::new(logic, 3u64);
+    assert_eq!(fee.kind(), Fee::resource_kind(logic));
+    assert_eq!(fee.balance_summary().get(&fee.kind()), 3);
+    assert_eq!(
+        fee.delta_commitment().inner(),
+        fee.kind().derive_kind() * pallas::Scalar::from(3u64)
+    );
+    assert_ne!(fee.kind(), Fee::resource_kind(logic + pallas::Base::one()));
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::Fee;
+    use crate::nullifier::tests::arb_base;
+    use proptest::prelude::*;
+
+    impl Arbitrary for Fee {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (arb_base(), any::<u64>())
+                .prop_map(|(logic, quantity)| Fee::new(logic, quantity))
+                .boxed()
+        }
+    }
+
+    proptest! {
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_fee_borsh_round_trip(fee: Fee) {
+            let bytes = borsh::to_vec(&fee).unwrap();
+            prop_assert_eq!(borsh::from_slice::<Fee>(&bytes).unwrap(), fee);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_fee_serde_round_trip(fee: Fee) {
+            let json = serde_json::to_string(&fee).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Fee>(&json).unwrap(), fee);
+        }
+    }
+}
@@ -0,0 +1,273 @@
+//! C bindings of the prover and verifier entry points, so that hosts other than
+//! the Elixir NIF can embed Taiga. Build the library as a cdylib with
+//! `cargo rustc --release --features ffi --crate-type cdylib`, the declarations
+//! are in `include/taiga.h`.
+//!
+//! Inputs are borrowed byte buffers in the encodings of `taiga_api`, outputs
+//! are [`TaigaBuffer`]s owned by the library that the host releases with
+//! [`taiga_buffer_free`]. Every function returns a [`TaigaStatus`] and only
+//! writes its output on success.
+use crate::{
+    circuit::resource_logic_bytecode::ApplicationByteCode,
+    compliance::ComplianceInfo,
+    resource_encryption::scan_transaction,
+    taiga_api::{create_shielded_partial_transaction, create_transaction},
+    transaction::Transaction,
+    utils::from_le_bytes,
+};
+use borsh::BorshDeserialize;
+use std::panic::{catch_unwind, UnwindSafe};
+
+/// The status codes of the C API.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaigaStatus {
+    Ok = 0,
+    /// A required pointer is null.
+    NullPointer = 1,
+    /// An input can't be decoded.
+    InvalidInput = 2,
+    /// The proofs of the transaction can't be created.
+    ProvingFailed = 3,
+    /// The transaction doesn't verify.
+    InvalidTransaction = 4,
+    /// The library panicked, this is a bug.
+    Panic = 5,
+}
+
+/// A byte buffer allocated by the library.
+#[repr(C)]
+#[derive(Debug)]
+pub struct TaigaBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl TaigaBuffer {
+    fn new(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        Self { data, len }
+    }
+}
+
+/// Releases a buffer returned by the library. Null buffers are ignored.
+///
+/// # Safety
+/// The buffer must come from the library and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn taiga_buffer_free(buffer: TaigaBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+// Borrows an input buffer, an empty buffer may be null.
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], TaigaStatus> {
+    if len == 0 {
+        Ok(&[])
+    } else if data.is_null() {
+        Err(TaigaStatus::NullPointer)
+    } else {
+        Ok(std::slice::from_raw_parts(data, len))
+    }
+}
+
+fn decode<T: BorshDeserialize>(bytes: &[u8]) -> Result<T, TaigaStatus> {
+    borsh::from_slice(bytes).map_err(|_| TaigaStatus::InvalidInput)
+}
+
+// Runs `f` and writes its output, without unwinding into the host.
+unsafe fn run(
+    out: *mut TaigaBuffer,
+    f: impl FnOnce() -> Result<Vec<u8>, TaigaStatus> + UnwindSafe,
+) -> TaigaStatus {
+    if out.is_null() {
+        return TaigaStatus::NullPointer;
+    }
+    match catch_unwind(f) {
+        Ok(Ok(bytes)) => {
+            out.write(TaigaBuffer::new(bytes));
+            TaigaStatus::Ok
+        }
+        Ok(Err(status)) => status,
+        Err(_) => TaigaStatus::Panic,
+    }
+}
+
+/// Proves a shielded partial transaction and composes it into a transaction.
+///
+/// `compliances`, `input_apps` and `output_apps` are the borsh encodings of a
+/// `Vec<ComplianceInfo>` and two `Vec<ApplicationByteCode>` of the same
+/// length. `out` receives the canonical encoding of the transaction, see
+/// `Transaction::to_bytes`.
+///
+/// # Safety
+/// Every pointer must be valid for its length, `out` must be writable.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn taiga_create_shielded_transaction(
+    compliances: *const u8,
+    compliances_len: usize,
+    input_apps: *const u8,
+    input_apps_len: usize,
+    output_apps: *const u8,
+    output_apps_len: usize,
+    hints: *const u8,
+    hints_len: usize,
+    out: *mut TaigaBuffer,
+) -> TaigaStatus {
+    let inputs: Result<_, TaigaStatus> = (|| {
+        Ok((
+            input(compliances, compliances_len)?,
+            input(input_apps, input_apps_len)?,
+            input(output_apps, output_apps_len)?,
+            input(hints, hints_len)?,
+        ))
+    })();
+    let (compliances, input_apps, output_apps, hints) = match inputs {
+        Ok(inputs) => inputs,
+        Err(status) => return status,
+    };
+    run(out, || {
+        let compliances: Vec<ComplianceInfo> = decode(compliances)?;
+        let input_apps: Vec<ApplicationByteCode> = decode(input_apps)?;
+        let output_apps: Vec<ApplicationByteCode> = decode(output_apps)?;
+        if compliances.len() != input_apps.len() || compliances.len() != output_apps.len() {
+            return Err(TaigaStatus::InvalidInput);
+        }
+        let ptx = create_shielded_partial_transaction(
+            compliances,
+            input_apps,
+            output_apps,
+            hints.to_vec(),
+        )
+        .map_err(|_| TaigaStatus::ProvingFailed)?;
+        let tx = create_transaction(vec![ptx]).map_err(|_| TaigaStatus::ProvingFailed)?;
+        Ok(tx.to_bytes())
+    })
+}
+
+/// Verifies a transaction in the canonical encoding. `out` receives the borsh
+/// encoding of its `TransactionResult`.
+///
+/// # Safety
+/// `tx` must be valid for `tx_len` bytes, `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn taiga_verify_transaction(
+    tx: *const u8,
+    tx_len: usize,
+    out: *mut TaigaBuffer,
+) -> TaigaStatus {
+    let tx = match input(tx, tx_len) {
+        Ok(tx) => tx,
+        Err(status) => return status,
+    };
+    run(out, || {
+        let tx = Transaction::from_bytes(tx).map_err(|_| TaigaStatus::InvalidInput)?;
+        let result = tx.execute().map_err(|_| TaigaStatus::InvalidTransaction)?;
+        Ok(borsh::to_vec(&result).unwrap())
+    })
+}
+
+/// Decrypts the output resources of a transaction sent to a viewing key, the
+/// 32-byte little-endian receiver secret key. `out` receives the borsh
+/// encoding of the `Vec<Resource>`, empty if none of them is for the key.
+///
+/// # Safety
+/// `tx` must be valid for `tx_len` bytes, `viewing_key` for 32 bytes and `out`
+/// must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn taiga_decrypt_resource(
+    tx: *const u8,
+    tx_len: usize,
+    viewing_key: *const u8,
+    out: *mut TaigaBuffer,
+) -> TaigaStatus {
+    let inputs: Result<_, TaigaStatus> = (|| Ok((input(tx, tx_len)?, input(viewing_key, 32)?)))();
+    let (tx, viewing_key) = match inputs {
+        Ok(inputs) => inputs,
+        Err(status) => return status,
+    };
+    run(out, || {
+        let tx = Transaction::from_bytes(tx).map_err(|_| TaigaStatus::InvalidInput)?;
+        let viewing_key = from_le_bytes(viewing_key).ok_or(TaigaStatus::InvalidInput)?;
+        Ok(borsh::to_vec(&scan_transaction(&tx, viewing_key)).unwrap())
+    })
+}
+
+#[test]
+fn test_ffi() {
+    use crate::{resource::Resource, transaction::TransactionResult};
+    use std::ptr;
+
+    let tx = {
+        use crate::transaction::testing::create_shielded_ptx_bundle;
+        Transaction::build(
+            rand::rngs::OsRng,
+            create_shielded_ptx_bundle(1),
+            Default::default(),
+        )
+        .unwrap()
+        .to_bytes()
+    };
+    let mut out = TaigaBuffer {
+        data: ptr::null_mut(),
+        len: 0,
+    };
+    unsafe {
+        assert_eq!(
+            taiga_verify_transaction(tx.as_ptr(), tx.len(), &mut out),
+            TaigaStatus::Ok
+        );
+        let result: TransactionResult =
+            borsh::from_slice(std::slice::from_raw_parts(out.data, out.len)).unwrap();
+        assert_eq!(result.nullifiers.len(), 2);
+        taiga_buffer_free(out);
+
+        let viewing_key = [7u8; 32];
+        let mut out = TaigaBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            taiga_decrypt_resource(tx.as_ptr(), tx.len(), viewing_key.as_ptr(), &mut out),
+            TaigaStatus::Ok
+        );
+        let resources: Vec<Resource> =
+            borsh::from_slice(std::slice::from_raw_parts(out.data, out.len)).unwrap();
+        assert!(resources.is_empty());
+        taiga_buffer_free(out);
+
+        // Errors
+        assert_eq!(
+            taiga_verify_transaction(tx.as_ptr(), tx.len(), ptr::null_mut()),
+            TaigaStatus::NullPointer
+        );
+        assert_eq!(
+            taiga_verify_transaction(ptr::null(), 1, &mut out),
+            TaigaStatus::NullPointer
+        );
+        assert_eq!(
+            taiga_verify_transaction(tx.as_ptr(), 1, &mut out),
+            TaigaStatus::InvalidInput
+        );
+        assert_eq!(
+            taiga_create_shielded_transaction(
+                [0u8; 4].as_ptr(),
+                4,
+                ptr::null(),
+                0,
+                ptr::null(),
+                0,
+                ptr::null(),
+                0,
+                &mut out,
+            ),
+            TaigaStatus::InvalidInput
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Canonical encoding of application data into field elements.
+//!
+//! Every primitive takes one field element and arrays take the elements of
+//! their items, so the encoding of a type has a fixed length and the structs
+//! built with [`impl_field_encoding!`] can be nested. The resource value of
+//! an application state is the digest of its encoding, see
+//! [`ToFieldElements::to_resource_value`].
+use crate::utils::poseidon_hash;
+use ff::PrimeField;
+use pasta_curves::pallas;
+
+pub trait ToFieldElements {
+    /// The number of field elements of the encoding.
+    const FIELD_ELEMENTS_NUM: usize;
+
+    fn encode_to(&self, elements: &mut Vec<pallas::Base>);
+
+    fn to_field_elements(&self) -> Vec<pallas::Base> {
+        let mut elements = Vec::with_capacity(Self::FIELD_ELEMENTS_NUM);
+        self.encode_to(&mut elements);
+        elements
+    }
+
+    /// The encoding itself if it's a single element, the Poseidon hash chain
+    /// of the elements otherwise.
+    fn to_resource_value(&self) -> pallas::Base {
+        let elements = self.to_field_elements();
+        match elements.as_slice() {
+            [element] => *element,
+            _ => elements.into_iter().fold(
+                pallas::Base::from(Self::FIELD_ELEMENTS_NUM as u64),
+                poseidon_hash,
+            ),
+        }
+    }
+}
+
+pub trait FromFieldElements: Sized {
+    /// Decodes the value from the first elements and advances the slice. Fails
+    /// if the elements are not a canonical encoding.
+    fn decode_from(elements: &mut &[pallas::Base]) -> Option<Self>;
+
+    /// Decodes the value from exactly all the elements.
+    fn from_field_elements(mut elements: &[pallas::Base]) -> Option<Self> {
+        let value = Self::decode_from(&mut elements)?;
+        elements.is_empty().then_some(value)
+    }
+}
+
+fn next_element(elements: &mut &[pallas::Base]) -> Option<pallas::Base> {
+    let (first, rest) = elements.split_first()?;
+    *elements = rest;
+    Some(*first)
+}
+
+impl ToFieldElements for pallas::Base {
+    const FIELD_ELEMENTS_NUM: usize = 1;
+
+    fn encode_to(&self, elements: &mut Vec<pallas::Base>) {
+        elements.push(*self);
+    }
+}
+
+impl FromFieldElements for pallas::Base {
+    fn decode_from(elements: &mut &[pallas::Base]) -> Option<Self> {
+        next_element(elements)
+    }
+}
+
+macro_rules! impl_uint_field_encoding {
+    ($($ty:ty),*) => {
+        $(
+            impl ToFieldElements for $ty {
+                const FIELD_ELEMENTS_NUM: usize = 1;
+
+                fn encode_to(&self, elements: &mut Vec<pallas::Base>) {
+                    let mut repr = [0u8; 32];
+                    repr[..std::mem::size_of::<$ty>()].copy_from_slice(&self.to_le_bytes());
+                    elements.push(pallas::Base::from_repr(repr).unwrap());
+                }
+            }
+
+            impl FromFieldElements for $ty {
+                fn decode_from(elements: &mut &[pallas::Base]) -> Option<Self> {
+                    let repr = next_element(elements)?.to_repr();
+                    let (low, high) = repr.split_at(std::mem::size_of::<$ty>());
+                    if high.iter().any(|b| *b != 0) {
+                        return None;
+                    }
+                    Some(<$ty>::from_le_bytes(low.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+impl_uint_field_encoding!(u8, u16, u32, u64, u128);
+
+impl ToFieldElements for bool {
+    const FIELD_ELEMENTS_NUM: usize = 1;
+
+    fn encode_to(&self, elements: &mut Vec<pallas::Base>) {
+        (*self as u8).encode_to(elements);
+    }
+}
+
+impl FromFieldElements for bool {
+    fn decode_from(elements: &mut &[pallas::Base]) -> Option<Self> {
+        match u8::decode_from(elements)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl<T: ToFieldElements, const N: usize> ToFieldElements for [T; N] {
+    const FIELD_ELEMENTS_NUM: usize = T::FIELD_ELEMENTS_NUM * N;
+
+    fn encode_to(&self, elements: &mut Vec<pallas::Base>) {
+        self.iter().for_each(|item| item.encode_to(elements));
+    }
+}
+
+impl<T: FromFieldElements, const N: usize> FromFieldElements for [T; N] {
+    fn decode_from(elements: &mut &[pallas::Base]) -> Option<Self> {
+        let items = (0..N)
+            .map(|_| T::decode_from(elements))
+            .collect::<Option<Vec<_>>>()?;
+        items.try_into().ok()
+    }
+}
+
+/// Implements [`ToFieldElements`] and [`FromFieldElements`] for a struct with
+/// the encodings of its fields, in order.
+///
+/// ```ignore
+/// pub struct SudokuState {
+///     pub state: [[u8; 9]; 9],
+/// }
+///
+/// impl_field_encoding!(SudokuState { state: [[u8; 9]; 9] });
+/// ```
+#[macro_export]
+macro_rules! impl_field_encoding {
+    ($name:ident { $($field:ident: $field_ty:ty),* $(,)? }) => {
+        impl $crate::field_encoding::ToFieldElements for $name {
+            const FIELD_ELEMENTS_NUM: usize =
+                0 $(+ <$field_ty as $crate::field_encoding::ToFieldElements>::FIELD_ELEMENTS_NUM)*;
+
+            fn encode_to(&self, elements: &mut Vec<pasta_curves::pallas::Base>) {
+                $($crate::field_encoding::ToFieldElements::encode_to(&self.$field, elements);)*
+            }
+        }
+
+        impl $crate::field_encoding::FromFieldElements for $name {
+            fn decode_from(elements: &mut &[pasta_curves::pallas::Base]) -> Option<Self> {
+                Some(Self {
+                    $($field: <$field_ty as $crate::field_encoding::FromFieldElements>::decode_from(
+                        elements,
+                    )?,)*
+                })
+            }
+        }
+    };
+}
+
+#[test]
+fn test_field_encoding() {
+    use ff::Field;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Move {
+        position: [u8; 2],
+        value: u8,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Game {
+        board: [[u8; 3]; 3],
+        last_move: Move,
+        finished: bool,
+        nonce: u64,
+    }
+
+    impl_field_encoding!(Move {
+        position: [u8; 2],
+        value: u8,
+    });
+    impl_field_encoding!(Game {
+        board: [[u8; 3]; 3],
+        last_move: Move,
+        finished: bool,
+        nonce: u64,
+    });
+
+    let game = Game {
+        board: [[1, 2, 0], [0, 2, 1], [0, 0, 1]],
+        last_move: Move {
+            position: [2, 2],
+            value: 1,
+        },
+        finished: false,
+        nonce: u64::MAX,
+    };
+    let elements = game.to_field_elements();
+    assert_eq!(elements.len(), Game::FIELD_ELEMENTS_NUM);
+    assert_eq!(Game::FIELD_ELEMENTS_NUM, 9 + 3 + 1 + 1);
+    assert_eq!(Game::from_field_elements(&elements), Some(game));
+
+    // Values out of the range of the type are not canonical
+    let mut invalid = elements.clone();
+    invalid[0] = pallas::Base::from(256);
+    assert!(Game::from_field_elements(&invalid).is_none());
+    let mut invalid = elements.clone();
+    invalid[12] = pallas::Base::from(2);
+    assert!(Game::from_field_elements(&invalid).is_none());
+    assert!(Game::from_field_elements(&elements[1..]).is_none());
+    assert!(Game::from_field_elements(&[elements, vec![pallas::Base::zero()]].concat()).is_none());
+
+    assert_eq!(7u64.to_resource_value(), pallas::Base::from(7));
+}
@@ -0,0 +1,119 @@
+//! Valid serialized transactions, with their proofs, for the decoders and
+//! verifiers of other implementations, e.g. the node, to test against without
+//! running the prover.
+//!
+//! The fixtures are the borsh encoding of the transaction, see
+//! `taiga_api::transaction_serialize`, in `fixtures/<name>.tx`. They are
+//! generated deterministically from `FIXTURE_SEED` by the `export_fixtures`
+//! example and must be exported again whenever the circuits, the params or the
+//! encoding change, which `verify_exported_fixtures` catches. The test loads
+//! them at runtime and skips the fixtures that are not exported yet.
+use crate::{
+    apps::{intent, token},
+    builder::TransactionBuilder,
+    circuit::resource_logic_examples::{
+        TrivialResourceLogicCircuit, COMPRESSED_TRIVIAL_RESOURCE_LOGIC_VK,
+    },
+    constant::TAIGA_COMMITMENT_TREE_DEPTH,
+    error::TransactionError,
+    merkle_tree::MerklePath,
+    nullifier::NullifierKeyContainer,
+    taiga_api::transaction_serialize,
+    transaction::Transaction,
+};
+use ff::Field;
+use pasta_curves::pallas;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::path::PathBuf;
+
+pub const FIXTURE_SEED: [u8; 32] = [0; 32];
+
+/// The names of the fixtures, in the order of `generate_fixtures`.
+pub const FIXTURE_NAMES: [&str; 2] = ["token_transfer", "intent"];
+
+pub fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join(format!("{}.tx", name))
+}
+
+/// The exported fixture, `None` if it is not exported yet.
+pub fn load_fixture(name: &str) -> Option<Vec<u8>> {
+    std::fs::read(fixture_path(name)).ok()
+}
+
+/// Builds the fixtures from `FIXTURE_SEED`: a transfer of the token app and an
+/// intent consumed by a solver in a later ptx.
+pub fn generate_fixtures() -> Result<Vec<(&'static str, Transaction)>, TransactionError> {
+    let mut rng = ChaCha20Rng::from_seed(FIXTURE_SEED);
+
+    let auth_sk = pallas::Scalar::random(&mut rng);
+    let auth = token::TokenAuthorization::from_sk_vk(&auth_sk, &token::COMPRESSED_TOKEN_AUTH_VK);
+    let nk = pallas::Base::random(&mut rng);
+    let resource = token::Token::new("btc".to_string(), 5u64)
+        .create_random_input_token_resource(&mut rng, nk, &auth);
+    let receiver_npk = NullifierKeyContainer::from_key(pallas::Base::random(&mut rng)).get_npk();
+    let mut builder = TransactionBuilder::new();
+    token::transfer(
+        &mut builder,
+        &mut rng,
+        &resource,
+        MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+        auth_sk,
+        auth,
+        receiver_npk,
+    );
+    let token_transfer = builder.finalize(&mut rng)?;
+
+    let intent = intent::IntentResource::new(
+        *COMPRESSED_TRIVIAL_RESOURCE_LOGIC_VK,
+        pallas::Base::random(&mut rng),
+    );
+    let mut user = TransactionBuilder::new();
+    intent::create(&mut user, &mut rng, intent);
+    let user_ptx = user.finalize_partial_transaction(&mut rng)?;
+    let mut solver = TransactionBuilder::new();
+    intent::consume(&mut solver, &mut rng, intent, |witness| {
+        TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path()).to_bytecode()
+    });
+    let intent = solver
+        .add_partial_transaction(user_ptx)
+        .finalize(&mut rng)?;
+
+    Ok(FIXTURE_NAMES
+        .into_iter()
+        .zip([token_transfer, intent])
+        .collect())
+}
+
+/// Generates the fixtures, checks them and writes them to `fixture_path`.
+pub fn export_fixtures() -> Result<(), TransactionError> {
+    for (name, tx) in generate_fixtures()? {
+        tx.execute()?;
+        let path = fixture_path(name);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, transaction_serialize(&tx)?)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn verify_exported_fixtures() {
+    use crate::taiga_api::{transaction_deserialize, verify_transaction};
+
+    for name in FIXTURE_NAMES {
+        let Some(bytes) = load_fixture(name) else {
+            eprintln!(
+                "skipping fixture {}: not exported, see export_fixtures",
+                name
+            );
+            continue;
+        };
+        let result = verify_transaction(bytes.clone());
+        assert!(result.is_ok(), "fixture {} is invalid: {:?}", name, result);
+        // The encoding is canonical
+        let tx = transaction_deserialize(bytes.clone()).unwrap();
+        assert_eq!(transaction_serialize(&tx).unwrap(), bytes);
+    }
+}
@@ -0,0 +1,278 @@
+//! A hash-chained log of the resources a wallet sent and received.
+//!
+//! Each entry hashes the previous one, so a record can't be dropped or
+//! reordered without breaking the chain. The wallet periodically anchors the
+//! head of the chain to a resource it owns, the value of the resource commits
+//! to the head. Once the anchor resource is in the commitment tree, the wallet
+//! can't present an auditor a shorter or rewritten history up to that anchor.
+use crate::{
+    domains::HISTORY_LOG_PERSONALIZATION,
+    error::TransactionError,
+    resource::{Resource, ResourceCommitment},
+};
+use blake2b_simd::Params as Blake2bParams;
+use ff::FromUniformBytes;
+use pasta_curves::pallas;
+
+#[cfg(feature = "serde")]
+use serde;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HistoryDirection {
+    Sent,
+    Received,
+}
+
+/// A resource sent or received by the wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryRecord {
+    pub direction: HistoryDirection,
+    pub cm: ResourceCommitment,
+    /// The id of the transaction, e.g. its binding sighash.
+    pub tx_id: [u8; 32],
+    /// The height of the block including the transaction.
+    pub height: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryEntry {
+    pub record: HistoryRecord,
+    /// The head of the chain after the record.
+    pub hash: [u8; 32],
+}
+
+/// The head of the log at the time it was anchored, and the commitment of the
+/// anchor resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryAnchor {
+    /// The number of entries covered by the anchor.
+    pub len: usize,
+    pub head: [u8; 32],
+    pub cm: ResourceCommitment,
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryLog {
+    entries: Vec<HistoryEntry>,
+    anchors: Vec<HistoryAnchor>,
+}
+
+impl HistoryRecord {
+    pub fn to_bytes(&self) -> [u8; 73] {
+        let mut bytes = [0u8; 73];
+        bytes[0] = match self.direction {
+            HistoryDirection::Sent => 0,
+            HistoryDirection::Received => 1,
+        };
+        bytes[1..33].copy_from_slice(&self.cm.to_bytes());
+        bytes[33..65].copy_from_slice(&self.tx_id);
+        bytes[65..73].copy_from_slice(&self.height.to_le_bytes());
+        bytes
+    }
+
+    /// The head of the chain after appending the record to `prev`.
+    pub fn chain(&self, prev: &[u8; 32]) -> [u8; 32] {
+        let mut h = Blake2bParams::new()
+            .hash_length(32)
+            .personal(HISTORY_LOG_PERSONALIZATION)
+            .to_state();
+        h.update(prev);
+        h.update(&self.to_bytes());
+        h.finalize().as_bytes().try_into().unwrap()
+    }
+}
+
+impl HistoryAnchor {
+    /// The value of the anchor resource committing to `head`. The 64-byte
+    /// digest is domain-separated from the 32-byte chain hashes by its length.
+    pub fn value_of(head: &[u8; 32]) -> pallas::Base {
+        let hash = Blake2bParams::new()
+            .hash_length(64)
+            .personal(HISTORY_LOG_PERSONALIZATION)
+            .hash(head);
+        pallas::Base::from_uniform_bytes(hash.as_array())
+    }
+}
+
+impl HistoryLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn anchors(&self) -> &[HistoryAnchor] {
+        &self.anchors
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The head of the chain, zero for an empty log.
+    pub fn head(&self) -> [u8; 32] {
+        self.head_at(self.entries.len()).unwrap()
+    }
+
+    /// The head of the chain after the first `len` entries.
+    fn head_at(&self, len: usize) -> Option<[u8; 32]> {
+        match len {
+            0 => Some([0u8; 32]),
+            _ => self.entries.get(len - 1).map(|entry| entry.hash),
+        }
+    }
+
+    /// Appends a record and returns the new head. The heights of the records
+    /// must not decrease.
+    pub fn append(&mut self, record: HistoryRecord) -> Result<[u8; 32], TransactionError> {
+        if let Some(last) = self.entries.last() {
+            if record.height < last.record.height {
+                return Err(TransactionError::InvalidHistoryLog);
+            }
+        }
+        let hash = record.chain(&self.head());
+        self.entries.push(HistoryEntry { record, hash });
+        Ok(hash)
+    }
+
+    /// The value the next anchor resource must carry.
+    pub fn anchor_value(&self) -> pallas::Base {
+        HistoryAnchor::value_of(&self.head())
+    }
+
+    /// Records `resource` as the anchor of the current head. The resource must
+    /// carry the `anchor_value` and be owned by the wallet, it's up to the
+    /// wallet to create it in a transaction.
+    pub fn anchor(&mut self, resource: &Resource) -> Result<HistoryAnchor, TransactionError> {
+        if resource.value != self.anchor_value() {
+            return Err(TransactionError::HistoryAnchorMismatch);
+        }
+        let anchor = HistoryAnchor {
+            len: self.entries.len(),
+            head: self.head(),
+            cm: resource.commitment(),
+        };
+        self.anchors.push(anchor);
+        Ok(anchor)
+    }
+
+    /// The most recent anchor, if any.
+    pub fn last_anchor(&self) -> Option<&HistoryAnchor> {
+        self.anchors.last()
+    }
+
+    pub fn contains_tx(&self, tx_id: &[u8; 32]) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.record.tx_id == *tx_id)
+    }
+
+    /// Recomputes the chain and checks the heights and the recorded anchors.
+    pub fn verify(&self) -> Result<(), TransactionError> {
+        let mut prev = [0u8; 32];
+        let mut height = 0;
+        for entry in self.entries.iter() {
+            if entry.record.height < height || entry.record.chain(&prev) != entry.hash {
+                return Err(TransactionError::InvalidHistoryLog);
+            }
+            prev = entry.hash;
+            height = entry.record.height;
+        }
+        for anchor in self.anchors.iter() {
+            if self.head_at(anchor.len) != Some(anchor.head) {
+                return Err(TransactionError::HistoryAnchorMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `resource` anchors the log at `anchor`. An auditor checks
+    /// in addition that the commitment of the resource is in the commitment
+    /// tree, the history up to the anchor is then complete.
+    pub fn verify_anchor(
+        &self,
+        anchor: &HistoryAnchor,
+        resource: &Resource,
+    ) -> Result<(), TransactionError> {
+        self.verify()?;
+        if resource.commitment() != anchor.cm
+            || resource.value != HistoryAnchor::value_of(&anchor.head)
+            || self.head_at(anchor.len) != Some(anchor.head)
+        {
+            return Err(TransactionError::HistoryAnchorMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_history_log() {
+    use crate::resource::tests::random_resource;
+    use rand::rngs::OsRng;
+    use rand::RngCore;
+
+    fn record<R: RngCore>(mut rng: R, direction: HistoryDirection, height: u64) -> HistoryRecord {
+        let mut tx_id = [0u8; 32];
+        rng.fill_bytes(&mut tx_id);
+        HistoryRecord {
+            direction,
+            cm: random_resource(&mut rng).commitment(),
+            tx_id,
+            height,
+        }
+    }
+
+    let mut rng = OsRng;
+    let records = [
+        record(&mut rng, HistoryDirection::Received, 1),
+        record(&mut rng, HistoryDirection::Sent, 3),
+        record(&mut rng, HistoryDirection::Received, 3),
+    ];
+
+    let mut log = HistoryLog::new();
+    log.append(records[0]).unwrap();
+    log.append(records[1]).unwrap();
+    let mut resource = random_resource(&mut rng);
+    assert!(matches!(
+        log.anchor(&resource),
+        Err(TransactionError::HistoryAnchorMismatch)
+    ));
+    resource.value = log.anchor_value();
+    let anchor = log.anchor(&resource).unwrap();
+    log.append(records[2]).unwrap();
+    assert!(log.contains_tx(&records[1].tx_id));
+    assert!(log.verify().is_ok());
+    assert!(log.verify_anchor(&anchor, &resource).is_ok());
+
+    // Heights must not decrease
+    let mut late = record(&mut rng, HistoryDirection::Sent, 2);
+    assert!(matches!(
+        log.append(late),
+        Err(TransactionError::InvalidHistoryLog)
+    ));
+
+    // Dropping an anchored record breaks the anchor
+    let mut dropped = HistoryLog::new();
+    dropped.append(records[0]).unwrap();
+    assert!(dropped.verify_anchor(&anchor, &resource).is_err());
+
+    // Rewriting a record breaks the chain
+    let mut tampered = log.clone();
+    late.height = 3;
+    tampered.entries[1].record = late;
+    assert!(matches!(
+        tampered.verify(),
+        Err(TransactionError::InvalidHistoryLog)
+    ));
+}
@@ -0,0 +1,277 @@
+//! Deterministic key hierarchy of wallets.
+//!
+//! A seed, e.g. the one of a BIP-39 mnemonic, derives a master spending key,
+//! from which the account keys are derived along hardened paths in the
+//! manner of ZIP-32. A spending key derives the nullifier key, whose
+//! commitment npk goes in the resources, and the encryption secret key that
+//! the resources and memos sent to the account are encrypted to. The viewing
+//! key holds both, so that a watch-only wallet finds the resources of the
+//! account and detects when they are spent.
+//!
+//! The key structs are zeroized on drop, and so is the nullifier key they
+//! return, see [`Secret`]. The other field elements they return are `Copy`
+//! and are not, so keep the keys rather than their outputs around.
+use crate::{
+    constant::{
+        GENERATOR, KEY_CHILD_PERSONALIZATION, KEY_MASTER_PERSONALIZATION, PRF_EXPAND_ENCRYPTION_SK,
+        PRF_EXPAND_NK, PRF_EXPAND_PERSONALIZATION,
+    },
+    error::TransactionError,
+    nullifier::NullifierKeyContainer,
+    resource::Resource,
+    resource_encryption::{scan_transaction, Address, Diversifier},
+    secret::Secret,
+    transaction::Transaction,
+    utils::mod_r_p,
+};
+use blake2b_simd::Params as Blake2bParams;
+use ff::FromUniformBytes;
+use pasta_curves::{group::Curve, pallas};
+use std::fmt;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Child indices at or above this one are hardened. Only hardened
+/// derivation is supported: the parent key is needed to derive a child key.
+pub const HARDENED_KEY_START: u32 = 1 << 31;
+
+/// A spending key and the chain code deriving its children.
+#[derive(Clone)]
+pub struct ExtendedSpendingKey {
+    key: [u8; 32],
+    chain_code: [u8; 32],
+}
+
+/// The keys to find the resources of an account and compute their
+/// nullifiers, without the seed of the account.
+#[derive(Clone)]
+pub struct ViewingKey {
+    nk: [u8; 64],
+    encryption_sk: [u8; 64],
+}
+
+impl ExtendedSpendingKey {
+    /// Derives the master key. The seed must be 32 to 252 bytes long, as in
+    /// BIP-32.
+    pub fn master(seed: &[u8]) -> Result<Self, TransactionError> {
+        if !(32..=252).contains(&seed.len()) {
+            return Err(TransactionError::InvalidSeed);
+        }
+        let hash = Blake2bParams::new()
+            .hash_length(64)
+            .personal(KEY_MASTER_PERSONALIZATION)
+            .hash(seed);
+        Ok(Self::from_hash(hash.as_array()))
+    }
+
+    /// Derives the hardened child at `index`, i.e. at `index + 2^31`. The
+    /// index must be less than `HARDENED_KEY_START`, so that no two indices
+    /// derive the same child.
+    pub fn derive_child(&self, index: u32) -> Result<Self, TransactionError> {
+        if index >= HARDENED_KEY_START {
+            return Err(TransactionError::InvalidKeyIndex(index));
+        }
+        let index = index | HARDENED_KEY_START;
+        let hash = Blake2bParams::new()
+            .hash_length(64)
+            .personal(KEY_CHILD_PERSONALIZATION)
+            .key(&self.chain_code)
+            .to_state()
+            .update(&self.key)
+            .update(&index.to_le_bytes())
+            .finalize();
+        Ok(Self::from_hash(hash.as_array()))
+    }
+
+    /// Derives the key at the hardened path, e.g. `[account]`.
+    pub fn derive_path(&self, path: &[u32]) -> Result<Self, TransactionError> {
+        path.iter()
+            .try_fold(self.clone(), |key, index| key.derive_child(*index))
+    }
+
+    /// The spending key of the account `m/account'` of the seed.
+    pub fn account(seed: &[u8], account: u32) -> Result<Self, TransactionError> {
+        Self::master(seed)?.derive_child(account)
+    }
+
+    fn from_hash(hash: &[u8; 64]) -> Self {
+        let mut key = [0u8; 32];
+        let mut chain_code = [0u8; 32];
+        key.copy_from_slice(&hash[..32]);
+        chain_code.copy_from_slice(&hash[32..]);
+        Self { key, chain_code }
+    }
+
+    fn prf_expand(&self, tag: u8) -> [u8; 64] {
+        let hash = Blake2bParams::new()
+            .hash_length(64)
+            .personal(PRF_EXPAND_PERSONALIZATION)
+            .to_state()
+            .update(&[tag])
+            .update(&self.key)
+            .finalize();
+        *hash.as_array()
+    }
+
+    pub fn viewing_key(&self) -> ViewingKey {
+        ViewingKey {
+            nk: self.prf_expand(PRF_EXPAND_NK),
+            encryption_sk: self.prf_expand(PRF_EXPAND_ENCRYPTION_SK),
+        }
+    }
+
+    pub fn nk(&self) -> Secret<NullifierKeyContainer> {
+        self.viewing_key().nk()
+    }
+
+    pub fn npk(&self) -> pallas::Base {
+        self.viewing_key().npk()
+    }
+
+    pub fn encryption_sk(&self) -> pallas::Base {
+        self.viewing_key().encryption_sk()
+    }
+}
+
+impl ViewingKey {
+    /// The nullifier key of the account, to put in the resources it spends.
+    pub fn nk(&self) -> Secret<NullifierKeyContainer> {
+        Secret::new(NullifierKeyContainer::from_key(
+            pallas::Base::from_uniform_bytes(&self.nk),
+        ))
+    }
+
+    /// The nullifier key commitment of the resources of the account.
+    pub fn npk(&self) -> pallas::Base {
+        self.nk().expose_secret().get_npk()
+    }
+
+    /// The secret key decrypting the resources and memos of the account, see
+    /// `scan_transaction`.
+    pub fn encryption_sk(&self) -> pallas::Base {
+        pallas::Base::from_uniform_bytes(&self.encryption_sk)
+    }
+
+    /// The public key the resources and memos of the account are encrypted
+    /// to.
+    pub fn encryption_pk(&self) -> pallas::Point {
+        GENERATOR.to_curve() * mod_r_p(self.encryption_sk())
+    }
+
+    /// The diversified address of the account at `diversifier`. The
+    /// addresses of an account are unlinkable, and all scanned with the
+    /// encryption secret key.
+    pub fn address(&self, diversifier: Diversifier) -> Address {
+        Address::derive(&self.encryption_sk(), diversifier)
+    }
+
+    /// Finds the resources the transaction creates for the account.
+    pub fn scan(&self, tx: &Transaction) -> Vec<Resource> {
+        scan_transaction(tx, self.encryption_sk())
+    }
+}
+
+impl Drop for ExtendedSpendingKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.chain_code.zeroize();
+    }
+}
+
+impl Drop for ViewingKey {
+    fn drop(&mut self) {
+        self.nk.zeroize();
+        self.encryption_sk.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for ExtendedSpendingKey {}
+
+impl ZeroizeOnDrop for ViewingKey {}
+
+impl ConstantTimeEq for ExtendedSpendingKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.key[..].ct_eq(&other.key[..]) & self.chain_code[..].ct_eq(&other.chain_code[..])
+    }
+}
+
+impl PartialEq for ExtendedSpendingKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for ExtendedSpendingKey {}
+
+impl ConstantTimeEq for ViewingKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.nk[..].ct_eq(&other.nk[..]) & self.encryption_sk[..].ct_eq(&other.encryption_sk[..])
+    }
+}
+
+impl PartialEq for ViewingKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for ViewingKey {}
+
+impl fmt::Debug for ExtendedSpendingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ExtendedSpendingKey(..)")
+    }
+}
+
+impl fmt::Debug for ViewingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ViewingKey(..)")
+    }
+}
+
+#[test]
+fn test_key_derivation() {
+    let seed = [7u8; 32];
+    let account = ExtendedSpendingKey::account(&seed, 0).unwrap();
+
+    // Restoring from the seed gives the same keys
+    let master = ExtendedSpendingKey::master(&seed).unwrap();
+    assert_eq!(master.derive_path(&[0]).unwrap(), account);
+    assert_eq!(account.npk(), account.viewing_key().npk());
+    assert!(account.nk().expose_secret().possesses(account.npk()));
+
+    // The paths, the seeds and the keys of an account are independent
+    let other = master.derive_path(&[1]).unwrap();
+    assert_ne!(other.npk(), account.npk());
+    assert_ne!(other.encryption_sk(), account.encryption_sk());
+    assert_ne!(master.derive_path(&[0, 0]).unwrap(), account);
+    assert_ne!(
+        ExtendedSpendingKey::account(&[8u8; 32], 0).unwrap().npk(),
+        account.npk()
+    );
+    assert_ne!(
+        account.nk().expose_secret().get_nk().unwrap(),
+        account.encryption_sk()
+    );
+
+    // An index already hardened would alias the child at index - 2^31
+    for index in [HARDENED_KEY_START, u32::MAX] {
+        assert!(matches!(
+            master.derive_child(index),
+            Err(TransactionError::InvalidKeyIndex(i)) if i == index
+        ));
+    }
+    assert!(matches!(
+        master.derive_path(&[0, HARDENED_KEY_START]),
+        Err(TransactionError::InvalidKeyIndex(_))
+    ));
+
+    assert!(matches!(
+        ExtendedSpendingKey::master(&[0u8; 16]),
+        Err(TransactionError::InvalidSeed)
+    ));
+    assert!(matches!(
+        ExtendedSpendingKey::master(&[0u8; 253]),
+        Err(TransactionError::InvalidSeed)
+    ));
+}
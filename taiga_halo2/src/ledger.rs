@@ -0,0 +1,844 @@
+//! The ledger state of an executor node: the published nullifiers and the
+//! created resource commitments.
+//!
+//! Executor nodes apply transactions from several worker threads, so the
+//! state is shared by reference and locks internally. Applying a transaction
+//! result is atomic: either all its nullifiers and commitments are recorded,
+//! or none of them is.
+//!
+//! The issuers of resource kinds authorize the conversions into their kinds,
+//! a transaction applying a conversion rule is only recorded once the issuer
+//! of the target kind signed the rule.
+//!
+//! The ledger is at an epoch, set by the executor, and only records the
+//! transactions whose compliances are proven at it, so that the logics of
+//! timelocked resources check their window against the epoch of the executor.
+//! Likewise it enforces a blacklist, set by the executor, and only records the
+//! transactions whose blacklist logics are proven against it.
+//!
+//! Solvers build transactions from a [`LedgerView`], a frozen snapshot of the
+//! state, so that the anchor they build against doesn't move while the
+//! executor keeps applying blocks. The ledger keeps the last
+//! [`ANCHOR_HISTORY_SIZE`] roots of the commitment tree and only records the
+//! transactions proven against one of them.
+use crate::{
+    constant::TAIGA_COMMITMENT_TREE_DEPTH,
+    conversion::{ConversionAuthorization, ConversionRuleId, ConversionVerificationKey},
+    error::TransactionError,
+    executable::{StateView, StateWrite},
+    fee::Fee,
+    merkle_tree::{commitment_tree_path, Anchor, CommitmentFrontier, MerklePath, Node},
+    nullifier::Nullifier,
+    resource::{ResourceCommitment, ResourceKind},
+    resource_tree::SortedMerkleTree,
+    transaction::{Transaction, TransactionResult},
+};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// The number of past roots of the commitment tree a transaction can still be
+/// proven against, besides the current one.
+pub const ANCHOR_HISTORY_SIZE: usize = 100;
+
+#[derive(Debug, Default)]
+pub struct LedgerState {
+    inner: RwLock<LedgerView>,
+    // The changes made since each checkpoint, the last one is the latest.
+    // Locked after `inner`.
+    checkpoints: Mutex<VecDeque<Checkpoint>>,
+}
+
+// The changes made to the ledger since a checkpoint, undone by `rollback`.
+// Only the delta of a block is kept rather than a snapshot of the state, so
+// that a write doesn't copy the whole sets shared with the checkpoints.
+#[derive(Debug)]
+struct Checkpoint {
+    // The nullifiers published since
+    nullifiers: Vec<Nullifier>,
+    // The number of commitments and the frontier at the checkpoint, the
+    // commitments are only ever appended
+    commitment_num: usize,
+    frontier: CommitmentFrontier,
+    // The previous key of each kind registered since, in order
+    issuers: Vec<(ResourceKind, Option<ConversionVerificationKey>)>,
+    // The conversion rules newly authorized since
+    conversion_rules: Vec<ConversionRuleId>,
+    // The epoch at the checkpoint
+    epoch: u64,
+    // The blacklist root at the checkpoint
+    blacklist_root: Option<Anchor>,
+    // The past roots at the checkpoint, bounded so it is cheap to keep
+    past_anchors: Arc<VecDeque<Anchor>>,
+}
+
+/// An immutable snapshot of the ledger state. Taking a view is cheap: the
+/// sets are shared with the ledger until the next write copies them.
+#[derive(Debug, Clone, Default)]
+pub struct LedgerView {
+    nullifiers: Arc<HashSet<Nullifier>>,
+    commitments: Arc<Vec<ResourceCommitment>>,
+    // The right edge of the commitment tree, the new commitments are appended
+    // to it rather than rebuilding the tree from all the leaves
+    frontier: CommitmentFrontier,
+    issuers: Arc<Vec<(ResourceKind, ConversionVerificationKey)>>,
+    conversion_rules: Arc<HashSet<ConversionRuleId>>,
+    epoch: u64,
+    // The root of the enforced blacklist, None until one is set
+    blacklist_root: Option<Anchor>,
+    // The roots before the current one, the last one is the latest
+    past_anchors: Arc<VecDeque<Anchor>>,
+}
+
+/// The changes made by an executed transaction, for a storage layer to apply
+/// without decoding the transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionResult {
+    /// The nullifiers published.
+    pub nullifiers: Vec<Nullifier>,
+    /// The cms appended to the commitment tree, in order.
+    pub output_cms: Vec<ResourceCommitment>,
+    /// The root of the commitment tree after the transaction.
+    pub anchor: Anchor,
+    /// The writes of the transparent ptxs to the state of the apps.
+    pub writes: Vec<StateWrite>,
+    /// The fee to collect, see `crate::fee`.
+    pub fee: Option<Fee>,
+}
+
+// The ledger is shared across executor threads.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<LedgerState>();
+};
+
+impl LedgerState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, LedgerView> {
+        self.inner.read().expect("ledger lock poisoned")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, LedgerView> {
+        self.inner.write().expect("ledger lock poisoned")
+    }
+
+    /// Returns a snapshot of the current state.
+    pub fn read_view(&self) -> LedgerView {
+        self.read().clone()
+    }
+
+    pub fn contains_nullifier(&self, nf: &Nullifier) -> bool {
+        self.read().contains_nullifier(nf)
+    }
+
+    pub fn nullifier_num(&self) -> usize {
+        self.read().nullifier_num()
+    }
+
+    pub fn commitments(&self) -> Vec<ResourceCommitment> {
+        self.read().commitments().to_vec()
+    }
+
+    pub fn anchor(&self) -> Anchor {
+        self.read().anchor()
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.read().epoch()
+    }
+
+    pub fn is_known_anchor(&self, anchor: &Anchor) -> bool {
+        self.read().is_known_anchor(anchor)
+    }
+
+    pub fn blacklist_root(&self) -> Anchor {
+        self.read().blacklist_root()
+    }
+
+    /// Moves the ledger to `epoch`, e.g. at the start of a block. The
+    /// transactions are then only recorded if proven at it, see
+    /// `ComplianceInfo::with_epoch`.
+    pub fn set_epoch(&self, epoch: u64) {
+        self.write().epoch = epoch;
+    }
+
+    /// Enforces `blacklist`, replacing the previous one. The transactions are
+    /// then only recorded if their blacklist logics are proven against it,
+    /// see `BlacklistResourceLogicCircuit`.
+    pub fn set_blacklist(&self, blacklist: &SortedMerkleTree) {
+        self.write().blacklist_root = Some(blacklist.root().into());
+    }
+
+    /// Registers the key of the issuer of a kind, replacing the previous one.
+    pub fn register_issuer(&self, kind: ResourceKind, vk: ConversionVerificationKey) {
+        let mut inner = self.write();
+        let issuers = Arc::make_mut(&mut inner.issuers);
+        let previous = issuers
+            .iter()
+            .position(|(k, _)| *k == kind)
+            .map(|position| issuers.remove(position).1);
+        issuers.push((kind, vk));
+        if let Some(checkpoint) = self.checkpoints().back_mut() {
+            checkpoint.issuers.push((kind, previous));
+        }
+    }
+
+    /// Records a conversion rule signed by the issuer of its target kind.
+    pub fn authorize_conversion(
+        &self,
+        authorization: &ConversionAuthorization,
+    ) -> Result<(), TransactionError> {
+        let mut inner = self.write();
+        let id = authorization.rule.id();
+        let (_, vk) = inner
+            .issuers
+            .iter()
+            .find(|(kind, _)| *kind == authorization.rule.to)
+            .ok_or(TransactionError::UnauthorizedConversion(id.inner()))?;
+        authorization.verify(vk)?;
+        if Arc::make_mut(&mut inner.conversion_rules).insert(id) {
+            if let Some(checkpoint) = self.checkpoints().back_mut() {
+                checkpoint.conversion_rules.push(id);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_conversion_authorized(&self, id: &ConversionRuleId) -> bool {
+        self.read().is_conversion_authorized(id)
+    }
+
+    fn checkpoints(&self) -> MutexGuard<'_, VecDeque<Checkpoint>> {
+        self.checkpoints.lock().expect("ledger lock poisoned")
+    }
+
+    /// Records the current state, e.g. before applying a block, so that
+    /// `rollback` reverts to it on a reorg. A checkpoint only records the
+    /// changes made since, not a copy of the state.
+    pub fn checkpoint(&self) {
+        let inner = self.write();
+        self.checkpoints().push_back(Checkpoint {
+            nullifiers: vec![],
+            commitment_num: inner.commitments.len(),
+            frontier: inner.frontier.clone(),
+            issuers: vec![],
+            conversion_rules: vec![],
+            epoch: inner.epoch,
+            blacklist_root: inner.blacklist_root,
+            past_anchors: inner.past_anchors.clone(),
+        });
+    }
+
+    /// Restores the state of the last checkpoint and removes it, reverting the
+    /// nullifiers, the commitments, the anchors, the issuers, the conversion
+    /// rules, the epoch and the blacklist recorded since. Rolling back N times
+    /// reverts the last N checkpointed blocks.
+    pub fn rollback(&self) -> Result<(), TransactionError> {
+        let mut inner = self.write();
+        let checkpoint = self
+            .checkpoints()
+            .pop_back()
+            .ok_or(TransactionError::NoCheckpoint)?;
+        let nullifiers = Arc::make_mut(&mut inner.nullifiers);
+        for nf in checkpoint.nullifiers.iter() {
+            nullifiers.remove(nf);
+        }
+        Arc::make_mut(&mut inner.commitments).truncate(checkpoint.commitment_num);
+        inner.frontier = checkpoint.frontier;
+        inner.past_anchors = checkpoint.past_anchors;
+        let issuers = Arc::make_mut(&mut inner.issuers);
+        for (kind, previous) in checkpoint.issuers.into_iter().rev() {
+            issuers.retain(|(k, _)| *k != kind);
+            if let Some(vk) = previous {
+                issuers.push((kind, vk));
+            }
+        }
+        let conversion_rules = Arc::make_mut(&mut inner.conversion_rules);
+        for id in checkpoint.conversion_rules.iter() {
+            conversion_rules.remove(id);
+        }
+        inner.epoch = checkpoint.epoch;
+        inner.blacklist_root = checkpoint.blacklist_root;
+        Ok(())
+    }
+
+    /// Drops the checkpoints but the last `depth` ones, once the older blocks
+    /// are final.
+    pub fn prune(&self, depth: usize) {
+        let _inner = self.write();
+        let mut checkpoints = self.checkpoints();
+        let excess = checkpoints.len().saturating_sub(depth);
+        checkpoints.drain(..excess);
+    }
+
+    pub fn checkpoint_num(&self) -> usize {
+        let _inner = self.read();
+        self.checkpoints().len()
+    }
+
+    /// Executes a transaction and records its result.
+    pub fn execute(&self, tx: &Transaction) -> Result<ExecutionResult, TransactionError> {
+        let result = tx.execute()?;
+        let anchor = self.apply_with_anchor(&result)?;
+        Ok(ExecutionResult {
+            nullifiers: result.nullifiers,
+            output_cms: result.output_cms,
+            anchor,
+            writes: tx.get_state_writes(),
+            fee: tx.get_fee(),
+        })
+    }
+
+    /// Records the nullifiers and the output commitments of an executed
+    /// transaction. Fails without changing the state if any nullifier is
+    /// already published or appears twice in the transaction, if an anchor is
+    /// not a recent root of the commitment tree, if a conversion rule is not
+    /// authorized, if a compliance is not proven at the current epoch, or if a
+    /// blacklist logic is not proven against the enforced blacklist.
+    pub fn apply(&self, result: &TransactionResult) -> Result<(), TransactionError> {
+        self.apply_with_anchor(result).map(|_| ())
+    }
+
+    // Returns the anchor right after the result is applied, before any
+    // other writer moves it.
+    fn apply_with_anchor(&self, result: &TransactionResult) -> Result<Anchor, TransactionError> {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let outcome = self.apply_unmetered(result);
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_verification(crate::metrics::KIND_LEDGER, start, &outcome);
+        outcome
+    }
+
+    fn apply_unmetered(&self, result: &TransactionResult) -> Result<Anchor, TransactionError> {
+        let mut inner = self.write();
+        let mut new_nullifiers = HashSet::with_capacity(result.nullifiers.len());
+        for nf in result.nullifiers.iter() {
+            if inner.nullifiers.contains(nf) || !new_nullifiers.insert(*nf) {
+                return Err(TransactionError::DoubleSpend(nf.inner()));
+            }
+        }
+        let root = inner.anchor();
+        if result
+            .anchors
+            .iter()
+            .any(|anchor| *anchor != root && !inner.past_anchors.contains(anchor))
+        {
+            return Err(TransactionError::UnknownAnchor);
+        }
+        if let Some(id) = result
+            .conversion_rules
+            .iter()
+            .find(|id| !inner.conversion_rules.contains(id))
+        {
+            return Err(TransactionError::UnauthorizedConversion(id.inner()));
+        }
+        if result.epochs.iter().any(|epoch| *epoch != inner.epoch) {
+            return Err(TransactionError::InvalidEpoch);
+        }
+        if result
+            .blacklist_roots
+            .iter()
+            .any(|root| *root != inner.blacklist_root())
+        {
+            return Err(TransactionError::UnknownBlacklist);
+        }
+        // Appending can only fail once the tree is full, checked before any
+        // write so that the result is recorded atomically
+        let free = (1u64 << TAIGA_COMMITMENT_TREE_DEPTH) - inner.frontier.size();
+        if result.output_cms.len() as u64 > free {
+            return Err(TransactionError::CommitmentTreeFull);
+        }
+        if let Some(checkpoint) = self.checkpoints().back_mut() {
+            checkpoint.nullifiers.extend(new_nullifiers.iter().copied());
+        }
+        Arc::make_mut(&mut inner.nullifiers).extend(new_nullifiers);
+        if !result.output_cms.is_empty() {
+            let past_anchors = Arc::make_mut(&mut inner.past_anchors);
+            past_anchors.push_back(root);
+            if past_anchors.len() > ANCHOR_HISTORY_SIZE {
+                past_anchors.pop_front();
+            }
+        }
+        for cm in result.output_cms.iter() {
+            inner.frontier.append(Node::from(*cm))?;
+        }
+        Arc::make_mut(&mut inner.commitments).extend(result.output_cms.iter().copied());
+        Ok(inner.anchor())
+    }
+}
+
+impl LedgerView {
+    fn leaves(&self) -> Vec<Node> {
+        self.commitments.iter().map(|cm| Node::from(*cm)).collect()
+    }
+
+    /// The root of the commitment tree in this snapshot.
+    pub fn anchor(&self) -> Anchor {
+        self.frontier.root()
+    }
+
+    pub fn contains_nullifier(&self, nf: &Nullifier) -> bool {
+        self.nullifiers.contains(nf)
+    }
+
+    pub fn nullifier_num(&self) -> usize {
+        self.nullifiers.len()
+    }
+
+    pub fn commitments(&self) -> &[ResourceCommitment] {
+        &self.commitments
+    }
+
+    pub fn is_conversion_authorized(&self, id: &ConversionRuleId) -> bool {
+        self.conversion_rules.contains(id)
+    }
+
+    /// The epoch the transactions must be proven at.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Whether the transactions can be proven against `anchor`: it is the
+    /// current root or one of the last `ANCHOR_HISTORY_SIZE` ones.
+    pub fn is_known_anchor(&self, anchor: &Anchor) -> bool {
+        self.anchor() == *anchor || self.past_anchors.contains(anchor)
+    }
+
+    /// The root of the enforced blacklist, the empty one until one is set.
+    pub fn blacklist_root(&self) -> Anchor {
+        self.blacklist_root
+            .unwrap_or_else(|| SortedMerkleTree::new(vec![]).root().into())
+    }
+
+    /// Returns the path of a commitment to the anchor of this snapshot.
+    pub fn merkle_path(&self, cm: &ResourceCommitment) -> Option<MerklePath> {
+        let position = self.commitments.iter().position(|c| c == cm)?;
+        commitment_tree_path(&self.leaves(), position)
+    }
+}
+
+impl StateView for LedgerView {
+    fn contains_nullifier(&self, nf: &Nullifier) -> bool {
+        LedgerView::contains_nullifier(self, nf)
+    }
+
+    fn is_known_anchor(&self, anchor: &Anchor) -> bool {
+        LedgerView::is_known_anchor(self, anchor)
+    }
+
+    fn is_conversion_authorized(&self, id: &ConversionRuleId) -> bool {
+        LedgerView::is_conversion_authorized(self, id)
+    }
+
+    fn epoch(&self) -> u64 {
+        LedgerView::epoch(self)
+    }
+
+    fn blacklist_root(&self) -> Anchor {
+        LedgerView::blacklist_root(self)
+    }
+}
+
+impl StateView for LedgerState {
+    fn contains_nullifier(&self, nf: &Nullifier) -> bool {
+        LedgerState::contains_nullifier(self, nf)
+    }
+
+    fn is_known_anchor(&self, anchor: &Anchor) -> bool {
+        LedgerState::is_known_anchor(self, anchor)
+    }
+
+    fn is_conversion_authorized(&self, id: &ConversionRuleId) -> bool {
+        LedgerState::is_conversion_authorized(self, id)
+    }
+
+    fn epoch(&self) -> u64 {
+        LedgerState::epoch(self)
+    }
+
+    fn blacklist_root(&self) -> Anchor {
+        LedgerState::blacklist_root(self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::LedgerState;
+    use crate::error::TransactionError;
+    use crate::nullifier::Nullifier;
+    use crate::resource::ResourceCommitment;
+    use crate::transaction::TransactionResult;
+    use halo2_proofs::arithmetic::Field;
+    use pasta_curves::pallas;
+    use rand::rngs::OsRng;
+
+    pub fn random_transaction_result(nullifiers: Vec<Nullifier>) -> TransactionResult {
+        let mut rng = OsRng;
+        TransactionResult {
+            anchors: vec![],
+            output_cms: nullifiers
+                .iter()
+                .map(|_| ResourceCommitment::from(pallas::Base::random(&mut rng)))
+                .collect(),
+            nullifiers,
+            conversion_rules: vec![],
+            epochs: vec![],
+            blacklist_roots: vec![],
+        }
+    }
+
+    #[test]
+    fn test_ledger_double_spend() {
+        let mut rng = OsRng;
+        let ledger = LedgerState::new();
+        let nf = Nullifier::random(&mut rng);
+        let other_nf = Nullifier::random(&mut rng);
+
+        ledger.apply(&random_transaction_result(vec![nf])).unwrap();
+        assert!(ledger.contains_nullifier(&nf));
+
+        // A spent nullifier is rejected and the state is unchanged
+        assert!(matches!(
+            ledger.apply(&random_transaction_result(vec![other_nf, nf])),
+            Err(TransactionError::DoubleSpend(_))
+        ));
+        assert!(!ledger.contains_nullifier(&other_nf));
+        assert_eq!(ledger.commitments().len(), 1);
+
+        // A nullifier can't be spent twice in the same transaction
+        assert!(ledger
+            .apply(&random_transaction_result(vec![other_nf, other_nf]))
+            .is_err());
+        assert_eq!(ledger.nullifier_num(), 1);
+    }
+
+    #[test]
+    fn test_ledger_conversion_authorization() {
+        use crate::conversion::{ConversionRule, ConversionSigningKey};
+        use crate::resource::tests::random_resource;
+
+        let mut rng = OsRng;
+        let ledger = LedgerState::new();
+        let from = random_resource(&mut rng).kind;
+        let to = random_resource(&mut rng).kind;
+        let rule = ConversionRule::new(from, to, 1, 2).unwrap();
+        let mut result = random_transaction_result(vec![Nullifier::random(&mut rng)]);
+        result.conversion_rules = vec![rule.id()];
+
+        // The rule is not authorized yet
+        assert!(matches!(
+            ledger.apply(&result),
+            Err(TransactionError::UnauthorizedConversion(_))
+        ));
+
+        // Only the issuer of the target kind authorizes the rule
+        let issuer = ConversionSigningKey::random(&mut rng);
+        let other = ConversionSigningKey::random(&mut rng);
+        ledger.register_issuer(to, issuer.get_vk());
+        assert!(ledger
+            .authorize_conversion(&other.authorize(&mut rng, rule))
+            .is_err());
+        ledger
+            .authorize_conversion(&issuer.authorize(&mut rng, rule))
+            .unwrap();
+        assert!(ledger.is_conversion_authorized(&rule.id()));
+        ledger.apply(&result).unwrap();
+    }
+
+    #[test]
+    fn test_ledger_read_view() {
+        let mut rng = OsRng;
+        let ledger = LedgerState::new();
+        let result = random_transaction_result(vec![Nullifier::random(&mut rng)]);
+        ledger.apply(&result).unwrap();
+
+        let view = ledger.read_view();
+        let anchor = view.anchor();
+        let cm = result.output_cms[0];
+        let path = view.merkle_path(&cm).unwrap();
+        assert_eq!(path.root(cm.into()), anchor);
+
+        // The view stays consistent while the ledger moves on
+        let next = random_transaction_result(vec![Nullifier::random(&mut rng)]);
+        ledger.apply(&next).unwrap();
+        assert_ne!(ledger.anchor(), anchor);
+        assert_eq!(view.anchor(), anchor);
+        assert_eq!(view.commitments().len(), 1);
+        assert!(!view.contains_nullifier(&next.nullifiers[0]));
+        assert!(view.merkle_path(&next.output_cms[0]).is_none());
+        assert_eq!(view.merkle_path(&cm).unwrap().root(cm.into()), anchor);
+    }
+
+    #[test]
+    fn test_ledger_concurrent_apply() {
+        use crate::merkle_tree::{commitment_tree_root, Node};
+
+        const THREAD_NUM: usize = 8;
+        const TX_NUM: usize = 50;
+
+        let mut rng = OsRng;
+        let ledger = LedgerState::new();
+        let contested = Nullifier::random(&mut rng);
+
+        let successes: usize = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..THREAD_NUM)
+                .map(|_| {
+                    let ledger = &ledger;
+                    s.spawn(move || {
+                        let mut rng = OsRng;
+                        // Every thread tries to spend the contested nullifier once
+                        let contested_ok = ledger
+                            .apply(&random_transaction_result(vec![
+                                Nullifier::random(&mut rng),
+                                contested,
+                            ]))
+                            .is_ok() as usize;
+                        for _ in 0..TX_NUM {
+                            ledger
+                                .apply(&random_transaction_result(vec![Nullifier::random(
+                                    &mut rng,
+                                )]))
+                                .unwrap();
+                        }
+                        contested_ok
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        });
+
+        assert_eq!(successes, 1);
+        assert_eq!(ledger.nullifier_num(), THREAD_NUM * TX_NUM + 2);
+        assert_eq!(ledger.commitments().len(), THREAD_NUM * TX_NUM + 2);
+
+        // The incrementally appended tree has the root of all the leaves
+        let leaves: Vec<Node> = ledger.commitments().into_iter().map(Node::from).collect();
+        assert_eq!(ledger.anchor(), commitment_tree_root(&leaves));
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_ledger_execute_ptx() {
+        use crate::executable::Executable;
+        use crate::transparent_ptx::testing::{create_transparent_ptx, create_transparent_ptx_in};
+
+        let ledger = LedgerState::new();
+        // A ptx proven against the roots of another tree is rejected
+        assert!(matches!(
+            create_transparent_ptx().execute(&ledger),
+            Err(TransactionError::UnknownAnchor)
+        ));
+
+        let ptx = create_transparent_ptx_in(&ledger);
+        let delta = ptx.execute(&ledger).unwrap();
+        assert_eq!(delta.nullifiers, ptx.get_nullifiers());
+        assert_eq!(delta.output_cms, ptx.get_output_cms());
+
+        // Once its nullifiers are published, the ptx is a double spend
+        let result = TransactionResult {
+            anchors: vec![],
+            nullifiers: delta.nullifiers,
+            output_cms: delta.output_cms,
+            conversion_rules: vec![],
+            epochs: vec![],
+            blacklist_roots: vec![],
+        };
+        ledger.apply(&result).unwrap();
+        assert!(matches!(
+            ptx.execute(&ledger.read_view()),
+            Err(TransactionError::DoubleSpend(_))
+        ));
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_ledger_execution_result() {
+        use crate::executable::StateWrite;
+        use crate::transaction::{
+            ShieldedPartialTxBundle, Transaction, TransparentPartialTxBundle,
+        };
+        use crate::transparent_ptx::testing::create_transparent_ptx_in;
+
+        let ledger = LedgerState::new();
+        let tx = Transaction::build(
+            OsRng,
+            ShieldedPartialTxBundle::default(),
+            TransparentPartialTxBundle::new(vec![create_transparent_ptx_in(&ledger)]),
+        )
+        .unwrap();
+        let result = ledger.execute(&tx).unwrap();
+        assert_eq!(result.nullifiers, tx.get_nullifiers());
+        assert!(ledger.commitments().ends_with(&result.output_cms));
+        assert_eq!(result.anchor, ledger.anchor());
+
+        // The created transparent resources are decoded
+        let created: Vec<_> = result
+            .writes
+            .iter()
+            .filter_map(|write| match write {
+                StateWrite::Insert(cm, resource) => {
+                    assert_eq!(*cm, resource.commitment());
+                    Some(*cm)
+                }
+                StateWrite::Delete(_) => None,
+            })
+            .collect();
+        assert_eq!(created, result.output_cms);
+    }
+
+    #[test]
+    fn test_ledger_anchor_history() {
+        use super::ANCHOR_HISTORY_SIZE;
+        use crate::merkle_tree::Anchor;
+
+        let mut rng = OsRng;
+        let ledger = LedgerState::new();
+        let mut anchors = vec![ledger.anchor()];
+        for _ in 0..ANCHOR_HISTORY_SIZE {
+            ledger
+                .apply(&random_transaction_result(vec![Nullifier::random(
+                    &mut rng,
+                )]))
+                .unwrap();
+            anchors.push(ledger.anchor());
+        }
+
+        // A transaction proven against a made-up root is rejected and the
+        // state is unchanged
+        let mut result = random_transaction_result(vec![Nullifier::random(&mut rng)]);
+        result.anchors = vec![Anchor::from(pallas::Base::random(&mut rng))];
+        assert!(matches!(
+            ledger.apply(&result),
+            Err(TransactionError::UnknownAnchor)
+        ));
+        assert!(!ledger.contains_nullifier(&result.nullifiers[0]));
+
+        // The last roots are known, the oldest one falls out of the history
+        // once the tree moves on
+        assert!(anchors.iter().all(|anchor| ledger.is_known_anchor(anchor)));
+        result.anchors = vec![anchors[0], anchors[ANCHOR_HISTORY_SIZE]];
+        ledger.apply(&result).unwrap();
+        assert!(!ledger.is_known_anchor(&anchors[0]));
+        assert!(ledger.is_known_anchor(&anchors[1]));
+
+        // A rollback restores the history
+        ledger.checkpoint();
+        ledger
+            .apply(&random_transaction_result(vec![Nullifier::random(
+                &mut rng,
+            )]))
+            .unwrap();
+        assert!(!ledger.is_known_anchor(&anchors[1]));
+        ledger.rollback().unwrap();
+        assert!(ledger.is_known_anchor(&anchors[1]));
+    }
+
+    #[test]
+    fn test_ledger_rollback() {
+        let mut rng = OsRng;
+        let ledger = LedgerState::new();
+        assert!(matches!(
+            ledger.rollback(),
+            Err(TransactionError::NoCheckpoint)
+        ));
+
+        // Three blocks, each applied after a checkpoint
+        let mut anchors = vec![ledger.anchor()];
+        let mut results = vec![];
+        for epoch in 1..4 {
+            ledger.checkpoint();
+            ledger.set_epoch(epoch);
+            let result = random_transaction_result(vec![Nullifier::random(&mut rng)]);
+            ledger.apply(&result).unwrap();
+            anchors.push(ledger.anchor());
+            results.push(result);
+        }
+        assert_eq!(ledger.checkpoint_num(), 3);
+
+        // Revert the last two blocks
+        ledger.rollback().unwrap();
+        ledger.rollback().unwrap();
+        assert_eq!(ledger.anchor(), anchors[1]);
+        assert_eq!(ledger.epoch(), 1);
+        assert_eq!(ledger.commitments(), results[0].output_cms);
+        assert!(ledger.contains_nullifier(&results[0].nullifiers[0]));
+        assert!(!ledger.contains_nullifier(&results[1].nullifiers[0]));
+        // The reverted nullifiers can be spent again
+        ledger.apply(&results[2]).unwrap();
+
+        // Pruned checkpoints can't be rolled back to
+        ledger.checkpoint();
+        ledger.prune(1);
+        assert_eq!(ledger.checkpoint_num(), 1);
+        ledger.rollback().unwrap();
+        assert!(ledger.rollback().is_err());
+        assert_eq!(ledger.nullifier_num(), 2);
+    }
+
+    #[test]
+    fn test_ledger_rollback_conversions() {
+        use crate::conversion::{ConversionRule, ConversionSigningKey};
+        use crate::resource::tests::random_resource;
+
+        let mut rng = OsRng;
+        let ledger = LedgerState::new();
+        let from = random_resource(&mut rng).kind;
+        let to = random_resource(&mut rng).kind;
+        let rule = ConversionRule::new(from, to, 1, 2).unwrap();
+        let issuer = ConversionSigningKey::random(&mut rng);
+        let next_issuer = ConversionSigningKey::random(&mut rng);
+        ledger.register_issuer(to, issuer.get_vk());
+
+        // The issuer changes and authorizes the rule in a reverted block
+        ledger.checkpoint();
+        ledger.register_issuer(to, next_issuer.get_vk());
+        ledger
+            .authorize_conversion(&next_issuer.authorize(&mut rng, rule))
+            .unwrap();
+        ledger.rollback().unwrap();
+        assert!(!ledger.is_conversion_authorized(&rule.id()));
+
+        // The previous issuer is restored
+        assert!(ledger
+            .authorize_conversion(&next_issuer.authorize(&mut rng, rule))
+            .is_err());
+        ledger
+            .authorize_conversion(&issuer.authorize(&mut rng, rule))
+            .unwrap();
+        assert!(ledger.is_conversion_authorized(&rule.id()));
+    }
+
+    #[test]
+    fn test_ledger_blacklist() {
+        use crate::merkle_tree::Anchor;
+        use crate::resource_tree::SortedMerkleTree;
+
+        let mut rng = OsRng;
+        let ledger = LedgerState::new();
+        let blacklist = SortedMerkleTree::new(vec![pallas::Base::from(42u64)]);
+        let mut result = random_transaction_result(vec![Nullifier::random(&mut rng)]);
+        result.blacklist_roots = vec![Anchor::from(blacklist.root())];
+
+        // The blacklist logic is proven against another blacklist
+        assert_eq!(
+            ledger.blacklist_root(),
+            Anchor::from(SortedMerkleTree::new(vec![]).root())
+        );
+        assert!(matches!(
+            ledger.apply(&result),
+            Err(TransactionError::UnknownBlacklist)
+        ));
+
+        // Setting the blacklist is reverted like the rest of a block
+        ledger.checkpoint();
+        ledger.set_blacklist(&blacklist);
+        ledger.rollback().unwrap();
+        assert!(ledger.apply(&result).is_err());
+
+        ledger.set_blacklist(&blacklist);
+        ledger.apply(&result).unwrap();
+    }
+}
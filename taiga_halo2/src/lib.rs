@@ -1,23 +1,64 @@
 #![allow(dead_code)]
 #![allow(clippy::large_enum_variant)]
 
+#[cfg(feature = "apps")]
+pub mod apps;
+pub mod auxiliary_delta;
 pub mod binding_signature;
+pub mod block;
+pub mod builder;
 pub mod circuit;
+pub mod commitment_tree;
 pub mod compliance;
 pub mod constant;
+pub mod conversion;
 pub mod delta_commitment;
+#[cfg(feature = "borsh")]
+pub mod doc_examples;
+pub mod domains;
 pub mod error;
-mod executable;
+pub mod executable;
+pub mod fee;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod field_encoding;
+#[cfg(feature = "apps")]
+pub mod fixtures;
+pub mod history;
+pub mod keys;
+pub mod ledger;
+pub mod linkability;
 pub mod merkle_tree;
+pub mod method_call;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "nif")]
+pub mod nif;
 pub mod nullifier;
+pub mod packed;
+pub mod params;
 pub mod proof;
+pub mod proving_cost;
+pub mod pruned_transaction;
+pub mod quantity;
 pub mod resource;
 pub mod resource_encryption;
 pub mod resource_logic_commitment;
 pub mod resource_logic_vk;
 pub mod resource_tree;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+pub mod secret;
 pub mod shielded_ptx;
+pub mod spent_proof;
 pub mod taiga_api;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+pub mod trace;
 pub mod transaction;
+pub mod transcript;
 pub mod transparent_ptx;
 pub mod utils;
+pub mod wallet;
+pub mod witness_set;
+pub mod zkvm_receipt;
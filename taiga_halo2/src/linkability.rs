@@ -0,0 +1,109 @@
+//! Linkability tags let an app detect that the same credential was used twice
+//! across transactions without revealing it, e.g. one vote per credential.
+//!
+//! The logic of the app publishes the tag PRF(nk, app_domain) of the nk behind
+//! its resource at `RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX`, see
+//! `linkability_tag_gadget`. The tag is the same every time the nk is used for
+//! the app, but unlinkable to the nk and to the tags of other apps. Executors
+//! of the app collect the published tags in a [`LinkabilityTagSet`].
+use crate::{
+    circuit::resource_logic_circuit::ResourceLogicPublicInputs,
+    constant::{LINKABILITY_TAG_DOMAIN, RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX},
+    error::TransactionError,
+    utils::poseidon_hash_n,
+};
+use ff::PrimeField;
+use pasta_curves::pallas;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub struct LinkabilityTag(pallas::Base);
+
+impl LinkabilityTag {
+    /// tag = poseidon_hash(LINKABILITY_TAG_DOMAIN || nk || app_domain)
+    pub fn derive(nk: &pallas::Base, app_domain: &pallas::Base) -> Self {
+        Self(poseidon_hash_n([*LINKABILITY_TAG_DOMAIN, *nk, *app_domain]))
+    }
+
+    /// The tag published by a logic. Only meaningful for the logics publishing
+    /// one.
+    pub fn from_public_inputs(public_inputs: &ResourceLogicPublicInputs) -> Self {
+        Self(public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_LINKABILITY_TAG_IDX))
+    }
+
+    pub fn inner(&self) -> pallas::Base {
+        self.0
+    }
+}
+
+impl Hash for LinkabilityTag {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_repr().hash(state);
+    }
+}
+
+/// The tags published for an app.
+#[derive(Debug, Clone, Default)]
+pub struct LinkabilityTagSet(HashSet<LinkabilityTag>);
+
+impl LinkabilityTagSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, tag: &LinkabilityTag) -> bool {
+        self.0.contains(tag)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Checks the tags of a transaction, then inserts them. Fails without
+    /// inserting any of them if one is already in the set or repeated.
+    pub fn check_and_insert(&mut self, tags: &[LinkabilityTag]) -> Result<(), TransactionError> {
+        let mut new_tags = HashSet::with_capacity(tags.len());
+        for tag in tags {
+            if self.contains(tag) || !new_tags.insert(*tag) {
+                return Err(TransactionError::DuplicateLinkabilityTag(tag.inner()));
+            }
+        }
+        self.0.extend(new_tags);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_linkability_tag_set() {
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let nk = pallas::Base::random(&mut rng);
+    let app_domain = pallas::Base::random(&mut rng);
+    let tag = LinkabilityTag::derive(&nk, &app_domain);
+    // The tag only depends on the nk and the app
+    assert_eq!(tag, LinkabilityTag::derive(&nk, &app_domain));
+    let other_app_tag = LinkabilityTag::derive(&nk, &pallas::Base::random(&mut rng));
+    assert_ne!(tag, other_app_tag);
+    let other_tag = LinkabilityTag::derive(&pallas::Base::random(&mut rng), &app_domain);
+
+    let mut tags = LinkabilityTagSet::new();
+    assert!(tags.check_and_insert(&[tag]).is_ok());
+    assert!(matches!(
+        tags.check_and_insert(&[other_tag, tag]),
+        Err(TransactionError::DuplicateLinkabilityTag(_))
+    ));
+    // Nothing is inserted from a rejected transaction
+    assert!(!tags.contains(&other_tag));
+    assert!(tags
+        .check_and_insert(&[other_app_tag, other_app_tag])
+        .is_err());
+    assert!(tags.check_and_insert(&[other_tag, other_app_tag]).is_ok());
+    assert_eq!(tags.len(), 3);
+}
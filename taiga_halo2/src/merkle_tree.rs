@@ -1,14 +1,13 @@
 use std::hash::{Hash, Hasher};
 
+use crate::error::TransactionError;
 use crate::merkle_tree::LR::{L, R};
 use crate::resource::ResourceCommitment;
 use crate::utils::poseidon_hash;
-use crate::{
-    constant::{TAIGA_COMMITMENT_TREE_DEPTH, TAIGA_RESOURCE_TREE_DEPTH},
-    resource::Resource,
-};
+use crate::{constant::TAIGA_COMMITMENT_TREE_DEPTH, resource::Resource};
 use ff::PrimeField;
 use halo2_proofs::arithmetic::Field;
+use lazy_static::lazy_static;
 use pasta_curves::pallas;
 use rand::distributions::{Distribution, Standard};
 use rand::{Rng, RngCore};
@@ -164,8 +163,8 @@ impl Default for MerklePath {
     }
 }
 
-impl From<[(pallas::Base, LR); TAIGA_RESOURCE_TREE_DEPTH]> for MerklePath {
-    fn from(path: [(pallas::Base, LR); TAIGA_RESOURCE_TREE_DEPTH]) -> Self {
+impl<const DEPTH: usize> From<[(pallas::Base, LR); DEPTH]> for MerklePath {
+    fn from(path: [(pallas::Base, LR); DEPTH]) -> Self {
         let merkle_path = path
             .into_iter()
             .map(|(value, b)| (Node::from(value), b))
@@ -232,3 +231,438 @@ impl Hash for Node {
         self.0.to_repr().hash(state);
     }
 }
+
+/// Returns the levels of the commitment tree whose leftmost leaves are `leaves`
+/// and whose other leaves are `padding`. Only the non-empty part of every
+/// level is kept, together with the padding node of that level.
+fn padded_tree_levels(leaves: &[Node], padding: Node) -> Vec<(Vec<Node>, Node)> {
+    let mut levels = Vec::with_capacity(TAIGA_COMMITMENT_TREE_DEPTH + 1);
+    let mut level = leaves.to_vec();
+    let mut empty = padding;
+    for _ in 0..TAIGA_COMMITMENT_TREE_DEPTH {
+        if level.len() % 2 == 1 {
+            level.push(empty);
+        }
+        let next = level
+            .chunks(2)
+            .map(|pair| Node::combine(&pair[0], &pair[1]))
+            .collect();
+        levels.push((level, empty));
+        level = next;
+        empty = Node::combine(&empty, &empty);
+    }
+    levels.push((level, empty));
+    levels
+}
+
+/// Returns the root of the commitment tree whose leftmost leaves are `leaves`
+/// and whose other leaves are `padding`.
+pub(crate) fn padded_tree_root(leaves: &[Node], padding: Node) -> Anchor {
+    let (top, empty) = padded_tree_levels(leaves, padding).pop().unwrap();
+    top.first().copied().unwrap_or(empty).into()
+}
+
+/// Returns the path of the leaf at `position` in the commitment tree whose
+/// leftmost leaves are `leaves` and whose other leaves are `padding`.
+pub(crate) fn padded_tree_path(
+    leaves: &[Node],
+    padding: Node,
+    position: usize,
+) -> Option<MerklePath> {
+    if position >= leaves.len() {
+        return None;
+    }
+    let merkle_path = padded_tree_levels(leaves, padding)
+        .into_iter()
+        .take(TAIGA_COMMITMENT_TREE_DEPTH)
+        .enumerate()
+        .map(|(depth, (level, _))| {
+            let sibling = (position >> depth) ^ 1;
+            let side = if sibling % 2 == 1 { R } else { L };
+            (level[sibling], side)
+        })
+        .collect();
+    Some(MerklePath::from_path(merkle_path))
+}
+
+/// Returns the root of the commitment tree whose leftmost leaves are `leaves`
+/// and whose other leaves are zero.
+pub fn commitment_tree_root(leaves: &[Node]) -> Anchor {
+    padded_tree_root(leaves, Node::from(pallas::Base::zero()))
+}
+
+/// Returns the path of the leaf at `position` in the commitment tree whose
+/// leftmost leaves are `leaves` and whose other leaves are zero.
+pub fn commitment_tree_path(leaves: &[Node], position: usize) -> Option<MerklePath> {
+    padded_tree_path(leaves, Node::from(pallas::Base::zero()), position)
+}
+
+lazy_static! {
+    // The empty node of every level of the commitment tree, the leaves first
+    static ref EMPTY_NODES: Vec<Node> = {
+        let mut empty = Vec::with_capacity(TAIGA_COMMITMENT_TREE_DEPTH + 1);
+        empty.push(Node::from(pallas::Base::zero()));
+        for level in 0..TAIGA_COMMITMENT_TREE_DEPTH {
+            empty.push(Node::combine(&empty[level], &empty[level]));
+        }
+        empty
+    };
+}
+
+/// The right edge of the commitment tree: the last left node of every level,
+/// enough to append leaves and compute the root without the other leaves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "CommitmentFrontierFields"))]
+pub struct CommitmentFrontier {
+    size: u64,
+    frontier: Vec<Node>,
+    root: Node,
+}
+
+// The fields of a deserialized frontier, checked by `try_from`
+#[cfg(any(feature = "borsh", feature = "serde"))]
+#[cfg_attr(feature = "borsh", derive(BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+struct CommitmentFrontierFields {
+    size: u64,
+    frontier: Vec<Node>,
+    root: Node,
+}
+
+#[cfg(any(feature = "borsh", feature = "serde"))]
+impl TryFrom<CommitmentFrontierFields> for CommitmentFrontier {
+    type Error = &'static str;
+
+    fn try_from(fields: CommitmentFrontierFields) -> Result<Self, Self::Error> {
+        if fields.frontier.len() != TAIGA_COMMITMENT_TREE_DEPTH {
+            return Err("frontier is not as deep as the commitment tree");
+        }
+        if fields.size > 1 << TAIGA_COMMITMENT_TREE_DEPTH {
+            return Err("frontier has more leaves than the commitment tree");
+        }
+        Ok(Self {
+            size: fields.size,
+            frontier: fields.frontier,
+            root: fields.root,
+        })
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for CommitmentFrontier {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let fields = CommitmentFrontierFields::deserialize_reader(reader)?;
+        Self::try_from(fields).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl Default for CommitmentFrontier {
+    fn default() -> Self {
+        Self {
+            size: 0,
+            frontier: EMPTY_NODES[..TAIGA_COMMITMENT_TREE_DEPTH].to_vec(),
+            root: EMPTY_NODES[TAIGA_COMMITMENT_TREE_DEPTH],
+        }
+    }
+}
+
+impl CommitmentFrontier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of leaves in the tree.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn root(&self) -> Anchor {
+        self.root.into()
+    }
+
+    /// Appends a leaf and returns its position.
+    pub fn append(&mut self, leaf: Node) -> Result<u64, TransactionError> {
+        self.append_with(leaf, |_, _| {})
+    }
+
+    // Appends a leaf, calling `visit` with the level and the node of every
+    // subtree on the path of the leaf
+    fn append_with(
+        &mut self,
+        leaf: Node,
+        mut visit: impl FnMut(usize, &Node),
+    ) -> Result<u64, TransactionError> {
+        let position = self.size;
+        if position >> TAIGA_COMMITMENT_TREE_DEPTH != 0 {
+            return Err(TransactionError::CommitmentTreeFull);
+        }
+        let empty = &*EMPTY_NODES;
+        let mut node = leaf;
+        for level in 0..TAIGA_COMMITMENT_TREE_DEPTH {
+            visit(level, &node);
+            node = if (position >> level) & 1 == 1 {
+                Node::combine(&self.frontier[level], &node)
+            } else {
+                self.frontier[level] = node;
+                Node::combine(&node, &empty[level])
+            };
+        }
+        self.size += 1;
+        self.root = node;
+        Ok(position)
+    }
+}
+
+/// The path of a leaf kept up to date as the commitments after it are
+/// appended, like the `IncrementalWitness` of zcash. A wallet creates it when
+/// its commitment is appended and feeds it every later commitment, each costing
+/// one hash per level, without storing the other leaves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "IncrementalWitnessFields"))]
+pub struct IncrementalWitness {
+    leaf: Node,
+    position: u64,
+    // The siblings of the path, the right ones change as leaves are appended
+    siblings: Vec<Node>,
+    tree: CommitmentFrontier,
+}
+
+// The fields of a deserialized witness, checked by `try_from`
+#[cfg(any(feature = "borsh", feature = "serde"))]
+#[cfg_attr(feature = "borsh", derive(BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+struct IncrementalWitnessFields {
+    leaf: Node,
+    position: u64,
+    siblings: Vec<Node>,
+    tree: CommitmentFrontier,
+}
+
+#[cfg(any(feature = "borsh", feature = "serde"))]
+impl TryFrom<IncrementalWitnessFields> for IncrementalWitness {
+    type Error = &'static str;
+
+    fn try_from(fields: IncrementalWitnessFields) -> Result<Self, Self::Error> {
+        if fields.siblings.len() != TAIGA_COMMITMENT_TREE_DEPTH {
+            return Err("witness path is not as deep as the commitment tree");
+        }
+        if fields.position >= fields.tree.size() {
+            return Err("witnessed leaf is not in the tree");
+        }
+        let witness = Self {
+            leaf: fields.leaf,
+            position: fields.position,
+            siblings: fields.siblings,
+            tree: fields.tree,
+        };
+        if witness.root() != witness.tree.root() {
+            return Err("witness path is not a path of the tree");
+        }
+        Ok(witness)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for IncrementalWitness {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let fields = IncrementalWitnessFields::deserialize_reader(reader)?;
+        Self::try_from(fields).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl IncrementalWitness {
+    /// Witnesses the last leaf appended to the tree. Returns None if the tree
+    /// is empty.
+    pub fn from_frontier(tree: &CommitmentFrontier, leaf: Node) -> Option<Self> {
+        let position = tree.size().checked_sub(1)?;
+        let empty = &*EMPTY_NODES;
+        let siblings = (0..TAIGA_COMMITMENT_TREE_DEPTH)
+            .map(|level| {
+                if (position >> level) & 1 == 1 {
+                    tree.frontier[level]
+                } else {
+                    empty[level]
+                }
+            })
+            .collect();
+        let witness = Self {
+            leaf,
+            position,
+            siblings,
+            tree: tree.clone(),
+        };
+        // The leaf must be the last one of the tree
+        (witness.root() == tree.root()).then_some(witness)
+    }
+
+    pub fn leaf(&self) -> Node {
+        self.leaf
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Appends a leaf after the witnessed one and updates the path.
+    pub fn append(&mut self, leaf: Node) -> Result<(), TransactionError> {
+        let position = self.position;
+        let siblings = &mut self.siblings;
+        let appended = self.tree.size();
+        self.tree.append_with(leaf, |level, node| {
+            // The appended leaf is in the right sibling of the path at the level
+            if (appended >> level) == ((position >> level) ^ 1) {
+                siblings[level] = *node;
+            }
+        })?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> MerklePath {
+        let merkle_path = self
+            .siblings
+            .iter()
+            .enumerate()
+            .map(|(level, sibling)| {
+                let side = if (self.position >> level) & 1 == 1 {
+                    L
+                } else {
+                    R
+                };
+                (*sibling, side)
+            })
+            .collect();
+        MerklePath::from_path(merkle_path)
+    }
+
+    /// The current root of the tree, the anchor of the path.
+    pub fn root(&self) -> Anchor {
+        self.path().root(self.leaf)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{
+        commitment_tree_path, commitment_tree_root, CommitmentFrontier, IncrementalWitness,
+        MerklePath, Node, LR,
+    };
+    use crate::constant::TAIGA_COMMITMENT_TREE_DEPTH;
+    use crate::nullifier::tests::arb_base;
+    use proptest::prelude::*;
+
+    impl Arbitrary for MerklePath {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            prop::collection::vec(
+                (
+                    arb_base().prop_map(Node::from),
+                    any::<bool>().prop_map(LR::from),
+                ),
+                0..=TAIGA_COMMITMENT_TREE_DEPTH,
+            )
+            .prop_map(MerklePath::from_path)
+            .boxed()
+        }
+    }
+
+    /// Builds the paths of all the leaves in a commitment tree whose leftmost
+    /// leaves are `leaves` and whose remaining leaves are zero.
+    pub fn merkle_paths_from_leaves(leaves: &[Node]) -> Vec<MerklePath> {
+        (0..leaves.len())
+            .map(|position| commitment_tree_path(leaves, position).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_commitment_tree() {
+        use rand::rngs::OsRng;
+
+        let mut rng = OsRng;
+        let leaves: Vec<Node> = (0..5).map(|_| Node::rand(&mut rng)).collect();
+        let root = commitment_tree_root(&leaves);
+        for (leaf, path) in leaves.iter().zip(merkle_paths_from_leaves(&leaves)) {
+            assert_eq!(path.root(*leaf), root);
+        }
+        assert!(commitment_tree_path(&leaves, leaves.len()).is_none());
+        assert_ne!(commitment_tree_root(&leaves[..4]), root);
+    }
+
+    #[test]
+    fn test_incremental_witness() {
+        use rand::rngs::OsRng;
+
+        let mut rng = OsRng;
+        let leaves: Vec<Node> = (0..11).map(|_| Node::rand(&mut rng)).collect();
+        let mut tree = CommitmentFrontier::new();
+        let mut witnesses: Vec<IncrementalWitness> = vec![];
+        for (size, leaf) in leaves.iter().enumerate() {
+            for witness in witnesses.iter_mut() {
+                witness.append(*leaf).unwrap();
+            }
+            assert_eq!(tree.append(*leaf).unwrap(), size as u64);
+            witnesses.push(IncrementalWitness::from_frontier(&tree, *leaf).unwrap());
+
+            // All the witnesses are fresh
+            let root = commitment_tree_root(&leaves[..=size]);
+            assert_eq!(tree.root(), root);
+            for witness in witnesses.iter() {
+                assert_eq!(witness.root(), root);
+                assert_eq!(
+                    witness.path(),
+                    commitment_tree_path(&leaves[..=size], witness.position() as usize).unwrap()
+                );
+            }
+        }
+
+        // Only the last leaf can be witnessed
+        assert!(IncrementalWitness::from_frontier(&tree, leaves[0]).is_none());
+        assert!(IncrementalWitness::from_frontier(&CommitmentFrontier::new(), leaves[0]).is_none());
+
+        #[cfg(feature = "borsh")]
+        {
+            let bytes = borsh::to_vec(&tree).unwrap();
+            assert_eq!(
+                borsh::from_slice::<CommitmentFrontier>(&bytes).unwrap(),
+                tree
+            );
+            let bytes = borsh::to_vec(&witnesses[3]).unwrap();
+            assert_eq!(
+                borsh::from_slice::<IncrementalWitness>(&bytes).unwrap(),
+                witnesses[3]
+            );
+
+            // A frontier shorter than the tree is rejected
+            let mut short = tree.clone();
+            short.frontier.pop();
+            let bytes = borsh::to_vec(&short).unwrap();
+            assert!(borsh::from_slice::<CommitmentFrontier>(&bytes).is_err());
+            // So is a witness of another leaf
+            let mut moved = witnesses[3].clone();
+            moved.leaf = leaves[4];
+            let bytes = borsh::to_vec(&moved).unwrap();
+            assert!(borsh::from_slice::<IncrementalWitness>(&bytes).is_err());
+        }
+    }
+
+    proptest! {
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_merkle_path_borsh_round_trip(path: MerklePath) {
+            let bytes = borsh::to_vec(&path).unwrap();
+            prop_assert_eq!(borsh::from_slice::<MerklePath>(&bytes).unwrap(), path);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_merkle_path_serde_round_trip(path: MerklePath) {
+            let json = serde_json::to_string(&path).unwrap();
+            prop_assert_eq!(serde_json::from_str::<MerklePath>(&json).unwrap(), path);
+        }
+    }
+}
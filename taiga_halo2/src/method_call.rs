@@ -0,0 +1,45 @@
+//! Method calls let an app express several operations under one logic vk.
+//!
+//! By convention the `value` of the resource encodes the call: the selector of
+//! the method and the digest of its arguments. The logic of the app opens the
+//! value with `method_call_gadget` and dispatches on the selector with
+//! `MethodDispatchConfig`, which only accepts the selectors allowed for the
+//! resource version and returns a flag per allowed method. The constraints of
+//! each method are conditioned on its flag.
+use crate::{constant::METHOD_CALL_DOMAIN, utils::poseidon_hash_n, utils::to_field_elements};
+use pasta_curves::pallas;
+
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq)]
+pub struct MethodCall {
+    pub selector: pallas::Base,
+    /// The digest of the arguments, its encoding is up to the method.
+    pub args_digest: pallas::Base,
+}
+
+impl MethodCall {
+    pub fn new(method: &str, args_digest: pallas::Base) -> Self {
+        Self {
+            selector: method_selector(method),
+            args_digest,
+        }
+    }
+
+    /// value = poseidon_hash(METHOD_CALL_DOMAIN || selector || args_digest)
+    pub fn encode_value(&self) -> pallas::Base {
+        poseidon_hash_n([*METHOD_CALL_DOMAIN, self.selector, self.args_digest])
+    }
+
+    /// The index of the method in `allowed`, the order of the flags returned
+    /// by `MethodDispatchConfig`. None if the method is not allowed.
+    pub fn dispatch(&self, allowed: &[pallas::Base]) -> Option<usize> {
+        allowed
+            .iter()
+            .position(|selector| *selector == self.selector)
+    }
+}
+
+/// The selector of a method name, the name is at most 31 bytes.
+pub fn method_selector(method: &str) -> pallas::Base {
+    assert!(!method.is_empty() && method.len() <= 31);
+    to_field_elements(method.as_bytes())[0]
+}
@@ -0,0 +1,169 @@
+//! Metrics of the verification paths, for the capacity planning of executor
+//! nodes.
+//!
+//! Taiga doesn't depend on a metrics backend: operators install a
+//! [`MetricsSink`] forwarding to theirs (Prometheus, StatsD...) once at
+//! startup with [`set_metrics_sink`]. Nothing is recorded until then.
+use crate::error::TransactionError;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Verifications by kind and result: `accepted` or the rejection reason.
+pub const VERIFICATIONS_TOTAL: &str = "taiga_verifications_total";
+/// Verification latency in seconds, by kind.
+pub const VERIFICATION_SECONDS: &str = "taiga_verification_seconds";
+/// Proofs verified, individually or in a batch.
+pub const PROOFS_VERIFIED_TOTAL: &str = "taiga_proofs_verified_total";
+/// Number of transactions per batch verification.
+pub const BATCH_SIZE: &str = "taiga_batch_size";
+
+pub const KIND_TRANSACTION: &str = "transaction";
+pub const KIND_BATCH: &str = "batch";
+pub const KIND_PARTIAL_TRANSACTION: &str = "partial_transaction";
+pub const KIND_LEDGER: &str = "ledger";
+
+pub type Labels<'a> = &'a [(&'static str, &'a str)];
+
+pub trait MetricsSink: Send + Sync {
+    fn increment_counter(&self, name: &'static str, labels: Labels, value: u64);
+
+    fn record_histogram(&self, name: &'static str, labels: Labels, value: f64);
+}
+
+static METRICS_SINK: OnceLock<Box<dyn MetricsSink>> = OnceLock::new();
+
+/// Installs the sink of the metrics. Returns it back if one is already
+/// installed.
+pub fn set_metrics_sink(sink: Box<dyn MetricsSink>) -> Result<(), Box<dyn MetricsSink>> {
+    METRICS_SINK.set(sink)
+}
+
+pub(crate) fn increment_counter(name: &'static str, labels: Labels, value: u64) {
+    if let Some(sink) = METRICS_SINK.get() {
+        sink.increment_counter(name, labels, value);
+    }
+}
+
+pub(crate) fn record_histogram(name: &'static str, labels: Labels, value: f64) {
+    if let Some(sink) = METRICS_SINK.get() {
+        sink.record_histogram(name, labels, value);
+    }
+}
+
+/// The name of the error variant, e.g. `DoubleSpend`.
+pub fn rejection_reason(error: &TransactionError) -> String {
+    let debug = format!("{error:?}");
+    debug
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Records the result and the latency of a verification started at `start`.
+pub(crate) fn record_verification<T>(
+    kind: &'static str,
+    start: Instant,
+    result: &Result<T, TransactionError>,
+) {
+    if METRICS_SINK.get().is_none() {
+        return;
+    }
+    let reason = match result {
+        Ok(_) => "accepted".to_string(),
+        Err(e) => rejection_reason(e),
+    };
+    increment_counter(
+        VERIFICATIONS_TOTAL,
+        &[("kind", kind), ("result", &reason)],
+        1,
+    );
+    record_histogram(
+        VERIFICATION_SECONDS,
+        &[("kind", kind)],
+        start.elapsed().as_secs_f64(),
+    );
+}
+
+#[test]
+fn test_metrics_sink() {
+    use crate::transaction::{
+        testing::create_shielded_ptx_bundle, Transaction, TransparentPartialTxBundle,
+    };
+    use rand::rngs::OsRng;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        counters: Mutex<HashMap<String, u64>>,
+        histograms: Mutex<HashMap<String, Vec<f64>>>,
+    }
+
+    fn key(name: &str, labels: Labels) -> String {
+        labels.iter().fold(name.to_string(), |key, (label, value)| {
+            format!("{key},{label}={value}")
+        })
+    }
+
+    impl MetricsSink for &'static RecordingSink {
+        fn increment_counter(&self, name: &'static str, labels: Labels, value: u64) {
+            *self
+                .counters
+                .lock()
+                .unwrap()
+                .entry(key(name, labels))
+                .or_default() += value;
+        }
+
+        fn record_histogram(&self, name: &'static str, labels: Labels, value: f64) {
+            self.histograms
+                .lock()
+                .unwrap()
+                .entry(key(name, labels))
+                .or_default()
+                .push(value);
+        }
+    }
+
+    let sink: &'static RecordingSink = Box::leak(Box::default());
+    assert!(set_metrics_sink(Box::new(sink)).is_ok());
+    assert!(set_metrics_sink(Box::new(sink)).is_err());
+
+    let mut rng = OsRng;
+    let tx = Transaction::build(
+        &mut rng,
+        create_shielded_ptx_bundle(1),
+        TransparentPartialTxBundle::default(),
+    )
+    .unwrap();
+    tx.execute().unwrap();
+    Transaction::batch_verify(&[tx.clone(), tx]).unwrap();
+
+    let counters = sink.counters.lock().unwrap();
+    assert!(
+        counters[&key(
+            VERIFICATIONS_TOTAL,
+            &[("kind", KIND_TRANSACTION), ("result", "accepted")]
+        )] >= 1
+    );
+    assert!(
+        counters[&key(
+            VERIFICATIONS_TOTAL,
+            &[("kind", KIND_BATCH), ("result", "accepted")]
+        )] >= 1
+    );
+    assert!(counters[&key(PROOFS_VERIFIED_TOTAL, &[])] >= 3);
+    let histograms = sink.histograms.lock().unwrap();
+    assert!(histograms[&key(BATCH_SIZE, &[])].contains(&2.0));
+    assert!(!histograms[&key(VERIFICATION_SECONDS, &[("kind", KIND_TRANSACTION)])].is_empty());
+
+    assert_eq!(
+        rejection_reason(&TransactionError::DoubleSpend(Default::default())),
+        "DoubleSpend"
+    );
+    assert_eq!(
+        rejection_reason(&TransactionError::InvalidBindingSignature),
+        "InvalidBindingSignature"
+    );
+}
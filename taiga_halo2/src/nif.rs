@@ -0,0 +1,232 @@
+//! NIF functions to build, compose and verify transactions from Elixir.
+//!
+//! The functions return `{:ok, value}` or `{:error, reason}` where the reason
+//! is the snake case name of the `TransactionError`, e.g.
+//! `:unbalanced_resources`, or `:invalid_argument` for terms that can't be
+//! decoded. Proving and verifying run on the dirty CPU schedulers. The NIF
+//! library registers them with
+//! `rustler::init!("Elixir.Taiga.Native", [taiga_halo2::nif::create_shielded_ptx, ..])`.
+//!
+//! Merkle paths and application bytecodes are passed as their borsh encodings.
+use crate::{
+    circuit::resource_logic_bytecode::ApplicationByteCode,
+    compliance::ComplianceInfo,
+    error::TransactionError,
+    merkle_tree::{Anchor, MerklePath},
+    resource::Resource,
+    shielded_ptx::ShieldedPartialTransaction,
+    taiga_api::{create_shielded_partial_transaction, create_transaction},
+    transaction::{Transaction, TransactionResult},
+};
+use borsh::BorshDeserialize;
+use rand::rngs::OsRng;
+use rustler::{types::atom::Atom, Encoder, Env, NifResult, Term};
+
+/// The reason of a failed NIF call, encoded as an atom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorAtom(&'static str);
+
+impl ErrorAtom {
+    pub fn invalid_argument() -> Self {
+        Self("invalid_argument")
+    }
+
+    pub fn name(&self) -> &str {
+        self.0
+    }
+}
+
+impl From<TransactionError> for ErrorAtom {
+    fn from(e: TransactionError) -> Self {
+        use TransactionError::*;
+        // The atoms are part of the NIF API, a new variant gets its own
+        Self(match e {
+            Proof(_) => "proof",
+            Keygen(_) => "keygen",
+            InvalidBindingSignature => "invalid_binding_signature",
+            MissingBindingSignatures => "missing_binding_signatures",
+            InconsistentNullifier => "inconsistent_nullifier",
+            InconsistentOutputResourceCommitment => "inconsistent_output_resource_commitment",
+            InconsistentSelfResourceID => "inconsistent_self_resource_id",
+            IoError(_) => "io_error",
+            MissingTransparentResourceNullifierKey => "missing_transparent_resource_nullifier_key",
+            MissingTransparentResourceMerklePath => "missing_transparent_resource_merkle_path",
+            MissingPartialTxBindingSignatureR => "missing_partial_tx_binding_signature_r",
+            InvalidResourceLogicRepresentation => "invalid_resource_logic_representation",
+            InconsistentResourceMerkleRoot => "inconsistent_resource_merkle_root",
+            MissingResourceLogicProof(_) => "missing_resource_logic_proof",
+            DuplicateResourceLogicProof(_) => "duplicate_resource_logic_proof",
+            UnknownOwnedResource(_) => "unknown_owned_resource",
+            InsufficientReserves => "insufficient_reserves",
+            InconsistentReserveAnchor => "inconsistent_reserve_anchor",
+            MissingNullifierKey => "missing_nullifier_key",
+            UnknownResourceCommitment => "unknown_resource_commitment",
+            InvalidSpentProof => "invalid_spent_proof",
+            DoubleSpend(_) => "double_spend",
+            CyclicTransactionDependency => "cyclic_transaction_dependency",
+            UnpairedResources => "unpaired_resources",
+            UnbalancedResources => "unbalanced_resources",
+            TooManyResources => "too_many_resources",
+            MissingApplicationLogic => "missing_application_logic",
+            InvalidChannelUpdate => "invalid_channel_update",
+            StaleChannelUpdate => "stale_channel_update",
+            InvalidAuxiliaryDelta => "invalid_auxiliary_delta",
+            UnbalancedAuxiliaryDelta => "unbalanced_auxiliary_delta",
+            ReceiptClaimMismatch => "receipt_claim_mismatch",
+            InvalidReceipt => "invalid_receipt",
+            TransactionNotArchived => "transaction_not_archived",
+            PrunedTransactionMismatch => "pruned_transaction_mismatch",
+            MailboxCommitmentMismatch => "mailbox_commitment_mismatch",
+            InvalidMailbox => "invalid_mailbox",
+            UnknownParamsSize(_) => "unknown_params_size",
+            ParamsDigestMismatch(_) => "params_digest_mismatch",
+            CommitmentTreeFull => "commitment_tree_full",
+            InvalidOutputResourceNonce => "invalid_output_resource_nonce",
+            UnsatisfiedResourceLogic => "unsatisfied_resource_logic",
+            DuplicateLinkabilityTag(_) => "duplicate_linkability_tag",
+            DuplicateCompliance => "duplicate_compliance",
+            UnregisteredResourceLogic(_) => "unregistered_resource_logic",
+            InvalidHistoryLog => "invalid_history_log",
+            HistoryAnchorMismatch => "history_anchor_mismatch",
+            InvalidWasmResourceLogic(_) => "invalid_wasm_resource_logic",
+            InvalidNoirResourceLogic(_) => "invalid_noir_resource_logic",
+            SweepNotAllowed => "sweep_not_allowed",
+            InvalidEpoch => "invalid_epoch",
+            InvalidVote => "invalid_vote",
+            InvalidRoyalty => "invalid_royalty",
+            UnbalancedResourceKind(_) => "unbalanced_resource_kind",
+            UnbalancedShieldedResources => "unbalanced_shielded_resources",
+            InvalidBalanceOpening => "invalid_balance_opening",
+            InvalidTransactionEncoding => "invalid_transaction_encoding",
+            UnknownTransactionVersion(_) => "unknown_transaction_version",
+            InvalidVampIRResourceLogic(_) => "invalid_vamp_ir_resource_logic",
+            InvalidConversion => "invalid_conversion",
+            UnauthorizedConversion(_) => "unauthorized_conversion",
+            MemoTooLong => "memo_too_long",
+            InvalidMemo => "invalid_memo",
+            InvalidSeed => "invalid_seed",
+            InvalidKeyIndex(_) => "invalid_key_index",
+            InvalidComplianceAnchor => "invalid_compliance_anchor",
+            NoCheckpoint => "no_checkpoint",
+            InvalidResource(_) => "invalid_resource",
+            DuplicateReserveResource => "duplicate_reserve_resource",
+            UnknownAnchor => "unknown_anchor",
+            MissingReceipt => "missing_receipt",
+            MissingReceiptVerifier => "missing_receipt_verifier",
+            InvalidSwapFill => "invalid_swap_fill",
+            UnsupportedParamsSize(_) => "unsupported_params_size",
+            InvalidSweep => "invalid_sweep",
+            UnknownBlacklist => "unknown_blacklist",
+        })
+    }
+}
+
+impl Encoder for ErrorAtom {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        Atom::from_str(env, self.0)
+            .expect("error atoms are short ascii")
+            .encode(env)
+    }
+}
+
+fn error(reason: impl Into<ErrorAtom>) -> rustler::Error {
+    rustler::Error::Term(Box::new(reason.into()))
+}
+
+fn decode<T: BorshDeserialize>(bytes: &[u8]) -> NifResult<T> {
+    let mut bytes = bytes;
+    BorshDeserialize::deserialize(&mut bytes).map_err(|_| error(ErrorAtom::invalid_argument()))
+}
+
+/// Builds the compliances of a ptx from `{input, merkle_path, anchor, output}`
+/// tuples, where the anchor is nil to use the root of the merkle path, and
+/// proves the ptx with the applications of the resources. Returns the ptx and
+/// the output resources, whose nonces are derived from the inputs.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn create_shielded_ptx(
+    compliances: Vec<(Resource, Vec<u8>, Option<Anchor>, Resource)>,
+    input_apps: Vec<Vec<u8>>,
+    output_apps: Vec<Vec<u8>>,
+    hints: Vec<u8>,
+) -> NifResult<(ShieldedPartialTransaction, Vec<Resource>)> {
+    if compliances.len() != input_apps.len() || compliances.len() != output_apps.len() {
+        return Err(error(ErrorAtom::invalid_argument()));
+    }
+    let mut outputs = Vec::with_capacity(compliances.len());
+    let compliances = compliances
+        .into_iter()
+        .map(|(input, merkle_path, anchor, mut output)| {
+            let merkle_path: MerklePath = decode(&merkle_path)?;
+            let compliance = ComplianceInfo::new(input, merkle_path, anchor, &mut output, OsRng);
+            outputs.push(output);
+            Ok(compliance)
+        })
+        .collect::<NifResult<Vec<_>>>()?;
+    let input_apps = input_apps
+        .iter()
+        .map(|app| decode::<ApplicationByteCode>(app))
+        .collect::<NifResult<Vec<_>>>()?;
+    let output_apps = output_apps
+        .iter()
+        .map(|app| decode::<ApplicationByteCode>(app))
+        .collect::<NifResult<Vec<_>>>()?;
+    let ptx = create_shielded_partial_transaction(compliances, input_apps, output_apps, hints)
+        .map_err(error)?;
+    Ok((ptx, outputs))
+}
+
+/// Composes ptxs into a balanced transaction.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn compose_transaction(ptxs: Vec<ShieldedPartialTransaction>) -> NifResult<Transaction> {
+    create_transaction(ptxs).map_err(error)
+}
+
+/// Verifies a transaction and returns the ledger changes it makes.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn execute_transaction(tx: Transaction) -> NifResult<TransactionResult> {
+    tx.execute().map_err(error)
+}
+
+#[test]
+fn test_error_atom() {
+    use pasta_curves::pallas;
+
+    assert_eq!(
+        ErrorAtom::from(TransactionError::UnbalancedResources).name(),
+        "unbalanced_resources"
+    );
+    assert_eq!(
+        ErrorAtom::from(TransactionError::DoubleSpend(pallas::Base::one())).name(),
+        "double_spend"
+    );
+    assert_eq!(
+        ErrorAtom::from(TransactionError::UnknownTransactionVersion(2)).name(),
+        "unknown_transaction_version"
+    );
+    assert_eq!(
+        ErrorAtom::from(TransactionError::InconsistentSelfResourceID).name(),
+        "inconsistent_self_resource_id"
+    );
+    for (e, name) in [
+        (TransactionError::UnknownAnchor, "unknown_anchor"),
+        (TransactionError::MissingReceipt, "missing_receipt"),
+        (
+            TransactionError::MissingReceiptVerifier,
+            "missing_receipt_verifier",
+        ),
+        (TransactionError::InvalidSwapFill, "invalid_swap_fill"),
+        (
+            TransactionError::UnsupportedParamsSize(13),
+            "unsupported_params_size",
+        ),
+        (TransactionError::InvalidSweep, "invalid_sweep"),
+        (TransactionError::UnknownBlacklist, "unknown_blacklist"),
+        (TransactionError::InvalidRoyalty, "invalid_royalty"),
+        (
+            TransactionError::InvalidKeyIndex(1 << 31),
+            "invalid_key_index",
+        ),
+    ] {
+        assert_eq!(ErrorAtom::from(e).name(), name);
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt;
 use std::hash::Hash;
 
 use crate::{
@@ -10,7 +11,8 @@ use pasta_curves::pallas;
 use rand::RngCore;
 #[cfg(feature = "nif")]
 use rustler::{NifTaggedEnum, NifTuple};
-use subtle::CtOption;
+use subtle::{Choice, ConstantTimeEq, CtOption};
+use zeroize::DefaultIsZeroes;
 
 #[cfg(feature = "serde")]
 use serde;
@@ -25,7 +27,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 pub struct Nullifier(pallas::Base);
 
 /// The NullifierKeyContainer contains the nullifier_key or the nullifier_key commitment
-#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone)]
 #[cfg_attr(feature = "nif", derive(NifTaggedEnum))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NullifierKeyContainer {
@@ -96,6 +98,35 @@ impl BorshDeserialize for Nullifier {
     }
 }
 
+#[cfg(feature = "borsh")]
+impl BorshSerialize for NullifierKeyContainer {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let (tag, nk) = match self {
+            NullifierKeyContainer::PublicKey(npk) => (1u8, npk),
+            NullifierKeyContainer::Key(nk) => (2u8, nk),
+        };
+        writer.write_all(&[tag])?;
+        writer.write_all(&nk.to_repr())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for NullifierKeyContainer {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        use std::io;
+        let tag = u8::deserialize_reader(reader)?;
+        let nk = crate::utils::read_base_field(reader)?;
+        match tag {
+            1 => Ok(NullifierKeyContainer::from_npk(nk)),
+            2 => Ok(NullifierKeyContainer::from_key(nk)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid nullifier key container",
+            )),
+        }
+    }
+}
+
 impl Hash for Nullifier {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.0.to_repr().hash(state);
@@ -131,13 +162,22 @@ impl NullifierKeyContainer {
     pub fn get_npk(&self) -> pallas::Base {
         match self {
             NullifierKeyContainer::PublicKey(v) => *v,
-            NullifierKeyContainer::Key(key) => {
-                // Commitment(nk, zero), use poseidon hash as Commitment.
-                prf_nf(*key, pallas::Base::zero())
-            }
+            NullifierKeyContainer::Key(key) => Self::derive_npk(*key),
         }
     }
 
+    /// npk = Commitment(nk, zero), use poseidon hash as Commitment. The
+    /// circuit counterpart is `derive_npk_gadget`.
+    pub fn derive_npk(nk: pallas::Base) -> pallas::Base {
+        prf_nf(nk, pallas::Base::zero())
+    }
+
+    /// Whether the container holds the nk behind `npk`, i.e. whether it can
+    /// prove the possession of the key with `nk_possession_gadget`.
+    pub fn possesses(&self, npk: pallas::Base) -> bool {
+        matches!(self.get_nk(), Some(nk) if bool::from(Self::derive_npk(nk).ct_eq(&npk)))
+    }
+
     pub fn to_commitment(&self) -> Self {
         match self {
             NullifierKeyContainer::PublicKey(_) => *self,
@@ -146,6 +186,40 @@ impl NullifierKeyContainer {
     }
 }
 
+impl DefaultIsZeroes for NullifierKeyContainer {}
+
+/// Compares the keys in constant time. Containers holding a key and its
+/// commitment differ.
+impl ConstantTimeEq for NullifierKeyContainer {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        match (self, other) {
+            (NullifierKeyContainer::Key(a), NullifierKeyContainer::Key(b))
+            | (NullifierKeyContainer::PublicKey(a), NullifierKeyContainer::PublicKey(b)) => {
+                a.ct_eq(b)
+            }
+            _ => Choice::from(0),
+        }
+    }
+}
+
+impl PartialEq for NullifierKeyContainer {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for NullifierKeyContainer {}
+
+// The key is not printed, its commitment is
+impl fmt::Debug for NullifierKeyContainer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NullifierKeyContainer::PublicKey(npk) => f.debug_tuple("PublicKey").field(npk).finish(),
+            NullifierKeyContainer::Key(_) => f.write_str("Key(..)"),
+        }
+    }
+}
+
 impl Default for NullifierKeyContainer {
     fn default() -> NullifierKeyContainer {
         let key = pallas::Base::default();
@@ -155,12 +229,45 @@ impl Default for NullifierKeyContainer {
 
 #[cfg(test)]
 pub mod tests {
+    use ff::FromUniformBytes;
     use halo2_proofs::arithmetic::Field;
     use pasta_curves::pallas;
+    use proptest::prelude::*;
     use rand::RngCore;
 
     use super::{Nullifier, NullifierKeyContainer};
 
+    /// Any base field element, the 32 bytes reduced modulo p.
+    pub fn arb_base() -> impl Strategy<Value = pallas::Base> {
+        any::<[u8; 32]>().prop_map(|bytes| {
+            let mut wide = [0u8; 64];
+            wide[..32].copy_from_slice(&bytes);
+            pallas::Base::from_uniform_bytes(&wide)
+        })
+    }
+
+    impl Arbitrary for Nullifier {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            arb_base().prop_map(Nullifier::from).boxed()
+        }
+    }
+
+    impl Arbitrary for NullifierKeyContainer {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            prop_oneof![
+                arb_base().prop_map(NullifierKeyContainer::from_key),
+                arb_base().prop_map(NullifierKeyContainer::from_npk),
+            ]
+            .boxed()
+        }
+    }
+
     pub fn random_nullifier<R: RngCore>(mut rng: R) -> Nullifier {
         Nullifier::from(pallas::Base::random(&mut rng))
     }
@@ -172,4 +279,41 @@ pub mod tests {
     pub fn random_nullifier_key_commitment<R: RngCore>(mut rng: R) -> NullifierKeyContainer {
         NullifierKeyContainer::from_npk(pallas::Base::random(&mut rng))
     }
+
+    #[test]
+    fn test_nullifier_key_secrecy() {
+        use rand::rngs::OsRng;
+        use subtle::ConstantTimeEq;
+        use zeroize::Zeroize;
+
+        let mut nk = random_nullifier_key(OsRng);
+        let npk = nk.get_npk();
+        assert!(bool::from(nk.ct_eq(&nk)));
+        assert!(!bool::from(nk.ct_eq(&nk.to_commitment())));
+        assert!(nk.possesses(npk));
+
+        nk.zeroize();
+        assert_eq!(nk, NullifierKeyContainer::default());
+        assert!(!nk.possesses(npk));
+    }
+
+    proptest! {
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_nullifier_borsh_round_trip(nf: Nullifier, nk: NullifierKeyContainer) {
+            let bytes = borsh::to_vec(&nf).unwrap();
+            prop_assert_eq!(borsh::from_slice::<Nullifier>(&bytes).unwrap(), nf);
+            let bytes = borsh::to_vec(&nk).unwrap();
+            prop_assert_eq!(borsh::from_slice::<NullifierKeyContainer>(&bytes).unwrap(), nk);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_nullifier_serde_round_trip(nf: Nullifier, nk: NullifierKeyContainer) {
+            let json = serde_json::to_string(&nf).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Nullifier>(&json).unwrap(), nf);
+            let json = serde_json::to_string(&nk).unwrap();
+            prop_assert_eq!(serde_json::from_str::<NullifierKeyContainer>(&json).unwrap(), nk);
+        }
+    }
 }
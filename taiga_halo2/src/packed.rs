@@ -0,0 +1,245 @@
+//! Packed encodings of public inputs and proofs for the NIF boundary.
+//!
+//! The term encodings send every field element as its own 32-byte binary and
+//! every proof as a list of bytes, which is slow for large batches. The packed
+//! encodings send a batch as `{count, binary}`: the binary concatenates the
+//! 32-byte representations of the public inputs, or the proofs each prefixed
+//! by its u32 little-endian length. [`PackedPublicInputs`] and
+//! [`PackedProofs`] read a batch in place from the binary of the term, see
+//! `benches/packed_encoding.rs` for the comparison with the term encodings.
+use crate::{
+    circuit::resource_logic_circuit::ResourceLogicPublicInputs,
+    constant::RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM, proof::Proof,
+};
+use ff::PrimeField;
+use pasta_curves::pallas;
+
+#[cfg(feature = "nif")]
+use rustler::{Binary, Decoder, Encoder, Env, NifResult, OwnedBinary, Term};
+
+/// The size of a packed field element.
+pub const PACKED_ELEMENT_SIZE: usize = 32;
+/// The size of packed public inputs.
+pub const PACKED_PUBLIC_INPUTS_SIZE: usize =
+    PACKED_ELEMENT_SIZE * RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM;
+
+pub fn pack_public_inputs(public_inputs: &[ResourceLogicPublicInputs]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(public_inputs.len() * PACKED_PUBLIC_INPUTS_SIZE);
+    for element in public_inputs.iter().flat_map(|inputs| inputs.inner()) {
+        bytes.extend_from_slice(&element.to_repr());
+    }
+    bytes
+}
+
+pub fn pack_proofs(proofs: &[Proof]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for proof in proofs {
+        let proof = proof.inner();
+        bytes.extend_from_slice(&(proof.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&proof);
+    }
+    bytes
+}
+
+/// A batch of packed public inputs, borrowed from its binary.
+#[derive(Clone, Copy, Debug)]
+pub struct PackedPublicInputs<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> PackedPublicInputs<'a> {
+    /// Returns None if the binary is not `count` packed public inputs.
+    pub fn new(bytes: &'a [u8], count: usize) -> Option<Self> {
+        (count.checked_mul(PACKED_PUBLIC_INPUTS_SIZE)? == bytes.len()).then_some(Self { bytes })
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len() / PACKED_PUBLIC_INPUTS_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Decodes the public inputs at `index`. Returns None if the index is out
+    /// of bounds or an element is not canonical.
+    pub fn get(&self, index: usize) -> Option<ResourceLogicPublicInputs> {
+        let begin = index.checked_mul(PACKED_PUBLIC_INPUTS_SIZE)?;
+        let end = begin.checked_add(PACKED_PUBLIC_INPUTS_SIZE)?;
+        let bytes = self.bytes.get(begin..end)?;
+        decode_public_inputs(bytes)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Option<ResourceLogicPublicInputs>> + 'a {
+        self.bytes
+            .chunks_exact(PACKED_PUBLIC_INPUTS_SIZE)
+            .map(decode_public_inputs)
+    }
+
+    /// Decodes the whole batch. Returns None if an element is not canonical.
+    pub fn decode(&self) -> Option<Vec<ResourceLogicPublicInputs>> {
+        self.iter().collect()
+    }
+}
+
+fn decode_public_inputs(bytes: &[u8]) -> Option<ResourceLogicPublicInputs> {
+    let mut public_inputs = [pallas::Base::zero(); RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM];
+    for (element, repr) in public_inputs
+        .iter_mut()
+        .zip(bytes.chunks_exact(PACKED_ELEMENT_SIZE))
+    {
+        *element = Option::from(pallas::Base::from_repr(repr.try_into().unwrap()))?;
+    }
+    Some(public_inputs.into())
+}
+
+/// A batch of packed proofs, borrowed from its binary.
+#[derive(Clone, Copy, Debug)]
+pub struct PackedProofs<'a> {
+    bytes: &'a [u8],
+    count: usize,
+}
+
+impl<'a> PackedProofs<'a> {
+    /// Returns None if the binary is not `count` packed proofs.
+    pub fn new(bytes: &'a [u8], count: usize) -> Option<Self> {
+        let mut rest = bytes;
+        let mut found = 0;
+        while !rest.is_empty() {
+            (_, rest) = split_proof(rest)?;
+            found += 1;
+        }
+        (found == count).then_some(Self { bytes, count })
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The bytes of the proofs, without copying them.
+    pub fn iter(&self) -> impl Iterator<Item = &'a [u8]> {
+        let mut rest = self.bytes;
+        std::iter::from_fn(move || {
+            // The framing is checked by `new`
+            let (proof, next) = split_proof(rest)?;
+            rest = next;
+            Some(proof)
+        })
+    }
+
+    pub fn decode(&self) -> Vec<Proof> {
+        self.iter()
+            .map(|proof| Proof::new(proof.to_vec()))
+            .collect()
+    }
+}
+
+// Splits the first packed proof from the rest of the binary
+fn split_proof(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (len, rest) = bytes.split_first_chunk::<4>()?;
+    let len = u32::from_le_bytes(*len) as usize;
+    (len <= rest.len()).then(|| rest.split_at(len))
+}
+
+#[cfg(feature = "nif")]
+fn encode_packed<'a>(env: Env<'a>, count: usize, bytes: &[u8]) -> Term<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).expect("failure to allocate a binary");
+    binary.as_mut_slice().copy_from_slice(bytes);
+    (count, Binary::from_owned(binary, env)).encode(env)
+}
+
+/// A batch of public inputs encoded as `{count, binary}` across the NIF
+/// boundary.
+#[derive(Clone, Debug, Default)]
+pub struct PublicInputsBatch(pub Vec<ResourceLogicPublicInputs>);
+
+#[cfg(feature = "nif")]
+impl Encoder for PublicInputsBatch {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        encode_packed(env, self.0.len(), &pack_public_inputs(&self.0))
+    }
+}
+
+#[cfg(feature = "nif")]
+impl<'a> Decoder<'a> for PublicInputsBatch {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let (count, binary): (usize, Binary<'a>) = term.decode()?;
+        PackedPublicInputs::new(binary.as_slice(), count)
+            .and_then(|packed| packed.decode())
+            .map(PublicInputsBatch)
+            .ok_or(rustler::Error::Atom("failure to decode"))
+    }
+}
+
+/// A batch of proofs encoded as `{count, binary}` across the NIF boundary.
+#[derive(Clone, Debug, Default)]
+pub struct ProofBatch(pub Vec<Proof>);
+
+#[cfg(feature = "nif")]
+impl Encoder for ProofBatch {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        encode_packed(env, self.0.len(), &pack_proofs(&self.0))
+    }
+}
+
+#[cfg(feature = "nif")]
+impl<'a> Decoder<'a> for ProofBatch {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let (count, binary): (usize, Binary<'a>) = term.decode()?;
+        PackedProofs::new(binary.as_slice(), count)
+            .map(|packed| ProofBatch(packed.decode()))
+            .ok_or(rustler::Error::Atom("failure to decode"))
+    }
+}
+
+#[test]
+fn test_packed_encoding() {
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let public_inputs: Vec<ResourceLogicPublicInputs> = (0..3)
+        .map(|_| {
+            (0..RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM)
+                .map(|_| pallas::Base::random(&mut rng))
+                .collect::<Vec<_>>()
+                .into()
+        })
+        .collect();
+    let mut bytes = pack_public_inputs(&public_inputs);
+    let packed = PackedPublicInputs::new(&bytes, 3).unwrap();
+    assert_eq!(packed.len(), 3);
+    assert_eq!(packed.get(1).unwrap().inner(), public_inputs[1].inner());
+    let decoded = packed.decode().unwrap();
+    for (decoded, public_inputs) in decoded.iter().zip(public_inputs.iter()) {
+        assert_eq!(decoded.inner(), public_inputs.inner());
+    }
+    assert!(packed.get(3).is_none());
+    assert!(packed.get(usize::MAX / PACKED_PUBLIC_INPUTS_SIZE).is_none());
+    // The count must match the binary
+    assert!(PackedPublicInputs::new(&bytes, 2).is_none());
+    // Non-canonical elements are rejected
+    bytes[PACKED_ELEMENT_SIZE - 1] = 0xff;
+    assert!(PackedPublicInputs::new(&bytes, 3)
+        .unwrap()
+        .decode()
+        .is_none());
+
+    let proofs = vec![
+        Proof::new(vec![1, 2, 3]),
+        Proof::new(vec![]),
+        Proof::new(vec![4; 100]),
+    ];
+    let bytes = pack_proofs(&proofs);
+    let packed = PackedProofs::new(&bytes, 3).unwrap();
+    assert_eq!(
+        packed.iter().collect::<Vec<_>>(),
+        [&[1, 2, 3][..], &[], &[4; 100]]
+    );
+    assert!(PackedProofs::new(&bytes, 2).is_none());
+    assert!(PackedProofs::new(&bytes[..bytes.len() - 1], 3).is_none());
+}
@@ -0,0 +1,459 @@
+//! Proofs created with params that differ from the verifier's don't verify,
+//! and nothing tells why. The expected digests of the setup params are
+//! embedded in [`crate::constant::SETUP_PARAMS_DIGESTS`]; nodes should call
+//! [`integrity_check`] at startup to fail fast when the params they ship are
+//! not the ones of this version.
+//!
+//! Resource logics may use params of other sizes, see [`get_params`].
+//!
+//! The proving and verification APIs use the setup params by default. A
+//! [`ProvingContext`] or a [`VerifyingContext`] passed to their `_with_context`
+//! variants injects other params, e.g. smaller ones in tests, and owns the
+//! params it generates for the other sizes.
+use crate::circuit::{
+    compliance_circuit::ComplianceCircuit, read_circuit::ReadCircuit,
+    reserve_circuit::ReserveCircuit, spent_circuit::SpentCircuit,
+};
+use crate::constant::{
+    COMPLIANCE_CIRCUIT_PARAMS_SIZE, COMPLIANCE_PROVING_KEY, COMPLIANCE_VERIFYING_KEY,
+    PARAMS_DIGEST_PERSONALIZATION, READ_CIRCUIT_PARAMS_SIZE, READ_PROVING_KEY, READ_VERIFYING_KEY,
+    RESERVE_CIRCUIT_PARAMS_SIZE, RESERVE_PROVING_KEY, RESERVE_VERIFYING_KEY,
+    RESOURCE_LOGIC_CIRCUIT_MAX_PARAMS_SIZE, RESOURCE_LOGIC_CIRCUIT_MIN_PARAMS_SIZE,
+    SETUP_PARAMS_DIGESTS, SETUP_PARAMS_MAP, SPENT_CIRCUIT_PARAMS_SIZE, SPENT_PROVING_KEY,
+    SPENT_VERIFYING_KEY,
+};
+use crate::error::TransactionError;
+use crate::quantity::Quantity;
+use crate::zkvm_receipt::ReceiptVerifier;
+use blake2b_simd::Params as Blake2bParams;
+use halo2_proofs::{
+    plonk::{keygen_pk, keygen_vk, Circuit, ProvingKey, VerifyingKey},
+    poly::commitment::Params,
+};
+use lazy_static::lazy_static;
+use pasta_curves::{pallas, vesta};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+const GENERATED_PARAMS_NUM: usize =
+    (RESOURCE_LOGIC_CIRCUIT_MAX_PARAMS_SIZE - RESOURCE_LOGIC_CIRCUIT_MIN_PARAMS_SIZE + 1) as usize;
+
+/// The params generated on demand, one slot per supported size.
+#[derive(Debug, Default)]
+struct GeneratedParams([OnceLock<Params<vesta::Affine>>; GENERATED_PARAMS_NUM]);
+
+impl GeneratedParams {
+    fn get(&self, k: u32) -> Result<&Params<vesta::Affine>, TransactionError> {
+        let slot = k
+            .checked_sub(RESOURCE_LOGIC_CIRCUIT_MIN_PARAMS_SIZE)
+            .and_then(|index| self.0.get(index as usize))
+            .ok_or(TransactionError::UnsupportedParamsSize(k))?;
+        Ok(slot.get_or_init(|| Params::new(k)))
+    }
+}
+
+lazy_static! {
+    static ref GENERATED_PARAMS: GeneratedParams = GeneratedParams::default();
+}
+
+/// The digest of the serialized params.
+pub fn params_digest(params: &Params<vesta::Affine>) -> [u8; 32] {
+    let mut bytes = vec![];
+    params
+        .write(&mut bytes)
+        .expect("writing to a vec should not fail");
+    Blake2bParams::new()
+        .hash_length(32)
+        .personal(PARAMS_DIGEST_PERSONALIZATION)
+        .hash(&bytes)
+        .as_bytes()
+        .try_into()
+        .unwrap()
+}
+
+pub fn expected_params_digest(k: u32) -> Option<&'static [u8; 32]> {
+    SETUP_PARAMS_DIGESTS
+        .iter()
+        .find(|(size, _)| *size == k)
+        .map(|(_, digest)| digest)
+}
+
+/// Checks the params of size `k` against the expected digest.
+pub fn check_params(k: u32, params: &Params<vesta::Affine>) -> Result<(), TransactionError> {
+    let expected = expected_params_digest(k).ok_or(TransactionError::UnknownParamsSize(k))?;
+    if params_digest(params) != *expected {
+        return Err(TransactionError::ParamsDigestMismatch(k));
+    }
+    Ok(())
+}
+
+/// Reads params of size `k`, e.g. from a file shipped by the operator, and
+/// checks them.
+pub fn read_params<R: std::io::Read>(
+    k: u32,
+    reader: &mut R,
+) -> Result<Params<vesta::Affine>, TransactionError> {
+    let params = Params::<vesta::Affine>::read(reader)?;
+    check_params(k, &params)?;
+    Ok(params)
+}
+
+pub fn is_supported_params_size(k: u32) -> bool {
+    SETUP_PARAMS_MAP.contains_key(&k)
+        || (RESOURCE_LOGIC_CIRCUIT_MIN_PARAMS_SIZE..=RESOURCE_LOGIC_CIRCUIT_MAX_PARAMS_SIZE)
+            .contains(&k)
+}
+
+/// The params of size `k`: the setup params if shipped, otherwise generated
+/// on the first use and cached. The IPA params are transparent, generating
+/// them only costs time.
+///
+/// Fails if the size is not supported, see [`is_supported_params_size`].
+pub fn get_params(k: u32) -> Result<&'static Params<vesta::Affine>, TransactionError> {
+    match SETUP_PARAMS_MAP.get(&k) {
+        Some(params) => Ok(params),
+        None => GENERATED_PARAMS.get(k),
+    }
+}
+
+/// The proving key of the empty circuit `C`.
+fn circuit_proving_key<C: Circuit<pallas::Base> + Default>(
+    params: &Params<vesta::Affine>,
+) -> ProvingKey<vesta::Affine> {
+    let empty_circuit = C::default();
+    let vk = keygen_vk(params, &empty_circuit).expect("keygen_vk should not fail");
+    keygen_pk(params, vk, &empty_circuit).expect("keygen_pk should not fail")
+}
+
+/// The params and the compliance, reserve, spent and read proving keys the proofs
+/// are created with. The params set with `with_params` replace the setup ones
+/// of their size, the params of the other supported sizes are generated on
+/// the first use and kept by the context. The keys are derived from the params
+/// of their circuit on the first use when they are replaced.
+///
+/// The default context uses the setup params and keys. Clones share the
+/// params and the derived keys, reuse a context to keep its generated params.
+#[derive(Clone, Debug, Default)]
+pub struct ProvingContext {
+    params: HashMap<u32, Arc<Params<vesta::Affine>>>,
+    generated: Arc<GeneratedParams>,
+    compliance_pk: Arc<OnceLock<ProvingKey<vesta::Affine>>>,
+    reserve_pk: Arc<OnceLock<ProvingKey<vesta::Affine>>>,
+    spent_pk: Arc<OnceLock<ProvingKey<vesta::Affine>>>,
+    read_pk: Arc<OnceLock<ProvingKey<vesta::Affine>>>,
+}
+
+/// Same as [`ProvingContext`], for the verifiers.
+#[derive(Clone, Debug, Default)]
+pub struct VerifyingContext {
+    params: HashMap<u32, Arc<Params<vesta::Affine>>>,
+    generated: Arc<GeneratedParams>,
+    compliance_vk: Arc<OnceLock<VerifyingKey<vesta::Affine>>>,
+    reserve_vk: Arc<OnceLock<VerifyingKey<vesta::Affine>>>,
+    spent_vk: Arc<OnceLock<VerifyingKey<vesta::Affine>>>,
+    read_vk: Arc<OnceLock<VerifyingKey<vesta::Affine>>>,
+    receipt_verifier: Option<Arc<dyn ReceiptVerifier>>,
+    dust_threshold: Quantity,
+}
+
+impl ProvingContext {
+    /// Replaces the params of size `k`, e.g. with params read by
+    /// [`read_params`].
+    pub fn with_params(mut self, k: u32, params: Params<vesta::Affine>) -> Self {
+        self.params.insert(k, Arc::new(params));
+        if k == COMPLIANCE_CIRCUIT_PARAMS_SIZE {
+            self.compliance_pk = Arc::default();
+        }
+        if k == RESERVE_CIRCUIT_PARAMS_SIZE {
+            self.reserve_pk = Arc::default();
+        }
+        if k == SPENT_CIRCUIT_PARAMS_SIZE {
+            self.spent_pk = Arc::default();
+        }
+        if k == READ_CIRCUIT_PARAMS_SIZE {
+            self.read_pk = Arc::default();
+        }
+        self
+    }
+
+    /// Whether the params of size `k` replace the setup ones, i.e. the keys
+    /// precomputed from the setup params can't be used.
+    pub fn has_params(&self, k: u32) -> bool {
+        self.params.contains_key(&k)
+    }
+
+    /// The params of size `k`. Fails if they are not set and the size is not
+    /// supported, see [`is_supported_params_size`].
+    pub fn params(&self, k: u32) -> Result<&Params<vesta::Affine>, TransactionError> {
+        match (self.params.get(&k), SETUP_PARAMS_MAP.get(&k)) {
+            (Some(params), _) => Ok(params),
+            (None, Some(params)) => Ok(params),
+            (None, None) => self.generated.get(k),
+        }
+    }
+
+    /// The params of the compliance, reserve, spent and read circuits, whose size is
+    /// the one of the setup params.
+    pub(crate) fn setup_params(&self, k: u32) -> &Params<vesta::Affine> {
+        match self.params.get(&k) {
+            Some(params) => params,
+            None => &SETUP_PARAMS_MAP[&k],
+        }
+    }
+
+    pub fn compliance_proving_key(&self) -> &ProvingKey<vesta::Affine> {
+        if !self.has_params(COMPLIANCE_CIRCUIT_PARAMS_SIZE) {
+            return &COMPLIANCE_PROVING_KEY;
+        }
+        self.compliance_pk.get_or_init(|| {
+            circuit_proving_key::<ComplianceCircuit>(
+                self.setup_params(COMPLIANCE_CIRCUIT_PARAMS_SIZE),
+            )
+        })
+    }
+
+    pub fn reserve_proving_key(&self) -> &ProvingKey<vesta::Affine> {
+        if !self.has_params(RESERVE_CIRCUIT_PARAMS_SIZE) {
+            return &RESERVE_PROVING_KEY;
+        }
+        self.reserve_pk.get_or_init(|| {
+            circuit_proving_key::<ReserveCircuit>(self.setup_params(RESERVE_CIRCUIT_PARAMS_SIZE))
+        })
+    }
+
+    pub fn spent_proving_key(&self) -> &ProvingKey<vesta::Affine> {
+        if !self.has_params(SPENT_CIRCUIT_PARAMS_SIZE) {
+            return &SPENT_PROVING_KEY;
+        }
+        self.spent_pk.get_or_init(|| {
+            circuit_proving_key::<SpentCircuit>(self.setup_params(SPENT_CIRCUIT_PARAMS_SIZE))
+        })
+    }
+
+    pub fn read_proving_key(&self) -> &ProvingKey<vesta::Affine> {
+        if !self.has_params(READ_CIRCUIT_PARAMS_SIZE) {
+            return &READ_PROVING_KEY;
+        }
+        self.read_pk.get_or_init(|| {
+            circuit_proving_key::<ReadCircuit>(self.setup_params(READ_CIRCUIT_PARAMS_SIZE))
+        })
+    }
+
+    /// The context verifying the proofs created with this one.
+    pub fn verifying_context(&self) -> VerifyingContext {
+        let derived_vk = |pk: &OnceLock<ProvingKey<vesta::Affine>>| {
+            let vk = OnceLock::new();
+            if let Some(pk) = pk.get() {
+                let _ = vk.set(pk.get_vk().clone());
+            }
+            Arc::new(vk)
+        };
+        VerifyingContext {
+            params: self.params.clone(),
+            generated: self.generated.clone(),
+            compliance_vk: derived_vk(&self.compliance_pk),
+            reserve_vk: derived_vk(&self.reserve_pk),
+            spent_vk: derived_vk(&self.spent_pk),
+            read_vk: derived_vk(&self.read_pk),
+            receipt_verifier: None,
+            dust_threshold: Quantity::default(),
+        }
+    }
+}
+
+impl VerifyingContext {
+    /// Replaces the params of size `k`, see [`ProvingContext::with_params`].
+    pub fn with_params(mut self, k: u32, params: Params<vesta::Affine>) -> Self {
+        self.params.insert(k, Arc::new(params));
+        if k == COMPLIANCE_CIRCUIT_PARAMS_SIZE {
+            self.compliance_vk = Arc::default();
+        }
+        if k == RESERVE_CIRCUIT_PARAMS_SIZE {
+            self.reserve_vk = Arc::default();
+        }
+        if k == SPENT_CIRCUIT_PARAMS_SIZE {
+            self.spent_vk = Arc::default();
+        }
+        if k == READ_CIRCUIT_PARAMS_SIZE {
+            self.read_vk = Arc::default();
+        }
+        self
+    }
+
+    /// Sets the verifier of the zkVM receipts the resource logics delegate to,
+    /// see [`ZkvmReceipt`](crate::zkvm_receipt::ZkvmReceipt). A transaction
+    /// carrying receipts doesn't verify without one.
+    pub fn with_receipt_verifier(mut self, verifier: impl ReceiptVerifier + 'static) -> Self {
+        self.receipt_verifier = Some(Arc::new(verifier));
+        self
+    }
+
+    pub fn receipt_verifier(&self) -> Option<&dyn ReceiptVerifier> {
+        self.receipt_verifier.as_deref()
+    }
+
+    /// Sets the quantity up to which anyone may sweep a sweepable resource
+    /// before its expiry, see `apps::sweep`. Zero by default, only expired
+    /// resources are swept.
+    pub fn with_dust_threshold(mut self, dust_threshold: Quantity) -> Self {
+        self.dust_threshold = dust_threshold;
+        self
+    }
+
+    pub fn dust_threshold(&self) -> Quantity {
+        self.dust_threshold
+    }
+
+    /// The params of size `k`, see [`ProvingContext::params`].
+    pub fn params(&self, k: u32) -> Result<&Params<vesta::Affine>, TransactionError> {
+        match (self.params.get(&k), SETUP_PARAMS_MAP.get(&k)) {
+            (Some(params), _) => Ok(params),
+            (None, Some(params)) => Ok(params),
+            (None, None) => self.generated.get(k),
+        }
+    }
+
+    /// See [`ProvingContext::setup_params`].
+    pub(crate) fn setup_params(&self, k: u32) -> &Params<vesta::Affine> {
+        match self.params.get(&k) {
+            Some(params) => params,
+            None => &SETUP_PARAMS_MAP[&k],
+        }
+    }
+
+    pub fn compliance_verifying_key(&self) -> &VerifyingKey<vesta::Affine> {
+        if !self.params.contains_key(&COMPLIANCE_CIRCUIT_PARAMS_SIZE) {
+            return &COMPLIANCE_VERIFYING_KEY;
+        }
+        self.compliance_vk.get_or_init(|| {
+            keygen_vk(
+                self.setup_params(COMPLIANCE_CIRCUIT_PARAMS_SIZE),
+                &ComplianceCircuit::default(),
+            )
+            .expect("keygen_vk should not fail")
+        })
+    }
+
+    pub fn reserve_verifying_key(&self) -> &VerifyingKey<vesta::Affine> {
+        if !self.params.contains_key(&RESERVE_CIRCUIT_PARAMS_SIZE) {
+            return &RESERVE_VERIFYING_KEY;
+        }
+        self.reserve_vk.get_or_init(|| {
+            keygen_vk(
+                self.setup_params(RESERVE_CIRCUIT_PARAMS_SIZE),
+                &ReserveCircuit::default(),
+            )
+            .expect("keygen_vk should not fail")
+        })
+    }
+
+    pub fn spent_verifying_key(&self) -> &VerifyingKey<vesta::Affine> {
+        if !self.params.contains_key(&SPENT_CIRCUIT_PARAMS_SIZE) {
+            return &SPENT_VERIFYING_KEY;
+        }
+        self.spent_vk.get_or_init(|| {
+            keygen_vk(
+                self.setup_params(SPENT_CIRCUIT_PARAMS_SIZE),
+                &SpentCircuit::default(),
+            )
+            .expect("keygen_vk should not fail")
+        })
+    }
+
+    pub fn read_verifying_key(&self) -> &VerifyingKey<vesta::Affine> {
+        if !self.params.contains_key(&READ_CIRCUIT_PARAMS_SIZE) {
+            return &READ_VERIFYING_KEY;
+        }
+        self.read_vk.get_or_init(|| {
+            keygen_vk(
+                self.setup_params(READ_CIRCUIT_PARAMS_SIZE),
+                &ReadCircuit::default(),
+            )
+            .expect("keygen_vk should not fail")
+        })
+    }
+}
+
+/// Checks all the setup params against the expected digests.
+pub fn integrity_check() -> Result<(), TransactionError> {
+    for (k, params) in SETUP_PARAMS_MAP.iter() {
+        check_params(*k, params)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_params_integrity_check() {
+    use crate::constant::PARAMS_SIZE;
+
+    integrity_check().unwrap();
+
+    let bytes = include_bytes!("../params/params_15");
+    read_params(PARAMS_SIZE, &mut &bytes[..]).unwrap();
+
+    let params = Params::<vesta::Affine>::new(4);
+    assert!(matches!(
+        check_params(PARAMS_SIZE, &params),
+        Err(TransactionError::ParamsDigestMismatch(PARAMS_SIZE))
+    ));
+    assert!(matches!(
+        check_params(4, &params),
+        Err(TransactionError::UnknownParamsSize(4))
+    ));
+
+    // The setup params are shipped, the others are generated once
+    assert!(std::ptr::eq(
+        get_params(PARAMS_SIZE).unwrap(),
+        SETUP_PARAMS_MAP.get(&PARAMS_SIZE).unwrap()
+    ));
+    let k = RESOURCE_LOGIC_CIRCUIT_MIN_PARAMS_SIZE;
+    assert!(std::ptr::eq(get_params(k).unwrap(), get_params(k).unwrap()));
+    let k = RESOURCE_LOGIC_CIRCUIT_MAX_PARAMS_SIZE + 1;
+    assert!(!is_supported_params_size(k));
+    assert!(matches!(
+        get_params(k),
+        Err(TransactionError::UnsupportedParamsSize(_))
+    ));
+}
+
+#[test]
+fn test_proving_context() {
+    use crate::circuit::{
+        resource_logic_circuit::ResourceLogicVerifyingInfoTrait,
+        resource_logic_examples::TrivialResourceLogicCircuit,
+    };
+    use crate::constant::RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+    use rand::rngs::OsRng;
+
+    // Params injected by the context rather than read from the setup ones
+    let k = RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE;
+    let context = ProvingContext::default().with_params(k, get_params(k).unwrap().clone());
+    assert!(context.has_params(k));
+    assert!(!std::ptr::eq(
+        context.params(k).unwrap(),
+        get_params(k).unwrap()
+    ));
+
+    let logic = TrivialResourceLogicCircuit::default();
+    let verifying_info = logic
+        .get_verifying_info_with_context(&context, &mut OsRng)
+        .unwrap();
+    verifying_info
+        .verify_with_context(&context.verifying_context())
+        .unwrap();
+    verifying_info.verify().unwrap();
+
+    // The generated params are kept by the context and its verifying context
+    let k = RESOURCE_LOGIC_CIRCUIT_MIN_PARAMS_SIZE;
+    let params = context.params(k).unwrap();
+    assert!(std::ptr::eq(params, context.params(k).unwrap()));
+    assert!(std::ptr::eq(
+        params,
+        context.verifying_context().params(k).unwrap()
+    ));
+    assert!(!std::ptr::eq(params, get_params(k).unwrap()));
+    assert!(matches!(
+        context.params(RESOURCE_LOGIC_CIRCUIT_MAX_PARAMS_SIZE + 1),
+        Err(TransactionError::UnsupportedParamsSize(_))
+    ));
+}
@@ -1,9 +1,12 @@
+use crate::transcript::{Blake2bTranscript, TranscriptHash};
+use ff::Field;
 use halo2_proofs::{
-    plonk::{self, Circuit, ProvingKey, SingleVerifier, VerifyingKey},
-    poly::commitment::Params,
-    transcript::{Blake2bRead, Blake2bWrite},
+    plonk::{self, Circuit, ProvingKey, SingleVerifier, VerificationStrategy, VerifyingKey},
+    poly::commitment::{Guard, Params, MSM},
+    transcript::EncodedChallenge,
 };
 use pasta_curves::{pallas, vesta};
+use rand::rngs::OsRng;
 use rand::RngCore;
 #[cfg(feature = "nif")]
 use rustler::NifTuple;
@@ -23,13 +26,28 @@ pub struct Proof(Vec<u8>);
 impl Proof {
     /// Creates a proof for the given circuits and instances.
     pub fn create<C: Circuit<pallas::Base>>(
+        pk: &ProvingKey<vesta::Affine>,
+        params: &Params<vesta::Affine>,
+        circuit: C,
+        instance: &[&[pallas::Base]],
+        rng: impl RngCore,
+    ) -> Result<Self, plonk::Error> {
+        Self::create_with_transcript::<Blake2bTranscript, C>(pk, params, circuit, instance, rng)
+    }
+
+    /// Creates a proof with the transcript hash `T`, see [`crate::transcript`].
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "proof.create", skip_all)
+    )]
+    pub fn create_with_transcript<T: TranscriptHash, C: Circuit<pallas::Base>>(
         pk: &ProvingKey<vesta::Affine>,
         params: &Params<vesta::Affine>,
         circuit: C,
         instance: &[&[pallas::Base]],
         mut rng: impl RngCore,
     ) -> Result<Self, plonk::Error> {
-        let mut transcript = Blake2bWrite::<_, vesta::Affine, _>::init(vec![]);
+        let mut transcript = T::init_write();
         plonk::create_proof(
             params,
             pk,
@@ -38,7 +56,7 @@ impl Proof {
             &mut rng,
             &mut transcript,
         )?;
-        Ok(Proof(transcript.finalize()))
+        Ok(Proof(T::finalize(transcript)))
     }
 
     /// Verifies this proof with the given instances.
@@ -48,8 +66,24 @@ impl Proof {
         params: &Params<vesta::Affine>,
         instance: &[&[pallas::Base]],
     ) -> Result<(), plonk::Error> {
+        self.verify_with_transcript::<Blake2bTranscript>(vk, params, instance)
+    }
+
+    /// Verifies a proof created with the transcript hash `T`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "proof.verify", skip_all)
+    )]
+    pub fn verify_with_transcript<T: TranscriptHash>(
+        &self,
+        vk: &VerifyingKey<vesta::Affine>,
+        params: &Params<vesta::Affine>,
+        instance: &[&[pallas::Base]],
+    ) -> Result<(), plonk::Error> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::increment_counter(crate::metrics::PROOFS_VERIFIED_TOTAL, &[], 1);
         let strategy = SingleVerifier::new(params);
-        let mut transcript = Blake2bRead::init(&self.0[..]);
+        let mut transcript = T::init_read(&self.0[..]);
         plonk::verify_proof(params, vk, strategy, &[instance], &mut transcript)
     }
 
@@ -62,3 +96,119 @@ impl Proof {
         self.0.clone()
     }
 }
+
+/// A verification strategy returning the MSM of the IPA check instead of
+/// evaluating it.
+struct AccumulatorStrategy<'params> {
+    msm: MSM<'params, vesta::Affine>,
+}
+
+impl<'params> VerificationStrategy<'params, vesta::Affine> for AccumulatorStrategy<'params> {
+    type Output = MSM<'params, vesta::Affine>;
+
+    fn process<E: EncodedChallenge<vesta::Affine>>(
+        self,
+        f: impl FnOnce(
+            MSM<'params, vesta::Affine>,
+        ) -> Result<Guard<'params, vesta::Affine, E>, plonk::Error>,
+    ) -> Result<Self::Output, plonk::Error> {
+        let guard = f(self.msm)?;
+        Ok(guard.use_challenges())
+    }
+}
+
+/// Accumulates the IPA checks of several proofs, possibly of different
+/// circuits, and evaluates them with a single multi-scalar multiplication per
+/// params.
+#[derive(Debug, Default)]
+pub struct BatchVerifier {
+    accumulators: Vec<(&'static Params<vesta::Affine>, MSM<'static, vesta::Affine>)>,
+}
+
+impl BatchVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Verifies the proof except for its IPA check, which is deferred to
+    /// `finalize`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "proof.batch_add", skip_all)
+    )]
+    pub fn add_proof(
+        &mut self,
+        proof: &Proof,
+        vk: &VerifyingKey<vesta::Affine>,
+        params: &'static Params<vesta::Affine>,
+        instance: &[&[pallas::Base]],
+    ) -> Result<(), plonk::Error> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::increment_counter(crate::metrics::PROOFS_VERIFIED_TOTAL, &[], 1);
+        let strategy = AccumulatorStrategy {
+            msm: params.empty_msm(),
+        };
+        let mut transcript = Blake2bTranscript::init_read(&proof.0[..]);
+        let mut msm = plonk::verify_proof(params, vk, strategy, &[instance], &mut transcript)?;
+        // Randomize the check so that invalid proofs can't cancel each other out
+        msm.scale(vesta::Scalar::random(OsRng));
+        match self
+            .accumulators
+            .iter_mut()
+            .find(|(accumulator_params, _)| std::ptr::eq(*accumulator_params, params))
+        {
+            Some((_, accumulator)) => accumulator.add_msm(&msm),
+            None => self.accumulators.push((params, msm)),
+        }
+        Ok(())
+    }
+
+    /// Evaluates the accumulated IPA checks.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "proof.batch_finalize", skip_all)
+    )]
+    pub fn finalize(self) -> Result<(), plonk::Error> {
+        if self.accumulators.iter().all(|(_, msm)| msm.eval()) {
+            Ok(())
+        } else {
+            Err(plonk::Error::ConstraintSystemFailure)
+        }
+    }
+}
+
+#[test]
+fn test_halo2_poseidon_transcript_proof() {
+    use crate::compliance::tests::random_compliance_info;
+    use crate::constant::{
+        COMPLIANCE_CIRCUIT_PARAMS_SIZE, COMPLIANCE_PROVING_KEY, COMPLIANCE_VERIFYING_KEY,
+        SETUP_PARAMS_MAP,
+    };
+    use crate::transcript::PoseidonTranscript;
+
+    let mut rng = OsRng;
+    let (compliance, compliance_circuit) = random_compliance_info(&mut rng).build();
+    let instance = compliance.to_instance();
+    let params = SETUP_PARAMS_MAP
+        .get(&COMPLIANCE_CIRCUIT_PARAMS_SIZE)
+        .unwrap();
+    let proof = Proof::create_with_transcript::<PoseidonTranscript, _>(
+        &COMPLIANCE_PROVING_KEY,
+        params,
+        compliance_circuit,
+        &[&instance],
+        &mut rng,
+    )
+    .unwrap();
+    assert!(proof
+        .verify_with_transcript::<PoseidonTranscript>(
+            &COMPLIANCE_VERIFYING_KEY,
+            params,
+            &[&instance]
+        )
+        .is_ok());
+    // The proof doesn't verify with the default transcript
+    assert!(proof
+        .verify(&COMPLIANCE_VERIFYING_KEY, params, &[&instance])
+        .is_err());
+}
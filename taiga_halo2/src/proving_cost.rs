@@ -0,0 +1,247 @@
+//! Dry-run estimates of the proving cost of a transaction, so that wallets can
+//! warn before a long build, see `TransactionBuilder::estimate`.
+//!
+//! The cost of a proof is extrapolated from a proof of the same kind measured
+//! on the host: the proving time grows as n log n in the number of rows
+//! n = 2^k and the memory as n. The host is calibrated once per process by
+//! `host_calibration`, with the `std` feature, or with a [`HostCalibration`]
+//! the wallet saved from a previous run, see [`set_host_calibration`].
+#[cfg(feature = "std")]
+use crate::{
+    circuit::{
+        resource_logic_circuit::ResourceLogicVerifyingInfoTrait,
+        resource_logic_examples::{TrivialResourceLogicCircuit, TRIVIAL_RESOURCE_LOGIC_PK},
+    },
+    compliance::ComplianceInfo,
+    constant::{
+        COMPLIANCE_CIRCUIT_PARAMS_SIZE, COMPLIANCE_PROVING_KEY, RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE,
+        TAIGA_COMMITMENT_TREE_DEPTH,
+    },
+    merkle_tree::MerklePath,
+    resource::Resource,
+    shielded_ptx::ComplianceVerifyingInfo,
+};
+#[cfg(feature = "std")]
+use rand::rngs::OsRng;
+use std::sync::OnceLock;
+use std::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+#[cfg(feature = "serde")]
+use serde;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// The cost of a single proof with params of size `k`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProofBenchmark {
+    pub k: u32,
+    pub seconds: f64,
+    /// Peak memory in bytes.
+    pub memory: u64,
+}
+
+impl ProofBenchmark {
+    /// The cost of a proof of the same kind with params of size `k`.
+    pub fn scale(&self, k: u32) -> Self {
+        let rows = 2f64.powi(k as i32 - self.k as i32);
+        Self {
+            k,
+            seconds: self.seconds * rows * k as f64 / self.k as f64,
+            memory: (self.memory as f64 * rows) as u64,
+        }
+    }
+}
+
+/// The single-proof benchmarks of the host.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostCalibration {
+    pub compliance: ProofBenchmark,
+    pub resource_logic: ProofBenchmark,
+}
+
+/// The predicted cost of proving a ptx.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ProvingEstimate {
+    pub proofs: usize,
+    pub wall_time: Duration,
+    /// Peak memory in bytes.
+    pub peak_memory: u64,
+}
+
+static HOST_CALIBRATION: OnceLock<HostCalibration> = OnceLock::new();
+
+/// The calibration of the host, measured on the first call.
+#[cfg(feature = "std")]
+pub fn host_calibration() -> &'static HostCalibration {
+    HOST_CALIBRATION.get_or_init(HostCalibration::measure)
+}
+
+/// Installs a calibration measured before, e.g. in a previous run. Returns it
+/// back if the host is already calibrated.
+pub fn set_host_calibration(calibration: HostCalibration) -> Result<(), HostCalibration> {
+    HOST_CALIBRATION.set(calibration)
+}
+
+// The peak resident set of the process in bytes, reset before the measure
+// where the kernel allows it. None off Linux.
+#[cfg(feature = "std")]
+fn peak_resident_memory(reset: bool) -> Option<u64> {
+    if reset {
+        // Writing 5 to clear_refs resets the peak resident set
+        let _ = std::fs::write("/proc/self/clear_refs", "5");
+    }
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kb = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(feature = "std")]
+fn measure(k: u32, prove: impl FnOnce()) -> ProofBenchmark {
+    let base_memory = peak_resident_memory(true);
+    let start = Instant::now();
+    prove();
+    let seconds = start.elapsed().as_secs_f64();
+    let memory = base_memory
+        .zip(peak_resident_memory(false))
+        .map(|(base, peak)| peak.saturating_sub(base))
+        .unwrap_or_default();
+    ProofBenchmark { k, seconds, memory }
+}
+
+impl HostCalibration {
+    /// Measures a compliance proof and a trivial resource logic proof. The
+    /// memory is only measured on Linux.
+    #[cfg(feature = "std")]
+    pub fn measure() -> Self {
+        let mut rng = OsRng;
+        // The proving keys are generated once, outside of the measures
+        lazy_static::initialize(&COMPLIANCE_PROVING_KEY);
+        lazy_static::initialize(&TRIVIAL_RESOURCE_LOGIC_PK);
+
+        let mut output = Resource::padding_resource(&mut rng);
+        let compliance_info = ComplianceInfo::new(
+            Resource::padding_resource(&mut rng),
+            MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+            None,
+            &mut output,
+            &mut rng,
+        );
+        let compliance = measure(COMPLIANCE_CIRCUIT_PARAMS_SIZE, || {
+            ComplianceVerifyingInfo::create(&compliance_info, &mut rng)
+                .expect("compliance proof should not fail");
+        });
+        let resource_logic = measure(RESOURCE_LOGIC_CIRCUIT_PARAMS_SIZE, || {
+            TrivialResourceLogicCircuit::default()
+                .get_verifying_info()
+                .expect("resource logic proof should not fail");
+        });
+        Self {
+            compliance,
+            resource_logic,
+        }
+    }
+
+    /// Estimates proving `compliance_num` compliances and the applications
+    /// given by the params sizes of their logics, on `threads` threads as
+    /// `ShieldedPartialTransaction::from_bytecode_with_threads` does: every
+    /// compliance and every application is a job, the logics of an
+    /// application are proven one after the other.
+    pub fn estimate(
+        &self,
+        compliance_num: usize,
+        apps: &[Vec<u32>],
+        threads: usize,
+    ) -> ProvingEstimate {
+        let jobs: Vec<ProofBenchmark> = std::iter::repeat(self.compliance)
+            .take(compliance_num)
+            .chain(apps.iter().map(|logic_sizes| {
+                logic_sizes
+                    .iter()
+                    .map(|k| self.resource_logic.scale(*k))
+                    .fold(
+                        ProofBenchmark {
+                            k: 0,
+                            seconds: 0.0,
+                            memory: 0,
+                        },
+                        |job, proof| ProofBenchmark {
+                            k: job.k.max(proof.k),
+                            seconds: job.seconds + proof.seconds,
+                            memory: job.memory.max(proof.memory),
+                        },
+                    )
+            }))
+            .collect();
+
+        // The jobs are handed out in order to the first idle thread
+        let threads = threads.clamp(1, jobs.len().max(1));
+        let mut busy_until = vec![0f64; threads];
+        for job in jobs.iter() {
+            let idle = busy_until.iter_mut().min_by(|a, b| a.total_cmp(b)).unwrap();
+            *idle += job.seconds;
+        }
+        let wall_time = busy_until.into_iter().fold(0f64, f64::max);
+
+        // At worst the largest jobs run together
+        let mut memories: Vec<u64> = jobs.iter().map(|job| job.memory).collect();
+        memories.sort_unstable_by(|a, b| b.cmp(a));
+        let peak_memory = memories.into_iter().take(threads).sum();
+
+        ProvingEstimate {
+            proofs: compliance_num + apps.iter().map(Vec::len).sum::<usize>(),
+            wall_time: Duration::from_secs_f64(wall_time),
+            peak_memory,
+        }
+    }
+}
+
+#[test]
+fn test_proving_estimate() {
+    let calibration = HostCalibration {
+        compliance: ProofBenchmark {
+            k: 15,
+            seconds: 3.0,
+            memory: 1 << 30,
+        },
+        resource_logic: ProofBenchmark {
+            k: 15,
+            seconds: 2.0,
+            memory: 1 << 29,
+        },
+    };
+    // A logic twice as large takes a bit more than twice as long
+    let scaled = calibration.resource_logic.scale(16);
+    assert_eq!(scaled.seconds, 2.0 * 2.0 * 16.0 / 15.0);
+    assert_eq!(scaled.memory, 1 << 30);
+
+    // Two compliances and four single-logic applications
+    let apps = vec![vec![15]; 4];
+    let serial = calibration.estimate(2, &apps, 0);
+    assert_eq!(serial.proofs, 6);
+    assert_eq!(serial.wall_time, Duration::from_secs(14));
+    assert_eq!(serial.peak_memory, 1 << 30);
+
+    // Thread 0 proves a compliance and two logics, thread 1 the other three
+    let parallel = calibration.estimate(2, &apps, 2);
+    assert_eq!(parallel.wall_time, Duration::from_secs(7));
+    assert_eq!(parallel.peak_memory, 2 << 30);
+
+    // An application proves its dynamic logics after its own
+    let dynamic = calibration.estimate(0, &[vec![15, 15, 15]], 4);
+    assert_eq!(dynamic.proofs, 3);
+    assert_eq!(dynamic.wall_time, Duration::from_secs(6));
+}
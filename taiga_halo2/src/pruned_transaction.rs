@@ -0,0 +1,207 @@
+//! Once a transaction is final, full nodes don't need its proofs anymore: they
+//! only keep the nullifiers and commitments the state is made of. A
+//! [`PrunedTransaction`] retains those, the fingerprints of the logics the
+//! transaction was checked against and a digest of the stripped proofs, so
+//! that the full transaction served by an archival node can be checked against
+//! it, see [`TransactionArchive::restore`].
+use crate::{
+    error::TransactionError, nullifier::Nullifier, resource::ResourceCommitment,
+    transaction::Transaction,
+};
+use pasta_curves::pallas;
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(feature = "borsh")]
+use ff::PrimeField;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrunedTransaction {
+    id: [u8; 32],
+    nullifiers: Vec<Nullifier>,
+    output_cms: Vec<ResourceCommitment>,
+    /// The compressed vks of the shielded resource logics
+    logic_fingerprints: Vec<pallas::Base>,
+    /// The digest of the stripped proofs
+    proofs_digest: [u8; 32],
+}
+
+impl PrunedTransaction {
+    pub fn new(
+        id: [u8; 32],
+        nullifiers: Vec<Nullifier>,
+        output_cms: Vec<ResourceCommitment>,
+        logic_fingerprints: Vec<pallas::Base>,
+        proofs_digest: [u8; 32],
+    ) -> Self {
+        Self {
+            id,
+            nullifiers,
+            output_cms,
+            logic_fingerprints,
+            proofs_digest,
+        }
+    }
+
+    pub fn id(&self) -> &[u8; 32] {
+        &self.id
+    }
+
+    pub fn nullifiers(&self) -> &[Nullifier] {
+        &self.nullifiers
+    }
+
+    pub fn output_cms(&self) -> &[ResourceCommitment] {
+        &self.output_cms
+    }
+
+    pub fn logic_fingerprints(&self) -> &[pallas::Base] {
+        &self.logic_fingerprints
+    }
+
+    pub fn proofs_digest(&self) -> &[u8; 32] {
+        &self.proofs_digest
+    }
+
+    /// Whether the pruned transaction was pruned from `tx`.
+    pub fn is_pruned_from(&self, tx: &Transaction) -> bool {
+        *self == tx.prune()
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for PrunedTransaction {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        use byteorder::{LittleEndian, WriteBytesExt};
+        writer.write_all(&self.id)?;
+        self.nullifiers.serialize(writer)?;
+        self.output_cms.serialize(writer)?;
+        writer.write_u32::<LittleEndian>(self.logic_fingerprints.len() as u32)?;
+        for fingerprint in self.logic_fingerprints.iter() {
+            writer.write_all(&fingerprint.to_repr())?;
+        }
+        writer.write_all(&self.proofs_digest)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for PrunedTransaction {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        use byteorder::{LittleEndian, ReadBytesExt};
+        let mut id = [0u8; 32];
+        reader.read_exact(&mut id)?;
+        let nullifiers = Vec::<Nullifier>::deserialize_reader(reader)?;
+        let output_cms = Vec::<ResourceCommitment>::deserialize_reader(reader)?;
+        let len = reader.read_u32::<LittleEndian>()?;
+        let logic_fingerprints = (0..len)
+            .map(|_| crate::utils::read_base_field(reader))
+            .collect::<Result<_, _>>()?;
+        let mut proofs_digest = [0u8; 32];
+        reader.read_exact(&mut proofs_digest)?;
+        Ok(Self {
+            id,
+            nullifiers,
+            output_cms,
+            logic_fingerprints,
+            proofs_digest,
+        })
+    }
+}
+
+/// Serves the full data of pruned transactions, e.g. an archival node.
+pub trait TransactionArchive {
+    fn get_transaction(&self, id: &[u8; 32]) -> Option<Transaction>;
+
+    /// Fetches the full transaction and checks it against the pruned one.
+    fn restore(&self, pruned: &PrunedTransaction) -> Result<Transaction, TransactionError> {
+        let tx = self
+            .get_transaction(pruned.id())
+            .ok_or(TransactionError::TransactionNotArchived)?;
+        if !pruned.is_pruned_from(&tx) {
+            return Err(TransactionError::PrunedTransactionMismatch);
+        }
+        Ok(tx)
+    }
+}
+
+/// An archive keeping the full transactions in memory.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryTransactionArchive {
+    transactions: HashMap<[u8; 32], Transaction>,
+}
+
+impl InMemoryTransactionArchive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Archives the transaction and returns its pruned version.
+    pub fn archive(&mut self, tx: Transaction) -> PrunedTransaction {
+        let pruned = tx.prune();
+        self.transactions.insert(tx.id(), tx);
+        pruned
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+}
+
+impl TransactionArchive for InMemoryTransactionArchive {
+    fn get_transaction(&self, id: &[u8; 32]) -> Option<Transaction> {
+        self.transactions.get(id).cloned()
+    }
+}
+
+#[test]
+fn test_halo2_pruned_transaction() {
+    use crate::transaction::{testing::create_shielded_ptx_bundle, TransparentPartialTxBundle};
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let mut build_tx = || {
+        Transaction::build(
+            &mut rng,
+            create_shielded_ptx_bundle(1),
+            TransparentPartialTxBundle::default(),
+        )
+        .unwrap()
+    };
+    let tx = build_tx();
+    let other_tx = build_tx();
+
+    let result = tx.execute().unwrap();
+    let mut archive = InMemoryTransactionArchive::new();
+    let pruned = archive.archive(tx.clone());
+    assert_eq!(pruned.nullifiers(), result.nullifiers.as_slice());
+    assert_eq!(pruned.output_cms(), result.output_cms.as_slice());
+    assert!(pruned.is_pruned_from(&tx));
+    assert!(!pruned.is_pruned_from(&other_tx));
+
+    let restored = archive.restore(&pruned).unwrap();
+    assert_eq!(restored.id(), tx.id());
+    assert_eq!(restored.execute().unwrap(), result);
+
+    // Transactions that were not archived can't be restored
+    assert!(matches!(
+        archive.restore(&other_tx.prune()),
+        Err(TransactionError::TransactionNotArchived)
+    ));
+
+    #[cfg(feature = "borsh")]
+    {
+        let borsh = borsh::to_vec(&pruned).unwrap();
+        let de_pruned: PrunedTransaction =
+            BorshDeserialize::deserialize(&mut borsh.as_ref()).unwrap();
+        assert_eq!(pruned, de_pruned);
+    }
+}
@@ -0,0 +1,156 @@
+use pasta_curves::pallas;
+use std::fmt;
+
+#[cfg(feature = "nif")]
+use rustler::{Decoder, Encoder, Env, NifResult, Term};
+
+#[cfg(feature = "serde")]
+use serde;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// The quantity of a resource.
+///
+/// Arithmetic is only exposed through checked operations so that app code
+/// can't silently wrap around on overflow or underflow.
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct Quantity(u64);
+
+// Quantities cross the NIF boundary as bare integers, like the u64 they were
+#[cfg(feature = "nif")]
+impl Encoder for Quantity {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        self.0.encode(env)
+    }
+}
+
+#[cfg(feature = "nif")]
+impl<'a> Decoder<'a> for Quantity {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        term.decode().map(Quantity)
+    }
+}
+
+impl Quantity {
+    pub const ZERO: Quantity = Quantity(0);
+    pub const MAX: Quantity = Quantity(u64::MAX);
+
+    pub const fn new(quantity: u64) -> Self {
+        Self(quantity)
+    }
+
+    pub fn inner(&self) -> u64 {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        self.0.checked_mul(rhs.0).map(Self)
+    }
+
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        self.0.checked_div(rhs.0).map(Self)
+    }
+
+    pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+        self.0.checked_rem(rhs.0).map(Self)
+    }
+
+    /// Display the quantity as a decimal number with `decimals` fractional
+    /// digits, e.g. `Quantity::new(12345).display(Some(2))` shows `123.45`.
+    /// With no decimals the raw integer is shown.
+    pub fn display(&self, decimals: Option<u8>) -> QuantityDisplay {
+        QuantityDisplay {
+            quantity: *self,
+            decimals,
+        }
+    }
+}
+
+impl From<u64> for Quantity {
+    fn from(quantity: u64) -> Self {
+        Quantity(quantity)
+    }
+}
+
+impl From<Quantity> for u64 {
+    fn from(quantity: Quantity) -> Self {
+        quantity.0
+    }
+}
+
+impl From<Quantity> for pallas::Base {
+    fn from(quantity: Quantity) -> Self {
+        pallas::Base::from(quantity.0)
+    }
+}
+
+impl From<Quantity> for pallas::Scalar {
+    fn from(quantity: Quantity) -> Self {
+        pallas::Scalar::from(quantity.0)
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Helper returned by [`Quantity::display`] to format a quantity with its
+/// denomination.
+#[derive(Copy, Debug, Clone)]
+pub struct QuantityDisplay {
+    quantity: Quantity,
+    decimals: Option<u8>,
+}
+
+impl fmt::Display for QuantityDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.decimals {
+            None | Some(0) => write!(f, "{}", self.quantity),
+            Some(decimals) => {
+                let digits = format!(
+                    "{:0>width$}",
+                    self.quantity.0,
+                    width = decimals as usize + 1
+                );
+                let (integer, fraction) = digits.split_at(digits.len() - decimals as usize);
+                write!(f, "{integer}.{fraction}")
+            }
+        }
+    }
+}
+
+#[test]
+fn test_quantity() {
+    let a = Quantity::new(5);
+    let b = Quantity::from(3u64);
+    assert_eq!(a.checked_add(b), Some(Quantity::new(8)));
+    assert_eq!(a.checked_sub(b), Some(Quantity::new(2)));
+    assert_eq!(b.checked_sub(a), None);
+    assert_eq!(a.checked_mul(b), Some(Quantity::new(15)));
+    assert_eq!(Quantity::MAX.checked_add(b), None);
+    assert_eq!(Quantity::MAX.checked_mul(b), None);
+    assert_eq!(a.checked_div(Quantity::ZERO), None);
+    assert_eq!(a.checked_rem(b), Some(Quantity::new(2)));
+
+    assert_eq!(Quantity::new(12345).display(None).to_string(), "12345");
+    assert_eq!(Quantity::new(12345).display(Some(2)).to_string(), "123.45");
+    assert_eq!(Quantity::new(5).display(Some(3)).to_string(), "0.005");
+    assert_eq!(Quantity::ZERO.display(Some(2)).to_string(), "0.00");
+}
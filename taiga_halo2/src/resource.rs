@@ -9,18 +9,26 @@ use crate::{
         PRF_EXPAND_PERSONALIZATION_TO_FIELD, PRF_EXPAND_PSI, PRF_EXPAND_PUBLIC_INPUT_PADDING,
         PRF_EXPAND_RCM, PRF_EXPAND_VCM_R, TAIGA_RESOURCE_TREE_DEPTH,
     },
+    error::TransactionError,
     merkle_tree::{Anchor, MerklePath, Node, LR},
     nullifier::{Nullifier, NullifierKeyContainer},
+    quantity::Quantity,
+    secret::Secret,
     shielded_ptx::ResourceLogicVerifyingInfoSet,
-    utils::{poseidon_hash_n, poseidon_to_curve},
+    utils::{from_le_bytes, poseidon_hash_n, poseidon_to_curve},
 };
 use blake2b_simd::Params as Blake2bParams;
 use ff::{FromUniformBytes, PrimeField};
 use halo2_proofs::arithmetic::Field;
+use lazy_static::lazy_static;
 use pasta_curves::pallas;
-use rand::RngCore;
+use rand::{rngs::OsRng, RngCore};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::hash::{Hash, Hasher};
-use subtle::CtOption;
+use std::sync::Mutex;
+use subtle::{Choice, ConstantTimeEq, CtOption};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 #[cfg(feature = "nif")]
 use rustler::{NifStruct, NifTuple};
@@ -79,8 +87,9 @@ impl Hash for ResourceCommitment {
     }
 }
 
-/// A resource
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// A resource. Its nullifier key and its seed are secret: they are zeroized
+/// on drop and compared in constant time, so the resource is not `Copy`.
+#[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "nif", derive(NifStruct))]
 #[cfg_attr(feature = "nif", module = "Taiga.Resource")]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -90,15 +99,15 @@ pub struct Resource {
     /// sub-resource_logics and any other data can be encoded to the value
     pub value: pallas::Base,
     /// the quantity of the resource.
-    pub quantity: u64,
+    pub quantity: Quantity,
     /// NullifierKeyContainer contains the nullifier_key or the nullifier_key commitment.
-    pub nk_container: NullifierKeyContainer,
+    pub nk_container: Secret<NullifierKeyContainer>,
     /// nonce guarantees the uniqueness of the resource computable fields
     pub nonce: Nullifier,
     /// If the is_ephemeral flag is false, the merkle path authorization(membership) of input resource will be checked in ComplianceProof.
     pub is_ephemeral: bool,
     /// randomness seed used to derive whatever randomness needed (e.g., the resource commitment randomness and nullifier derivation randomness)
-    pub rseed: pallas::Base,
+    pub rseed: Secret<pallas::Base>,
 }
 
 /// The parameters in the ResourceKind are used to derive resource kind.
@@ -113,11 +122,44 @@ pub struct ResourceKind {
     pub label: pallas::Base,
 }
 
-#[derive(Copy, Clone, Debug, Default)]
+/// The window of epochs in which a resource can be consumed, e.g. vested
+/// tokens or offers expiring at a deadline. A timelocked resource encodes the
+/// window in its value, see [`Timelock::encode_value`], and its logic checks
+/// the epoch of its compliance against the window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct Timelock {
+    pub not_before: u64,
+    pub expiry: u64,
+}
+
+/// The seed of the randomness of a compliance or a logic. It is not `Copy`,
+/// so that it is zeroized on drop.
+#[derive(Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
 pub struct RandomSeed([u8; 32]);
 
+/// Builds a resource from its fields, see [`Resource::builder`]. The fields
+/// are checked when the resource is built, a quantity that doesn't fit in 64
+/// bits or a non-canonical field encoding is an error rather than a circuit
+/// failure when the resource is proven.
+#[derive(Clone, Debug, Default)]
+pub struct ResourceBuilder {
+    logic: Option<pallas::Base>,
+    label: pallas::Base,
+    value: pallas::Base,
+    quantity: Quantity,
+    nk_container: Option<Secret<NullifierKeyContainer>>,
+    nonce: Nullifier,
+    is_ephemeral: bool,
+    rseed: Option<Secret<pallas::Base>>,
+    timelock: Option<Timelock>,
+    // The first invalid field, reported by `build`
+    error: Option<String>,
+}
+
 /// ResourceLogics consists of one application(static) resource logic and a few user(dynamic) resource logics.
 #[derive(Clone)]
 pub struct ResourceLogics {
@@ -131,7 +173,7 @@ impl Resource {
         logic: pallas::Base,
         label: pallas::Base,
         value: pallas::Base,
-        quantity: u64,
+        quantity: Quantity,
         nk: pallas::Base,
         nonce: Nullifier,
         is_ephemeral: bool,
@@ -142,10 +184,10 @@ impl Resource {
             kind,
             value,
             quantity,
-            nk_container: NullifierKeyContainer::Key(nk),
+            nk_container: NullifierKeyContainer::Key(nk).into(),
             is_ephemeral,
             nonce,
-            rseed,
+            rseed: rseed.into(),
         }
     }
 
@@ -155,7 +197,7 @@ impl Resource {
         logic: pallas::Base,
         label: pallas::Base,
         value: pallas::Base,
-        quantity: u64,
+        quantity: Quantity,
         npk: pallas::Base,
         is_ephemeral: bool,
         rseed: pallas::Base,
@@ -165,9 +207,9 @@ impl Resource {
             kind,
             value,
             quantity,
-            nk_container: NullifierKeyContainer::PublicKey(npk),
+            nk_container: NullifierKeyContainer::PublicKey(npk).into(),
             is_ephemeral,
-            rseed,
+            rseed: rseed.into(),
             nonce: Nullifier::default(),
         }
     }
@@ -177,7 +219,7 @@ impl Resource {
         logic: pallas::Base,
         label: pallas::Base,
         value: pallas::Base,
-        quantity: u64,
+        quantity: Quantity,
         nk_container: NullifierKeyContainer,
         nonce: Nullifier,
         is_ephemeral: bool,
@@ -188,14 +230,19 @@ impl Resource {
             kind,
             value,
             quantity,
-            nk_container,
+            nk_container: nk_container.into(),
             is_ephemeral,
             nonce,
-            rseed,
+            rseed: rseed.into(),
         }
     }
 
-    pub fn random_padding_resource<R: RngCore>(mut rng: R) -> Self {
+    /// A resource filling an unused compliance slot of a ptx. Its fields are
+    /// random, but the quantity is zero so that it doesn't unbalance the ptx
+    /// and it is ephemeral so that its merkle path is not checked. Padding
+    /// resources are not hidden: their trivial logic is verified with its vk
+    /// in the clear, which tells the padding compliances of a ptx apart.
+    pub fn padding_resource<R: RngCore>(mut rng: R) -> Self {
         let logic = *COMPRESSED_TRIVIAL_RESOURCE_LOGIC_VK;
         let label = pallas::Base::random(&mut rng);
         let kind = ResourceKind::new(logic, label);
@@ -206,16 +253,26 @@ impl Resource {
         Resource {
             kind,
             value,
-            quantity: 0,
-            nk_container: nk,
+            quantity: Quantity::ZERO,
+            nk_container: nk.into(),
             nonce,
-            rseed,
+            rseed: rseed.into(),
             is_ephemeral: true,
         }
     }
 
     // resource_commitment = poseidon_hash(logic || label || value || npk || nonce || psi || is_ephemeral || quantity || rcm)
     pub fn commitment(&self) -> ResourceCommitment {
+        self.commitment_with(self.get_npk(), self.get_psi(), self.get_rcm())
+    }
+
+    /// Same as `commitment`, but with the npk, psi and rcm already derived.
+    pub(crate) fn commitment_with(
+        &self,
+        npk: pallas::Base,
+        psi: pallas::Base,
+        rcm: pallas::Base,
+    ) -> ResourceCommitment {
         let compose_is_ephemeral_quantity = if self.is_ephemeral {
             pallas::Base::from_u128(1 << 64).square() + pallas::Base::from(self.quantity)
         } else {
@@ -225,34 +282,47 @@ impl Resource {
             self.get_logic(),
             self.get_label(),
             self.value,
-            self.get_npk(),
+            npk,
             self.nonce.inner(),
-            self.get_psi(),
+            psi,
             compose_is_ephemeral_quantity,
-            self.get_rcm(),
+            rcm,
         ]);
         ResourceCommitment(ret)
     }
 
     pub fn get_nf(&self) -> Option<Nullifier> {
         Nullifier::derive(
-            &self.nk_container,
+            self.nk_container.expose_secret(),
             &self.nonce.inner(),
             &self.get_psi(),
             &self.commitment(),
         )
     }
 
+    /// The tag of the resource in the resource tree of a ptx reading it: the
+    /// hash of its nullifier and its commitment. The tag hides the resource
+    /// until it is spent, the reads of a resource are linkable to each other
+    /// and, once its nullifier is published, to the resource.
+    pub fn get_read_tag(&self) -> Option<pallas::Base> {
+        self.get_nf()
+            .map(|nf| poseidon_hash_n([nf.inner(), self.commitment().inner()]))
+    }
+
     pub fn get_nk(&self) -> Option<pallas::Base> {
-        self.nk_container.get_nk()
+        self.nk_container.expose_secret().get_nk()
     }
 
     pub fn get_npk(&self) -> pallas::Base {
-        self.nk_container.get_npk()
+        self.nk_container.expose_secret().get_npk()
     }
 
-    pub fn get_kind(&self) -> pallas::Point {
-        self.kind.derive_kind()
+    /// The derived kind, looked up in the global kind cache.
+    pub fn kind(&self) -> pallas::Point {
+        KIND_CACHE
+            .lock()
+            .expect("kind cache lock poisoned")
+            .get_or_derive(&self.kind)
     }
 
     pub fn get_logic(&self) -> pallas::Base {
@@ -268,7 +338,7 @@ impl Resource {
         poseidon_hash_n([
             *PRF_EXPAND_PERSONALIZATION_TO_FIELD,
             pallas::Base::from(PRF_EXPAND_PSI as u64),
-            self.rseed,
+            *self.rseed.expose_secret(),
             self.nonce.inner(),
         ])
     }
@@ -278,7 +348,7 @@ impl Resource {
         poseidon_hash_n([
             *PRF_EXPAND_PERSONALIZATION_TO_FIELD,
             pallas::Base::from(PRF_EXPAND_RCM as u64),
-            self.rseed,
+            *self.rseed.expose_secret(),
             self.nonce.inner(),
         ])
     }
@@ -291,37 +361,193 @@ impl Resource {
     pub fn set_nonce(&mut self, input_resource: &Resource) {
         self.nonce = input_resource.get_nf().unwrap();
     }
+
+    pub fn builder() -> ResourceBuilder {
+        ResourceBuilder::default()
+    }
+}
+
+impl ResourceBuilder {
+    /// The compressed vk of the application logic of the resource.
+    pub fn logic(&mut self, logic: pallas::Base) -> &mut Self {
+        self.logic = Some(logic);
+        self
+    }
+
+    pub fn label(&mut self, label: pallas::Base) -> &mut Self {
+        self.label = label;
+        self
+    }
+
+    pub fn value(&mut self, value: pallas::Base) -> &mut Self {
+        self.value = value;
+        self
+    }
+
+    pub fn quantity(&mut self, quantity: impl Into<Quantity>) -> &mut Self {
+        self.quantity = quantity.into();
+        self
+    }
+
+    /// The owner of a created resource, i.e. the commitment of its nullifier
+    /// key.
+    pub fn npk(&mut self, npk: pallas::Base) -> &mut Self {
+        self.nk_container = Some(NullifierKeyContainer::from_npk(npk).into());
+        self
+    }
+
+    /// The nullifier key of a consumed resource.
+    pub fn nk(&mut self, nk: pallas::Base) -> &mut Self {
+        self.nk_container = Some(NullifierKeyContainer::from_key(nk).into());
+        self
+    }
+
+    /// The nonce of a consumed resource. The nonce of a created resource is
+    /// set by its compliance.
+    pub fn nonce(&mut self, nonce: Nullifier) -> &mut Self {
+        self.nonce = nonce;
+        self
+    }
+
+    pub fn ephemeral(&mut self, is_ephemeral: bool) -> &mut Self {
+        self.is_ephemeral = is_ephemeral;
+        self
+    }
+
+    /// The seed of the randomness of the resource. A random one is drawn by
+    /// `build` if it is not set.
+    pub fn rseed(&mut self, rseed: pallas::Base) -> &mut Self {
+        self.rseed = Some(rseed.into());
+        self
+    }
+
+    /// Locks the resource to a window of epochs: its value is then the value
+    /// set with `value` encoded with the window, see [`Timelock`].
+    pub fn timelock(&mut self, timelock: Timelock) -> &mut Self {
+        self.timelock = Some(timelock);
+        self
+    }
+
+    /// Same as `logic`, from the 32-byte little-endian encoding of the vk.
+    pub fn logic_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        if let Some(logic) = self.decode("logic", bytes) {
+            self.logic(logic);
+        }
+        self
+    }
+
+    pub fn label_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        if let Some(label) = self.decode("label", bytes) {
+            self.label(label);
+        }
+        self
+    }
+
+    pub fn value_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        if let Some(value) = self.decode("value", bytes) {
+            self.value(value);
+        }
+        self
+    }
+
+    pub fn npk_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        if let Some(npk) = self.decode("npk", bytes) {
+            self.npk(npk);
+        }
+        self
+    }
+
+    /// Same as `quantity`, from the field element the circuits encode it as,
+    /// e.g. a quantity computed by another implementation. It must be less
+    /// than 2^64.
+    pub fn quantity_field(&mut self, quantity: pallas::Base) -> &mut Self {
+        let repr = quantity.to_repr();
+        if repr[8..].iter().any(|byte| *byte != 0) {
+            self.invalid("quantity doesn't fit in 64 bits");
+        } else {
+            self.quantity(u64::from_le_bytes(repr[..8].try_into().unwrap()));
+        }
+        self
+    }
+
+    /// Checks the fields and builds the resource, drawing the rseed from `rng`
+    /// if it is not set.
+    pub fn build<R: RngCore>(&self, mut rng: R) -> Result<Resource, TransactionError> {
+        if let Some(error) = &self.error {
+            return Err(TransactionError::InvalidResource(error.clone()));
+        }
+        let logic = self
+            .logic
+            .ok_or_else(|| TransactionError::InvalidResource("logic is missing".to_string()))?;
+        let nk_container = self
+            .nk_container
+            .clone()
+            .ok_or_else(|| TransactionError::InvalidResource("owner is missing".to_string()))?;
+        Ok(Resource {
+            kind: ResourceKind::new(logic, self.label),
+            value: self
+                .timelock
+                .map_or(self.value, |timelock| timelock.encode_value(self.value)),
+            quantity: self.quantity,
+            nk_container,
+            nonce: self.nonce,
+            is_ephemeral: self.is_ephemeral,
+            rseed: self
+                .rseed
+                .clone()
+                .unwrap_or_else(|| pallas::Base::random(&mut rng).into()),
+        })
+    }
+
+    fn decode(&mut self, field: &str, bytes: &[u8]) -> Option<pallas::Base> {
+        let decoded = from_le_bytes(bytes);
+        if decoded.is_none() {
+            self.invalid(&format!("{field} is not a canonical field element"));
+        }
+        decoded
+    }
+
+    // Only the first invalid field is reported
+    fn invalid(&mut self, error: &str) {
+        self.error.get_or_insert_with(|| error.to_string());
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for ResourceKind {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.logic.to_repr())?;
+        writer.write_all(&self.label.to_repr())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for ResourceKind {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let logic = crate::utils::read_base_field(reader)?;
+        let label = crate::utils::read_base_field(reader)?;
+        Ok(Self { logic, label })
+    }
 }
 
 #[cfg(feature = "borsh")]
 impl BorshSerialize for Resource {
     fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
         use byteorder::{LittleEndian, WriteBytesExt};
-        // Write logic
-        writer.write_all(&self.kind.logic.to_repr())?;
-        // Write label
-        writer.write_all(&self.kind.label.to_repr())?;
+        // Write kind
+        self.kind.serialize(writer)?;
         // Write value
         writer.write_all(&self.value.to_repr())?;
         // Write resource quantity
-        writer.write_u64::<LittleEndian>(self.quantity)?;
+        writer.write_u64::<LittleEndian>(self.quantity.inner())?;
         // Write nk_container
-        match self.nk_container {
-            NullifierKeyContainer::PublicKey(nk) => {
-                writer.write_u8(1)?;
-                writer.write_all(&nk.to_repr())
-            }
-            NullifierKeyContainer::Key(nk) => {
-                writer.write_u8(2)?;
-                writer.write_all(&nk.to_repr())
-            }
-        }?;
+        self.nk_container.expose_secret().serialize(writer)?;
         // Write nonce
         writer.write_all(&self.nonce.to_bytes())?;
         // Write is_ephemeral
         writer.write_u8(if self.is_ephemeral { 1 } else { 0 })?;
         // Write rseed
-        writer.write_all(&self.rseed.to_repr())?;
+        writer.write_all(&self.rseed.expose_secret().to_repr())?;
 
         Ok(())
     }
@@ -333,22 +559,14 @@ impl BorshDeserialize for Resource {
         use crate::utils::read_base_field;
         use byteorder::{LittleEndian, ReadBytesExt};
         use std::io;
-        // Read logic
-        let logic = read_base_field(reader)?;
-        // Read label
-        let label = read_base_field(reader)?;
+        // Read kind
+        let kind = ResourceKind::deserialize_reader(reader)?;
         // Read value
         let value = read_base_field(reader)?;
         // Read resource quantity
-        let quantity = reader.read_u64::<LittleEndian>()?;
+        let quantity = Quantity::from(reader.read_u64::<LittleEndian>()?);
         // Read nk_container
-        let nk_container_type = reader.read_u8()?;
-        let nk = read_base_field(reader)?;
-        let nk_container = if nk_container_type == 0x01 {
-            NullifierKeyContainer::from_npk(nk)
-        } else {
-            NullifierKeyContainer::from_key(nk)
-        };
+        let nk_container = NullifierKeyContainer::deserialize_reader(reader)?;
         // Read nonce
         let mut nonce_bytes = [0u8; 32];
         reader.read_exact(&mut nonce_bytes)?;
@@ -356,18 +574,24 @@ impl BorshDeserialize for Resource {
             .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "nonce not in field"))?;
 
         // Read is_ephemeral
-        let mut is_ephemeral_byte = [0u8; 1];
-        reader.read_exact(&mut is_ephemeral_byte)?;
-        let is_ephemeral_byte = is_ephemeral_byte[0];
-        let is_ephemeral = is_ephemeral_byte == 0x01;
+        let is_ephemeral = match reader.read_u8()? {
+            0 => false,
+            1 => true,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid is_ephemeral flag",
+                ))
+            }
+        };
 
         // Read rseed
         let rseed = read_base_field(reader)?;
 
         // Construct resource
         Ok(Resource::from_full(
-            logic,
-            label,
+            kind.logic,
+            kind.label,
             value,
             quantity,
             nk_container,
@@ -392,6 +616,99 @@ impl ResourceKind {
     }
 }
 
+impl Default for Timelock {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+impl Timelock {
+    /// The window from `not_before` to `expiry`, both included, unbounded on
+    /// the missing sides.
+    pub fn new(not_before: Option<u64>, expiry: Option<u64>) -> Self {
+        Self {
+            not_before: not_before.unwrap_or(0),
+            expiry: expiry.unwrap_or(u64::MAX),
+        }
+    }
+
+    pub fn is_open(&self, epoch: u64) -> bool {
+        self.not_before <= epoch && epoch <= self.expiry
+    }
+
+    /// The value of a resource carrying `data` under the timelock:
+    /// Poseidon(data, not_before, expiry).
+    pub fn encode_value(&self, data: pallas::Base) -> pallas::Base {
+        poseidon_hash_n([
+            data,
+            pallas::Base::from(self.not_before),
+            pallas::Base::from(self.expiry),
+        ])
+    }
+}
+
+/// Default number of kinds kept by the global [`KindCache`].
+pub const KIND_CACHE_CAPACITY: usize = 1024;
+
+lazy_static! {
+    static ref KIND_CACHE: Mutex<KindCache> = Mutex::new(KindCache::new(KIND_CACHE_CAPACITY));
+}
+
+/// A least-recently-used cache of derived resource kinds keyed by (logic, label).
+///
+/// Deriving a kind is a hash to curve, which dominates delta accumulation in
+/// bundles with many resources of the same kind.
+#[derive(Debug, Clone)]
+pub struct KindCache {
+    capacity: usize,
+    // The derived kinds and the tick of their last use
+    kinds: HashMap<ResourceKind, (pallas::Point, u64)>,
+    // The kinds by the tick of their last use, least recently used first
+    recency: BTreeMap<u64, ResourceKind>,
+    tick: u64,
+}
+
+impl KindCache {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            capacity,
+            kinds: HashMap::with_capacity(capacity),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.kinds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    /// Returns the derived kind, computing and caching it on a miss.
+    pub fn get_or_derive(&mut self, kind: &ResourceKind) -> pallas::Point {
+        self.tick += 1;
+        if let Some((point, last_use)) = self.kinds.get_mut(kind) {
+            self.recency.remove(last_use);
+            *last_use = self.tick;
+            self.recency.insert(self.tick, *kind);
+            return *point;
+        }
+
+        let point = kind.derive_kind();
+        if self.kinds.len() == self.capacity {
+            if let Some((_, evicted)) = self.recency.pop_first() {
+                self.kinds.remove(&evicted);
+            }
+        }
+        self.kinds.insert(*kind, (point, self.tick));
+        self.recency.insert(self.tick, *kind);
+        point
+    }
+}
+
 impl Hash for ResourceKind {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.logic.to_repr().as_ref().hash(state);
@@ -399,6 +716,70 @@ impl Hash for ResourceKind {
     }
 }
 
+/// Compares the resources in constant time, the nullifier key and the seed
+/// are secret.
+impl ConstantTimeEq for Resource {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.kind.logic.ct_eq(&other.kind.logic)
+            & self.kind.label.ct_eq(&other.kind.label)
+            & self.value.ct_eq(&other.value)
+            & self.quantity.inner().ct_eq(&other.quantity.inner())
+            & self.nk_container.ct_eq(&other.nk_container)
+            & self.nonce.inner().ct_eq(&other.nonce.inner())
+            & u8::from(self.is_ephemeral).ct_eq(&u8::from(other.is_ephemeral))
+            & self.rseed.ct_eq(&other.rseed)
+    }
+}
+
+impl PartialEq for Resource {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for Resource {}
+
+impl Hash for Resource {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kind.hash(state);
+        self.value.to_repr().as_ref().hash(state);
+        self.quantity.hash(state);
+        match self.nk_container.expose_secret() {
+            NullifierKeyContainer::PublicKey(npk) => (0u8, npk.to_repr()).hash(state),
+            NullifierKeyContainer::Key(nk) => (1u8, nk.to_repr()).hash(state),
+        }
+        self.nonce.hash(state);
+        self.is_ephemeral.hash(state);
+        self.rseed.expose_secret().to_repr().as_ref().hash(state);
+    }
+}
+
+impl Zeroize for RandomSeed {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for RandomSeed {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for RandomSeed {}
+
+impl fmt::Debug for RandomSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RandomSeed(..)")
+    }
+}
+
+impl ConstantTimeEq for RandomSeed {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0[..].ct_eq(&other.0[..])
+    }
+}
+
 impl RandomSeed {
     pub fn random<R: RngCore>(mut rng: R) -> Self {
         let mut rseed = [0; 32];
@@ -460,20 +841,30 @@ impl ResourceLogics {
     }
 
     // Generate resource logic proofs
-    pub fn build(&self) -> ResourceLogicVerifyingInfoSet {
-        let app_resource_logic_verifying_info =
-            self.application_resource_logic.get_verifying_info();
+    pub fn build(&self) -> Result<ResourceLogicVerifyingInfoSet, TransactionError> {
+        self.build_with_rng(&mut OsRng)
+    }
+
+    /// Same as `build`, with the randomness taken from `rng`.
+    pub fn build_with_rng(
+        &self,
+        rng: &mut dyn RngCore,
+    ) -> Result<ResourceLogicVerifyingInfoSet, TransactionError> {
+        let app_resource_logic_verifying_info = self
+            .application_resource_logic
+            .get_verifying_info_with_rng(&mut *rng)?;
 
         let app_dynamic_resource_logic_verifying_info = self
             .dynamic_resource_logics
             .iter()
-            .map(|verifying_info| verifying_info.get_verifying_info())
-            .collect();
+            .map(|verifying_info| verifying_info.get_verifying_info_with_rng(&mut *rng))
+            .collect::<Result<_, _>>()?;
 
-        ResourceLogicVerifyingInfoSet::new(
+        Ok(ResourceLogicVerifyingInfoSet::new_with_owned_resources(
             app_resource_logic_verifying_info,
             app_dynamic_resource_logic_verifying_info,
-        )
+            self.application_resource_logic.get_owned_resource_ids(),
+        ))
     }
 
     // Create resource logics for a padding resource
@@ -493,11 +884,53 @@ impl ResourceLogics {
 #[cfg(test)]
 pub mod tests {
     use super::{Resource, ResourceKind};
-    use crate::nullifier::tests::*;
+    use crate::nullifier::{tests::*, Nullifier, NullifierKeyContainer};
+    use crate::quantity::Quantity;
     use halo2_proofs::arithmetic::Field;
     use pasta_curves::pallas;
+    use proptest::prelude::*;
     use rand::{Rng, RngCore};
 
+    impl Arbitrary for ResourceKind {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (arb_base(), arb_base())
+                .prop_map(|(logic, label)| ResourceKind::new(logic, label))
+                .boxed()
+        }
+    }
+
+    impl Arbitrary for Resource {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (
+                any::<ResourceKind>(),
+                arb_base(),
+                any::<u64>(),
+                any::<NullifierKeyContainer>(),
+                any::<bool>(),
+                any::<Nullifier>(),
+                arb_base(),
+            )
+                .prop_map(
+                    |(kind, value, quantity, nk_container, is_ephemeral, nonce, rseed)| Resource {
+                        kind,
+                        value,
+                        quantity: Quantity::new(quantity),
+                        nk_container: nk_container.into(),
+                        is_ephemeral,
+                        nonce,
+                        rseed: rseed.into(),
+                    },
+                )
+                .boxed()
+        }
+    }
+
     pub fn random_kind<R: RngCore>(mut rng: R) -> ResourceKind {
         let logic = pallas::Base::random(&mut rng);
         let label = pallas::Base::random(&mut rng);
@@ -510,11 +943,11 @@ pub mod tests {
         Resource {
             kind: random_kind(&mut rng),
             value: pallas::Base::random(&mut rng),
-            quantity: rng.gen(),
-            nk_container: random_nullifier_key(&mut rng),
+            quantity: Quantity::new(rng.gen()),
+            nk_container: random_nullifier_key(&mut rng).into(),
             is_ephemeral: false,
             nonce,
-            rseed,
+            rseed: rseed.into(),
         }
     }
 
@@ -534,11 +967,19 @@ pub mod tests {
             // BorshDeserialize
             let de_resource: Resource = BorshDeserialize::deserialize(&mut borsh.as_ref()).unwrap();
             assert_eq!(input_resource, de_resource);
+
+            // Unknown nullifier key container tags and flags are rejected
+            let mut invalid = borsh.clone();
+            invalid[32 * 3 + 8] = 3;
+            assert!(Resource::deserialize(&mut invalid.as_ref()).is_err());
+            let mut invalid = borsh;
+            invalid[32 * 3 + 8 + 33 + 32] = 2;
+            assert!(Resource::deserialize(&mut invalid.as_ref()).is_err());
         }
 
-        let mut output_resource = input_resource;
+        let mut output_resource = input_resource.clone();
         {
-            output_resource.nk_container = random_nullifier_key_commitment(&mut rng);
+            output_resource.nk_container = random_nullifier_key_commitment(&mut rng).into();
             // BorshSerialize
             let borsh = borsh::to_vec(&output_resource).unwrap();
             // BorshDeserialize
@@ -566,4 +1007,146 @@ pub mod tests {
             assert_eq!(ocm, de_ocm);
         }
     }
+
+    #[test]
+    fn test_random_seed_secrecy() {
+        use super::RandomSeed;
+        use subtle::ConstantTimeEq;
+        use zeroize::Zeroize;
+
+        let mut rseed = RandomSeed::from_bytes([7u8; 32]);
+        assert!(bool::from(rseed.ct_eq(&rseed.clone())));
+        assert_eq!(format!("{rseed:?}"), "RandomSeed(..)");
+
+        rseed.zeroize();
+        assert!(bool::from(rseed.ct_eq(&RandomSeed::default())));
+    }
+
+    #[test]
+    fn test_kind_cache() {
+        use super::KindCache;
+
+        let mut rng = rand::rngs::OsRng;
+        let kinds: Vec<ResourceKind> = (0..3).map(|_| random_kind(&mut rng)).collect();
+        let mut cache = KindCache::new(2);
+
+        assert_eq!(cache.get_or_derive(&kinds[0]), kinds[0].derive_kind());
+        assert_eq!(cache.get_or_derive(&kinds[1]), kinds[1].derive_kind());
+        // Touch kinds[0] so kinds[1] becomes the least recently used one
+        assert_eq!(cache.get_or_derive(&kinds[0]), kinds[0].derive_kind());
+        assert_eq!(cache.get_or_derive(&kinds[2]), kinds[2].derive_kind());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.kinds.contains_key(&kinds[0]));
+        assert!(!cache.kinds.contains_key(&kinds[1]));
+
+        let resource = random_resource(&mut rng);
+        assert_eq!(resource.kind(), resource.kind.derive_kind());
+    }
+
+    #[test]
+    fn test_resource_builder() {
+        use crate::error::TransactionError;
+        use ff::PrimeField;
+        use rand::rngs::OsRng;
+
+        let mut rng = OsRng;
+        let resource = random_resource(&mut rng);
+        let built = Resource::builder()
+            .logic(resource.get_logic())
+            .label(resource.get_label())
+            .value(resource.value)
+            .quantity(resource.quantity)
+            .nk(resource.get_nk().unwrap())
+            .nonce(resource.nonce)
+            .rseed(*resource.rseed.expose_secret())
+            .build(&mut rng)
+            .unwrap();
+        assert_eq!(built, resource);
+
+        // A created resource from its encodings
+        let npk = pallas::Base::random(&mut rng);
+        let built = Resource::builder()
+            .logic_bytes(&resource.get_logic().to_repr())
+            .label_bytes(&resource.get_label().to_repr())
+            .npk_bytes(&npk.to_repr())
+            .quantity_field(pallas::Base::from(u64::MAX))
+            .ephemeral(true)
+            .build(&mut rng)
+            .unwrap();
+        assert_eq!(built.kind, resource.kind);
+        assert_eq!(built.get_npk(), npk);
+        assert_eq!(built.quantity, Quantity::MAX);
+        assert!(built.is_ephemeral);
+
+        // A timelocked resource encodes the window in its value
+        let timelock = super::Timelock::new(Some(10), None);
+        let built = Resource::builder()
+            .logic(resource.get_logic())
+            .value(resource.value)
+            .timelock(timelock)
+            .npk(npk)
+            .build(&mut rng)
+            .unwrap();
+        assert_eq!(built.value, timelock.encode_value(resource.value));
+
+        // Missing and invalid fields are errors
+        let invalid = |builder: &mut super::ResourceBuilder| {
+            matches!(
+                builder.build(OsRng),
+                Err(TransactionError::InvalidResource(_))
+            )
+        };
+        assert!(invalid(Resource::builder().npk(npk)));
+        assert!(invalid(Resource::builder().logic(npk)));
+        assert!(invalid(
+            Resource::builder()
+                .logic(npk)
+                .npk(npk)
+                .quantity_field(pallas::Base::from_u128(1 << 64))
+        ));
+        assert!(invalid(
+            Resource::builder()
+                .logic(npk)
+                .npk(npk)
+                .value_bytes(&[0xff; 32])
+        ));
+        assert!(invalid(
+            Resource::builder()
+                .logic(npk)
+                .npk(npk)
+                .label_bytes(&[0; 31])
+        ));
+    }
+
+    proptest! {
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_resource_borsh_round_trip(resource: Resource) {
+            use super::ResourceCommitment;
+
+            let bytes = borsh::to_vec(&resource).unwrap();
+            prop_assert_eq!(borsh::from_slice::<Resource>(&bytes).unwrap(), resource.clone());
+            let bytes = borsh::to_vec(&resource.kind).unwrap();
+            prop_assert_eq!(borsh::from_slice::<ResourceKind>(&bytes).unwrap(), resource.kind);
+            let cm = resource.commitment();
+            let bytes = borsh::to_vec(&cm).unwrap();
+            prop_assert_eq!(borsh::from_slice::<ResourceCommitment>(&bytes).unwrap(), cm);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_resource_serde_round_trip(resource: Resource) {
+            let json = serde_json::to_string(&resource).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Resource>(&json).unwrap(), resource);
+        }
+
+        // Resources differing in any field have different nullifiers
+        #[test]
+        fn test_nullifier_uniqueness(mut a: Resource, mut b: Resource, nk: pallas::Base) {
+            a.nk_container = NullifierKeyContainer::from_key(nk).into();
+            b.nk_container = a.nk_container.clone();
+            prop_assume!(a != b);
+            prop_assert_ne!(a.get_nf().unwrap(), b.get_nf().unwrap());
+        }
+    }
 }
@@ -1,130 +1,689 @@
+use crate::circuit::resource_logic_circuit::ResourceLogicPublicInputs;
+use crate::circuit::resource_logic_circuit::ResourceLogicVerifyingInfo;
 use crate::constant::{
-    POSEIDON_RATE, POSEIDON_WIDTH, RESOURCE_ENCRYPTION_CIPHERTEXT_NUM,
-    RESOURCE_ENCRYPTION_PLAINTEXT_NUM,
+    DIVERSIFIER_PERSONALIZATION, GENERATOR, MAILBOX_PLAINTEXT_NUM, MEMO_PLAINTEXT_NUM, MEMO_SIZE,
+    POSEIDON_RATE, POSEIDON_WIDTH, RESOURCE_ENCRYPTION_LARGE_PLAINTEXT_NUM,
+    RESOURCE_ENCRYPTION_MEDIUM_PLAINTEXT_NUM, RESOURCE_ENCRYPTION_PLAINTEXT_NUM,
+    RESOURCE_ENCRYPTION_TAG, RESOURCE_LOGIC_CIRCUIT_MAILBOX_IDX,
 };
+use crate::error::TransactionError;
+use crate::nullifier::{Nullifier, NullifierKeyContainer};
+use crate::resource::{Resource, ResourceCommitment};
+use crate::secret::Secret;
+use crate::transaction::Transaction;
+use crate::utils::{mod_r_p, poseidon_hash, poseidon_hash_n, to_field_elements};
 use ff::PrimeField;
-use group::Curve;
+use group::{cofactor::CofactorCurveAffine, Curve, Group, GroupEncoding};
 use halo2_gadgets::poseidon::primitives as poseidon;
 use halo2_proofs::arithmetic::CurveAffine;
-use pasta_curves::pallas;
+use pasta_curves::{arithmetic::CurveExt, pallas};
+use rand::RngCore;
 
-#[derive(Debug, Clone)]
-pub struct ResourceCiphertext([pallas::Base; RESOURCE_ENCRYPTION_CIPHERTEXT_NUM]);
+#[cfg(feature = "serde")]
+use serde;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// The standardized plaintext lengths of encrypted resources. The ciphertexts
+/// of a class all have the same length, so they only leak the class.
+///
+/// Small ciphertexts are published in the resource encryption public inputs of
+/// the logic. The larger classes are published in an extra instance column
+/// along with the class, see [`ResourceCiphertext::to_instance`].
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EncryptionClass {
+    #[default]
+    Small,
+    Medium,
+    Large,
+}
+
+impl EncryptionClass {
+    pub const ALL: [EncryptionClass; 3] = [
+        EncryptionClass::Small,
+        EncryptionClass::Medium,
+        EncryptionClass::Large,
+    ];
+
+    pub fn plaintext_num(&self) -> usize {
+        match self {
+            EncryptionClass::Small => RESOURCE_ENCRYPTION_PLAINTEXT_NUM,
+            EncryptionClass::Medium => RESOURCE_ENCRYPTION_MEDIUM_PLAINTEXT_NUM,
+            EncryptionClass::Large => RESOURCE_ENCRYPTION_LARGE_PLAINTEXT_NUM,
+        }
+    }
+
+    // msg + encrypt_nonce + MAC
+    pub fn ciphertext_num(&self) -> usize {
+        self.plaintext_num() + 2
+    }
+
+    /// The smallest class fitting a message of `len` elements.
+    pub fn fitting(len: usize) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|class| len <= class.plaintext_num())
+    }
+
+    pub fn from_plaintext_num(len: usize) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|class| class.plaintext_num() == len)
+    }
+
+    pub fn from_ciphertext_num(len: usize) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|class| class.ciphertext_num() == len)
+    }
+
+    /// The class as recorded in the instance column.
+    pub fn to_field(&self) -> pallas::Base {
+        pallas::Base::from(*self as u64)
+    }
+
+    pub fn from_field(class: &pallas::Base) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.to_field() == *class)
+    }
+}
 
 #[derive(Debug, Clone)]
-pub struct ResourcePlaintext([pallas::Base; RESOURCE_ENCRYPTION_PLAINTEXT_NUM]);
+pub struct ResourceCiphertext(Vec<pallas::Base>);
 
 #[derive(Debug, Clone)]
-pub struct SecretKey(pallas::Point);
+pub struct ResourcePlaintext(Vec<pallas::Base>);
+
+/// The key shared by the sender and the receiver, zeroized on drop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretKey(Secret<pallas::Point>);
 
 impl ResourceCiphertext {
-    pub fn inner(&self) -> &[pallas::Base; RESOURCE_ENCRYPTION_CIPHERTEXT_NUM] {
+    pub fn inner(&self) -> &[pallas::Base] {
         &self.0
     }
 
+    pub fn class(&self) -> EncryptionClass {
+        EncryptionClass::from_ciphertext_num(self.0.len()).unwrap()
+    }
+
     pub fn encrypt(
         message: &ResourcePlaintext,
         secret_key: &SecretKey,
         encrypt_nonce: &pallas::Base,
     ) -> Self {
-        // Init poseidon sponge state
-        let mut poseidon_sponge =
-            Self::poseidon_sponge_init(message.inner().len(), secret_key, encrypt_nonce);
+        let mut encryptor = PayloadEncryptor::new(secret_key, encrypt_nonce, message.inner().len());
+        let mut cipher = encryptor.update(message.inner());
+        cipher.extend(encryptor.finalize());
+        cipher.into()
+    }
+
+    pub fn decrypt(&self, secret_key: &SecretKey) -> Option<Vec<pallas::Base>> {
+        sponge_decrypt(&self.0, secret_key)
+    }
+
+    /// The extra instance column publishing the ciphertext:
+    /// RESOURCE_ENCRYPTION_TAG || class || ciphertext || sender_pk.
+    pub fn to_instance(&self, sender_pk: &pallas::Point) -> Vec<pallas::Base> {
+        let sender_pk = sender_pk.to_affine().coordinates().unwrap();
+        [*RESOURCE_ENCRYPTION_TAG, self.class().to_field()]
+            .into_iter()
+            .chain(self.0.iter().copied())
+            .chain([*sender_pk.x(), *sender_pk.y()])
+            .collect()
+    }
+
+    /// Reads the ciphertext and the sender pk from an extra instance column.
+    /// Returns None if the column doesn't publish a ciphertext.
+    pub fn from_instance(column: &[pallas::Base]) -> Option<(Self, pallas::Point)> {
+        let (tag, column) = column.split_first()?;
+        if *tag != *RESOURCE_ENCRYPTION_TAG {
+            return None;
+        }
+        let (class, column) = column.split_first()?;
+        let cipher_num = EncryptionClass::from_field(class)?.ciphertext_num();
+        if column.len() < cipher_num + 2 {
+            return None;
+        }
+        let sender_pk: Option<pallas::Affine> =
+            pallas::Affine::from_xy(column[cipher_num], column[cipher_num + 1]).into();
+        Some((Self(column[..cipher_num].to_vec()), sender_pk?.to_curve()))
+    }
+}
+
+type PoseidonSponge = poseidon::Sponge<
+    pallas::Base,
+    poseidon::P128Pow5T3,
+    poseidon::Absorbing<pallas::Base, POSEIDON_RATE>,
+    POSEIDON_WIDTH,
+    POSEIDON_RATE,
+>;
+
+fn poseidon_sponge_init(
+    message_len: usize,
+    secret_key: &SecretKey,
+    encrypt_nonce: &pallas::Base,
+) -> PoseidonSponge {
+    let key_coord = secret_key.get_coordinates();
+    let length_nonce = encrypt_nonce
+        + pallas::Base::from(message_len as u64) * pallas::Base::from_u128(1 << 64).square();
+    let state = [key_coord.0, key_coord.1, length_nonce];
+    poseidon::Sponge::<_, poseidon::P128Pow5T3, _, POSEIDON_WIDTH, POSEIDON_RATE>::init(state)
+}
+
+fn poseidon_sponge_permute(sponge: &mut PoseidonSponge) {
+    poseidon::permute::<_, poseidon::P128Pow5T3, POSEIDON_WIDTH, POSEIDON_RATE>(
+        &mut sponge.state,
+        &sponge.mds_matrix,
+        &sponge.round_constants,
+    );
+}
+
+// Decrypt the cipher: the encrypted message || encrypt_nonce || MAC
+fn sponge_decrypt(cipher: &[pallas::Base], secret_key: &SecretKey) -> Option<Vec<pallas::Base>> {
+    let cipher_len = cipher.len();
+    if cipher_len < 2 {
+        return None;
+    }
+    let mac = cipher[cipher_len - 1];
+    let encrypt_nonce = cipher[cipher_len - 2];
+    // Init poseidon sponge state
+    let mut poseidon_sponge = poseidon_sponge_init(cipher_len - 2, secret_key, &encrypt_nonce);
+
+    // Decrypt
+    let mut msg = vec![];
+    for chunk in cipher[0..cipher_len - 2].chunks(POSEIDON_RATE) {
+        poseidon_sponge_permute(&mut poseidon_sponge);
+        for (idx, cipher_element) in chunk.iter().enumerate() {
+            let msg_element = *cipher_element - poseidon_sponge.state[idx];
+            msg.push(msg_element);
+            poseidon_sponge.state[idx] = *cipher_element;
+        }
+    }
+
+    // Check MAC
+    poseidon_sponge_permute(&mut poseidon_sponge);
+    if mac != poseidon_sponge.state[0] {
+        return None;
+    }
+
+    Some(msg)
+}
+
+/// Encrypts a message of known length block by block, so that large payloads
+/// don't need to be held in memory at once. The ciphertext is the same as
+/// encrypting the whole message at once.
+pub struct PayloadEncryptor {
+    sponge: PoseidonSponge,
+    encrypt_nonce: pallas::Base,
+    // The elements waiting for a full block
+    pending: Vec<pallas::Base>,
+    remaining: usize,
+}
+
+impl PayloadEncryptor {
+    pub fn new(secret_key: &SecretKey, encrypt_nonce: &pallas::Base, message_len: usize) -> Self {
+        Self {
+            sponge: poseidon_sponge_init(message_len, secret_key, encrypt_nonce),
+            encrypt_nonce: *encrypt_nonce,
+            pending: vec![],
+            remaining: message_len,
+        }
+    }
+
+    fn encrypt_block(&mut self, block: &[pallas::Base], cipher: &mut Vec<pallas::Base>) {
+        poseidon_sponge_permute(&mut self.sponge);
+        for (idx, msg_element) in block.iter().enumerate() {
+            self.sponge.state[idx] += msg_element;
+            cipher.push(self.sponge.state[idx]);
+        }
+    }
+
+    /// Encrypts the next elements of the message and returns the ciphertext of
+    /// the complete blocks.
+    pub fn update(&mut self, message: &[pallas::Base]) -> Vec<pallas::Base> {
+        assert!(
+            message.len() <= self.remaining,
+            "message longer than declared"
+        );
+        self.remaining -= message.len();
+        self.pending.extend_from_slice(message);
 
-        // Encrypt
         let mut cipher = vec![];
-        for chunk in message.inner().chunks(POSEIDON_RATE) {
-            poseidon::permute::<_, poseidon::P128Pow5T3, POSEIDON_WIDTH, POSEIDON_RATE>(
-                &mut poseidon_sponge.state,
-                &poseidon_sponge.mds_matrix,
-                &poseidon_sponge.round_constants,
-            );
-            for (idx, msg_element) in chunk.iter().enumerate() {
-                poseidon_sponge.state[idx] += msg_element;
-                cipher.push(poseidon_sponge.state[idx]);
-            }
+        let full_len = self.pending.len() - self.pending.len() % POSEIDON_RATE;
+        let pending: Vec<pallas::Base> = self.pending.drain(..full_len).collect();
+        for block in pending.chunks(POSEIDON_RATE) {
+            self.encrypt_block(block, &mut cipher);
+        }
+        cipher
+    }
+
+    /// Returns the ciphertext of the last partial block, the encrypt nonce and
+    /// the MAC.
+    pub fn finalize(mut self) -> Vec<pallas::Base> {
+        assert_eq!(self.remaining, 0, "message shorter than declared");
+        let mut cipher = vec![];
+        let pending = std::mem::take(&mut self.pending);
+        if !pending.is_empty() {
+            self.encrypt_block(&pending, &mut cipher);
         }
 
         // Add encrypt_nonce
-        cipher.push(*encrypt_nonce);
+        cipher.push(self.encrypt_nonce);
 
         // Compute the MAC
-        poseidon::permute::<_, poseidon::P128Pow5T3, POSEIDON_WIDTH, POSEIDON_RATE>(
-            &mut poseidon_sponge.state,
-            &poseidon_sponge.mds_matrix,
-            &poseidon_sponge.round_constants,
-        );
-        cipher.push(poseidon_sponge.state[0]);
-        cipher.into()
+        poseidon_sponge_permute(&mut self.sponge);
+        cipher.push(self.sponge.state[0]);
+        cipher
+    }
+}
+
+/// The ciphertext of an app payload of any length. Only its commitment is a
+/// public input of the logic, the ciphertext itself is distributed along with
+/// the transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PayloadCiphertext(Vec<pallas::Base>);
+
+impl PayloadCiphertext {
+    pub fn inner(&self) -> &[pallas::Base] {
+        &self.0
+    }
+
+    pub fn encrypt(
+        payload: &[pallas::Base],
+        secret_key: &SecretKey,
+        encrypt_nonce: &pallas::Base,
+    ) -> Self {
+        let mut encryptor = PayloadEncryptor::new(secret_key, encrypt_nonce, payload.len());
+        let mut cipher = encryptor.update(payload);
+        cipher.extend(encryptor.finalize());
+        Self(cipher)
     }
 
     pub fn decrypt(&self, secret_key: &SecretKey) -> Option<Vec<pallas::Base>> {
-        let cipher_len = self.0.len();
-        let mac = self.0[cipher_len - 1];
-        let encrypt_nonce = self.0[cipher_len - 2];
-        // Init poseidon sponge state
-        let mut poseidon_sponge =
-            Self::poseidon_sponge_init(cipher_len - 2, secret_key, &encrypt_nonce);
-
-        // Decrypt
-        let mut msg = vec![];
-        for chunk in self.0[0..cipher_len - 2].chunks(POSEIDON_RATE) {
-            poseidon::permute::<_, poseidon::P128Pow5T3, POSEIDON_WIDTH, POSEIDON_RATE>(
-                &mut poseidon_sponge.state,
-                &poseidon_sponge.mds_matrix,
-                &poseidon_sponge.round_constants,
-            );
-            for (idx, cipher_element) in chunk.iter().enumerate() {
-                let msg_element = *cipher_element - poseidon_sponge.state[idx];
-                msg.push(msg_element);
-                poseidon_sponge.state[idx] = *cipher_element;
-            }
-        }
+        sponge_decrypt(&self.0, secret_key)
+    }
+
+    /// The commitment is a Poseidon hash chain over the ciphertext, including
+    /// the encrypt nonce and the MAC.
+    pub fn commitment(&self) -> pallas::Base {
+        self.0
+            .iter()
+            .fold(pallas::Base::zero(), |acc, ele| poseidon_hash(acc, *ele))
+    }
+}
 
-        // Check MAC
-        poseidon::permute::<_, poseidon::P128Pow5T3, POSEIDON_WIDTH, POSEIDON_RATE>(
-            &mut poseidon_sponge.state,
-            &poseidon_sponge.mds_matrix,
-            &poseidon_sponge.round_constants,
+/// A message a logic leaves to the owner of its resource, e.g. the state of a
+/// protocol for the next consumer of the resource. The logic publishes the
+/// commitment at `RESOURCE_LOGIC_CIRCUIT_MAILBOX_IDX`, see `mailbox_gadget`,
+/// and the mailbox is distributed along with the transaction. The logic binds
+/// the receiver to the owner of the resource, see `Mailbox::owner`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mailbox {
+    ciphertext: PayloadCiphertext,
+    sender_pk: pallas::Point,
+}
+
+impl Mailbox {
+    /// Encrypts the message, padded to `MAILBOX_PLAINTEXT_NUM`, to `rcv_pk`.
+    pub fn seal(
+        message: &[pallas::Base],
+        rcv_pk: &pallas::Point,
+        sender_sk: &pallas::Base,
+        encrypt_nonce: &pallas::Base,
+    ) -> Self {
+        assert!(
+            message.len() <= MAILBOX_PLAINTEXT_NUM,
+            "The mailbox message exceeds MAILBOX_PLAINTEXT_NUM"
         );
-        if mac != poseidon_sponge.state[0] {
+        let mut plaintext = message.to_vec();
+        plaintext.resize(MAILBOX_PLAINTEXT_NUM, pallas::Base::zero());
+        let sender_sk = mod_r_p(*sender_sk);
+        let key = SecretKey::from_dh_exchange(rcv_pk, &sender_sk);
+        Self {
+            ciphertext: PayloadCiphertext::encrypt(&plaintext, &key, encrypt_nonce),
+            sender_pk: GENERATOR.to_curve() * sender_sk,
+        }
+    }
+
+    /// The owner a logic stores in its resource, e.g. in the value, to receive
+    /// mailboxes under `rcv_pk`: the Poseidon hash of the coordinates of the pk.
+    pub fn owner(rcv_pk: &pallas::Point) -> pallas::Base {
+        let rcv_pk = rcv_pk.to_affine().coordinates().unwrap();
+        poseidon_hash(*rcv_pk.x(), *rcv_pk.y())
+    }
+
+    pub fn ciphertext(&self) -> &PayloadCiphertext {
+        &self.ciphertext
+    }
+
+    pub fn sender_pk(&self) -> pallas::Point {
+        self.sender_pk
+    }
+
+    /// Whether the ciphertext has the length of a mailbox.
+    pub fn is_well_formed(&self) -> bool {
+        self.ciphertext.inner().len() == MAILBOX_PLAINTEXT_NUM + 2
+    }
+
+    /// The ciphertext and the sender pk.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.ciphertext
+            .inner()
+            .iter()
+            .for_each(|element| bytes.extend(element.to_repr()));
+        bytes.extend(self.sender_pk.to_bytes());
+        bytes
+    }
+
+    /// The hash chain over the ciphertext followed by the sender pk.
+    pub fn commitment(&self) -> pallas::Base {
+        let sender_pk = self.sender_pk.to_affine().coordinates().unwrap();
+        [*sender_pk.x(), *sender_pk.y()]
+            .into_iter()
+            .fold(self.ciphertext.commitment(), poseidon_hash)
+    }
+
+    /// Decrypts the padded message.
+    pub fn open(&self, rcv_sk: &pallas::Scalar) -> Option<Vec<pallas::Base>> {
+        if !self.is_well_formed() {
             return None;
         }
+        let key = SecretKey::from_dh_exchange(&self.sender_pk, rcv_sk);
+        self.ciphertext.decrypt(&key)
+    }
 
-        Some(msg)
+    /// Checks that the logic publishes the mailbox before decrypting it.
+    pub fn open_checked(
+        &self,
+        public_inputs: &ResourceLogicPublicInputs,
+        rcv_sk: &pallas::Scalar,
+    ) -> Result<Vec<pallas::Base>, TransactionError> {
+        if public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_MAILBOX_IDX) != self.commitment() {
+            return Err(TransactionError::MailboxCommitmentMismatch);
+        }
+        self.open(rcv_sk).ok_or(TransactionError::InvalidMailbox)
     }
+}
 
-    fn poseidon_sponge_init(
-        message_len: usize,
-        secret_key: &SecretKey,
+#[cfg(feature = "borsh")]
+impl BorshSerialize for Mailbox {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        if !self.is_well_formed() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "mailbox ciphertext with incorrect length",
+            ));
+        }
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for Mailbox {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        use crate::utils::{read_base_field, read_point};
+        let ciphertext = (0..MAILBOX_PLAINTEXT_NUM + 2)
+            .map(|_| read_base_field(reader))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let sender_pk = read_point(reader)?;
+        Ok(Self {
+            ciphertext: PayloadCiphertext(ciphertext),
+            sender_pk,
+        })
+    }
+}
+
+/// The diversifier of an address. A receiver derives an address from its
+/// encryption secret key for every diversifier it picks, see
+/// `Address::derive`.
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diversifier(pub [u8; 11]);
+
+impl Diversifier {
+    pub fn random(mut rng: impl RngCore) -> Self {
+        let mut diversifier = [0u8; 11];
+        rng.fill_bytes(&mut diversifier);
+        Self(diversifier)
+    }
+
+    /// The diversified base g_d.
+    pub fn g_d(&self) -> pallas::Point {
+        pallas::Point::hash_to_curve(DIVERSIFIER_PERSONALIZATION)(&self.0)
+    }
+}
+
+/// A diversified address (d, pk_d = ivk * g_d), as in ZIP-32. The addresses of
+/// a receiver are unlinkable, and what is sent to any of them decrypts with
+/// the same key ivk: the sender publishes esk * g_d as its pk and encrypts
+/// with esk * pk_d, which the receiver recomputes as ivk * esk * g_d. The
+/// resources and memos sent to the addresses are thus found by the usual
+/// scanning, e.g. `scan_transaction` with ivk.
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Address {
+    diversifier: Diversifier,
+    pk_d: pallas::Point,
+}
+
+impl Address {
+    /// The address of the receiver of `rcv_sk` at `diversifier`.
+    pub fn derive(rcv_sk: &pallas::Base, diversifier: Diversifier) -> Self {
+        Self {
+            diversifier,
+            pk_d: diversifier.g_d() * mod_r_p(*rcv_sk),
+        }
+    }
+
+    pub fn diversifier(&self) -> Diversifier {
+        self.diversifier
+    }
+
+    pub fn g_d(&self) -> pallas::Point {
+        self.diversifier.g_d()
+    }
+
+    pub fn pk_d(&self) -> pallas::Point {
+        self.pk_d
+    }
+
+    /// The shared key and the sender pk of an encryption to the address.
+    pub fn dh_exchange(&self, sender_sk: &pallas::Scalar) -> (SecretKey, pallas::Point) {
+        (
+            SecretKey::from_dh_exchange(&self.pk_d, sender_sk),
+            self.g_d() * sender_sk,
+        )
+    }
+
+    /// The owner encoded in the value of the resources sent to the address,
+    /// Poseidon(g_d.x, g_d.y, pk_d.x, pk_d.y). Logics encrypting their
+    /// resource to the address check it against the g_d and pk_d they
+    /// encrypt with, see `ReceiverVar::Address`.
+    pub fn owner(&self) -> pallas::Base {
+        let g_d = self.g_d().to_affine().coordinates().unwrap();
+        let pk_d = self.pk_d.to_affine().coordinates().unwrap();
+        poseidon_hash_n([*g_d.x(), *g_d.y(), *pk_d.x(), *pk_d.y()])
+    }
+
+    pub fn to_bytes(&self) -> [u8; 43] {
+        let mut bytes = [0u8; 43];
+        bytes[..11].copy_from_slice(&self.diversifier.0);
+        bytes[11..].copy_from_slice(&self.pk_d.to_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; 43]) -> Option<Self> {
+        let mut diversifier = [0u8; 11];
+        diversifier.copy_from_slice(&bytes[..11]);
+        let mut pk_d = [0u8; 32];
+        pk_d.copy_from_slice(&bytes[11..]);
+        let pk_d: pallas::Point = Option::from(pallas::Point::from_bytes(&pk_d))?;
+        if bool::from(pk_d.is_identity()) {
+            return None;
+        }
+        Some(Self {
+            diversifier: Diversifier(diversifier),
+            pk_d,
+        })
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for Address {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for Address {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut bytes = [0u8; 43];
+        reader.read_exact(&mut bytes)?;
+        Self::from_bytes(&bytes)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid address"))
+    }
+}
+
+/// A memo the sender attaches to a resource created by a transaction, e.g. a
+/// payment reference, like the memos of Zcash. The memo is padded to
+/// `MEMO_SIZE` bytes and encrypted to the receiver of the resource, and the
+/// binding signature of the transaction covers it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Memo {
+    output_cm: ResourceCommitment,
+    ciphertext: PayloadCiphertext,
+    sender_pk: pallas::Point,
+}
+
+impl Memo {
+    /// Encrypts the memo of the created resource `output_cm` to the address.
+    pub fn seal(
+        memo: &[u8],
+        output_cm: ResourceCommitment,
+        address: &Address,
+        sender_sk: &pallas::Base,
         encrypt_nonce: &pallas::Base,
-    ) -> poseidon::Sponge<
-        pallas::Base,
-        poseidon::P128Pow5T3,
-        poseidon::Absorbing<pallas::Base, POSEIDON_RATE>,
-        POSEIDON_WIDTH,
-        POSEIDON_RATE,
-    > {
-        let key_coord = secret_key.get_coordinates();
-        let length_nonce = encrypt_nonce
-            + pallas::Base::from(message_len as u64) * pallas::Base::from_u128(1 << 64).square();
-        let state = [key_coord.0, key_coord.1, length_nonce];
-        poseidon::Sponge::<_, poseidon::P128Pow5T3, _, POSEIDON_WIDTH, POSEIDON_RATE>::init(state)
+    ) -> Result<Self, TransactionError> {
+        if memo.len() > MEMO_SIZE {
+            return Err(TransactionError::MemoTooLong);
+        }
+        let mut padded = memo.to_vec();
+        padded.resize(MEMO_SIZE, 0);
+        let (key, sender_pk) = address.dh_exchange(&mod_r_p(*sender_sk));
+        Ok(Self {
+            output_cm,
+            ciphertext: PayloadCiphertext::encrypt(
+                &to_field_elements(&padded),
+                &key,
+                encrypt_nonce,
+            ),
+            sender_pk,
+        })
+    }
+
+    pub fn output_cm(&self) -> ResourceCommitment {
+        self.output_cm
+    }
+
+    pub fn ciphertext(&self) -> &PayloadCiphertext {
+        &self.ciphertext
+    }
+
+    pub fn sender_pk(&self) -> pallas::Point {
+        self.sender_pk
+    }
+
+    /// Whether the ciphertext has the length of a memo.
+    pub fn is_well_formed(&self) -> bool {
+        self.ciphertext.inner().len() == MEMO_PLAINTEXT_NUM + 2
+    }
+
+    /// Decrypts the memo, padded with zeros to `MEMO_SIZE` bytes.
+    pub fn open(&self, rcv_sk: &pallas::Scalar) -> Option<Vec<u8>> {
+        if !self.is_well_formed() {
+            return None;
+        }
+        let key = SecretKey::from_dh_exchange(&self.sender_pk, rcv_sk);
+        let mut memo: Vec<u8> = self
+            .ciphertext
+            .decrypt(&key)?
+            .iter()
+            .flat_map(|element| element.to_repr()[..31].to_vec())
+            .collect();
+        memo.truncate(MEMO_SIZE);
+        Some(memo)
+    }
+
+    /// The output cm, the ciphertext and the sender pk.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.output_cm.to_bytes().to_vec();
+        self.ciphertext
+            .inner()
+            .iter()
+            .for_each(|element| bytes.extend(element.to_repr()));
+        bytes.extend(self.sender_pk.to_bytes());
+        bytes
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for Memo {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        if !self.is_well_formed() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "memo ciphertext with incorrect length",
+            ));
+        }
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for Memo {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        use crate::utils::{read_base_field, read_point};
+        let output_cm = ResourceCommitment::from(read_base_field(reader)?);
+        let ciphertext = (0..MEMO_PLAINTEXT_NUM + 2)
+            .map(|_| read_base_field(reader))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let sender_pk = read_point(reader)?;
+        Ok(Self {
+            output_cm,
+            ciphertext: PayloadCiphertext(ciphertext),
+            sender_pk,
+        })
+    }
+}
+
+impl From<Vec<pallas::Base>> for PayloadCiphertext {
+    fn from(cipher: Vec<pallas::Base>) -> Self {
+        PayloadCiphertext(cipher)
     }
 }
 
 impl From<Vec<pallas::Base>> for ResourceCiphertext {
     fn from(input_vec: Vec<pallas::Base>) -> Self {
-        ResourceCiphertext(
-            input_vec
-                .try_into()
-                .expect("public input with incorrect length"),
-        )
+        assert!(
+            EncryptionClass::from_ciphertext_num(input_vec.len()).is_some(),
+            "ciphertext with incorrect length"
+        );
+        ResourceCiphertext(input_vec)
     }
 }
 
 impl ResourcePlaintext {
-    pub fn inner(&self) -> &[pallas::Base; RESOURCE_ENCRYPTION_PLAINTEXT_NUM] {
+    pub fn inner(&self) -> &[pallas::Base] {
         &self.0
     }
 
@@ -132,36 +691,178 @@ impl ResourcePlaintext {
         self.0.to_vec()
     }
 
+    pub fn class(&self) -> EncryptionClass {
+        EncryptionClass::from_plaintext_num(self.0.len()).unwrap()
+    }
+
+    /// Pads the message to the small class.
     pub fn padding(msg: &[pallas::Base]) -> Self {
+        Self::padding_to_class(msg, EncryptionClass::Small)
+    }
+
+    pub fn padding_to_class(msg: &[pallas::Base], class: EncryptionClass) -> Self {
+        assert!(
+            msg.len() <= class.plaintext_num(),
+            "The message exceeds the plaintext of the encryption class"
+        );
         let mut plaintext = msg.to_owned();
-        let padding = std::iter::repeat(pallas::Base::zero())
-            .take(RESOURCE_ENCRYPTION_PLAINTEXT_NUM - msg.len());
-        plaintext.extend(padding);
+        plaintext.resize(class.plaintext_num(), pallas::Base::zero());
         plaintext.into()
     }
+
+    /// The resource fields the receiver needs to spend it: logic, label,
+    /// value, quantity, nonce, npk, is_ephemeral and rseed.
+    pub fn from_resource(resource: &Resource) -> Self {
+        Self::padding(&[
+            resource.kind.logic,
+            resource.kind.label,
+            resource.value,
+            pallas::Base::from(resource.quantity),
+            resource.nonce.inner(),
+            resource.get_npk(),
+            pallas::Base::from(resource.is_ephemeral as u64),
+            *resource.rseed.expose_secret(),
+        ])
+    }
+
+    /// Reconstructs the resource, with its npk only. Fails if the plaintext is
+    /// not the encoding of a resource.
+    pub fn to_resource(&self) -> Option<Resource> {
+        let [logic, label, value, quantity, nonce, npk, is_ephemeral, rseed, ..] =
+            *self.0.as_slice()
+        else {
+            return None;
+        };
+        let quantity = quantity.to_repr();
+        if quantity[8..].iter().any(|b| *b != 0) {
+            return None;
+        }
+        let quantity = u64::from_le_bytes(quantity[..8].try_into().unwrap());
+        let is_ephemeral = if is_ephemeral == pallas::Base::zero() {
+            false
+        } else if is_ephemeral == pallas::Base::one() {
+            true
+        } else {
+            return None;
+        };
+        Some(Resource::from_full(
+            logic,
+            label,
+            value,
+            quantity.into(),
+            NullifierKeyContainer::from_npk(npk),
+            Nullifier::from(nonce),
+            is_ephemeral,
+            rseed,
+        ))
+    }
+}
+
+// Decodes a decrypted plaintext of any class
+fn decode_resource(plaintext: Vec<pallas::Base>) -> Option<Resource> {
+    EncryptionClass::from_plaintext_num(plaintext.len())?;
+    ResourcePlaintext(plaintext).to_resource()
+}
+
+/// Trial-decrypts the small resources encrypted to the receiver of `rcv_sk` in
+/// the public inputs of resource logics.
+pub fn scan_public_inputs<'a>(
+    public_inputs: impl IntoIterator<Item = &'a ResourceLogicPublicInputs>,
+    rcv_sk: pallas::Base,
+) -> Vec<Resource> {
+    public_inputs
+        .into_iter()
+        .filter_map(|public_inputs| public_inputs.decrypt(rcv_sk))
+        .filter_map(decode_resource)
+        .collect()
+}
+
+/// Trial-decrypts the resources of all the encryption classes encrypted to
+/// the receiver of `rcv_sk` by resource logics.
+pub fn scan_verifying_infos<'a>(
+    verifying_infos: impl IntoIterator<Item = &'a ResourceLogicVerifyingInfo>,
+    rcv_sk: pallas::Base,
+) -> Vec<Resource> {
+    verifying_infos
+        .into_iter()
+        .filter_map(|verifying_info| verifying_info.decrypt(rcv_sk))
+        .filter_map(decode_resource)
+        .collect()
+}
+
+/// Finds the resources created by the transaction for the receiver of
+/// `rcv_sk`. A resource published by several logics is returned once, and
+/// only if the transaction creates it.
+pub fn scan_transaction(tx: &Transaction, rcv_sk: pallas::Base) -> Vec<Resource> {
+    let mut output_cms = tx.get_output_cms();
+    scan_verifying_infos(tx.get_resource_logic_verifying_infos(), rcv_sk)
+        .into_iter()
+        .filter(|resource| {
+            // Remove the commitment so that duplicates are skipped
+            let cm = resource.commitment();
+            match output_cms.iter().position(|output_cm| *output_cm == cm) {
+                Some(position) => {
+                    output_cms.swap_remove(position);
+                    true
+                }
+                None => false,
+            }
+        })
+        .collect()
+}
+
+/// Like `scan_transaction`, along with the memo the sender attached to each
+/// resource, if any.
+pub fn scan_transaction_with_memos(
+    tx: &Transaction,
+    rcv_sk: pallas::Base,
+) -> Vec<(Resource, Option<Vec<u8>>)> {
+    let memo_sk = mod_r_p(rcv_sk);
+    scan_transaction(tx, rcv_sk)
+        .into_iter()
+        .map(|resource| {
+            let cm = resource.commitment();
+            let memo = tx
+                .get_memos()
+                .iter()
+                .filter(|memo| memo.output_cm() == cm)
+                .find_map(|memo| memo.open(&memo_sk));
+            (resource, memo)
+        })
+        .collect()
+}
+
+/// Scans the transactions, e.g. those of a block, in order.
+pub fn scan_transactions<'a>(
+    txs: impl IntoIterator<Item = &'a Transaction>,
+    rcv_sk: pallas::Base,
+) -> Vec<Resource> {
+    txs.into_iter()
+        .flat_map(|tx| scan_transaction(tx, rcv_sk))
+        .collect()
 }
 
 impl From<Vec<pallas::Base>> for ResourcePlaintext {
     fn from(input_vec: Vec<pallas::Base>) -> Self {
-        ResourcePlaintext(
-            input_vec
-                .try_into()
-                .expect("public input with incorrect length"),
-        )
+        assert!(
+            EncryptionClass::from_plaintext_num(input_vec.len()).is_some(),
+            "plaintext with incorrect length"
+        );
+        ResourcePlaintext(input_vec)
     }
 }
 
 impl SecretKey {
     pub fn from_dh_exchange(pk: &pallas::Point, sk: &pallas::Scalar) -> Self {
-        Self(pk * sk)
+        Self(Secret::new(pk * sk))
     }
 
     pub fn inner(&self) -> pallas::Point {
-        self.0
+        *self.0.expose_secret()
     }
 
     pub fn get_coordinates(&self) -> (pallas::Base, pallas::Base) {
-        let coordinates = self.0.to_affine().coordinates().unwrap();
+        let coordinates = self.inner().to_affine().coordinates().unwrap();
         (*coordinates.x(), *coordinates.y())
     }
 }
@@ -193,3 +894,329 @@ fn test_halo2_resource_encryption() {
     let decryption = cipher.decrypt(&key).unwrap();
     assert_eq!(plaintext.to_vec(), decryption);
 }
+
+#[test]
+fn test_encryption_classes() {
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    assert_eq!(EncryptionClass::fitting(8), Some(EncryptionClass::Small));
+    assert_eq!(
+        EncryptionClass::fitting(RESOURCE_ENCRYPTION_PLAINTEXT_NUM + 1),
+        Some(EncryptionClass::Medium)
+    );
+    assert_eq!(
+        EncryptionClass::fitting(RESOURCE_ENCRYPTION_LARGE_PLAINTEXT_NUM + 1),
+        None
+    );
+
+    let rcv_sk = pallas::Scalar::random(&mut rng);
+    let rcv_pk = GENERATOR.to_curve() * rcv_sk;
+    let sender_sk = pallas::Scalar::random(&mut rng);
+    let sender_pk = GENERATOR.to_curve() * sender_sk;
+    let key = SecretKey::from_dh_exchange(&rcv_pk, &sender_sk);
+    for class in EncryptionClass::ALL {
+        let plaintext = ResourcePlaintext::padding_to_class(&[pallas::Base::one()], class);
+        let cipher = ResourceCiphertext::encrypt(&plaintext, &key, &pallas::Base::random(&mut rng));
+        assert_eq!(cipher.inner().len(), class.ciphertext_num());
+
+        // The class and the sender pk are read back from the instance column
+        let instance = cipher.to_instance(&sender_pk);
+        let (read, read_pk) = ResourceCiphertext::from_instance(&instance).unwrap();
+        assert_eq!(read.class(), class);
+        let key = SecretKey::from_dh_exchange(&read_pk, &rcv_sk);
+        assert_eq!(read.decrypt(&key).unwrap(), plaintext.to_vec());
+
+        // Columns without the tag don't publish a ciphertext
+        let mut untagged = instance.clone();
+        untagged[0] += pallas::Base::one();
+        assert!(ResourceCiphertext::from_instance(&untagged).is_none());
+    }
+}
+
+#[test]
+fn test_halo2_payload_encryption() {
+    use ff::Field;
+    use group::Group;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let sk = pallas::Scalar::random(&mut rng);
+    let pk = pallas::Point::random(&mut rng);
+    let key = SecretKey::from_dh_exchange(&pk, &sk);
+    let encrypt_nonce = pallas::Base::random(&mut rng);
+    let payload: Vec<pallas::Base> = (0..25).map(|_| pallas::Base::random(&mut rng)).collect();
+
+    let cipher = PayloadCiphertext::encrypt(&payload, &key, &encrypt_nonce);
+    assert_eq!(cipher.inner().len(), payload.len() + 2);
+    assert_eq!(cipher.decrypt(&key).unwrap(), payload);
+
+    // Streaming odd-sized blocks gives the same ciphertext
+    let mut encryptor = PayloadEncryptor::new(&key, &encrypt_nonce, payload.len());
+    let mut streamed = vec![];
+    for block in payload.chunks(7) {
+        streamed.extend(encryptor.update(block));
+    }
+    streamed.extend(encryptor.finalize());
+    let streamed = PayloadCiphertext::from(streamed);
+    assert_eq!(streamed, cipher);
+    assert_eq!(streamed.commitment(), cipher.commitment());
+
+    // A tampered ciphertext fails the MAC and changes the commitment
+    let mut tampered = cipher.inner().to_vec();
+    tampered[3] += pallas::Base::one();
+    let tampered = PayloadCiphertext::from(tampered);
+    assert!(tampered.decrypt(&key).is_none());
+    assert_ne!(tampered.commitment(), cipher.commitment());
+}
+
+#[test]
+fn test_mailbox() {
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let rcv_sk = pallas::Scalar::random(&mut rng);
+    let rcv_pk = GENERATOR.to_curve() * rcv_sk;
+    let message = [pallas::Base::random(&mut rng), pallas::Base::one()];
+    let mailbox = Mailbox::seal(
+        &message,
+        &rcv_pk,
+        &pallas::Base::random(&mut rng),
+        &pallas::Base::random(&mut rng),
+    );
+    assert_eq!(
+        mailbox.ciphertext().inner().len(),
+        MAILBOX_PLAINTEXT_NUM + 2
+    );
+
+    let mut plaintext = mailbox.open(&rcv_sk).unwrap();
+    assert_eq!(plaintext.len(), MAILBOX_PLAINTEXT_NUM);
+    plaintext.truncate(message.len());
+    assert_eq!(plaintext, message);
+    assert!(mailbox.open(&pallas::Scalar::random(&mut rng)).is_none());
+}
+
+#[test]
+fn test_memo() {
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let ivk = pallas::Base::random(&mut rng);
+    let rcv_sk = mod_r_p(ivk);
+    let address = Address::derive(&ivk, Diversifier::random(&mut rng));
+    let output_cm = ResourceCommitment::from(pallas::Base::random(&mut rng));
+    let memo = Memo::seal(
+        b"invoice 42",
+        output_cm,
+        &address,
+        &pallas::Base::random(&mut rng),
+        &pallas::Base::random(&mut rng),
+    )
+    .unwrap();
+    assert!(memo.is_well_formed());
+    assert_eq!(memo.output_cm(), output_cm);
+
+    // The memo is padded to MEMO_SIZE bytes
+    let opened = memo.open(&rcv_sk).unwrap();
+    assert_eq!(opened.len(), MEMO_SIZE);
+    assert_eq!(&opened[..10], b"invoice 42");
+    assert!(opened[10..].iter().all(|b| *b == 0));
+    assert!(memo.open(&pallas::Scalar::random(&mut rng)).is_none());
+
+    let full = vec![0xab; MEMO_SIZE];
+    let sealed = Memo::seal(
+        &full,
+        output_cm,
+        &address,
+        &pallas::Base::one(),
+        &pallas::Base::one(),
+    );
+    assert_eq!(sealed.unwrap().open(&rcv_sk).unwrap(), full);
+    assert!(matches!(
+        Memo::seal(
+            &[0; MEMO_SIZE + 1],
+            output_cm,
+            &address,
+            &pallas::Base::one(),
+            &pallas::Base::one()
+        ),
+        Err(TransactionError::MemoTooLong)
+    ));
+}
+
+#[test]
+fn test_diversified_addresses() {
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let ivk = pallas::Base::random(&mut rng);
+    let address = Address::derive(&ivk, Diversifier::random(&mut rng));
+    let other_address = Address::derive(&ivk, Diversifier::random(&mut rng));
+    assert_ne!(address.pk_d(), other_address.pk_d());
+    assert_ne!(address.owner(), other_address.owner());
+
+    // Both addresses decrypt with ivk
+    let plaintext = ResourcePlaintext::padding(&[pallas::Base::one()]);
+    for address in [address, other_address] {
+        let (key, sender_pk) = address.dh_exchange(&pallas::Scalar::random(&mut rng));
+        let cipher = ResourceCiphertext::encrypt(&plaintext, &key, &pallas::Base::one());
+        let key = SecretKey::from_dh_exchange(&sender_pk, &mod_r_p(ivk));
+        assert_eq!(cipher.decrypt(&key).unwrap(), plaintext.to_vec());
+    }
+
+    assert_eq!(Address::from_bytes(&address.to_bytes()), Some(address));
+    let mut identity = address.to_bytes();
+    identity[11..].copy_from_slice(&pallas::Point::identity().to_bytes());
+    assert_eq!(Address::from_bytes(&identity), None);
+}
+
+#[test]
+fn test_scan_resources() {
+    use crate::constant::{
+        RESOURCE_ENCRYPTION_CIPHERTEXT_NUM, RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM,
+        RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PK_X_IDX,
+        RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PK_Y_IDX,
+        RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PUBLIC_INPUT_BEGIN_IDX,
+    };
+    use crate::resource::tests::random_resource;
+    use crate::transaction::{testing::create_shielded_ptx_bundle, TransparentPartialTxBundle};
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let rcv_sk = pallas::Base::random(&mut rng);
+    let rcv_pk = GENERATOR.to_curve() * mod_r_p(rcv_sk);
+    let random_public_inputs = || -> ResourceLogicPublicInputs {
+        (0..RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM)
+            .map(|_| pallas::Base::random(&mut OsRng))
+            .collect::<Vec<_>>()
+            .into()
+    };
+
+    // Encrypt the resource to the receiver as a resource logic does
+    let resource = random_resource(&mut rng);
+    let sender_sk = pallas::Base::random(&mut rng);
+    let key = SecretKey::from_dh_exchange(&rcv_pk, &mod_r_p(sender_sk));
+    let cipher = ResourceCiphertext::encrypt(
+        &ResourcePlaintext::from_resource(&resource),
+        &key,
+        &pallas::Base::random(&mut rng),
+    );
+    let sender_pk = (GENERATOR.to_curve() * mod_r_p(sender_sk))
+        .to_affine()
+        .coordinates()
+        .unwrap();
+    let mut public_inputs = random_public_inputs().to_vec();
+    public_inputs.splice(
+        RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PUBLIC_INPUT_BEGIN_IDX
+            ..RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PUBLIC_INPUT_BEGIN_IDX
+                + RESOURCE_ENCRYPTION_CIPHERTEXT_NUM,
+        cipher.inner().iter().copied(),
+    );
+    public_inputs[RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PK_X_IDX] = *sender_pk.x();
+    public_inputs[RESOURCE_LOGIC_CIRCUIT_RESOURCE_ENCRYPTION_PK_Y_IDX] = *sender_pk.y();
+    let public_inputs = ResourceLogicPublicInputs::from(public_inputs);
+
+    // Only the resource encrypted to the receiver is found
+    let found = scan_public_inputs([&random_public_inputs(), &public_inputs], rcv_sk);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].commitment(), resource.commitment());
+    assert_eq!(found[0].quantity, resource.quantity);
+    assert!(scan_public_inputs([&public_inputs], pallas::Base::random(&mut rng)).is_empty());
+
+    // The logics of the test transactions don't encrypt their resources
+    let tx = Transaction::build(
+        &mut rng,
+        create_shielded_ptx_bundle(1),
+        TransparentPartialTxBundle::default(),
+    )
+    .unwrap();
+    assert!(scan_transactions([&tx, &tx], rcv_sk).is_empty());
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{Address, Diversifier, Mailbox, Memo};
+    use crate::constant::{GENERATOR, MAILBOX_PLAINTEXT_NUM, MEMO_SIZE};
+    use crate::nullifier::tests::arb_base;
+    use crate::resource::ResourceCommitment;
+    use crate::utils::mod_r_p;
+    use group::cofactor::CofactorCurveAffine;
+    use proptest::prelude::*;
+
+    impl Arbitrary for Mailbox {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (
+                prop::collection::vec(arb_base(), 0..=MAILBOX_PLAINTEXT_NUM),
+                arb_base(),
+                arb_base(),
+                arb_base(),
+            )
+                .prop_map(|(message, rcv_sk, sender_sk, nonce)| {
+                    let rcv_pk = GENERATOR.to_curve() * mod_r_p(rcv_sk);
+                    Mailbox::seal(&message, &rcv_pk, &sender_sk, &nonce)
+                })
+                .boxed()
+        }
+    }
+
+    impl Arbitrary for Memo {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (
+                prop::collection::vec(any::<u8>(), 0..=MEMO_SIZE),
+                arb_base(),
+                arb_base(),
+                any::<[u8; 11]>(),
+                arb_base(),
+                arb_base(),
+            )
+                .prop_map(|(memo, output_cm, rcv_sk, diversifier, sender_sk, nonce)| {
+                    let address = Address::derive(&rcv_sk, Diversifier(diversifier));
+                    Memo::seal(
+                        &memo,
+                        ResourceCommitment::from(output_cm),
+                        &address,
+                        &sender_sk,
+                        &nonce,
+                    )
+                    .unwrap()
+                })
+                .boxed()
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_memo_borsh_round_trip(memo: Memo) {
+            let bytes = borsh::to_vec(&memo).unwrap();
+            prop_assert_eq!(borsh::from_slice::<Memo>(&bytes).unwrap(), memo);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_memo_serde_round_trip(memo: Memo) {
+            let json = serde_json::to_string(&memo).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Memo>(&json).unwrap(), memo);
+        }
+
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_mailbox_borsh_round_trip(mailbox: Mailbox) {
+            let bytes = borsh::to_vec(&mailbox).unwrap();
+            prop_assert_eq!(borsh::from_slice::<Mailbox>(&bytes).unwrap(), mailbox);
+        }
+    }
+}
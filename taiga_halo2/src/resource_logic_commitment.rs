@@ -1,4 +1,5 @@
 use crate::constant::RESOURCE_LOGIC_COMMITMENT_PERSONALIZATION;
+use crate::utils::to_le_bytes;
 use blake2s_simd::Params;
 use byteorder::{ByteOrder, LittleEndian};
 use ff::PrimeField;
@@ -7,9 +8,13 @@ use rustler::NifTuple;
 #[cfg(feature = "serde")]
 use serde;
 
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "nif", derive(NifTuple))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Vec<u8>"))]
 pub struct ResourceLogicCommitment(Vec<u8>);
 
 impl ResourceLogicCommitment {
@@ -18,8 +23,8 @@ impl ResourceLogicCommitment {
             .hash_length(32)
             .personal(RESOURCE_LOGIC_COMMITMENT_PERSONALIZATION)
             .to_state()
-            .update(resource_logic.to_repr().as_ref())
-            .update(rcm.to_repr().as_ref())
+            .update(&to_le_bytes(resource_logic))
+            .update(&to_le_bytes(rcm))
             .finalize();
         Self(hash.as_bytes().to_vec())
     }
@@ -34,8 +39,8 @@ impl ResourceLogicCommitment {
 
     pub fn from_public_inputs<F: PrimeField>(public_inputs: &[F; 2]) -> Self {
         let mut bytes: [u8; 32] = [0; 32];
-        bytes[0..16].copy_from_slice(&public_inputs[0].to_repr().as_ref()[0..16]);
-        bytes[16..32].copy_from_slice(&public_inputs[1].to_repr().as_ref()[0..16]);
+        bytes[0..16].copy_from_slice(&to_le_bytes(&public_inputs[0])[0..16]);
+        bytes[16..32].copy_from_slice(&to_le_bytes(&public_inputs[1])[0..16]);
         Self(bytes.to_vec())
     }
 
@@ -46,6 +51,32 @@ impl ResourceLogicCommitment {
     }
 }
 
+impl TryFrom<Vec<u8>> for ResourceLogicCommitment {
+    type Error = &'static str;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        if bytes.len() != 32 {
+            return Err("resource logic commitment is not 32 bytes");
+        }
+        Ok(Self(bytes))
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for ResourceLogicCommitment {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for ResourceLogicCommitment {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let bytes = <[u8; 32]>::deserialize_reader(reader)?;
+        Ok(Self::from_bytes(bytes))
+    }
+}
+
 impl Default for ResourceLogicCommitment {
     fn default() -> ResourceLogicCommitment {
         ResourceLogicCommitment([0u8; 32].to_vec())
@@ -1,3 +1,4 @@
+use crate::domains::RESOURCE_LOGIC_VK_COMPRESS_PERSONALIZATION;
 use blake2b_simd::Params as Blake2bParams;
 use halo2_proofs::plonk::VerifyingKey;
 use pasta_curves::{
@@ -35,7 +36,7 @@ impl ResourceLogicVerifyingKey {
             ResourceLogicVerifyingKey::Uncompressed(vk) => {
                 let mut hasher = Blake2bParams::new()
                     .hash_length(64)
-                    .personal(b"Halo2-Verify-Key")
+                    .personal(RESOURCE_LOGIC_VK_COMPRESS_PERSONALIZATION)
                     .to_state();
 
                 let s = format!("{:?}", vk.pinned());
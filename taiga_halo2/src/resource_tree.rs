@@ -1,23 +1,64 @@
 use crate::{
-    constant::{TAIGA_RESOURCE_TREE_DEPTH, TAIGA_RESOURCE_TREE_LEAVES_NUM},
-    merkle_tree::{MerklePath, Node, LR},
+    constant::{
+        TAIGA_COMMITMENT_TREE_DEPTH, TAIGA_RESOURCE_TREE_DEPTH, TAIGA_RESOURCE_TREE_LEAVES_NUM,
+    },
+    merkle_tree::{padded_tree_path, padded_tree_root, MerklePath, Node, LR},
     resource::Resource,
     utils::poseidon_hash,
 };
 #[cfg(feature = "borsh")]
 use borsh::{BorshDeserialize, BorshSerialize};
+use ff::PrimeField;
 use pasta_curves::pallas;
+use std::cmp::Ordering;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResourceExistenceWitness {
     resource: Resource,
     merkle_path: [(pallas::Base, LR); TAIGA_RESOURCE_TREE_DEPTH],
 }
 
+/// Witness that a value is absent from a sorted tree: the two adjacent leaves
+/// enclosing the value, together with their merkle paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResourceNonExistenceWitness {
+    value: pallas::Base,
+    low: (
+        pallas::Base,
+        [(pallas::Base, LR); TAIGA_COMMITMENT_TREE_DEPTH],
+    ),
+    high: (
+        pallas::Base,
+        [(pallas::Base, LR); TAIGA_COMMITMENT_TREE_DEPTH],
+    ),
+}
+
+/// Opens several leaves of a resource tree at once. The nodes shared by the
+/// openings are computed once, so the multiproof only holds the siblings that
+/// can't be derived from the opened leaves, in the order they are consumed.
+/// The positions are strictly increasing and in the tree, the opened leaves
+/// are given in the same order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResourceMultiproof {
+    /// The positions of the opened leaves
+    positions: Vec<usize>,
+    siblings: Vec<pallas::Base>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ResourceMerkleTreeLeaves(Vec<pallas::Base>);
 
+/// A sorted tree of the depth of the commitment tree, used for non-membership
+/// proofs, e.g. of the owners of a blacklist. The leaves are sorted and
+/// prefixed with zero, all the leaves after them are
+/// `sorted_resource_tree_upper_bound()`, so that every absent value lies
+/// between two adjacent leaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortedMerkleTree(Vec<pallas::Base>);
+
 impl ResourceExistenceWitness {
     pub fn new(
         resource: Resource,
@@ -30,7 +71,7 @@ impl ResourceExistenceWitness {
     }
 
     pub fn get_resource(&self) -> Resource {
-        self.resource
+        self.resource.clone()
     }
 
     pub fn get_path(&self) -> [(pallas::Base, LR); TAIGA_RESOURCE_TREE_DEPTH] {
@@ -70,7 +111,6 @@ impl ResourceExistenceWitness {
 impl BorshSerialize for ResourceExistenceWitness {
     fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
         use byteorder::WriteBytesExt;
-        use ff::PrimeField;
         self.resource.serialize(writer)?;
         for node in self.merkle_path {
             writer.write_all(&node.0.to_repr())?;
@@ -102,6 +142,232 @@ impl BorshDeserialize for ResourceExistenceWitness {
     }
 }
 
+#[cfg(feature = "borsh")]
+impl BorshSerialize for ResourceNonExistenceWitness {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        use byteorder::WriteBytesExt;
+        writer.write_all(&self.value.to_repr())?;
+        for (leaf, merkle_path) in [self.low, self.high] {
+            writer.write_all(&leaf.to_repr())?;
+            for node in merkle_path {
+                writer.write_all(&node.0.to_repr())?;
+                writer.write_u8(if node.1.is_left() { 1 } else { 0 })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for ResourceNonExistenceWitness {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        use crate::utils::read_base_field;
+        use byteorder::ReadBytesExt;
+        let value = read_base_field(reader)?;
+        let mut read_leaf = || -> std::io::Result<_> {
+            let leaf = read_base_field(reader)?;
+            let mut path = vec![];
+            for _ in 0..TAIGA_COMMITMENT_TREE_DEPTH {
+                let v = read_base_field(reader)?;
+                let byte = reader.read_u8()?;
+                let b = if byte == 0x01 { LR::L } else { LR::R };
+                path.push((v, b));
+            }
+            Ok((leaf, path.try_into().unwrap()))
+        };
+        let low = read_leaf()?;
+        let high = read_leaf()?;
+
+        Ok(Self { value, low, high })
+    }
+}
+
+impl ResourceNonExistenceWitness {
+    pub fn new(
+        value: pallas::Base,
+        low: (
+            pallas::Base,
+            [(pallas::Base, LR); TAIGA_COMMITMENT_TREE_DEPTH],
+        ),
+        high: (
+            pallas::Base,
+            [(pallas::Base, LR); TAIGA_COMMITMENT_TREE_DEPTH],
+        ),
+    ) -> Self {
+        Self { value, low, high }
+    }
+
+    pub fn get_value(&self) -> pallas::Base {
+        self.value
+    }
+
+    pub fn get_low_leaf(&self) -> pallas::Base {
+        self.low.0
+    }
+
+    pub fn get_low_path(&self) -> [(pallas::Base, LR); TAIGA_COMMITMENT_TREE_DEPTH] {
+        self.low.1
+    }
+
+    pub fn get_high_leaf(&self) -> pallas::Base {
+        self.high.0
+    }
+
+    pub fn get_high_path(&self) -> [(pallas::Base, LR); TAIGA_COMMITMENT_TREE_DEPTH] {
+        self.high.1
+    }
+
+    pub fn get_root(&self) -> pallas::Base {
+        MerklePath::from(self.low.1)
+            .root(Node::from(self.low.0))
+            .inner()
+    }
+
+    /// Checks the witness out of circuit, mirroring the non-membership gadget.
+    pub fn verify(&self, root: pallas::Base) -> bool {
+        let high_root = MerklePath::from(self.high.1)
+            .root(Node::from(self.high.0))
+            .inner();
+        self.get_root() == root
+            && high_root == root
+            && path_position(&self.high.1) == path_position(&self.low.1) + 1
+            && is_comparable(&self.value)
+            && is_comparable(&self.high.0)
+            && cmp_leaves(&self.low.0, &self.value) == Ordering::Less
+            && cmp_leaves(&self.value, &self.high.0) == Ordering::Less
+    }
+}
+
+impl ResourceMultiproof {
+    pub fn new(positions: Vec<usize>, siblings: Vec<pallas::Base>) -> Self {
+        Self {
+            positions,
+            siblings,
+        }
+    }
+
+    pub fn positions(&self) -> &[usize] {
+        &self.positions
+    }
+
+    pub fn siblings(&self) -> &[pallas::Base] {
+        &self.siblings
+    }
+
+    /// Computes the root from the opened leaves, given in the order of the
+    /// positions. Returns None if the positions are not strictly increasing or
+    /// out of the tree, or if the leaves or the siblings don't match them.
+    pub fn root(&self, leaves: &[pallas::Base]) -> Option<pallas::Base> {
+        let mut siblings = self.siblings.iter();
+        let root = fold_multiproof(
+            &self.positions,
+            leaves.to_vec(),
+            |_, _| siblings.next().copied().ok_or(()),
+            |left, right| Ok(poseidon_hash(left, right)),
+            || (),
+        )
+        .ok()?;
+        siblings.next().is_none().then_some(root)
+    }
+
+    pub fn verify(&self, leaves: &[pallas::Base], root: pallas::Base) -> bool {
+        self.root(leaves) == Some(root)
+    }
+
+    /// The number of hashes needed to compute the root.
+    pub fn hash_count(&self) -> usize {
+        let mut count = 0;
+        let _ = fold_multiproof(
+            &self.positions,
+            vec![(); self.positions.len()],
+            |_, _| Ok::<_, ()>(()),
+            |_, _| {
+                count += 1;
+                Ok(())
+            },
+            || (),
+        );
+        count
+    }
+}
+
+/// Walks the opened nodes up to the root. At each level, an opened node is
+/// combined with the next opened node if they are siblings, with the
+/// `sibling(level, position)` otherwise. Fails with `invalid()` unless the
+/// positions are strictly increasing, in the tree and match the leaves, and
+/// the walk ends at the single root.
+pub(crate) fn fold_multiproof<T, E>(
+    positions: &[usize],
+    leaves: Vec<T>,
+    mut sibling: impl FnMut(usize, usize) -> Result<T, E>,
+    mut hash: impl FnMut(T, T) -> Result<T, E>,
+    invalid: impl Fn() -> E,
+) -> Result<T, E> {
+    if positions.is_empty()
+        || positions.len() != leaves.len()
+        || positions.windows(2).any(|pair| pair[0] >= pair[1])
+        || positions[positions.len() - 1] >= TAIGA_RESOURCE_TREE_LEAVES_NUM
+    {
+        return Err(invalid());
+    }
+    let mut layer: Vec<(usize, T)> = positions.iter().copied().zip(leaves).collect();
+    for level in 0..TAIGA_RESOURCE_TREE_DEPTH {
+        let mut next_layer = Vec::with_capacity(layer.len());
+        let mut nodes = layer.into_iter().peekable();
+        while let Some((position, node)) = nodes.next() {
+            let (left, right) = if position % 2 == 0 {
+                match nodes.next_if(|(next, _)| *next == position + 1) {
+                    Some((_, right)) => (node, right),
+                    None => (node, sibling(level, position + 1)?),
+                }
+            } else {
+                (sibling(level, position - 1)?, node)
+            };
+            next_layer.push((position / 2, hash(left, right)?));
+        }
+        layer = next_layer;
+    }
+    match (layer.pop(), layer.is_empty()) {
+        (Some((0, root)), true) => Ok(root),
+        _ => Err(invalid()),
+    }
+}
+
+/// Hash of the ids of the resources owned by one logic invocation, published
+/// in place of the self resource id. The hash of a single id is the id itself,
+/// so logics owning one resource are unaffected.
+pub fn owned_resources_hash(ids: &[pallas::Base]) -> pallas::Base {
+    assert!(!ids.is_empty(), "A logic must own at least one resource");
+    ids[1..]
+        .iter()
+        .fold(ids[0], |hash, id| poseidon_hash(hash, *id))
+}
+
+/// The largest value a sorted tree can hold. It is also used to pad sorted
+/// trees, and values at or above it cannot be proven absent.
+pub fn sorted_resource_tree_upper_bound() -> pallas::Base {
+    pallas::Base::from_u128(1 << 127).square() - pallas::Base::one()
+}
+
+/// Orders leaves by their canonical integer representation.
+pub fn cmp_leaves(a: &pallas::Base, b: &pallas::Base) -> Ordering {
+    a.to_repr().iter().rev().cmp(b.to_repr().iter().rev())
+}
+
+// The non-membership gadget compares values as two 127-bit limbs, so only
+// values below 2^254 are comparable.
+fn is_comparable(value: &pallas::Base) -> bool {
+    value.to_repr()[31] < 0x40
+}
+
+fn path_position(path: &[(pallas::Base, LR)]) -> u64 {
+    path.iter()
+        .enumerate()
+        .map(|(i, (_, lr))| u64::from(lr.is_left()) << i)
+        .sum()
+}
+
 impl ResourceMerkleTreeLeaves {
     pub fn new(leaves: Vec<pallas::Base>) -> Self {
         assert!(
@@ -127,6 +393,46 @@ impl ResourceMerkleTreeLeaves {
         cur_layer[0]
     }
 
+    /// Generates the multiproof of the distinct `leaves`, given in the order of
+    /// the tree. Returns None if a leaf is not in the tree, is opened twice or
+    /// out of order.
+    pub fn generate_multiproof(&self, leaves: &[pallas::Base]) -> Option<ResourceMultiproof> {
+        let mut cur_layer = self.0.clone();
+        cur_layer.resize(TAIGA_RESOURCE_TREE_LEAVES_NUM, pallas::Base::zero());
+        let positions = leaves
+            .iter()
+            .map(|leaf| cur_layer.iter().position(|v| v == leaf))
+            .collect::<Option<Vec<_>>>()?;
+        if positions.is_empty() || positions.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return None;
+        }
+
+        let mut layers = vec![cur_layer];
+        while layers.last().unwrap().len() > 1 {
+            let next_layer = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| poseidon_hash(pair[0], pair[1]))
+                .collect();
+            layers.push(next_layer);
+        }
+
+        let mut siblings = vec![];
+        fold_multiproof(
+            &positions,
+            leaves.to_vec(),
+            |level, position| {
+                siblings.push(layers[level][position]);
+                Ok::<_, ()>(layers[level][position])
+            },
+            |left, right| Ok(poseidon_hash(left, right)),
+            || (),
+        )
+        .ok()?;
+        Some(ResourceMultiproof::new(positions, siblings))
+    }
+
     // Generate the merkle path for the current leave
     pub fn generate_path(
         &self,
@@ -168,6 +474,64 @@ impl ResourceMerkleTreeLeaves {
     }
 }
 
+impl SortedMerkleTree {
+    /// Builds the sorted tree of the `leaves`, in any order.
+    pub fn new(mut leaves: Vec<pallas::Base>) -> Self {
+        assert!(
+            leaves.len() as u64 + 2 <= 1u64 << TAIGA_COMMITMENT_TREE_DEPTH,
+            "The number of leaves exceeds the capacity of a sorted tree"
+        );
+        assert!(
+            leaves.iter().all(is_comparable),
+            "The leaves of a sorted tree must be less than 2^254"
+        );
+        leaves.sort_by(cmp_leaves);
+        leaves.insert(0, pallas::Base::zero());
+        leaves.push(sorted_resource_tree_upper_bound());
+        SortedMerkleTree(leaves)
+    }
+
+    fn nodes(&self) -> Vec<Node> {
+        self.0.iter().map(|leaf| Node::from(*leaf)).collect()
+    }
+
+    pub fn root(&self) -> pallas::Base {
+        padded_tree_root(
+            &self.nodes(),
+            Node::from(sorted_resource_tree_upper_bound()),
+        )
+        .inner()
+    }
+
+    /// Generates the non-membership witness of `value`. Returns None if the
+    /// value is in the tree or out of range.
+    pub fn generate_non_membership_witness(
+        &self,
+        value: pallas::Base,
+    ) -> Option<ResourceNonExistenceWitness> {
+        if !is_comparable(&value) {
+            return None;
+        }
+        let position = self.0.windows(2).position(|pair| {
+            cmp_leaves(&pair[0], &value) == Ordering::Less
+                && cmp_leaves(&value, &pair[1]) == Ordering::Less
+        })?;
+        let nodes = self.nodes();
+        let padding = Node::from(sorted_resource_tree_upper_bound());
+        let path = |position: usize| -> Option<[(pallas::Base, LR); TAIGA_COMMITMENT_TREE_DEPTH]> {
+            padded_tree_path(&nodes, padding, position)?
+                .inner()
+                .try_into()
+                .ok()
+        };
+        Some(ResourceNonExistenceWitness::new(
+            value,
+            (self.0[position], path(position)?),
+            (self.0[position + 1], path(position + 1)?),
+        ))
+    }
+}
+
 #[test]
 fn test_resource_merkle_leave() {
     use crate::merkle_tree::{MerklePath, Node};
@@ -193,3 +557,83 @@ fn test_resource_merkle_leave() {
 
     assert_eq!(merkle_root, expected_root.inner());
 }
+
+#[test]
+fn test_sorted_merkle_tree_non_membership() {
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let leaves: Vec<pallas::Base> = (0..4)
+        .map(|_| pallas::Base::random(&mut rng))
+        .filter(is_comparable)
+        .collect();
+    let tree = SortedMerkleTree::new(leaves.clone());
+    let root = tree.root();
+
+    for leaf in leaves.iter() {
+        assert!(tree.generate_non_membership_witness(*leaf).is_none());
+    }
+    assert!(tree
+        .generate_non_membership_witness(sorted_resource_tree_upper_bound())
+        .is_none());
+
+    let value = pallas::Base::one();
+    let witness = tree.generate_non_membership_witness(value).unwrap();
+    assert!(witness.verify(root));
+    assert_eq!(witness.get_root(), root);
+
+    // A witness for an existing leaf must not verify
+    let fake_witness = ResourceNonExistenceWitness::new(
+        witness.get_low_leaf(),
+        (witness.get_low_leaf(), witness.get_low_path()),
+        (witness.get_high_leaf(), witness.get_high_path()),
+    );
+    assert!(!fake_witness.verify(root));
+}
+
+#[test]
+fn test_resource_multiproof() {
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let leaves: Vec<pallas::Base> = (0..8).map(|_| pallas::Base::random(&mut rng)).collect();
+    let tree = ResourceMerkleTreeLeaves::new(leaves.clone());
+    let root = tree.root();
+
+    // Leaves 0 and 1 share all their nodes but the first level
+    let opened = [leaves[0], leaves[1], leaves[5]];
+    let multiproof = tree.generate_multiproof(&opened).unwrap();
+    assert_eq!(multiproof.positions(), &[0, 1, 5]);
+    assert!(multiproof.verify(&opened, root));
+    assert!(multiproof.hash_count() < opened.len() * TAIGA_RESOURCE_TREE_DEPTH);
+    assert!(multiproof.siblings().len() < opened.len() * TAIGA_RESOURCE_TREE_DEPTH);
+
+    // The multiproof of a single leaf is its merkle path
+    let single = tree.generate_multiproof(&[leaves[3]]).unwrap();
+    let path = tree.generate_path(leaves[3]).unwrap();
+    assert_eq!(
+        single.siblings(),
+        path.iter().map(|(sibling, _)| *sibling).collect::<Vec<_>>()
+    );
+    assert_eq!(single.hash_count(), TAIGA_RESOURCE_TREE_DEPTH);
+
+    assert!(!multiproof.verify(&[leaves[1], leaves[0], leaves[5]], root));
+    assert!(tree.generate_multiproof(&[leaves[0], leaves[0]]).is_none());
+    assert!(tree.generate_multiproof(&[leaves[1], leaves[0]]).is_none());
+
+    // The positions must be strictly increasing and in the tree, so that no
+    // opening is dropped or counted twice
+    let forged = |positions: Vec<usize>| {
+        ResourceMultiproof::new(positions, multiproof.siblings().to_vec()).verify(&opened, root)
+    };
+    assert!(forged(vec![0, 1, 5]));
+    assert!(!forged(vec![1, 0, 5]));
+    assert!(!forged(vec![0, 0, 5]));
+    assert!(!forged(vec![0, 1, 5 + TAIGA_RESOURCE_TREE_LEAVES_NUM]));
+    assert!(!ResourceMultiproof::default().verify(&[], root));
+    assert!(tree
+        .generate_multiproof(&[pallas::Base::random(&mut rng)])
+        .is_none());
+}
@@ -0,0 +1,386 @@
+//! A JSON-RPC 2.0 service exposing the Taiga API, so that clients written in
+//! other languages, e.g. the Anoma node or wallets, use Taiga without FFI.
+//!
+//! Requests and responses are newline-delimited JSON objects over TCP. Binary
+//! values are hex strings: ptxs, compliances and logics are borsh encoded,
+//! transactions use the canonical encoding of `Transaction::to_bytes`.
+//!
+//! | method    | params                                               | result                 |
+//! |   -       |   -                                                  |   -                    |
+//! | `prove`   | `compliances`, `input_apps`, `output_apps`, `hints`  | `ptx`                  |
+//! | `compose` | `ptxs`                                               | `tx`, `txid`           |
+//! | `verify`  | `tx`                                                 | `txid`, `result`       |
+//! | `decrypt` | `tx`, `viewing_key`                                  | `resources`            |
+//!
+//! The service has neither authentication nor TLS, and `decrypt` is sent the
+//! viewing keys, so it only listens on loopback addresses. Remote clients go
+//! through a proxy terminating TLS.
+pub use crate::utils::{from_hex, to_hex};
+use crate::{
+    circuit::resource_logic_bytecode::ApplicationByteCode,
+    compliance::ComplianceInfo,
+    error::TransactionError,
+    resource_encryption::scan_transaction,
+    shielded_ptx::ShieldedPartialTransaction,
+    taiga_api::{create_shielded_partial_transaction, create_transaction},
+    transaction::Transaction,
+};
+use borsh::BorshDeserialize;
+use pasta_curves::pallas;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+// JSON-RPC 2.0 error codes
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// The transaction is rejected, the message is the `TransactionError`.
+pub const TRANSACTION_ERROR: i64 = -32000;
+
+/// The default number of connections served at once, see `RpcService::serve`.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 16;
+/// The default max length of a request line, in bytes.
+pub const DEFAULT_MAX_REQUEST_LEN: usize = 16 << 20;
+// A connection idle for longer is closed, so that it doesn't hold a thread
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn invalid_params(message: &str) -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<TransactionError> for RpcError {
+    fn from(e: TransactionError) -> Self {
+        Self {
+            code: TRANSACTION_ERROR,
+            message: e.to_string(),
+        }
+    }
+}
+
+fn hex_param(params: &Value, name: &str) -> Result<Vec<u8>, RpcError> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .and_then(from_hex)
+        .ok_or_else(|| RpcError::invalid_params(&format!("{name} is not a hex string")))
+}
+
+fn borsh_param<T: BorshDeserialize>(params: &Value, name: &str) -> Result<T, RpcError> {
+    borsh::from_slice(&hex_param(params, name)?)
+        .map_err(|_| RpcError::invalid_params(&format!("{name} can't be decoded")))
+}
+
+fn tx_param(params: &Value) -> Result<Transaction, RpcError> {
+    Transaction::from_bytes(&hex_param(params, "tx")?).map_err(RpcError::from)
+}
+
+/// The Taiga API as JSON-RPC methods. The service is stateless, the ledger
+/// checks are left to the caller.
+#[derive(Debug, Clone, Copy)]
+pub struct RpcService {
+    max_connections: usize,
+    max_request_len: usize,
+}
+
+impl Default for RpcService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RpcService {
+    pub fn new() -> Self {
+        Self {
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            max_request_len: DEFAULT_MAX_REQUEST_LEN,
+        }
+    }
+
+    /// Sets the number of connections served at once, at least one.
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections.max(1);
+        self
+    }
+
+    /// Sets the max length of a request line, in bytes.
+    pub fn with_max_request_len(mut self, max_request_len: usize) -> Self {
+        self.max_request_len = max_request_len;
+        self
+    }
+
+    /// Runs a method on its params.
+    pub fn call(&self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "prove" => {
+                let compliances: Vec<ComplianceInfo> = borsh_param(params, "compliances")?;
+                let input_apps: Vec<ApplicationByteCode> = borsh_param(params, "input_apps")?;
+                let output_apps: Vec<ApplicationByteCode> = borsh_param(params, "output_apps")?;
+                let hints = match params.get("hints") {
+                    Some(_) => hex_param(params, "hints")?,
+                    None => vec![],
+                };
+                if compliances.len() != input_apps.len() || compliances.len() != output_apps.len() {
+                    return Err(RpcError::invalid_params(
+                        "every compliance needs an input and an output application",
+                    ));
+                }
+                let ptx = create_shielded_partial_transaction(
+                    compliances,
+                    input_apps,
+                    output_apps,
+                    hints,
+                )?;
+                Ok(json!({ "ptx": to_hex(&borsh::to_vec(&ptx).unwrap()) }))
+            }
+            "compose" => {
+                let ptxs = params
+                    .get("ptxs")
+                    .and_then(Value::as_array)
+                    .filter(|ptxs| !ptxs.is_empty())
+                    .ok_or_else(|| RpcError::invalid_params("ptxs is not a non-empty list"))?
+                    .iter()
+                    .map(|ptx| {
+                        ptx.as_str()
+                            .and_then(from_hex)
+                            .and_then(|bytes| {
+                                ShieldedPartialTransaction::try_from_slice(&bytes).ok()
+                            })
+                            .ok_or_else(|| RpcError::invalid_params("ptxs can't be decoded"))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let tx = create_transaction(ptxs)?;
+                Ok(json!({
+                    "tx": to_hex(&tx.to_bytes()),
+                    "txid": tx.txid().to_string(),
+                }))
+            }
+            "verify" => {
+                let tx = tx_param(params)?;
+                let result = tx.execute()?;
+                Ok(json!({
+                    "txid": tx.txid().to_string(),
+                    "result": serde_json::to_value(result).unwrap(),
+                }))
+            }
+            "decrypt" => {
+                let tx = tx_param(params)?;
+                let viewing_key: pallas::Base =
+                    borsh_param(params, "viewing_key").and_then(|bytes: [u8; 32]| {
+                        crate::utils::from_le_bytes(&bytes)
+                            .ok_or_else(|| RpcError::invalid_params("viewing_key is not canonical"))
+                    })?;
+                let resources: Vec<String> = scan_transaction(&tx, viewing_key)
+                    .iter()
+                    .map(|resource| to_hex(&borsh::to_vec(resource).unwrap()))
+                    .collect();
+                Ok(json!({ "resources": resources }))
+            }
+            _ => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("unknown method {method}"),
+            }),
+        }
+    }
+
+    /// Handles a JSON-RPC request and returns the response.
+    pub fn handle(&self, request: &str) -> String {
+        let request: Value = match serde_json::from_str(request) {
+            Ok(request) => request,
+            Err(e) => {
+                return response(
+                    Value::Null,
+                    Err(RpcError {
+                        code: PARSE_ERROR,
+                        message: e.to_string(),
+                    }),
+                )
+            }
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let result = match request.get("method").and_then(Value::as_str) {
+            Some(method) => self.call(method, request.get("params").unwrap_or(&Value::Null)),
+            None => Err(RpcError {
+                code: INVALID_REQUEST,
+                message: "method is missing".to_string(),
+            }),
+        };
+        response(id, result)
+    }
+
+    /// Serves the requests of a connection, one per line, until it closes. A
+    /// request longer than the max length is answered with an error and
+    /// closes the connection.
+    pub fn serve_connection(&self, stream: TcpStream) -> std::io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        let mut reader = BufReader::new(stream);
+        let limit = self.max_request_len as u64 + 1;
+        let mut line = vec![];
+        loop {
+            line.clear();
+            let len = (&mut reader).take(limit).read_until(b'\n', &mut line)?;
+            if len == 0 {
+                return Ok(());
+            }
+            if len as u64 == limit && line.last() != Some(&b'\n') {
+                let error = RpcError {
+                    code: INVALID_REQUEST,
+                    message: format!("request longer than {} bytes", self.max_request_len),
+                };
+                return writeln!(writer, "{}", response(Value::Null, Err(error)));
+            }
+            let request = String::from_utf8_lossy(&line);
+            if request.trim().is_empty() {
+                continue;
+            }
+            writeln!(writer, "{}", self.handle(&request))?;
+        }
+    }
+
+    /// Listens on `addr` and serves the connections on `max_connections`
+    /// threads, the other connections wait to be served. Only the loopback
+    /// addresses are served, see the module docs.
+    pub fn serve(&self, addr: impl ToSocketAddrs) -> std::io::Result<()> {
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        if addrs.is_empty() || addrs.iter().any(|addr| !addr.ip().is_loopback()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "the service only listens on loopback addresses",
+            ));
+        }
+        let listener = TcpListener::bind(addrs.as_slice())?;
+
+        let (sender, receiver) = mpsc::sync_channel::<TcpStream>(self.max_connections);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..self.max_connections {
+            let service = *self;
+            let receiver = receiver.clone();
+            std::thread::spawn(move || loop {
+                let stream = match receiver.lock().expect("rpc lock poisoned").recv() {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                };
+                // A failed connection doesn't stop the worker
+                let _ = stream
+                    .set_read_timeout(Some(READ_TIMEOUT))
+                    .and_then(|_| service.serve_connection(stream));
+            });
+        }
+        for stream in listener.incoming() {
+            sender.send(stream?).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::Other, "the rpc workers stopped")
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> String {
+    let response = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": e.code, "message": e.message },
+        }),
+    };
+    response.to_string()
+}
+
+#[test]
+fn test_rpc_service() {
+    use crate::shielded_ptx::testing::create_shielded_ptx;
+
+    let service = RpcService::new();
+    let call = |request: Value| -> Value {
+        serde_json::from_str(&service.handle(&request.to_string())).unwrap()
+    };
+
+    // Compose a transaction from a ptx and verify it
+    let ptx = to_hex(&borsh::to_vec(&create_shielded_ptx()).unwrap());
+    let composed = call(json!({
+        "jsonrpc": "2.0", "id": 1, "method": "compose", "params": { "ptxs": [ptx] },
+    }));
+    assert_eq!(composed["id"], 1);
+    let tx = composed["result"]["tx"].clone();
+    let verified = call(json!({
+        "jsonrpc": "2.0", "id": 2, "method": "verify", "params": { "tx": tx },
+    }));
+    assert_eq!(verified["result"]["txid"], composed["result"]["txid"]);
+    assert_eq!(
+        verified["result"]["result"]["nullifiers"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // Nothing is encrypted to a random viewing key
+    let decrypted = call(json!({
+        "jsonrpc": "2.0", "id": 3, "method": "decrypt",
+        "params": { "tx": tx, "viewing_key": to_hex(&[7; 32]) },
+    }));
+    assert_eq!(decrypted["result"]["resources"], json!([]));
+
+    // Errors
+    assert_eq!(
+        service.call("mint", &Value::Null).unwrap_err().code,
+        METHOD_NOT_FOUND
+    );
+    let invalid = call(json!({
+        "jsonrpc": "2.0", "id": 4, "method": "verify", "params": { "tx": "0g" },
+    }));
+    assert_eq!(invalid["error"]["code"], INVALID_PARAMS);
+    let rejected = call(json!({
+        "jsonrpc": "2.0", "id": 5, "method": "verify", "params": { "tx": "00" },
+    }));
+    assert_eq!(rejected["error"]["code"], TRANSACTION_ERROR);
+    let unparsed: Value = serde_json::from_str(&service.handle("{")).unwrap();
+    assert_eq!(unparsed["error"]["code"], PARSE_ERROR);
+    assert_eq!(from_hex("0a0B"), Some(vec![10, 11]));
+}
+
+#[test]
+fn test_rpc_connection_bounds() {
+    // Only the loopback addresses are served
+    let service = RpcService::new().with_max_request_len(64);
+    let error = service.serve("0.0.0.0:0").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        service.serve_connection(stream)
+    });
+    let mut client = TcpStream::connect(addr).unwrap();
+    let mut responses = BufReader::new(client.try_clone().unwrap()).lines();
+
+    // A request up to the max length is served
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "mint" }).to_string();
+    assert!(request.len() <= 64);
+    writeln!(client, "{request}").unwrap();
+    let served: Value = serde_json::from_str(&responses.next().unwrap().unwrap()).unwrap();
+    assert_eq!(served["error"]["code"], METHOD_NOT_FOUND);
+
+    // A longer one closes the connection
+    write!(client, "{}", " ".repeat(65)).unwrap();
+    let rejected: Value = serde_json::from_str(&responses.next().unwrap().unwrap()).unwrap();
+    assert_eq!(rejected["error"]["code"], INVALID_REQUEST);
+    assert!(server.join().unwrap().is_ok());
+    assert!(responses.next().is_none());
+}
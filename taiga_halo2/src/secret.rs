@@ -0,0 +1,121 @@
+//! Secret material: nullifier keys, random seeds and encryption keys.
+//!
+//! [`Secret`] holds a secret value, zeroizes it on drop, compares it in
+//! constant time and doesn't print it. The wallet keys return the nullifier
+//! key wrapped in it, and the random seeds of the compliances and the logics
+//! are zeroized on drop too. A resource holds its nullifier key container and
+//! its seed in a `Secret`, so it is not `Copy`: its clones are zeroized on
+//! drop like the original.
+use std::fmt;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{DefaultIsZeroes, Zeroize, ZeroizeOnDrop};
+
+#[cfg(feature = "nif")]
+use rustler::{Decoder, Encoder, Env, NifResult, Term};
+
+// Zeroized by overwriting it with the default value
+#[derive(Copy, Clone, Default)]
+struct Zeroizable<T>(T);
+
+impl<T: Copy + Default> DefaultIsZeroes for Zeroizable<T> {}
+
+/// A secret value, zeroized on drop.
+#[derive(Clone, Default)]
+pub struct Secret<T: Copy + Default>(Zeroizable<T>);
+
+impl<T: Copy + Default> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(Zeroizable(value))
+    }
+
+    /// The secret value. The copies of the value are not zeroized.
+    pub fn expose_secret(&self) -> &T {
+        &self.0 .0
+    }
+}
+
+impl<T: Copy + Default> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Copy + Default + ConstantTimeEq> ConstantTimeEq for Secret<T> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.expose_secret().ct_eq(other.expose_secret())
+    }
+}
+
+impl<T: Copy + Default + ConstantTimeEq> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<T: Copy + Default + ConstantTimeEq> Eq for Secret<T> {}
+
+impl<T: Copy + Default> Zeroize for Secret<T> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Copy + Default> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<T: Copy + Default> ZeroizeOnDrop for Secret<T> {}
+
+impl<T: Copy + Default> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+// Serialized as the value itself
+#[cfg(feature = "serde")]
+impl<T: Copy + Default + serde::Serialize> serde::Serialize for Secret<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.expose_secret().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Copy + Default + serde::Deserialize<'de>> serde::Deserialize<'de> for Secret<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(feature = "nif")]
+impl<T: Copy + Default + Encoder> Encoder for Secret<T> {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        self.expose_secret().encode(env)
+    }
+}
+
+#[cfg(feature = "nif")]
+impl<'a, T: Copy + Default + Decoder<'a>> Decoder<'a> for Secret<T> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        T::decode(term).map(Self::new)
+    }
+}
+
+#[test]
+fn test_secret() {
+    use ff::Field;
+    use pasta_curves::pallas;
+    use rand::rngs::OsRng;
+
+    let value = pallas::Base::random(&mut OsRng);
+    let mut secret = Secret::new(value);
+    assert_eq!(*secret.expose_secret(), value);
+    assert_eq!(secret, Secret::from(value));
+    assert_ne!(secret, Secret::new(value + pallas::Base::one()));
+    assert_eq!(format!("{secret:?}"), "Secret(..)");
+
+    secret.zeroize();
+    assert_eq!(*secret.expose_secret(), pallas::Base::zero());
+}
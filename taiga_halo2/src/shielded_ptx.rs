@@ -1,19 +1,32 @@
-use crate::circuit::resource_logic_circuit::{ResourceLogic, ResourceLogicVerifyingInfo};
-use crate::compliance::{ComplianceInfo, CompliancePublicInputs};
+use crate::auxiliary_delta::AuxiliaryDelta;
+use crate::circuit::compliance_circuit::ComplianceCircuit;
+use crate::circuit::resource_logic_circuit::{
+    ResourceLogic, ResourceLogicPublicInputs, ResourceLogicVerifyingInfo,
+};
+use crate::compliance::{ComplianceInfo, CompliancePublicInputs, ReadInfo, ReadPublicInputs};
 use crate::constant::{
-    COMPLIANCE_CIRCUIT_PARAMS_SIZE, COMPLIANCE_PROVING_KEY, COMPLIANCE_VERIFYING_KEY,
-    MAX_DYNAMIC_RESOURCE_LOGIC_NUM, SETUP_PARAMS_MAP,
+    COMPLIANCE_CIRCUIT_PARAMS_SIZE, MAX_DYNAMIC_RESOURCE_LOGIC_NUM, MAX_PTX_COMPLIANCE_NUM,
+    READ_CIRCUIT_PARAMS_SIZE,
 };
-use crate::delta_commitment::DeltaCommitment;
+use crate::conversion::ConversionRuleId;
+use crate::delta_commitment::{BalanceSummary, DeltaCommitment};
 use crate::error::TransactionError;
 use crate::executable::Executable;
 use crate::merkle_tree::Anchor;
 use crate::nullifier::Nullifier;
-use crate::proof::Proof;
+use crate::params::{ProvingContext, VerifyingContext};
+use crate::proof::{BatchVerifier, Proof};
 use crate::resource::{ResourceCommitment, ResourceLogics};
+use crate::resource_logic_vk::ResourceLogicVerifyingKey;
+use crate::resource_tree::owned_resources_hash;
+use crate::witness_set::WitnessSet;
 use halo2_proofs::plonk::Error;
 use pasta_curves::pallas;
-use rand::RngCore;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::collections::HashSet;
+use std::ops::Deref;
+use std::sync::Mutex;
 
 #[cfg(feature = "nif")]
 use rustler::NifStruct;
@@ -23,6 +36,8 @@ use serde;
 
 use crate::circuit::resource_logic_bytecode::ApplicationByteCode;
 #[cfg(feature = "borsh")]
+use crate::transaction::{EncodingLayout, VersionedBorsh};
+#[cfg(feature = "borsh")]
 use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(feature = "borsh")]
 use ff::PrimeField;
@@ -35,6 +50,8 @@ pub struct ShieldedPartialTransaction {
     compliances: Vec<ComplianceVerifyingInfo>,
     inputs: Vec<ResourceLogicVerifyingInfoSet>,
     outputs: Vec<ResourceLogicVerifyingInfoSet>,
+    // The resources read by the ptx, see `ReadInfo`
+    reads: Vec<ReadVerifyingInfo>,
     binding_sig_r: Option<pallas::Scalar>,
     hints: Vec<u8>,
 }
@@ -50,13 +67,25 @@ pub struct ComplianceVerifyingInfo {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "nif", derive(NifStruct))]
+#[cfg_attr(feature = "nif", module = "Taiga.Read.VerifyingInfo")]
 #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReadVerifyingInfo {
+    read_proof: Proof,
+    read_instance: ReadPublicInputs,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "nif", derive(NifStruct))]
 #[cfg_attr(feature = "nif", module = "Taiga.Resource.VerifyingInfo")]
 pub struct ResourceLogicVerifyingInfoSet {
     app_resource_logic_verifying_info: ResourceLogicVerifyingInfo,
     app_dynamic_resource_logic_verifying_info: Vec<ResourceLogicVerifyingInfo>,
+    // The ids of the resources owned by the logics. Empty means the logics only
+    // own the resource identified by their self resource id.
+    owned_resource_ids: Vec<pallas::Base>,
     // TODO function privacy: add verifier proof and the corresponding public inputs.
     // When the verifier proof is added, we may need to reconsider the structure of `ResourceLogicVerifyingInfo`
 }
@@ -67,29 +96,103 @@ impl ShieldedPartialTransaction {
         input_resource_app: Vec<ApplicationByteCode>,
         output_resource_app: Vec<ApplicationByteCode>,
         hints: Vec<u8>,
+        rng: R,
+    ) -> Result<Self, TransactionError> {
+        Self::from_bytecode_with_threads(
+            compliances,
+            input_resource_app,
+            output_resource_app,
+            hints,
+            1,
+            rng,
+        )
+    }
+
+    /// Same as `from_bytecode`, with the compliance proofs and the resource
+    /// logic proofs generated concurrently on up to `threads` threads. Zero or
+    /// one thread proves serially on the current thread.
+    pub fn from_bytecode_with_threads<R: RngCore>(
+        compliances: Vec<ComplianceInfo>,
+        input_resource_app: Vec<ApplicationByteCode>,
+        output_resource_app: Vec<ApplicationByteCode>,
+        hints: Vec<u8>,
+        threads: usize,
+        rng: R,
+    ) -> Result<Self, TransactionError> {
+        Self::from_bytecode_with_context(
+            compliances,
+            input_resource_app,
+            output_resource_app,
+            hints,
+            &ProvingContext::default(),
+            threads,
+            rng,
+        )
+    }
+
+    /// Same as `from_bytecode_with_threads`, with the params and the keys of
+    /// `context`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "ptx.prove", skip_all, fields(threads = threads))
+    )]
+    pub fn from_bytecode_with_context<R: RngCore>(
+        compliances: Vec<ComplianceInfo>,
+        input_resource_app: Vec<ApplicationByteCode>,
+        output_resource_app: Vec<ApplicationByteCode>,
+        hints: Vec<u8>,
+        context: &ProvingContext,
+        threads: usize,
         mut rng: R,
     ) -> Result<Self, TransactionError> {
-        let inputs: Result<Vec<_>, _> = input_resource_app
-            .into_iter()
-            .map(|bytecode| bytecode.generate_proofs())
-            .collect();
-        let outputs: Result<Vec<_>, _> = output_resource_app
-            .into_iter()
-            .map(|bytecode| bytecode.generate_proofs())
-            .collect();
-        let mut rcv_sum = pallas::Scalar::zero();
-        let compliances: Vec<ComplianceVerifyingInfo> = compliances
+        let rcv_sum = compliances
+            .iter()
+            .fold(pallas::Scalar::zero(), |sum, compliance_info| {
+                sum + compliance_info.get_rcv()
+            });
+        let compliance_num = compliances.len();
+        let input_num = input_resource_app.len();
+
+        // The caller's RNG can't be shared between threads, every proof gets
+        // its own RNG seeded from it in the order of the jobs, so that a seeded
+        // caller's RNG gives the same proofs on any number of threads.
+        let mut job_rng = || {
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
+            ChaCha20Rng::from_seed(seed)
+        };
+        let mut jobs: Vec<ProvingJob> = compliances
             .iter()
             .map(|compliance_info| {
-                rcv_sum += compliance_info.get_rcv();
-                ComplianceVerifyingInfo::create(compliance_info, &mut rng).unwrap()
+                let (compliance_instance, circuit) = compliance_info.build();
+                ProvingJob::Compliance(compliance_instance, circuit, job_rng())
             })
             .collect();
+        for app in input_resource_app.into_iter().chain(output_resource_app) {
+            jobs.push(ProvingJob::Application(app, job_rng()));
+        }
+
+        let mut outputs = parallel_map(jobs, threads, |job| job.prove(context))
+            .into_iter()
+            .collect::<Result<Vec<_>, TransactionError>>()?
+            .into_iter();
+        let compliances = outputs
+            .by_ref()
+            .take(compliance_num)
+            .map(ProvingOutput::into_compliance)
+            .collect();
+        let inputs = outputs
+            .by_ref()
+            .take(input_num)
+            .map(ProvingOutput::into_application)
+            .collect();
+        let outputs = outputs.map(ProvingOutput::into_application).collect();
 
         Ok(Self {
             compliances,
-            inputs: inputs?,
-            outputs: outputs?,
+            inputs,
+            outputs,
+            reads: vec![],
             binding_sig_r: Some(rcv_sum),
             hints,
         })
@@ -100,58 +203,193 @@ impl ShieldedPartialTransaction {
         input_resource_resource_logics: Vec<ResourceLogics>,
         output_resource_resource_logics: Vec<ResourceLogics>,
         hints: Vec<u8>,
+        rng: R,
+    ) -> Result<Self, TransactionError> {
+        let mut witnesses = WitnessSet::from_compliances(&compliance_pairs);
+        Self::build_with_witnesses(
+            compliance_pairs,
+            input_resource_resource_logics,
+            output_resource_resource_logics,
+            hints,
+            &mut witnesses,
+            rng,
+        )
+    }
+
+    /// Same as `build`, with the witnesses the resource logics were built
+    /// from, see `WitnessSet::existence_witness`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "ptx.prove", skip_all))]
+    pub fn build_with_witnesses<R: RngCore>(
+        compliance_pairs: Vec<ComplianceInfo>,
+        input_resource_resource_logics: Vec<ResourceLogics>,
+        output_resource_resource_logics: Vec<ResourceLogics>,
+        hints: Vec<u8>,
+        witnesses: &mut WitnessSet,
         mut rng: R,
-    ) -> Result<Self, Error> {
+    ) -> Result<Self, TransactionError> {
         // Generate compliance proofs
         let mut rcv_sum = pallas::Scalar::zero();
         let compliances: Vec<ComplianceVerifyingInfo> = compliance_pairs
             .iter()
             .map(|compliance_info| {
                 rcv_sum += compliance_info.get_rcv();
-                ComplianceVerifyingInfo::create(compliance_info, &mut rng).unwrap()
+                ComplianceVerifyingInfo::create_with_witnesses(compliance_info, witnesses, &mut rng)
             })
-            .collect();
+            .collect::<Result<_, Error>>()?;
 
         // Generate input resource logic proofs
         let inputs: Vec<ResourceLogicVerifyingInfoSet> = input_resource_resource_logics
             .iter()
-            .map(|input_resource_resource_logic| input_resource_resource_logic.build())
-            .collect();
+            .map(|input_resource_resource_logic| {
+                input_resource_resource_logic.build_with_rng(&mut rng)
+            })
+            .collect::<Result<_, TransactionError>>()?;
 
         // Generate output resource logic proofs
         let outputs: Vec<ResourceLogicVerifyingInfoSet> = output_resource_resource_logics
             .iter()
-            .map(|output_resource_resource_logic| output_resource_resource_logic.build())
-            .collect();
+            .map(|output_resource_resource_logic| {
+                output_resource_resource_logic.build_with_rng(&mut rng)
+            })
+            .collect::<Result<_, TransactionError>>()?;
 
         Ok(Self {
             compliances,
             inputs,
             outputs,
+            reads: vec![],
             binding_sig_r: Some(rcv_sum),
             hints,
         })
     }
 
     // verify zk proof
+    // The ptx still carries one proof per compliance and per logic, only the
+    // final IPA checks of the proofs are batched into a single accumulator, so
+    // that the ptx costs one multi-scalar multiplication. This is batching,
+    // not aggregation: the proofs are not folded into a single succinct proof
+    // and the verifier still reads all of them. The
+    // `individual-proof-verification` feature checks them one by one instead.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "ptx.verify", skip_all)
+    )]
     pub fn verify_proof(&self) -> Result<(), TransactionError> {
+        self.verify_proof_with_context(&VerifyingContext::default())
+    }
+
+    // Same as `verify_proof`, with the params and the keys of `context`
+    pub fn verify_proof_with_context(
+        &self,
+        context: &VerifyingContext,
+    ) -> Result<(), TransactionError> {
+        if cfg!(feature = "individual-proof-verification") {
+            return self.verify_individual_proofs_with_context(context);
+        }
+        let mut batch = BatchVerifier::new();
+        self.batch_verify_proof_with_context(&mut batch, context)?;
+        batch.finalize()?;
+        Ok(())
+    }
+
+    // verify each zk proof on its own
+    pub fn verify_individual_proofs(&self) -> Result<(), TransactionError> {
+        self.verify_individual_proofs_with_context(&VerifyingContext::default())
+    }
+
+    pub fn verify_individual_proofs_with_context(
+        &self,
+        context: &VerifyingContext,
+    ) -> Result<(), TransactionError> {
         // Verify compliance proofs
         for verifying_info in self.compliances.iter() {
-            verifying_info.verify()?;
+            verifying_info.verify_with_context(context)?;
+        }
+
+        // Verify read proofs
+        for verifying_info in self.reads.iter() {
+            verifying_info.verify_with_context(context)?;
         }
 
         // Verify resource logic proofs of input resources
         for verifying_info in self.inputs.iter() {
-            verifying_info.verify()?;
+            verifying_info.verify_with_context(context)?;
         }
         // Verify resource logic proofs of output resources
         for verifying_info in self.outputs.iter() {
-            verifying_info.verify()?;
+            verifying_info.verify_with_context(context)?;
         }
 
         Ok(())
     }
 
+    // Same as `verify_proof`, with the IPA checks deferred to the batch verifier
+    pub fn batch_verify_proof(&self, batch: &mut BatchVerifier) -> Result<(), TransactionError> {
+        self.batch_verify_proof_with_context(batch, &VerifyingContext::default())
+    }
+
+    pub fn batch_verify_proof_with_context(
+        &self,
+        batch: &mut BatchVerifier,
+        context: &VerifyingContext,
+    ) -> Result<(), TransactionError> {
+        for verifying_info in self.compliances.iter() {
+            verifying_info.batch_verify_with_context(batch, context)?;
+        }
+        for verifying_info in self.reads.iter() {
+            verifying_info.batch_verify_with_context(batch, context)?;
+        }
+        for verifying_info in self.inputs.iter().chain(self.outputs.iter()) {
+            verifying_info.batch_verify_with_context(batch, context)?;
+        }
+
+        Ok(())
+    }
+
+    // Same as `execute`, with the IPA checks deferred to the batch verifier
+    pub fn batch_execute(&self, batch: &mut BatchVerifier) -> Result<(), TransactionError> {
+        self.batch_execute_with_context(batch, &VerifyingContext::default())
+    }
+
+    pub fn batch_execute_with_context(
+        &self,
+        batch: &mut BatchVerifier,
+        context: &VerifyingContext,
+    ) -> Result<(), TransactionError> {
+        self.batch_verify_proof_with_context(batch, context)?;
+        self.check_public_inputs()
+    }
+
+    /// Same as `Executable::validate`, with the params and the keys of
+    /// `context`.
+    pub fn validate_with_context(
+        &self,
+        context: &VerifyingContext,
+    ) -> Result<(), TransactionError> {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = self
+            .verify_proof_with_context(context)
+            .and_then(|_| self.check_public_inputs());
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_verification(
+            crate::metrics::KIND_PARTIAL_TRANSACTION,
+            start,
+            &result,
+        );
+        result
+    }
+
+    fn check_public_inputs(&self) -> Result<(), TransactionError> {
+        if self.compliances.len() + self.reads.len() > MAX_PTX_COMPLIANCE_NUM {
+            return Err(TransactionError::TooManyResources);
+        }
+        check_unique_compliances(self.get_compliance_public_inputs())?;
+        self.check_nullifiers()?;
+        self.check_resource_commitments()?;
+        self.check_resource_merkle_roots()
+    }
+
     // check resource merkle roots
     fn check_resource_merkle_roots(&self) -> Result<(), TransactionError> {
         let root_from_compliance = self.get_resource_merkle_root();
@@ -168,62 +406,114 @@ impl ShieldedPartialTransaction {
 
     // check the nullifiers are from compliance proofs
     fn check_nullifiers(&self) -> Result<(), TransactionError> {
-        let compliance_nfs = self.get_nullifiers();
-        for (resource_logic_info, compliance_nf) in self.inputs.iter().zip(compliance_nfs.iter()) {
-            // Check the app resource logic and the sub resource logics use the same self_resource_id in one resource
-            let self_resource_id = resource_logic_info
-                .app_resource_logic_verifying_info
-                .get_self_resource_id();
-            for logic_resource_logic_verifying_info in resource_logic_info
-                .app_dynamic_resource_logic_verifying_info
-                .iter()
-            {
-                if self_resource_id != logic_resource_logic_verifying_info.get_self_resource_id() {
-                    return Err(TransactionError::InconsistentSelfResourceID);
-                }
-            }
-
-            // Check the self_resource_id that resource logic uses is consistent with the nf from the compliance circuit
-            if self_resource_id != compliance_nf.inner() {
-                return Err(TransactionError::InconsistentSelfResourceID);
-            }
-        }
-        Ok(())
+        let compliance_nfs: Vec<pallas::Base> =
+            self.get_nullifiers().iter().map(|nf| nf.inner()).collect();
+        check_resource_logic_claims(&compliance_nfs, &self.inputs)
     }
 
     // check the output cms are from compliance proofs
     fn check_resource_commitments(&self) -> Result<(), TransactionError> {
-        let compliance_cms = self.get_output_cms();
-        for (resource_logic_info, compliance_cm) in self.outputs.iter().zip(compliance_cms.iter()) {
-            // Check that the app resource logic and the sub resource_logics use the same self_resource_id in one resource
-            let self_resource_id = resource_logic_info
-                .app_resource_logic_verifying_info
-                .get_self_resource_id();
-            for logic_resource_logic_verifying_info in resource_logic_info
-                .app_dynamic_resource_logic_verifying_info
-                .iter()
-            {
-                if self_resource_id != logic_resource_logic_verifying_info.get_self_resource_id() {
-                    return Err(TransactionError::InconsistentSelfResourceID);
-                }
-            }
-
-            // Check the self_resource_id that resource logic uses is consistent with the cm from the compliance circuit
-            if self_resource_id != compliance_cm.inner() {
-                return Err(TransactionError::InconsistentSelfResourceID);
-            }
-        }
-        Ok(())
+        let compliance_cms: Vec<pallas::Base> =
+            self.get_output_cms().iter().map(|cm| cm.inner()).collect();
+        check_resource_logic_claims(&compliance_cms, &self.outputs)
     }
 
     pub fn get_binding_sig_r(&self) -> Option<pallas::Scalar> {
         self.binding_sig_r
     }
 
+    /// The net quantity of each kind, for the creator of the ptx holding the
+    /// compliances it's built from. The kinds are hidden in the delta
+    /// commitments, so the compliances must open them in order.
+    pub fn get_balance_summary(
+        &self,
+        compliances: &[ComplianceInfo],
+    ) -> Result<BalanceSummary, TransactionError> {
+        let delta_commitments = self.get_delta_commitments();
+        if delta_commitments.len() != compliances.len() {
+            return Err(TransactionError::InvalidBalanceOpening);
+        }
+        for (delta, compliance) in delta_commitments.iter().zip(compliances) {
+            let opened = compliance.get_delta_commitment(&compliance.get_rcv());
+            if opened.inner() != delta.inner() {
+                return Err(TransactionError::InvalidBalanceOpening);
+            }
+        }
+        Ok(BalanceSummary::from_compliances(compliances))
+    }
+
     pub fn get_hints(&self) -> Vec<u8> {
         self.hints.clone()
     }
 
+    /// The auxiliary deltas contributed by the logics, with the compressed vk
+    /// of the logic contributing each.
+    pub fn get_auxiliary_deltas(
+        &self,
+    ) -> Result<Vec<(pallas::Base, AuxiliaryDelta)>, TransactionError> {
+        let mut deltas = vec![];
+        for resource_logic_set in self.inputs.iter().chain(self.outputs.iter()) {
+            deltas.extend(resource_logic_set.get_auxiliary_deltas()?);
+        }
+        Ok(deltas)
+    }
+
+    pub fn get_compliance_public_inputs(&self) -> Vec<&CompliancePublicInputs> {
+        self.compliances
+            .iter()
+            .map(|compliance| compliance.get_public_inputs())
+            .collect()
+    }
+
+    pub fn get_read_public_inputs(&self) -> Vec<&ReadPublicInputs> {
+        self.reads
+            .iter()
+            .map(|read| read.get_public_inputs())
+            .collect()
+    }
+
+    /// The compliance and the read proofs followed by the resource logic
+    /// proofs.
+    pub fn get_proofs(&self) -> Vec<&Proof> {
+        self.compliances
+            .iter()
+            .map(|compliance| &compliance.compliance_proof)
+            .chain(self.reads.iter().map(|read| &read.read_proof))
+            .chain(
+                self.inputs
+                    .iter()
+                    .chain(self.outputs.iter())
+                    .flat_map(|resource_logic_set| resource_logic_set.get_proofs()),
+            )
+            .collect()
+    }
+
+    /// The public inputs of the resource logics.
+    pub fn get_resource_logic_public_inputs(&self) -> Vec<&ResourceLogicPublicInputs> {
+        self.inputs
+            .iter()
+            .chain(self.outputs.iter())
+            .flat_map(|resource_logic_set| resource_logic_set.get_public_inputs())
+            .collect()
+    }
+
+    pub fn get_resource_logic_verifying_infos(&self) -> Vec<&ResourceLogicVerifyingInfo> {
+        self.inputs
+            .iter()
+            .chain(self.outputs.iter())
+            .flat_map(|resource_logic_set| resource_logic_set.get_verifying_infos())
+            .collect()
+    }
+
+    /// The compressed vks of the resource logics.
+    pub fn get_logic_fingerprints(&self) -> Vec<pallas::Base> {
+        self.inputs
+            .iter()
+            .chain(self.outputs.iter())
+            .flat_map(|resource_logic_set| resource_logic_set.get_logic_fingerprints())
+            .collect()
+    }
+
     pub fn clean_private_info(&mut self) {
         self.binding_sig_r = None;
         self.hints = vec![];
@@ -231,50 +521,120 @@ impl ShieldedPartialTransaction {
 }
 
 impl Executable for ShieldedPartialTransaction {
-    fn execute(&self) -> Result<(), TransactionError> {
-        self.verify_proof()?;
-        self.check_nullifiers()?;
-        self.check_resource_commitments()?;
-        self.check_resource_merkle_roots()?;
-        Ok(())
+    fn validate(&self) -> Result<(), TransactionError> {
+        self.validate_with_context(&VerifyingContext::default())
     }
 
     fn get_nullifiers(&self) -> Vec<Nullifier> {
-        self.compliances
-            .iter()
-            .map(|compliance| compliance.compliance_instance.nf)
+        self.get_compliance_public_inputs()
+            .into_iter()
+            .map(|public_inputs| public_inputs.nf)
             .collect()
     }
 
     fn get_output_cms(&self) -> Vec<ResourceCommitment> {
-        self.compliances
-            .iter()
-            .map(|compliance| compliance.compliance_instance.cm)
+        self.get_compliance_public_inputs()
+            .into_iter()
+            .map(|public_inputs| public_inputs.cm)
             .collect()
     }
 
     fn get_delta_commitments(&self) -> Vec<DeltaCommitment> {
-        self.compliances
-            .iter()
-            .map(|compliance| compliance.compliance_instance.delta)
+        self.get_compliance_public_inputs()
+            .into_iter()
+            .map(|public_inputs| public_inputs.delta)
             .collect()
     }
 
     fn get_anchors(&self) -> Vec<Anchor> {
-        self.compliances
-            .iter()
-            .map(|compliance| compliance.compliance_instance.anchor)
+        self.get_compliance_public_inputs()
+            .into_iter()
+            .map(|public_inputs| public_inputs.anchor)
+            .chain(
+                self.get_read_public_inputs()
+                    .into_iter()
+                    .map(|public_inputs| public_inputs.anchor),
+            )
+            .collect()
+    }
+
+    fn get_read_tags(&self) -> Vec<pallas::Base> {
+        self.get_read_public_inputs()
+            .into_iter()
+            .map(|public_inputs| public_inputs.tag)
             .collect()
     }
+
+    fn get_conversion_rules(&self) -> Vec<ConversionRuleId> {
+        self.get_compliance_public_inputs()
+            .into_iter()
+            .map(|public_inputs| public_inputs.conversion_rule)
+            .filter(|id| !id.is_zero())
+            .collect()
+    }
+
+    fn get_epochs(&self) -> Vec<u64> {
+        self.get_compliance_public_inputs()
+            .into_iter()
+            .map(|public_inputs| public_inputs.epoch)
+            .collect()
+    }
+
+    // The blacklist logics are recognized by their vk, the other logics
+    // publish no blacklist root.
+    fn get_blacklist_roots(&self) -> Vec<Anchor> {
+        #[cfg(feature = "examples")]
+        {
+            use crate::circuit::resource_logic_examples::blacklist::COMPRESSED_BLACKLIST_VK;
+            use crate::constant::RESOURCE_LOGIC_CIRCUIT_BLACKLIST_ROOT_IDX;
+            self.get_resource_logic_verifying_infos()
+                .into_iter()
+                .zip(self.get_logic_fingerprints())
+                .filter(|(_, logic)| *logic == *COMPRESSED_BLACKLIST_VK)
+                .map(|(info, _)| {
+                    Anchor::from(
+                        info.public_inputs
+                            .get_from_index(RESOURCE_LOGIC_CIRCUIT_BLACKLIST_ROOT_IDX),
+                    )
+                })
+                .collect()
+        }
+        #[cfg(not(feature = "examples"))]
+        vec![]
+    }
 }
 
 #[cfg(feature = "borsh")]
 impl BorshSerialize for ShieldedPartialTransaction {
     fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.serialize_versioned(writer, &EncodingLayout::current())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for ShieldedPartialTransaction {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Self::deserialize_versioned(reader, &EncodingLayout::current())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl VersionedBorsh for ShieldedPartialTransaction {
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<()> {
         use byteorder::WriteBytesExt;
-        self.compliances.serialize(writer)?;
+        self.compliances.serialize_versioned(writer, layout)?;
         self.inputs.serialize(writer)?;
         self.outputs.serialize(writer)?;
+        layout.serialize_references(writer)?;
+        if layout.reads {
+            self.reads.serialize(writer)?;
+        } else if !self.reads.is_empty() {
+            return Err(EncodingLayout::unsupported("reads"));
+        }
 
         // Write binding_sig_r
         match self.binding_sig_r {
@@ -291,21 +651,29 @@ impl BorshSerialize for ShieldedPartialTransaction {
 
         Ok(())
     }
-}
 
-#[cfg(feature = "borsh")]
-impl BorshDeserialize for ShieldedPartialTransaction {
-    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+    fn deserialize_versioned<R: std::io::Read>(
+        reader: &mut R,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<Self> {
         use byteorder::ReadBytesExt;
-        let compliances = Vec::<ComplianceVerifyingInfo>::deserialize_reader(reader)?;
+        let compliances = Vec::<ComplianceVerifyingInfo>::deserialize_versioned(reader, layout)?;
         let inputs = Vec::<ResourceLogicVerifyingInfoSet>::deserialize_reader(reader)?;
         let outputs = Vec::<ResourceLogicVerifyingInfoSet>::deserialize_reader(reader)?;
-        let binding_sig_r_type = reader.read_u8()?;
-        let binding_sig_r = if binding_sig_r_type == 0 {
-            None
-        } else {
-            let r = crate::utils::read_scalar_field(reader)?;
-            Some(r)
+        layout.deserialize_references(reader)?;
+        let reads = match layout.reads {
+            true => Vec::<ReadVerifyingInfo>::deserialize_reader(reader)?,
+            false => vec![],
+        };
+        let binding_sig_r = match reader.read_u8()? {
+            0 => None,
+            1 => Some(crate::utils::read_scalar_field(reader)?),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "invalid binding_sig_r tag",
+                ))
+            }
         };
 
         let hints = Vec::<u8>::deserialize_reader(reader)?;
@@ -313,21 +681,392 @@ impl BorshDeserialize for ShieldedPartialTransaction {
             compliances,
             inputs,
             outputs,
+            reads,
             binding_sig_r,
             hints,
         })
     }
 }
 
+#[cfg(feature = "borsh")]
+impl VersionedBorsh for ComplianceVerifyingInfo {
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<()> {
+        self.compliance_proof.serialize(writer)?;
+        self.compliance_instance.serialize_versioned(writer, layout)
+    }
+
+    fn deserialize_versioned<R: std::io::Read>(
+        reader: &mut R,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<Self> {
+        let compliance_proof = Proof::deserialize_reader(reader)?;
+        let compliance_instance = CompliancePublicInputs::deserialize_versioned(reader, layout)?;
+        Ok(Self {
+            compliance_proof,
+            compliance_instance,
+        })
+    }
+}
+
+/// A ptx whose witnesses are being collected, the first phase of
+/// `DraftedPartialTransaction -> ProvenPartialTransaction ->
+/// SealedPartialTransaction`. Nothing is proven yet, so it can't be executed
+/// or serialized.
+#[derive(Debug, Clone, Default)]
+pub struct DraftedPartialTransaction {
+    compliances: Vec<ComplianceInfo>,
+    input_resource_app: Vec<ApplicationByteCode>,
+    output_resource_app: Vec<ApplicationByteCode>,
+    reads: Vec<ReadInfo>,
+    hints: Vec<u8>,
+}
+
+/// A ptx with all its proofs. It can still be amended, e.g. with hints, until
+/// it's sealed.
+#[derive(Debug, Clone)]
+pub struct ProvenPartialTransaction(ShieldedPartialTransaction);
+
+/// A ptx checked by its creator and frozen, ready to be serialized and sent
+/// to the party building the transaction. A deserialized ptx is sealed again,
+/// so it's validated like one sealed locally.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "ShieldedPartialTransaction",
+        into = "ShieldedPartialTransaction"
+    )
+)]
+pub struct SealedPartialTransaction(ShieldedPartialTransaction);
+
+impl DraftedPartialTransaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a compliance with the application logics of its input and output
+    /// resources.
+    pub fn add_compliance(
+        &mut self,
+        compliance: ComplianceInfo,
+        input_resource_app: ApplicationByteCode,
+        output_resource_app: ApplicationByteCode,
+    ) -> &mut Self {
+        self.compliances.push(compliance);
+        self.input_resource_app.push(input_resource_app);
+        self.output_resource_app.push(output_resource_app);
+        self
+    }
+
+    /// Adds a resource read by the ptx, see `ReadInfo`.
+    pub fn add_read(&mut self, read: ReadInfo) -> &mut Self {
+        self.reads.push(read);
+        self
+    }
+
+    pub fn add_hints(&mut self, hints: Vec<u8>) -> &mut Self {
+        self.hints.extend(hints);
+        self
+    }
+
+    /// The net quantity of each kind of the compliances added so far.
+    pub fn get_balance_summary(&self) -> BalanceSummary {
+        BalanceSummary::from_compliances(&self.compliances)
+    }
+
+    pub fn prove<R: RngCore>(self, rng: R) -> Result<ProvenPartialTransaction, TransactionError> {
+        self.prove_with_threads(1, rng)
+    }
+
+    /// Same as `prove`, see `ShieldedPartialTransaction::from_bytecode_with_threads`.
+    pub fn prove_with_threads<R: RngCore>(
+        self,
+        threads: usize,
+        rng: R,
+    ) -> Result<ProvenPartialTransaction, TransactionError> {
+        self.prove_with_context(&ProvingContext::default(), threads, rng)
+    }
+
+    /// Same as `prove_with_threads`, see
+    /// `ShieldedPartialTransaction::from_bytecode_with_context`.
+    pub fn prove_with_context<R: RngCore>(
+        self,
+        context: &ProvingContext,
+        threads: usize,
+        mut rng: R,
+    ) -> Result<ProvenPartialTransaction, TransactionError> {
+        let mut ptx = ShieldedPartialTransaction::from_bytecode_with_context(
+            self.compliances,
+            self.input_resource_app,
+            self.output_resource_app,
+            self.hints,
+            context,
+            threads,
+            &mut rng,
+        )?;
+        // The read proofs get their RNGs seeded after the other proofs, see
+        // `from_bytecode_with_threads`
+        let jobs: Vec<(ReadInfo, ChaCha20Rng)> = self
+            .reads
+            .into_iter()
+            .map(|read| {
+                let mut seed = [0u8; 32];
+                rng.fill_bytes(&mut seed);
+                (read, ChaCha20Rng::from_seed(seed))
+            })
+            .collect();
+        ptx.reads = parallel_map(jobs, threads, |(read, rng)| {
+            ReadVerifyingInfo::create_with_context(&read, context, rng)
+        })
+        .into_iter()
+        .collect::<Result<_, Error>>()?;
+        Ok(ProvenPartialTransaction(ptx))
+    }
+}
+
+impl ProvenPartialTransaction {
+    pub fn set_hints(&mut self, hints: Vec<u8>) -> &mut Self {
+        self.0.hints = hints;
+        self
+    }
+
+    /// Validates the ptx and freezes it.
+    pub fn seal(self) -> Result<SealedPartialTransaction, TransactionError> {
+        self.0.validate()?;
+        Ok(SealedPartialTransaction(self.0))
+    }
+}
+
+impl Deref for ProvenPartialTransaction {
+    type Target = ShieldedPartialTransaction;
+
+    fn deref(&self) -> &ShieldedPartialTransaction {
+        &self.0
+    }
+}
+
+impl Deref for SealedPartialTransaction {
+    type Target = ShieldedPartialTransaction;
+
+    fn deref(&self) -> &ShieldedPartialTransaction {
+        &self.0
+    }
+}
+
+// The ptxs built with `ShieldedPartialTransaction::build` or `from_bytecode`
+// hold their proofs, they can enter the phases as proven ptxs.
+impl From<ShieldedPartialTransaction> for ProvenPartialTransaction {
+    fn from(ptx: ShieldedPartialTransaction) -> Self {
+        Self(ptx)
+    }
+}
+
+impl From<ProvenPartialTransaction> for ShieldedPartialTransaction {
+    fn from(ptx: ProvenPartialTransaction) -> Self {
+        ptx.0
+    }
+}
+
+impl From<SealedPartialTransaction> for ShieldedPartialTransaction {
+    fn from(ptx: SealedPartialTransaction) -> Self {
+        ptx.0
+    }
+}
+
+impl TryFrom<ShieldedPartialTransaction> for SealedPartialTransaction {
+    type Error = TransactionError;
+
+    fn try_from(ptx: ShieldedPartialTransaction) -> Result<Self, Self::Error> {
+        ProvenPartialTransaction(ptx).seal()
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for SealedPartialTransaction {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let ptx = ShieldedPartialTransaction::deserialize_reader(reader)?;
+        Self::try_from(ptx)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+    }
+}
+
+// Map each resource id(nf or cm) from the compliance proofs to the resource
+// logic sets claiming it, and check every resource is claimed exactly once
+fn check_resource_logic_claims(
+    resource_ids: &[pallas::Base],
+    resource_logic_sets: &[ResourceLogicVerifyingInfoSet],
+) -> Result<(), TransactionError> {
+    let mut claims: Vec<(pallas::Base, Vec<usize>)> =
+        resource_ids.iter().map(|id| (*id, vec![])).collect();
+    for (set_idx, resource_logic_set) in resource_logic_sets.iter().enumerate() {
+        for owned_resource_id in resource_logic_set.get_owned_resource_ids()? {
+            match claims.iter_mut().find(|(id, _)| *id == owned_resource_id) {
+                Some((_, claimers)) => claimers.push(set_idx),
+                None => {
+                    return Err(TransactionError::UnknownOwnedResource(owned_resource_id));
+                }
+            }
+        }
+    }
+
+    for (id, claimers) in claims {
+        match claimers.len() {
+            0 => return Err(TransactionError::MissingResourceLogicProof(id)),
+            1 => {}
+            _ => return Err(TransactionError::DuplicateResourceLogicProof(id)),
+        }
+    }
+
+    Ok(())
+}
+
+// check that no compliance is included twice, by the digests of their public
+// inputs
+pub(crate) fn check_unique_compliances<'a>(
+    public_inputs: impl IntoIterator<Item = &'a CompliancePublicInputs>,
+) -> Result<(), TransactionError> {
+    let mut digests = HashSet::new();
+    for public_inputs in public_inputs {
+        if !digests.insert(public_inputs.digest()) {
+            return Err(TransactionError::DuplicateCompliance);
+        }
+    }
+    Ok(())
+}
+
+// A proof of the ptx, to be generated on any thread
+enum ProvingJob {
+    Compliance(CompliancePublicInputs, ComplianceCircuit, ChaCha20Rng),
+    Application(ApplicationByteCode, ChaCha20Rng),
+}
+
+enum ProvingOutput {
+    Compliance(ComplianceVerifyingInfo),
+    Application(ResourceLogicVerifyingInfoSet),
+}
+
+impl ProvingJob {
+    fn prove(self, context: &ProvingContext) -> Result<ProvingOutput, TransactionError> {
+        match self {
+            ProvingJob::Compliance(compliance_instance, circuit, rng) => {
+                let verifying_info =
+                    ComplianceVerifyingInfo::prove(compliance_instance, circuit, context, rng)?;
+                Ok(ProvingOutput::Compliance(verifying_info))
+            }
+            ProvingJob::Application(app, mut rng) => Ok(ProvingOutput::Application(
+                app.generate_proofs_with_context(context, &mut rng)?,
+            )),
+        }
+    }
+}
+
+impl ProvingOutput {
+    fn into_compliance(self) -> ComplianceVerifyingInfo {
+        match self {
+            ProvingOutput::Compliance(verifying_info) => verifying_info,
+            ProvingOutput::Application(_) => unreachable!("the compliance proofs come first"),
+        }
+    }
+
+    fn into_application(self) -> ResourceLogicVerifyingInfoSet {
+        match self {
+            ProvingOutput::Application(verifying_info) => verifying_info,
+            ProvingOutput::Compliance(_) => unreachable!("the compliance proofs come first"),
+        }
+    }
+}
+
+// Maps the items on up to `threads` scoped threads and keeps their order. The
+// items are handed out one at a time, so that a slow proof doesn't hold back
+// the items queued behind it. Without std they are mapped on the calling
+// thread.
+fn parallel_map<T: Send, U: Send>(
+    items: Vec<T>,
+    threads: usize,
+    f: impl Fn(T) -> U + Sync,
+) -> Vec<U> {
+    let threads = threads.min(items.len());
+    if threads <= 1 || cfg!(not(feature = "std")) {
+        return items.into_iter().map(f).collect();
+    }
+    let item_num = items.len();
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let mut results: Vec<Option<U>> = (0..item_num).map(|_| None).collect();
+    std::thread::scope(|s| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut done = vec![];
+                    loop {
+                        let next = queue.lock().unwrap().next();
+                        match next {
+                            Some((index, item)) => done.push((index, f(item))),
+                            None => return done,
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            for (index, result) in handle.join().unwrap() {
+                results[index] = Some(result);
+            }
+        }
+    });
+    results
+        .into_iter()
+        .map(|result| result.expect("every item is mapped"))
+        .collect()
+}
+
 impl ComplianceVerifyingInfo {
-    pub fn create<R: RngCore>(compliance_info: &ComplianceInfo, mut rng: R) -> Result<Self, Error> {
+    pub fn create<R: RngCore>(compliance_info: &ComplianceInfo, rng: R) -> Result<Self, Error> {
+        Self::create_with_witnesses(compliance_info, &mut WitnessSet::new(), rng)
+    }
+
+    pub fn create_with_witnesses<R: RngCore>(
+        compliance_info: &ComplianceInfo,
+        witnesses: &mut WitnessSet,
+        rng: R,
+    ) -> Result<Self, Error> {
+        let (compliance_instance, circuit) = compliance_info.build_with_witnesses(witnesses);
+        Self::prove(
+            compliance_instance,
+            circuit,
+            &ProvingContext::default(),
+            rng,
+        )
+    }
+
+    /// Same as `create`, with the params and the proving key of `context`.
+    pub fn create_with_context<R: RngCore>(
+        compliance_info: &ComplianceInfo,
+        context: &ProvingContext,
+        rng: R,
+    ) -> Result<Self, Error> {
         let (compliance_instance, circuit) = compliance_info.build();
-        let params = SETUP_PARAMS_MAP
-            .get(&COMPLIANCE_CIRCUIT_PARAMS_SIZE)
-            .unwrap();
+        Self::prove(compliance_instance, circuit, context, rng)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "compliance.prove", skip_all)
+    )]
+    fn prove<R: RngCore>(
+        compliance_instance: CompliancePublicInputs,
+        circuit: ComplianceCircuit,
+        context: &ProvingContext,
+        mut rng: R,
+    ) -> Result<Self, Error> {
         let compliance_proof = Proof::create(
-            &COMPLIANCE_PROVING_KEY,
-            params,
+            context.compliance_proving_key(),
+            context.setup_params(COMPLIANCE_CIRCUIT_PARAMS_SIZE),
             circuit,
             &[&compliance_instance.to_instance()],
             &mut rng,
@@ -338,18 +1077,99 @@ impl ComplianceVerifyingInfo {
         })
     }
 
+    pub fn get_public_inputs(&self) -> &CompliancePublicInputs {
+        &self.compliance_instance
+    }
+
     pub fn verify(&self) -> Result<(), Error> {
-        let params = SETUP_PARAMS_MAP
-            .get(&COMPLIANCE_CIRCUIT_PARAMS_SIZE)
-            .unwrap();
+        self.verify_with_context(&VerifyingContext::default())
+    }
+
+    pub fn verify_with_context(&self, context: &VerifyingContext) -> Result<(), Error> {
         self.compliance_proof.verify(
-            &COMPLIANCE_VERIFYING_KEY,
-            params,
+            context.compliance_verifying_key(),
+            context.setup_params(COMPLIANCE_CIRCUIT_PARAMS_SIZE),
+            &[&self.compliance_instance.to_instance()],
+        )
+    }
+
+    pub fn batch_verify(&self, batch: &mut BatchVerifier) -> Result<(), Error> {
+        self.batch_verify_with_context(batch, &VerifyingContext::default())
+    }
+
+    pub fn batch_verify_with_context(
+        &self,
+        batch: &mut BatchVerifier,
+        context: &VerifyingContext,
+    ) -> Result<(), Error> {
+        batch.add_proof(
+            &self.compliance_proof,
+            context.compliance_verifying_key(),
+            context.setup_params(COMPLIANCE_CIRCUIT_PARAMS_SIZE),
             &[&self.compliance_instance.to_instance()],
         )
     }
 }
 
+impl ReadVerifyingInfo {
+    pub fn create<R: RngCore>(read_info: &ReadInfo, rng: R) -> Result<Self, Error> {
+        Self::create_with_context(read_info, &ProvingContext::default(), rng)
+    }
+
+    /// Same as `create`, with the params and the proving key of `context`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "read.prove", skip_all)
+    )]
+    pub fn create_with_context<R: RngCore>(
+        read_info: &ReadInfo,
+        context: &ProvingContext,
+        mut rng: R,
+    ) -> Result<Self, Error> {
+        let (read_instance, circuit) = read_info.build();
+        let read_proof = Proof::create(
+            context.read_proving_key(),
+            context.setup_params(READ_CIRCUIT_PARAMS_SIZE),
+            circuit,
+            &[&read_instance.to_instance()],
+            &mut rng,
+        )?;
+        Ok(Self {
+            read_proof,
+            read_instance,
+        })
+    }
+
+    pub fn get_public_inputs(&self) -> &ReadPublicInputs {
+        &self.read_instance
+    }
+
+    pub fn verify(&self) -> Result<(), Error> {
+        self.verify_with_context(&VerifyingContext::default())
+    }
+
+    pub fn verify_with_context(&self, context: &VerifyingContext) -> Result<(), Error> {
+        self.read_proof.verify(
+            context.read_verifying_key(),
+            context.setup_params(READ_CIRCUIT_PARAMS_SIZE),
+            &[&self.read_instance.to_instance()],
+        )
+    }
+
+    pub fn batch_verify_with_context(
+        &self,
+        batch: &mut BatchVerifier,
+        context: &VerifyingContext,
+    ) -> Result<(), Error> {
+        batch.add_proof(
+            &self.read_proof,
+            context.read_verifying_key(),
+            context.setup_params(READ_CIRCUIT_PARAMS_SIZE),
+            &[&self.read_instance.to_instance()],
+        )
+    }
+}
+
 impl ResourceLogicVerifyingInfoSet {
     pub fn new(
         app_resource_logic_verifying_info: ResourceLogicVerifyingInfo,
@@ -360,36 +1180,58 @@ impl ResourceLogicVerifyingInfoSet {
         Self {
             app_resource_logic_verifying_info,
             app_dynamic_resource_logic_verifying_info,
+            owned_resource_ids: vec![],
         }
     }
 
+    // Create a set whose logics own several resources
+    pub fn new_with_owned_resources(
+        app_resource_logic_verifying_info: ResourceLogicVerifyingInfo,
+        app_dynamic_resource_logic_verifying_info: Vec<ResourceLogicVerifyingInfo>,
+        owned_resource_ids: Vec<pallas::Base>,
+    ) -> Self {
+        let mut set = Self::new(
+            app_resource_logic_verifying_info,
+            app_dynamic_resource_logic_verifying_info,
+        );
+        set.owned_resource_ids = owned_resource_ids;
+        set
+    }
+
     // TODO: remove it.
     pub fn build(
         application_resource_logic: Box<ResourceLogic>,
         dynamic_resource_logics: Vec<Box<ResourceLogic>>,
-    ) -> Self {
+    ) -> Result<Self, TransactionError> {
         assert!(dynamic_resource_logics.len() <= MAX_DYNAMIC_RESOURCE_LOGIC_NUM);
 
-        let app_resource_logic_verifying_info = application_resource_logic.get_verifying_info();
+        let app_resource_logic_verifying_info = application_resource_logic.get_verifying_info()?;
+        let owned_resource_ids = application_resource_logic.get_owned_resource_ids();
 
         let app_dynamic_resource_logic_verifying_info = dynamic_resource_logics
             .into_iter()
             .map(|verifying_info| verifying_info.get_verifying_info())
-            .collect();
+            .collect::<Result<_, _>>()?;
 
-        Self {
+        Ok(Self {
             app_resource_logic_verifying_info,
             app_dynamic_resource_logic_verifying_info,
-        }
+            owned_resource_ids,
+        })
     }
 
-    pub fn verify(&self) -> Result<(), Error> {
+    pub fn verify(&self) -> Result<(), TransactionError> {
+        self.verify_with_context(&VerifyingContext::default())
+    }
+
+    pub fn verify_with_context(&self, context: &VerifyingContext) -> Result<(), TransactionError> {
         // Verify the application resource logic proof
-        self.app_resource_logic_verifying_info.verify()?;
+        self.app_resource_logic_verifying_info
+            .verify_with_context(context)?;
 
         // Verify application dynamic resource logic proofs
         for verify_info in self.app_dynamic_resource_logic_verifying_info.iter() {
-            verify_info.verify()?;
+            verify_info.verify_with_context(context)?;
         }
 
         // TODO function privacy: Verify resource logic verifier proofs
@@ -397,6 +1239,49 @@ impl ResourceLogicVerifyingInfoSet {
         Ok(())
     }
 
+    pub fn batch_verify(&self, batch: &mut BatchVerifier) -> Result<(), TransactionError> {
+        self.batch_verify_with_context(batch, &VerifyingContext::default())
+    }
+
+    pub fn batch_verify_with_context(
+        &self,
+        batch: &mut BatchVerifier,
+        context: &VerifyingContext,
+    ) -> Result<(), TransactionError> {
+        self.app_resource_logic_verifying_info
+            .batch_verify_with_context(batch, context)?;
+        for verify_info in self.app_dynamic_resource_logic_verifying_info.iter() {
+            verify_info.batch_verify_with_context(batch, context)?;
+        }
+
+        Ok(())
+    }
+
+    // Get the ids of the owned resources, checking that the app resource logic
+    // and the dynamic resource logics publish the same owned resources
+    pub fn get_owned_resource_ids(&self) -> Result<Vec<pallas::Base>, TransactionError> {
+        let self_resource_id = self
+            .app_resource_logic_verifying_info
+            .get_self_resource_id();
+        for dynamic_resource_logic_verifying_info in
+            self.app_dynamic_resource_logic_verifying_info.iter()
+        {
+            if self_resource_id != dynamic_resource_logic_verifying_info.get_self_resource_id() {
+                return Err(TransactionError::InconsistentSelfResourceID);
+            }
+        }
+
+        if self.owned_resource_ids.is_empty() {
+            return Ok(vec![self_resource_id]);
+        }
+
+        // The self resource id is the hash of the owned resource ids
+        if self_resource_id != owned_resources_hash(&self.owned_resource_ids) {
+            return Err(TransactionError::InconsistentSelfResourceID);
+        }
+        Ok(self.owned_resource_ids.clone())
+    }
+
     pub fn get_resource_merkle_roots(&self) -> Vec<pallas::Base> {
         let mut roots: Vec<pallas::Base> = self
             .app_dynamic_resource_logic_verifying_info
@@ -409,32 +1294,233 @@ impl ResourceLogicVerifyingInfoSet {
         );
         roots
     }
+
+    pub fn get_proofs(&self) -> Vec<&Proof> {
+        std::iter::once(&self.app_resource_logic_verifying_info)
+            .chain(self.app_dynamic_resource_logic_verifying_info.iter())
+            .map(|info| &info.proof)
+            .collect()
+    }
+
+    pub fn get_public_inputs(&self) -> Vec<&ResourceLogicPublicInputs> {
+        std::iter::once(&self.app_resource_logic_verifying_info)
+            .chain(self.app_dynamic_resource_logic_verifying_info.iter())
+            .map(|info| &info.public_inputs)
+            .collect()
+    }
+
+    pub fn get_verifying_infos(&self) -> Vec<&ResourceLogicVerifyingInfo> {
+        std::iter::once(&self.app_resource_logic_verifying_info)
+            .chain(self.app_dynamic_resource_logic_verifying_info.iter())
+            .collect()
+    }
+
+    pub fn get_logic_fingerprints(&self) -> Vec<pallas::Base> {
+        std::iter::once(&self.app_resource_logic_verifying_info)
+            .chain(self.app_dynamic_resource_logic_verifying_info.iter())
+            .map(|info| ResourceLogicVerifyingKey::from_vk(info.vk.clone()).get_compressed())
+            .collect()
+    }
+
+    // Get the auxiliary deltas contributed by the app resource logic and the
+    // dynamic resource logics, keyed by the compressed vk of the logic
+    pub fn get_auxiliary_deltas(
+        &self,
+    ) -> Result<Vec<(pallas::Base, AuxiliaryDelta)>, TransactionError> {
+        let mut deltas = vec![];
+        for info in std::iter::once(&self.app_resource_logic_verifying_info)
+            .chain(self.app_dynamic_resource_logic_verifying_info.iter())
+        {
+            if let Some(delta) = info.get_auxiliary_delta()? {
+                let logic = ResourceLogicVerifyingKey::from_vk(info.vk.clone()).get_compressed();
+                deltas.push((logic, delta));
+            }
+        }
+        Ok(deltas)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for ResourceLogicVerifyingInfoSet {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.app_resource_logic_verifying_info.serialize(writer)?;
+        self.app_dynamic_resource_logic_verifying_info
+            .serialize(writer)?;
+        (self.owned_resource_ids.len() as u32).serialize(writer)?;
+        for id in self.owned_resource_ids.iter() {
+            writer.write_all(&id.to_repr())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for ResourceLogicVerifyingInfoSet {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        use crate::utils::read_base_field;
+        let app_resource_logic_verifying_info =
+            ResourceLogicVerifyingInfo::deserialize_reader(reader)?;
+        let app_dynamic_resource_logic_verifying_info =
+            Vec::<ResourceLogicVerifyingInfo>::deserialize_reader(reader)?;
+        let owned_resource_ids_len = u32::deserialize_reader(reader)?;
+        let owned_resource_ids = (0..owned_resource_ids_len)
+            .map(|_| read_base_field(reader))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            app_resource_logic_verifying_info,
+            app_dynamic_resource_logic_verifying_info,
+            owned_resource_ids,
+        })
+    }
 }
 
 #[cfg(test)]
 pub mod testing {
     use crate::{
-        circuit::resource_logic_circuit::ResourceLogicVerifyingInfoTrait,
-        circuit::resource_logic_examples::TrivialResourceLogicCircuit,
-        compliance::ComplianceInfo,
-        constant::TAIGA_COMMITMENT_TREE_DEPTH,
+        circuit::resource_logic_circuit::{
+            ResourceLogicVerifyingInfo, ResourceLogicVerifyingInfoTrait,
+        },
+        circuit::resource_logic_examples::{
+            TrivialResourceLogicCircuit, TRIVIAL_RESOURCE_LOGIC_VK,
+        },
+        compliance::{ComplianceInfo, ReadPublicInputs},
+        constant::{
+            MAX_DYNAMIC_RESOURCE_LOGIC_NUM, MAX_PTX_COMPLIANCE_NUM,
+            RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM, TAIGA_COMMITMENT_TREE_DEPTH,
+        },
+        error::TransactionError,
         merkle_tree::MerklePath,
-        nullifier::Nullifier,
+        nullifier::{tests::arb_base, Nullifier},
+        proof::{BatchVerifier, Proof},
+        quantity::Quantity,
         resource::{Resource, ResourceLogics},
-        resource_tree::ResourceMerkleTreeLeaves,
-        shielded_ptx::ShieldedPartialTransaction,
+        shielded_ptx::{
+            ComplianceVerifyingInfo, ProvenPartialTransaction, ReadVerifyingInfo,
+            ResourceLogicVerifyingInfoSet, ShieldedPartialTransaction,
+        },
+        transaction::{ShieldedPartialTxBundle, Transaction, TransparentPartialTxBundle},
         utils::poseidon_hash,
+        witness_set::WitnessSet,
     };
+    use ff::FromUniformBytes;
     use halo2_proofs::arithmetic::Field;
     use pasta_curves::pallas;
+    use proptest::prelude::*;
     use rand::rngs::OsRng;
 
+    /// Verifying infos of the trivial logic, with arbitrary proofs and public
+    /// inputs: the encodings don't check the proofs.
+    fn arb_resource_logic_verifying_info() -> impl Strategy<Value = ResourceLogicVerifyingInfo> {
+        (
+            prop::collection::vec(any::<u8>(), 0..128),
+            prop::collection::vec(arb_base(), RESOURCE_LOGIC_CIRCUIT_PUBLIC_INPUT_NUM),
+            prop::collection::vec(prop::collection::vec(arb_base(), 0..3), 0..2),
+        )
+            .prop_map(|(proof, public_inputs, extra_instances)| {
+                ResourceLogicVerifyingInfo {
+                    vk: TRIVIAL_RESOURCE_LOGIC_VK.get_vk().unwrap(),
+                    proof: Proof::new(proof),
+                    public_inputs: public_inputs.into(),
+                    extra_instances,
+                }
+            })
+    }
+
+    fn arb_resource_logic_verifying_info_set(
+    ) -> impl Strategy<Value = ResourceLogicVerifyingInfoSet> {
+        (
+            arb_resource_logic_verifying_info(),
+            prop::collection::vec(
+                arb_resource_logic_verifying_info(),
+                0..=MAX_DYNAMIC_RESOURCE_LOGIC_NUM,
+            ),
+            prop::collection::vec(arb_base(), 0..3),
+        )
+            .prop_map(|(app, dynamic, owned_resource_ids)| {
+                ResourceLogicVerifyingInfoSet::new_with_owned_resources(
+                    app,
+                    dynamic,
+                    owned_resource_ids,
+                )
+            })
+    }
+
+    impl Arbitrary for ShieldedPartialTransaction {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (
+                prop::collection::vec(
+                    (
+                        any::<ComplianceInfo>(),
+                        prop::collection::vec(any::<u8>(), 0..128),
+                        arb_resource_logic_verifying_info_set(),
+                        arb_resource_logic_verifying_info_set(),
+                    ),
+                    1..=MAX_PTX_COMPLIANCE_NUM,
+                ),
+                prop::collection::vec(
+                    (
+                        arb_base(),
+                        arb_base(),
+                        prop::collection::vec(any::<u8>(), 0..128),
+                    ),
+                    0..2,
+                ),
+                prop::option::of(any::<[u8; 32]>()),
+                prop::collection::vec(any::<u8>(), 0..32),
+            )
+                .prop_map(|(compliances, reads, binding_sig_r, hints)| {
+                    let mut inputs = vec![];
+                    let mut outputs = vec![];
+                    let compliances = compliances
+                        .into_iter()
+                        .map(|(info, proof, input, output)| {
+                            inputs.push(input);
+                            outputs.push(output);
+                            ComplianceVerifyingInfo {
+                                compliance_proof: Proof::new(proof),
+                                compliance_instance: info.build().0,
+                            }
+                        })
+                        .collect();
+                    let reads = reads
+                        .into_iter()
+                        .map(|(anchor, tag, proof)| ReadVerifyingInfo {
+                            read_proof: Proof::new(proof),
+                            read_instance: ReadPublicInputs {
+                                anchor: anchor.into(),
+                                tag,
+                            },
+                        })
+                        .collect();
+                    let binding_sig_r = binding_sig_r.map(|bytes| {
+                        let mut wide = [0u8; 64];
+                        wide[..32].copy_from_slice(&bytes);
+                        pallas::Scalar::from_uniform_bytes(&wide)
+                    });
+                    ShieldedPartialTransaction {
+                        compliances,
+                        inputs,
+                        outputs,
+                        reads,
+                        binding_sig_r,
+                        hints,
+                    }
+                })
+                .boxed()
+        }
+    }
+
     pub fn create_shielded_ptx() -> ShieldedPartialTransaction {
         let mut rng = OsRng;
 
         // Create empty resource logic circuit without resource info
         let trivial_resource_logic_circuit = TrivialResourceLogicCircuit::default();
-        let trivial_resource_logic_vk = trivial_resource_logic_circuit.get_resource_logic_vk();
+        let trivial_resource_logic_vk = trivial_resource_logic_circuit
+            .get_resource_logic_vk()
+            .unwrap();
         let compressed_trivial_resource_logic_vk = trivial_resource_logic_vk.get_compressed();
 
         // Generate resources
@@ -453,7 +1539,7 @@ pub mod testing {
                 app_dynamic_resource_logic_vk[1],
             );
             let nonce = Nullifier::from(pallas::Base::random(&mut rng));
-            let quantity = 5000u64;
+            let quantity = Quantity::new(5000);
             let nk = pallas::Base::random(&mut rng);
             let rseed = pallas::Base::random(&mut rng);
             let is_ephemeral = false;
@@ -473,7 +1559,7 @@ pub mod testing {
             // TODO: add real application dynamic resource logics and encode them to value later.
             // If the dynamic resource logic is not used, set value pallas::Base::zero() by default.
             let value = pallas::Base::zero();
-            let quantity = 5000u64;
+            let quantity = Quantity::new(5000);
             let npk = pallas::Base::random(&mut rng);
             let rseed = pallas::Base::random(&mut rng);
             let is_ephemeral = false;
@@ -491,7 +1577,7 @@ pub mod testing {
         // Construct compliance pair
         let merkle_path_1 = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
         let compliance_1 = ComplianceInfo::new(
-            input_resource_1,
+            input_resource_1.clone(),
             merkle_path_1,
             None,
             &mut output_resource_1,
@@ -503,7 +1589,7 @@ pub mod testing {
             let label = pallas::Base::one();
             let value = pallas::Base::zero();
             let nonce = Nullifier::from(pallas::Base::random(&mut rng));
-            let quantity = 10u64;
+            let quantity = Quantity::new(10);
             let nk = pallas::Base::random(&mut rng);
             let rseed = pallas::Base::random(&mut rng);
             let is_ephemeral = false;
@@ -521,7 +1607,7 @@ pub mod testing {
         let mut output_resource_2 = {
             let label = pallas::Base::one();
             let value = pallas::Base::zero();
-            let quantity = 10u64;
+            let quantity = Quantity::new(10);
             let npk = pallas::Base::random(&mut rng);
             let rseed = pallas::Base::random(&mut rng);
             let is_ephemeral = false;
@@ -539,32 +1625,27 @@ pub mod testing {
         // Construct compliance pair
         let merkle_path_2 = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
         let compliance_2 = ComplianceInfo::new(
-            input_resource_2,
+            input_resource_2.clone(),
             merkle_path_2,
             None,
             &mut output_resource_2,
             &mut rng,
         );
 
-        // Collect resource merkle leaves
-        let input_resource_nf_1 = input_resource_1.get_nf().unwrap().inner();
-        let output_resource_cm_1 = output_resource_1.commitment().inner();
-        let input_resource_nf_2 = input_resource_2.get_nf().unwrap().inner();
-        let output_resource_cm_2 = output_resource_2.commitment().inner();
-        let resource_merkle_tree = ResourceMerkleTreeLeaves::new(vec![
-            input_resource_nf_1,
-            output_resource_cm_1,
-            input_resource_nf_2,
-            output_resource_cm_2,
-        ]);
+        // Collect resource merkle leaves, deriving the resource witnesses once
+        let compliance_pairs = vec![compliance_1, compliance_2];
+        let mut witnesses = WitnessSet::from_compliances(&compliance_pairs);
+        let resource_merkle_tree = witnesses.resource_tree(&compliance_pairs);
 
         // Create resource logic circuit and complete the resource info
         let input_resource_resource_logics_1 = {
-            let input_resource_path_1 = resource_merkle_tree
-                .generate_path(input_resource_nf_1)
+            let input_resource_witness_1 = witnesses
+                .existence_witness(&resource_merkle_tree, &input_resource_1, true)
                 .unwrap();
-            let input_resource_application_logic_1 =
-                TrivialResourceLogicCircuit::new(input_resource_1, input_resource_path_1);
+            let input_resource_application_logic_1 = TrivialResourceLogicCircuit::new(
+                input_resource_witness_1.get_resource(),
+                input_resource_witness_1.get_path(),
+            );
             ResourceLogics::new(
                 Box::new(input_resource_application_logic_1.clone()),
                 vec![
@@ -575,35 +1656,41 @@ pub mod testing {
         };
 
         let output_resource_resource_logics_1 = {
-            let output_resource_path_1 = resource_merkle_tree
-                .generate_path(output_resource_cm_1)
+            let output_resource_witness_1 = witnesses
+                .existence_witness(&resource_merkle_tree, &output_resource_1, false)
                 .unwrap();
-            let output_resource_application_logic_1 =
-                TrivialResourceLogicCircuit::new(output_resource_1, output_resource_path_1);
+            let output_resource_application_logic_1 = TrivialResourceLogicCircuit::new(
+                output_resource_witness_1.get_resource(),
+                output_resource_witness_1.get_path(),
+            );
             ResourceLogics::new(Box::new(output_resource_application_logic_1), vec![])
         };
 
         let input_resource_resource_logics_2 = {
-            let input_resource_path_2 = resource_merkle_tree
-                .generate_path(input_resource_nf_2)
+            let input_resource_witness_2 = witnesses
+                .existence_witness(&resource_merkle_tree, &input_resource_2, true)
                 .unwrap();
-            let input_resource_application_logic_2 =
-                TrivialResourceLogicCircuit::new(input_resource_2, input_resource_path_2);
+            let input_resource_application_logic_2 = TrivialResourceLogicCircuit::new(
+                input_resource_witness_2.get_resource(),
+                input_resource_witness_2.get_path(),
+            );
             ResourceLogics::new(Box::new(input_resource_application_logic_2), vec![])
         };
 
         let output_resource_resource_logics_2 = {
-            let output_resource_path_2 = resource_merkle_tree
-                .generate_path(output_resource_cm_2)
+            let output_resource_witness_2 = witnesses
+                .existence_witness(&resource_merkle_tree, &output_resource_2, false)
                 .unwrap();
-            let output_resource_application_logic_2 =
-                TrivialResourceLogicCircuit::new(output_resource_2, output_resource_path_2);
+            let output_resource_application_logic_2 = TrivialResourceLogicCircuit::new(
+                output_resource_witness_2.get_resource(),
+                output_resource_witness_2.get_path(),
+            );
             ResourceLogics::new(Box::new(output_resource_application_logic_2), vec![])
         };
 
         // Create shielded partial tx
-        ShieldedPartialTransaction::build(
-            vec![compliance_1, compliance_2],
+        ShieldedPartialTransaction::build_with_witnesses(
+            compliance_pairs,
             vec![
                 input_resource_resource_logics_1,
                 input_resource_resource_logics_2,
@@ -613,8 +1700,144 @@ pub mod testing {
                 output_resource_resource_logics_2,
             ],
             vec![],
+            &mut witnesses,
             &mut rng,
         )
         .unwrap()
     }
+
+    #[test]
+    fn test_shielded_ptx_resource_logic_claims() {
+        let ptx = create_shielded_ptx();
+        assert!(ptx.check_nullifiers().is_ok());
+        assert!(ptx.check_resource_commitments().is_ok());
+
+        // An input resource without resource logic proofs
+        let mut missing_claim_ptx = ptx.clone();
+        missing_claim_ptx.inputs.pop();
+        assert!(matches!(
+            missing_claim_ptx.check_nullifiers(),
+            Err(TransactionError::MissingResourceLogicProof(_))
+        ));
+
+        // An output resource claimed by two resource logic sets
+        let mut duplicate_claim_ptx = ptx.clone();
+        duplicate_claim_ptx
+            .outputs
+            .push(duplicate_claim_ptx.outputs[0].clone());
+        assert!(matches!(
+            duplicate_claim_ptx.check_resource_commitments(),
+            Err(TransactionError::DuplicateResourceLogicProof(_))
+        ));
+
+        // Input resource logic sets can't claim output resources
+        let mut unknown_claim_ptx = ptx;
+        unknown_claim_ptx.inputs[0] = unknown_claim_ptx.outputs[0].clone();
+        assert!(matches!(
+            unknown_claim_ptx.check_nullifiers(),
+            Err(TransactionError::UnknownOwnedResource(_))
+        ));
+    }
+
+    #[test]
+    fn test_shielded_ptx_batch_verify() {
+        let ptx = create_shielded_ptx();
+        let mut batch = BatchVerifier::new();
+        ptx.batch_execute(&mut batch).unwrap();
+        assert!(batch.finalize().is_ok());
+
+        // Swapping the instances of the compliance proofs fails the batch
+        let mut invalid_ptx = ptx.clone();
+        invalid_ptx.compliances[0].compliance_instance =
+            ptx.compliances[1].compliance_instance.clone();
+        invalid_ptx.compliances[1].compliance_instance =
+            ptx.compliances[0].compliance_instance.clone();
+        let mut batch = BatchVerifier::new();
+        ptx.batch_verify_proof(&mut batch).unwrap();
+        assert!(invalid_ptx.batch_verify_proof(&mut batch).is_err() || batch.finalize().is_err());
+    }
+
+    #[test]
+    fn test_shielded_ptx_phases() {
+        let mut rng = OsRng;
+        let mut proven = ProvenPartialTransaction::from(create_shielded_ptx());
+        proven.set_hints(vec![1, 2, 3]);
+        let sealed = proven.seal().unwrap();
+        assert_eq!(sealed.get_hints(), vec![1, 2, 3]);
+
+        #[cfg(feature = "borsh")]
+        let sealed = {
+            use borsh::BorshDeserialize;
+            let bytes = borsh::to_vec(&sealed).unwrap();
+            crate::shielded_ptx::SealedPartialTransaction::deserialize(&mut bytes.as_ref()).unwrap()
+        };
+        let tx = Transaction::build(
+            &mut rng,
+            ShieldedPartialTxBundle::new(vec![sealed.into()]),
+            TransparentPartialTxBundle::default(),
+        )
+        .unwrap();
+        tx.execute().unwrap();
+
+        // An invalid ptx can't be sealed
+        let mut invalid_ptx = create_shielded_ptx();
+        invalid_ptx.compliances[0].compliance_instance =
+            invalid_ptx.compliances[1].compliance_instance.clone();
+        assert!(ProvenPartialTransaction::from(invalid_ptx.clone())
+            .seal()
+            .is_err());
+
+        // Nor deserialized as a sealed ptx
+        #[cfg(feature = "borsh")]
+        {
+            use borsh::BorshDeserialize;
+            let bytes = borsh::to_vec(&invalid_ptx).unwrap();
+            assert!(crate::shielded_ptx::SealedPartialTransaction::deserialize(
+                &mut bytes.as_ref()
+            )
+            .is_err());
+        }
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&invalid_ptx).unwrap();
+            assert!(
+                serde_json::from_str::<crate::shielded_ptx::SealedPartialTransaction>(&json)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_shielded_ptx_batched_verify_proof() {
+        let ptx = create_shielded_ptx();
+        ptx.verify_proof().unwrap();
+        ptx.verify_individual_proofs().unwrap();
+
+        // A single invalid proof fails the whole accumulator
+        let mut invalid_ptx = ptx.clone();
+        invalid_ptx.compliances[0].compliance_instance =
+            ptx.compliances[1].compliance_instance.clone();
+        assert!(invalid_ptx.verify_proof().is_err());
+        assert!(invalid_ptx.verify_individual_proofs().is_err());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_shielded_ptx_borsh_round_trip(ptx: ShieldedPartialTransaction) {
+            let bytes = borsh::to_vec(&ptx).unwrap();
+            let decoded: ShieldedPartialTransaction = borsh::from_slice(&bytes).unwrap();
+            prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_shielded_ptx_serde_round_trip(ptx: ShieldedPartialTransaction) {
+            let json = serde_json::to_string(&ptx).unwrap();
+            let decoded: ShieldedPartialTransaction = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+        }
+    }
 }
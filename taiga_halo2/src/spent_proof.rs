@@ -0,0 +1,182 @@
+//! A spent proof lets the owner of a resource show an auditor that a specific
+//! resource commitment was spent, by revealing the link between the
+//! commitment and its nullifier for that resource only. The auditor checks the
+//! proof and looks the nullifier up in the nullifier set; no viewing key is
+//! shared and the other resources of the owner stay unlinkable.
+use crate::{
+    circuit::spent_circuit::SpentCircuit,
+    constant::SPENT_CIRCUIT_PARAMS_SIZE,
+    error::TransactionError,
+    nullifier::Nullifier,
+    params::{ProvingContext, VerifyingContext},
+    proof::Proof,
+    resource::{Resource, ResourceCommitment},
+};
+use pasta_curves::pallas;
+use rand::RngCore;
+
+#[cfg(feature = "serde")]
+use serde;
+
+/// The public inputs of spent proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpentPublicInputs {
+    /// The commitment of the spent resource.
+    pub cm: ResourceCommitment,
+    /// The nullifier of the spent resource.
+    pub nf: Nullifier,
+    /// The audit epoch the proof is produced for. It binds the proof to the
+    /// epoch so that the consent given for one audit can't be replayed.
+    pub epoch: u64,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpentProof {
+    pub public_inputs: SpentPublicInputs,
+    pub proof: Proof,
+}
+
+impl SpentPublicInputs {
+    pub fn to_instance(&self) -> Vec<pallas::Base> {
+        vec![
+            self.cm.inner(),
+            self.nf.inner(),
+            pallas::Base::from(self.epoch),
+        ]
+    }
+}
+
+impl SpentProof {
+    /// Creates the spent proof of a resource. The nullifier key of the resource is required.
+    pub fn create<R: RngCore>(
+        resource: &Resource,
+        epoch: u64,
+        rng: R,
+    ) -> Result<Self, TransactionError> {
+        Self::create_with_context(resource, epoch, &ProvingContext::default(), rng)
+    }
+
+    /// Same as `create`, with the params and the keys of the context.
+    pub fn create_with_context<R: RngCore>(
+        resource: &Resource,
+        epoch: u64,
+        context: &ProvingContext,
+        rng: R,
+    ) -> Result<Self, TransactionError> {
+        let nf = resource
+            .get_nf()
+            .ok_or(TransactionError::MissingNullifierKey)?;
+        let public_inputs = SpentPublicInputs {
+            cm: resource.commitment(),
+            nf,
+            epoch,
+        };
+        let circuit = SpentCircuit {
+            resource: resource.clone(),
+            epoch,
+        };
+        let proof = Proof::create(
+            context.spent_proving_key(),
+            context.setup_params(SPENT_CIRCUIT_PARAMS_SIZE),
+            circuit,
+            &[&public_inputs.to_instance()],
+            rng,
+        )?;
+
+        Ok(Self {
+            public_inputs,
+            proof,
+        })
+    }
+
+    /// Verifies that the nullifier belongs to the commitment. The caller still
+    /// has to check that the nullifier is in the nullifier set.
+    pub fn verify(&self) -> Result<(), TransactionError> {
+        self.verify_with_context(&VerifyingContext::default())
+    }
+
+    /// Same as `verify`, with the params and the keys of the context.
+    pub fn verify_with_context(&self, context: &VerifyingContext) -> Result<(), TransactionError> {
+        self.proof.verify(
+            context.spent_verifying_key(),
+            context.setup_params(SPENT_CIRCUIT_PARAMS_SIZE),
+            &[&self.public_inputs.to_instance()],
+        )?;
+        Ok(())
+    }
+
+    /// Verifies the proof for the expected commitment and epoch, and returns the
+    /// nullifier to look up in the nullifier set.
+    pub fn verify_for(
+        &self,
+        cm: &ResourceCommitment,
+        epoch: u64,
+    ) -> Result<Nullifier, TransactionError> {
+        self.verify_for_with_context(cm, epoch, &VerifyingContext::default())
+    }
+
+    /// Same as `verify_for`, with the params and the keys of the context.
+    pub fn verify_for_with_context(
+        &self,
+        cm: &ResourceCommitment,
+        epoch: u64,
+        context: &VerifyingContext,
+    ) -> Result<Nullifier, TransactionError> {
+        if self.public_inputs.cm != *cm || self.public_inputs.epoch != epoch {
+            return Err(TransactionError::InvalidSpentProof);
+        }
+        self.verify_with_context(context)?;
+        Ok(self.public_inputs.nf)
+    }
+}
+
+#[test]
+fn test_spent_proof() {
+    use crate::resource::tests::random_resource;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let resource = random_resource(&mut rng);
+    let cm = resource.commitment();
+    let spent_proof = SpentProof::create(&resource, 1, &mut rng).unwrap();
+    assert_eq!(
+        spent_proof.verify_for(&cm, 1).unwrap(),
+        resource.get_nf().unwrap()
+    );
+
+    // The proof can't be replayed in another epoch
+    assert!(spent_proof.verify_for(&cm, 2).is_err());
+    let mut replayed = spent_proof.clone();
+    replayed.public_inputs.epoch = 2;
+    assert!(replayed.verify().is_err());
+
+    // The proof doesn't link another commitment
+    let other_cm = random_resource(&mut rng).commitment();
+    let mut forged = spent_proof;
+    forged.public_inputs.cm = other_cm;
+    assert!(forged.verify().is_err());
+}
+
+#[test]
+fn test_spent_proof_with_context() {
+    use crate::params::get_params;
+    use crate::resource::tests::random_resource;
+    use rand::rngs::OsRng;
+
+    // The keys are derived from the params injected by the context
+    let mut rng = OsRng;
+    let k = SPENT_CIRCUIT_PARAMS_SIZE;
+    let context = ProvingContext::default().with_params(k, get_params(k).unwrap().clone());
+    let resource = random_resource(&mut rng);
+    let cm = resource.commitment();
+    let spent_proof = SpentProof::create_with_context(&resource, 1, &context, &mut rng).unwrap();
+    assert_eq!(
+        spent_proof
+            .verify_for_with_context(&cm, 1, &context.verifying_context())
+            .unwrap(),
+        resource.get_nf().unwrap()
+    );
+    spent_proof.verify().unwrap();
+}
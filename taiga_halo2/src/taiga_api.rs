@@ -6,6 +6,7 @@ use crate::{
 use crate::{
     error::TransactionError,
     nullifier::Nullifier,
+    quantity::Quantity,
     resource::Resource,
     shielded_ptx::ShieldedPartialTransaction,
     transaction::{ShieldedPartialTxBundle, Transaction, TransparentPartialTxBundle},
@@ -33,7 +34,7 @@ pub fn create_input_resource(
     logic: pallas::Base,
     label: pallas::Base,
     value: pallas::Base,
-    quantity: u64,
+    quantity: Quantity,
     nk: pallas::Base,
     is_ephemeral: bool,
 ) -> Resource {
@@ -56,7 +57,7 @@ pub fn create_output_resource(
     logic: pallas::Base,
     label: pallas::Base,
     value: pallas::Base,
-    quantity: u64,
+    quantity: Quantity,
     // The owner of output resource has the nullifier key and exposes the nullifier_key commitment to output creator.
     npk: pallas::Base,
     is_ephemeral: bool,
@@ -106,7 +107,7 @@ pub fn resource_deserialize(bytes: Vec<u8>) -> std::io::Result<Resource> {
 /// Shielded Partial Transaction layout:
 /// | Parameters                        | type                  | size(bytes)   |
 /// |       -                           |       -               |   -           |
-/// | 2 compliance proofs               | ComplianceVerifyingInfo| 4676 * 2      |
+/// | 2 compliance proofs               | ComplianceVerifyingInfo| 4716 * 2      |
 /// | input1 static resource_logic proof            | ResourceLogicVerifyingInfo       | 158216        |
 /// | input1 dynamic resource_logic num(by borsh)   | u32                   | 4             |
 /// | input1 dynamic resource_logic proof           | ResourceLogicVerifyingInfo       | 158216 * num  |
@@ -119,6 +120,8 @@ pub fn resource_deserialize(bytes: Vec<u8>) -> std::io::Result<Resource> {
 /// | output2 static resource_logic proof           | ResourceLogicVerifyingInfo       | 158216        |
 /// | output2 dynamic resource_logic num(by borsh)  | u32                   | 4             |
 /// | output2 dynamic resource_logic proofs         | ResourceLogicVerifyingInfo       | 158216 * num  |
+/// | referenced cm num(by borsh)       | u32                   | 4             |
+/// | referenced cms                    | pallas::Base          | 32 * num      |
 /// | binding_sig_r                     | Option<pallas::Scalar>| 1 or (1 + 32) |
 /// | hints                             | Vec<u8>               | -             |
 ///
@@ -202,6 +205,8 @@ pub fn create_transaction(
 /// | nullifiers     | pallas::Base | 32 * num   |
 /// | output cm num  | u32          | 4          |
 /// | output cms     | pallas::Base | 32 * num   |
+/// | referenced num | u32          | 4          |
+/// | referenced cms | pallas::Base | 32 * num   |
 ///
 #[cfg(feature = "borsh")]
 pub fn verify_transaction(tx_bytes: Vec<u8>) -> Result<TransactionResult, TransactionError> {
@@ -223,6 +228,98 @@ pub fn verify_shielded_partial_transaction(ptx_bytes: Vec<u8>) -> Result<(), Tra
     ptx.verify_proof()
 }
 
+/// The shortest path from resources to an executed transaction.
+#[cfg(feature = "borsh")]
+pub mod examples {
+    use crate::{
+        circuit::{
+            resource_logic_bytecode::ApplicationByteCode,
+            resource_logic_examples::{
+                TrivialResourceLogicCircuit, COMPRESSED_TRIVIAL_RESOURCE_LOGIC_VK,
+            },
+        },
+        error::TransactionError,
+        ledger::{ExecutionResult, LedgerState},
+        nullifier::NullifierKeyContainer,
+        resource_tree::ResourceExistenceWitness,
+        taiga_api::{create_input_resource, create_output_resource},
+        transaction::{Transaction, TransactionResult},
+    };
+    use ff::Field;
+    use pasta_curves::pallas;
+    use rand::{CryptoRng, RngCore};
+
+    /// Alice sends her 10 tokens to Bob: the resource of Alice is minted in
+    /// the ledger, spent in a ptx creating the resource of Bob, and the
+    /// transaction is executed against the ledger.
+    pub fn hello_transfer<R: RngCore + CryptoRng>(
+        mut rng: R,
+    ) -> Result<(LedgerState, ExecutionResult), TransactionError> {
+        let logic = *COMPRESSED_TRIVIAL_RESOURCE_LOGIC_VK;
+        let label = pallas::Base::from(1u64);
+        let alice_nk = pallas::Base::random(&mut rng);
+        let bob_npk = NullifierKeyContainer::from_key(pallas::Base::random(&mut rng)).get_npk();
+        let alice = create_input_resource(
+            logic,
+            label,
+            pallas::Base::zero(),
+            10u64.into(),
+            alice_nk,
+            false,
+        );
+        let bob = create_output_resource(
+            logic,
+            label,
+            pallas::Base::zero(),
+            10u64.into(),
+            bob_npk,
+            false,
+        );
+
+        // Mint the resource of Alice
+        let ledger = LedgerState::new();
+        ledger.apply(&TransactionResult {
+            anchors: vec![],
+            nullifiers: vec![],
+            output_cms: vec![alice.commitment()],
+            conversion_rules: vec![],
+            epochs: vec![],
+            blacklist_roots: vec![],
+        })?;
+        let merkle_path = ledger
+            .read_view()
+            .merkle_path(&alice.commitment())
+            .expect("the resource is minted");
+
+        // Build, prove and execute the transfer
+        let trivial_logic = |witness: ResourceExistenceWitness| {
+            let logic =
+                TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path());
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        };
+        let tx: Transaction = Transaction::builder()
+            .spend(alice, merkle_path, None)
+            .create(bob)
+            .add_resource_logic(true, 0, trivial_logic)
+            .add_resource_logic(false, 0, trivial_logic)
+            .finalize(&mut rng)?;
+        let result = ledger.execute(&tx)?;
+        Ok((ledger, result))
+    }
+
+    #[test]
+    fn test_hello_transfer() {
+        use rand::rngs::OsRng;
+
+        let (ledger, result) = hello_transfer(OsRng).unwrap();
+        assert!(ledger.contains_nullifier(&result.nullifiers[0]));
+        assert_eq!(ledger.commitments()[1..], result.output_cms[..]);
+        assert_eq!(ledger.anchor(), result.anchor);
+        // The ptx is shielded, its resources are not decoded
+        assert!(result.writes.is_empty());
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "borsh")]
 pub mod tests {
@@ -243,8 +340,8 @@ pub mod tests {
         }
 
         {
-            let mut output_resource = input_resource;
-            output_resource.nk_container = random_nullifier_key_commitment(&mut rng);
+            let mut output_resource = input_resource.clone();
+            output_resource.nk_container = random_nullifier_key_commitment(&mut rng).into();
             let bytes = resource_serialize(&output_resource).unwrap();
             let de_output_resource = resource_deserialize(bytes).unwrap();
             assert_eq!(output_resource, de_output_resource);
@@ -267,7 +364,7 @@ pub mod tests {
         let mut output_resource_1 = random_resource(&mut rng);
         let merkle_path_1 = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
         let compliance_1 = ComplianceInfo::new(
-            input_resource_1,
+            input_resource_1.clone(),
             merkle_path_1,
             None,
             &mut output_resource_1,
@@ -278,7 +375,7 @@ pub mod tests {
         let mut output_resource_2 = random_resource(&mut rng);
         let merkle_path_2 = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
         let compliance_2 = ComplianceInfo::new(
-            input_resource_2,
+            input_resource_2.clone(),
             merkle_path_2,
             None,
             &mut output_resource_2,
@@ -0,0 +1,282 @@
+//! JSON test vectors of the resource commitments, nullifiers, delta
+//! commitments, logic commitments and a full transaction, for the other
+//! implementations, e.g. the Elixir and Juvix ones, to check they compute the
+//! same values.
+//!
+//! Field elements, points and digests are the hex of their 32-byte encoding,
+//! the transaction the hex of its canonical encoding, see
+//! `Transaction::to_bytes`. The vectors are generated deterministically from
+//! `TEST_VECTOR_SEED` by the `export_test_vectors` example, in
+//! `fixtures/test_vectors.json`, and must be exported again whenever the
+//! commitments, the circuits or the encoding change, which
+//! `verify_test_vectors` catches. The tests embed them, missing vectors don't
+//! compile.
+use crate::{
+    builder::TransactionBuilder,
+    circuit::resource_logic_bytecode::ApplicationByteCode,
+    circuit::resource_logic_examples::{
+        TrivialResourceLogicCircuit, COMPRESSED_TRIVIAL_RESOURCE_LOGIC_VK,
+    },
+    constant::TAIGA_COMMITMENT_TREE_DEPTH,
+    delta_commitment::DeltaCommitment,
+    error::TransactionError,
+    merkle_tree::MerklePath,
+    nullifier::{Nullifier, NullifierKeyContainer},
+    quantity::Quantity,
+    resource::{Resource, ResourceKind},
+    resource_logic_commitment::ResourceLogicCommitment,
+    resource_tree::ResourceExistenceWitness,
+    transaction::Transaction,
+    utils::{from_hex, to_hex},
+};
+use ff::{Field, PrimeField};
+use pasta_curves::pallas;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::path::PathBuf;
+
+pub const TEST_VECTOR_SEED: u64 = 0;
+
+const RESOURCE_VECTOR_NUM: usize = 4;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ResourceVector {
+    pub logic: String,
+    pub label: String,
+    pub value: String,
+    pub quantity: u64,
+    pub nk: String,
+    pub npk: String,
+    pub nonce: String,
+    pub rseed: String,
+    pub is_ephemeral: bool,
+    pub psi: String,
+    pub rcm: String,
+    pub cm: String,
+    pub nf: String,
+}
+
+/// The delta commitment of the consumed resource `input` and the created
+/// resource `output`, the indexes of the resource vectors.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DeltaCommitmentVector {
+    pub input: usize,
+    pub output: usize,
+    pub blind: String,
+    pub delta: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LogicCommitmentVector {
+    pub logic: String,
+    pub rcm: String,
+    pub commitment: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TransactionVector {
+    pub tx: String,
+    pub txid: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TestVectors {
+    pub resources: Vec<ResourceVector>,
+    pub delta_commitments: Vec<DeltaCommitmentVector>,
+    pub logic_commitments: Vec<LogicCommitmentVector>,
+    pub transaction: TransactionVector,
+}
+
+impl From<&Resource> for ResourceVector {
+    fn from(resource: &Resource) -> Self {
+        Self {
+            logic: hex_field(&resource.get_logic()),
+            label: hex_field(&resource.get_label()),
+            value: hex_field(&resource.value),
+            quantity: resource.quantity.inner(),
+            nk: hex_field(&resource.get_nk().expect("the vectors are consumable")),
+            npk: hex_field(&resource.get_npk()),
+            nonce: hex_field(&resource.nonce.inner()),
+            rseed: hex_field(resource.rseed.expose_secret()),
+            is_ephemeral: resource.is_ephemeral,
+            psi: hex_field(&resource.get_psi()),
+            rcm: hex_field(&resource.get_rcm()),
+            cm: to_hex(&resource.commitment().to_bytes()),
+            nf: to_hex(
+                &resource
+                    .get_nf()
+                    .expect("the vectors are consumable")
+                    .to_bytes(),
+            ),
+        }
+    }
+}
+
+impl TestVectors {
+    /// Generates the vectors from `TEST_VECTOR_SEED`, proving the transaction.
+    pub fn generate() -> Result<Self, TransactionError> {
+        let mut rng = ChaCha20Rng::seed_from_u64(TEST_VECTOR_SEED);
+        let resources = random_resources(&mut rng);
+        let delta_commitments = delta_commitment_vectors(&resources, &mut rng);
+        let logic_commitments = logic_commitment_vectors(&mut rng);
+
+        // A transfer of a resource of the trivial logic
+        let mut input_resource = resources[0].clone();
+        input_resource.kind = ResourceKind::new(
+            *COMPRESSED_TRIVIAL_RESOURCE_LOGIC_VK,
+            input_resource.get_label(),
+        );
+        let mut output_resource = resources[1].clone();
+        output_resource.kind = input_resource.kind;
+        output_resource.quantity = input_resource.quantity;
+        let trivial_logic = |witness: ResourceExistenceWitness| {
+            let logic =
+                TrivialResourceLogicCircuit::new(witness.get_resource(), witness.get_path());
+            ApplicationByteCode::new(logic.to_bytecode(), vec![])
+        };
+        let mut builder = TransactionBuilder::new();
+        builder
+            .spend(
+                input_resource,
+                MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+                None,
+            )
+            .create(output_resource)
+            .add_resource_logic(true, 0, trivial_logic)
+            .add_resource_logic(false, 0, trivial_logic);
+        let tx = builder.finalize(&mut rng)?;
+
+        Ok(Self {
+            resources: resources.iter().map(ResourceVector::from).collect(),
+            delta_commitments,
+            logic_commitments,
+            transaction: TransactionVector {
+                tx: to_hex(&tx.to_bytes()),
+                txid: tx.txid().to_string(),
+            },
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("the vectors are serializable")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+pub fn test_vectors_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join("test_vectors.json")
+}
+
+/// The exported vectors, if any.
+pub fn load_test_vectors() -> Option<String> {
+    std::fs::read_to_string(test_vectors_path()).ok()
+}
+
+/// Generates the vectors, checks the transaction and writes them to
+/// `test_vectors_path`.
+pub fn export_test_vectors() -> Result<(), TransactionError> {
+    let vectors = TestVectors::generate()?;
+    let bytes =
+        from_hex(&vectors.transaction.tx).ok_or(TransactionError::InvalidTransactionEncoding)?;
+    Transaction::from_bytes(&bytes)?.execute()?;
+    let path = test_vectors_path();
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    std::fs::write(path, vectors.to_json())?;
+    Ok(())
+}
+
+fn hex_field<F: PrimeField<Repr = [u8; 32]>>(field: &F) -> String {
+    to_hex(&field.to_repr())
+}
+
+// Consumable resources, every other one ephemeral
+fn random_resources<R: RngCore>(mut rng: R) -> Vec<Resource> {
+    (0..RESOURCE_VECTOR_NUM)
+        .map(|i| {
+            let kind = ResourceKind::new(
+                pallas::Base::random(&mut rng),
+                pallas::Base::random(&mut rng),
+            );
+            Resource {
+                kind,
+                value: pallas::Base::random(&mut rng),
+                quantity: Quantity::new(rng.gen()),
+                nk_container: NullifierKeyContainer::from_key(pallas::Base::random(&mut rng))
+                    .into(),
+                is_ephemeral: i % 2 == 1,
+                nonce: Nullifier::from(pallas::Base::random(&mut rng)),
+                rseed: pallas::Base::random(&mut rng).into(),
+            }
+        })
+        .collect()
+}
+
+// The delta commitments of the pairs of consecutive resources
+fn delta_commitment_vectors<R: RngCore>(
+    resources: &[Resource],
+    mut rng: R,
+) -> Vec<DeltaCommitmentVector> {
+    (0..resources.len() / 2)
+        .map(|i| {
+            let (input, output) = (2 * i, 2 * i + 1);
+            let blind = pallas::Scalar::random(&mut rng);
+            let delta = DeltaCommitment::commit(&resources[input], &resources[output], &blind);
+            DeltaCommitmentVector {
+                input,
+                output,
+                blind: hex_field(&blind),
+                delta: to_hex(&delta.to_bytes()),
+            }
+        })
+        .collect()
+}
+
+fn logic_commitment_vectors<R: RngCore>(mut rng: R) -> Vec<LogicCommitmentVector> {
+    (0..2)
+        .map(|_| {
+            let logic = pallas::Base::random(&mut rng);
+            let rcm = pallas::Base::random(&mut rng);
+            LogicCommitmentVector {
+                logic: hex_field(&logic),
+                rcm: hex_field(&rcm),
+                commitment: to_hex(&ResourceLogicCommitment::commit(&logic, &rcm).to_bytes()),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn verify_test_vectors() {
+    let Some(json) = load_test_vectors() else {
+        eprintln!("skipping test vectors: not exported, see export_test_vectors");
+        return;
+    };
+    let vectors = TestVectors::from_json(&json).expect("the exported vectors are valid json");
+
+    // The vectors without proofs are reproduced from the seed
+    let mut rng = ChaCha20Rng::seed_from_u64(TEST_VECTOR_SEED);
+    let resources = random_resources(&mut rng);
+    let resource_vectors: Vec<ResourceVector> =
+        resources.iter().map(ResourceVector::from).collect();
+    assert_eq!(vectors.resources, resource_vectors);
+    assert_eq!(
+        vectors.delta_commitments,
+        delta_commitment_vectors(&resources, &mut rng)
+    );
+    assert_eq!(
+        vectors.logic_commitments,
+        logic_commitment_vectors(&mut rng)
+    );
+
+    // The transaction is valid, canonically encoded and has the same id
+    let bytes = from_hex(&vectors.transaction.tx).unwrap();
+    let tx = Transaction::from_bytes(&bytes).unwrap();
+    tx.execute().unwrap();
+    assert_eq!(tx.to_bytes(), bytes);
+    assert_eq!(tx.txid().to_string(), vectors.transaction.txid);
+}
@@ -0,0 +1,25 @@
+//! Spans of the proving pipeline: keygen, witness synthesis, proof creation
+//! and verification, so that integrators can profile where the time of
+//! building a transaction goes.
+//!
+//! The spans are recorded with the `tracing` crate when the `tracing` feature
+//! is enabled, integrators install the subscriber of their choice. They are
+//! no-ops otherwise.
+
+/// The guard of an entered span, exiting it when dropped.
+pub struct SpanGuard(#[cfg(feature = "tracing")] tracing::span::EnteredSpan);
+
+/// Enters the span of the pipeline `step` for the resource logic `circuit`.
+/// Used by the resource logic macros, which expand in the crates of the
+/// logics, where the `tracing` feature of this crate can't be checked.
+pub fn enter(step: &'static str, circuit: &'static str) -> SpanGuard {
+    #[cfg(feature = "tracing")]
+    {
+        SpanGuard(tracing::info_span!("resource_logic", step, circuit).entered())
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = (step, circuit);
+        SpanGuard()
+    }
+}
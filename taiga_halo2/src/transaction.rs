@@ -1,14 +1,35 @@
-use crate::binding_signature::{BindingSignature, BindingSigningKey, BindingVerificationKey};
-use crate::constant::TRANSACTION_BINDING_HASH_PERSONALIZATION;
-use crate::delta_commitment::DeltaCommitment;
+use crate::auxiliary_delta::AuxiliaryDelta;
+use crate::binding_signature::{
+    BindingNonceCommitment, BindingSignature, BindingSignatureShare, BindingSigningKey,
+    BindingSigningSession, BindingVerificationKey,
+};
+use crate::builder::TransactionBuilder;
+use crate::circuit::resource_logic_circuit::{
+    ResourceLogicPublicInputs, ResourceLogicVerifyingInfo,
+};
+use crate::constant::{
+    RESOURCE_LOGIC_CIRCUIT_MAILBOX_IDX, TRANSACTION_BINDING_HASH_PERSONALIZATION,
+    TRANSACTION_ID_PERSONALIZATION, TRANSACTION_PROOFS_HASH_PERSONALIZATION,
+};
+use crate::conversion::ConversionRuleId;
+use crate::delta_commitment::{BalanceSummary, DeltaCommitment};
 use crate::error::TransactionError;
-use crate::executable::Executable;
+use crate::executable::{Executable, StateWrite};
+use crate::fee::Fee;
+use crate::linkability::LinkabilityTag;
 use crate::merkle_tree::Anchor;
 use crate::nullifier::Nullifier;
+use crate::params::VerifyingContext;
+use crate::proof::{BatchVerifier, Proof};
+use crate::pruned_transaction::PrunedTransaction;
 use crate::resource::ResourceCommitment;
-use crate::shielded_ptx::ShieldedPartialTransaction;
+use crate::resource_encryption::{Mailbox, Memo};
+use crate::shielded_ptx::{check_unique_compliances, ShieldedPartialTransaction};
 use crate::transparent_ptx::TransparentPartialTransaction;
+use crate::zkvm_receipt::ZkvmReceipt;
 use blake2b_simd::Params as Blake2bParams;
+use blake2s_simd::Params as Blake2sParams;
+use ff::PrimeField;
 use pasta_curves::{group::Group, pallas};
 use rand::{CryptoRng, RngCore};
 
@@ -21,6 +42,11 @@ use serde;
 #[cfg(feature = "borsh")]
 use borsh::{BorshDeserialize, BorshSerialize};
 
+/// The magic bytes of the canonical encoding of a transaction.
+pub const TRANSACTION_MAGIC: [u8; 4] = *b"TGTX";
+/// The version of the canonical encoding written by `Transaction::to_bytes`.
+pub const TRANSACTION_ENCODING_VERSION: u8 = 7;
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -30,6 +56,210 @@ pub struct Transaction {
     transparent_ptx_bundle: TransparentPartialTxBundle,
     // binding signature to check balance
     signature: BindingSignature,
+    // The encrypted memos of the created resources, see `Memo`
+    memos: Vec<Memo>,
+    // The fee paid to the executor, see `Fee`
+    fee: Option<Fee>,
+    // The zkVM receipts the resource logics delegate to, see `ZkvmReceipt`
+    receipts: Vec<ZkvmReceipt>,
+    // The mailboxes the resource logics leave to their owners, see `Mailbox`
+    mailboxes: Vec<Mailbox>,
+}
+
+/// The layout of the body of a version of the canonical encoding of
+/// transactions, see `Transaction::to_bytes`. A change of the borsh layout of
+/// the transaction or of a type it carries bumps
+/// `TRANSACTION_ENCODING_VERSION` with a new layout.
+#[cfg(feature = "borsh")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodingLayout {
+    /// The encrypted memos of the transaction, since version 2
+    pub memos: bool,
+    /// The fee of the transaction, since version 3
+    pub fee: bool,
+    /// The zkVM receipts of the transaction, since version 4
+    pub receipts: bool,
+    /// The mailboxes of the transaction, since version 5
+    pub mailboxes: bool,
+    /// The cms read by the shielded ptxs and the resources read by the
+    /// transparent ones, until version 3. They are only decoded when empty.
+    pub references: bool,
+    /// The conversion rule of the compliances, since version 2
+    pub conversion: bool,
+    /// The epoch of the compliances, since version 4
+    pub epoch: bool,
+    /// The Wasm and the Noir representations of the logics right after the
+    /// VampIR one instead of at the end, until version 5
+    pub interleaved_representations: bool,
+    /// The resources read by the shielded ptxs, proven by membership, since
+    /// version 7
+    pub reads: bool,
+}
+
+#[cfg(feature = "borsh")]
+impl EncodingLayout {
+    /// The layout of a version of the encoding, none if the version is unknown.
+    pub fn of(version: u8) -> Option<Self> {
+        match version {
+            1 => Some(Self {
+                memos: false,
+                fee: false,
+                receipts: false,
+                mailboxes: false,
+                references: true,
+                conversion: false,
+                epoch: false,
+                interleaved_representations: true,
+                reads: false,
+            }),
+            2 => Some(Self {
+                memos: true,
+                conversion: true,
+                ..Self::of(1)?
+            }),
+            3 => Some(Self {
+                fee: true,
+                ..Self::of(2)?
+            }),
+            4 => Some(Self {
+                receipts: true,
+                references: false,
+                epoch: true,
+                ..Self::of(3)?
+            }),
+            5 => Some(Self {
+                mailboxes: true,
+                ..Self::of(4)?
+            }),
+            6 => Some(Self {
+                interleaved_representations: false,
+                ..Self::of(5)?
+            }),
+            7 => Some(Self {
+                reads: true,
+                ..Self::of(6)?
+            }),
+            _ => None,
+        }
+    }
+
+    /// The layout of `TRANSACTION_ENCODING_VERSION`
+    pub fn current() -> Self {
+        Self::of(TRANSACTION_ENCODING_VERSION).expect("the current version has a layout")
+    }
+
+    // Writes the empty references of the ptxs if they are in the layout
+    pub(crate) fn serialize_references<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+    ) -> std::io::Result<()> {
+        if self.references {
+            0u32.serialize(writer)?;
+        }
+        Ok(())
+    }
+
+    // Reads the references of the ptxs if they are in the layout, only the
+    // empty ones are supported
+    pub(crate) fn deserialize_references<R: std::io::Read>(
+        &self,
+        reader: &mut R,
+    ) -> std::io::Result<()> {
+        if self.references && u32::deserialize_reader(reader)? != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "the references of the resources read are not supported",
+            ));
+        }
+        Ok(())
+    }
+
+    // The error of a field of a value that the layout can't encode
+    pub(crate) fn unsupported(field: &str) -> std::io::Error {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{field} can't be encoded in the layout"),
+        )
+    }
+}
+
+/// The borsh encoding of the types whose layout changed across the versions
+/// of the canonical encoding of transactions, see `EncodingLayout`. The borsh
+/// encoding of a type is its encoding in the current layout.
+#[cfg(feature = "borsh")]
+pub(crate) trait VersionedBorsh: Sized {
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<()>;
+
+    fn deserialize_versioned<R: std::io::Read>(
+        reader: &mut R,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<Self>;
+}
+
+#[cfg(feature = "borsh")]
+impl<T: VersionedBorsh> VersionedBorsh for Vec<T> {
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<()> {
+        let len = u32::try_from(self.len()).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "too many elements")
+        })?;
+        len.serialize(writer)?;
+        self.iter()
+            .try_for_each(|item| item.serialize_versioned(writer, layout))
+    }
+
+    fn deserialize_versioned<R: std::io::Read>(
+        reader: &mut R,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<Self> {
+        let len = u32::deserialize_reader(reader)?;
+        (0..len)
+            .map(|_| T::deserialize_versioned(reader, layout))
+            .collect()
+    }
+}
+
+/// The canonical identifier of a transaction, see `Transaction::txid`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TxId([u8; 32]);
+
+impl TxId {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl std::fmt::Display for TxId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+/// A transaction without its binding signature, to be signed apart from where
+/// it is built, e.g. by a hardware wallet or a remote service. It keeps the
+/// binding randomness of the shielded ptxs, the signing key, so it must only
+/// be shipped to the signer.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnsignedTransaction {
+    shielded_ptx_bundle: ShieldedPartialTxBundle,
+    transparent_ptx_bundle: TransparentPartialTxBundle,
+    memos: Vec<Memo>,
+    fee: Option<Fee>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -41,6 +271,15 @@ pub struct TransactionResult {
     pub anchors: Vec<Anchor>,
     pub nullifiers: Vec<Nullifier>,
     pub output_cms: Vec<ResourceCommitment>,
+    /// The ids of the conversion rules applied by the compliances, which must
+    /// be authorized by the issuers of the target kinds.
+    pub conversion_rules: Vec<ConversionRuleId>,
+    /// The epochs the compliances are proven at, which must be the current
+    /// epoch of the executor.
+    pub epochs: Vec<u64>,
+    /// The roots of the blacklists the blacklist logics are proven against,
+    /// which must be the root of the blacklist enforced by the executor.
+    pub blacklist_roots: Vec<Anchor>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -56,66 +295,518 @@ pub struct ShieldedPartialTxBundle(Vec<ShieldedPartialTransaction>);
 pub struct TransparentPartialTxBundle(Vec<TransparentPartialTransaction>);
 
 impl Transaction {
+    /// A builder assembling the compliances, the logics and the binding
+    /// signature of a transaction, see [`TransactionBuilder`].
+    pub fn builder() -> TransactionBuilder {
+        TransactionBuilder::new()
+    }
+
     // Generate the transaction
     pub fn build<R: RngCore + CryptoRng>(
         rng: R,
+        shielded_ptx_bundle: ShieldedPartialTxBundle,
+        transparent_ptx_bundle: TransparentPartialTxBundle,
+    ) -> Result<Self, TransactionError> {
+        let unsigned = UnsignedTransaction::new(shielded_ptx_bundle, transparent_ptx_bundle);
+        let signature = unsigned.sign(rng)?;
+        Ok(unsigned.into_transaction(signature))
+    }
+
+    /// The signing session of a transaction assembled from the ptxs of
+    /// several parties, none of which knows the binding randomness of the
+    /// others. The bundles must not change after the session is started.
+    ///
+    /// Each party publishes the commitment of a fresh `BindingNonce`, signs
+    /// its share with the sum of the binding randomness of its ptxs and the
+    /// shares are aggregated by [`Transaction::finalize`]. As with
+    /// `UnsignedTransaction::with_memos` and `UnsignedTransaction::with_fee`,
+    /// the memos and the fee are signed along with the bundles.
+    pub fn binding_signing_session(
+        shielded_ptx_bundle: &ShieldedPartialTxBundle,
+        transparent_ptx_bundle: &TransparentPartialTxBundle,
+        memos: &[Memo],
+        fee: Option<&Fee>,
+        nonce_commitments: &[BindingNonceCommitment],
+    ) -> BindingSigningSession {
+        let sig_hash = Self::digest(shielded_ptx_bundle, transparent_ptx_bundle, memos, fee);
+        let vk = Self::binding_vk(shielded_ptx_bundle, transparent_ptx_bundle, fee);
+        BindingSigningSession::new(&sig_hash, vk, nonce_commitments)
+    }
+
+    /// Generates the transaction from the binding signature shares of all the
+    /// parties of `session`, with the memos and the fee the session was
+    /// started with. Fails if a share is missing or invalid.
+    pub fn finalize(
         mut shielded_ptx_bundle: ShieldedPartialTxBundle,
         transparent_ptx_bundle: TransparentPartialTxBundle,
+        memos: Vec<Memo>,
+        fee: Option<Fee>,
+        session: &BindingSigningSession,
+        shares: &[BindingSignatureShare],
     ) -> Result<Self, TransactionError> {
         assert!(!(shielded_ptx_bundle.is_empty() && transparent_ptx_bundle.is_empty()));
-        let shielded_sk = shielded_ptx_bundle.get_binding_sig_r()?;
-        let binding_sk = BindingSigningKey::from(shielded_sk);
-        let sig_hash = Self::digest(&shielded_ptx_bundle, &transparent_ptx_bundle);
-        let signature = binding_sk.sign(rng, &sig_hash);
+        let signature = session.aggregate(shares);
         shielded_ptx_bundle.clean_private_info();
 
-        Ok(Self {
+        let tx = Self {
             shielded_ptx_bundle,
             transparent_ptx_bundle,
             signature,
-        })
+            memos,
+            fee,
+            receipts: vec![],
+            mailboxes: vec![],
+        };
+        tx.verify_binding_sig()?;
+        Ok(tx)
     }
 
     #[allow(clippy::type_complexity)]
     pub fn execute(&self) -> Result<TransactionResult, TransactionError> {
-        let mut result = self.shielded_ptx_bundle.execute()?;
+        self.execute_with_context(&VerifyingContext::default())
+    }
+
+    /// Same as `execute`, verifying the proofs with the params and the keys of
+    /// `context`.
+    #[allow(clippy::type_complexity)]
+    pub fn execute_with_context(
+        &self,
+        context: &VerifyingContext,
+    ) -> Result<TransactionResult, TransactionError> {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = self.execute_unmetered(context);
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_verification(crate::metrics::KIND_TRANSACTION, start, &result);
+        result
+    }
+
+    fn execute_unmetered(
+        &self,
+        context: &VerifyingContext,
+    ) -> Result<TransactionResult, TransactionError> {
+        let mut result = self.shielded_ptx_bundle.execute_with_context(context)?;
         let mut transparent_result = self.transparent_ptx_bundle.execute()?;
         result.append(&mut transparent_result);
 
         // check balance
-        self.verify_binding_sig()?;
+        self.verify_balance()?;
+        self.check_auxiliary_deltas()?;
+        self.check_memos()?;
+        self.check_receipts(context)?;
+        self.check_mailboxes()?;
+        #[cfg(feature = "apps")]
+        crate::apps::timelock::check_epoch(self, &result.epochs)?;
+        #[cfg(feature = "apps")]
+        crate::apps::sweep::check_sweeps(self, &result.epochs, context)?;
 
         Ok(result)
     }
 
+    /// Executes the transactions, accumulating the IPA checks of all their
+    /// proofs into a single batch check.
+    pub fn batch_verify(txs: &[Transaction]) -> Result<Vec<TransactionResult>, TransactionError> {
+        Self::batch_verify_with_context(txs, &VerifyingContext::default())
+    }
+
+    /// Same as `batch_verify`, with the params and the keys of `context`.
+    pub fn batch_verify_with_context(
+        txs: &[Transaction],
+        context: &VerifyingContext,
+    ) -> Result<Vec<TransactionResult>, TransactionError> {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = Self::batch_verify_unmetered(txs, context);
+        #[cfg(feature = "metrics")]
+        {
+            crate::metrics::record_histogram(crate::metrics::BATCH_SIZE, &[], txs.len() as f64);
+            crate::metrics::record_verification(crate::metrics::KIND_BATCH, start, &result);
+        }
+        result
+    }
+
+    fn batch_verify_unmetered(
+        txs: &[Transaction],
+        context: &VerifyingContext,
+    ) -> Result<Vec<TransactionResult>, TransactionError> {
+        let mut batch = BatchVerifier::new();
+        let results = txs
+            .iter()
+            .map(|tx| tx.batch_execute(&mut batch, context))
+            .collect::<Result<Vec<_>, _>>()?;
+        batch.finalize()?;
+        Ok(results)
+    }
+
+    fn batch_execute(
+        &self,
+        batch: &mut BatchVerifier,
+        context: &VerifyingContext,
+    ) -> Result<TransactionResult, TransactionError> {
+        let mut result = self
+            .shielded_ptx_bundle
+            .batch_execute_with_context(batch, context)?;
+        let mut transparent_result = self.transparent_ptx_bundle.execute()?;
+        result.append(&mut transparent_result);
+
+        // check balance
+        self.verify_balance()?;
+        self.check_auxiliary_deltas()?;
+        self.check_memos()?;
+        self.check_receipts(context)?;
+        self.check_mailboxes()?;
+        #[cfg(feature = "apps")]
+        crate::apps::timelock::check_epoch(self, &result.epochs)?;
+        #[cfg(feature = "apps")]
+        crate::apps::sweep::check_sweeps(self, &result.epochs, context)?;
+
+        Ok(result)
+    }
+
+    /// The canonical encoding of the transaction: the magic bytes, the version
+    /// of the encoding and the body. The body is the borsh encoding of the
+    /// transaction in the layout of the version, see `EncodingLayout`. The
+    /// layout of a version is frozen, a change of the borsh layout of the
+    /// transaction or of a type it carries bumps `TRANSACTION_ENCODING_VERSION`
+    /// and `from_bytes` keeps decoding the previous versions.
+    #[cfg(feature = "borsh")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = TRANSACTION_MAGIC.to_vec();
+        bytes.push(TRANSACTION_ENCODING_VERSION);
+        self.serialize(&mut bytes)
+            .expect("failure to serialize the transaction");
+        bytes
+    }
+
+    /// The canonical encoding of the transaction in a previous version, for
+    /// the peers still decoding it. It fails if the transaction carries what
+    /// the version can't encode, e.g. the mailboxes before version 5.
+    #[cfg(feature = "borsh")]
+    pub fn to_bytes_with_version(&self, version: u8) -> Result<Vec<u8>, TransactionError> {
+        let layout = EncodingLayout::of(version)
+            .ok_or(TransactionError::UnknownTransactionVersion(version))?;
+        let mut bytes = TRANSACTION_MAGIC.to_vec();
+        bytes.push(version);
+        self.serialize_versioned(&mut bytes, &layout)
+            .map_err(|_| TransactionError::InvalidTransactionEncoding)?;
+        Ok(bytes)
+    }
+
+    /// Decodes the canonical encoding of a transaction, see `to_bytes`.
+    #[cfg(feature = "borsh")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TransactionError> {
+        let (version, mut body) = bytes
+            .strip_prefix(&TRANSACTION_MAGIC)
+            .and_then(|bytes| bytes.split_first())
+            .ok_or(TransactionError::InvalidTransactionEncoding)?;
+        let layout = EncodingLayout::of(*version)
+            .ok_or(TransactionError::UnknownTransactionVersion(*version))?;
+        let tx = Self::deserialize_versioned(&mut body, &layout)
+            .map_err(|_| TransactionError::InvalidTransactionEncoding)?;
+        // Trailing bytes are not canonical
+        if !body.is_empty() {
+            return Err(TransactionError::InvalidTransactionEncoding);
+        }
+        Ok(tx)
+    }
+
+    /// The canonical identifier of the transaction, for executors and
+    /// indexers to key transactions by: the Blake2s digest of the fields of
+    /// the transaction, without the binding signature and the private info of
+    /// the ptxs, which can be changed without changing what the transaction
+    /// does. Unlike `id`, it commits to the proofs and the hints.
+    ///
+    /// The digest doesn't depend on the version of the encoding, see
+    /// `to_bytes`: a transaction decoded from a previous version keeps its id
+    /// once encoded with the current one.
+    #[cfg(feature = "borsh")]
+    pub fn txid(&self) -> TxId {
+        let mut shielded_ptx_bundle = self.shielded_ptx_bundle.clone();
+        shielded_ptx_bundle.clean_private_info();
+        let mut h = Blake2sParams::new()
+            .hash_length(32)
+            .personal(TRANSACTION_ID_PERSONALIZATION)
+            .to_state();
+        h.update(&borsh::to_vec(&shielded_ptx_bundle).expect("failure to serialize the ptxs"));
+        h.update(
+            &borsh::to_vec(&self.transparent_ptx_bundle).expect("failure to serialize the ptxs"),
+        );
+        h.update(&borsh::to_vec(&self.memos).expect("failure to serialize the memos"));
+        h.update(&borsh::to_vec(&self.fee).expect("failure to serialize the fee"));
+        h.update(&borsh::to_vec(&self.receipts).expect("failure to serialize the receipts"));
+        h.update(&borsh::to_vec(&self.mailboxes).expect("failure to serialize the mailboxes"));
+        TxId(h.finalize().as_bytes().try_into().unwrap())
+    }
+
+    /// The id of the transaction, the message of its binding signature.
+    pub fn id(&self) -> [u8; 32] {
+        Self::digest(
+            &self.shielded_ptx_bundle,
+            &self.transparent_ptx_bundle,
+            &self.memos,
+            self.fee.as_ref(),
+        )
+    }
+
+    /// Strips the proofs of a final transaction, keeping what the state needs
+    /// and the digests to check the full data served by archival nodes.
+    pub fn prune(&self) -> PrunedTransaction {
+        PrunedTransaction::new(
+            self.id(),
+            self.get_nullifiers(),
+            self.get_output_cms(),
+            self.shielded_ptx_bundle.get_logic_fingerprints(),
+            self.proofs_digest(),
+        )
+    }
+
+    /// The digest of the compliance and resource logic proofs.
+    pub fn proofs_digest(&self) -> [u8; 32] {
+        let mut h = Blake2bParams::new()
+            .hash_length(32)
+            .personal(TRANSACTION_PROOFS_HASH_PERSONALIZATION)
+            .to_state();
+        for proof in self.shielded_ptx_bundle.get_proofs() {
+            let bytes = proof.inner();
+            h.update(&(bytes.len() as u64).to_le_bytes());
+            h.update(&bytes);
+        }
+        h.finalize().as_bytes().try_into().unwrap()
+    }
+
+    pub fn get_nullifiers(&self) -> Vec<Nullifier> {
+        let mut nullifiers = self.shielded_ptx_bundle.get_nullifiers();
+        nullifiers.extend(self.transparent_ptx_bundle.get_nullifiers());
+        nullifiers
+    }
+
+    pub fn get_output_cms(&self) -> Vec<ResourceCommitment> {
+        let mut cms = self.shielded_ptx_bundle.get_output_cms();
+        cms.extend(self.transparent_ptx_bundle.get_output_cms());
+        cms
+    }
+
+    pub fn get_memos(&self) -> &[Memo] {
+        &self.memos
+    }
+
+    /// The fee the transaction pays to the executor, if any.
+    pub fn get_fee(&self) -> Option<Fee> {
+        self.fee
+    }
+
+    /// Attaches the zkVM receipts the resource logics delegate to. They are
+    /// not signed: the claim a logic publishes binds the program and the
+    /// journal of its receipt, so a receipt can only be swapped for another
+    /// proof of the same claim.
+    pub fn with_receipts(mut self, receipts: Vec<ZkvmReceipt>) -> Self {
+        self.receipts = receipts;
+        self
+    }
+
+    pub fn get_receipts(&self) -> &[ZkvmReceipt] {
+        &self.receipts
+    }
+
+    /// Attaches the mailboxes the resource logics publish, see
+    /// `mailbox_gadget`. Like the receipts they are not signed: the logic
+    /// publishes the commitment of its mailbox.
+    pub fn with_mailboxes(mut self, mailboxes: Vec<Mailbox>) -> Self {
+        self.mailboxes = mailboxes;
+        self
+    }
+
+    pub fn get_mailboxes(&self) -> &[Mailbox] {
+        &self.mailboxes
+    }
+
+    pub fn get_conversion_rules(&self) -> Vec<ConversionRuleId> {
+        let mut rules = self.shielded_ptx_bundle.get_conversion_rules();
+        rules.extend(self.transparent_ptx_bundle.get_conversion_rules());
+        rules
+    }
+
+    pub fn get_epochs(&self) -> Vec<u64> {
+        let mut epochs = self.shielded_ptx_bundle.get_epochs();
+        epochs.extend(self.transparent_ptx_bundle.get_epochs());
+        epochs
+    }
+
+    pub fn get_blacklist_roots(&self) -> Vec<Anchor> {
+        let mut roots = self.shielded_ptx_bundle.get_blacklist_roots();
+        roots.extend(self.transparent_ptx_bundle.get_blacklist_roots());
+        roots
+    }
+
+    /// The public inputs of the shielded resource logics.
+    pub fn get_resource_logic_public_inputs(&self) -> Vec<&ResourceLogicPublicInputs> {
+        self.shielded_ptx_bundle.get_resource_logic_public_inputs()
+    }
+
+    pub fn get_resource_logic_verifying_infos(&self) -> Vec<&ResourceLogicVerifyingInfo> {
+        self.shielded_ptx_bundle
+            .get_resource_logic_verifying_infos()
+    }
+
+    /// The public inputs of the shielded resource logics with the fingerprint
+    /// `logic`.
+    pub fn get_logic_public_inputs(&self, logic: &pallas::Base) -> Vec<&ResourceLogicPublicInputs> {
+        self.shielded_ptx_bundle.get_logic_public_inputs(logic)
+    }
+
+    /// The linkability tags published by the shielded resource logics with
+    /// the fingerprint `logic`.
+    pub fn get_linkability_tags(&self, logic: &pallas::Base) -> Vec<LinkabilityTag> {
+        self.shielded_ptx_bundle.get_linkability_tags(logic)
+    }
+
+    // The commitments of the consumed resources are hidden in shielded ptxs,
+    // only the transparent ones are returned.
+    pub fn get_input_cms(&self) -> Vec<ResourceCommitment> {
+        self.transparent_ptx_bundle.get_input_cms()
+    }
+
+    /// The writes of the transparent ptxs to the state of the apps, see
+    /// `TransparentPartialTransaction::get_state_writes`.
+    pub fn get_state_writes(&self) -> Vec<StateWrite> {
+        self.transparent_ptx_bundle.get_state_writes()
+    }
+
+    /// Checks that the resources of every kind balance. The kinds of the
+    /// shielded resources are hidden in their delta commitments, so only the
+    /// transparent kinds can be reported: if the binding signature fails, the
+    /// first transparent kind left unbalanced is reported, the shielded
+    /// resources otherwise.
+    pub fn check_balance(&self) -> Result<(), TransactionError> {
+        let mut summary = self.transparent_ptx_bundle.get_balance_summary();
+        if let Some(fee) = self.fee {
+            summary.add(fee.kind(), -i128::from(fee.quantity().inner()));
+        }
+        let unbalanced = summary.unbalanced_kinds();
+        if !self.shielded_ptx_bundle.is_empty() && self.verify_binding_sig().is_ok() {
+            return Ok(());
+        }
+        match unbalanced.first() {
+            Some(kind) => Err(TransactionError::UnbalancedResourceKind(*kind)),
+            None if self.shielded_ptx_bundle.is_empty() => Ok(()),
+            None => Err(TransactionError::UnbalancedShieldedResources),
+        }
+    }
+
+    // Checks the binding signature, reporting the unbalanced transparent kind
+    // if it fails
+    fn verify_balance(&self) -> Result<(), TransactionError> {
+        self.verify_binding_sig()
+            .map_err(|err| match self.check_balance() {
+                Err(TransactionError::UnbalancedResourceKind(kind)) => {
+                    TransactionError::UnbalancedResourceKind(kind)
+                }
+                _ => err,
+            })
+    }
+
     fn verify_binding_sig(&self) -> Result<(), TransactionError> {
-        let binding_vk = self.get_binding_vk();
-        let sig_hash = Self::digest(&self.shielded_ptx_bundle, &self.transparent_ptx_bundle);
+        let binding_vk = BindingVerificationKey::from(Self::binding_vk(
+            &self.shielded_ptx_bundle,
+            &self.transparent_ptx_bundle,
+            self.fee.as_ref(),
+        ));
+        let sig_hash = self.id();
         binding_vk
             .verify(&sig_hash, &self.signature)
             .map_err(|_| TransactionError::InvalidBindingSignature)
     }
 
-    fn get_binding_vk(&self) -> BindingVerificationKey {
-        let mut vk = pallas::Point::identity();
-        vk = self
-            .shielded_ptx_bundle
-            .get_delta_commitments()
+    // Every memo is attached to a resource created by the transaction
+    fn check_memos(&self) -> Result<(), TransactionError> {
+        let output_cms = self.get_output_cms();
+        if self
+            .memos
             .iter()
-            .fold(vk, |acc, cv| acc + cv.inner());
+            .all(|memo| memo.is_well_formed() && output_cms.contains(&memo.output_cm()))
+        {
+            Ok(())
+        } else {
+            Err(TransactionError::InvalidMemo)
+        }
+    }
+
+    // Every resource logic delegating to a zkVM program has the receipt of its
+    // claim, and every receipt is claimed by a logic and verifies with the
+    // verifier of `context`
+    fn check_receipts(&self, context: &VerifyingContext) -> Result<(), TransactionError> {
+        let infos = self.get_resource_logic_verifying_infos();
+        for info in infos.iter() {
+            if ZkvmReceipt::is_claimed_by(&info.extra_instances)
+                && !self
+                    .receipts
+                    .iter()
+                    .any(|receipt| receipt.check_claim(&info.extra_instances).is_ok())
+            {
+                return Err(TransactionError::MissingReceipt);
+            }
+        }
+        for receipt in self.receipts.iter() {
+            let info = infos
+                .iter()
+                .find(|info| receipt.check_claim(&info.extra_instances).is_ok())
+                .ok_or(TransactionError::ReceiptClaimMismatch)?;
+            let verifier = context
+                .receipt_verifier()
+                .ok_or(TransactionError::MissingReceiptVerifier)?;
+            receipt.verify(info, verifier)?;
+        }
+        Ok(())
+    }
+
+    // Every mailbox is published by a shielded resource logic
+    fn check_mailboxes(&self) -> Result<(), TransactionError> {
+        let public_inputs = self.get_resource_logic_public_inputs();
+        for mailbox in self.mailboxes.iter() {
+            if !mailbox.is_well_formed() {
+                return Err(TransactionError::InvalidMailbox);
+            }
+            let commitment = mailbox.commitment();
+            if !public_inputs.iter().any(|public_inputs| {
+                public_inputs.get_from_index(RESOURCE_LOGIC_CIRCUIT_MAILBOX_IDX) == commitment
+            }) {
+                return Err(TransactionError::MailboxCommitmentMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    // The app-defined conservation laws hold if the auxiliary deltas each
+    // logic contributes to the shielded ptxs sum to zero.
+    fn check_auxiliary_deltas(&self) -> Result<(), TransactionError> {
+        AuxiliaryDelta::check_balances(&self.shielded_ptx_bundle.get_auxiliary_deltas()?)
+    }
 
-        vk = self
-            .transparent_ptx_bundle
+    // The sum of the delta commitments, minus the fee they leave unbalanced
+    fn binding_vk(
+        shielded_bundle: &ShieldedPartialTxBundle,
+        transparent_bundle: &TransparentPartialTxBundle,
+        fee: Option<&Fee>,
+    ) -> pallas::Point {
+        let mut vk = fee.map_or(pallas::Point::identity(), |fee| {
+            -fee.delta_commitment().inner()
+        });
+        vk = shielded_bundle
             .get_delta_commitments()
             .iter()
             .fold(vk, |acc, cv| acc + cv.inner());
 
-        BindingVerificationKey::from(vk)
+        transparent_bundle
+            .get_delta_commitments()
+            .iter()
+            .fold(vk, |acc, cv| acc + cv.inner())
     }
 
     fn digest(
         shielded_bundle: &ShieldedPartialTxBundle,
         transparent_bundle: &TransparentPartialTxBundle,
+        memos: &[Memo],
+        fee: Option<&Fee>,
     ) -> [u8; 32] {
         let mut h = Blake2bParams::new()
             .hash_length(32)
@@ -153,6 +844,14 @@ impl Transaction {
         transparent_bundle.get_anchors().iter().for_each(|anchor| {
             h.update(&anchor.to_bytes());
         });
+        memos.iter().for_each(|memo| {
+            h.update(&memo.to_bytes());
+        });
+        if let Some(fee) = fee {
+            h.update(&fee.kind().logic.to_repr());
+            h.update(&fee.kind().label.to_repr());
+            h.update(&fee.quantity().inner().to_le_bytes());
+        }
 
         h.finalize().as_bytes().try_into().unwrap()
     }
@@ -173,6 +872,14 @@ impl rustler::Encoder for Transaction {
             borsh::to_vec(&self.signature)
                 .unwrap_or_default()
                 .encode(env),
+            borsh::to_vec(&self.memos).unwrap_or_default().encode(env),
+            borsh::to_vec(&self.fee).unwrap_or_default().encode(env),
+            borsh::to_vec(&self.receipts)
+                .unwrap_or_default()
+                .encode(env),
+            borsh::to_vec(&self.mailboxes)
+                .unwrap_or_default()
+                .encode(env),
         )
             .encode(env)
     }
@@ -181,11 +888,24 @@ impl rustler::Encoder for Transaction {
 #[cfg(feature = "nif")]
 impl<'a> Decoder<'a> for Transaction {
     fn decode(term: Term<'a>) -> NifResult<Self> {
-        let (term, shielded_ptx_bundle, transparent_bytes, sig_bytes): (
+        let (
+            term,
+            shielded_ptx_bundle,
+            transparent_bytes,
+            sig_bytes,
+            memo_bytes,
+            fee_bytes,
+            receipt_bytes,
+            mailbox_bytes,
+        ): (
             atom::Atom,
             ShieldedPartialTxBundle,
             Vec<u8>,
             Vec<u8>,
+            Vec<u8>,
+            Vec<u8>,
+            Vec<u8>,
+            Vec<u8>,
         ) = term.decode()?;
         if term == transaction() {
             let transparent_ptx_bundle =
@@ -193,10 +913,22 @@ impl<'a> Decoder<'a> for Transaction {
                     .map_err(|_e| rustler::Error::Atom("Failure to decode"))?;
             let signature = BorshDeserialize::deserialize(&mut sig_bytes.as_slice())
                 .map_err(|_e| rustler::Error::Atom("Failure to decode"))?;
+            let memos = BorshDeserialize::deserialize(&mut memo_bytes.as_slice())
+                .map_err(|_e| rustler::Error::Atom("Failure to decode"))?;
+            let fee = BorshDeserialize::deserialize(&mut fee_bytes.as_slice())
+                .map_err(|_e| rustler::Error::Atom("Failure to decode"))?;
+            let receipts = BorshDeserialize::deserialize(&mut receipt_bytes.as_slice())
+                .map_err(|_e| rustler::Error::Atom("Failure to decode"))?;
+            let mailboxes = BorshDeserialize::deserialize(&mut mailbox_bytes.as_slice())
+                .map_err(|_e| rustler::Error::Atom("Failure to decode"))?;
             Ok(Transaction {
                 shielded_ptx_bundle,
                 signature,
                 transparent_ptx_bundle,
+                memos,
+                fee,
+                receipts,
+                mailboxes,
             })
         } else {
             Err(rustler::Error::BadArg)
@@ -204,11 +936,170 @@ impl<'a> Decoder<'a> for Transaction {
     }
 }
 
+#[cfg(feature = "borsh")]
+impl VersionedBorsh for Transaction {
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<()> {
+        self.shielded_ptx_bundle
+            .0
+            .serialize_versioned(writer, layout)?;
+        self.transparent_ptx_bundle
+            .0
+            .serialize_versioned(writer, layout)?;
+        self.signature.serialize(writer)?;
+        if layout.memos {
+            self.memos.serialize(writer)?;
+        } else if !self.memos.is_empty() {
+            return Err(EncodingLayout::unsupported("memos"));
+        }
+        if layout.fee {
+            self.fee.serialize(writer)?;
+        } else if self.fee.is_some() {
+            return Err(EncodingLayout::unsupported("fee"));
+        }
+        if layout.receipts {
+            self.receipts.serialize(writer)?;
+        } else if !self.receipts.is_empty() {
+            return Err(EncodingLayout::unsupported("receipts"));
+        }
+        if layout.mailboxes {
+            self.mailboxes.serialize(writer)?;
+        } else if !self.mailboxes.is_empty() {
+            return Err(EncodingLayout::unsupported("mailboxes"));
+        }
+        Ok(())
+    }
+
+    fn deserialize_versioned<R: std::io::Read>(
+        reader: &mut R,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<Self> {
+        let shielded_ptx_bundle =
+            ShieldedPartialTxBundle(Vec::deserialize_versioned(reader, layout)?);
+        let transparent_ptx_bundle =
+            TransparentPartialTxBundle(Vec::deserialize_versioned(reader, layout)?);
+        let signature = BindingSignature::deserialize_reader(reader)?;
+        let memos = match layout.memos {
+            true => Vec::<Memo>::deserialize_reader(reader)?,
+            false => vec![],
+        };
+        let fee = match layout.fee {
+            true => Option::<Fee>::deserialize_reader(reader)?,
+            false => None,
+        };
+        let receipts = match layout.receipts {
+            true => Vec::<ZkvmReceipt>::deserialize_reader(reader)?,
+            false => vec![],
+        };
+        let mailboxes = match layout.mailboxes {
+            true => Vec::<Mailbox>::deserialize_reader(reader)?,
+            false => vec![],
+        };
+        Ok(Self {
+            shielded_ptx_bundle,
+            transparent_ptx_bundle,
+            signature,
+            memos,
+            fee,
+            receipts,
+            mailboxes,
+        })
+    }
+}
+
 impl TransactionResult {
     pub fn append(&mut self, result: &mut TransactionResult) {
         self.anchors.append(&mut result.anchors);
         self.nullifiers.append(&mut result.nullifiers);
         self.output_cms.append(&mut result.output_cms);
+        self.conversion_rules.append(&mut result.conversion_rules);
+        self.epochs.append(&mut result.epochs);
+        self.blacklist_roots.append(&mut result.blacklist_roots);
+    }
+}
+
+impl UnsignedTransaction {
+    pub fn new(
+        shielded_ptx_bundle: ShieldedPartialTxBundle,
+        transparent_ptx_bundle: TransparentPartialTxBundle,
+    ) -> Self {
+        assert!(!(shielded_ptx_bundle.is_empty() && transparent_ptx_bundle.is_empty()));
+        Self {
+            shielded_ptx_bundle,
+            transparent_ptx_bundle,
+            memos: vec![],
+            fee: None,
+        }
+    }
+
+    /// Attaches the memos of the created resources, see `Memo::seal`.
+    pub fn with_memos(mut self, memos: Vec<Memo>) -> Self {
+        self.memos = memos;
+        self
+    }
+
+    /// Declares the fee paid by the surplus of fee resources, see `Fee`.
+    pub fn with_fee(mut self, fee: Option<Fee>) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// The message of the binding signature, the id of the signed transaction.
+    pub fn sighash(&self) -> [u8; 32] {
+        Transaction::digest(
+            &self.shielded_ptx_bundle,
+            &self.transparent_ptx_bundle,
+            &self.memos,
+            self.fee.as_ref(),
+        )
+    }
+
+    /// The key the signature must verify against, for the signer to check
+    /// that it holds the right binding randomness.
+    pub fn binding_vk(&self) -> BindingVerificationKey {
+        BindingVerificationKey::from(Transaction::binding_vk(
+            &self.shielded_ptx_bundle,
+            &self.transparent_ptx_bundle,
+            self.fee.as_ref(),
+        ))
+    }
+
+    /// The signing key, the sum of the binding randomness of the shielded ptxs.
+    pub fn binding_signing_key(&self) -> Result<BindingSigningKey, TransactionError> {
+        let shielded_sk = self.shielded_ptx_bundle.get_binding_sig_r()?;
+        Ok(BindingSigningKey::from(shielded_sk))
+    }
+
+    /// Signs the transaction, on the signer side.
+    pub fn sign<R: RngCore + CryptoRng>(
+        &self,
+        rng: R,
+    ) -> Result<BindingSignature, TransactionError> {
+        Ok(self.binding_signing_key()?.sign(rng, &self.sighash()))
+    }
+
+    /// Combines the transaction with the signature returned by the signer.
+    /// Fails if the signature is invalid.
+    pub fn finalize(self, signature: BindingSignature) -> Result<Transaction, TransactionError> {
+        let tx = self.into_transaction(signature);
+        tx.verify_binding_sig()?;
+        Ok(tx)
+    }
+
+    fn into_transaction(mut self, signature: BindingSignature) -> Transaction {
+        self.shielded_ptx_bundle.clean_private_info();
+        Transaction {
+            shielded_ptx_bundle: self.shielded_ptx_bundle,
+            transparent_ptx_bundle: self.transparent_ptx_bundle,
+            signature,
+            memos: self.memos,
+            fee: self.fee,
+            receipts: vec![],
+            mailboxes: vec![],
+        }
     }
 }
 
@@ -244,8 +1135,17 @@ impl ShieldedPartialTxBundle {
 
     #[allow(clippy::type_complexity)]
     pub fn execute(&self) -> Result<TransactionResult, TransactionError> {
+        self.execute_with_context(&VerifyingContext::default())
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn execute_with_context(
+        &self,
+        context: &VerifyingContext,
+    ) -> Result<TransactionResult, TransactionError> {
+        self.check_unique_compliances()?;
         for partial_tx in self.0.iter() {
-            partial_tx.execute()?;
+            partial_tx.validate_with_context(context)?;
         }
 
         // Return Nullifiers to check double-spent, ResourceCommitments to store, anchors to check the root-existence
@@ -253,9 +1153,49 @@ impl ShieldedPartialTxBundle {
             nullifiers: self.get_nullifiers(),
             output_cms: self.get_output_cms(),
             anchors: self.get_anchors(),
+            conversion_rules: self.get_conversion_rules(),
+            epochs: self.get_epochs(),
+            blacklist_roots: self.get_blacklist_roots(),
         })
     }
 
+    pub fn batch_execute(
+        &self,
+        batch: &mut BatchVerifier,
+    ) -> Result<TransactionResult, TransactionError> {
+        self.batch_execute_with_context(batch, &VerifyingContext::default())
+    }
+
+    pub fn batch_execute_with_context(
+        &self,
+        batch: &mut BatchVerifier,
+        context: &VerifyingContext,
+    ) -> Result<TransactionResult, TransactionError> {
+        self.check_unique_compliances()?;
+        for partial_tx in self.0.iter() {
+            partial_tx.batch_execute_with_context(batch, context)?;
+        }
+
+        Ok(TransactionResult {
+            nullifiers: self.get_nullifiers(),
+            output_cms: self.get_output_cms(),
+            anchors: self.get_anchors(),
+            conversion_rules: self.get_conversion_rules(),
+            epochs: self.get_epochs(),
+            blacklist_roots: self.get_blacklist_roots(),
+        })
+    }
+
+    // check that no compliance is included twice across the ptxs, e.g. a
+    // ptx included twice
+    fn check_unique_compliances(&self) -> Result<(), TransactionError> {
+        check_unique_compliances(
+            self.0
+                .iter()
+                .flat_map(|ptx| ptx.get_compliance_public_inputs()),
+        )
+    }
+
     pub fn get_delta_commitments(&self) -> Vec<DeltaCommitment> {
         self.0
             .iter()
@@ -263,6 +1203,16 @@ impl ShieldedPartialTxBundle {
             .collect()
     }
 
+    pub fn get_auxiliary_deltas(
+        &self,
+    ) -> Result<Vec<(pallas::Base, AuxiliaryDelta)>, TransactionError> {
+        let mut deltas = vec![];
+        for ptx in self.0.iter() {
+            deltas.extend(ptx.get_auxiliary_deltas()?);
+        }
+        Ok(deltas)
+    }
+
     pub fn get_nullifiers(&self) -> Vec<Nullifier> {
         self.0.iter().flat_map(|ptx| ptx.get_nullifiers()).collect()
     }
@@ -274,6 +1224,66 @@ impl ShieldedPartialTxBundle {
     pub fn get_anchors(&self) -> Vec<Anchor> {
         self.0.iter().flat_map(|ptx| ptx.get_anchors()).collect()
     }
+
+    pub fn get_conversion_rules(&self) -> Vec<ConversionRuleId> {
+        self.0
+            .iter()
+            .flat_map(|ptx| ptx.get_conversion_rules())
+            .collect()
+    }
+
+    pub fn get_epochs(&self) -> Vec<u64> {
+        self.0.iter().flat_map(|ptx| ptx.get_epochs()).collect()
+    }
+
+    pub fn get_blacklist_roots(&self) -> Vec<Anchor> {
+        self.0
+            .iter()
+            .flat_map(|ptx| ptx.get_blacklist_roots())
+            .collect()
+    }
+
+    pub fn get_proofs(&self) -> Vec<&Proof> {
+        self.0.iter().flat_map(|ptx| ptx.get_proofs()).collect()
+    }
+
+    pub fn get_logic_fingerprints(&self) -> Vec<pallas::Base> {
+        self.0
+            .iter()
+            .flat_map(|ptx| ptx.get_logic_fingerprints())
+            .collect()
+    }
+
+    pub fn get_resource_logic_public_inputs(&self) -> Vec<&ResourceLogicPublicInputs> {
+        self.0
+            .iter()
+            .flat_map(|ptx| ptx.get_resource_logic_public_inputs())
+            .collect()
+    }
+
+    pub fn get_resource_logic_verifying_infos(&self) -> Vec<&ResourceLogicVerifyingInfo> {
+        self.0
+            .iter()
+            .flat_map(|ptx| ptx.get_resource_logic_verifying_infos())
+            .collect()
+    }
+
+    // The fingerprints and the public inputs are listed in the same order
+    pub fn get_logic_public_inputs(&self, logic: &pallas::Base) -> Vec<&ResourceLogicPublicInputs> {
+        self.get_logic_fingerprints()
+            .into_iter()
+            .zip(self.get_resource_logic_public_inputs())
+            .filter(|(fingerprint, _)| fingerprint == logic)
+            .map(|(_, public_inputs)| public_inputs)
+            .collect()
+    }
+
+    pub fn get_linkability_tags(&self, logic: &pallas::Base) -> Vec<LinkabilityTag> {
+        self.get_logic_public_inputs(logic)
+            .into_iter()
+            .map(LinkabilityTag::from_public_inputs)
+            .collect()
+    }
 }
 
 impl TransparentPartialTxBundle {
@@ -291,13 +1301,16 @@ impl TransparentPartialTxBundle {
 
     pub fn execute(&self) -> Result<TransactionResult, TransactionError> {
         for partial_tx in self.0.iter() {
-            partial_tx.execute()?;
+            partial_tx.validate()?;
         }
 
         Ok(TransactionResult {
             nullifiers: self.get_nullifiers(),
             output_cms: self.get_output_cms(),
             anchors: self.get_anchors(),
+            conversion_rules: self.get_conversion_rules(),
+            epochs: self.get_epochs(),
+            blacklist_roots: self.get_blacklist_roots(),
         })
     }
 
@@ -308,6 +1321,14 @@ impl TransparentPartialTxBundle {
             .collect()
     }
 
+    pub fn get_balance_summary(&self) -> BalanceSummary {
+        let mut summary = BalanceSummary::default();
+        for ptx in self.0.iter() {
+            summary.merge(&ptx.get_balance_summary());
+        }
+        summary
+    }
+
     pub fn get_nullifiers(&self) -> Vec<Nullifier> {
         self.0.iter().flat_map(|ptx| ptx.get_nullifiers()).collect()
     }
@@ -319,14 +1340,133 @@ impl TransparentPartialTxBundle {
     pub fn get_anchors(&self) -> Vec<Anchor> {
         self.0.iter().flat_map(|ptx| ptx.get_anchors()).collect()
     }
+
+    pub fn get_conversion_rules(&self) -> Vec<ConversionRuleId> {
+        self.0
+            .iter()
+            .flat_map(|ptx| ptx.get_conversion_rules())
+            .collect()
+    }
+
+    pub fn get_epochs(&self) -> Vec<u64> {
+        self.0.iter().flat_map(|ptx| ptx.get_epochs()).collect()
+    }
+
+    pub fn get_blacklist_roots(&self) -> Vec<Anchor> {
+        self.0
+            .iter()
+            .flat_map(|ptx| ptx.get_blacklist_roots())
+            .collect()
+    }
+
+    pub fn get_input_cms(&self) -> Vec<ResourceCommitment> {
+        self.0.iter().flat_map(|ptx| ptx.get_input_cms()).collect()
+    }
+
+    pub fn get_state_writes(&self) -> Vec<StateWrite> {
+        self.0
+            .iter()
+            .flat_map(|ptx| ptx.get_state_writes())
+            .collect()
+    }
 }
 
 #[cfg(test)]
 pub mod testing {
-    use crate::shielded_ptx::testing::create_shielded_ptx;
-    use crate::transaction::{ShieldedPartialTxBundle, TransparentPartialTxBundle};
+    use crate::binding_signature::BindingSigningKey;
+    use crate::fee::Fee;
+    use crate::resource_encryption::{Mailbox, Memo};
+    use crate::shielded_ptx::{testing::create_shielded_ptx, ShieldedPartialTransaction};
+    use crate::transaction::{
+        ShieldedPartialTxBundle, Transaction, TransparentPartialTxBundle, TxId,
+    };
     #[cfg(feature = "borsh")]
     use crate::transparent_ptx::testing::create_transparent_ptx;
+    use crate::transparent_ptx::TransparentPartialTransaction;
+    use crate::zkvm_receipt::ZkvmReceipt;
+    use ff::FromUniformBytes;
+    use pasta_curves::pallas;
+    use proptest::prelude::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    impl Arbitrary for TxId {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            any::<[u8; 32]>().prop_map(TxId::from_bytes).boxed()
+        }
+    }
+
+    impl Arbitrary for ShieldedPartialTxBundle {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            prop::collection::vec(any::<ShieldedPartialTransaction>(), 0..2)
+                .prop_map(ShieldedPartialTxBundle::new)
+                .boxed()
+        }
+    }
+
+    impl Arbitrary for TransparentPartialTxBundle {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            prop::collection::vec(any::<TransparentPartialTransaction>(), 0..2)
+                .prop_map(TransparentPartialTxBundle::new)
+                .boxed()
+        }
+    }
+
+    /// Transactions of arbitrary bundles, signed with an arbitrary key: the
+    /// encodings don't check the signature.
+    impl Arbitrary for Transaction {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (
+                any::<ShieldedPartialTxBundle>(),
+                any::<TransparentPartialTxBundle>(),
+                prop::collection::vec(any::<Memo>(), 0..2),
+                any::<Option<Fee>>(),
+                prop::collection::vec(any::<ZkvmReceipt>(), 0..2),
+                prop::collection::vec(any::<Mailbox>(), 0..2),
+                any::<[u8; 32]>(),
+                any::<u64>(),
+            )
+                .prop_map(
+                    |(
+                        shielded_ptx_bundle,
+                        transparent_ptx_bundle,
+                        memos,
+                        fee,
+                        receipts,
+                        mailboxes,
+                        key,
+                        seed,
+                    )| {
+                        let mut wide = [0u8; 64];
+                        wide[..32].copy_from_slice(&key);
+                        let signature =
+                            BindingSigningKey::from(pallas::Scalar::from_uniform_bytes(&wide))
+                                .sign(StdRng::seed_from_u64(seed), &key);
+                        Transaction {
+                            shielded_ptx_bundle,
+                            transparent_ptx_bundle,
+                            signature,
+                            memos,
+                            fee,
+                            receipts,
+                            mailboxes,
+                        }
+                    },
+                )
+                .boxed()
+        }
+    }
 
     pub fn create_shielded_ptx_bundle(num: usize) -> ShieldedPartialTxBundle {
         let mut bundle = vec![];
@@ -372,4 +1512,507 @@ pub mod testing {
             assert_eq!(_ret, de_ret);
         }
     }
+
+    #[test]
+    fn test_halo2_transaction_batch_verify() {
+        use super::*;
+        use rand::rngs::OsRng;
+
+        let mut rng = OsRng;
+        let txs: Vec<Transaction> = (0..2)
+            .map(|_| {
+                Transaction::build(
+                    &mut rng,
+                    create_shielded_ptx_bundle(1),
+                    TransparentPartialTxBundle::default(),
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let results = Transaction::batch_verify(&txs).unwrap();
+        for (tx, result) in txs.iter().zip(results) {
+            assert_eq!(tx.execute().unwrap(), result);
+        }
+    }
+
+    #[test]
+    fn test_duplicate_compliance() {
+        use super::*;
+
+        // A ptx included twice is rejected before its proofs are verified
+        let ptx = create_shielded_ptx();
+        let bundle = ShieldedPartialTxBundle::new(vec![ptx.clone(), ptx]);
+        assert!(matches!(
+            bundle.execute(),
+            Err(TransactionError::DuplicateCompliance)
+        ));
+    }
+
+    #[test]
+    fn test_multi_party_binding_signature() {
+        use super::*;
+        use crate::binding_signature::BindingNonce;
+        use rand::rngs::OsRng;
+
+        let mut rng = OsRng;
+        // Each party keeps the binding randomness of its ptx and shares the
+        // ptx without it
+        let parties: Vec<(pallas::Scalar, ShieldedPartialTransaction)> = (0..2)
+            .map(|_| {
+                let mut ptx = create_shielded_ptx();
+                let r = ptx.get_binding_sig_r().unwrap();
+                ptx.clean_private_info();
+                (r, ptx)
+            })
+            .collect();
+        let shielded_ptx_bundle =
+            ShieldedPartialTxBundle::new(parties.iter().map(|(_, ptx)| ptx.clone()).collect());
+        let transparent_ptx_bundle = TransparentPartialTxBundle::default();
+
+        let nonces: Vec<BindingNonce> = parties
+            .iter()
+            .map(|_| BindingNonce::random(&mut rng))
+            .collect();
+        let nonce_commitments: Vec<BindingNonceCommitment> =
+            nonces.iter().map(|nonce| nonce.commitment()).collect();
+        let session = Transaction::binding_signing_session(
+            &shielded_ptx_bundle,
+            &transparent_ptx_bundle,
+            &[],
+            None,
+            &nonce_commitments,
+        );
+        let shares: Vec<BindingSignatureShare> = nonces
+            .into_iter()
+            .zip(parties.iter())
+            .map(|(nonce, (r, _))| nonce.sign_share(r, &session))
+            .collect();
+
+        let tx = Transaction::finalize(
+            shielded_ptx_bundle.clone(),
+            transparent_ptx_bundle.clone(),
+            vec![],
+            None,
+            &session,
+            &shares,
+        )
+        .unwrap();
+        tx.execute().unwrap();
+
+        // A missing share
+        assert!(matches!(
+            Transaction::finalize(
+                shielded_ptx_bundle.clone(),
+                transparent_ptx_bundle.clone(),
+                vec![],
+                None,
+                &session,
+                &shares[..1],
+            ),
+            Err(TransactionError::InvalidBindingSignature)
+        ));
+
+        // A fee the session was not started with
+        let fee = Fee::new(pallas::Base::from(1u64), 1u64);
+        assert!(matches!(
+            Transaction::finalize(
+                shielded_ptx_bundle,
+                transparent_ptx_bundle,
+                vec![],
+                Some(fee),
+                &session,
+                &shares,
+            ),
+            Err(TransactionError::InvalidBindingSignature)
+        ));
+    }
+
+    #[test]
+    fn test_unsigned_transaction() {
+        use super::*;
+        use rand::rngs::OsRng;
+
+        let mut rng = OsRng;
+        let unsigned = UnsignedTransaction::new(create_shielded_ptx_bundle(1), Default::default());
+
+        // Shipped to the signer and back
+        #[cfg(feature = "borsh")]
+        let unsigned: UnsignedTransaction =
+            borsh::from_slice(&borsh::to_vec(&unsigned).unwrap()).unwrap();
+        let signature = unsigned.sign(&mut rng).unwrap();
+        assert!(unsigned
+            .binding_vk()
+            .verify(&unsigned.sighash(), &signature)
+            .is_ok());
+
+        let tx = unsigned.clone().finalize(signature).unwrap();
+        assert_eq!(tx.id(), unsigned.sighash());
+        tx.execute().unwrap();
+
+        // A signature of another transaction
+        let other = UnsignedTransaction::new(create_shielded_ptx_bundle(1), Default::default());
+        let signature = other.sign(&mut rng).unwrap();
+        assert!(matches!(
+            unsigned.finalize(signature),
+            Err(TransactionError::InvalidBindingSignature)
+        ));
+    }
+
+    #[test]
+    fn test_transaction_receipts() {
+        use super::*;
+        use crate::zkvm_receipt::ReceiptVerifier;
+        use rand::rngs::OsRng;
+
+        // Stands in for a zkVM verifier: the seal is the journal
+        #[derive(Debug)]
+        struct MockVerifier;
+
+        impl ReceiptVerifier for MockVerifier {
+            fn verify(&self, _image_id: &[u8; 32], journal: &[u8], seal: &[u8]) -> bool {
+                journal == seal
+            }
+        }
+
+        let context = VerifyingContext::default().with_receipt_verifier(MockVerifier);
+        let tx =
+            Transaction::build(OsRng, create_shielded_ptx_bundle(1), Default::default()).unwrap();
+        tx.execute_with_context(&context).unwrap();
+
+        // The receipts are carried by the transaction, one no logic delegates
+        // to is rejected
+        let tx = tx.with_receipts(vec![ZkvmReceipt::new([7u8; 32], vec![], vec![])]);
+        #[cfg(feature = "borsh")]
+        assert_eq!(
+            Transaction::from_bytes(&tx.to_bytes())
+                .unwrap()
+                .get_receipts(),
+            tx.get_receipts()
+        );
+        assert!(matches!(
+            tx.execute_with_context(&context),
+            Err(TransactionError::ReceiptClaimMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_transaction_mailboxes() {
+        use super::*;
+        use ff::Field;
+        use rand::rngs::OsRng;
+
+        let mut rng = OsRng;
+        let tx = Transaction::build(&mut rng, create_shielded_ptx_bundle(1), Default::default())
+            .unwrap();
+
+        // The mailboxes are carried by the transaction, one no logic publishes
+        // is rejected
+        let mailbox = Mailbox::seal(
+            &[pallas::Base::one()],
+            &pallas::Point::random(&mut rng),
+            &pallas::Base::random(&mut rng),
+            &pallas::Base::random(&mut rng),
+        );
+        let tx = tx.with_mailboxes(vec![mailbox]);
+        #[cfg(feature = "borsh")]
+        assert_eq!(
+            Transaction::from_bytes(&tx.to_bytes())
+                .unwrap()
+                .get_mailboxes(),
+            tx.get_mailboxes()
+        );
+        assert!(matches!(
+            tx.execute(),
+            Err(TransactionError::MailboxCommitmentMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_check_balance() {
+        use super::*;
+        use crate::compliance::ComplianceInfo;
+        use crate::constant::TAIGA_COMMITMENT_TREE_DEPTH;
+        use crate::merkle_tree::MerklePath;
+        use crate::resource::tests::random_resource;
+        use rand::rngs::OsRng;
+
+        let mut rng = OsRng;
+        // 3 more units of the kind are created than consumed
+        let input_resource = random_resource(&mut rng);
+        let mut output_resource = random_resource(&mut rng);
+        output_resource.kind = input_resource.kind;
+        output_resource.quantity = (input_resource.quantity.inner() + 3).into();
+        let compliance = ComplianceInfo::new(
+            input_resource.clone(),
+            MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH),
+            None,
+            &mut output_resource,
+            &mut rng,
+        );
+        let ptx = TransparentPartialTransaction::new(vec![compliance], vec![], vec![], vec![]);
+        let summary = ptx.get_balance_summary();
+        assert_eq!(summary.get(&input_resource.kind), -3);
+        assert_eq!(summary.unbalanced_kinds(), vec![input_resource.kind]);
+        assert_eq!(
+            summary.delta_commitment().inner(),
+            ptx.get_delta_commitments()[0].inner()
+        );
+
+        // The unbalanced kind is reported instead of the binding signature
+        let tx = Transaction::build(
+            &mut rng,
+            ShieldedPartialTxBundle::default(),
+            TransparentPartialTxBundle::new(vec![ptx]),
+        )
+        .unwrap();
+        assert!(matches!(
+            tx.check_balance(),
+            Err(TransactionError::UnbalancedResourceKind(kind)) if kind == input_resource.kind
+        ));
+        assert!(matches!(
+            tx.execute(),
+            Err(TransactionError::UnbalancedResourceKind(kind)) if kind == input_resource.kind
+        ));
+
+        // The shielded ptxs balance on their own
+        let tx = Transaction::build(
+            &mut rng,
+            create_shielded_ptx_bundle(1),
+            TransparentPartialTxBundle::default(),
+        )
+        .unwrap();
+        assert!(tx.check_balance().is_ok());
+    }
+
+    #[cfg(all(feature = "borsh", feature = "serde"))]
+    #[test]
+    fn test_transaction_serialization() {
+        use super::*;
+        use rand::rngs::OsRng;
+
+        let tx = Transaction::build(
+            OsRng,
+            create_shielded_ptx_bundle(1),
+            create_transparent_ptx_bundle(1),
+        )
+        .unwrap();
+        let result = tx.execute().unwrap();
+
+        let bytes = borsh::to_vec(&tx).unwrap();
+        let borsh_tx: Transaction = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(borsh_tx.id(), tx.id());
+        assert_eq!(borsh_tx.execute().unwrap(), result);
+
+        let json = serde_json::to_string(&tx).unwrap();
+        let serde_tx: Transaction = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_tx.id(), tx.id());
+        assert_eq!(serde_tx.execute().unwrap(), result);
+
+        // Truncated or corrupted bytes are rejected without panicking
+        let step = bytes.len() / 16;
+        for len in (0..bytes.len()).step_by(step) {
+            assert!(borsh::from_slice::<Transaction>(&bytes[..len]).is_err());
+        }
+        for position in (0..bytes.len()).step_by(step) {
+            let mut corrupted = bytes.clone();
+            corrupted[position] ^= 0xff;
+            let _ = borsh::from_slice::<Transaction>(&corrupted);
+        }
+        assert!(serde_json::from_str::<Transaction>(&json[..json.len() / 2]).is_err());
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_txid() {
+        use super::*;
+        use rand::rngs::OsRng;
+        use std::collections::HashMap;
+
+        let tx =
+            Transaction::build(OsRng, create_shielded_ptx_bundle(1), Default::default()).unwrap();
+        let other_tx =
+            Transaction::build(OsRng, create_shielded_ptx_bundle(1), Default::default()).unwrap();
+        let txid = tx.txid();
+        assert_ne!(txid, other_tx.txid());
+        assert_eq!(
+            Transaction::from_bytes(&tx.to_bytes()).unwrap().txid(),
+            txid
+        );
+        assert_eq!(txid.to_string().len(), 64);
+
+        // Nor does the version of the encoding
+        let v1 = tx.to_bytes_with_version(1).unwrap();
+        assert_eq!(Transaction::from_bytes(&v1).unwrap().txid(), txid);
+
+        // The binding signature doesn't change the id
+        let mut resigned = tx.clone();
+        resigned.signature = other_tx.signature.clone();
+        assert_eq!(resigned.txid(), txid);
+
+        let mut index = HashMap::new();
+        index.insert(txid, tx);
+        assert!(index.contains_key(&TxId::from_bytes(txid.to_bytes())));
+        let bytes = borsh::to_vec(&txid).unwrap();
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(borsh::from_slice::<TxId>(&bytes).unwrap(), txid);
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_transaction_canonical_encoding() {
+        use super::*;
+        use rand::rngs::OsRng;
+
+        let tx = Transaction::build(
+            OsRng,
+            create_shielded_ptx_bundle(1),
+            create_transparent_ptx_bundle(1),
+        )
+        .unwrap();
+        let bytes = tx.to_bytes();
+        assert_eq!(bytes[..4], TRANSACTION_MAGIC);
+        assert_eq!(bytes[4], TRANSACTION_ENCODING_VERSION);
+        // The body is the borsh encoding
+        assert_eq!(bytes[5..], borsh::to_vec(&tx).unwrap());
+        assert_eq!(Transaction::from_bytes(&bytes).unwrap().id(), tx.id());
+
+        // The previous versions are decoded in their own layouts
+        for version in 1..TRANSACTION_ENCODING_VERSION {
+            let legacy = tx.to_bytes_with_version(version).unwrap();
+            assert_eq!(legacy[4], version);
+            assert_ne!(legacy[5..], bytes[5..]);
+            assert_eq!(Transaction::from_bytes(&legacy).unwrap().id(), tx.id());
+        }
+        assert_eq!(
+            tx.to_bytes_with_version(TRANSACTION_ENCODING_VERSION)
+                .unwrap(),
+            bytes
+        );
+
+        // Version 1 is the encoding without the memos, the conversion rules
+        // and the epochs of the compliances, with the references of the ptxs
+        let mut v1 = bytes[..5].to_vec();
+        v1[4] = 1;
+        let layout = EncodingLayout::of(1).unwrap();
+        tx.shielded_ptx_bundle
+            .0
+            .serialize_versioned(&mut v1, &layout)
+            .unwrap();
+        tx.transparent_ptx_bundle
+            .0
+            .serialize_versioned(&mut v1, &layout)
+            .unwrap();
+        v1.extend(borsh::to_vec(&tx.signature).unwrap());
+        assert_eq!(v1, tx.to_bytes_with_version(1).unwrap());
+
+        // Only the empty references are decoded
+        // The references of the transparent ptx precede its empty hints
+        let references_offset = v1.len() - borsh::to_vec(&tx.signature).unwrap().len() - 8;
+        assert_eq!(v1[references_offset..references_offset + 8], [0u8; 8]);
+        let mut with_references = v1.clone();
+        with_references[references_offset] = 1;
+        assert!(matches!(
+            Transaction::from_bytes(&with_references),
+            Err(TransactionError::InvalidTransactionEncoding)
+        ));
+
+        // What a previous version can't encode is an error
+        let with_receipts =
+            tx.clone()
+                .with_receipts(vec![ZkvmReceipt::new([7u8; 32], vec![], vec![])]);
+        assert!(with_receipts.to_bytes_with_version(4).is_ok());
+        assert!(matches!(
+            with_receipts.to_bytes_with_version(3),
+            Err(TransactionError::InvalidTransactionEncoding)
+        ));
+
+        let mut unknown_version = bytes.clone();
+        unknown_version[4] = TRANSACTION_ENCODING_VERSION + 1;
+        assert!(matches!(
+            Transaction::from_bytes(&unknown_version),
+            Err(TransactionError::UnknownTransactionVersion(version))
+                if version == TRANSACTION_ENCODING_VERSION + 1
+        ));
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] ^= 0xff;
+        assert!(matches!(
+            Transaction::from_bytes(&bad_magic),
+            Err(TransactionError::InvalidTransactionEncoding)
+        ));
+        assert!(matches!(
+            Transaction::from_bytes(&bytes[..4]),
+            Err(TransactionError::InvalidTransactionEncoding)
+        ));
+        // Trailing bytes are not canonical
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(matches!(
+            Transaction::from_bytes(&trailing),
+            Err(TransactionError::InvalidTransactionEncoding)
+        ));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_txid_borsh_round_trip(txid: TxId) {
+            let bytes = borsh::to_vec(&txid).unwrap();
+            prop_assert_eq!(borsh::from_slice::<TxId>(&bytes).unwrap(), txid);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_txid_serde_round_trip(txid: TxId) {
+            let json = serde_json::to_string(&txid).unwrap();
+            prop_assert_eq!(serde_json::from_str::<TxId>(&json).unwrap(), txid);
+        }
+
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_bundles_borsh_round_trip(
+            shielded: ShieldedPartialTxBundle,
+            transparent: TransparentPartialTxBundle,
+        ) {
+            let bytes = borsh::to_vec(&shielded).unwrap();
+            let decoded: ShieldedPartialTxBundle = borsh::from_slice(&bytes).unwrap();
+            prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+            let bytes = borsh::to_vec(&transparent).unwrap();
+            let decoded: TransparentPartialTxBundle = borsh::from_slice(&bytes).unwrap();
+            prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_bundles_serde_round_trip(
+            shielded: ShieldedPartialTxBundle,
+            transparent: TransparentPartialTxBundle,
+        ) {
+            let json = serde_json::to_string(&shielded).unwrap();
+            let decoded: ShieldedPartialTxBundle = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+            let json = serde_json::to_string(&transparent).unwrap();
+            let decoded: TransparentPartialTxBundle = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+        }
+
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_transaction_borsh_round_trip(tx: Transaction) {
+            let bytes = tx.to_bytes();
+            let decoded = Transaction::from_bytes(&bytes).unwrap();
+            prop_assert_eq!(decoded.to_bytes(), bytes);
+            prop_assert_eq!(decoded.txid(), tx.txid());
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_transaction_serde_round_trip(tx: Transaction) {
+            let json = serde_json::to_string(&tx).unwrap();
+            let decoded: Transaction = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+        }
+    }
 }
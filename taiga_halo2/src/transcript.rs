@@ -0,0 +1,231 @@
+//! The transcript hash of the proofs. Blake2b is the default and the one all
+//! the proofs of a transaction are created with; Poseidon is cheaper to
+//! compute for verifiers that only have field arithmetic at hand, e.g. in a
+//! circuit or with the EVM precompiles. A proof only verifies with the
+//! transcript it was created with.
+use crate::constant::POSEIDON_TRANSCRIPT_PERSONALIZATION;
+use ff::PrimeField;
+use group::GroupEncoding;
+use halo2_gadgets::poseidon::primitives as poseidon;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, Transcript, TranscriptRead,
+        TranscriptWrite,
+    },
+};
+use pasta_curves::vesta;
+use std::io::{self, Read, Write};
+
+pub trait TranscriptHash {
+    type Writer: TranscriptWrite<vesta::Affine, Challenge255<vesta::Affine>>;
+    type Reader<'a>: TranscriptRead<vesta::Affine, Challenge255<vesta::Affine>>;
+
+    fn init_write() -> Self::Writer;
+
+    /// Returns the proof bytes written to the transcript.
+    fn finalize(transcript: Self::Writer) -> Vec<u8>;
+
+    fn init_read(proof: &[u8]) -> Self::Reader<'_>;
+}
+
+/// The default transcript hash.
+#[derive(Debug, Clone, Copy)]
+pub enum Blake2bTranscript {}
+
+impl TranscriptHash for Blake2bTranscript {
+    type Writer = Blake2bWrite<Vec<u8>, vesta::Affine, Challenge255<vesta::Affine>>;
+    type Reader<'a> = Blake2bRead<&'a [u8], vesta::Affine, Challenge255<vesta::Affine>>;
+
+    fn init_write() -> Self::Writer {
+        Blake2bWrite::init(vec![])
+    }
+
+    fn finalize(transcript: Self::Writer) -> Vec<u8> {
+        transcript.finalize()
+    }
+
+    fn init_read(proof: &[u8]) -> Self::Reader<'_> {
+        Blake2bRead::init(proof)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PoseidonTranscript {}
+
+impl TranscriptHash for PoseidonTranscript {
+    type Writer = PoseidonWrite<Vec<u8>>;
+    type Reader<'a> = PoseidonRead<&'a [u8]>;
+
+    fn init_write() -> Self::Writer {
+        PoseidonWrite::init(vec![])
+    }
+
+    fn finalize(transcript: Self::Writer) -> Vec<u8> {
+        transcript.finalize()
+    }
+
+    fn init_read(proof: &[u8]) -> Self::Reader<'_> {
+        PoseidonRead::init(proof)
+    }
+}
+
+// The tags separating the kinds of absorbed items, as in the Blake2b transcript
+const POSEIDON_TAG_CHALLENGE: u64 = 0;
+const POSEIDON_TAG_POINT: u64 = 1;
+const POSEIDON_TAG_SCALAR: u64 = 2;
+
+/// The Poseidon sponge over the base field of vesta, in which the points of
+/// the proof are natively absorbed.
+#[derive(Debug, Clone)]
+struct PoseidonSponge {
+    state: vesta::Base,
+}
+
+impl PoseidonSponge {
+    fn new() -> Self {
+        Self {
+            state: vesta::Base::from_u128(u128::from_le_bytes(
+                *POSEIDON_TRANSCRIPT_PERSONALIZATION,
+            )),
+        }
+    }
+
+    fn hash<const L: usize>(message: [vesta::Base; L]) -> vesta::Base {
+        poseidon::Hash::<_, poseidon::P128Pow5T3, poseidon::ConstantLength<L>, 3, 2>::init()
+            .hash(message)
+    }
+
+    fn squeeze(&mut self) -> Challenge255<vesta::Affine> {
+        self.state = Self::hash([self.state, vesta::Base::from(POSEIDON_TAG_CHALLENGE)]);
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.state.to_repr());
+        Challenge255::new(&bytes)
+    }
+
+    fn absorb_point(&mut self, point: vesta::Affine) -> io::Result<()> {
+        let coords: Option<_> = point.coordinates().into();
+        let coords = coords.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "cannot write points at infinity to the transcript",
+            )
+        })?;
+        self.state = Self::hash([
+            self.state,
+            vesta::Base::from(POSEIDON_TAG_POINT),
+            *coords.x(),
+            *coords.y(),
+        ]);
+        Ok(())
+    }
+
+    fn absorb_scalar(&mut self, scalar: vesta::Scalar) {
+        // The scalar field of vesta is smaller than its base field
+        let scalar = vesta::Base::from_repr(scalar.to_repr()).unwrap();
+        self.state = Self::hash([self.state, vesta::Base::from(POSEIDON_TAG_SCALAR), scalar]);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PoseidonWrite<W: Write> {
+    writer: W,
+    sponge: PoseidonSponge,
+}
+
+impl<W: Write> PoseidonWrite<W> {
+    pub fn init(writer: W) -> Self {
+        Self {
+            writer,
+            sponge: PoseidonSponge::new(),
+        }
+    }
+
+    pub fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Transcript<vesta::Affine, Challenge255<vesta::Affine>> for PoseidonWrite<W> {
+    fn squeeze_challenge(&mut self) -> Challenge255<vesta::Affine> {
+        self.sponge.squeeze()
+    }
+
+    fn common_point(&mut self, point: vesta::Affine) -> io::Result<()> {
+        self.sponge.absorb_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: vesta::Scalar) -> io::Result<()> {
+        self.sponge.absorb_scalar(scalar);
+        Ok(())
+    }
+}
+
+impl<W: Write> TranscriptWrite<vesta::Affine, Challenge255<vesta::Affine>> for PoseidonWrite<W> {
+    fn write_point(&mut self, point: vesta::Affine) -> io::Result<()> {
+        self.common_point(point)?;
+        self.writer.write_all(point.to_bytes().as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: vesta::Scalar) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        self.writer.write_all(scalar.to_repr().as_ref())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PoseidonRead<R: Read> {
+    reader: R,
+    sponge: PoseidonSponge,
+}
+
+impl<R: Read> PoseidonRead<R> {
+    pub fn init(reader: R) -> Self {
+        Self {
+            reader,
+            sponge: PoseidonSponge::new(),
+        }
+    }
+}
+
+impl<R: Read> Transcript<vesta::Affine, Challenge255<vesta::Affine>> for PoseidonRead<R> {
+    fn squeeze_challenge(&mut self) -> Challenge255<vesta::Affine> {
+        self.sponge.squeeze()
+    }
+
+    fn common_point(&mut self, point: vesta::Affine) -> io::Result<()> {
+        self.sponge.absorb_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: vesta::Scalar) -> io::Result<()> {
+        self.sponge.absorb_scalar(scalar);
+        Ok(())
+    }
+}
+
+impl<R: Read> TranscriptRead<vesta::Affine, Challenge255<vesta::Affine>> for PoseidonRead<R> {
+    fn read_point(&mut self) -> io::Result<vesta::Affine> {
+        let mut bytes = <vesta::Affine as GroupEncoding>::Repr::default();
+        self.reader.read_exact(bytes.as_mut())?;
+        let point: Option<vesta::Affine> = vesta::Affine::from_bytes(&bytes).into();
+        let point = point.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof")
+        })?;
+        self.common_point(point)?;
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<vesta::Scalar> {
+        let mut bytes = [0u8; 32];
+        self.reader.read_exact(&mut bytes)?;
+        let scalar: Option<vesta::Scalar> = vesta::Scalar::from_repr(bytes).into();
+        let scalar = scalar.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "invalid field element encoding in proof",
+            )
+        })?;
+        self.common_scalar(scalar)?;
+        Ok(scalar)
+    }
+}
@@ -1,13 +1,22 @@
 use crate::{
-    circuit::resource_logic_bytecode::ApplicationByteCode, compliance::ComplianceInfo,
-    delta_commitment::DeltaCommitment, error::TransactionError, executable::Executable,
-    merkle_tree::Anchor, nullifier::Nullifier, resource::ResourceCommitment,
+    circuit::resource_logic_bytecode::ApplicationByteCode,
+    compliance::ComplianceInfo,
+    constant::MAX_PTX_COMPLIANCE_NUM,
+    conversion::ConversionRuleId,
+    delta_commitment::{BalanceSummary, DeltaCommitment},
+    error::TransactionError,
+    executable::{Executable, StateWrite},
+    merkle_tree::Anchor,
+    nullifier::Nullifier,
+    resource::ResourceCommitment,
 };
 
 use pasta_curves::pallas;
 #[cfg(feature = "serde")]
 use serde;
 
+#[cfg(feature = "borsh")]
+use crate::transaction::{EncodingLayout, VersionedBorsh};
 #[cfg(feature = "borsh")]
 use borsh::{BorshDeserialize, BorshSerialize};
 
@@ -35,10 +44,49 @@ impl TransparentPartialTransaction {
             hints,
         }
     }
+
+    // get input cms from compliances, they are only visible in transparent ptxs
+    pub fn get_input_cms(&self) -> Vec<ResourceCommitment> {
+        self.compliances
+            .iter()
+            .map(|compliance| compliance.get_input_resource_cm())
+            .collect()
+    }
+
+    /// The writes of the ptx to the state of the apps: the consumed resources
+    /// are deleted, except the ephemeral ones which were never stored, and
+    /// the created ones inserted.
+    pub fn get_state_writes(&self) -> Vec<StateWrite> {
+        let mut writes = vec![];
+        for compliance in self.compliances.iter() {
+            if !compliance.get_input_resource().is_ephemeral {
+                writes.push(StateWrite::Delete(compliance.get_input_resource_cm()));
+            }
+            writes.push(StateWrite::Insert(
+                compliance.get_output_resource_cm(),
+                compliance.get_output_resource().clone(),
+            ));
+        }
+        writes
+    }
+
+    /// The net quantity of each kind, the opening of the delta commitments.
+    pub fn get_balance_summary(&self) -> BalanceSummary {
+        BalanceSummary::from_compliances(&self.compliances)
+    }
 }
 
 impl Executable for TransparentPartialTransaction {
-    fn execute(&self) -> Result<(), TransactionError> {
+    fn validate(&self) -> Result<(), TransactionError> {
+        if self.compliances.len() > MAX_PTX_COMPLIANCE_NUM {
+            return Err(TransactionError::TooManyResources);
+        }
+
+        // check compliances natively
+        for compliance in self.compliances.iter() {
+            compliance.verify_transparently()?;
+        }
+
         // check resource logics, nullifiers, and resource commitments
         let compliance_nfs = self.get_nullifiers();
         let compliance_cms = self.get_output_cms();
@@ -95,48 +143,128 @@ impl Executable for TransparentPartialTransaction {
             .map(|compliance| compliance.calculate_root())
             .collect()
     }
+
+    fn get_conversion_rules(&self) -> Vec<ConversionRuleId> {
+        self.compliances
+            .iter()
+            .filter_map(|compliance| compliance.get_conversion().map(|rule| rule.id()))
+            .collect()
+    }
+
+    fn get_epochs(&self) -> Vec<u64> {
+        self.compliances
+            .iter()
+            .map(|compliance| compliance.get_epoch())
+            .collect()
+    }
+
+    // The transparent resources are in the clear, they are not read
+    fn get_read_tags(&self) -> Vec<pallas::Base> {
+        vec![]
+    }
+
+    fn get_blacklist_roots(&self) -> Vec<Anchor> {
+        self.input_resource_app
+            .iter()
+            .chain(self.output_resource_app.iter())
+            .flat_map(|app| app.get_blacklist_roots())
+            .map(Anchor::from)
+            .collect()
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl VersionedBorsh for TransparentPartialTransaction {
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<()> {
+        self.compliances.serialize_versioned(writer, layout)?;
+        self.input_resource_app
+            .serialize_versioned(writer, layout)?;
+        self.output_resource_app
+            .serialize_versioned(writer, layout)?;
+        layout.serialize_references(writer)?;
+        self.hints.serialize(writer)
+    }
+
+    fn deserialize_versioned<R: std::io::Read>(
+        reader: &mut R,
+        layout: &EncodingLayout,
+    ) -> std::io::Result<Self> {
+        let compliances = Vec::<ComplianceInfo>::deserialize_versioned(reader, layout)?;
+        let input_resource_app = Vec::<ApplicationByteCode>::deserialize_versioned(reader, layout)?;
+        let output_resource_app =
+            Vec::<ApplicationByteCode>::deserialize_versioned(reader, layout)?;
+        layout.deserialize_references(reader)?;
+        let hints = Vec::<u8>::deserialize_reader(reader)?;
+        Ok(Self {
+            compliances,
+            input_resource_app,
+            output_resource_app,
+            hints,
+        })
+    }
 }
 
 #[cfg(test)]
 #[cfg(feature = "borsh")]
 pub mod testing {
     use crate::{
-        circuit::resource_logic_examples::TrivialResourceLogicCircuit,
-        constant::TAIGA_COMMITMENT_TREE_DEPTH, merkle_tree::MerklePath,
+        circuit::resource_logic_examples::TrivialResourceLogicCircuit, ledger::LedgerState,
         resource::tests::random_resource, resource_tree::ResourceMerkleTreeLeaves,
-        transparent_ptx::*,
+        transaction::TransactionResult, transparent_ptx::*,
     };
     use rand::rngs::OsRng;
 
     pub fn create_transparent_ptx() -> TransparentPartialTransaction {
+        create_transparent_ptx_in(&LedgerState::new())
+    }
+
+    /// Creates a ptx spending two resources first minted into `ledger`, so that
+    /// it is proven against an anchor of the ledger.
+    pub fn create_transparent_ptx_in(ledger: &LedgerState) -> TransparentPartialTransaction {
         let mut rng = OsRng;
         // construct resources
         let input_resource_1 = random_resource(&mut rng);
+        let input_resource_2 = random_resource(&mut rng);
+        ledger
+            .apply(&TransactionResult {
+                anchors: vec![],
+                nullifiers: vec![],
+                output_cms: vec![input_resource_1.commitment(), input_resource_2.commitment()],
+                conversion_rules: vec![],
+                epochs: vec![],
+                blacklist_roots: vec![],
+            })
+            .unwrap();
+        let view = ledger.read_view();
+
         let mut output_resource_1 = {
             let mut resource = random_resource(&mut rng);
             resource.kind = input_resource_1.kind;
             resource.quantity = input_resource_1.quantity;
             resource
         };
-        let merkle_path_1 = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
+        let merkle_path_1 = view.merkle_path(&input_resource_1.commitment()).unwrap();
         let compliance_1 = ComplianceInfo::new(
-            input_resource_1,
+            input_resource_1.clone(),
             merkle_path_1,
             None,
             &mut output_resource_1,
             &mut rng,
         );
 
-        let input_resource_2 = random_resource(&mut rng);
         let mut output_resource_2 = {
             let mut resource = random_resource(&mut rng);
             resource.kind = input_resource_2.kind;
             resource.quantity = input_resource_2.quantity;
             resource
         };
-        let merkle_path_2 = MerklePath::random(&mut rng, TAIGA_COMMITMENT_TREE_DEPTH);
+        let merkle_path_2 = view.merkle_path(&input_resource_2.commitment()).unwrap();
         let compliance_2 = ComplianceInfo::new(
-            input_resource_2,
+            input_resource_2.clone(),
             merkle_path_2,
             None,
             &mut output_resource_2,
@@ -204,3 +332,75 @@ pub mod testing {
         )
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::TransparentPartialTransaction;
+    use crate::circuit::resource_logic_bytecode::{
+        ApplicationByteCode, ResourceLogicByteCode, ResourceLogicRepresentation,
+    };
+    use crate::compliance::ComplianceInfo;
+    use crate::constant::MAX_PTX_COMPLIANCE_NUM;
+    use proptest::prelude::*;
+
+    /// The bytecode of an application, the inputs are arbitrary bytes as they
+    /// are only decoded when the logics are proven.
+    fn arb_app() -> impl Strategy<Value = ApplicationByteCode> {
+        let bytecode = || {
+            prop::collection::vec(any::<u8>(), 0..64).prop_map(|inputs| {
+                ResourceLogicByteCode::new(ResourceLogicRepresentation::Trivial, inputs)
+            })
+        };
+        (bytecode(), prop::collection::vec(bytecode(), 0..2))
+            .prop_map(|(app, dynamic)| ApplicationByteCode::new(app, dynamic))
+    }
+
+    impl Arbitrary for TransparentPartialTransaction {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (
+                prop::collection::vec(
+                    (any::<ComplianceInfo>(), arb_app(), arb_app()),
+                    1..=MAX_PTX_COMPLIANCE_NUM,
+                ),
+                prop::collection::vec(any::<u8>(), 0..32),
+            )
+                .prop_map(|(compliances, hints)| {
+                    let mut inputs = vec![];
+                    let mut outputs = vec![];
+                    let compliances = compliances
+                        .into_iter()
+                        .map(|(compliance, input, output)| {
+                            inputs.push(input);
+                            outputs.push(output);
+                            compliance
+                        })
+                        .collect();
+                    TransparentPartialTransaction::new(compliances, inputs, outputs, hints)
+                })
+                .boxed()
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[cfg(feature = "borsh")]
+        #[test]
+        fn test_transparent_ptx_borsh_round_trip(ptx: TransparentPartialTransaction) {
+            let bytes = borsh::to_vec(&ptx).unwrap();
+            let decoded: TransparentPartialTransaction = borsh::from_slice(&bytes).unwrap();
+            prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_transparent_ptx_serde_round_trip(ptx: TransparentPartialTransaction) {
+            let json = serde_json::to_string(&ptx).unwrap();
+            let decoded: TransparentPartialTransaction = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+        }
+    }
+}
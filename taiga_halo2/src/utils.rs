@@ -97,17 +97,60 @@ pub fn to_field_elements(bytes: &[u8]) -> Vec<pallas::Base> {
         .collect::<Vec<pallas::Base>>()
 }
 
+// `PrimeField::Repr` leaves the byte order to the field, so it is read from
+// the representation of one rather than assumed
+fn repr_is_le<F: PrimeField>() -> bool {
+    F::ONE.to_repr().as_ref()[0] == 1
+}
+
+/// The little-endian bytes of a field element, whatever the byte order of its
+/// representation and of the target.
+pub fn to_le_bytes<F: PrimeField>(x: &F) -> Vec<u8> {
+    let mut bytes = x.to_repr().as_ref().to_vec();
+    if !repr_is_le::<F>() {
+        bytes.reverse();
+    }
+    bytes
+}
+
+/// Decodes the little-endian bytes of a field element. Returns None if the
+/// length doesn't match the field or the element is not canonical.
+pub fn from_le_bytes<F: PrimeField>(bytes: &[u8]) -> Option<F> {
+    let mut repr = F::Repr::default();
+    if repr.as_ref().len() != bytes.len() {
+        return None;
+    }
+    repr.as_mut().copy_from_slice(bytes);
+    if !repr_is_le::<F>() {
+        repr.as_mut().reverse();
+    }
+    Option::from(F::from_repr(repr))
+}
+
+/// The little-endian u32 words of the bytes, as Blake2s reads its message.
+/// Trailing bytes that don't fill a word are ignored.
+pub fn to_le_words(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+        .collect()
+}
+
+pub fn from_le_words(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
 pub fn read_base_field<R: std::io::Read>(reader: &mut R) -> std::io::Result<pallas::Base> {
     let mut bytes = [0u8; 32];
     reader.read_exact(&mut bytes)?;
-    Option::from(pallas::Base::from_repr(bytes))
+    from_le_bytes(&bytes)
         .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid base field"))
 }
 
 pub fn read_scalar_field<R: std::io::Read>(reader: &mut R) -> std::io::Result<pallas::Scalar> {
     let mut bytes = [0u8; 32];
     reader.read_exact(&mut bytes)?;
-    Option::from(pallas::Scalar::from_repr(bytes))
+    from_le_bytes(&bytes)
         .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid scalar field"))
 }
 
@@ -117,3 +160,58 @@ pub fn read_point<R: std::io::Read>(reader: &mut R) -> std::io::Result<pallas::P
     Option::from(pallas::Point::from_bytes(&bytes))
         .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid point"))
 }
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[test]
+fn test_le_encoding() {
+    use ff::Field;
+    use rand::rngs::OsRng;
+
+    // The bytes a big-endian host holds for the u64 limbs of x, most
+    // significant limb first, reversed into little-endian
+    let x = pallas::Base::random(OsRng);
+    let be_limbs: Vec<u8> = to_le_bytes(&x)
+        .chunks(8)
+        .rev()
+        .flat_map(|limb| u64::from_le_bytes(limb.try_into().unwrap()).to_be_bytes())
+        .collect();
+    let le_bytes: Vec<u8> = be_limbs.into_iter().rev().collect();
+    assert_eq!(from_le_bytes::<pallas::Base>(&le_bytes), Some(x));
+    assert_eq!(to_le_bytes(&x), x.to_repr().to_vec());
+    let scalar = pallas::Scalar::random(OsRng);
+    assert_eq!(from_le_bytes(&to_le_bytes(&scalar)), Some(scalar));
+
+    // The byte order is fixed, not the one of the target
+    let mut one = [0u8; 32];
+    one[0] = 1;
+    assert_eq!(from_le_bytes(&one), Some(pallas::Base::one()));
+    assert_eq!(to_le_bytes(&pallas::Base::from(0x0403_0201)), {
+        let mut bytes = vec![0u8; 32];
+        bytes[..4].copy_from_slice(&[1, 2, 3, 4]);
+        bytes
+    });
+    assert_eq!(
+        to_le_words(&[1, 2, 3, 4, 5, 6, 7, 8]),
+        [0x0403_0201, 0x0807_0605]
+    );
+    assert_eq!(from_le_words(&[0x0403_0201]), [1, 2, 3, 4]);
+    let bytes = to_le_bytes(&x);
+    assert_eq!(from_le_words(&to_le_words(&bytes)), bytes);
+
+    // Wrong lengths and non-canonical elements are rejected
+    assert!(from_le_bytes::<pallas::Base>(&one[..31]).is_none());
+    assert!(from_le_bytes::<pallas::Base>(&[0xff; 32]).is_none());
+}
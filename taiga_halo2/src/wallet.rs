@@ -0,0 +1,373 @@
+//! A wallet keeps track of the resources a user owns, together with the merkle
+//! paths of their commitments, and produces reports over them.
+use crate::{
+    circuit::reserve_circuit::ReserveCircuit,
+    constant::{RESERVE_CIRCUIT_PARAMS_SIZE, RESERVE_CIRCUIT_RESOURCE_NUM},
+    error::TransactionError,
+    history::HistoryLog,
+    merkle_tree::{Anchor, MerklePath},
+    nullifier::Nullifier,
+    params::{ProvingContext, VerifyingContext},
+    proof::Proof,
+    quantity::Quantity,
+    resource::{Resource, ResourceCommitment, ResourceKind},
+    spent_proof::SpentProof,
+};
+use pasta_curves::pallas;
+use rand::RngCore;
+use std::collections::HashSet;
+
+#[cfg(feature = "serde")]
+use serde;
+
+/// A resource owned by the wallet and the path of its commitment.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedResource {
+    pub resource: Resource,
+    pub merkle_path: MerklePath,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Wallet {
+    resources: Vec<OwnedResource>,
+    spent: HashSet<Nullifier>,
+    history: HistoryLog,
+}
+
+/// The public inputs of reserve proof.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReservePublicInputs {
+    /// The root of the resource commitment Merkle tree.
+    pub anchor: Anchor,
+    /// The kind of the reserve.
+    pub logic: pallas::Base,
+    pub label: pallas::Base,
+    /// The quantity the prover controls at least.
+    pub min_amount: Quantity,
+    /// The nullifiers of the resources used in the proof. The verifier must
+    /// check that none of them is in the nullifier set.
+    pub nullifiers: [Nullifier; RESERVE_CIRCUIT_RESOURCE_NUM],
+}
+
+/// A proof that the prover controls at least `min_amount` of a kind.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReserveProof {
+    pub public_inputs: ReservePublicInputs,
+    pub proof: Proof,
+}
+
+impl ReservePublicInputs {
+    pub fn to_instance(&self) -> Vec<pallas::Base> {
+        let mut instance = vec![
+            self.anchor.inner(),
+            self.logic,
+            self.label,
+            pallas::Base::from(self.min_amount),
+        ];
+        instance.extend(self.nullifiers.iter().map(|nf| nf.inner()));
+        instance
+    }
+}
+
+impl ReserveProof {
+    /// Verifies the proof. It doesn't check that the nullifiers are unspent.
+    ///
+    /// The circuit doesn't constrain the resources to be distinct, a resource
+    /// filling several slots would be counted several times, so the proof is
+    /// rejected if a nullifier is published twice.
+    pub fn verify(&self) -> Result<(), TransactionError> {
+        self.verify_with_context(&VerifyingContext::default())
+    }
+
+    /// Same as `verify`, with the params and the keys of the context.
+    pub fn verify_with_context(&self, context: &VerifyingContext) -> Result<(), TransactionError> {
+        let mut nullifiers = HashSet::new();
+        if !self
+            .public_inputs
+            .nullifiers
+            .iter()
+            .all(|nf| nullifiers.insert(*nf))
+        {
+            return Err(TransactionError::DuplicateReserveResource);
+        }
+        self.proof.verify(
+            context.reserve_verifying_key(),
+            context.setup_params(RESERVE_CIRCUIT_PARAMS_SIZE),
+            &[&self.public_inputs.to_instance()],
+        )?;
+        Ok(())
+    }
+}
+
+impl Wallet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a resource the wallet can spend, i.e. with its nullifier key.
+    pub fn add_resource(&mut self, resource: Resource, merkle_path: MerklePath) {
+        self.resources.push(OwnedResource {
+            resource,
+            merkle_path,
+        });
+    }
+
+    pub fn mark_spent(&mut self, nf: Nullifier) {
+        self.spent.insert(nf);
+    }
+
+    /// The hash-chained log of the resources sent and received by the wallet.
+    pub fn history(&self) -> &HistoryLog {
+        &self.history
+    }
+
+    pub fn history_mut(&mut self) -> &mut HistoryLog {
+        &mut self.history
+    }
+
+    pub fn unspent_resources(&self) -> impl Iterator<Item = &OwnedResource> {
+        self.resources
+            .iter()
+            .filter(|owned| match owned.resource.get_nf() {
+                Some(nf) => !self.spent.contains(&nf),
+                None => false,
+            })
+    }
+
+    /// Returns the total unspent quantity of a kind, or None on overflow.
+    pub fn balance(&self, kind: &ResourceKind) -> Option<Quantity> {
+        self.unspent_resources()
+            .filter(|owned| !owned.resource.is_ephemeral && owned.resource.kind == *kind)
+            .try_fold(Quantity::ZERO, |acc, owned| {
+                acc.checked_add(owned.resource.quantity)
+            })
+    }
+
+    /// Produces a spent proof of the wallet resource with commitment `cm` for an
+    /// auditor. Only the nullifier of that resource is revealed.
+    pub fn prove_spent<R: RngCore>(
+        &self,
+        cm: &ResourceCommitment,
+        epoch: u64,
+        rng: R,
+    ) -> Result<SpentProof, TransactionError> {
+        self.prove_spent_with_context(cm, epoch, &ProvingContext::default(), rng)
+    }
+
+    /// Same as `prove_spent`, with the params and the keys of the context.
+    pub fn prove_spent_with_context<R: RngCore>(
+        &self,
+        cm: &ResourceCommitment,
+        epoch: u64,
+        context: &ProvingContext,
+        rng: R,
+    ) -> Result<SpentProof, TransactionError> {
+        let owned = self
+            .resources
+            .iter()
+            .find(|owned| owned.resource.commitment() == *cm)
+            .ok_or(TransactionError::UnknownResourceCommitment)?;
+        SpentProof::create_with_context(&owned.resource, epoch, context, rng)
+    }
+
+    /// Produces a zero-knowledge proof that the wallet controls at least
+    /// `min_amount` of `kind` across its unspent resources, without revealing
+    /// which. At most RESERVE_CIRCUIT_RESOURCE_NUM resources can be used, the
+    /// largest ones are picked first.
+    pub fn prove_reserves<R: RngCore>(
+        &self,
+        kind: &ResourceKind,
+        min_amount: Quantity,
+        rng: R,
+    ) -> Result<ReserveProof, TransactionError> {
+        self.prove_reserves_with_context(kind, min_amount, &ProvingContext::default(), rng)
+    }
+
+    /// Same as `prove_reserves`, with the params and the keys of the context.
+    pub fn prove_reserves_with_context<R: RngCore>(
+        &self,
+        kind: &ResourceKind,
+        min_amount: Quantity,
+        context: &ProvingContext,
+        mut rng: R,
+    ) -> Result<ReserveProof, TransactionError> {
+        let mut candidates: Vec<&OwnedResource> = self
+            .unspent_resources()
+            .filter(|owned| !owned.resource.is_ephemeral && owned.resource.kind == *kind)
+            .collect();
+        candidates.sort_by(|a, b| b.resource.quantity.cmp(&a.resource.quantity));
+
+        let mut selected = vec![];
+        let mut total: u128 = 0;
+        for owned in candidates.into_iter().take(RESERVE_CIRCUIT_RESOURCE_NUM) {
+            if !selected.is_empty() && total >= min_amount.inner() as u128 {
+                break;
+            }
+            total += owned.resource.quantity.inner() as u128;
+            selected.push(owned);
+        }
+        if selected.is_empty() || total < min_amount.inner() as u128 {
+            return Err(TransactionError::InsufficientReserves);
+        }
+
+        let anchor = selected[0]
+            .resource
+            .calculate_root(&selected[0].merkle_path);
+        if selected
+            .iter()
+            .any(|owned| owned.resource.calculate_root(&owned.merkle_path) != anchor)
+        {
+            return Err(TransactionError::InconsistentReserveAnchor);
+        }
+
+        // Pad the remaining slots with ephemeral resources
+        let mut resources =
+            [(); RESERVE_CIRCUIT_RESOURCE_NUM].map(|_| Resource::padding_resource(&mut rng));
+        let mut merkle_paths = [(); RESERVE_CIRCUIT_RESOURCE_NUM]
+            .map(|_| MerklePath::default().inner().try_into().unwrap());
+        for (i, owned) in selected.iter().enumerate() {
+            resources[i] = owned.resource.clone();
+            merkle_paths[i] = owned.merkle_path.inner().try_into().unwrap();
+        }
+
+        let public_inputs = ReservePublicInputs {
+            anchor,
+            logic: kind.logic,
+            label: kind.label,
+            min_amount,
+            nullifiers: resources.map(|resource| resource.get_nf().unwrap()),
+        };
+        let circuit = ReserveCircuit {
+            resources,
+            merkle_paths,
+        };
+        let proof = Proof::create(
+            context.reserve_proving_key(),
+            context.setup_params(RESERVE_CIRCUIT_PARAMS_SIZE),
+            circuit,
+            &[&public_inputs.to_instance()],
+            &mut rng,
+        )?;
+
+        Ok(ReserveProof {
+            public_inputs,
+            proof,
+        })
+    }
+}
+
+#[test]
+fn test_prove_reserves() {
+    use crate::merkle_tree::{tests::merkle_paths_from_leaves, Node};
+    use crate::params::get_params;
+    use crate::resource::tests::random_resource;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let kind = random_resource(&mut rng).kind;
+    let mut resources: Vec<Resource> = [10, 10, 10, 100]
+        .into_iter()
+        .map(|quantity| {
+            let mut resource = random_resource(&mut rng);
+            resource.kind = kind;
+            resource.quantity = Quantity::new(quantity);
+            resource
+        })
+        .collect();
+    // A resource of another kind
+    resources.push(random_resource(&mut rng));
+
+    let leaves: Vec<Node> = resources.iter().map(Node::from).collect();
+    let paths = merkle_paths_from_leaves(&leaves);
+    let mut wallet = Wallet::new();
+    for (resource, path) in resources.iter().zip(paths) {
+        wallet.add_resource(resource.clone(), path);
+    }
+    wallet.mark_spent(resources[3].get_nf().unwrap());
+    assert_eq!(wallet.balance(&kind), Some(Quantity::new(30)));
+
+    let reserve_proof = wallet
+        .prove_reserves(&kind, Quantity::new(25), &mut rng)
+        .unwrap();
+    assert!(reserve_proof.verify().is_ok());
+    assert_eq!(
+        reserve_proof.public_inputs.anchor,
+        resources[0].calculate_root(&wallet.resources[0].merkle_path)
+    );
+
+    // A tampered min amount must not verify
+    let mut tampered = reserve_proof.clone();
+    tampered.public_inputs.min_amount = Quantity::new(31);
+    assert!(tampered.verify().is_err());
+
+    // The keys are derived from the params injected by the context
+    let k = RESERVE_CIRCUIT_PARAMS_SIZE;
+    let context = ProvingContext::default().with_params(k, get_params(k).unwrap().clone());
+    let reserve_proof = wallet
+        .prove_reserves_with_context(&kind, Quantity::new(25), &context, &mut rng)
+        .unwrap();
+    reserve_proof
+        .verify_with_context(&context.verifying_context())
+        .unwrap();
+
+    // The spent resource can be disclosed to an auditor
+    let cm = resources[3].commitment();
+    let spent_proof = wallet.prove_spent(&cm, 0, &mut rng).unwrap();
+    assert_eq!(
+        spent_proof.verify_for(&cm, 0).unwrap(),
+        resources[3].get_nf().unwrap()
+    );
+
+    // The spent resource doesn't count
+    assert!(matches!(
+        wallet.prove_reserves(&kind, Quantity::new(31), &mut rng),
+        Err(TransactionError::InsufficientReserves)
+    ));
+}
+
+#[test]
+fn test_reserve_duplicate_resource() {
+    use crate::merkle_tree::{tests::merkle_paths_from_leaves, Node};
+    use crate::resource::tests::random_resource;
+    use rand::rngs::OsRng;
+
+    // A single resource of quantity 10 filling every slot, claiming a reserve
+    // of RESERVE_CIRCUIT_RESOURCE_NUM * 10
+    let mut rng = OsRng;
+    let mut resource = random_resource(&mut rng);
+    resource.quantity = Quantity::new(10);
+    let paths = merkle_paths_from_leaves(&[Node::from(&resource)]);
+    let public_inputs = ReservePublicInputs {
+        anchor: resource.calculate_root(&paths[0]),
+        logic: resource.get_logic(),
+        label: resource.get_label(),
+        min_amount: Quantity::new(10 * RESERVE_CIRCUIT_RESOURCE_NUM as u64),
+        nullifiers: [resource.get_nf().unwrap(); RESERVE_CIRCUIT_RESOURCE_NUM],
+    };
+    let circuit = ReserveCircuit {
+        resources: [(); RESERVE_CIRCUIT_RESOURCE_NUM].map(|_| resource.clone()),
+        merkle_paths: [(); RESERVE_CIRCUIT_RESOURCE_NUM]
+            .map(|_| paths[0].inner().try_into().unwrap()),
+    };
+    let context = ProvingContext::default();
+    let proof = Proof::create(
+        context.reserve_proving_key(),
+        context.setup_params(RESERVE_CIRCUIT_PARAMS_SIZE),
+        circuit,
+        &[&public_inputs.to_instance()],
+        &mut rng,
+    )
+    .unwrap();
+
+    let reserve_proof = ReserveProof {
+        public_inputs,
+        proof,
+    };
+    assert!(matches!(
+        reserve_proof.verify(),
+        Err(TransactionError::DuplicateReserveResource)
+    ));
+}
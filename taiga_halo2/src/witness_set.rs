@@ -0,0 +1,183 @@
+//! The resources of a partial transaction are witnessed several times: in
+//! their compliance circuit, in the resource tree the logics prove membership
+//! in, and in the logic circuits themselves. Each of them derives the same npk,
+//! psi, rcm, commitment and nullifier natively. A [`WitnessSet`] derives them
+//! once per ptx and shares them with every synthesis path.
+use crate::{
+    compliance::ComplianceInfo,
+    nullifier::Nullifier,
+    resource::{Resource, ResourceCommitment},
+    resource_tree::{ResourceExistenceWitness, ResourceMerkleTreeLeaves},
+};
+use pasta_curves::pallas;
+use std::collections::HashMap;
+
+/// The native values derived from a resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceWitness {
+    pub npk: pallas::Base,
+    pub psi: pallas::Base,
+    pub rcm: pallas::Base,
+    pub cm: ResourceCommitment,
+    /// None if the resource only contains the npk
+    pub nf: Option<Nullifier>,
+}
+
+impl ResourceWitness {
+    pub fn derive(resource: &Resource) -> Self {
+        let npk = resource.get_npk();
+        let psi = resource.get_psi();
+        let rcm = resource.get_rcm();
+        let cm = resource.commitment_with(npk, psi, rcm);
+        let nf = Nullifier::derive(
+            resource.nk_container.expose_secret(),
+            &resource.nonce.inner(),
+            &psi,
+            &cm,
+        );
+        Self {
+            npk,
+            psi,
+            rcm,
+            cm,
+            nf,
+        }
+    }
+
+    /// The input nf or the output cm, see
+    /// [`ResourceExistenceWitness::get_identity`].
+    pub fn get_identity(&self, is_input: bool) -> pallas::Base {
+        if is_input {
+            self.nf
+                .expect("the input resource must contain the nullifier key")
+                .inner()
+        } else {
+            self.cm.inner()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WitnessSet {
+    witnesses: HashMap<Resource, ResourceWitness>,
+}
+
+impl WitnessSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Derives the witnesses of all the resources of the compliances.
+    pub fn from_compliances(compliances: &[ComplianceInfo]) -> Self {
+        let mut witness_set = Self::new();
+        for compliance in compliances.iter() {
+            witness_set.get_or_derive(compliance.get_input_resource());
+            witness_set.get_or_derive(compliance.get_output_resource());
+        }
+        witness_set
+    }
+
+    pub fn get(&self, resource: &Resource) -> Option<&ResourceWitness> {
+        self.witnesses.get(resource)
+    }
+
+    pub fn get_or_derive(&mut self, resource: &Resource) -> ResourceWitness {
+        *self
+            .witnesses
+            .entry(resource.clone())
+            .or_insert_with(|| ResourceWitness::derive(resource))
+    }
+
+    /// The resource tree of the ptx: the input nfs and the output cms of the
+    /// compliances, in order.
+    pub fn resource_tree(&mut self, compliances: &[ComplianceInfo]) -> ResourceMerkleTreeLeaves {
+        let leaves = compliances
+            .iter()
+            .flat_map(|compliance| {
+                [
+                    self.get_or_derive(compliance.get_input_resource())
+                        .get_identity(true),
+                    self.get_or_derive(compliance.get_output_resource())
+                        .get_identity(false),
+                ]
+            })
+            .collect();
+        ResourceMerkleTreeLeaves::new(leaves)
+    }
+
+    /// The witness of `resource` in `resource_tree` to be given to the logic
+    /// circuits. Returns None if the resource is not in the tree.
+    pub fn existence_witness(
+        &mut self,
+        resource_tree: &ResourceMerkleTreeLeaves,
+        resource: &Resource,
+        is_input: bool,
+    ) -> Option<ResourceExistenceWitness> {
+        let identity = self.get_or_derive(resource).get_identity(is_input);
+        let merkle_path = resource_tree.generate_path(identity)?;
+        Some(ResourceExistenceWitness::new(resource.clone(), merkle_path))
+    }
+
+    pub fn len(&self) -> usize {
+        self.witnesses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.witnesses.is_empty()
+    }
+}
+
+#[test]
+fn test_witness_set() {
+    use crate::compliance::tests::random_compliance_info;
+    use rand::rngs::OsRng;
+
+    let mut rng = OsRng;
+    let compliances = [
+        random_compliance_info(&mut rng),
+        random_compliance_info(&mut rng),
+    ];
+    let mut witness_set = WitnessSet::from_compliances(&compliances);
+    assert_eq!(witness_set.len(), 4);
+
+    for compliance in compliances.iter() {
+        let input_resource = compliance.get_input_resource();
+        let witness = witness_set.get(input_resource).unwrap();
+        assert_eq!(witness.npk, input_resource.get_npk());
+        assert_eq!(witness.psi, input_resource.get_psi());
+        assert_eq!(witness.rcm, input_resource.get_rcm());
+        assert_eq!(witness.cm, input_resource.commitment());
+        assert_eq!(witness.nf, input_resource.get_nf());
+
+        // The compliance built from the set is the same as the one built directly
+        let (expected_public_inputs, _) = compliance.build();
+        let (public_inputs, _) = compliance.build_with_witnesses(&mut witness_set);
+        assert_eq!(public_inputs.nf, expected_public_inputs.nf);
+        assert_eq!(public_inputs.cm, expected_public_inputs.cm);
+    }
+    // Nothing is derived twice
+    assert_eq!(witness_set.len(), 4);
+
+    // The existence witnesses match the ones derived from the resources
+    let resource_tree = witness_set.resource_tree(&compliances);
+    let input_resource = compliances[1].get_input_resource();
+    let output_resource = compliances[1].get_output_resource();
+    let input_witness = witness_set
+        .existence_witness(&resource_tree, input_resource, true)
+        .unwrap();
+    let output_witness = witness_set
+        .existence_witness(&resource_tree, output_resource, false)
+        .unwrap();
+    assert!(input_witness.is_input());
+    assert!(!output_witness.is_input());
+    assert_eq!(
+        input_witness.get_identity(),
+        input_resource.get_nf().unwrap().inner()
+    );
+    assert_eq!(
+        output_witness.get_identity(),
+        output_resource.commitment().inner()
+    );
+    assert_eq!(input_witness.get_root(), resource_tree.root());
+    assert_eq!(output_witness.get_root(), resource_tree.root());
+}
@@ -0,0 +1,203 @@
+//! An escape hatch for resource logics whose business logic is too heavy to
+//! express in halo2 gates. The logic delegates the computation to a RISC-V
+//! zkVM program (RISC Zero, SP1, ...) and only proves, with the Blake2s chip,
+//! that the claim of the receipt binds the program and the journal it expects:
+//!
+//! `claim = Blake2s(image_id_lo, image_id_hi, journal[0], ..., journal[n])`
+//!
+//! The logic publishes the claim in an extra instance column as
+//! `[tag, claim_lo, claim_hi]`. The receipt itself is carried by the
+//! transaction and checked outside of the circuit, when the transaction is
+//! executed, by the zkVM-specific [`ReceiptVerifier`] of the verifying context.
+use crate::circuit::resource_logic_circuit::ResourceLogicVerifyingInfo;
+use crate::constant::{ZKVM_RECEIPT_CLAIM_PERSONALIZATION, ZKVM_RECEIPT_TAG};
+use crate::error::TransactionError;
+use blake2s_simd::Params;
+use byteorder::{ByteOrder, LittleEndian};
+use ff::PrimeField;
+use pasta_curves::pallas;
+
+#[cfg(feature = "serde")]
+use serde;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Verifies the seal of a zkVM receipt, e.g. a wrapper around the verifier of
+/// the zkVM. It is shared by the executor threads, see
+/// `VerifyingContext::with_receipt_verifier`.
+pub trait ReceiptVerifier: std::fmt::Debug + Send + Sync {
+    fn verify(&self, image_id: &[u8; 32], journal: &[u8], seal: &[u8]) -> bool;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZkvmReceipt {
+    /// The id of the guest program
+    image_id: [u8; 32],
+    /// The public outputs committed by the guest program
+    journal: Vec<pallas::Base>,
+    /// The zkVM proof
+    seal: Vec<u8>,
+}
+
+impl ZkvmReceipt {
+    pub fn new(image_id: [u8; 32], journal: Vec<pallas::Base>, seal: Vec<u8>) -> Self {
+        Self {
+            image_id,
+            journal,
+            seal,
+        }
+    }
+
+    pub fn image_id(&self) -> &[u8; 32] {
+        &self.image_id
+    }
+
+    pub fn journal(&self) -> &[pallas::Base] {
+        &self.journal
+    }
+
+    pub fn seal(&self) -> &[u8] {
+        &self.seal
+    }
+
+    /// The image id as two field elements of 128 bits.
+    pub fn image_id_fields(image_id: &[u8; 32]) -> [pallas::Base; 2] {
+        [
+            pallas::Base::from_u128(LittleEndian::read_u128(&image_id[0..16])),
+            pallas::Base::from_u128(LittleEndian::read_u128(&image_id[16..32])),
+        ]
+    }
+
+    /// The journal as the guest program commits it: the little-endian
+    /// encodings of the elements.
+    pub fn journal_bytes(&self) -> Vec<u8> {
+        self.journal
+            .iter()
+            .flat_map(|ele| ele.to_repr().as_ref().to_vec())
+            .collect()
+    }
+
+    /// The Blake2s chip hashes field elements in pairs, the journal is padded
+    /// with a zero if its length is odd.
+    pub fn claim_digest(&self) -> [u8; 32] {
+        let mut state = Params::new()
+            .hash_length(32)
+            .personal(ZKVM_RECEIPT_CLAIM_PERSONALIZATION)
+            .to_state();
+        for ele in Self::image_id_fields(&self.image_id)
+            .iter()
+            .chain(self.journal.iter())
+        {
+            state.update(ele.to_repr().as_ref());
+        }
+        if self.journal.len() % 2 == 1 {
+            state.update(pallas::Base::zero().to_repr().as_ref());
+        }
+        state.finalize().as_bytes().try_into().unwrap()
+    }
+
+    /// The extra instance column publishing the claim.
+    pub fn to_instance(&self) -> Vec<pallas::Base> {
+        let claim = self.claim_digest();
+        vec![
+            *ZKVM_RECEIPT_TAG,
+            pallas::Base::from_u128(LittleEndian::read_u128(&claim[0..16])),
+            pallas::Base::from_u128(LittleEndian::read_u128(&claim[16..32])),
+        ]
+    }
+
+    /// Whether one of the extra instance columns of a logic publishes the claim
+    /// of a receipt, i.e. the logic delegates to a zkVM program.
+    pub fn is_claimed_by(extra_instances: &[Vec<pallas::Base>]) -> bool {
+        extra_instances
+            .iter()
+            .any(|column| column.first() == Some(&*ZKVM_RECEIPT_TAG))
+    }
+
+    /// Checks that one of the extra instance columns publishes the claim of
+    /// the receipt.
+    pub fn check_claim(
+        &self,
+        extra_instances: &[Vec<pallas::Base>],
+    ) -> Result<(), TransactionError> {
+        let instance = self.to_instance();
+        if extra_instances
+            .iter()
+            .any(|column| column.starts_with(&instance))
+        {
+            Ok(())
+        } else {
+            Err(TransactionError::ReceiptClaimMismatch)
+        }
+    }
+
+    /// Checks that the resource logic delegates to this receipt and that the
+    /// receipt is valid.
+    pub fn verify(
+        &self,
+        verifying_info: &ResourceLogicVerifyingInfo,
+        verifier: &dyn ReceiptVerifier,
+    ) -> Result<(), TransactionError> {
+        self.check_claim(&verifying_info.extra_instances)?;
+        if !verifier.verify(&self.image_id, &self.journal_bytes(), &self.seal) {
+            return Err(TransactionError::InvalidReceipt);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for ZkvmReceipt {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        use byteorder::WriteBytesExt;
+        writer.write_all(&self.image_id)?;
+        writer.write_u32::<LittleEndian>(self.journal.len() as u32)?;
+        for ele in self.journal.iter() {
+            writer.write_all(&ele.to_repr())?;
+        }
+        self.seal.serialize(writer)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl BorshDeserialize for ZkvmReceipt {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        use byteorder::ReadBytesExt;
+        let mut image_id = [0u8; 32];
+        reader.read_exact(&mut image_id)?;
+        let len = reader.read_u32::<LittleEndian>()?;
+        let journal = (0..len)
+            .map(|_| crate::utils::read_base_field(reader))
+            .collect::<Result<_, _>>()?;
+        let seal = Vec::<u8>::deserialize_reader(reader)?;
+        Ok(Self {
+            image_id,
+            journal,
+            seal,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::ZkvmReceipt;
+    use crate::nullifier::tests::arb_base;
+    use proptest::prelude::*;
+
+    impl Arbitrary for ZkvmReceipt {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (
+                any::<[u8; 32]>(),
+                prop::collection::vec(arb_base(), 0..4),
+                prop::collection::vec(any::<u8>(), 0..64),
+            )
+                .prop_map(|(image_id, journal, seal)| ZkvmReceipt::new(image_id, journal, seal))
+                .boxed()
+        }
+    }
+}